@@ -1,7 +1,17 @@
 use std::env;
 use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
 
+use openttd_core::gamelog::print_gamelog;
+use openttd_core::newgrf::{GrfFile, GrfStatus};
+use openttd_savegame::diff::{diff_savegames, DiffLevel};
+use openttd_savegame::gamelog::load_gamelog;
 use openttd_savegame::header::SavegameHeader;
+use openttd_savegame::newgrf::load_newgrf_configs;
+use openttd_savegame::{
+    validate, CompressionType, SavegameDocument, SavegameFormat, SavegameReader,
+};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
@@ -9,18 +19,263 @@ use std::sync::{
 
 use openttd_video::sdl2::{VideoEvent, VideoMode, VideoSubsystem, WindowOptions};
 
+const USAGE: &str = "usage: openttd_cli [--window] <savegame>
+       openttd_cli export <savegame> [<output.json>]
+       openttd_cli import <input.json|-> <savegame> [--compression <lzo|none|zlib|lzma>[:<level>]]
+       openttd_cli diff <old.sav> <new.sav> [--level chunk|record|field]
+       openttd_cli validate <savegame>
+       openttd_cli gamelog <savegame>
+       openttd_cli newgrf <savegame> [<content dir>]";
+
+fn fail(message: String) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
+}
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    std::process::exit(2);
+}
+
+/// Dump a savegame as JSON, to stdout without an output path
+fn export(args: &[String]) {
+    let (input, output) = match args {
+        [input] => (input, None),
+        [input, output] => (input, Some(output)),
+        _ => usage(),
+    };
+    let bytes =
+        fs::read(input).unwrap_or_else(|err| fail(format!("failed to read {input}: {err}")));
+    let json = SavegameDocument::from_savegame(&bytes)
+        .and_then(|document| document.to_json())
+        .unwrap_or_else(|err| fail(format!("failed to export {input}: {err}")));
+    let written = match output {
+        Some(output) => fs::write(output, json),
+        None => writeln!(io::stdout(), "{json}"),
+    };
+    written.unwrap_or_else(|err| fail(format!("failed to write JSON: {err}")));
+}
+
+/// Rebuild a savegame from JSON, read from stdin for `-`
+fn import(args: &[String]) {
+    let (input, output, format) = match args {
+        [input, output] => (input, output, CompressionType::Zlib.into()),
+        [input, output, flag, format] if flag == "--compression" => {
+            let format: SavegameFormat =
+                format.parse().unwrap_or_else(|err| fail(format!("{err}")));
+            (input, output, format)
+        }
+        _ => usage(),
+    };
+    let json = if input == "-" {
+        let mut json = String::new();
+        io::stdin().read_to_string(&mut json).map(|_| json)
+    } else {
+        fs::read_to_string(input)
+    }
+    .unwrap_or_else(|err| fail(format!("failed to read {input}: {err}")));
+    let bytes = SavegameDocument::from_json(&json)
+        .and_then(|document| document.to_savegame(format))
+        .unwrap_or_else(|err| fail(format!("failed to import {input}: {err}")));
+    fs::write(output, bytes).unwrap_or_else(|err| fail(format!("failed to write {output}: {err}")));
+}
+
+/// Print the differences between two savegames; exits with 1 when they differ
+fn diff(args: &[String]) {
+    let (old, new, level) = match args {
+        [old, new] => (old, new, None),
+        [old, new, flag, name] if flag == "--level" => {
+            let level = match name.as_str() {
+                "chunk" => DiffLevel::Chunk,
+                "record" => DiffLevel::Record,
+                "field" => DiffLevel::Field,
+                _ => usage(),
+            };
+            (old, new, Some(level))
+        }
+        _ => usage(),
+    };
+    let read = |path: &String| {
+        fs::read(path).unwrap_or_else(|err| fail(format!("failed to read {path}: {err}")))
+    };
+    let differences = diff_savegames(&read(old), &read(new))
+        .unwrap_or_else(|err| fail(format!("failed to compare savegames: {err}")));
+    let differences: Vec<_> = differences
+        .into_iter()
+        .filter(|difference| level.is_none_or(|level| difference.level == level))
+        .collect();
+    for difference in &differences {
+        println!("{difference}");
+    }
+    if !differences.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Print the broken references of a savegame; exits with 1 when there are any
+fn check(args: &[String]) {
+    let [path] = args else { usage() };
+    let bytes = fs::read(path).unwrap_or_else(|err| fail(format!("failed to read {path}: {err}")));
+    let document = SavegameDocument::from_savegame(&bytes)
+        .unwrap_or_else(|err| fail(format!("failed to load {path}: {err}")));
+    let violations = validate(&document);
+    for violation in &violations {
+        println!("{violation}");
+    }
+    if !violations.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Print the gamelog of a savegame like the `gamelog` console command
+fn gamelog(args: &[String]) {
+    let [path] = args else { usage() };
+    let bytes = fs::read(path).unwrap_or_else(|err| fail(format!("failed to read {path}: {err}")));
+    let actions = SavegameReader::new(&bytes)
+        .and_then(|reader| load_gamelog(&reader.read_chunks()?, reader.header().version))
+        .unwrap_or_else(|err| fail(format!("failed to load the gamelog of {path}: {err}")));
+    for line in print_gamelog(&actions) {
+        println!("{line}");
+    }
+}
+
+/// Size of the header and data blocks of a tar file
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// Regular files in a tar archive, like the NewGRF packages of the online content
+fn tar_entries(data: &[u8]) -> Vec<(String, &[u8])> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while let Some(header) = data.get(offset..offset + TAR_BLOCK_SIZE) {
+        let field = |range: std::ops::Range<usize>| {
+            let bytes = &header[range];
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        let name = field(0..100);
+        if name.is_empty() {
+            break;
+        }
+        let Ok(size) = usize::from_str_radix(&field(124..136), 8) else {
+            break;
+        };
+        let prefix = field(345..500);
+        let start = offset + TAR_BLOCK_SIZE;
+        if matches!(header[156], 0 | b'0') {
+            let Some(contents) = data.get(start..start.saturating_add(size)) else {
+                break;
+            };
+            let name = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            };
+            entries.push((name, contents));
+        }
+        offset = start.saturating_add(size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE);
+    }
+    entries
+}
+
+/// Scan the NewGRFs in a directory tree, including those packed in tar files
+fn scan_content(root: &Path, dir: &Path, files: &mut Vec<GrfFile>) {
+    let entries = fs::read_dir(dir)
+        .unwrap_or_else(|err| fail(format!("failed to read {}: {err}", dir.display())));
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            scan_content(root, &path, files);
+            continue;
+        }
+        let name = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        let extension = path.extension().map(|e| e.to_ascii_lowercase());
+        let Ok(data) = fs::read(&path) else { continue };
+        // Files that are not valid NewGRFs are skipped, like the game's scan does
+        match extension.as_ref().and_then(|e| e.to_str()) {
+            Some("grf") => files.extend(GrfFile::scan(&name, &data).ok()),
+            Some("tar") => {
+                for (entry, contents) in tar_entries(&data) {
+                    if entry.to_ascii_lowercase().ends_with(".grf") {
+                        files.extend(GrfFile::scan(&format!("{name}/{entry}"), contents).ok());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// List the NewGRFs a savegame needs; with a content directory, also which
+/// of them are available there, exiting with 1 when any is missing
+fn newgrf(args: &[String]) {
+    let (path, content) = match args {
+        [path] => (path, None),
+        [path, content] => (path, Some(Path::new(content))),
+        _ => usage(),
+    };
+    let bytes = fs::read(path).unwrap_or_else(|err| fail(format!("failed to read {path}: {err}")));
+    let configs = SavegameReader::new(&bytes)
+        .and_then(|reader| load_newgrf_configs(&reader.read_chunks()?, reader.header().version))
+        .unwrap_or_else(|err| fail(format!("failed to load the NewGRFs of {path}: {err}")));
+
+    let mut files = Vec::new();
+    if let Some(content) = content {
+        scan_content(content, content, &mut files);
+    }
+    let mut missing = false;
+    for config in &configs {
+        let md5sum: String = config.md5sum.iter().map(|b| format!("{b:02X}")).collect();
+        let mut line = format!(
+            "{:08X} {md5sum} {}",
+            config.grfid.swap_bytes(),
+            config.filename
+        );
+        if !config.params.is_empty() {
+            let params: Vec<String> = config.params.iter().map(u32::to_string).collect();
+            line += &format!(" [{}]", params.join(" "));
+        }
+        if content.is_some() {
+            let status = match config.find(&files) {
+                (GrfStatus::Found, Some(file)) => format!("found {}", file.filename),
+                (GrfStatus::Compatible, Some(file)) => format!("compatible {}", file.filename),
+                _ => {
+                    missing = true;
+                    "missing".to_string()
+                }
+            };
+            line += &format!(": {status}");
+        }
+        println!("{line}");
+    }
+    if missing {
+        std::process::exit(1);
+    }
+}
+
 fn main() {
-    let mut args = env::args().skip(1);
+    let command: Vec<String> = env::args().skip(1).collect();
+    match command.first().map(String::as_str) {
+        Some("export") => return export(&command[1..]),
+        Some("import") => return import(&command[1..]),
+        Some("diff") => return diff(&command[1..]),
+        Some("validate") => return check(&command[1..]),
+        Some("gamelog") => return gamelog(&command[1..]),
+        Some("newgrf") => return newgrf(&command[1..]),
+        _ => {}
+    }
+
+    let mut args = command.into_iter();
     let mut maybe_path = None;
     let mut make_window = false;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--window" => make_window = true,
-            "--help" | "-h" => {
-                eprintln!("usage: openttd_cli [--window] <savegame>");
-                std::process::exit(2);
-            }
+            "--help" | "-h" => usage(),
             _ => {
                 if maybe_path.is_none() {
                     maybe_path = Some(arg);
@@ -35,10 +290,7 @@ fn main() {
             // Allow testing window without savegame
             String::new()
         }
-        None => {
-            eprintln!("usage: openttd_cli [--window] <savegame>");
-            std::process::exit(2);
-        }
+        None => usage(),
     };
 
     if !path.is_empty() {
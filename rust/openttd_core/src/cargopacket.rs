@@ -0,0 +1,1131 @@
+//! Cargo packet data structures for OpenTTD
+//!
+//! Cargo is tracked as packets in a pool; stations and vehicles keep lists of
+//! packet indices. The list operations follow C++ cargopacket.cpp and
+//! cargoaction.cpp, with payment and routing left to the caller.
+
+use crate::error::CoreError;
+use crate::map::{Map, TileIndex};
+use crate::order::OrderUnloadType;
+use crate::types::{Money, StationID};
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+/// Cargo packet pool index (matches C++ CargoPacketID)
+pub type CargoPacketID = u32;
+
+/// Maximum number of cargo packets (matches C++ CargoPacketPool)
+pub const MAX_CARGO_PACKETS: usize = 0xFFF000;
+
+/// Kind of the source of cargo (matches C++ SourceType)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum SourceType {
+    #[default]
+    Industry = 0,
+    Town = 1,
+    Headquarters = 2,
+}
+
+impl TryFrom<u8> for SourceType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => SourceType::Industry,
+            1 => SourceType::Town,
+            2 => SourceType::Headquarters,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid source type {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+/// Industry, town or company headquarters cargo comes from (matches C++ Source)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Source {
+    pub id: u16,
+    pub type_: SourceType,
+}
+
+impl Source {
+    pub const INVALID_ID: u16 = 0xFFFF;
+
+    pub fn is_valid(&self) -> bool {
+        self.id != Self::INVALID_ID
+    }
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Self {
+            id: Self::INVALID_ID,
+            type_: SourceType::Industry,
+        }
+    }
+}
+
+/// An amount of cargo travelling together (matches C++ CargoPacket)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CargoPacket {
+    pub index: CargoPacketID,
+    pub count: u16,
+    pub periods_in_transit: u16,
+    /// Value of feeder transfers, paid out on final delivery
+    pub feeder_share: Money,
+    pub source_xy: TileIndex,
+    /// In a station the vector from the unloading tile to the source tile,
+    /// in a vehicle that vector plus the loading tile
+    pub travelled_x: i16,
+    pub travelled_y: i16,
+    pub source: Source,
+    pub first_station: StationID,
+    pub next_hop: StationID,
+}
+
+impl CargoPacket {
+    pub const MAX_COUNT: u16 = u16::MAX;
+
+    pub fn new(first_station: StationID, count: u16, source: Source) -> Self {
+        Self {
+            index: 0,
+            count,
+            periods_in_transit: 0,
+            feeder_share: 0,
+            source_xy: TileIndex::INVALID,
+            travelled_x: 0,
+            travelled_y: 0,
+            source,
+            first_station,
+            next_hop: StationID::INVALID,
+        }
+    }
+
+    /// The feeder share of `part` of the cargo
+    pub fn feeder_share_of(&self, part: u32) -> Money {
+        self.feeder_share * part as Money / self.count as Money
+    }
+
+    fn reduce(&mut self, count: u16) {
+        self.feeder_share -= self.feeder_share_of(count as u32);
+        self.count -= count;
+    }
+
+    pub fn update_loading_tile(&mut self, map: &Map, tile: TileIndex) {
+        if self.source_xy == TileIndex::INVALID {
+            self.source_xy = tile;
+        }
+        self.travelled_x = self.travelled_x.wrapping_add(map.tile_x(tile) as i16);
+        self.travelled_y = self.travelled_y.wrapping_add(map.tile_y(tile) as i16);
+    }
+
+    pub fn update_unloading_tile(&mut self, map: &Map, tile: TileIndex) {
+        self.travelled_x = self.travelled_x.wrapping_sub(map.tile_x(tile) as i16);
+        self.travelled_y = self.travelled_y.wrapping_sub(map.tile_y(tile) as i16);
+    }
+
+    /// Distance the cargo in a vehicle travelled to `current_tile`, capped at
+    /// the distance from its source
+    pub fn distance(&self, map: &Map, current_tile: TileIndex) -> u32 {
+        let dx = self.travelled_x as i32 - map.tile_x(current_tile) as i32;
+        let dy = self.travelled_y as i32 - map.tile_y(current_tile) as i32;
+        let travelled = dx.unsigned_abs() + dy.unsigned_abs();
+        let direct = map
+            .tile_x(self.source_xy)
+            .abs_diff(map.tile_x(current_tile))
+            + map
+                .tile_y(self.source_xy)
+                .abs_diff(map.tile_y(current_tile));
+        travelled.min(direct)
+    }
+
+    fn is_mergable(&self, other: &CargoPacket) -> bool {
+        self.source_xy == other.source_xy
+            && self.periods_in_transit == other.periods_in_transit
+            && self.first_station == other.first_station
+            && self.source == other.source
+    }
+}
+
+/// All cargo packets of a game, by index
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CargoPacketPool {
+    packets: Vec<Option<CargoPacket>>,
+}
+
+impl CargoPacketPool {
+    /// Build a pool from packets at their own indices
+    pub fn from_packets(packets: Vec<CargoPacket>) -> Result<Self, CoreError> {
+        let mut pool = Self::default();
+        for packet in packets {
+            let index = packet.index as usize;
+            if index >= MAX_CARGO_PACKETS {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid cargo packet index {}",
+                    index
+                )));
+            }
+            if index >= pool.packets.len() {
+                pool.packets.resize(index + 1, None);
+            }
+            if pool.packets[index].replace(packet).is_some() {
+                return Err(CoreError::InvalidData(format!(
+                    "Duplicate cargo packet {}",
+                    index
+                )));
+            }
+        }
+        Ok(pool)
+    }
+
+    pub fn get(&self, index: CargoPacketID) -> Option<&CargoPacket> {
+        self.packets.get(index as usize)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, index: CargoPacketID) -> Option<&mut CargoPacket> {
+        self.packets.get_mut(index as usize)?.as_mut()
+    }
+
+    fn packet(&self, index: CargoPacketID) -> &CargoPacket {
+        self.get(index)
+            .expect("cargo list refers to a missing packet")
+    }
+
+    fn packet_mut(&mut self, index: CargoPacketID) -> &mut CargoPacket {
+        self.get_mut(index)
+            .expect("cargo list refers to a missing packet")
+    }
+
+    /// Add a packet at the first free index; None when the pool is full
+    pub fn insert(&mut self, mut packet: CargoPacket) -> Option<CargoPacketID> {
+        let index = match self.packets.iter().position(Option::is_none) {
+            Some(index) => index,
+            None if self.packets.len() < MAX_CARGO_PACKETS => {
+                self.packets.push(None);
+                self.packets.len() - 1
+            }
+            None => return None,
+        };
+        packet.index = index as CargoPacketID;
+        self.packets[index] = Some(packet);
+        Some(index as CargoPacketID)
+    }
+
+    pub fn remove(&mut self, index: CargoPacketID) -> Option<CargoPacket> {
+        self.packets.get_mut(index as usize)?.take()
+    }
+
+    /// Packets in index order
+    pub fn iter(&self) -> impl Iterator<Item = &CargoPacket> {
+        self.packets.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.iter().all(Option::is_none)
+    }
+
+    /// Split `new_size` cargo off into a new packet; None when the pool is full
+    pub fn split(&mut self, index: CargoPacketID, new_size: u16) -> Option<CargoPacketID> {
+        let original = self.packet(index);
+        let share = original.feeder_share_of(new_size as u32);
+        let mut new_packet = original.clone();
+        new_packet.count = new_size;
+        new_packet.feeder_share = share;
+        let new_index = self.insert(new_packet)?;
+        let original = self.packet_mut(index);
+        original.feeder_share -= share;
+        original.count -= new_size;
+        Some(new_index)
+    }
+
+    /// Merge `other` into `index` if they are alike and fit in one packet
+    fn try_merge(&mut self, index: CargoPacketID, other: CargoPacketID) -> bool {
+        let (packet, merged) = (self.packet(index), self.packet(other));
+        if !packet.is_mergable(merged)
+            || packet.count as u32 + merged.count as u32 > CargoPacket::MAX_COUNT as u32
+        {
+            return false;
+        }
+        let merged = self.remove(other).unwrap();
+        let packet = self.packet_mut(index);
+        packet.count += merged.count;
+        packet.feeder_share += merged.feeder_share;
+        true
+    }
+
+    /// Forget the source of all cargo from `source`, e.g. when it is removed
+    pub fn invalidate_all_from_source(&mut self, source: Source) {
+        for packet in self.packets.iter_mut().flatten() {
+            if packet.source == source {
+                packet.source.id = Source::INVALID_ID;
+            }
+        }
+    }
+
+    /// Forget the first station of all cargo from `station`
+    pub fn invalidate_all_from_station(&mut self, station: StationID) {
+        for packet in self.packets.iter_mut().flatten() {
+            if packet.first_station == station {
+                packet.first_station = StationID::INVALID;
+            }
+        }
+    }
+}
+
+/// What happens to cargo in a vehicle at the current station (matches C++ MoveToAction)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum MoveToAction {
+    Transfer = 0,
+    Deliver = 1,
+    Keep = 2,
+    Load = 3,
+}
+
+/// Number of move-to actions tracked by a vehicle cargo list
+pub const NUM_MOVE_TO_ACTION: usize = 4;
+
+/// Sum of the periods in transit of each cargo entity, divided by the amount
+fn average_periods_in_transit<'a>(packets: impl Iterator<Item = &'a CargoPacket>) -> u16 {
+    let (count, periods) = packets.fold((0u64, 0u64), |(count, periods), cp| {
+        (
+            count + cp.count as u64,
+            periods + cp.periods_in_transit as u64 * cp.count as u64,
+        )
+    });
+    periods.checked_div(count).unwrap_or(0) as u16
+}
+
+/// Cargo packets loaded in a vehicle (matches C++ VehicleCargoList)
+///
+/// The packets are ordered by action: transfers first, then deliveries,
+/// then cargo to keep, then cargo reserved for loading.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VehicleCargoList {
+    /// Indices into the cargo packet pool
+    pub packets: Vec<CargoPacketID>,
+    /// Amount of cargo designated for each move-to action
+    pub action_counts: [u32; NUM_MOVE_TO_ACTION],
+}
+
+impl VehicleCargoList {
+    pub fn action_count(&self, action: MoveToAction) -> u32 {
+        self.action_counts[action as usize]
+    }
+
+    pub fn total_count(&self) -> u32 {
+        self.action_counts.iter().sum()
+    }
+
+    /// Cargo in the vehicle, without cargo reserved for loading
+    pub fn stored_count(&self) -> u32 {
+        self.total_count() - self.action_count(MoveToAction::Load)
+    }
+
+    pub fn reserved_count(&self) -> u32 {
+        self.action_count(MoveToAction::Load)
+    }
+
+    pub fn unload_count(&self) -> u32 {
+        self.action_count(MoveToAction::Transfer) + self.action_count(MoveToAction::Deliver)
+    }
+
+    pub fn remaining_count(&self) -> u32 {
+        self.action_count(MoveToAction::Keep) + self.action_count(MoveToAction::Load)
+    }
+
+    pub fn feeder_share(&self, pool: &CargoPacketPool) -> Money {
+        self.packets
+            .iter()
+            .map(|&id| pool.packet(id).feeder_share)
+            .sum()
+    }
+
+    pub fn periods_in_transit(&self, pool: &CargoPacketPool) -> u16 {
+        average_periods_in_transit(self.packets.iter().map(|&id| pool.packet(id)))
+    }
+
+    pub fn first_station(&self, pool: &CargoPacketPool) -> StationID {
+        self.packets
+            .first()
+            .map_or(StationID::INVALID, |&id| pool.packet(id).first_station)
+    }
+
+    /// Add a packet to keep or to load, merging it with a similar one of the same action
+    pub fn append(&mut self, pool: &mut CargoPacketPool, id: CargoPacketID, action: MoveToAction) {
+        debug_assert!(
+            action == MoveToAction::Load
+                || (action == MoveToAction::Keep && self.reserved_count() == 0)
+        );
+        let count = pool.packet(id).count as u32;
+        self.action_counts[action as usize] += count;
+        if self.total_count() == count {
+            self.packets.push(id);
+            return;
+        }
+
+        let mut sum = count;
+        for &other in self.packets.iter().rev() {
+            if pool.try_merge(other, id) {
+                return;
+            }
+            sum += pool.packet(other).count as u32;
+            if sum >= self.action_counts[action as usize] {
+                break;
+            }
+        }
+        self.packets.push(id);
+    }
+
+    /// Keep all cargo in the vehicle
+    pub fn keep_all(&mut self) {
+        let total = self.total_count();
+        self.action_counts = [0; NUM_MOVE_TO_ACTION];
+        self.action_counts[MoveToAction::Keep as usize] = total;
+    }
+
+    pub fn age_cargo(&self, pool: &mut CargoPacketPool) {
+        for &id in &self.packets {
+            let packet = pool.packet_mut(id);
+            packet.periods_in_transit = packet.periods_in_transit.saturating_add(1);
+        }
+    }
+
+    fn choose_action(
+        packet: &CargoPacket,
+        cargo_next: StationID,
+        current_station: StationID,
+        accepted: bool,
+        next_station: &[StationID],
+    ) -> MoveToAction {
+        if cargo_next == StationID::INVALID {
+            if accepted && packet.first_station != current_station {
+                MoveToAction::Deliver
+            } else {
+                MoveToAction::Keep
+            }
+        } else if cargo_next == current_station {
+            MoveToAction::Deliver
+        } else if next_station.contains(&cargo_next) {
+            MoveToAction::Keep
+        } else {
+            MoveToAction::Transfer
+        }
+    }
+
+    /// Decide what happens to each packet at `current_station`
+    ///
+    /// `route` gives the planned next hop of cargo from a first station, or
+    /// `StationID::INVALID` without a plan. `pay_transfer` returns the feeder
+    /// share credited for transferring a packet. Returns whether any cargo is
+    /// to be unloaded.
+    #[allow(clippy::too_many_arguments)]
+    pub fn stage(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        accepted: bool,
+        current_station: StationID,
+        next_station: &[StationID],
+        unload_type: OrderUnloadType,
+        route: impl Fn(StationID) -> StationID,
+        mut pay_transfer: impl FnMut(&CargoPacket) -> Money,
+    ) -> bool {
+        debug_assert_eq!(self.reserved_count(), 0);
+        let force_keep = unload_type == OrderUnloadType::NoUnload;
+        let force_unload = unload_type == OrderUnloadType::Unload;
+        let force_transfer = matches!(
+            unload_type,
+            OrderUnloadType::Transfer | OrderUnloadType::Unload
+        );
+
+        let (mut transfer, mut deliver, mut keep) = (Vec::new(), Vec::new(), Vec::new());
+        self.action_counts = [0; NUM_MOVE_TO_ACTION];
+        for id in std::mem::take(&mut self.packets) {
+            let packet = pool.packet(id);
+            let mut cargo_next = StationID::INVALID;
+            let action = if force_keep {
+                MoveToAction::Keep
+            } else if force_unload && accepted && packet.first_station != current_station {
+                MoveToAction::Deliver
+            } else if force_transfer {
+                // The cargo cannot go to where the vehicle goes next
+                cargo_next = route(packet.first_station);
+                if cargo_next == current_station || next_station.contains(&cargo_next) {
+                    cargo_next = StationID::INVALID;
+                }
+                MoveToAction::Transfer
+            } else {
+                cargo_next = route(packet.first_station);
+                Self::choose_action(packet, cargo_next, current_station, accepted, next_station)
+            };
+
+            self.action_counts[action as usize] += packet.count as u32;
+            match action {
+                MoveToAction::Transfer => {
+                    let share = pay_transfer(packet);
+                    let packet = pool.packet_mut(id);
+                    packet.feeder_share += share;
+                    packet.next_hop = cargo_next;
+                    transfer.push(id);
+                }
+                MoveToAction::Deliver => deliver.push(id),
+                _ => keep.push(id),
+            }
+        }
+        // Transfers are pushed to the front in C++, so they end up reversed
+        transfer.reverse();
+        self.packets = transfer;
+        self.packets.extend(deliver);
+        self.packets.extend(keep);
+        self.unload_count() > 0
+    }
+
+    /// Move up to `max_move` cargo between two actions that are next to each
+    /// other in the list, without moving packets; not for transfers
+    pub fn reassign(&mut self, from: MoveToAction, to: MoveToAction, max_move: u32) -> u32 {
+        assert!(from != MoveToAction::Transfer && to != MoveToAction::Transfer);
+        assert_eq!((from as u8).abs_diff(to as u8), 1);
+        let max_move = max_move.min(self.action_count(from));
+        self.action_counts[from as usize] -= max_move;
+        self.action_counts[to as usize] += max_move;
+        max_move
+    }
+
+    /// Turn up to `max_move` cargo to deliver into cargo to transfer, without a next hop
+    pub fn reassign_deliver_to_transfer(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        max_move: u32,
+    ) -> u32 {
+        let max_move = max_move.min(self.action_count(MoveToAction::Deliver));
+        let start = self.action_count(MoveToAction::Transfer);
+        let end = start + max_move;
+        let mut sum = 0;
+        let mut i = 0;
+        while sum < end {
+            let id = self.packets[i];
+            sum += pool.packet(id).count as u32;
+            i += 1;
+            if sum <= start {
+                continue;
+            }
+            if sum > end {
+                // Leave the part beyond `max_move` to be delivered
+                if let Some(rest) = pool.split(id, (sum - end) as u16) {
+                    sum = end;
+                    self.packets.insert(i, rest);
+                }
+            }
+            pool.packet_mut(id).next_hop = StationID::INVALID;
+        }
+        self.action_counts[MoveToAction::Deliver as usize] -= max_move;
+        self.action_counts[MoveToAction::Transfer as usize] += max_move;
+        max_move
+    }
+
+    /// Take up to `max_move` cargo of `action`, whole packets or split off ones,
+    /// from the front or the back of the list
+    fn take(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        action: MoveToAction,
+        max_move: u32,
+        from_back: bool,
+        split_whole: bool,
+    ) -> Vec<CargoPacketID> {
+        let mut taken = Vec::new();
+        let mut left = max_move;
+        while left > 0 && !self.packets.is_empty() {
+            let position = if from_back { self.packets.len() - 1 } else { 0 };
+            let id = self.packets[position];
+            let count = pool.packet(id).count as u32;
+            if left < count {
+                match pool.split(id, left as u16) {
+                    Some(new_id) => taken.push(new_id),
+                    // C++ moves the whole packet for some actions when it cannot split
+                    None if split_whole => {
+                        self.packets.remove(position);
+                        taken.push(id);
+                    }
+                    None => {}
+                }
+                break;
+            }
+            self.packets.remove(position);
+            taken.push(id);
+            left -= count;
+        }
+        for &id in &taken {
+            self.action_counts[action as usize] -= pool.packet(id).count as u32;
+        }
+        taken
+    }
+
+    /// Return up to `max_move` cargo reserved for loading to the station
+    pub fn return_cargo(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        max_move: u32,
+        dest: &mut StationCargoList,
+        next: StationID,
+        map: &Map,
+        current_tile: TileIndex,
+    ) -> u32 {
+        let max_move = max_move.min(self.reserved_count());
+        let mut moved = 0;
+        for id in self.take(pool, MoveToAction::Load, max_move, true, true) {
+            let packet = pool.packet_mut(id);
+            packet.update_unloading_tile(map, current_tile);
+            moved += packet.count as u32;
+            dest.reserved_count -= packet.count as u32;
+            dest.append(pool, id, next);
+        }
+        moved
+    }
+
+    /// Move up to `max_move` cargo to keep into another vehicle
+    pub fn shift(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        max_move: u32,
+        dest: &mut VehicleCargoList,
+    ) -> u32 {
+        let max_move = max_move.min(self.total_count());
+        let mut moved = 0;
+        for id in self.take(pool, MoveToAction::Keep, max_move, true, true) {
+            moved += pool.packet(id).count as u32;
+            dest.append(pool, id, MoveToAction::Keep);
+        }
+        moved
+    }
+
+    /// Transfer and then deliver up to `max_move` cargo at the current station
+    ///
+    /// Transferred cargo goes to `dest` for its next hop; `on_delivery` is
+    /// called for every delivered part of a packet before it is removed.
+    pub fn unload(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        max_move: u32,
+        dest: &mut StationCargoList,
+        map: &Map,
+        current_tile: TileIndex,
+        mut on_delivery: impl FnMut(&CargoPacket, u16),
+    ) -> u32 {
+        let mut moved = 0;
+        let transfer = self.action_count(MoveToAction::Transfer);
+        if transfer > 0 {
+            let max = transfer.min(max_move);
+            for id in self.take(pool, MoveToAction::Transfer, max, false, false) {
+                let packet = pool.packet_mut(id);
+                packet.update_unloading_tile(map, current_tile);
+                let next_hop = packet.next_hop;
+                dest.append(pool, id, next_hop);
+            }
+            moved += max;
+        }
+        let deliver = self.action_count(MoveToAction::Deliver);
+        if self.action_count(MoveToAction::Transfer) == 0 && deliver > 0 && moved < max_move {
+            let max = deliver.min(max_move - moved);
+            self.remove_front(pool, MoveToAction::Deliver, max, &mut on_delivery);
+            moved += max;
+        }
+        moved
+    }
+
+    /// Remove up to `max_move` cargo from the front, reducing the last packet if needed
+    fn remove_front(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        action: MoveToAction,
+        max_move: u32,
+        on_removal: &mut impl FnMut(&CargoPacket, u16),
+    ) {
+        let mut left = max_move;
+        while left > 0 && !self.packets.is_empty() {
+            let id = self.packets[0];
+            let packet = pool.packet_mut(id);
+            let remove = left.min(packet.count as u32) as u16;
+            on_removal(packet, remove);
+            self.action_counts[action as usize] -= remove as u32;
+            left -= remove as u32;
+            if remove == packet.count {
+                pool.remove(id);
+                self.packets.remove(0);
+            } else {
+                packet.reduce(remove);
+            }
+        }
+    }
+
+    /// Remove up to `max_move` cargo to keep from the back of the list
+    pub fn truncate(&mut self, pool: &mut CargoPacketPool, max_move: u32) -> u32 {
+        let max_move = max_move.min(self.total_count());
+        let mut left = max_move;
+        while left > 0 && !self.packets.is_empty() {
+            let id = *self.packets.last().unwrap();
+            let packet = pool.packet_mut(id);
+            let remove = left.min(packet.count as u32) as u16;
+            self.action_counts[MoveToAction::Keep as usize] -= remove as u32;
+            left -= remove as u32;
+            if remove == packet.count {
+                pool.remove(id);
+                self.packets.pop();
+            } else {
+                packet.reduce(remove);
+            }
+        }
+        max_move
+    }
+}
+
+/// Cargo packets waiting at a station for the same next hop
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationCargoPackets {
+    pub next_hop: StationID,
+    /// Indices into the cargo packet pool
+    pub packets: Vec<CargoPacketID>,
+}
+
+/// Cargo packets waiting at a station, grouped by next hop (matches C++ StationCargoList)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationCargoList {
+    /// Groups in increasing next hop order
+    pub packets: Vec<StationCargoPackets>,
+    /// Cargo reserved by vehicles that are loading; no longer in `packets`
+    pub reserved_count: u32,
+}
+
+impl StationCargoList {
+    fn group(&self, next: StationID) -> Option<&StationCargoPackets> {
+        self.packets.iter().find(|g| g.next_hop == next)
+    }
+
+    fn group_mut(&mut self, next: StationID) -> &mut StationCargoPackets {
+        let position = match self.packets.binary_search_by_key(&next.0, |g| g.next_hop.0) {
+            Ok(position) => position,
+            Err(position) => {
+                self.packets.insert(
+                    position,
+                    StationCargoPackets {
+                        next_hop: next,
+                        packets: Vec::new(),
+                    },
+                );
+                position
+            }
+        };
+        &mut self.packets[position]
+    }
+
+    fn ids(&self) -> impl Iterator<Item = CargoPacketID> + '_ {
+        self.packets.iter().flat_map(|g| g.packets.iter().copied())
+    }
+
+    /// Cargo waiting and not reserved
+    pub fn available_count(&self, pool: &CargoPacketPool) -> u32 {
+        self.ids().map(|id| pool.packet(id).count as u32).sum()
+    }
+
+    pub fn reserved_count(&self) -> u32 {
+        self.reserved_count
+    }
+
+    pub fn total_count(&self, pool: &CargoPacketPool) -> u32 {
+        self.available_count(pool) + self.reserved_count
+    }
+
+    pub fn periods_in_transit(&self, pool: &CargoPacketPool) -> u16 {
+        average_periods_in_transit(self.ids().map(|id| pool.packet(id)))
+    }
+
+    pub fn first_station(&self, pool: &CargoPacketPool) -> StationID {
+        self.ids()
+            .next()
+            .map_or(StationID::INVALID, |id| pool.packet(id).first_station)
+    }
+
+    /// Whether there is cargo for one of `next`, or cargo that may go anywhere
+    pub fn has_cargo_for(&self, next: &[StationID]) -> bool {
+        next.iter()
+            .chain([&StationID::INVALID])
+            .any(|&station| self.group(station).is_some())
+    }
+
+    /// Add a packet for `next`, merging it with a similar one if possible
+    pub fn append(&mut self, pool: &mut CargoPacketPool, id: CargoPacketID, next: StationID) {
+        let group = self.group_mut(next);
+        if !group
+            .packets
+            .iter()
+            .rev()
+            .any(|&other| pool.try_merge(other, id))
+        {
+            group.packets.push(id);
+        }
+    }
+
+    /// Take up to `max_move` cargo for `next` from the front, splitting the last packet
+    fn take(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        next: StationID,
+        max_move: u32,
+    ) -> Vec<CargoPacketID> {
+        let mut taken = Vec::new();
+        let Some(position) = self.packets.iter().position(|g| g.next_hop == next) else {
+            return taken;
+        };
+        let group = &mut self.packets[position].packets;
+        let mut left = max_move;
+        while left > 0 && !group.is_empty() {
+            let count = pool.packet(group[0]).count as u32;
+            if left < count {
+                taken.extend(pool.split(group[0], left as u16));
+                break;
+            }
+            taken.push(group.remove(0));
+            left -= count;
+        }
+        if group.is_empty() {
+            self.packets.remove(position);
+        }
+        taken
+    }
+
+    /// Move up to `max_move` cargo for the next stations, last one first, and
+    /// then cargo for anywhere into `dest`
+    #[allow(clippy::too_many_arguments)]
+    fn move_to_vehicle(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        max_move: u32,
+        dest: &mut VehicleCargoList,
+        next_station: &[StationID],
+        action: MoveToAction,
+        map: &Map,
+        current_tile: TileIndex,
+    ) -> u32 {
+        let mut moved = 0;
+        for &next in next_station.iter().rev().chain([&StationID::INVALID]) {
+            if moved == max_move {
+                break;
+            }
+            for id in self.take(pool, next, max_move - moved) {
+                let packet = pool.packet_mut(id);
+                packet.update_loading_tile(map, current_tile);
+                moved += packet.count as u32;
+                dest.append(pool, id, action);
+            }
+        }
+        moved
+    }
+
+    /// Reserve up to `max_move` cargo for a vehicle that is going to load it
+    pub fn reserve(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        max_move: u32,
+        dest: &mut VehicleCargoList,
+        next_station: &[StationID],
+        map: &Map,
+        current_tile: TileIndex,
+    ) -> u32 {
+        let moved = self.move_to_vehicle(
+            pool,
+            max_move,
+            dest,
+            next_station,
+            MoveToAction::Load,
+            map,
+            current_tile,
+        );
+        self.reserved_count += moved;
+        moved
+    }
+
+    /// Load up to `max_move` cargo, from the vehicle's reservation if it has one
+    pub fn load(
+        &mut self,
+        pool: &mut CargoPacketPool,
+        max_move: u32,
+        dest: &mut VehicleCargoList,
+        next_station: &[StationID],
+        map: &Map,
+        current_tile: TileIndex,
+    ) -> u32 {
+        let reserved = dest.reserved_count().min(max_move);
+        if reserved > 0 {
+            self.reserved_count -= reserved;
+            dest.reassign(MoveToAction::Load, MoveToAction::Keep, reserved)
+        } else {
+            self.move_to_vehicle(
+                pool,
+                max_move,
+                dest,
+                next_station,
+                MoveToAction::Keep,
+                map,
+                current_tile,
+            )
+        }
+    }
+
+    /// Remove up to `max_move` waiting cargo, oldest first for every next hop
+    ///
+    /// C++ picks the packets to remove at random; this removes them in order.
+    pub fn truncate(&mut self, pool: &mut CargoPacketPool, max_move: u32) -> u32 {
+        let mut left = max_move;
+        for group in &mut self.packets {
+            while left > 0 && !group.packets.is_empty() {
+                let packet = pool.packet_mut(group.packets[0]);
+                if (packet.count as u32) > left {
+                    packet.reduce(left as u16);
+                    left = 0;
+                } else {
+                    left -= packet.count as u32;
+                    pool.remove(group.packets.remove(0));
+                }
+            }
+        }
+        self.packets.retain(|g| !g.packets.is_empty());
+        max_move - left
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: StationID = StationID(1);
+    const B: StationID = StationID(2);
+    const C: StationID = StationID(3);
+
+    fn packet(pool: &mut CargoPacketPool, first_station: StationID, count: u16) -> CargoPacketID {
+        let source = Source {
+            id: 5,
+            type_: SourceType::Town,
+        };
+        pool.insert(CargoPacket::new(first_station, count, source))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_pool() {
+        let mut pool = CargoPacketPool::default();
+        let a = packet(&mut pool, A, 10);
+        let b = packet(&mut pool, A, 20);
+        assert_eq!((a, b), (0, 1));
+        pool.remove(a);
+        assert_eq!(packet(&mut pool, B, 5), 0);
+        assert_eq!(pool.len(), 2);
+
+        pool.get_mut(b).unwrap().feeder_share = 200;
+        let split = pool.split(b, 5).unwrap();
+        assert_eq!(pool.get(split).unwrap().count, 5);
+        assert_eq!(pool.get(split).unwrap().feeder_share, 50);
+        assert_eq!(pool.get(b).unwrap().count, 15);
+        assert_eq!(pool.get(b).unwrap().feeder_share, 150);
+
+        let packets: Vec<CargoPacket> = pool.iter().cloned().collect();
+        assert_eq!(
+            CargoPacketPool::from_packets(packets.clone()).unwrap(),
+            pool
+        );
+        let duplicate = vec![packets[0].clone(), packets[0].clone()];
+        assert!(CargoPacketPool::from_packets(duplicate).is_err());
+    }
+
+    #[test]
+    fn test_station_append_merges() {
+        let mut pool = CargoPacketPool::default();
+        let mut list = StationCargoList::default();
+        let a = packet(&mut pool, A, 10);
+        let b = packet(&mut pool, A, 20);
+        let c = packet(&mut pool, B, 30);
+        list.append(&mut pool, a, C);
+        list.append(&mut pool, b, C);
+        list.append(&mut pool, c, StationID::INVALID);
+        let full = packet(&mut pool, A, u16::MAX);
+        list.append(&mut pool, full, C);
+
+        assert_eq!(pool.len(), 3);
+        assert_eq!(list.packets.len(), 2);
+        assert_eq!(list.packets[0].next_hop, C);
+        assert_eq!(list.packets[0].packets[0], a);
+        assert_eq!(pool.get(a).unwrap().count, 30);
+        assert_eq!(list.available_count(&pool), 30 + 30 + u16::MAX as u32);
+        assert!(list.has_cargo_for(&[B]));
+        assert_eq!(list.first_station(&pool), A);
+    }
+
+    #[test]
+    fn test_reserve_and_load() {
+        let map = Map::new(6, 6).unwrap();
+        let tile = map.tile_xy(3, 4);
+        let mut pool = CargoPacketPool::default();
+        let mut station = StationCargoList::default();
+        let mut vehicle = VehicleCargoList::default();
+        let p = packet(&mut pool, A, 50);
+        station.append(&mut pool, p, B);
+        let q = packet(&mut pool, B, 10);
+        station.append(&mut pool, q, StationID::INVALID);
+
+        // Cargo for the next stops is taken before cargo for anywhere
+        assert_eq!(
+            station.reserve(&mut pool, 55, &mut vehicle, &[B], &map, tile),
+            55
+        );
+        assert_eq!(station.reserved_count(), 55);
+        assert_eq!(station.available_count(&pool), 5);
+        assert_eq!(vehicle.reserved_count(), 55);
+        assert_eq!(vehicle.stored_count(), 0);
+        let loaded = pool.get(vehicle.packets[0]).unwrap();
+        assert_eq!(
+            (loaded.source_xy, loaded.travelled_x, loaded.travelled_y),
+            (tile, 3, 4)
+        );
+
+        assert_eq!(
+            station.load(&mut pool, 40, &mut vehicle, &[B], &map, tile),
+            40
+        );
+        assert_eq!(vehicle.action_count(MoveToAction::Keep), 40);
+        assert_eq!(station.reserved_count(), 15);
+
+        // Returning the rest puts it back for the given next hop
+        assert_eq!(
+            vehicle.return_cargo(&mut pool, 100, &mut station, C, &map, tile),
+            15
+        );
+        assert_eq!(station.reserved_count(), 0);
+        assert_eq!(station.total_count(&pool), 20);
+        assert_eq!(vehicle.total_count(), 40);
+        assert!(station.has_cargo_for(&[C]));
+    }
+
+    #[test]
+    fn test_stage_and_unload() {
+        let map = Map::new(6, 6).unwrap();
+        let here = map.tile_xy(10, 10);
+        let mut pool = CargoPacketPool::default();
+        let mut vehicle = VehicleCargoList::default();
+        let mut station = StationCargoList::default();
+        for (first, count) in [(A, 10), (B, 20), (C, 30)] {
+            let id = packet(&mut pool, first, count);
+            pool.get_mut(id).unwrap().periods_in_transit = count;
+            vehicle.append(&mut pool, id, MoveToAction::Keep);
+        }
+        assert_eq!(vehicle.periods_in_transit(&pool), 23);
+
+        // Cargo from A is routed here, from B onwards to C and from C nowhere
+        let route = |first: StationID| match first {
+            A => StationID(9),
+            B => C,
+            _ => StationID::INVALID,
+        };
+        let unloading = vehicle.stage(
+            &mut pool,
+            true,
+            StationID(9),
+            &[StationID(4)],
+            OrderUnloadType::UnloadIfPossible,
+            route,
+            |cp| cp.count as Money * 3,
+        );
+        assert!(unloading);
+        assert_eq!(vehicle.action_counts, [20, 40, 0, 0]);
+        assert_eq!(vehicle.feeder_share(&pool), 60);
+
+        let mut delivered = Vec::new();
+        let moved = vehicle.unload(&mut pool, 45, &mut station, &map, here, |cp, count| {
+            delivered.push((cp.first_station, count))
+        });
+        assert_eq!(moved, 45);
+        assert_eq!(delivered, [(A, 10), (C, 15)]);
+        assert!(station.has_cargo_for(&[C]));
+        assert_eq!(station.available_count(&pool), 20);
+        assert_eq!(vehicle.action_counts, [0, 15, 0, 0]);
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_forced_unload() {
+        let mut pool = CargoPacketPool::default();
+        let mut vehicle = VehicleCargoList::default();
+        let a = packet(&mut pool, A, 10);
+        vehicle.append(&mut pool, a, MoveToAction::Keep);
+        let b = packet(&mut pool, B, 10);
+        vehicle.append(&mut pool, b, MoveToAction::Keep);
+        assert_eq!(pool.len(), 2);
+
+        // Cargo that came from here cannot be delivered here and is transferred
+        vehicle.stage(
+            &mut pool,
+            true,
+            A,
+            &[],
+            OrderUnloadType::Unload,
+            |_| A,
+            |_| 0,
+        );
+        assert_eq!(vehicle.packets, [a, b]);
+        assert_eq!(vehicle.action_counts, [10, 10, 0, 0]);
+        assert_eq!(pool.get(a).unwrap().next_hop, StationID::INVALID);
+
+        assert_eq!(vehicle.reassign_deliver_to_transfer(&mut pool, 4), 4);
+        assert_eq!(vehicle.action_counts, [14, 6, 0, 0]);
+        assert_eq!(vehicle.packets.len(), 3);
+        assert_eq!(pool.get(vehicle.packets[2]).unwrap().count, 6);
+
+        vehicle.stage(
+            &mut pool,
+            false,
+            A,
+            &[],
+            OrderUnloadType::NoUnload,
+            |_| A,
+            |_| 0,
+        );
+        assert_eq!(vehicle.action_counts, [0, 0, 20, 0]);
+    }
+
+    #[test]
+    fn test_shift_and_truncate() {
+        let mut pool = CargoPacketPool::default();
+        let mut front = VehicleCargoList::default();
+        let mut back = VehicleCargoList::default();
+        let a = packet(&mut pool, A, 10);
+        front.append(&mut pool, a, MoveToAction::Keep);
+        let b = packet(&mut pool, B, 10);
+        front.append(&mut pool, b, MoveToAction::Keep);
+
+        assert_eq!(front.shift(&mut pool, 15, &mut back), 15);
+        assert_eq!(front.total_count(), 5);
+        assert_eq!(back.total_count(), 15);
+        assert_eq!(back.first_station(&pool), B);
+        assert_eq!(back.truncate(&mut pool, 12), 12);
+        assert_eq!(back.total_count(), 3);
+        back.age_cargo(&mut pool);
+        assert_eq!(back.periods_in_transit(&pool), 1);
+
+        let mut station = StationCargoList::default();
+        let c = packet(&mut pool, C, 8);
+        station.append(&mut pool, c, A);
+        let d = packet(&mut pool, A, 8);
+        station.append(&mut pool, d, B);
+        assert_eq!(station.truncate(&mut pool, 10), 10);
+        assert_eq!(station.packets.len(), 1);
+        assert_eq!(station.available_count(&pool), 6);
+        assert_eq!(station.truncate(&mut pool, 10), 6);
+        assert!(station.packets.is_empty());
+    }
+}
@@ -29,19 +29,22 @@ pub enum LiveryScheme {
     MaglevEngine = 5,
     DMU = 6,
     EMU = 7,
-    PassengerWagon = 8,
-    FreightWagon = 9,
-    Bus = 10,
-    Truck = 11,
-    PassengerShip = 12,
-    FreightShip = 13,
-    Helicopter = 14,
-    SmallPlane = 15,
-    LargePlane = 16,
-    PassengerTram = 17,
-    FreightTram = 18,
-    RoadVehicle = 19,
-    End = 20,
+    PassengerWagonSteam = 8,
+    PassengerWagonDiesel = 9,
+    PassengerWagonElectric = 10,
+    PassengerWagonMonorail = 11,
+    PassengerWagonMaglev = 12,
+    FreightWagon = 13,
+    Bus = 14,
+    Truck = 15,
+    PassengerShip = 16,
+    FreightShip = 17,
+    Helicopter = 18,
+    SmallPlane = 19,
+    LargePlane = 20,
+    PassengerTram = 21,
+    FreightTram = 22,
+    End = 23,
 }
 
 pub const LS_END: usize = LiveryScheme::End as usize;
@@ -190,7 +193,7 @@ pub struct CompanySettings {
 /// This represents the core company data that is saved in savegames.
 /// The structure must maintain compatibility with C++ for save/load.
 #[repr(C)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Company {
     // Pool item fields
     pub index: u8, // CompanyID
@@ -205,8 +208,12 @@ pub struct Company {
     pub president_name_2: u32,
     pub president_name: String,
 
+    // Network clients allowed to join
+    pub allow_list: Vec<String>,
+
     // Appearance
     pub face: CompanyManagerFace,
+    pub face_style: String,
     pub colour: Colours,
 
     // Financial
@@ -249,6 +256,7 @@ pub struct Company {
 
     // Settings
     pub settings: CompanySettings,
+    pub engine_renew_list: Option<u32>, // Index of the first autoreplace rule
 
     // Infrastructure
     pub infrastructure: CompanyInfrastructure,
@@ -273,7 +281,9 @@ impl Company {
             president_name_1: INVALID_STRING_ID,
             president_name_2: 0,
             president_name: String::new(),
+            allow_list: Vec::new(),
             face: 0,
+            face_style: String::new(),
             colour: Colours::Red,
             money: 100000, // Starting money
             money_fraction: 0,
@@ -298,6 +308,7 @@ impl Company {
             num_valid_stat_ent: 0,
             livery: [Livery::default(); LS_END],
             settings: CompanySettings::default(),
+            engine_renew_list: None,
             infrastructure: CompanyInfrastructure::default(),
             avail_railtypes: 0,
             avail_roadtypes: 0,
@@ -380,9 +391,10 @@ mod tests {
     #[test]
     fn test_livery_scheme_values() {
         assert_eq!(LiveryScheme::Default as u8, 0);
-        assert_eq!(LiveryScheme::Bus as u8, 10);
-        assert_eq!(LiveryScheme::End as u8, 20);
-        assert_eq!(LS_END, 20);
+        assert_eq!(LiveryScheme::FreightWagon as u8, 13);
+        assert_eq!(LiveryScheme::Bus as u8, 14);
+        assert_eq!(LiveryScheme::End as u8, 23);
+        assert_eq!(LS_END, 23);
     }
 
     #[test]
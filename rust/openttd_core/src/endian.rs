@@ -72,6 +72,20 @@ impl<'a> BigEndianReader<'a> {
         Ok(i64::from_be_bytes(bytes))
     }
 
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], CoreError> {
+        if self.remaining() < len {
+            return Err(CoreError::UnexpectedEof);
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Get the unread part of the buffer
+    pub fn rest(&self) -> &'a [u8] {
+        &self.buf[self.pos.min(self.buf.len())..]
+    }
+
     pub fn read_exact<const N: usize>(&mut self) -> Result<[u8; N], CoreError> {
         if self.remaining() < N {
             return Err(CoreError::UnexpectedEof);
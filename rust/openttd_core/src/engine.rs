@@ -0,0 +1,1416 @@
+//! Engines: the vehicle models companies can buy
+//!
+//! An engine is introduced at a (randomised) date, offered to a single
+//! company as an exclusive preview and a year later made available to all
+//! companies. Its reliability rises, stays at its maximum and decays again
+//! until the model is retired. The base set data lives in engine_tables;
+//! NewGRFs add engines through the EngineOverrideManager.
+
+use crate::company::RailTypes;
+use crate::engine_tables::{
+    ORIG_AIRCRAFT_VEHICLE_INFO, ORIG_ENGINE_INFO, ORIG_RAIL_VEHICLE_INFO, ORIG_ROAD_VEHICLE_INFO,
+    ORIG_SHIP_VEHICLE_INFO,
+};
+use crate::random::{gb, Randomizer};
+use crate::types::dates::{
+    convert_date_to_ymd, convert_ymd_to_date, YearMonthDay, DAYS_IN_LEAP_YEAR, DAYS_IN_YEAR,
+};
+use crate::types::{
+    CalendarDate, CargoLabel, CargoType, CompanyMask, EngineID, LandscapeType, LandscapeTypes,
+    Owner,
+};
+use crate::vehicle::VehicleType;
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+/// Sound effect (matches C++ SoundID)
+pub type SoundID = u8;
+
+/// Road type (matches C++ RoadType)
+pub type RoadType = u8;
+pub const ROADTYPE_ROAD: RoadType = 0;
+
+/// Visual effect based on the engine class (matches C++ VE_DEFAULT)
+pub const VE_DEFAULT: u8 = 0xFF;
+
+/// Default ticks between two agings of cargo in a vehicle (matches C++ CARGO_AGING_TICKS)
+pub const CARGO_AGING_TICKS: u16 = 185;
+
+/// Aircraft subtype bits; no bits means a helicopter (matches C++ AircraftSubTypeBits)
+pub const AIR_HELI: u8 = 0;
+pub const AIR_CTOL: u8 = 1;
+pub const AIR_FAST: u8 = 2;
+
+/// GRF ID of the base set engines (matches C++ INVALID_GRFID)
+pub const INVALID_GRFID: u32 = 0xFFFF_FFFF;
+
+/// Maximum number of companies (matches C++ MAX_COMPANIES)
+const MAX_COMPANIES: u32 = 15;
+
+/// Company mask with all companies set
+pub const ALL_COMPANIES: CompanyMask = (1 << MAX_COMPANIES) - 1;
+
+/// Number of base set engines per vehicle type, indexed by VehicleType
+pub const ENGINE_COUNTS: [usize; 4] = [
+    ORIG_RAIL_VEHICLE_INFO.len(),
+    ORIG_ROAD_VEHICLE_INFO.len(),
+    ORIG_SHIP_VEHICLE_INFO.len(),
+    ORIG_AIRCRAFT_VEHICLE_INFO.len(),
+];
+
+/// Offset of the first engine of each vehicle type in ORIG_ENGINE_INFO
+pub const ENGINE_OFFSETS: [usize; 4] = [
+    0,
+    ENGINE_COUNTS[0],
+    ENGINE_COUNTS[0] + ENGINE_COUNTS[1],
+    ENGINE_COUNTS[0] + ENGINE_COUNTS[1] + ENGINE_COUNTS[2],
+];
+
+const _: () = assert!(ENGINE_OFFSETS[3] + ENGINE_COUNTS[3] == ORIG_ENGINE_INFO.len());
+
+/// Vehicle types that companies can buy
+const COMPANY_VEHICLE_TYPES: [VehicleType; 4] = [
+    VehicleType::Train,
+    VehicleType::Road,
+    VehicleType::Ship,
+    VehicleType::Aircraft,
+];
+
+/// Rail types of the base set (matches C++ RailType)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum RailType {
+    Rail = 0,
+    Electric = 1,
+    Monorail = 2,
+    Maglev = 3,
+}
+
+/// Kind of rail vehicle (matches C++ RailVehicleTypes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum RailVehicleType {
+    /// Standalone locomotive
+    Singlehead = 0,
+    /// Combination of two locomotives
+    Multihead = 1,
+    /// Wagon without power
+    Wagon = 2,
+}
+
+/// Class of a rail engine (matches C++ EngineClass)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum EngineClass {
+    Steam = 0,
+    Diesel = 1,
+    Electric = 2,
+    Monorail = 3,
+    Maglev = 4,
+}
+
+/// Base prices of buying and running vehicles (matches the vehicle entries of C++ Price)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum Price {
+    BuildVehicleTrain = 15,
+    BuildVehicleWagon = 16,
+    BuildVehicleAircraft = 17,
+    BuildVehicleRoad = 18,
+    BuildVehicleShip = 19,
+    RunningTrainSteam = 42,
+    RunningTrainDiesel = 43,
+    RunningTrainElectric = 44,
+    RunningAircraft = 45,
+    RunningRoadveh = 46,
+    RunningShip = 47,
+    Invalid = 0xFF,
+}
+
+/// Cargo that differs per climate (matches C++ MixedCargoType)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum MixedCargoType {
+    LivestockFruit = 0,
+    GrainWheatMaize = 1,
+    ValuablesGoldDiamonds = 2,
+}
+
+/// Default cargo of an engine before it is resolved to a cargo type
+/// (matches C++ std::variant<CargoLabel, MixedCargoType>)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DefaultCargo {
+    Label(CargoLabel),
+    Mixed(MixedCargoType),
+}
+
+bitflags! {
+    /// Miscellaneous engine flags (matches C++ EngineMiscFlags)
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct EngineMiscFlags: u8 {
+        /// Rail vehicle tilts in curves
+        const RAIL_TILTS = 1 << 0;
+        /// Road vehicle is a tram
+        const ROAD_IS_TRAM = 1 << 0;
+        const USES_2CC = 1 << 1;
+        /// Rail vehicle is a multiple unit (DMU/EMU)
+        const RAIL_IS_MU = 1 << 2;
+        const RAIL_FLIPS = 1 << 3;
+        const AUTO_REFIT = 1 << 4;
+        const NO_DEFAULT_CARGO_MULTIPLIER = 1 << 5;
+        const NO_BREAKDOWN_SMOKE = 1 << 6;
+        const SPRITE_STACK = 1 << 7;
+    }
+}
+
+bitflags! {
+    /// Extra engine flags for NewGRFs (matches C++ ExtraEngineFlags)
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct ExtraEngineFlags: u8 {
+        /// No news when the engine becomes available
+        const NO_NEWS = 1 << 0;
+        /// No exclusive preview
+        const NO_PREVIEW = 1 << 1;
+        /// Joins the exclusive preview of its variant parent
+        const JOIN_PREVIEW = 1 << 2;
+        /// Reliability follows the variant parent
+        const SYNC_RELIABILITY = 1 << 3;
+    }
+}
+
+bitflags! {
+    /// State of an engine's model life (matches C++ EngineFlags)
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct EngineFlags: u8 {
+        /// Available to everyone
+        const AVAILABLE = 1 << 0;
+        /// Being offered to, or used by, a single company
+        const EXCLUSIVE_PREVIEW = 1 << 1;
+    }
+}
+
+/// Information about a rail vehicle (matches C++ RailVehicleInfo)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RailVehicleInfo {
+    pub image_index: u8,
+    pub railveh_type: RailVehicleType,
+    /// Purchase cost factor; for multiheaded engines the sum of both heads
+    pub cost_factor: u8,
+    /// Rail types, mangled if electrified rail is disabled
+    pub railtypes: RailTypes,
+    /// Rail types regardless of electrified rail being enabled
+    pub intended_railtypes: RailTypes,
+    pub ai_passenger_only: u8,
+    /// Maximum speed (1 unit = 1/1.6 mph = 1 km-ish/h)
+    pub max_speed: u16,
+    /// Power in hp; for multiheaded engines the sum of both heads
+    pub power: u16,
+    /// Weight in tons of a single head
+    pub weight: u16,
+    /// Running cost; for multiheaded engines the sum of both heads
+    pub running_cost: u8,
+    pub running_cost_class: Price,
+    pub engclass: EngineClass,
+    /// Cargo capacity of a single head
+    pub capacity: u8,
+    pub pow_wag_power: u16,
+    pub pow_wag_weight: u8,
+    pub visual_effect: u8,
+    /// Length on the map is 8 - shorten_factor
+    pub shorten_factor: u8,
+    pub tractive_effort: u8,
+    pub air_drag: u8,
+    pub user_def_data: u8,
+    /// Modifier of the maximum speed in curves (8 fractional bits)
+    pub curve_speed_mod: i16,
+}
+
+impl Default for RailVehicleInfo {
+    fn default() -> Self {
+        Self {
+            image_index: 0,
+            railveh_type: RailVehicleType::Wagon,
+            cost_factor: 0,
+            railtypes: 1 << RailType::Rail as u8,
+            intended_railtypes: 1 << RailType::Rail as u8,
+            ai_passenger_only: 0,
+            max_speed: 0,
+            power: 0,
+            weight: 0,
+            running_cost: 0,
+            running_cost_class: Price::Invalid,
+            engclass: EngineClass::Steam,
+            capacity: 0,
+            pow_wag_power: 0,
+            pow_wag_weight: 0,
+            visual_effect: VE_DEFAULT,
+            shorten_factor: 0,
+            tractive_effort: 0,
+            air_drag: 0,
+            user_def_data: 0,
+            curve_speed_mod: 0,
+        }
+    }
+}
+
+/// Information about a ship (matches C++ ShipVehicleInfo)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShipVehicleInfo {
+    pub image_index: u8,
+    pub cost_factor: u8,
+    pub running_cost: u8,
+    /// Acceleration (1 unit = 1/3.2 mph per tick)
+    pub acceleration: u8,
+    /// Maximum speed (1 unit = 1/3.2 mph = 0.5 km-ish/h)
+    pub max_speed: u16,
+    pub capacity: u16,
+    pub sfx: SoundID,
+    /// Only used while setting up the refit masks
+    pub old_refittable: bool,
+    pub visual_effect: u8,
+    /// Fraction of the maximum speed taken off on ocean tiles
+    pub ocean_speed_frac: u8,
+    /// Fraction of the maximum speed taken off on canal and river tiles
+    pub canal_speed_frac: u8,
+}
+
+impl Default for ShipVehicleInfo {
+    fn default() -> Self {
+        Self {
+            image_index: 0,
+            cost_factor: 0,
+            running_cost: 0,
+            acceleration: 1,
+            max_speed: 0,
+            capacity: 0,
+            sfx: 0,
+            old_refittable: false,
+            visual_effect: VE_DEFAULT,
+            ocean_speed_frac: 0,
+            canal_speed_frac: 0,
+        }
+    }
+}
+
+impl ShipVehicleInfo {
+    /// Reduce a speed by the ocean or canal speed fraction
+    pub fn apply_water_class_speed_frac(&self, raw_speed: u32, is_ocean: bool) -> u32 {
+        let frac = if is_ocean {
+            self.ocean_speed_frac
+        } else {
+            self.canal_speed_frac
+        };
+        raw_speed * (256 - frac as u32) / 256
+    }
+}
+
+/// Information about an aircraft (matches C++ AircraftVehicleInfo)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AircraftVehicleInfo {
+    pub image_index: u8,
+    pub cost_factor: u8,
+    pub running_cost: u8,
+    /// AIR_CTOL and AIR_FAST bits
+    pub subtype: u8,
+    pub sfx: SoundID,
+    /// Maximum speed in km-ish/h
+    pub max_speed: u16,
+    pub acceleration: u8,
+    pub mail_capacity: u8,
+    pub passenger_capacity: u16,
+    /// Range in tiles, 0 for unlimited
+    pub max_range: u16,
+}
+
+impl AircraftVehicleInfo {
+    pub fn is_helicopter(&self) -> bool {
+        self.subtype & AIR_CTOL == 0
+    }
+}
+
+/// Information about a road vehicle (matches C++ RoadVehicleInfo)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoadVehicleInfo {
+    pub image_index: u8,
+    pub cost_factor: u8,
+    pub running_cost: u8,
+    pub running_cost_class: Price,
+    pub sfx: SoundID,
+    /// Maximum speed (1 unit = 1/3.2 mph = 0.5 km-ish/h)
+    pub max_speed: u16,
+    pub capacity: u8,
+    /// Weight in 1/4 t
+    pub weight: u8,
+    /// Power in 10 hp
+    pub power: u8,
+    pub tractive_effort: u8,
+    pub air_drag: u8,
+    pub visual_effect: u8,
+    pub shorten_factor: u8,
+    pub roadtype: RoadType,
+}
+
+impl Default for RoadVehicleInfo {
+    fn default() -> Self {
+        Self {
+            image_index: 0,
+            cost_factor: 0,
+            running_cost: 0,
+            running_cost_class: Price::Invalid,
+            sfx: 0,
+            max_speed: 0,
+            capacity: 0,
+            weight: 0,
+            power: 0,
+            tractive_effort: 0x4C,
+            air_drag: 0,
+            visual_effect: VE_DEFAULT,
+            shorten_factor: 0,
+            roadtype: ROADTYPE_ROAD,
+        }
+    }
+}
+
+/// Vehicle type specific information of an engine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VehicleInfo {
+    #[default]
+    None,
+    Rail(RailVehicleInfo),
+    Road(RoadVehicleInfo),
+    Ship(ShipVehicleInfo),
+    Aircraft(AircraftVehicleInfo),
+}
+
+impl VehicleInfo {
+    /// Default information for a vehicle type without base set data
+    fn new(type_: VehicleType) -> Self {
+        match type_ {
+            VehicleType::Train => VehicleInfo::Rail(RailVehicleInfo::default()),
+            VehicleType::Road => VehicleInfo::Road(RoadVehicleInfo::default()),
+            VehicleType::Ship => VehicleInfo::Ship(ShipVehicleInfo::default()),
+            VehicleType::Aircraft => VehicleInfo::Aircraft(AircraftVehicleInfo::default()),
+            _ => VehicleInfo::None,
+        }
+    }
+}
+
+/// Information about a vehicle model (matches C++ EngineInfo)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineInfo {
+    /// Introduction date without the random part
+    pub base_intro: CalendarDate,
+    /// Life length of a single vehicle in years
+    pub lifelength: u8,
+    /// Years the model is available without the random part; 0xFF means forever
+    pub base_life: u8,
+    pub decay_speed: u8,
+    pub load_amount: u8,
+    pub climates: LandscapeTypes,
+    /// Default cargo, resolved from cargo_label
+    pub cargo_type: CargoType,
+    pub cargo_label: DefaultCargo,
+    pub refit_mask: u64,
+    pub refit_cost: u8,
+    pub misc_flags: EngineMiscFlags,
+    /// Years before the end of the model life that the engine is retired
+    pub retire_early: i8,
+    pub extra_flags: ExtraEngineFlags,
+    /// Ticks between two agings of the cargo
+    pub cargo_age_period: u16,
+    /// Parent engine in the purchase list
+    pub variant_id: EngineID,
+}
+
+impl Default for EngineInfo {
+    fn default() -> Self {
+        Self {
+            base_intro: CalendarDate(0),
+            lifelength: 0,
+            base_life: 0,
+            decay_speed: 0,
+            load_amount: 0,
+            climates: LandscapeTypes::empty(),
+            cargo_type: CargoType::INVALID,
+            cargo_label: DefaultCargo::Label(CargoLabel::INVALID),
+            refit_mask: 0,
+            refit_cost: 0,
+            misc_flags: EngineMiscFlags::empty(),
+            retire_early: 0,
+            extra_flags: ExtraEngineFlags::empty(),
+            cargo_age_period: 0,
+            variant_id: EngineID::INVALID,
+        }
+    }
+}
+
+/// Game settings that affect engines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineSettings {
+    pub landscape: LandscapeType,
+    pub starting_year: i32,
+    pub generation_seed: u32,
+    /// Models are never retired and keep their maximum reliability
+    pub never_expire_vehicles: bool,
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        Self {
+            landscape: LandscapeType::Temperate,
+            starting_year: 1950,
+            generation_seed: 0,
+            never_expire_vehicles: false,
+        }
+    }
+}
+
+/// A vehicle model (matches C++ Engine)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Engine {
+    pub index: EngineID,
+    pub type_: VehicleType,
+    /// Companies that may build the engine
+    pub company_avail: CompanyMask,
+    /// Companies that hide the engine in the purchase list
+    pub company_hidden: CompanyMask,
+    /// Companies that have been offered a preview
+    pub preview_asked: CompanyMask,
+    /// Custom name, empty if none
+    pub name: String,
+    pub intro_date: CalendarDate,
+    /// Age of the model in months
+    pub age: i32,
+    pub reliability: u16,
+    /// Reliability decay per day between services
+    pub reliability_spd_dec: u16,
+    pub reliability_start: u16,
+    pub reliability_max: u16,
+    pub reliability_final: u16,
+    /// Months of increasing reliability, from reliability_start to reliability_max
+    pub duration_phase_1: u16,
+    /// Months at reliability_max
+    pub duration_phase_2: u16,
+    /// Months of decaying reliability, down to reliability_final
+    pub duration_phase_3: u16,
+    pub flags: EngineFlags,
+    /// Company being offered a preview
+    pub preview_company: Owner,
+    /// Days left for preview_company to accept the preview
+    pub preview_wait: u8,
+    /// Image index of the base set vehicle this engine replaces
+    pub original_image_index: u8,
+    pub info: EngineInfo,
+    pub vehicle_info: VehicleInfo,
+    pub list_position: u16,
+    /// GRF providing the engine, 0 for base set engines
+    pub grfid: u32,
+    /// ID of the engine within its GRF
+    pub local_id: u16,
+}
+
+impl Engine {
+    /// Create an engine with the base set data of `local_id`, if any;
+    /// an invalid type creates an empty engine for loading saved data
+    pub fn new(index: EngineID, type_: VehicleType, local_id: u16) -> Self {
+        let mut engine = Engine {
+            index,
+            type_,
+            company_avail: 0,
+            company_hidden: 0,
+            preview_asked: 0,
+            name: String::new(),
+            intro_date: CalendarDate(0),
+            age: 0,
+            reliability: 0,
+            reliability_spd_dec: 0,
+            reliability_start: 0,
+            reliability_max: 0,
+            reliability_final: 0,
+            duration_phase_1: 0,
+            duration_phase_2: 0,
+            duration_phase_3: 0,
+            flags: EngineFlags::empty(),
+            preview_company: Owner::Invalid,
+            preview_wait: 0,
+            original_image_index: 0,
+            info: EngineInfo::default(),
+            vehicle_info: VehicleInfo::None,
+            list_position: 0,
+            grfid: 0,
+            local_id: 0,
+        };
+        let Some(type_index) = COMPANY_VEHICLE_TYPES.iter().position(|&t| t == type_) else {
+            return engine;
+        };
+        engine.local_id = local_id;
+        engine.list_position = local_id;
+
+        let local = local_id as usize;
+        if local >= ENGINE_COUNTS[type_index] {
+            engine.vehicle_info = VehicleInfo::new(type_);
+            // Maximum model life to make wagons available
+            engine.info.base_life = 0xFF;
+            // Aircraft have no default cargo property
+            engine.info.cargo_label = if type_ == VehicleType::Aircraft {
+                DefaultCargo::Label(CargoLabel::INVALID)
+            } else {
+                DefaultCargo::Label(CargoLabel::from_bytes(b"PASS"))
+            };
+            engine.info.cargo_age_period = CARGO_AGING_TICKS;
+            return engine;
+        }
+
+        engine.info = ORIG_ENGINE_INFO[ENGINE_OFFSETS[type_index] + local];
+        match type_ {
+            VehicleType::Train => {
+                let rvi = ORIG_RAIL_VEHICLE_INFO[local];
+                engine.original_image_index = rvi.image_index;
+                // Original wagons are available forever
+                if rvi.railveh_type == RailVehicleType::Wagon {
+                    engine.info.base_life = 0xFF;
+                }
+                engine.vehicle_info = VehicleInfo::Rail(rvi);
+            }
+            VehicleType::Road => {
+                let rvi = ORIG_ROAD_VEHICLE_INFO[local];
+                engine.original_image_index = rvi.image_index;
+                engine.vehicle_info = VehicleInfo::Road(rvi);
+            }
+            VehicleType::Ship => {
+                let svi = ORIG_SHIP_VEHICLE_INFO[local];
+                engine.original_image_index = svi.image_index;
+                engine.vehicle_info = VehicleInfo::Ship(svi);
+            }
+            _ => {
+                let avi = ORIG_AIRCRAFT_VEHICLE_INFO[local];
+                engine.original_image_index = avi.image_index;
+                engine.vehicle_info = VehicleInfo::Aircraft(avi);
+            }
+        }
+        engine
+    }
+
+    pub fn rail(&self) -> Option<&RailVehicleInfo> {
+        match &self.vehicle_info {
+            VehicleInfo::Rail(rvi) => Some(rvi),
+            _ => None,
+        }
+    }
+
+    pub fn road(&self) -> Option<&RoadVehicleInfo> {
+        match &self.vehicle_info {
+            VehicleInfo::Road(rvi) => Some(rvi),
+            _ => None,
+        }
+    }
+
+    pub fn ship(&self) -> Option<&ShipVehicleInfo> {
+        match &self.vehicle_info {
+            VehicleInfo::Ship(svi) => Some(svi),
+            _ => None,
+        }
+    }
+
+    pub fn aircraft(&self) -> Option<&AircraftVehicleInfo> {
+        match &self.vehicle_info {
+            VehicleInfo::Aircraft(avi) => Some(avi),
+            _ => None,
+        }
+    }
+
+    /// Whether the engine can appear in the climate
+    pub fn is_enabled(&self, landscape: LandscapeType) -> bool {
+        self.info.climates.contains_landscape(landscape)
+    }
+
+    pub fn is_wagon(&self) -> bool {
+        self.rail()
+            .is_some_and(|rvi| rvi.railveh_type == RailVehicleType::Wagon)
+    }
+
+    pub fn default_cargo_type(&self) -> CargoType {
+        self.info.cargo_type
+    }
+
+    /// Base price and cost factor of buying the engine; the price is
+    /// scaled by factor / 256 (matches C++ GetCost without NewGRF properties)
+    pub fn cost_base(&self) -> Option<(Price, u8)> {
+        match &self.vehicle_info {
+            VehicleInfo::Rail(rvi) if rvi.railveh_type == RailVehicleType::Wagon => {
+                Some((Price::BuildVehicleWagon, rvi.cost_factor))
+            }
+            VehicleInfo::Rail(rvi) => Some((Price::BuildVehicleTrain, rvi.cost_factor)),
+            VehicleInfo::Road(rvi) => Some((Price::BuildVehicleRoad, rvi.cost_factor)),
+            VehicleInfo::Ship(svi) => Some((Price::BuildVehicleShip, svi.cost_factor)),
+            VehicleInfo::Aircraft(avi) => Some((Price::BuildVehicleAircraft, avi.cost_factor)),
+            VehicleInfo::None => None,
+        }
+    }
+
+    /// Base price and cost factor of the yearly running cost, None if the
+    /// engine has no running cost (matches C++ GetRunningCost without NewGRF properties)
+    pub fn running_cost_base(&self) -> Option<(Price, u8)> {
+        let (price, factor) = match &self.vehicle_info {
+            VehicleInfo::Rail(rvi) => (rvi.running_cost_class, rvi.running_cost),
+            VehicleInfo::Road(rvi) => (rvi.running_cost_class, rvi.running_cost),
+            VehicleInfo::Ship(svi) => (Price::RunningShip, svi.running_cost),
+            VehicleInfo::Aircraft(avi) => (Price::RunningAircraft, avi.running_cost),
+            VehicleInfo::None => return None,
+        };
+        (price != Price::Invalid).then_some((price, factor))
+    }
+
+    /// Life length of a vehicle of this model in days, assuming leap years
+    pub fn life_length_in_days(&self, extend_vehicle_life: u8) -> i32 {
+        (self.info.lifelength as i32 + extend_vehicle_life as i32) * DAYS_IN_LEAP_YEAR
+    }
+
+    /// Whether `company` may build the engine; Deity asks whether anyone may
+    pub fn is_available_to(&self, company: Owner) -> bool {
+        match company {
+            // Previews do not count for the deity
+            Owner::Deity => self.flags.contains(EngineFlags::AVAILABLE) && self.company_avail != 0,
+            _ => company
+                .company_id()
+                .is_some_and(|c| self.company_avail & (1 << c) != 0),
+        }
+    }
+}
+
+/// Mapping of an engine to the GRF it came from (matches C++ EngineIDMapping)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineIDMapping {
+    pub grfid: u32,
+    /// ID of the engine within its GRF
+    pub internal_id: u16,
+    pub type_: VehicleType,
+    /// Base set engine to use if the GRF is missing
+    pub substitute_id: u8,
+    pub engine: EngineID,
+}
+
+impl EngineIDMapping {
+    fn key_of(grfid: u32, internal_id: u16) -> u64 {
+        (grfid as u64) << 32 | internal_id as u64
+    }
+
+    pub fn key(&self) -> u64 {
+        Self::key_of(self.grfid, self.internal_id)
+    }
+}
+
+/// Engine IDs of the GRF engines, per vehicle type and sorted by GRF and
+/// internal ID (matches C++ EngineOverrideManager)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineOverrideManager {
+    pub mappings: [Vec<EngineIDMapping>; 4],
+}
+
+impl EngineOverrideManager {
+    fn mapping(&self, type_: VehicleType) -> &Vec<EngineIDMapping> {
+        &self.mappings[type_ as usize]
+    }
+
+    fn mapping_mut(&mut self, type_: VehicleType) -> &mut Vec<EngineIDMapping> {
+        &mut self.mappings[type_ as usize]
+    }
+
+    /// Map the base set engines to the first engine IDs
+    pub fn reset_to_default_mapping(&mut self) {
+        let mut id = 0;
+        for (type_index, &type_) in COMPANY_VEHICLE_TYPES.iter().enumerate() {
+            let map = self.mapping_mut(type_);
+            map.clear();
+            for internal_id in 0..ENGINE_COUNTS[type_index] {
+                map.push(EngineIDMapping {
+                    grfid: INVALID_GRFID,
+                    internal_id: internal_id as u16,
+                    type_,
+                    substitute_id: internal_id as u8,
+                    engine: EngineID(id),
+                });
+                id += 1;
+            }
+        }
+    }
+
+    /// Engine ID of a GRF's local engine, if mapped
+    pub fn get_id(&self, type_: VehicleType, grf_local_id: u16, grfid: u32) -> EngineID {
+        let map = self.mapping(type_);
+        let key = EngineIDMapping::key_of(grfid, grf_local_id);
+        let pos = map.partition_point(|eid| eid.key() < key);
+        match map.get(pos) {
+            Some(eid) if eid.key() == key => eid.engine,
+            _ => EngineID::INVALID,
+        }
+    }
+
+    /// Find the base set engine with the same local ID and, unless only
+    /// looking, reserve it for `grfid`
+    pub fn use_unreserved_id(
+        &mut self,
+        type_: VehicleType,
+        grf_local_id: u16,
+        grfid: u32,
+        static_access: bool,
+    ) -> EngineID {
+        let map = self.mapping_mut(type_);
+        let key = EngineIDMapping::key_of(INVALID_GRFID, grf_local_id);
+        let pos = map.partition_point(|eid| eid.key() < key);
+        if map.get(pos).is_none_or(|eid| eid.key() != key) {
+            return EngineID::INVALID;
+        }
+        let engine = map[pos].engine;
+
+        if !static_access && grfid != INVALID_GRFID {
+            // Move the entry to its new position to keep the list sorted
+            let mut eid = map.remove(pos);
+            eid.grfid = grfid;
+            let key = eid.key();
+            let new_pos = map.partition_point(|other| other.key() < key);
+            map.insert(new_pos, eid);
+        }
+        engine
+    }
+
+    pub fn set_id(
+        &mut self,
+        type_: VehicleType,
+        grf_local_id: u16,
+        grfid: u32,
+        substitute_id: u8,
+        engine: EngineID,
+    ) {
+        let map = self.mapping_mut(type_);
+        let key = EngineIDMapping::key_of(grfid, grf_local_id);
+        let pos = map.partition_point(|eid| eid.key() < key);
+        match map.get_mut(pos) {
+            Some(eid) if eid.key() == key => eid.engine = engine,
+            _ => map.insert(
+                pos,
+                EngineIDMapping {
+                    grfid,
+                    internal_id: grf_local_id,
+                    type_,
+                    substitute_id,
+                    engine,
+                },
+            ),
+        }
+    }
+
+    /// All mappings ordered by engine ID
+    pub fn sorted_by_engine(&self) -> Vec<EngineIDMapping> {
+        let mut all: Vec<EngineIDMapping> = self.mappings.iter().flatten().copied().collect();
+        all.sort_by_key(|eid| eid.engine);
+        all
+    }
+}
+
+/// All engines of a game, ordered by ID (matches C++ EnginePool)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnginePool {
+    engines: Vec<Engine>,
+    /// Year from which engines no longer age and no new engines appear
+    pub year_engine_aging_stops: i32,
+}
+
+impl EnginePool {
+    /// Create the engines of all mappings (matches C++ SetupEngines)
+    pub fn setup(manager: &EngineOverrideManager) -> Self {
+        let mut engines: Vec<Engine> = COMPANY_VEHICLE_TYPES
+            .iter()
+            .flat_map(|&type_| manager.mapping(type_))
+            .map(|eid| Engine::new(eid.engine, eid.type_, eid.internal_id))
+            .collect();
+        engines.sort_by_key(|e| e.index);
+        Self {
+            engines,
+            year_engine_aging_stops: 2050,
+        }
+    }
+
+    fn position(&self, id: EngineID) -> Option<usize> {
+        self.engines.binary_search_by_key(&id, |e| e.index).ok()
+    }
+
+    pub fn get(&self, id: EngineID) -> Option<&Engine> {
+        self.position(id).map(|pos| &self.engines[pos])
+    }
+
+    pub fn get_mut(&mut self, id: EngineID) -> Option<&mut Engine> {
+        self.position(id).map(|pos| &mut self.engines[pos])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Engine> {
+        self.engines.iter()
+    }
+
+    pub fn iter_type(&self, type_: VehicleType) -> impl Iterator<Item = &Engine> {
+        self.engines.iter().filter(move |e| e.type_ == type_)
+    }
+
+    pub fn len(&self) -> usize {
+        self.engines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.engines.is_empty()
+    }
+
+    /// Copy the saved state of the engines over the set up engines
+    /// (matches C++ CopyTempEngineData)
+    pub fn copy_saved_data(&mut self, saved: &[Engine]) {
+        for e in &mut self.engines {
+            let Some(se) = saved.get(e.index.0 as usize) else {
+                break;
+            };
+            e.intro_date = se.intro_date;
+            e.age = se.age;
+            e.reliability = se.reliability;
+            e.reliability_spd_dec = se.reliability_spd_dec;
+            e.reliability_start = se.reliability_start;
+            e.reliability_max = se.reliability_max;
+            e.reliability_final = se.reliability_final;
+            e.duration_phase_1 = se.duration_phase_1;
+            e.duration_phase_2 = se.duration_phase_2;
+            e.duration_phase_3 = se.duration_phase_3;
+            e.flags = se.flags;
+            e.preview_asked = se.preview_asked;
+            e.preview_company = se.preview_company;
+            e.preview_wait = se.preview_wait;
+            e.company_avail = se.company_avail;
+            e.company_hidden = se.company_hidden;
+            e.name = se.name.clone();
+        }
+    }
+
+    /// Engine whose age determines the reliability of the engine at `pos`
+    fn reliability_source(&self, pos: usize) -> usize {
+        let mut re = pos;
+        while self.engines[re].info.variant_id.is_valid()
+            && self.engines[re]
+                .info
+                .extra_flags
+                .contains(ExtraEngineFlags::SYNC_RELIABILITY)
+        {
+            match self.position(self.engines[re].info.variant_id) {
+                Some(parent) => re = parent,
+                None => break,
+            }
+        }
+        re
+    }
+
+    /// Update the reliability of an engine for its age; returns whether the
+    /// model was retired, so it has to leave the purchase lists
+    /// (matches C++ CalcEngineReliability)
+    pub fn calc_reliability(
+        &mut self,
+        id: EngineID,
+        new_month: bool,
+        never_expire_vehicles: bool,
+    ) -> bool {
+        let Some(pos) = self.position(id) else {
+            return false;
+        };
+        let re = self.reliability_source(pos);
+        let mut age = self.engines[re].age as u32;
+        // The parent variant's age has not been updated yet this month
+        if new_month && self.engines[re].index > id && age != i32::MAX as u32 {
+            age += 1;
+        }
+
+        let e = &mut self.engines[pos];
+        let mut retired = false;
+
+        // Early retirement
+        if e.company_avail != 0 && !never_expire_vehicles && e.info.base_life != 0xFF {
+            let retire_early = e.info.retire_early as i32;
+            let retire_early_max_age = 0
+                .max(e.duration_phase_1 as i32 + e.duration_phase_2 as i32 - retire_early * 12)
+                as u32;
+            if retire_early != 0 && age >= retire_early_max_age {
+                e.company_avail = 0;
+                retired = true;
+            }
+        }
+
+        let peak_start = e.duration_phase_1 as u32;
+        let decay_start = peak_start + e.duration_phase_2 as u32;
+        if age < peak_start {
+            let start = e.reliability_start as u32;
+            e.reliability = (age.wrapping_mul((e.reliability_max as u32).wrapping_sub(start))
+                / peak_start)
+                .wrapping_add(start) as u16;
+        } else if age < decay_start || never_expire_vehicles || e.info.base_life == 0xFF {
+            // Peak of the model life, also for models that never expire
+            e.reliability = e.reliability_max;
+        } else if age - decay_start < e.duration_phase_3 as u32 {
+            let max = e.reliability_max as i32;
+            e.reliability = ((age - decay_start) as i32 * (e.reliability_final as i32 - max)
+                / e.duration_phase_3 as i32
+                + max) as u16;
+        } else {
+            // The model is retired completely
+            e.company_avail = 0;
+            e.reliability = e.reliability_final;
+            retired = true;
+        }
+        retired
+    }
+
+    /// Compute the year from which engines stop aging: when half the life of
+    /// the last model of the climate has passed, but not before 2050
+    /// (matches C++ SetYearEngineAgingStops)
+    pub fn set_year_engine_aging_stops(&mut self, landscape: LandscapeType) {
+        self.year_engine_aging_stops = self
+            .engines
+            .iter()
+            .filter(|e| e.is_enabled(landscape) && !e.is_wagon())
+            .map(|e| {
+                let half_life = e.info.lifelength as i32 * DAYS_IN_LEAP_YEAR / 2;
+                convert_date_to_ymd(e.info.base_intro.0 + half_life).year
+            })
+            .fold(2050, i32::max);
+    }
+
+    /// Randomise the introduction date and reliability curve of an engine
+    /// (matches C++ StartupOneEngine)
+    fn startup_one(
+        &mut self,
+        pos: usize,
+        aging_ymd: YearMonthDay,
+        seed: u32,
+        settings: &EngineSettings,
+        date: CalendarDate,
+    ) {
+        let re = &self.engines[self.reliability_source(pos)];
+        let re_seed = (re.index.0 as u32) << 16
+            ^ (re.info.base_intro.0 as u32) << 12
+            ^ (re.info.decay_speed as u32) << 8
+            ^ (re.info.lifelength as u32) << 4
+            ^ re.info.retire_early as i32 as u32;
+
+        let e = &mut self.engines[pos];
+        let ei = e.info;
+        e.age = 0;
+        e.flags = EngineFlags::empty();
+        e.company_avail = 0;
+        e.company_hidden = 0;
+
+        // Engines with the same base introduction date appear at the same time
+        let mut random = Randomizer::new(
+            settings.generation_seed ^ seed ^ ei.base_intro.0 as u32 ^ e.type_ as u32 ^ e.grfid,
+        );
+        let r = random.next_u32();
+
+        // No random delay in the first two years, so early games have engines
+        e.intro_date = if ei.base_intro.0 <= convert_ymd_to_date(settings.starting_year + 2, 0, 1) {
+            ei.base_intro
+        } else {
+            CalendarDate(gb(r, 0, 9) as i32 + ei.base_intro.0)
+        };
+        if e.intro_date.0 <= date.0 {
+            let intro_ymd = convert_date_to_ymd(e.intro_date.0);
+            let aging_months = aging_ymd.year * 12 + aging_ymd.month as i32;
+            let mut intro_months = intro_ymd.year * 12 + intro_ymd.month as i32;
+            // Engines appear at the first month start at or after their introduction
+            if intro_ymd.day > 1 {
+                intro_months += 1;
+            }
+            e.age = aging_months - intro_months;
+            e.company_avail = ALL_COMPANIES;
+            e.flags.insert(EngineFlags::AVAILABLE);
+        }
+
+        random.set_seed(settings.generation_seed ^ seed ^ re_seed ^ e.type_ as u32 ^ e.grfid);
+
+        // Base reliabilities of 48%, 75% and 25%
+        const RELIABILITY_START: u32 = u16::MAX as u32 * 48 / 100;
+        const RELIABILITY_MAX: u32 = u16::MAX as u32 * 75 / 100;
+        const RELIABILITY_FINAL: u32 = u16::MAX as u32 * 25 / 100;
+
+        // 14 random bits add up to 25%p
+        let r = random.next_u32();
+        e.reliability_start = (gb(r, 16, 14) + RELIABILITY_START) as u16;
+        e.reliability_max = (gb(r, 0, 14) + RELIABILITY_MAX) as u16;
+
+        let r = random.next_u32();
+        e.reliability_final = (gb(r, 16, 14) + RELIABILITY_FINAL) as u16;
+
+        e.duration_phase_1 = (gb(r, 0, 5) + 7) as u16;
+        e.duration_phase_2 = 0.max(gb(r, 5, 4) as i32 + ei.base_life as i32 * 12 - 96) as u16;
+        e.duration_phase_3 = (gb(r, 9, 7) + 120) as u16;
+
+        e.reliability_spd_dec = (ei.decay_speed as u16) << 2;
+
+        // Engines of other climates never appear
+        if !ei.climates.contains_landscape(settings.landscape) {
+            e.flags.insert(EngineFlags::AVAILABLE);
+            e.company_avail = 0;
+        }
+    }
+
+    /// Start the model life of all engines at `date`, e.g. for a new game
+    /// or after the NewGRFs changed; year_engine_aging_stops must be set
+    /// (matches C++ StartupEngines)
+    pub fn startup(&mut self, settings: &EngineSettings, date: CalendarDate, seed: u32) {
+        // Aging stops, so account for that when starting late
+        let aging_date = date
+            .0
+            .min(convert_ymd_to_date(self.year_engine_aging_stops, 0, 1));
+        let aging_ymd = convert_date_to_ymd(aging_date);
+
+        for pos in 0..self.engines.len() {
+            self.startup_one(pos, aging_ymd, seed, settings, date);
+        }
+        for pos in 0..self.engines.len() {
+            let id = self.engines[pos].index;
+            self.calc_reliability(id, false, settings.never_expire_vehicles);
+        }
+    }
+
+    /// Allow `company` to build an engine (matches C++ EnableEngineForCompany)
+    pub fn enable_for_company(&mut self, id: EngineID, company: Owner) {
+        if let (Some(e), Some(c)) = (self.get_mut(id), company.company_id()) {
+            e.company_avail |= 1 << c;
+        }
+    }
+
+    /// Forbid `company` to build an engine (matches C++ DisableEngineForCompany)
+    pub fn disable_for_company(&mut self, id: EngineID, company: Owner) {
+        if let (Some(e), Some(c)) = (self.get_mut(id), company.company_id()) {
+            e.company_avail &= !(1 << c);
+        }
+    }
+
+    /// `company` accepts the preview of an engine, together with the variants
+    /// that join its preview (matches C++ AcceptEnginePreview)
+    pub fn accept_preview(&mut self, id: EngineID, company: Owner) {
+        self.accept_preview_recursive(id, company, 0);
+    }
+
+    fn accept_preview_recursive(&mut self, id: EngineID, company: Owner, depth: u32) {
+        let Some(e) = self.get_mut(id) else {
+            return;
+        };
+        e.preview_company = Owner::Invalid;
+        e.preview_asked = ALL_COMPANIES;
+        let type_ = e.type_;
+        self.enable_for_company(id, company);
+
+        // Do not follow variants more than 10 levels deep
+        if depth >= 10 {
+            return;
+        }
+        let variants: Vec<EngineID> = self
+            .iter_type(type_)
+            .filter(|ve| {
+                ve.index != id
+                    && ve.info.variant_id == id
+                    && ve.info.extra_flags.contains(ExtraEngineFlags::JOIN_PREVIEW)
+            })
+            .map(|ve| ve.index)
+            .collect();
+        for variant in variants {
+            self.accept_preview_recursive(variant, company, depth + 1);
+        }
+    }
+
+    /// Daily handling of exclusive previews: withdraw offers that timed out
+    /// and offer engines in preview to the next company, chosen by
+    /// `preview_company` (Owner::Invalid if none qualifies). Returns the new
+    /// offers.
+    pub fn daily_loop(
+        &mut self,
+        year: i32,
+        mut preview_company: impl FnMut(&Engine) -> Owner,
+    ) -> Vec<(EngineID, Owner)> {
+        let mut offers = Vec::new();
+        if year >= self.year_engine_aging_stops {
+            return offers;
+        }
+
+        for e in &mut self.engines {
+            if !e.flags.contains(EngineFlags::EXCLUSIVE_PREVIEW) {
+                continue;
+            }
+            if e.preview_company != Owner::Invalid {
+                e.preview_wait = e.preview_wait.wrapping_sub(1);
+                if e.preview_wait == 0 {
+                    e.preview_company = Owner::Invalid;
+                }
+            } else if e.preview_asked.count_ones() < MAX_COMPANIES {
+                let company = preview_company(e);
+                let Some(c) = company.company_id() else {
+                    e.preview_asked = ALL_COMPANIES;
+                    continue;
+                };
+                e.preview_company = company;
+                e.preview_asked |= 1 << c;
+                e.preview_wait = 20;
+                offers.push((e.index, company));
+            }
+        }
+        offers
+    }
+
+    /// Monthly aging of the engines and start of previews and availability
+    /// (matches C++ CalendarEnginesMonthlyLoop). `is_type_disabled` tells
+    /// whether no company may build a vehicle type. Returns the engines,
+    /// except wagons, that became available to everyone, each with the
+    /// companies that had them in preview; those that did not build one
+    /// should be blocked from previews for a while.
+    pub fn monthly_loop(
+        &mut self,
+        date: CalendarDate,
+        settings: &EngineSettings,
+        is_type_disabled: impl Fn(VehicleType) -> bool,
+    ) -> Vec<(EngineID, CompanyMask)> {
+        let mut introduced = Vec::new();
+        if convert_date_to_ymd(date.0).year >= self.year_engine_aging_stops {
+            return introduced;
+        }
+
+        for pos in 0..self.engines.len() {
+            let e = &mut self.engines[pos];
+            if e.flags.contains(EngineFlags::AVAILABLE) && e.age != i32::MAX {
+                e.age += 1;
+                let id = e.index;
+                self.calc_reliability(id, true, settings.never_expire_vehicles);
+            }
+
+            let e = &mut self.engines[pos];
+            // Do not introduce engines of other climates
+            if !e.is_enabled(settings.landscape) {
+                continue;
+            }
+
+            if !e.flags.contains(EngineFlags::AVAILABLE) && date.0 >= e.intro_date.0 + DAYS_IN_YEAR
+            {
+                // Introduce it to all companies
+                let previewed = if e.flags.contains(EngineFlags::EXCLUSIVE_PREVIEW) {
+                    e.company_avail
+                } else {
+                    0
+                };
+                e.flags.remove(EngineFlags::EXCLUSIVE_PREVIEW);
+                e.flags.insert(EngineFlags::AVAILABLE);
+                e.company_avail = ALL_COMPANIES;
+                if !e.is_wagon() {
+                    introduced.push((e.index, previewed));
+                }
+            } else if !e
+                .flags
+                .intersects(EngineFlags::AVAILABLE | EngineFlags::EXCLUSIVE_PREVIEW)
+                && date.0 >= e.intro_date.0
+            {
+                // Offer a preview, unless nobody may build the engine anyway
+                if is_type_disabled(e.type_)
+                    || e.is_wagon()
+                    || e.info.extra_flags.contains(ExtraEngineFlags::NO_PREVIEW)
+                {
+                    continue;
+                }
+                e.flags.insert(EngineFlags::EXCLUSIVE_PREVIEW);
+                e.preview_company = Owner::Invalid;
+                e.preview_asked = 0;
+            }
+        }
+        introduced
+    }
+
+    /// Whether `company` may build an engine of `type_`; rail and road type
+    /// availability is left to the company (matches C++ IsEngineBuildable)
+    pub fn is_engine_buildable(
+        &self,
+        id: EngineID,
+        type_: VehicleType,
+        company: Owner,
+        landscape: LandscapeType,
+    ) -> bool {
+        self.get(id).is_some_and(|e| {
+            e.type_ == type_ && e.is_available_to(company) && e.is_enabled(landscape)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> EnginePool {
+        let mut manager = EngineOverrideManager::default();
+        manager.reset_to_default_mapping();
+        EnginePool::setup(&manager)
+    }
+
+    #[test]
+    fn test_original_engines() {
+        let pool = pool();
+        assert_eq!(pool.len(), 256);
+
+        let kirby = pool.get(EngineID(0)).unwrap();
+        assert_eq!(kirby.type_, VehicleType::Train);
+        assert_eq!(kirby.info.base_intro, CalendarDate(701265 + 1827));
+        assert_eq!(kirby.rail().unwrap().power, 300);
+        assert_eq!(
+            kirby.running_cost_base(),
+            Some((Price::RunningTrainSteam, 50))
+        );
+
+        // Wagons are available forever and have no running cost
+        let carriage = pool.get(EngineID(27)).unwrap();
+        assert!(carriage.is_wagon());
+        assert_eq!(carriage.info.base_life, 0xFF);
+        assert_eq!(carriage.running_cost_base(), None);
+        assert_eq!(carriage.cost_base(), Some((Price::BuildVehicleWagon, 247)));
+
+        let bus = pool.get(EngineID(116)).unwrap();
+        assert_eq!((bus.type_, bus.local_id), (VehicleType::Road, 0));
+        assert_eq!(bus.road().unwrap().capacity, 31);
+
+        let ship = pool.get(EngineID(204)).unwrap();
+        assert_eq!(ship.ship().unwrap().capacity, 220);
+        assert_eq!(ship.info.load_amount, 10);
+
+        let heli = pool.get(EngineID(253)).unwrap();
+        assert!(heli.aircraft().unwrap().is_helicopter());
+        assert_eq!(heli.aircraft().unwrap().max_speed, 25 * 128 / 10);
+        assert_eq!(
+            heli.info.cargo_label,
+            DefaultCargo::Label(CargoLabel::INVALID)
+        );
+
+        // Beyond the base set engines get default information
+        let extra = Engine::new(EngineID(300), VehicleType::Road, 100);
+        assert_eq!(extra.road().unwrap().tractive_effort, 0x4C);
+        assert_eq!(extra.info.base_life, 0xFF);
+    }
+
+    #[test]
+    fn test_override_manager() {
+        let mut manager = EngineOverrideManager::default();
+        manager.reset_to_default_mapping();
+        assert_eq!(
+            manager.get_id(VehicleType::Ship, 3, INVALID_GRFID),
+            EngineID(207)
+        );
+
+        // A GRF takes over a base set engine
+        assert_eq!(
+            manager.use_unreserved_id(VehicleType::Ship, 3, 0x1234, false),
+            EngineID(207)
+        );
+        assert_eq!(
+            manager.get_id(VehicleType::Ship, 3, INVALID_GRFID),
+            EngineID::INVALID
+        );
+        assert_eq!(manager.get_id(VehicleType::Ship, 3, 0x1234), EngineID(207));
+
+        manager.set_id(VehicleType::Ship, 50, 0x1234, 0, EngineID(256));
+        let ships = &manager.mappings[VehicleType::Ship as usize];
+        assert!(ships.windows(2).all(|pair| pair[0].key() < pair[1].key()));
+        assert_eq!(
+            manager.sorted_by_engine().last().unwrap().engine,
+            EngineID(256)
+        );
+    }
+
+    #[test]
+    fn test_reliability_curve() {
+        let mut pool = pool();
+        let e = pool.get_mut(EngineID(0)).unwrap();
+        e.reliability_start = 1000;
+        e.reliability_max = 2000;
+        e.reliability_final = 500;
+        e.duration_phase_1 = 10;
+        e.duration_phase_2 = 5;
+        e.duration_phase_3 = 10;
+        e.company_avail = ALL_COMPANIES;
+        e.age = 5;
+        assert!(!pool.calc_reliability(EngineID(0), false, false));
+        assert_eq!(pool.get(EngineID(0)).unwrap().reliability, 1500);
+
+        pool.get_mut(EngineID(0)).unwrap().age = 20;
+        pool.calc_reliability(EngineID(0), false, false);
+        assert_eq!(pool.get(EngineID(0)).unwrap().reliability, 1250);
+
+        // Never expiring models stay at their peak
+        pool.get_mut(EngineID(0)).unwrap().age = 40;
+        pool.calc_reliability(EngineID(0), false, true);
+        assert_eq!(pool.get(EngineID(0)).unwrap().reliability, 2000);
+
+        assert!(pool.calc_reliability(EngineID(0), false, false));
+        let e = pool.get(EngineID(0)).unwrap();
+        assert_eq!((e.reliability, e.company_avail), (500, 0));
+    }
+
+    #[test]
+    fn test_startup_and_model_life() {
+        let mut pool = pool();
+        let settings = EngineSettings::default();
+        pool.set_year_engine_aging_stops(settings.landscape);
+        assert!(pool.year_engine_aging_stops >= 2050);
+
+        let date = CalendarDate(convert_ymd_to_date(1950, 0, 1));
+        pool.startup(&settings, date, 42);
+        let kirby = pool.get(EngineID(0)).unwrap().clone();
+        assert!(kirby.flags.contains(EngineFlags::AVAILABLE));
+        assert_eq!(kirby.company_avail, ALL_COMPANIES);
+        assert!(kirby.reliability_max >= 0xBFFF);
+        assert_eq!(kirby.reliability_spd_dec, 20 << 2);
+
+        // The same seed gives the same engines
+        let mut again = self::pool();
+        again.set_year_engine_aging_stops(settings.landscape);
+        again.startup(&settings, date, 42);
+        assert_eq!(again, pool);
+
+        // Toyland engines are never available in temperate
+        let toyland = pool.get(EngineID(2)).unwrap();
+        assert_eq!(toyland.company_avail, 0);
+        assert!(!pool.is_engine_buildable(
+            EngineID(2),
+            VehicleType::Train,
+            Owner::Company0,
+            settings.landscape
+        ));
+        assert!(pool.is_engine_buildable(
+            EngineID(0),
+            VehicleType::Train,
+            Owner::Deity,
+            settings.landscape
+        ));
+
+        // An engine in the future goes through preview before becoming available
+        let id = pool
+            .iter()
+            .find(|e| e.is_enabled(settings.landscape) && !e.is_wagon() && e.intro_date.0 > date.0)
+            .unwrap()
+            .index;
+        let intro = pool.get(id).unwrap().intro_date;
+        assert!(pool.monthly_loop(intro, &settings, |_| false).is_empty());
+        assert!(pool
+            .get(id)
+            .unwrap()
+            .flags
+            .contains(EngineFlags::EXCLUSIVE_PREVIEW));
+
+        let year = convert_date_to_ymd(intro.0).year;
+        let offers = pool.daily_loop(year, |_| Owner::Company1);
+        assert!(offers.contains(&(id, Owner::Company1)));
+        pool.accept_preview(id, Owner::Company1);
+        let e = pool.get(id).unwrap();
+        assert_eq!(e.company_avail, 1 << 1);
+        assert_eq!(e.preview_company, Owner::Invalid);
+
+        let later = CalendarDate(intro.0 + DAYS_IN_YEAR);
+        let introduced = pool.monthly_loop(later, &settings, |_| false);
+        assert!(introduced.contains(&(id, 1 << 1)));
+        assert!(pool.get(id).unwrap().is_available_to(Owner::Company5));
+    }
+}
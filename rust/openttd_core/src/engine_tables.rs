@@ -0,0 +1,826 @@
+//! Original vehicle data of the base set
+//!
+//! Engine information and the rail, ship, aircraft and road vehicle tables
+//! for all climates, in the order of the C++ table/engines.h. The engine
+//! information table holds the rail vehicles first, followed by road
+//! vehicles, ships and aircraft.
+
+use crate::company::RailTypes;
+use crate::engine::{
+    AircraftVehicleInfo, EngineClass, EngineInfo, EngineMiscFlags, MixedCargoType, Price, RailType,
+    RailVehicleInfo, RailVehicleType, RoadVehicleInfo, ShipVehicleInfo, SoundID, CARGO_AGING_TICKS,
+    ROADTYPE_ROAD, VE_DEFAULT,
+};
+use crate::engine::{DefaultCargo, AIR_CTOL, AIR_FAST, AIR_HELI};
+use crate::types::dates::DAYS_TILL_ORIGINAL_BASE_YEAR;
+use crate::types::{CalendarDate, CargoLabel, CargoType, EngineID, LandscapeTypes};
+
+// Climates
+const T: u8 = LandscapeTypes::TEMPERATE.bits();
+const A: u8 = LandscapeTypes::ARCTIC.bits();
+const S: u8 = LandscapeTypes::TROPIC.bits();
+const Y: u8 = LandscapeTypes::TOYLAND.bits();
+
+// Default cargoes
+const CT_BATTERIES: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"BATT"));
+const CT_BUBBLES: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"BUBL"));
+const CT_CANDY: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"SWET"));
+const CT_COAL: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"COAL"));
+const CT_COLA: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"COLA"));
+const CT_COPPER_ORE: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"CORE"));
+const CT_COTTON_CANDY: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"CTCD"));
+const CT_FIZZY_DRINKS: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"FZDR"));
+const CT_FOOD: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"FOOD"));
+const CT_FRUIT: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"FRUT"));
+const CT_GOODS: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"GOOD"));
+const CT_IRON_ORE: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"IORE"));
+const CT_LIVESTOCK: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"LVST"));
+const CT_MAIL: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"MAIL"));
+const CT_NONE: DefaultCargo = CT_PASSENGERS;
+const CT_OIL: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"OIL_"));
+const CT_PAPER: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"PAPR"));
+const CT_PASSENGERS: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"PASS"));
+const CT_PLASTIC: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"PLST"));
+const CT_RUBBER: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"RUBR"));
+const CT_STEEL: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"STEL"));
+const CT_SUGAR: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"SUGR"));
+const CT_TOFFEE: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"TOFF"));
+const CT_TOYS: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"TOYS"));
+const CT_WATER: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"WATR"));
+const CT_WOOD: DefaultCargo = DefaultCargo::Label(CargoLabel::from_bytes(b"WOOD"));
+const MCT_GRAIN_WHEAT_MAIZE: DefaultCargo = DefaultCargo::Mixed(MixedCargoType::GrainWheatMaize);
+const MCT_VALUABLES_GOLD_DIAMONDS: DefaultCargo =
+    DefaultCargo::Mixed(MixedCargoType::ValuablesGoldDiamonds);
+
+/// Engine information of a base set vehicle
+#[allow(clippy::too_many_arguments)]
+const fn engine_info(
+    base_intro: i32,
+    decay_speed: u8,
+    lifelength: u8,
+    base_life: u8,
+    load_amount: u8,
+    cargo: DefaultCargo,
+    climates: u8,
+    misc_flags: EngineMiscFlags,
+) -> EngineInfo {
+    EngineInfo {
+        base_intro: CalendarDate(DAYS_TILL_ORIGINAL_BASE_YEAR + base_intro),
+        lifelength,
+        base_life,
+        decay_speed,
+        load_amount,
+        climates: LandscapeTypes::from_bits_retain(climates),
+        cargo_type: CargoType::INVALID,
+        cargo_label: cargo,
+        refit_mask: 0,
+        refit_cost: 8,
+        misc_flags,
+        retire_early: 0,
+        extra_flags: crate::engine::ExtraEngineFlags::empty(),
+        cargo_age_period: CARGO_AGING_TICKS,
+        variant_id: EngineID::INVALID,
+    }
+}
+
+/// Train engine
+const fn mt(a: i32, b: u8, c: u8, d: u8, e: DefaultCargo, f: u8) -> EngineInfo {
+    engine_info(a, b, c, d, 5, e, f, EngineMiscFlags::empty())
+}
+
+/// Multiple unit train engine
+const fn mm(a: i32, b: u8, c: u8, d: u8, e: DefaultCargo, f: u8) -> EngineInfo {
+    engine_info(a, b, c, d, 5, e, f, EngineMiscFlags::RAIL_IS_MU)
+}
+
+/// Wagon
+const fn mw(a: i32, b: u8, c: u8, d: u8, e: DefaultCargo, f: u8) -> EngineInfo {
+    engine_info(a, b, c, d, 5, e, f, EngineMiscFlags::empty())
+}
+
+/// Road vehicle
+const fn mr(a: i32, b: u8, c: u8, d: u8, e: DefaultCargo, f: u8) -> EngineInfo {
+    engine_info(a, b, c, d, 5, e, f, EngineMiscFlags::empty())
+}
+
+/// Ship
+const fn ms(a: i32, b: u8, c: u8, d: u8, e: DefaultCargo, f: u8) -> EngineInfo {
+    engine_info(a, b, c, d, 10, e, f, EngineMiscFlags::empty())
+}
+
+/// Aircraft; they have no default cargo of their own
+const fn ma(a: i32, b: u8, c: u8, d: u8, e: u8) -> EngineInfo {
+    engine_info(
+        a,
+        b,
+        c,
+        d,
+        20,
+        DefaultCargo::Label(CargoLabel::INVALID),
+        e,
+        EngineMiscFlags::empty(),
+    )
+}
+
+/// Engine information of all base set vehicles (matches C++ _orig_engine_info)
+///
+/// Arguments: days after 1920 of the introduction, reliability decay speed,
+/// vehicle life length in years, model life in years, cargo and climates.
+#[rustfmt::skip]
+pub const ORIG_ENGINE_INFO: [EngineInfo; 256] = [
+    mt(  1827,  20,  15,  30, CT_NONE,                      T), //   0 Kirby Paul Tank (Steam)
+    mt( 12784,  20,  22,  30, CT_NONE,                      A | S), //   1 MJS 250 (Diesel)
+    mt(  9497,  20,  20,  50, CT_NONE,                      Y), //   2 Ploddyphut Choo-Choo
+    mt( 11688,  20,  20,  30, CT_NONE,                      Y), //   3 Powernaut Choo-Choo
+    mt( 16802,  20,  20,  30, CT_NONE,                      Y), //   4 Mightymover Choo-Choo
+    mt( 18993,  20,  20,  30, CT_NONE,                      Y), //   5 Ploddyphut Diesel
+    mt( 20820,  20,  20,  30, CT_NONE,                      Y), //   6 Powernaut Diesel
+    mt(  8766,  20,  20,  30, CT_NONE,                      A | S), //   7 Wills 2-8-0 (Steam)
+    mt(  5114,  20,  21,  30, CT_NONE,                      T), //   8 Chaney 'Jubilee' (Steam)
+    mt(  5479,  20,  20,  30, CT_NONE,                      T), //   9 Ginzu 'A4' (Steam)
+    mt( 12419,  20,  23,  25, CT_NONE,                      T), //  10 SH '8P' (Steam)
+    mm( 13149,  20,  12,  30, CT_PASSENGERS,                T), //  11 Manley-Morel DMU (Diesel)
+    mm( 23376,  20,  15,  35, CT_PASSENGERS,                T), //  12 'Dash' (Diesel)
+    mt( 14976,  20,  18,  28, CT_NONE,                      T), //  13 SH/Hendry '25' (Diesel)
+    mt( 14245,  20,  20,  30, CT_NONE,                      T), //  14 UU '37' (Diesel)
+    mt( 15341,  20,  22,  33, CT_NONE,                      T), //  15 Floss '47' (Diesel)
+    mt( 14976,  20,  20,  25, CT_NONE,                      A | S), //  16 CS 4000 (Diesel)
+    mt( 16437,  20,  20,  30, CT_NONE,                      A | S), //  17 CS 2400 (Diesel)
+    mt( 18993,  20,  22,  30, CT_NONE,                      A | S), //  18 Centennial (Diesel)
+    mt( 13880,  20,  22,  30, CT_NONE,                      A | S), //  19 Kelling 3100 (Diesel)
+    mm( 20454,  20,  22,  30, CT_NONE,                      A | S), //  20 Turner Turbo (Diesel)
+    mt( 16071,  20,  22,  30, CT_NONE,                      A | S), //  21 MJS 1000 (Diesel)
+    mt( 20820,  20,  20,  25, CT_MAIL,                      T), //  22 SH '125' (Diesel)
+    mt( 16437,  20,  23,  30, CT_NONE,                      T), //  23 SH '30' (Electric)
+    mt( 19359,  20,  23,  80, CT_NONE,                      T), //  24 SH '40' (Electric)
+    mm( 23376,  20,  25,  30, CT_NONE,                      T), //  25 'T.I.M.' (Electric)
+    mm( 26298,  20,  25,  50, CT_NONE,                      T), //  26 'AsiaStar' (Electric)
+    mw(  1827,  20,  20,  50, CT_PASSENGERS,                T | A | S | Y), //  27 Passenger Carriage
+    mw(  1827,  20,  20,  50, CT_MAIL,                      T | A | S | Y), //  28 Mail Van
+    mw(  1827,  20,  20,  50, CT_COAL,                      T | A), //  29 Coal Truck
+    mw(  1827,  20,  20,  50, CT_OIL,                       T | A | S), //  30 Oil Tanker
+    mw(  1827,  20,  20,  50, CT_LIVESTOCK,                 T | A), //  31 Livestock Van
+    mw(  1827,  20,  20,  50, CT_GOODS,                     T | A | S), //  32 Goods Van
+    mw(  1827,  20,  20,  50, MCT_GRAIN_WHEAT_MAIZE,        T | A | S), //  33 Grain Hopper
+    mw(  1827,  20,  20,  50, CT_WOOD,                      T | A | S), //  34 Wood Truck
+    mw(  1827,  20,  20,  50, CT_IRON_ORE,                  T), //  35 Iron Ore Hopper
+    mw(  1827,  20,  20,  50, CT_STEEL,                     T), //  36 Steel Truck
+    mw(  1827,  20,  20,  50, MCT_VALUABLES_GOLD_DIAMONDS,  T | A | S), //  37 Armoured Van
+    mw(  1827,  20,  20,  50, CT_FOOD,                      A | S), //  38 Food Van
+    mw(  1827,  20,  20,  50, CT_PAPER,                     A), //  39 Paper Truck
+    mw(  1827,  20,  20,  50, CT_COPPER_ORE,                S), //  40 Copper Ore Hopper
+    mw(  1827,  20,  20,  50, CT_WATER,                     S), //  41 Water Tanker
+    mw(  1827,  20,  20,  50, CT_FRUIT,                     S), //  42 Fruit Truck
+    mw(  1827,  20,  20,  50, CT_RUBBER,                    S), //  43 Rubber Truck
+    mw(  1827,  20,  20,  50, CT_SUGAR,                     Y), //  44 Sugar Truck
+    mw(  1827,  20,  20,  50, CT_COTTON_CANDY,              Y), //  45 Candyfloss Hopper
+    mw(  1827,  20,  20,  50, CT_TOFFEE,                    Y), //  46 Toffee Hopper
+    mw(  1827,  20,  20,  50, CT_BUBBLES,                   Y), //  47 Bubble Van
+    mw(  1827,  20,  20,  50, CT_COLA,                      Y), //  48 Cola Tanker
+    mw(  1827,  20,  20,  50, CT_CANDY,                     Y), //  49 Sweet Van
+    mw(  1827,  20,  20,  50, CT_TOYS,                      Y), //  50 Toy Van
+    mw(  1827,  20,  20,  50, CT_BATTERIES,                 Y), //  51 Battery Truck
+    mw(  1827,  20,  20,  50, CT_FIZZY_DRINKS,              Y), //  52 Fizzy Drink Truck
+    mw(  1827,  20,  20,  50, CT_PLASTIC,                   Y), //  53 Plastic Truck
+    mt( 28490,  20,  20,  50, CT_NONE,                      T | A | S), //  54 'X2001' (Electric)
+    mt( 31047,  20,  20,  50, CT_PASSENGERS,                T | A | S), //  55 'Millennium Z1' (Electric)
+    mt( 28855,  20,  20,  50, CT_NONE,                      Y), //  56 Wizzowow Z99
+    mw(  1827,  20,  20,  50, CT_PASSENGERS,                T | A | S | Y), //  57 Passenger Carriage
+    mw(  1827,  20,  20,  50, CT_MAIL,                      T | A | S | Y), //  58 Mail Van
+    mw(  1827,  20,  20,  50, CT_COAL,                      T | A), //  59 Coal Truck
+    mw(  1827,  20,  20,  50, CT_OIL,                       T | A | S), //  60 Oil Tanker
+    mw(  1827,  20,  20,  50, CT_LIVESTOCK,                 T | A), //  61 Livestock Van
+    mw(  1827,  20,  20,  50, CT_GOODS,                     T | A | S), //  62 Goods Van
+    mw(  1827,  20,  20,  50, MCT_GRAIN_WHEAT_MAIZE,        T | A | S), //  63 Grain Hopper
+    mw(  1827,  20,  20,  50, CT_WOOD,                      T | A | S), //  64 Wood Truck
+    mw(  1827,  20,  20,  50, CT_IRON_ORE,                  T), //  65 Iron Ore Hopper
+    mw(  1827,  20,  20,  50, CT_STEEL,                     T), //  66 Steel Truck
+    mw(  1827,  20,  20,  50, MCT_VALUABLES_GOLD_DIAMONDS,  T | A | S), //  67 Armoured Van
+    mw(  1827,  20,  20,  50, CT_FOOD,                      A | S), //  68 Food Van
+    mw(  1827,  20,  20,  50, CT_PAPER,                     A), //  69 Paper Truck
+    mw(  1827,  20,  20,  50, CT_COPPER_ORE,                S), //  70 Copper Ore Hopper
+    mw(  1827,  20,  20,  50, CT_WATER,                     S), //  71 Water Tanker
+    mw(  1827,  20,  20,  50, CT_FRUIT,                     S), //  72 Fruit Truck
+    mw(  1827,  20,  20,  50, CT_RUBBER,                    S), //  73 Rubber Truck
+    mw(  1827,  20,  20,  50, CT_SUGAR,                     Y), //  74 Sugar Truck
+    mw(  1827,  20,  20,  50, CT_COTTON_CANDY,              Y), //  75 Candyfloss Hopper
+    mw(  1827,  20,  20,  50, CT_TOFFEE,                    Y), //  76 Toffee Hopper
+    mw(  1827,  20,  20,  50, CT_BUBBLES,                   Y), //  77 Bubble Van
+    mw(  1827,  20,  20,  50, CT_COLA,                      Y), //  78 Cola Tanker
+    mw(  1827,  20,  20,  50, CT_CANDY,                     Y), //  79 Sweet Van
+    mw(  1827,  20,  20,  50, CT_TOYS,                      Y), //  80 Toy Van
+    mw(  1827,  20,  20,  50, CT_BATTERIES,                 Y), //  81 Battery Truck
+    mw(  1827,  20,  20,  50, CT_FIZZY_DRINKS,              Y), //  82 Fizzy Drink Truck
+    mw(  1827,  20,  20,  50, CT_PLASTIC,                   Y), //  83 Plastic Truck
+    mt( 36525,  20,  20,  50, CT_NONE,                      T | A | S), //  84 Lev1 'Leviathan' (Electric)
+    mt( 39447,  20,  20,  50, CT_NONE,                      T | A | S), //  85 Lev2 'Cyclops' (Electric)
+    mt( 42004,  20,  20,  50, CT_NONE,                      T | A | S), //  86 Lev3 'Pegasus' (Electric)
+    mt( 42735,  20,  20,  50, CT_NONE,                      T | A | S), //  87 Lev4 'Chimaera' (Electric)
+    mt( 36891,  20,  20,  60, CT_NONE,                      Y), //  88 Wizzowow Rocketeer
+    mw(  1827,  20,  20,  50, CT_PASSENGERS,                T | A | S | Y), //  89 Passenger Carriage
+    mw(  1827,  20,  20,  50, CT_MAIL,                      T | A | S | Y), //  90 Mail Van
+    mw(  1827,  20,  20,  50, CT_COAL,                      T | A), //  91 Coal Truck
+    mw(  1827,  20,  20,  50, CT_OIL,                       T | A | S), //  92 Oil Tanker
+    mw(  1827,  20,  20,  50, CT_LIVESTOCK,                 T | A), //  93 Livestock Van
+    mw(  1827,  20,  20,  50, CT_GOODS,                     T | A | S), //  94 Goods Van
+    mw(  1827,  20,  20,  50, MCT_GRAIN_WHEAT_MAIZE,        T | A | S), //  95 Grain Hopper
+    mw(  1827,  20,  20,  50, CT_WOOD,                      T | A | S), //  96 Wood Truck
+    mw(  1827,  20,  20,  50, CT_IRON_ORE,                  T), //  97 Iron Ore Hopper
+    mw(  1827,  20,  20,  50, CT_STEEL,                     T), //  98 Steel Truck
+    mw(  1827,  20,  20,  50, MCT_VALUABLES_GOLD_DIAMONDS,  T | A | S), //  99 Armoured Van
+    mw(  1827,  20,  20,  50, CT_FOOD,                      A | S), // 100 Food Van
+    mw(  1827,  20,  20,  50, CT_PAPER,                     A), // 101 Paper Truck
+    mw(  1827,  20,  20,  50, CT_COPPER_ORE,                S), // 102 Copper Ore Hopper
+    mw(  1827,  20,  20,  50, CT_WATER,                     S), // 103 Water Tanker
+    mw(  1827,  20,  20,  50, CT_FRUIT,                     S), // 104 Fruit Truck
+    mw(  1827,  20,  20,  50, CT_RUBBER,                    S), // 105 Rubber Truck
+    mw(  1827,  20,  20,  50, CT_SUGAR,                     Y), // 106 Sugar Truck
+    mw(  1827,  20,  20,  50, CT_COTTON_CANDY,              Y), // 107 Candyfloss Hopper
+    mw(  1827,  20,  20,  50, CT_TOFFEE,                    Y), // 108 Toffee Hopper
+    mw(  1827,  20,  20,  50, CT_BUBBLES,                   Y), // 109 Bubble Van
+    mw(  1827,  20,  20,  50, CT_COLA,                      Y), // 110 Cola Tanker
+    mw(  1827,  20,  20,  50, CT_CANDY,                     Y), // 111 Sweet Van
+    mw(  1827,  20,  20,  50, CT_TOYS,                      Y), // 112 Toy Van
+    mw(  1827,  20,  20,  50, CT_BATTERIES,                 Y), // 113 Battery Truck
+    mw(  1827,  20,  20,  50, CT_FIZZY_DRINKS,              Y), // 114 Fizzy Drink Truck
+    mw(  1827,  20,  20,  50, CT_PLASTIC,                   Y), // 115 Plastic Truck
+    mr(  3378,  20,  12,  40, CT_PASSENGERS,                T | A | S), // 116 MPS Regal Bus
+    mr( 16071,  20,  15,  30, CT_PASSENGERS,                T | A | S), // 117 Hereford Leopard Bus
+    mr( 24107,  20,  15,  40, CT_PASSENGERS,                T | A | S), // 118 Foster Bus
+    mr( 32142,  20,  15,  80, CT_PASSENGERS,                T | A | S), // 119 Foster MkII Superbus
+    mr(  9132,  20,  15,  40, CT_PASSENGERS,                Y), // 120 Ploddyphut MkI Bus
+    mr( 18993,  20,  15,  40, CT_PASSENGERS,                Y), // 121 Ploddyphut MkII Bus
+    mr( 32873,  20,  15,  80, CT_PASSENGERS,                Y), // 122 Ploddyphut MkIII Bus
+    mr(  5479,  20,  15,  55, CT_COAL,                      T | A), // 123 Balogh Coal Truck
+    mr( 20089,  20,  15,  55, CT_COAL,                      T | A), // 124 Uhl Coal Truck
+    mr( 33969,  20,  15,  85, CT_COAL,                      T | A), // 125 DW Coal Truck
+    mr(  5479,  20,  15,  55, CT_MAIL,                      T | A | S), // 126 MPS Mail Truck
+    mr( 21550,  20,  15,  55, CT_MAIL,                      T | A | S), // 127 Reynard Mail Truck
+    mr( 35795,  20,  15,  85, CT_MAIL,                      T | A | S), // 128 Perry Mail Truck
+    mr(  5479,  20,  15,  55, CT_MAIL,                      Y), // 129 MightyMover Mail Truck
+    mr( 21550,  20,  15,  55, CT_MAIL,                      Y), // 130 Powernaught Mail Truck
+    mr( 35795,  20,  15,  85, CT_MAIL,                      Y), // 131 Wizzowow Mail Truck
+    mr(  5479,  20,  15,  55, CT_OIL,                       T | A | S), // 132 Witcombe Oil Tanker
+    mr( 19359,  20,  15,  55, CT_OIL,                       T | A | S), // 133 Foster Oil Tanker
+    mr( 31047,  20,  15,  85, CT_OIL,                       T | A | S), // 134 Perry Oil Tanker
+    mr(  5479,  20,  15,  55, CT_LIVESTOCK,                 T | A), // 135 Talbott Livestock Van
+    mr( 21915,  20,  15,  55, CT_LIVESTOCK,                 T | A), // 136 Uhl Livestock Van
+    mr( 37256,  20,  15,  85, CT_LIVESTOCK,                 T | A), // 137 Foster Livestock Van
+    mr(  5479,  20,  15,  55, CT_GOODS,                     T | A | S), // 138 Balogh Goods Truck
+    mr( 19724,  20,  15,  55, CT_GOODS,                     T | A | S), // 139 Craighead Goods Truck
+    mr( 31047,  20,  15,  85, CT_GOODS,                     T | A | S), // 140 Goss Goods Truck
+    mr(  5479,  20,  15,  55, MCT_GRAIN_WHEAT_MAIZE,        T | A | S), // 141 Hereford Grain Truck
+    mr( 21185,  20,  15,  55, MCT_GRAIN_WHEAT_MAIZE,        T | A | S), // 142 Thomas Grain Truck
+    mr( 32873,  20,  15,  85, MCT_GRAIN_WHEAT_MAIZE,        T | A | S), // 143 Goss Grain Truck
+    mr(  5479,  20,  15,  55, CT_WOOD,                      T | A | S), // 144 Witcombe Wood Truck
+    mr( 19724,  20,  15,  55, CT_WOOD,                      T | A | S), // 145 Foster Wood Truck
+    mr( 35430,  20,  15,  85, CT_WOOD,                      T | A | S), // 146 Moreland Wood Truck
+    mr(  5479,  20,  15,  55, CT_IRON_ORE,                  T), // 147 MPS Iron Ore Truck
+    mr( 20820,  20,  15,  55, CT_IRON_ORE,                  T), // 148 Uhl Iron Ore Truck
+    mr( 33238,  20,  15,  85, CT_IRON_ORE,                  T), // 149 Chippy Iron Ore Truck
+    mr(  5479,  20,  15,  55, CT_STEEL,                     T), // 150 Balogh Steel Truck
+    mr( 21185,  20,  15,  55, CT_STEEL,                     T), // 151 Uhl Steel Truck
+    mr( 31777,  20,  15,  85, CT_STEEL,                     T), // 152 Kelling Steel Truck
+    mr(  5479,  20,  15,  55, MCT_VALUABLES_GOLD_DIAMONDS,  T | A | S), // 153 Balogh Armoured Truck
+    mr( 22281,  20,  15,  55, MCT_VALUABLES_GOLD_DIAMONDS,  T | A | S), // 154 Uhl Armoured Truck
+    mr( 33603,  20,  15,  85, MCT_VALUABLES_GOLD_DIAMONDS,  T | A | S), // 155 Foster Armoured Truck
+    mr(  5479,  20,  15,  55, CT_FOOD,                      A | S), // 156 Foster Food Van
+    mr( 18628,  20,  15,  55, CT_FOOD,                      A | S), // 157 Perry Food Van
+    mr( 30681,  20,  15,  85, CT_FOOD,                      A | S), // 158 Chippy Food Van
+    mr(  5479,  20,  15,  55, CT_PAPER,                     A), // 159 Uhl Paper Truck
+    mr( 21185,  20,  15,  55, CT_PAPER,                     A), // 160 Balogh Paper Truck
+    mr( 31777,  20,  15,  85, CT_PAPER,                     A), // 161 MPS Paper Truck
+    mr(  5479,  20,  15,  55, CT_COPPER_ORE,                S), // 162 MPS Copper Ore Truck
+    mr( 20820,  20,  15,  55, CT_COPPER_ORE,                S), // 163 Uhl Copper Ore Truck
+    mr( 33238,  20,  15,  85, CT_COPPER_ORE,                S), // 164 Goss Copper Ore Truck
+    mr(  5479,  20,  15,  55, CT_WATER,                     S), // 165 Uhl Water Tanker
+    mr( 20970,  20,  15,  55, CT_WATER,                     S), // 166 Balogh Water Tanker
+    mr( 33388,  20,  15,  85, CT_WATER,                     S), // 167 MPS Water Tanker
+    mr(  5479,  20,  15,  55, CT_FRUIT,                     S), // 168 Balogh Fruit Truck
+    mr( 21335,  20,  15,  55, CT_FRUIT,                     S), // 169 Uhl Fruit Truck
+    mr( 33753,  20,  15,  85, CT_FRUIT,                     S), // 170 Kelling Fruit Truck
+    mr(  5479,  20,  15,  55, CT_RUBBER,                    S), // 171 Balogh Rubber Truck
+    mr( 20604,  20,  15,  55, CT_RUBBER,                    S), // 172 Uhl Rubber Truck
+    mr( 33023,  20,  15,  85, CT_RUBBER,                    S), // 173 RMT Rubber Truck
+    mr(  5479,  20,  15,  55, CT_SUGAR,                     Y), // 174 MightyMover Sugar Truck
+    mr( 19724,  20,  15,  55, CT_SUGAR,                     Y), // 175 Powernaught Sugar Truck
+    mr( 33238,  20,  15,  85, CT_SUGAR,                     Y), // 176 Wizzowow Sugar Truck
+    mr(  5479,  20,  15,  55, CT_COLA,                      Y), // 177 MightyMover Cola Truck
+    mr( 20089,  20,  15,  55, CT_COLA,                      Y), // 178 Powernaught Cola Truck
+    mr( 33603,  20,  15,  85, CT_COLA,                      Y), // 179 Wizzowow Cola Truck
+    mr(  5479,  20,  15,  55, CT_COTTON_CANDY,              Y), // 180 MightyMover Candyfloss Truck
+    mr( 20454,  20,  15,  55, CT_COTTON_CANDY,              Y), // 181 Powernaught Candyfloss Truck
+    mr( 33969,  20,  15,  85, CT_COTTON_CANDY,              Y), // 182 Wizzowow Candyfloss Truck
+    mr(  5479,  20,  15,  55, CT_TOFFEE,                    Y), // 183 MightyMover Toffee Truck
+    mr( 20820,  20,  15,  55, CT_TOFFEE,                    Y), // 184 Powernaught Toffee Truck
+    mr( 34334,  20,  15,  85, CT_TOFFEE,                    Y), // 185 Wizzowow Toffee Truck
+    mr(  5479,  20,  15,  55, CT_TOYS,                      Y), // 186 MightyMover Toy Van
+    mr( 21185,  20,  15,  55, CT_TOYS,                      Y), // 187 Powernaught Toy Van
+    mr( 34699,  20,  15,  85, CT_TOYS,                      Y), // 188 Wizzowow Toy Van
+    mr(  5479,  20,  15,  55, CT_CANDY,                     Y), // 189 MightyMover Sweet Truck
+    mr( 21550,  20,  15,  55, CT_CANDY,                     Y), // 190 Powernaught Sweet Truck
+    mr( 35064,  20,  15,  85, CT_CANDY,                     Y), // 191 Wizzowow Sweet Truck
+    mr(  5479,  20,  15,  55, CT_BATTERIES,                 Y), // 192 MightyMover Battery Truck
+    mr( 19874,  20,  15,  55, CT_BATTERIES,                 Y), // 193 Powernaught Battery Truck
+    mr( 35430,  20,  15,  85, CT_BATTERIES,                 Y), // 194 Wizzowow Battery Truck
+    mr(  5479,  20,  15,  55, CT_FIZZY_DRINKS,              Y), // 195 MightyMover Fizzy Drink Truck
+    mr( 20239,  20,  15,  55, CT_FIZZY_DRINKS,              Y), // 196 Powernaught Fizzy Drink Truck
+    mr( 35795,  20,  15,  85, CT_FIZZY_DRINKS,              Y), // 197 Wizzowow Fizzy Drink Truck
+    mr(  5479,  20,  15,  55, CT_PLASTIC,                   Y), // 198 MightyMover Plastic Truck
+    mr( 20604,  20,  15,  55, CT_PLASTIC,                   Y), // 199 Powernaught Plastic Truck
+    mr( 32873,  20,  15,  85, CT_PLASTIC,                   Y), // 200 Wizzowow Plastic Truck
+    mr(  5479,  20,  15,  55, CT_BUBBLES,                   Y), // 201 MightyMover Bubble Truck
+    mr( 20970,  20,  15,  55, CT_BUBBLES,                   Y), // 202 Powernaught Bubble Truck
+    mr( 33023,  20,  15,  85, CT_BUBBLES,                   Y), // 203 Wizzowow Bubble Truck
+    ms(  2922,   5,  30,  50, CT_OIL,                       T | A | S), // 204 MPS Oil Tanker
+    ms( 17167,   5,  30,  90, CT_OIL,                       T | A | S), // 205 CS-Inc. Oil Tanker
+    ms(  2192,   5,  30,  55, CT_PASSENGERS,                T | A | S), // 206 MPS Passenger Ferry
+    ms( 18628,   5,  30,  90, CT_PASSENGERS,                T | A | S), // 207 FFP Passenger Ferry
+    ms( 17257,  10,  25,  90, CT_PASSENGERS,                T | A | S), // 208 Bakewell 300 Hovercraft
+    ms(  9587,   5,  30,  40, CT_PASSENGERS,                Y), // 209 Chugger-Chug Passenger Ferry
+    ms( 20544,   5,  30,  90, CT_PASSENGERS,                Y), // 210 Shivershake Passenger Ferry
+    ms(  2557,   5,  30,  55, CT_GOODS,                     T | A | S), // 211 Yate Cargo ship
+    ms( 19724,   5,  30,  98, CT_GOODS,                     T | A | S), // 212 Bakewell Cargo ship
+    ms(  9587,   5,  30,  45, CT_GOODS,                     Y), // 213 Mightymover Cargo ship
+    ms( 22371,   5,  30,  90, CT_GOODS,                     Y), // 214 Powernaut Cargo ship
+    ma(  2922,  20,  20,  20,                               T | A | S), // 215 Sampson U52
+    ma(  9922,  20,  24,  20,                               T | A | S), // 216 Coleman Count
+    ma( 12659,  20,  18,  20,                               T | A | S), // 217 FFP Dart
+    ma( 17652,  20,  25,  35,                               T | A | S), // 218 Yate Haugan
+    ma(  4929,  20,  30,  30,                               T | A | S), // 219 Bakewell Cotswald LB-3
+    ma( 13695,  20,  23,  25,                               T | A | S), // 220 Bakewell Luckett LB-8
+    ma( 16341,  20,  26,  30,                               T | A | S), // 221 Bakewell Luckett LB-9
+    ma( 21395,  20,  25,  30,                               T | A | S), // 222 Bakewell Luckett LB80
+    ma( 18263,  20,  20,  30,                               T | A | S), // 223 Bakewell Luckett LB-10
+    ma( 25233,  20,  25,  30,                               T | A | S), // 224 Bakewell Luckett LB-11
+    ma( 15371,  20,  22,  25,                               T | A | S), // 225 Yate Aerospace YAC 1-11
+    ma( 15461,  20,  25,  25,                               T | A | S), // 226 Darwin 100
+    ma( 16952,  20,  22,  25,                               T | A | S), // 227 Darwin 200
+    ma( 17227,  20,  25,  30,                               T | A | S), // 228 Darwin 300
+    ma( 22371,  20,  25,  35,                               T | A | S), // 229 Darwin 400
+    ma( 22341,  20,  25,  30,                               T | A | S), // 230 Darwin 500
+    ma( 27209,  20,  25,  30,                               T | A | S), // 231 Darwin 600
+    ma( 17988,  20,  20,  30,                               T | A | S), // 232 Guru Galaxy
+    ma( 18993,  20,  24,  35,                               T | A | S), // 233 Airtaxi A21
+    ma( 22401,  20,  24,  30,                               T | A | S), // 234 Airtaxi A31
+    ma( 24472,  20,  24,  30,                               T | A | S), // 235 Airtaxi A32
+    ma( 26724,  20,  24,  30,                               T | A | S), // 236 Airtaxi A33
+    ma( 22005,  20,  25,  30,                               T | A | S), // 237 Yate Aerospace YAe46
+    ma( 24107,  20,  20,  35,                               T | A | S), // 238 Dinger 100
+    ma( 29310,  20,  25,  60,                               T | A | S), // 239 AirTaxi A34-1000
+    ma( 35520,  20,  22,  30,                               T | A | S), // 240 Yate Z-Shuttle
+    ma( 36981,  20,  22,  30,                               T | A | S), // 241 Kelling K1
+    ma( 38807,  20,  22,  50,                               T | A | S), // 242 Kelling K6
+    ma( 42094,  20,  25,  30,                               T | A | S), // 243 Kelling K7
+    ma( 44651,  20,  23,  30,                               T | A | S), // 244 Darwin 700
+    ma( 40268,  20,  25,  30,                               T | A | S), // 245 FFP Hyperdart 2
+    ma( 33693,  20,  25,  50,                               T | A | S), // 246 Dinger 200
+    ma( 32963,  20,  20,  60,                               T | A | S), // 247 Dinger 1000
+    ma(  9222,  20,  20,  35,                               Y), // 248 Ploddyphut 100
+    ma( 12874,  20,  20,  35,                               Y), // 249 Ploddyphut 500
+    ma( 16892,  20,  20,  35,                               Y), // 250 Flashbang X1
+    ma( 21275,  20,  20,  99,                               Y), // 251 Juggerplane M1
+    ma( 23832,  20,  20,  99,                               Y), // 252 Flashbang Wizzer
+    ma( 13575,  20,  20,  40,                               T | A | S), // 253 Tricario Helicopter
+    ma( 28215,  20,  20,  30,                               T | A | S), // 254 Guru X2 Helicopter
+    ma( 13575,  20,  20,  99,                               Y), // 255 Powernaut Helicopter
+];
+
+const M: RailVehicleType = RailVehicleType::Multihead;
+const W: RailVehicleType = RailVehicleType::Wagon;
+const G: RailVehicleType = RailVehicleType::Singlehead;
+
+const R: RailType = RailType::Rail;
+const C: RailType = RailType::Electric;
+const O: RailType = RailType::Monorail;
+const L: RailType = RailType::Maglev;
+
+const EC_S: EngineClass = EngineClass::Steam;
+const EC_D: EngineClass = EngineClass::Diesel;
+const EC_E: EngineClass = EngineClass::Electric;
+const EC_N: EngineClass = EngineClass::Monorail;
+const EC_V: EngineClass = EngineClass::Maglev;
+// Wagons always have engine class steam
+const EC_A: EngineClass = EngineClass::Steam;
+
+const RC_S: Price = Price::RunningTrainSteam;
+const RC_D: Price = Price::RunningTrainDiesel;
+const RC_E: Price = Price::RunningTrainElectric;
+const RC_W: Price = Price::Invalid;
+
+/// Rail vehicle with the default tractive effort of 0.3 (76/256)
+#[allow(clippy::too_many_arguments)]
+const fn rvi(
+    image_index: u8,
+    railveh_type: RailVehicleType,
+    cost_factor: u8,
+    max_speed: u16,
+    power: u16,
+    weight: u16,
+    running_cost: u8,
+    running_cost_class: Price,
+    capacity: u8,
+    railtype: RailType,
+    engclass: EngineClass,
+) -> RailVehicleInfo {
+    let railtypes: RailTypes = 1 << railtype as u8;
+    RailVehicleInfo {
+        image_index,
+        railveh_type,
+        cost_factor,
+        railtypes,
+        intended_railtypes: railtypes,
+        ai_passenger_only: 0,
+        max_speed,
+        power,
+        weight,
+        running_cost,
+        running_cost_class,
+        engclass,
+        capacity,
+        pow_wag_power: 0,
+        pow_wag_weight: 0,
+        visual_effect: VE_DEFAULT,
+        shorten_factor: 0,
+        tractive_effort: 76,
+        air_drag: 0,
+        user_def_data: 0,
+        curve_speed_mod: 0,
+    }
+}
+
+/// Base set rail vehicles (matches C++ _orig_rail_vehicle_info)
+///
+/// Arguments: image index, type, cost factor, max speed, power, weight,
+/// running cost, running cost class, capacity, rail type and engine class.
+#[rustfmt::skip]
+pub const ORIG_RAIL_VEHICLE_INFO: [RailVehicleInfo; 116] = [
+    rvi( 2, G,   7,  64,   300,  47,  50, RC_S,  0, R, EC_S), //   0 Kirby Paul Tank (Steam)
+    rvi(19, G,   8,  80,   600,  65,  65, RC_D,  0, R, EC_D), //   1 MJS 250 (Diesel)
+    rvi( 2, G,  10,  72,   400,  85,  90, RC_S,  0, R, EC_S), //   2 Ploddyphut Choo-Choo
+    rvi( 0, G,  15,  96,   900, 130, 130, RC_S,  0, R, EC_S), //   3 Powernaut Choo-Choo
+    rvi( 1, G,  19, 112,  1000, 140, 145, RC_S,  0, R, EC_S), //   4 Mightymover Choo-Choo
+    rvi(12, G,  16, 120,  1400,  95, 125, RC_D,  0, R, EC_D), //   5 Ploddyphut Diesel
+    rvi(14, G,  20, 152,  2000, 120, 135, RC_D,  0, R, EC_D), //   6 Powernaut Diesel
+    rvi( 3, G,  14,  88,  1100, 145, 130, RC_S,  0, R, EC_S), //   7 Wills 2-8-0 (Steam)
+    rvi( 0, G,  13, 112,  1000, 131, 120, RC_S,  0, R, EC_S), //   8 Chaney 'Jubilee' (Steam)
+    rvi( 1, G,  19, 128,  1200, 162, 140, RC_S,  0, R, EC_S), //   9 Ginzu 'A4' (Steam)
+    rvi( 0, G,  22, 144,  1600, 170, 130, RC_S,  0, R, EC_S), //  10 SH '8P' (Steam)
+    rvi( 8, M,  11, 112,   600,  32,  85, RC_D, 38, R, EC_D), //  11 Manley-Morel DMU (Diesel)
+    rvi(10, M,  14, 120,   700,  38,  70, RC_D, 40, R, EC_D), //  12 'Dash' (Diesel)
+    rvi( 4, G,  15, 128,  1250,  72,  95, RC_D,  0, R, EC_D), //  13 SH/Hendry '25' (Diesel)
+    rvi( 5, G,  17, 144,  1750, 101, 120, RC_D,  0, R, EC_D), //  14 UU '37' (Diesel)
+    rvi( 4, G,  18, 160,  2580, 112, 140, RC_D,  0, R, EC_D), //  15 Floss '47' (Diesel)
+    rvi(14, G,  23,  96,  4000, 150, 135, RC_D,  0, R, EC_D), //  16 CS 4000 (Diesel)
+    rvi(12, G,  16, 112,  2400, 120, 105, RC_D,  0, R, EC_D), //  17 CS 2400 (Diesel)
+    rvi(13, G,  30, 112,  6600, 207, 155, RC_D,  0, R, EC_D), //  18 Centennial (Diesel)
+    rvi(15, G,  18, 104,  1500, 110, 105, RC_D,  0, R, EC_D), //  19 Kelling 3100 (Diesel)
+    rvi(16, M,  35, 160,  3500,  95, 205, RC_D,  0, R, EC_D), //  20 Turner Turbo (Diesel)
+    rvi(18, G,  21, 104,  2200, 120, 145, RC_D,  0, R, EC_D), //  21 MJS 1000 (Diesel)
+    rvi( 6, M,  20, 200,  4500,  70, 190, RC_D,  4, R, EC_D), //  22 SH '125' (Diesel)
+    rvi(20, G,  26, 160,  3600,  84, 180, RC_E,  0, C, EC_E), //  23 SH '30' (Electric)
+    rvi(20, G,  30, 176,  5000,  82, 205, RC_E,  0, C, EC_E), //  24 SH '40' (Electric)
+    rvi(21, M,  40, 240,  7000,  90, 240, RC_E,  0, C, EC_E), //  25 'T.I.M.' (Electric)
+    rvi(23, M,  43, 264,  8000,  95, 250, RC_E,  0, C, EC_E), //  26 'AsiaStar' (Electric)
+    rvi(33, W, 247,   0,     0,  25,   0, RC_W, 40, R, EC_A), //  27 Passenger Carriage
+    rvi(35, W, 228,   0,     0,  21,   0, RC_W, 30, R, EC_A), //  28 Mail Van
+    rvi(34, W, 176,   0,     0,  18,   0, RC_W, 30, R, EC_A), //  29 Coal Truck
+    rvi(36, W, 200,   0,     0,  24,   0, RC_W, 30, R, EC_A), //  30 Oil Tanker
+    rvi(37, W, 192,   0,     0,  20,   0, RC_W, 25, R, EC_A), //  31 Livestock Van
+    rvi(38, W, 190,   0,     0,  21,   0, RC_W, 25, R, EC_A), //  32 Goods Van
+    rvi(39, W, 182,   0,     0,  19,   0, RC_W, 30, R, EC_A), //  33 Grain Hopper
+    rvi(40, W, 181,   0,     0,  16,   0, RC_W, 30, R, EC_A), //  34 Wood Truck
+    rvi(41, W, 179,   0,     0,  19,   0, RC_W, 30, R, EC_A), //  35 Iron Ore Hopper
+    rvi(42, W, 196,   0,     0,  18,   0, RC_W, 20, R, EC_A), //  36 Steel Truck
+    rvi(43, W, 255,   0,     0,  30,   0, RC_W, 20, R, EC_A), //  37 Armoured Van
+    rvi(44, W, 191,   0,     0,  22,   0, RC_W, 25, R, EC_A), //  38 Food Van
+    rvi(45, W, 196,   0,     0,  18,   0, RC_W, 20, R, EC_A), //  39 Paper Truck
+    rvi(46, W, 179,   0,     0,  19,   0, RC_W, 30, R, EC_A), //  40 Copper Ore Hopper
+    rvi(47, W, 199,   0,     0,  25,   0, RC_W, 25, R, EC_A), //  41 Water Tanker
+    rvi(48, W, 182,   0,     0,  18,   0, RC_W, 25, R, EC_A), //  42 Fruit Truck
+    rvi(49, W, 185,   0,     0,  19,   0, RC_W, 21, R, EC_A), //  43 Rubber Truck
+    rvi(50, W, 176,   0,     0,  19,   0, RC_W, 30, R, EC_A), //  44 Sugar Truck
+    rvi(51, W, 178,   0,     0,  20,   0, RC_W, 30, R, EC_A), //  45 Candyfloss Hopper
+    rvi(52, W, 192,   0,     0,  20,   0, RC_W, 30, R, EC_A), //  46 Toffee Hopper
+    rvi(53, W, 190,   0,     0,  21,   0, RC_W, 20, R, EC_A), //  47 Bubble Van
+    rvi(54, W, 182,   0,     0,  24,   0, RC_W, 25, R, EC_A), //  48 Cola Tanker
+    rvi(55, W, 181,   0,     0,  21,   0, RC_W, 25, R, EC_A), //  49 Sweet Van
+    rvi(56, W, 183,   0,     0,  21,   0, RC_W, 20, R, EC_A), //  50 Toy Van
+    rvi(57, W, 196,   0,     0,  18,   0, RC_W, 22, R, EC_A), //  51 Battery Truck
+    rvi(58, W, 193,   0,     0,  18,   0, RC_W, 25, R, EC_A), //  52 Fizzy Drink Truck
+    rvi(59, W, 191,   0,     0,  18,   0, RC_W, 30, R, EC_A), //  53 Plastic Truck
+    rvi(25, G,  52, 304,  9000,  95, 230, RC_E,  0, O, EC_N), //  54 'X2001' (Electric)
+    rvi(26, M,  60, 336, 10000,  85, 240, RC_E, 25, O, EC_N), //  55 'Millennium Z1' (Electric)
+    rvi(26, G,  53, 320,  5000,  95, 230, RC_E,  0, O, EC_N), //  56 Wizzowow Z99
+    rvi(60, W, 247,   0,     0,  25,   0, RC_W, 45, O, EC_A), //  57 Passenger Carriage
+    rvi(62, W, 228,   0,     0,  21,   0, RC_W, 35, O, EC_A), //  58 Mail Van
+    rvi(61, W, 176,   0,     0,  18,   0, RC_W, 35, O, EC_A), //  59 Coal Truck
+    rvi(63, W, 200,   0,     0,  24,   0, RC_W, 35, O, EC_A), //  60 Oil Tanker
+    rvi(64, W, 192,   0,     0,  20,   0, RC_W, 30, O, EC_A), //  61 Livestock Van
+    rvi(65, W, 190,   0,     0,  21,   0, RC_W, 30, O, EC_A), //  62 Goods Van
+    rvi(66, W, 182,   0,     0,  19,   0, RC_W, 35, O, EC_A), //  63 Grain Hopper
+    rvi(67, W, 181,   0,     0,  16,   0, RC_W, 35, O, EC_A), //  64 Wood Truck
+    rvi(68, W, 179,   0,     0,  19,   0, RC_W, 35, O, EC_A), //  65 Iron Ore Hopper
+    rvi(69, W, 196,   0,     0,  18,   0, RC_W, 25, O, EC_A), //  66 Steel Truck
+    rvi(70, W, 255,   0,     0,  30,   0, RC_W, 25, O, EC_A), //  67 Armoured Van
+    rvi(71, W, 191,   0,     0,  22,   0, RC_W, 30, O, EC_A), //  68 Food Van
+    rvi(72, W, 196,   0,     0,  18,   0, RC_W, 25, O, EC_A), //  69 Paper Truck
+    rvi(73, W, 179,   0,     0,  19,   0, RC_W, 35, O, EC_A), //  70 Copper Ore Hopper
+    rvi(47, W, 199,   0,     0,  25,   0, RC_W, 30, O, EC_A), //  71 Water Tanker
+    rvi(48, W, 182,   0,     0,  18,   0, RC_W, 30, O, EC_A), //  72 Fruit Truck
+    rvi(49, W, 185,   0,     0,  19,   0, RC_W, 26, O, EC_A), //  73 Rubber Truck
+    rvi(50, W, 176,   0,     0,  19,   0, RC_W, 35, O, EC_A), //  74 Sugar Truck
+    rvi(51, W, 178,   0,     0,  20,   0, RC_W, 35, O, EC_A), //  75 Candyfloss Hopper
+    rvi(52, W, 192,   0,     0,  20,   0, RC_W, 35, O, EC_A), //  76 Toffee Hopper
+    rvi(53, W, 190,   0,     0,  21,   0, RC_W, 25, O, EC_A), //  77 Bubble Van
+    rvi(54, W, 182,   0,     0,  24,   0, RC_W, 30, O, EC_A), //  78 Cola Tanker
+    rvi(55, W, 181,   0,     0,  21,   0, RC_W, 30, O, EC_A), //  79 Sweet Van
+    rvi(56, W, 183,   0,     0,  21,   0, RC_W, 25, O, EC_A), //  80 Toy Van
+    rvi(57, W, 196,   0,     0,  18,   0, RC_W, 27, O, EC_A), //  81 Battery Truck
+    rvi(58, W, 193,   0,     0,  18,   0, RC_W, 30, O, EC_A), //  82 Fizzy Drink Truck
+    rvi(59, W, 191,   0,     0,  18,   0, RC_W, 35, O, EC_A), //  83 Plastic Truck
+    rvi(28, G,  70, 400, 10000, 105, 250, RC_E,  0, L, EC_V), //  84 Lev1 'Leviathan' (Electric)
+    rvi(29, G,  74, 448, 12000, 120, 253, RC_E,  0, L, EC_V), //  85 Lev2 'Cyclops' (Electric)
+    rvi(30, G,  82, 480, 15000, 130, 254, RC_E,  0, L, EC_V), //  86 Lev3 'Pegasus' (Electric)
+    rvi(31, M,  95, 640, 20000, 150, 255, RC_E,  0, L, EC_V), //  87 Lev4 'Chimaera' (Electric)
+    rvi(28, G,  70, 480, 10000, 120, 250, RC_E,  0, L, EC_V), //  88 Wizzowow Rocketeer
+    rvi(60, W, 247,   0,     0,  25,   0, RC_W, 47, L, EC_A), //  89 Passenger Carriage
+    rvi(62, W, 228,   0,     0,  21,   0, RC_W, 37, L, EC_A), //  90 Mail Van
+    rvi(61, W, 176,   0,     0,  18,   0, RC_W, 37, L, EC_A), //  91 Coal Truck
+    rvi(63, W, 200,   0,     0,  24,   0, RC_W, 37, L, EC_A), //  92 Oil Tanker
+    rvi(64, W, 192,   0,     0,  20,   0, RC_W, 32, L, EC_A), //  93 Livestock Van
+    rvi(65, W, 190,   0,     0,  21,   0, RC_W, 32, L, EC_A), //  94 Goods Van
+    rvi(66, W, 182,   0,     0,  19,   0, RC_W, 37, L, EC_A), //  95 Grain Hopper
+    rvi(67, W, 181,   0,     0,  16,   0, RC_W, 37, L, EC_A), //  96 Wood Truck
+    rvi(68, W, 179,   0,     0,  19,   0, RC_W, 37, L, EC_A), //  97 Iron Ore Hopper
+    rvi(69, W, 196,   0,     0,  18,   0, RC_W, 27, L, EC_A), //  98 Steel Truck
+    rvi(70, W, 255,   0,     0,  30,   0, RC_W, 27, L, EC_A), //  99 Armoured Van
+    rvi(71, W, 191,   0,     0,  22,   0, RC_W, 32, L, EC_A), // 100 Food Van
+    rvi(72, W, 196,   0,     0,  18,   0, RC_W, 27, L, EC_A), // 101 Paper Truck
+    rvi(73, W, 179,   0,     0,  19,   0, RC_W, 37, L, EC_A), // 102 Copper Ore Hopper
+    rvi(47, W, 199,   0,     0,  25,   0, RC_W, 32, L, EC_A), // 103 Water Tanker
+    rvi(48, W, 182,   0,     0,  18,   0, RC_W, 32, L, EC_A), // 104 Fruit Truck
+    rvi(49, W, 185,   0,     0,  19,   0, RC_W, 28, L, EC_A), // 105 Rubber Truck
+    rvi(50, W, 176,   0,     0,  19,   0, RC_W, 37, L, EC_A), // 106 Sugar Truck
+    rvi(51, W, 178,   0,     0,  20,   0, RC_W, 37, L, EC_A), // 107 Candyfloss Hopper
+    rvi(52, W, 192,   0,     0,  20,   0, RC_W, 37, L, EC_A), // 108 Toffee Hopper
+    rvi(53, W, 190,   0,     0,  21,   0, RC_W, 27, L, EC_A), // 109 Bubble Van
+    rvi(54, W, 182,   0,     0,  24,   0, RC_W, 32, L, EC_A), // 110 Cola Tanker
+    rvi(55, W, 181,   0,     0,  21,   0, RC_W, 32, L, EC_A), // 111 Sweet Van
+    rvi(56, W, 183,   0,     0,  21,   0, RC_W, 27, L, EC_A), // 112 Toy Van
+    rvi(57, W, 196,   0,     0,  18,   0, RC_W, 29, L, EC_A), // 113 Battery Truck
+    rvi(58, W, 193,   0,     0,  18,   0, RC_W, 32, L, EC_A), // 114 Fizzy Drink Truck
+    rvi(59, W, 191,   0,     0,  18,   0, RC_W, 37, L, EC_A), // 115 Plastic Truck
+];
+
+/// Ship; acceleration and speed are in units of 1/3.2 mph (per tick)
+#[allow(clippy::too_many_arguments)]
+const fn svi(
+    image_index: u8,
+    cost_factor: u8,
+    acceleration: u8,
+    max_speed: u16,
+    capacity: u16,
+    running_cost: u8,
+    sfx: SoundID,
+    old_refittable: bool,
+) -> ShipVehicleInfo {
+    ShipVehicleInfo {
+        image_index,
+        cost_factor,
+        running_cost,
+        acceleration,
+        max_speed,
+        capacity,
+        sfx,
+        old_refittable,
+        visual_effect: VE_DEFAULT,
+        ocean_speed_frac: 0,
+        canal_speed_frac: 0,
+    }
+}
+
+/// Base set ships (matches C++ _orig_ship_vehicle_info)
+///
+/// Arguments: image index, cost factor, acceleration, max speed, capacity,
+/// running cost, sound effect and whether the ship is refittable.
+#[rustfmt::skip]
+pub const ORIG_SHIP_VEHICLE_INFO: [ShipVehicleInfo; 11] = [
+    svi( 1, 160, 1,  48, 220, 140,  4, false), //  0 MPS Oil Tanker
+    svi( 1, 176, 1,  80, 350, 125,  4, false), //  1 CS-Inc. Oil Tanker
+    svi( 2,  96, 1,  64, 100,  90,  5, false), //  2 MPS Passenger Ferry
+    svi( 2, 112, 1, 128, 130,  80,  5, false), //  3 FFP Passenger Ferry
+    svi( 3, 148, 1, 224, 100, 190,  5, false), //  4 Bakewell 300 Hovercraft
+    svi( 2,  96, 1,  64, 100,  90,  5, false), //  5 Chugger-Chug Passenger Ferry
+    svi( 2, 112, 1, 128, 130,  80,  5, false), //  6 Shivershake Passenger Ferry
+    svi( 0, 128, 1,  48, 160, 150,  4, true ), //  7 Yate Cargo ship
+    svi( 0, 144, 1,  80, 190, 113,  4, true ), //  8 Bakewell Cargo ship
+    svi( 0, 128, 1,  48, 160, 150,  4, true ), //  9 Mightymover Cargo ship
+    svi( 0, 144, 1,  80, 190, 113,  4, true ), // 10 Powernaut Cargo ship
+];
+
+const H: u8 = AIR_HELI;
+const P: u8 = AIR_CTOL;
+const J: u8 = AIR_CTOL | AIR_FAST;
+
+/// Aircraft; the speed is given in units of 8 mph and stored in km-ish/h
+#[allow(clippy::too_many_arguments)]
+const fn avi(
+    image_index: u8,
+    cost_factor: u8,
+    running_cost: u8,
+    subtype: u8,
+    sfx: SoundID,
+    acceleration: u8,
+    max_speed: u16,
+    mail_capacity: u8,
+    passenger_capacity: u16,
+) -> AircraftVehicleInfo {
+    AircraftVehicleInfo {
+        image_index,
+        cost_factor,
+        running_cost,
+        subtype,
+        sfx,
+        max_speed: (max_speed * 128) / 10,
+        acceleration,
+        mail_capacity,
+        passenger_capacity,
+        max_range: 0,
+    }
+}
+
+/// Base set aircraft (matches C++ _orig_aircraft_vehicle_info)
+///
+/// Arguments: image index, cost factor, running cost, subtype, sound effect,
+/// acceleration, max speed, mail capacity and passenger capacity.
+#[rustfmt::skip]
+pub const ORIG_AIRCRAFT_VEHICLE_INFO: [AircraftVehicleInfo; 41] = [
+    avi( 1, 14,  85, P,  6, 18,  37,  4,  25), //  0 Sampson U52
+    avi( 0, 15, 100, P,  6, 20,  37,  8,  65), //  1 Coleman Count
+    avi( 2, 16, 130, J,  7, 35,  74, 10,  90), //  2 FFP Dart
+    avi( 8, 75, 250, J, 59, 50, 181, 20, 100), //  3 Yate Haugan
+    avi( 5, 15,  98, P,  6, 20,  37,  6,  30), //  4 Bakewell Cotswald LB-3
+    avi( 6, 18, 240, J,  7, 40,  74, 30, 200), //  5 Bakewell Luckett LB-8
+    avi( 2, 17, 150, P,  7, 35,  74, 15, 100), //  6 Bakewell Luckett LB-9
+    avi( 2, 18, 245, J,  7, 40,  74, 30, 150), //  7 Bakewell Luckett LB80
+    avi( 3, 19, 192, J,  7, 40,  74, 40, 220), //  8 Bakewell Luckett LB-10
+    avi( 3, 20, 190, J,  7, 40,  74, 25, 230), //  9 Bakewell Luckett LB-11
+    avi( 2, 16, 135, J,  7, 35,  74, 10,  95), // 10 Yate Aerospace YAC 1-11
+    avi( 2, 18, 240, J,  7, 40,  74, 35, 170), // 11 Darwin 100
+    avi( 4, 17, 155, J,  7, 40,  74, 15, 110), // 12 Darwin 200
+    avi( 7, 30, 253, J, 61, 40,  74, 50, 300), // 13 Darwin 300
+    avi( 4, 18, 210, J,  7, 40,  74, 25, 200), // 14 Darwin 400
+    avi( 4, 19, 220, J,  7, 40,  74, 25, 240), // 15 Darwin 500
+    avi( 4, 27, 230, J,  7, 40,  74, 40, 260), // 16 Darwin 600
+    avi( 3, 25, 225, J,  7, 40,  74, 35, 240), // 17 Guru Galaxy
+    avi( 4, 20, 235, J,  7, 40,  74, 30, 260), // 18 Airtaxi A21
+    avi( 4, 19, 220, J,  7, 40,  74, 25, 210), // 19 Airtaxi A31
+    avi( 4, 18, 170, J,  7, 40,  74, 20, 160), // 20 Airtaxi A32
+    avi( 4, 26, 210, J,  7, 40,  74, 20, 220), // 21 Airtaxi A33
+    avi( 6, 16, 125, P,  7, 50,  74, 10,  80), // 22 Yate Aerospace YAe46
+    avi( 2, 17, 145, P,  7, 40,  74, 10,  85), // 23 Dinger 100
+    avi(11, 16, 130, P,  7, 40,  74, 10,  75), // 24 AirTaxi A34-1000
+    avi(10, 16, 149, P,  7, 40,  74, 10,  85), // 25 Yate Z-Shuttle
+    avi(15, 17, 170, P,  7, 40,  74, 18,  65), // 26 Kelling K1
+    avi(12, 18, 210, J,  7, 40,  74, 25, 110), // 27 Kelling K6
+    avi(13, 20, 230, J,  7, 40,  74, 60, 180), // 28 Kelling K7
+    avi(14, 21, 220, J,  7, 40,  74, 65, 150), // 29 Darwin 700
+    avi(16, 19, 160, J,  7, 40, 181, 45,  85), // 30 FFP Hyperdart 2
+    avi(17, 24, 248, J, 61, 40,  74, 80, 400), // 31 Dinger 200
+    avi(18, 80, 251, J, 59, 50, 181, 45, 130), // 32 Dinger 1000
+    avi(20, 13,  85, P, 69, 18,  37,  5,  25), // 33 Ploddyphut 100
+    avi(21, 18, 100, P, 70, 20,  37,  9,  60), // 34 Ploddyphut 500
+    avi(22, 25, 140, P,  7, 40,  74, 12,  90), // 35 Flashbang X1
+    avi(23, 32, 220, J, 61, 40,  74, 40, 200), // 36 Juggerplane M1
+    avi(24, 80, 255, J, 59, 50, 181, 30, 100), // 37 Flashbang Wizzer
+    avi( 9, 15,  81, H,  7, 20,  25, 15,  40), // 38 Tricario Helicopter
+    avi(19, 17,  77, H,  7, 20,  40, 20,  55), // 39 Guru X2 Helicopter
+    avi(25, 15,  80, H,  7, 20,  25, 10,  40), // 40 Powernaut Helicopter
+];
+
+/// Road vehicle with the default tractive effort of 0.3 (76/256)
+#[allow(clippy::too_many_arguments)]
+const fn rov(
+    image_index: u8,
+    cost_factor: u8,
+    running_cost: u8,
+    sfx: SoundID,
+    max_speed: u16,
+    capacity: u8,
+    weight: u8,
+    power: u8,
+) -> RoadVehicleInfo {
+    RoadVehicleInfo {
+        image_index,
+        cost_factor,
+        running_cost,
+        running_cost_class: Price::RunningRoadveh,
+        sfx,
+        max_speed,
+        capacity,
+        weight,
+        power,
+        tractive_effort: 76,
+        air_drag: 0,
+        visual_effect: VE_DEFAULT,
+        shorten_factor: 0,
+        roadtype: ROADTYPE_ROAD,
+    }
+}
+
+/// Base set road vehicles (matches C++ _orig_road_vehicle_info)
+///
+/// Arguments: image index, cost factor, running cost, sound effect, max
+/// speed, capacity, weight in 1/4 t and power in 10 hp.
+#[rustfmt::skip]
+pub const ORIG_ROAD_VEHICLE_INFO: [RoadVehicleInfo; 88] = [
+    rov( 0, 120,  91, 23, 112, 31,  42,  9), //  0 MPS Regal Bus
+    rov(17, 140, 128, 26, 176, 35,  60, 12), //  1 Hereford Leopard Bus
+    rov(17, 150, 178, 25, 224, 37,  70, 15), //  2 Foster Bus
+    rov(34, 160, 240, 25, 255, 40, 100, 25), //  3 Foster MkII Superbus
+    rov(51, 120,  91, 60, 112, 30,  42,  9), //  4 Ploddyphut MkI Bus
+    rov(51, 140, 171, 62, 192, 35,  60, 15), //  5 Ploddyphut MkII Bus
+    rov(51, 160, 240, 60, 240, 38,  90, 25), //  6 Ploddyphut MkIII Bus
+    rov( 1, 108,  90, 23,  96, 20,  38, 12), //  7 Balogh Coal Truck
+    rov(18, 128, 168, 23, 176, 25,  48, 22), //  8 Uhl Coal Truck
+    rov(35, 138, 240, 23, 224, 28,  69, 45), //  9 DW Coal Truck
+    rov( 2, 115,  90, 23,  96, 22,  38, 12), // 10 MPS Mail Truck
+    rov(19, 135, 168, 23, 176, 28,  48, 22), // 11 Reynard Mail Truck
+    rov(36, 145, 240, 23, 224, 30,  69, 45), // 12 Perry Mail Truck
+    rov(57, 115,  90, 62,  96, 22,  38, 12), // 13 MightyMover Mail Truck
+    rov(57, 135, 168, 60, 176, 28,  48, 22), // 14 Powernaught Mail Truck
+    rov(57, 145, 240, 62, 224, 30,  69, 45), // 15 Wizzowow Mail Truck
+    rov( 3, 110,  90, 23,  96, 21,  38, 12), // 16 Witcombe Oil Tanker
+    rov(20, 140, 168, 23, 176, 25,  48, 22), // 17 Foster Oil Tanker
+    rov(37, 150, 240, 23, 224, 27,  69, 45), // 18 Perry Oil Tanker
+    rov( 4, 105,  90, 23,  96, 14,  38, 12), // 19 Talbott Livestock Van
+    rov(21, 130, 168, 23, 176, 16,  48, 22), // 20 Uhl Livestock Van
+    rov(38, 140, 240, 23, 224, 18,  69, 45), // 21 Foster Livestock Van
+    rov( 5, 107,  90, 23,  96, 14,  38, 12), // 22 Balogh Goods Truck
+    rov(22, 130, 168, 23, 176, 16,  48, 22), // 23 Craighead Goods Truck
+    rov(39, 140, 240, 23, 224, 18,  69, 45), // 24 Goss Goods Truck
+    rov( 6, 114,  90, 23,  96, 20,  38, 12), // 25 Hereford Grain Truck
+    rov(23, 133, 168, 23, 176, 25,  48, 22), // 26 Thomas Grain Truck
+    rov(40, 143, 240, 23, 224, 30,  69, 45), // 27 Goss Grain Truck
+    rov( 7, 118,  90, 23,  96, 20,  38, 12), // 28 Witcombe Wood Truck
+    rov(24, 137, 168, 23, 176, 22,  48, 22), // 29 Foster Wood Truck
+    rov(41, 147, 240, 23, 224, 24,  69, 45), // 30 Moreland Wood Truck
+    rov( 8, 121,  90, 23,  96, 22,  38, 12), // 31 MPS Iron Ore Truck
+    rov(25, 140, 168, 23, 176, 25,  48, 22), // 32 Uhl Iron Ore Truck
+    rov(42, 150, 240, 23, 224, 27,  69, 45), // 33 Chippy Iron Ore Truck
+    rov( 9, 112,  90, 23,  96, 15,  38, 12), // 34 Balogh Steel Truck
+    rov(26, 135, 168, 23, 176, 18,  48, 22), // 35 Uhl Steel Truck
+    rov(43, 145, 240, 23, 224, 20,  69, 45), // 36 Kelling Steel Truck
+    rov(10, 145,  90, 23,  96, 12,  38, 12), // 37 Balogh Armoured Truck
+    rov(27, 170, 168, 23, 176, 15,  48, 22), // 38 Uhl Armoured Truck
+    rov(44, 180, 240, 23, 224, 16,  69, 45), // 39 Foster Armoured Truck
+    rov(11, 112,  90, 23,  96, 17,  38, 12), // 40 Foster Food Van
+    rov(28, 134, 168, 23, 176, 20,  48, 22), // 41 Perry Food Van
+    rov(45, 144, 240, 23, 224, 22,  69, 45), // 42 Chippy Food Van
+    rov(12, 112,  90, 23,  96, 15,  38, 12), // 43 Uhl Paper Truck
+    rov(29, 135, 168, 23, 176, 18,  48, 22), // 44 Balogh Paper Truck
+    rov(46, 145, 240, 23, 224, 20,  69, 45), // 45 MPS Paper Truck
+    rov(13, 121,  90, 23,  96, 22,  38, 12), // 46 MPS Copper Ore Truck
+    rov(30, 140, 168, 23, 176, 25,  48, 22), // 47 Uhl Copper Ore Truck
+    rov(47, 150, 240, 23, 224, 27,  69, 45), // 48 Goss Copper Ore Truck
+    rov(14, 111,  90, 23,  96, 21,  38, 12), // 49 Uhl Water Tanker
+    rov(31, 141, 168, 23, 176, 25,  48, 22), // 50 Balogh Water Tanker
+    rov(48, 151, 240, 23, 224, 27,  69, 45), // 51 MPS Water Tanker
+    rov(15, 118,  90, 23,  96, 18,  38, 12), // 52 Balogh Fruit Truck
+    rov(32, 148, 168, 23, 176, 20,  48, 22), // 53 Uhl Fruit Truck
+    rov(49, 158, 240, 23, 224, 23,  69, 45), // 54 Kelling Fruit Truck
+    rov(16, 117,  90, 23,  96, 17,  38, 12), // 55 Balogh Rubber Truck
+    rov(33, 147, 168, 23, 176, 19,  48, 22), // 56 Uhl Rubber Truck
+    rov(50, 157, 240, 23, 224, 22,  69, 45), // 57 RMT Rubber Truck
+    rov(52, 117,  90, 63,  96, 17,  38, 12), // 58 MightyMover Sugar Truck
+    rov(52, 147, 168, 64, 176, 19,  48, 22), // 59 Powernaught Sugar Truck
+    rov(52, 157, 240, 63, 224, 22,  69, 45), // 60 Wizzowow Sugar Truck
+    rov(53, 117,  90, 64,  96, 17,  38, 12), // 61 MightyMover Cola Truck
+    rov(53, 147, 168, 63, 176, 19,  48, 22), // 62 Powernaught Cola Truck
+    rov(53, 157, 240, 64, 224, 22,  69, 45), // 63 Wizzowow Cola Truck
+    rov(54, 117,  90, 63,  96, 17,  38, 12), // 64 MightyMover Candyfloss Truck
+    rov(54, 147, 168, 64, 176, 19,  48, 22), // 65 Powernaught Candyfloss Truck
+    rov(54, 157, 240, 63, 224, 22,  69, 45), // 66 Wizzowow Candyfloss Truck
+    rov(55, 117,  90, 64,  96, 17,  38, 12), // 67 MightyMover Toffee Truck
+    rov(55, 147, 168, 63, 176, 19,  48, 22), // 68 Powernaught Toffee Truck
+    rov(55, 157, 240, 64, 224, 22,  69, 45), // 69 Wizzowow Toffee Truck
+    rov(56, 117,  90, 63,  96, 17,  38, 12), // 70 MightyMover Toy Van
+    rov(56, 147, 168, 64, 176, 19,  48, 22), // 71 Powernaught Toy Van
+    rov(56, 157, 240, 63, 224, 22,  69, 45), // 72 Wizzowow Toy Van
+    rov(58, 117,  90, 64,  96, 17,  38, 12), // 73 MightyMover Sweet Truck
+    rov(58, 147, 168, 63, 176, 19,  48, 22), // 74 Powernaught Sweet Truck
+    rov(58, 157, 240, 64, 224, 22,  69, 45), // 75 Wizzowow Sweet Truck
+    rov(59, 117,  90, 63,  96, 17,  38, 12), // 76 MightyMover Battery Truck
+    rov(59, 147, 168, 64, 176, 19,  48, 22), // 77 Powernaught Battery Truck
+    rov(59, 157, 240, 63, 224, 22,  69, 45), // 78 Wizzowow Battery Truck
+    rov(60, 117,  90, 64,  96, 17,  38, 12), // 79 MightyMover Fizzy Drink Truck
+    rov(60, 147, 168, 63, 176, 19,  48, 22), // 80 Powernaught Fizzy Drink Truck
+    rov(60, 157, 240, 64, 224, 22,  69, 45), // 81 Wizzowow Fizzy Drink Truck
+    rov(61, 117,  90, 63,  96, 17,  38, 12), // 82 MightyMover Plastic Truck
+    rov(61, 147, 168, 64, 176, 19,  48, 22), // 83 Powernaught Plastic Truck
+    rov(61, 157, 240, 63, 224, 22,  69, 45), // 84 Wizzowow Plastic Truck
+    rov(62, 117,  90, 64,  96, 17,  38, 12), // 85 MightyMover Bubble Truck
+    rov(62, 147, 168, 63, 176, 19,  48, 22), // 86 Powernaught Bubble Truck
+    rov(62, 157, 240, 64, 224, 22,  69, 45), // 87 Wizzowow Bubble Truck
+];
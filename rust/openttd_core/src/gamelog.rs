@@ -0,0 +1,471 @@
+//! Gamelog data structures for OpenTTD
+//!
+//! The gamelog records events that can explain odd behaviour of a game:
+//! loads in other revisions, NewGRF changes, cheats, settings changed in
+//! game and emergency saves. It is saved in the GLOG chunk.
+
+use crate::error::CoreError;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use std::collections::HashMap;
+
+/// Type of a logged action (matches C++ GamelogActionType)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum GamelogActionType {
+    Start = 0,
+    Load = 1,
+    Grf = 2,
+    Cheat = 3,
+    Setting = 4,
+    GrfBug = 5,
+    Emergency = 6,
+}
+
+impl GamelogActionType {
+    /// Description printed in the action header (matches C++ la_text)
+    pub fn text(&self) -> &'static str {
+        match self {
+            GamelogActionType::Start => "new game started",
+            GamelogActionType::Load => "game loaded",
+            GamelogActionType::Grf => "GRF config changed",
+            GamelogActionType::Cheat => "cheat was used",
+            GamelogActionType::Setting => "settings changed",
+            GamelogActionType::GrfBug => "GRF bug triggered",
+            GamelogActionType::Emergency => "emergency savegame",
+        }
+    }
+}
+
+impl TryFrom<u8> for GamelogActionType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(GamelogActionType::Start),
+            1 => Ok(GamelogActionType::Load),
+            2 => Ok(GamelogActionType::Grf),
+            3 => Ok(GamelogActionType::Cheat),
+            4 => Ok(GamelogActionType::Setting),
+            5 => Ok(GamelogActionType::GrfBug),
+            6 => Ok(GamelogActionType::Emergency),
+            _ => Err(CoreError::InvalidData(format!(
+                "Invalid gamelog action type {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Type of a logged change (matches C++ GamelogChangeType)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum GamelogChangeType {
+    Mode = 0,
+    Revision = 1,
+    OldVersion = 2,
+    Setting = 3,
+    GrfAdd = 4,
+    GrfRemove = 5,
+    GrfCompat = 6,
+    GrfParameter = 7,
+    GrfMove = 8,
+    GrfBug = 9,
+    Emergency = 10,
+}
+
+impl TryFrom<u8> for GamelogChangeType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(GamelogChangeType::Mode),
+            1 => Ok(GamelogChangeType::Revision),
+            2 => Ok(GamelogChangeType::OldVersion),
+            3 => Ok(GamelogChangeType::Setting),
+            4 => Ok(GamelogChangeType::GrfAdd),
+            5 => Ok(GamelogChangeType::GrfRemove),
+            6 => Ok(GamelogChangeType::GrfCompat),
+            7 => Ok(GamelogChangeType::GrfParameter),
+            8 => Ok(GamelogChangeType::GrfMove),
+            9 => Ok(GamelogChangeType::GrfBug),
+            10 => Ok(GamelogChangeType::Emergency),
+            _ => Err(CoreError::InvalidData(format!(
+                "Invalid gamelog change type {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Savegame types converted by the old loader (matches C++ SavegameType)
+pub const SGT_TTD: u32 = 0;
+pub const SGT_TTDP1: u32 = 1;
+pub const SGT_TTDP2: u32 = 2;
+pub const SGT_OTTD: u32 = 3;
+pub const SGT_TTO: u32 = 4;
+
+/// A single logged change (matches the C++ LoggedChange subclasses)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GamelogChange {
+    /// Scenario editor or game, and the landscape
+    Mode {
+        mode: u8,
+        landscape: u8,
+    },
+    /// The game was loaded in a different revision
+    Revision {
+        text: String,
+        newgrf: u32,
+        slver: u16,
+        /// 0 = not modified, 1 = maybe modified, 2 = modified
+        modified: u8,
+    },
+    /// Loaded from a savegame without a gamelog
+    OldVersion {
+        savegame_type: u32,
+        version: u32,
+    },
+    /// A setting that is not synchronised over the network changed
+    Setting {
+        name: String,
+        old_value: i32,
+        new_value: i32,
+    },
+    GrfAdd {
+        grfid: u32,
+        md5sum: [u8; 16],
+    },
+    GrfRemove {
+        grfid: u32,
+    },
+    /// Another version of the same NewGRF was loaded
+    GrfCompat {
+        grfid: u32,
+        md5sum: [u8; 16],
+    },
+    GrfParameter {
+        grfid: u32,
+    },
+    GrfMove {
+        grfid: u32,
+        offset: i32,
+    },
+    GrfBug {
+        data: u64,
+        grfid: u32,
+        bug: u8,
+    },
+    Emergency,
+}
+
+impl GamelogChange {
+    pub fn change_type(&self) -> GamelogChangeType {
+        match self {
+            GamelogChange::Mode { .. } => GamelogChangeType::Mode,
+            GamelogChange::Revision { .. } => GamelogChangeType::Revision,
+            GamelogChange::OldVersion { .. } => GamelogChangeType::OldVersion,
+            GamelogChange::Setting { .. } => GamelogChangeType::Setting,
+            GamelogChange::GrfAdd { .. } => GamelogChangeType::GrfAdd,
+            GamelogChange::GrfRemove { .. } => GamelogChangeType::GrfRemove,
+            GamelogChange::GrfCompat { .. } => GamelogChangeType::GrfCompat,
+            GamelogChange::GrfParameter { .. } => GamelogChangeType::GrfParameter,
+            GamelogChange::GrfMove { .. } => GamelogChangeType::GrfMove,
+            GamelogChange::GrfBug { .. } => GamelogChangeType::GrfBug,
+            GamelogChange::Emergency => GamelogChangeType::Emergency,
+        }
+    }
+}
+
+/// An action with all changes logged during it (matches C++ LoggedAction)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamelogAction {
+    pub action_type: GamelogActionType,
+    pub tick: u64,
+    pub changes: Vec<GamelogChange>,
+}
+
+/// GRF ID as printed by the game, which keeps it in file byte order
+fn grf_id(grfid: u32) -> String {
+    format!("{:08X}", grfid.swap_bytes())
+}
+
+/// GRF ID and checksum (matches C++ AddGrfInfo without NewGRF files to search,
+/// so every GRF is reported as unknown)
+fn grf_info(grfid: u32, md5sum: Option<&[u8; 16]>) -> String {
+    match md5sum {
+        Some(md5sum) => {
+            let md5sum: String = md5sum.iter().map(|b| format!("{:02X}", b)).collect();
+            format!("GRF ID {}, checksum {}, unknown GRF", grf_id(grfid), md5sum)
+        }
+        None => format!("GRF ID {}, unknown GRF", grf_id(grfid)),
+    }
+}
+
+fn format_old_version(savegame_type: u32, version: u32) -> String {
+    let mut text = String::from("Conversion from ");
+    match savegame_type {
+        SGT_OTTD => {
+            text += &format!(
+                "OTTD savegame without gamelog: version {}, {}",
+                (version >> 8) & 0xFFFF,
+                version & 0xFF
+            )
+        }
+        SGT_TTO => text += "TTO savegame",
+        SGT_TTD => text += "TTD savegame",
+        SGT_TTDP1 | SGT_TTDP2 => {
+            let format = if savegame_type == SGT_TTDP1 {
+                "old"
+            } else {
+                "new"
+            };
+            text += &format!("TTDP savegame, {} format", format);
+            if version != 0 {
+                text += &format!(
+                    ", TTDP version {}.{}.{}.{}",
+                    version >> 24,
+                    (version >> 20) & 0x0F,
+                    (version >> 16) & 0x0F,
+                    version & 0xFFFF
+                );
+            }
+        }
+        _ => text += &format!("unknown savegame type {}", savegame_type),
+    }
+    text
+}
+
+/// Print the gamelog like the C++ `gamelog` console command
+///
+/// The GRF IDs seen so far are tracked to report the same inconsistencies;
+/// the value records whether a GRF went missing on load rather than being removed.
+pub fn print_gamelog(actions: &[GamelogAction]) -> Vec<String> {
+    const NEVER_ADDED: &str = ". Gamelog inconsistency: GrfID was never added!";
+    let mut grfs: HashMap<u32, bool> = HashMap::new();
+    let mut lines = vec!["---- gamelog start ----".to_string()];
+
+    for action in actions {
+        lines.push(format!(
+            "Tick {}: {}",
+            action.tick,
+            action.action_type.text()
+        ));
+        for change in &action.changes {
+            let line = match change {
+                GamelogChange::Mode { mode, landscape } => {
+                    format!("New game mode: {} landscape: {}", mode, landscape)
+                }
+                GamelogChange::Revision {
+                    text,
+                    newgrf,
+                    slver,
+                    modified,
+                } => {
+                    let modified = match modified {
+                        0 => "not ",
+                        1 => "maybe ",
+                        _ => "",
+                    };
+                    format!(
+                        "Revision text changed to {}, savegame version {}, {}modified, _openttd_newgrf_version = 0x{:08x}",
+                        text, slver, modified, newgrf
+                    )
+                }
+                GamelogChange::OldVersion {
+                    savegame_type,
+                    version,
+                } => format_old_version(*savegame_type, *version),
+                GamelogChange::Setting {
+                    name,
+                    old_value,
+                    new_value,
+                } => format!("Setting changed: {} : {} -> {}", name, old_value, new_value),
+                GamelogChange::GrfAdd { grfid, md5sum } => {
+                    let mut line = format!("Added NewGRF: {}", grf_info(*grfid, Some(md5sum)));
+                    if grfs.get(grfid) == Some(&false) {
+                        line += ". Gamelog inconsistency: GrfID was already added!";
+                    }
+                    grfs.insert(*grfid, false);
+                    line
+                }
+                GamelogChange::GrfRemove { grfid } => {
+                    let verb = if action.action_type == GamelogActionType::Load {
+                        "Missing"
+                    } else {
+                        "Removed"
+                    };
+                    let mut line = format!("{} NewGRF: {}", verb, grf_info(*grfid, None));
+                    if !grfs.contains_key(grfid) {
+                        line += NEVER_ADDED;
+                    } else if action.action_type == GamelogActionType::Load {
+                        // Missing GRFs on load are not removed from the configuration
+                        grfs.insert(*grfid, true);
+                    } else {
+                        grfs.remove(grfid);
+                    }
+                    line
+                }
+                GamelogChange::GrfCompat { grfid, md5sum } => {
+                    let mut line = format!(
+                        "Compatible NewGRF loaded: {}",
+                        grf_info(*grfid, Some(md5sum))
+                    );
+                    if !grfs.contains_key(grfid) {
+                        line += NEVER_ADDED;
+                    }
+                    grfs.insert(*grfid, false);
+                    line
+                }
+                GamelogChange::GrfParameter { grfid } => {
+                    let mut line = format!("GRF parameter changed: {}", grf_info(*grfid, None));
+                    if !grfs.contains_key(grfid) {
+                        line += NEVER_ADDED;
+                    }
+                    line
+                }
+                GamelogChange::GrfMove { grfid, offset } => {
+                    let mut line = format!(
+                        "GRF order changed: {} moved {} places {}{}",
+                        grf_id(*grfid),
+                        offset.unsigned_abs(),
+                        if *offset >= 0 { "down" } else { "up" },
+                        grf_info(*grfid, None)
+                    );
+                    if !grfs.contains_key(grfid) {
+                        line += NEVER_ADDED;
+                    }
+                    line
+                }
+                GamelogChange::GrfBug { data, grfid, .. } => {
+                    let mut line = format!(
+                        "Rail vehicle changes length outside a depot: GRF ID {}, internal ID 0x{:X}{}",
+                        grf_id(*grfid),
+                        data,
+                        grf_info(*grfid, None)
+                    );
+                    if !grfs.contains_key(grfid) {
+                        line += NEVER_ADDED;
+                    }
+                    line
+                }
+                // The action header already says it was an emergency save
+                GamelogChange::Emergency => String::new(),
+            };
+            lines.push(line);
+        }
+    }
+
+    lines.push("---- gamelog end ----".to_string());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(action_type: GamelogActionType, changes: Vec<GamelogChange>) -> GamelogAction {
+        GamelogAction {
+            action_type,
+            tick: 42,
+            changes,
+        }
+    }
+
+    #[test]
+    fn test_print_gamelog() {
+        let actions = vec![
+            action(
+                GamelogActionType::Start,
+                vec![
+                    GamelogChange::Revision {
+                        text: "14.1".into(),
+                        newgrf: 0x1E01_0000,
+                        slver: 308,
+                        modified: 0,
+                    },
+                    GamelogChange::Mode {
+                        mode: 1,
+                        landscape: 2,
+                    },
+                    GamelogChange::GrfAdd {
+                        grfid: 0x0403_2A4D,
+                        md5sum: [0xAB; 16],
+                    },
+                ],
+            ),
+            action(
+                GamelogActionType::Load,
+                vec![
+                    GamelogChange::GrfRemove { grfid: 0x0403_2A4D },
+                    GamelogChange::GrfParameter { grfid: 0x0102_0304 },
+                ],
+            ),
+            action(
+                GamelogActionType::Grf,
+                vec![GamelogChange::GrfMove {
+                    grfid: 0x0403_2A4D,
+                    offset: -2,
+                }],
+            ),
+            action(
+                GamelogActionType::Setting,
+                vec![GamelogChange::Setting {
+                    name: "vehicle.road_side".into(),
+                    old_value: 0,
+                    new_value: 1,
+                }],
+            ),
+        ];
+
+        let md5sum = "AB".repeat(16);
+        assert_eq!(
+            print_gamelog(&actions),
+            [
+                "---- gamelog start ----".to_string(),
+                "Tick 42: new game started".into(),
+                "Revision text changed to 14.1, savegame version 308, not modified, _openttd_newgrf_version = 0x1e010000".into(),
+                "New game mode: 1 landscape: 2".into(),
+                format!("Added NewGRF: GRF ID 4D2A0304, checksum {}, unknown GRF", md5sum),
+                "Tick 42: game loaded".into(),
+                "Missing NewGRF: GRF ID 4D2A0304, unknown GRF".into(),
+                "GRF parameter changed: GRF ID 04030201, unknown GRF. Gamelog inconsistency: GrfID was never added!".into(),
+                "Tick 42: GRF config changed".into(),
+                "GRF order changed: 4D2A0304 moved 2 places upGRF ID 4D2A0304, unknown GRF".into(),
+                "Tick 42: settings changed".into(),
+                "Setting changed: vehicle.road_side : 0 -> 1".into(),
+                "---- gamelog end ----".into(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_old_version() {
+        assert_eq!(
+            format_old_version(SGT_OTTD, (18 << 8) | 3),
+            "Conversion from OTTD savegame without gamelog: version 18, 3"
+        );
+        assert_eq!(
+            format_old_version(SGT_TTDP2, 0x0271_0123),
+            "Conversion from TTDP savegame, new format, TTDP version 2.7.1.291"
+        );
+        assert_eq!(
+            format_old_version(SGT_TTO, 0),
+            "Conversion from TTO savegame"
+        );
+    }
+
+    #[test]
+    fn test_invalid_types() {
+        assert!(GamelogActionType::try_from(7).is_err());
+        assert!(GamelogChangeType::try_from(11).is_err());
+        assert_eq!(
+            GamelogChangeType::try_from(8).unwrap(),
+            GamelogChange::GrfMove {
+                grfid: 0,
+                offset: 0
+            }
+            .change_type()
+        );
+    }
+}
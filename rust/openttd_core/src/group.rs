@@ -0,0 +1,1220 @@
+//! Vehicle groups
+//!
+//! Companies sort their vehicles into groups, which can be nested. Every
+//! group keeps statistics on its vehicles, as do the DEFAULT group of
+//! ungrouped vehicles and the ALL group of each company and vehicle type.
+//! The pool also holds the autoreplace rules of the companies, as a rule
+//! can be limited to a group and its sub-groups.
+
+use crate::company::{Company, Livery, LiveryScheme};
+use crate::engine::EnginePool;
+use crate::error::CoreError;
+use crate::types::{Colours, EngineID, GroupID, Money, Owner, VehicleID};
+use crate::vehicle::{Vehicle, VehicleType, VEHICLE_PROFIT_MIN_AGE};
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Maximum length of a group name in characters, including the terminator
+/// (matches C++ MAX_LENGTH_GROUP_NAME_CHARS)
+pub const MAX_LENGTH_GROUP_NAME_CHARS: usize = 32;
+
+/// Livery::in_use bit of a custom primary colour
+const LIVERY_PRIMARY: u8 = 1 << 0;
+/// Livery::in_use bit of a custom secondary colour
+const LIVERY_SECONDARY: u8 = 1 << 1;
+
+bitflags! {
+    /// Configuration flags of a group (matches C++ GroupFlags)
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct GroupFlags: u8 {
+        /// Autoreplace rules of the ALL group do not apply
+        const REPLACE_PROTECTION = 1 << 0;
+        /// Autoreplace removes wagons to keep the train length
+        const REPLACE_WAGON_REMOVAL = 1 << 1;
+    }
+}
+
+/// Statistics on the vehicles in a group (matches C++ GroupStatistics)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupStatistics {
+    /// Profit of last year of all vehicles
+    pub profit_last_year: Money,
+    /// Profit of last year of the vehicles old enough for profit statistics
+    pub profit_last_year_min_age: Money,
+    /// Number of engines per engine type
+    pub num_engines: BTreeMap<EngineID, u16>,
+    pub num_vehicle: u16,
+    /// Number of vehicles old enough for profit statistics
+    pub num_vehicle_min_age: u16,
+    /// Sum of the trip occupancies of all vehicles
+    pub trip_occupancy: i64,
+    /// Whether any autoreplace rule is set for the group
+    pub autoreplace_defined: bool,
+    /// Whether all vehicles the rules apply to have been replaced
+    pub autoreplace_finished: bool,
+}
+
+impl GroupStatistics {
+    pub fn clear(&mut self) {
+        self.num_vehicle = 0;
+        self.trip_occupancy = 0;
+        self.clear_profits();
+        // The engines may have changed, e.g. with the NewGRFs
+        self.num_engines.clear();
+    }
+
+    pub fn clear_profits(&mut self) {
+        self.profit_last_year = 0;
+        self.num_vehicle_min_age = 0;
+        self.profit_last_year_min_age = 0;
+    }
+
+    pub fn clear_autoreplace(&mut self) {
+        self.autoreplace_defined = false;
+        self.autoreplace_finished = false;
+    }
+
+    pub fn num_engines(&self, engine: EngineID) -> u16 {
+        self.num_engines.get(&engine).copied().unwrap_or(0)
+    }
+}
+
+/// A group of vehicles (matches C++ Group)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    pub index: GroupID,
+    /// Custom name, empty if none
+    pub name: String,
+    pub owner: Owner,
+    pub vehicle_type: VehicleType,
+    pub flags: GroupFlags,
+    pub livery: Livery,
+    pub parent: GroupID,
+    /// Per-company group number
+    pub number: u16,
+    /// Statistics on the vehicles in the group (not saved)
+    #[serde(skip)]
+    pub statistics: GroupStatistics,
+    /// Sub-groups (not saved)
+    #[serde(skip)]
+    pub children: BTreeSet<GroupID>,
+    /// Whether the group is folded in the group list (not saved)
+    #[serde(skip)]
+    pub folded: bool,
+}
+
+impl Group {
+    pub fn new(index: GroupID, owner: Owner, vehicle_type: VehicleType) -> Self {
+        Self {
+            index,
+            name: String::new(),
+            owner,
+            vehicle_type,
+            flags: GroupFlags::empty(),
+            livery: Livery::default(),
+            parent: GroupID::INVALID,
+            number: 0,
+            statistics: GroupStatistics::default(),
+            children: BTreeSet::new(),
+            folded: false,
+        }
+    }
+}
+
+/// An autoreplace rule (matches C++ EngineRenew)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineRenew {
+    pub from: EngineID,
+    pub to: EngineID,
+    /// Group the rule applies to, together with its sub-groups
+    pub group_id: GroupID,
+    /// Only replace vehicles that are old
+    pub replace_when_old: bool,
+}
+
+/// Statistics of the pseudo groups and the autoreplace rules of a company,
+/// which C++ keeps in the company
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CompanyGroups {
+    /// ALL group per vehicle type
+    all: [GroupStatistics; 4],
+    /// DEFAULT group per vehicle type
+    default: [GroupStatistics; 4],
+    /// Autoreplace rules, most recently added first
+    engine_renew: Vec<EngineRenew>,
+}
+
+/// Index of a company vehicle type in per-type arrays
+fn type_index(type_: VehicleType) -> Option<usize> {
+    match type_ {
+        VehicleType::Train | VehicleType::Road | VehicleType::Ship | VehicleType::Aircraft => {
+            Some(type_ as usize)
+        }
+        _ => None,
+    }
+}
+
+fn position(vehicles: &[Vehicle], id: VehicleID) -> Option<usize> {
+    vehicles.iter().position(|v| v.index == id)
+}
+
+/// Positions of a vehicle and the vehicles following it in its chain
+fn chain_positions(vehicles: &[Vehicle], first: usize) -> Vec<usize> {
+    let mut chain = vec![first];
+    while let Some(next) = vehicles[*chain.last().unwrap()]
+        .next
+        .and_then(|id| position(vehicles, id))
+    {
+        if chain.len() >= vehicles.len() {
+            break;
+        }
+        chain.push(next);
+    }
+    chain
+}
+
+/// All groups of a game (matches C++ GroupPool)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupPool {
+    groups: Vec<Option<Group>>,
+    companies: BTreeMap<u8, CompanyGroups>,
+}
+
+impl GroupPool {
+    /// Build a pool from groups at their own indices and link the sub-groups
+    pub fn from_groups(groups: Vec<Group>) -> Result<Self, CoreError> {
+        let mut pool = Self::default();
+        for group in groups {
+            let index = group.index.0 as usize;
+            if !group.index.is_valid() {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid group index {}",
+                    index
+                )));
+            }
+            if index >= pool.groups.len() {
+                pool.groups.resize(index + 1, None);
+            }
+            if pool.groups[index].replace(group).is_some() {
+                return Err(CoreError::InvalidData(format!("Duplicate group {}", index)));
+            }
+        }
+        pool.update_children();
+        Ok(pool)
+    }
+
+    pub fn get(&self, id: GroupID) -> Option<&Group> {
+        self.groups.get(id.0 as usize)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: GroupID) -> Option<&mut Group> {
+        self.groups.get_mut(id.0 as usize)?.as_mut()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Group> {
+        self.groups.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.iter().all(Option::is_none)
+    }
+
+    /// Rebuild the sub-group lists from the parents, dropping parents that
+    /// are missing or belong to another company or vehicle type
+    /// (matches C++ UpdateGroupChildren)
+    pub fn update_children(&mut self) {
+        for group in self.groups.iter_mut().flatten() {
+            group.children.clear();
+        }
+        for index in 0..self.groups.len() {
+            let Some(group) = &self.groups[index] else {
+                continue;
+            };
+            if group.parent == GroupID::INVALID {
+                continue;
+            }
+            let (id, parent, owner, vehicle_type) =
+                (group.index, group.parent, group.owner, group.vehicle_type);
+            match self.get_mut(parent) {
+                Some(pg) if pg.owner == owner && pg.vehicle_type == vehicle_type => {
+                    pg.children.insert(id);
+                }
+                // Keep groups left with an invalid parent by an old bug
+                _ => self.groups[index].as_mut().unwrap().parent = GroupID::INVALID,
+            }
+        }
+    }
+
+    /// Whether `search` is `group` or one of its sub-groups
+    /// (matches C++ GroupIsInGroup)
+    pub fn group_is_in_group(&self, search: GroupID, group: GroupID) -> bool {
+        if self.get(search).is_none() {
+            return search == group;
+        }
+        let mut search = search;
+        // A parent chain cannot be longer than the number of groups
+        for _ in 0..=self.groups.len() {
+            if search == group {
+                return true;
+            }
+            match self.get(search) {
+                Some(g) if g.parent != GroupID::INVALID => search = g.parent,
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    /// Statistics of a group or of the DEFAULT or ALL group of a company
+    pub fn statistics(
+        &self,
+        company: Owner,
+        id_g: GroupID,
+        type_: VehicleType,
+    ) -> Option<&GroupStatistics> {
+        if id_g.is_valid() {
+            return self.get(id_g).map(|g| &g.statistics);
+        }
+        let groups = self.companies.get(&company.company_id()?)?;
+        let t = type_index(type_)?;
+        match id_g {
+            GroupID::DEFAULT => Some(&groups.default[t]),
+            GroupID::ALL => Some(&groups.all[t]),
+            _ => None,
+        }
+    }
+
+    fn statistics_mut(
+        &mut self,
+        company: Owner,
+        id_g: GroupID,
+        type_: VehicleType,
+    ) -> Option<&mut GroupStatistics> {
+        if id_g.is_valid() {
+            return self.get_mut(id_g).map(|g| &mut g.statistics);
+        }
+        let t = type_index(type_)?;
+        let groups = self.companies.entry(company.company_id()?).or_default();
+        match id_g {
+            GroupID::DEFAULT => Some(&mut groups.default[t]),
+            GroupID::ALL => Some(&mut groups.all[t]),
+            _ => None,
+        }
+    }
+
+    /// Apply `f` to the statistics of a vehicle's group and of its ALL group
+    fn update_statistics(&mut self, v: &Vehicle, f: impl Fn(&mut GroupStatistics)) {
+        if let Some(stats) = self.statistics_mut(v.owner, GroupID::ALL, v.type_) {
+            f(stats);
+        }
+        if let Some(stats) = self.statistics_mut(v.owner, v.group_id, v.type_) {
+            f(stats);
+        }
+    }
+
+    /// Add (+1) or remove (-1) a primary vehicle from its group's statistics
+    /// (matches C++ GroupStatistics::CountVehicle)
+    pub fn count_vehicle(&mut self, v: &Vehicle, delta: i32) {
+        let profit = v.display_profit_last_year() * delta as Money;
+        let old_enough = v.economy_age.0 > VEHICLE_PROFIT_MIN_AGE;
+        let occupancy = v.trip_occupancy as i64 * delta as i64;
+        self.update_statistics(v, |stats| {
+            stats.num_vehicle = (stats.num_vehicle as i32 + delta) as u16;
+            stats.profit_last_year += profit;
+            stats.trip_occupancy += occupancy;
+            if old_enough {
+                stats.num_vehicle_min_age = (stats.num_vehicle_min_age as i32 + delta) as u16;
+                stats.profit_last_year_min_age += profit;
+            }
+        });
+    }
+
+    /// Add (+1) or remove (-1) an engine from its group's engine counts
+    /// (matches C++ GroupStatistics::CountEngine)
+    pub fn count_engine(&mut self, v: &Vehicle, delta: i32) {
+        let engine = v.engine_type;
+        self.update_statistics(v, |stats| {
+            let count = stats.num_engines.entry(engine).or_default();
+            *count = (*count as i32 + delta) as u16;
+        });
+    }
+
+    /// Add the profit of a vehicle to its group after the yearly reset
+    /// (matches C++ GroupStatistics::AddProfitLastYear)
+    pub fn add_profit_last_year(&mut self, v: &Vehicle) {
+        let profit = v.display_profit_last_year();
+        self.update_statistics(v, |stats| stats.profit_last_year += profit);
+    }
+
+    /// Start counting the profit of a vehicle that became old enough
+    /// (matches C++ GroupStatistics::VehicleReachedMinAge)
+    pub fn vehicle_reached_min_age(&mut self, v: &Vehicle) {
+        let profit = v.display_profit_last_year();
+        self.update_statistics(v, |stats| {
+            stats.num_vehicle_min_age += 1;
+            stats.profit_last_year_min_age += profit;
+        });
+    }
+
+    /// Set the trip occupancy of a primary vehicle, keeping its group's
+    /// occupancy up to date
+    pub fn set_trip_occupancy(&mut self, v: &mut Vehicle, occupancy: i8) {
+        let delta = (occupancy as i64 - v.trip_occupancy as i64) * v.is_primary_vehicle() as i64;
+        v.trip_occupancy = occupancy;
+        self.update_statistics(v, |stats| stats.trip_occupancy += delta);
+    }
+
+    /// Recompute all statistics, e.g. after loading a game
+    /// (matches C++ GroupStatistics::UpdateAfterLoad)
+    pub fn update_after_load(&mut self, vehicles: &[Vehicle], engines: &EnginePool) {
+        for groups in self.companies.values_mut() {
+            groups.all.iter_mut().for_each(GroupStatistics::clear);
+            groups.default.iter_mut().for_each(GroupStatistics::clear);
+        }
+        for group in self.groups.iter_mut().flatten() {
+            group.statistics.clear();
+        }
+
+        for v in vehicles.iter().filter(|v| v.is_engine_countable()) {
+            self.count_engine(v, 1);
+            if v.is_primary_vehicle() {
+                self.count_vehicle(v, 1);
+            }
+        }
+
+        let companies: BTreeSet<u8> = vehicles
+            .iter()
+            .filter_map(|v| v.owner.company_id())
+            .chain(self.companies.keys().copied())
+            .collect();
+        for company in companies {
+            self.update_autoreplace(Owner::from_company_id(company), engines);
+        }
+    }
+
+    /// Recompute the profits of all groups, e.g. at the start of a year
+    /// (matches C++ GroupStatistics::UpdateProfits)
+    pub fn update_profits(&mut self, vehicles: &[Vehicle]) {
+        for groups in self.companies.values_mut() {
+            groups
+                .all
+                .iter_mut()
+                .for_each(GroupStatistics::clear_profits);
+            groups
+                .default
+                .iter_mut()
+                .for_each(GroupStatistics::clear_profits);
+        }
+        for group in self.groups.iter_mut().flatten() {
+            group.statistics.clear_profits();
+        }
+
+        for v in vehicles.iter().filter(|v| v.is_primary_vehicle()) {
+            self.add_profit_last_year(v);
+            if v.economy_age.0 > VEHICLE_PROFIT_MIN_AGE {
+                self.vehicle_reached_min_age(v);
+            }
+        }
+    }
+
+    /// Recompute whether autoreplace rules are set for the groups of a
+    /// company and whether they are done
+    /// (matches C++ GroupStatistics::UpdateAutoreplace)
+    pub fn update_autoreplace(&mut self, company: Owner, engines: &EnginePool) {
+        let Some(c) = company.company_id() else {
+            return;
+        };
+        let groups = self.companies.entry(c).or_default();
+        groups
+            .all
+            .iter_mut()
+            .for_each(GroupStatistics::clear_autoreplace);
+        groups
+            .default
+            .iter_mut()
+            .for_each(GroupStatistics::clear_autoreplace);
+        let rules = groups.engine_renew.clone();
+        for group in self.groups.iter_mut().flatten() {
+            if group.owner == company {
+                group.statistics.clear_autoreplace();
+            }
+        }
+
+        for rule in rules {
+            let Some(engine) = engines.get(rule.from) else {
+                continue;
+            };
+            let in_use = self.num_engines(company, rule.group_id, rule.from, engine.type_) > 0;
+            if let Some(stats) = self.statistics_mut(company, rule.group_id, engine.type_) {
+                if !stats.autoreplace_defined {
+                    stats.autoreplace_defined = true;
+                    stats.autoreplace_finished = true;
+                }
+                if in_use {
+                    stats.autoreplace_finished = false;
+                }
+            }
+        }
+    }
+
+    /// Sum `value` over a group and its sub-groups
+    fn sum_recursive<T: std::iter::Sum<T> + std::ops::Add<Output = T> + Default>(
+        &self,
+        company: Owner,
+        id_g: GroupID,
+        type_: VehicleType,
+        value: &impl Fn(&GroupStatistics) -> T,
+    ) -> T {
+        let children: T = self
+            .get(id_g)
+            .map(|g| {
+                g.children
+                    .iter()
+                    .map(|&child| self.sum_recursive(company, child, type_, value))
+                    .sum()
+            })
+            .unwrap_or_default();
+        children
+            + self
+                .statistics(company, id_g, type_)
+                .map(value)
+                .unwrap_or_default()
+    }
+
+    /// Number of engines of a type in a group and its sub-groups
+    /// (matches C++ GetGroupNumEngines)
+    pub fn num_engines(
+        &self,
+        company: Owner,
+        id_g: GroupID,
+        engine: EngineID,
+        type_: VehicleType,
+    ) -> u32 {
+        self.sum_recursive(company, id_g, type_, &|s| s.num_engines(engine) as u32)
+    }
+
+    /// Number of vehicles in a group and its sub-groups
+    /// (matches C++ GetGroupNumVehicle)
+    pub fn num_vehicle(&self, company: Owner, id_g: GroupID, type_: VehicleType) -> u32 {
+        self.sum_recursive(company, id_g, type_, &|s| s.num_vehicle as u32)
+    }
+
+    /// Number of vehicles old enough for profit statistics in a group and
+    /// its sub-groups (matches C++ GetGroupNumVehicleMinAge)
+    pub fn num_vehicle_min_age(&self, company: Owner, id_g: GroupID, type_: VehicleType) -> u32 {
+        self.sum_recursive(company, id_g, type_, &|s| s.num_vehicle_min_age as u32)
+    }
+
+    /// Profit of last year of the vehicles old enough for profit statistics
+    /// in a group and its sub-groups (matches C++ GetGroupProfitLastYearMinAge)
+    pub fn profit_last_year_min_age(
+        &self,
+        company: Owner,
+        id_g: GroupID,
+        type_: VehicleType,
+    ) -> Money {
+        self.sum_recursive(company, id_g, type_, &|s| s.profit_last_year_min_age)
+    }
+
+    /// Average trip occupancy in percent of the vehicles in a group and its
+    /// sub-groups, None if there are no vehicles
+    pub fn occupancy(&self, company: Owner, id_g: GroupID, type_: VehicleType) -> Option<u8> {
+        let count = self.num_vehicle(company, id_g, type_);
+        let sum = self.sum_recursive(company, id_g, type_, &|s| s.trip_occupancy);
+        (count > 0).then(|| (sum / count as i64) as u8)
+    }
+
+    /// Livery a group inherits: its parent's or the company's default
+    fn parent_livery(&self, group: &Group, company_livery: &Livery) -> Livery {
+        self.get(group.parent)
+            .map(|pg| pg.livery)
+            .unwrap_or(*company_livery)
+    }
+
+    /// Pass the colours of a group on to the sub-groups without their own
+    fn propagate_child_livery(&mut self, id: GroupID) {
+        let Some(group) = self.get(id) else {
+            return;
+        };
+        let (livery, children) = (group.livery, group.children.clone());
+        for child in children {
+            if let Some(cg) = self.get_mut(child) {
+                if cg.livery.in_use & LIVERY_PRIMARY == 0 {
+                    cg.livery.colour1 = livery.colour1;
+                }
+                if cg.livery.in_use & LIVERY_SECONDARY == 0 {
+                    cg.livery.colour2 = livery.colour2;
+                }
+            }
+            self.propagate_child_livery(child);
+        }
+    }
+
+    /// Pass a changed default livery of a company on to its groups without
+    /// their own colours (matches C++ UpdateCompanyGroupLiveries)
+    pub fn update_company_group_liveries(&mut self, company: &Company) {
+        let owner = Owner::from_company_id(company.index);
+        let livery = company.livery[LiveryScheme::Default as usize];
+        let top: Vec<GroupID> = self
+            .iter()
+            .filter(|g| g.owner == owner && g.parent == GroupID::INVALID)
+            .map(|g| g.index)
+            .collect();
+        for id in top {
+            let group = self.get_mut(id).unwrap();
+            if group.livery.in_use & LIVERY_PRIMARY == 0 {
+                group.livery.colour1 = livery.colour1;
+            }
+            if group.livery.in_use & LIVERY_SECONDARY == 0 {
+                group.livery.colour2 = livery.colour2;
+            }
+            self.propagate_child_livery(id);
+        }
+    }
+
+    /// Create a group for `company`, optionally as a sub-group
+    /// (matches C++ CmdCreateGroup)
+    pub fn create(
+        &mut self,
+        company: &Company,
+        vehicle_type: VehicleType,
+        parent: GroupID,
+    ) -> Result<GroupID, CoreError> {
+        let owner = Owner::from_company_id(company.index);
+        if type_index(vehicle_type).is_none() {
+            return Err(CoreError::InvalidData(format!(
+                "Cannot group vehicles of type {:?}",
+                vehicle_type
+            )));
+        }
+        let pg = self.get(parent);
+        if let Some(pg) = pg {
+            if pg.owner != owner || pg.vehicle_type != vehicle_type {
+                return Err(CoreError::InvalidData(format!(
+                    "Group {} cannot be the parent of a new group",
+                    parent.0
+                )));
+            }
+        }
+
+        let Some(index) =
+            (0..GroupID::MAX_GROUPS).find(|&i| self.groups.get(i).is_none_or(Option::is_none))
+        else {
+            return Err(CoreError::InvalidData("Too many groups".into()));
+        };
+        let id = GroupID(index as u16);
+        let mut group = Group::new(id, owner, vehicle_type);
+
+        // The lowest number not used by the company's groups
+        let used: BTreeSet<u16> = self
+            .iter()
+            .filter(|g| g.owner == owner)
+            .map(|g| g.number)
+            .collect();
+        group.number = (1..).find(|n| !used.contains(n)).unwrap();
+
+        match pg {
+            None => {
+                let livery = company.livery[LiveryScheme::Default as usize];
+                group.livery.colour1 = livery.colour1;
+                group.livery.colour2 = livery.colour2;
+                if company.settings.renew_keep_length {
+                    group.flags.insert(GroupFlags::REPLACE_WAGON_REMOVAL);
+                }
+            }
+            Some(pg) => {
+                group.parent = parent;
+                group.livery.colour1 = pg.livery.colour1;
+                group.livery.colour2 = pg.livery.colour2;
+                group.flags = pg.flags;
+            }
+        }
+
+        if index >= self.groups.len() {
+            self.groups.resize(index + 1, None);
+        }
+        self.groups[index] = Some(group);
+        if let Some(pg) = self.get_mut(parent) {
+            pg.children.insert(id);
+        }
+        Ok(id)
+    }
+
+    /// Delete a group with its sub-groups and autoreplace rules, moving the
+    /// vehicles to the DEFAULT group (matches C++ CmdDeleteGroup)
+    pub fn delete(&mut self, id: GroupID, vehicles: &mut [Vehicle]) -> Result<(), CoreError> {
+        let Some(group) = self.get(id) else {
+            return Err(CoreError::InvalidData(format!("Invalid group {}", id.0)));
+        };
+        let (owner, parent, children) = (group.owner, group.parent, group.children.clone());
+
+        self.remove_all_vehicles(id, vehicles)?;
+        for child in children {
+            self.delete(child, vehicles)?;
+        }
+
+        if let Some(c) = owner.company_id() {
+            if let Some(groups) = self.companies.get_mut(&c) {
+                groups.engine_renew.retain(|rule| rule.group_id != id);
+            }
+        }
+        if let Some(pg) = self.get_mut(parent) {
+            pg.children.remove(&id);
+        }
+        self.groups[id.0 as usize] = None;
+        Ok(())
+    }
+
+    /// Delete all groups of a company (matches C++ RemoveAllGroupsForCompany)
+    pub fn remove_all_for_company(&mut self, company: Owner) {
+        for slot in &mut self.groups {
+            if slot.as_ref().is_some_and(|g| g.owner == company) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Rename a group; an empty name resets it to the default name
+    pub fn rename(&mut self, id: GroupID, name: &str) -> Result<(), CoreError> {
+        if name.chars().count() >= MAX_LENGTH_GROUP_NAME_CHARS {
+            return Err(CoreError::InvalidData(format!(
+                "Group name '{}' is too long",
+                name
+            )));
+        }
+        let group = self
+            .get_mut(id)
+            .ok_or_else(|| CoreError::InvalidData(format!("Invalid group {}", id.0)))?;
+        group.name = name.into();
+        Ok(())
+    }
+
+    /// Move a group below another group, or to the top with
+    /// GroupID::INVALID; the company's default livery is inherited by top
+    /// level groups
+    pub fn set_parent(
+        &mut self,
+        id: GroupID,
+        parent: GroupID,
+        company_livery: &Livery,
+    ) -> Result<(), CoreError> {
+        let Some(group) = self.get(id) else {
+            return Err(CoreError::InvalidData(format!("Invalid group {}", id.0)));
+        };
+        if group.parent == parent {
+            return Ok(());
+        }
+        let (owner, vehicle_type, old_parent) = (group.owner, group.vehicle_type, group.parent);
+        if let Some(pg) = self.get(parent) {
+            if pg.owner != owner || pg.vehicle_type != vehicle_type {
+                return Err(CoreError::InvalidData(format!(
+                    "Group {} cannot be the parent of group {}",
+                    parent.0, id.0
+                )));
+            }
+            // This is the only place where loops are prevented
+            if self.group_is_in_group(parent, id) {
+                return Err(CoreError::InvalidData(format!(
+                    "Group {} is a sub-group of group {}",
+                    parent.0, id.0
+                )));
+            }
+        } else if parent != GroupID::INVALID {
+            return Err(CoreError::InvalidData(format!(
+                "Invalid group {}",
+                parent.0
+            )));
+        }
+
+        if let Some(pg) = self.get_mut(old_parent) {
+            pg.children.remove(&id);
+        }
+        if let Some(pg) = self.get_mut(parent) {
+            pg.children.insert(id);
+        }
+        self.get_mut(id).unwrap().parent = parent;
+
+        // Inherit the new parent's colours where the group has none of its own
+        let group = self.get(id).unwrap();
+        if group.livery.in_use & (LIVERY_PRIMARY | LIVERY_SECONDARY)
+            != LIVERY_PRIMARY | LIVERY_SECONDARY
+        {
+            let livery = self.parent_livery(group, company_livery);
+            let group = self.get_mut(id).unwrap();
+            if group.livery.in_use & LIVERY_PRIMARY == 0 {
+                group.livery.colour1 = livery.colour1;
+            }
+            if group.livery.in_use & LIVERY_SECONDARY == 0 {
+                group.livery.colour2 = livery.colour2;
+            }
+            self.propagate_child_livery(id);
+        }
+        Ok(())
+    }
+
+    /// Set the primary or secondary colour of a group; Colours::Invalid
+    /// goes back to the inherited colour (matches C++ CmdSetGroupLivery)
+    pub fn set_livery(
+        &mut self,
+        id: GroupID,
+        primary: bool,
+        colour: Colours,
+        company_livery: &Livery,
+    ) -> Result<(), CoreError> {
+        if colour == Colours::End {
+            return Err(CoreError::InvalidData("Invalid group colour".into()));
+        }
+        let Some(group) = self.get(id) else {
+            return Err(CoreError::InvalidData(format!("Invalid group {}", id.0)));
+        };
+        let inherited = self.parent_livery(group, company_livery);
+        let group = self.get_mut(id).unwrap();
+        let custom = colour != Colours::Invalid;
+        if primary {
+            group.livery.in_use =
+                (group.livery.in_use & !LIVERY_PRIMARY) | if custom { LIVERY_PRIMARY } else { 0 };
+            group.livery.colour1 = if custom {
+                colour as u8
+            } else {
+                inherited.colour1
+            };
+        } else {
+            group.livery.in_use = (group.livery.in_use & !LIVERY_SECONDARY)
+                | if custom { LIVERY_SECONDARY } else { 0 };
+            group.livery.colour2 = if custom {
+                colour as u8
+            } else {
+                inherited.colour2
+            };
+        }
+        self.propagate_child_livery(id);
+        Ok(())
+    }
+
+    /// Set or clear a flag of a group and optionally of its sub-groups
+    /// (matches C++ CmdSetGroupFlag)
+    pub fn set_flag(
+        &mut self,
+        id: GroupID,
+        flag: GroupFlags,
+        value: bool,
+        recursive: bool,
+    ) -> Result<(), CoreError> {
+        let group = self
+            .get_mut(id)
+            .ok_or_else(|| CoreError::InvalidData(format!("Invalid group {}", id.0)))?;
+        group.flags.set(flag, value);
+        if recursive {
+            for child in group.children.clone() {
+                self.set_flag(child, flag, value, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move a vehicle and, for trains, its wagons to a group, keeping the
+    /// engine counts up to date (matches C++ SetTrainGroupID and the
+    /// non-train part of AddVehicleToGroup)
+    fn move_chain(&mut self, vehicles: &mut [Vehicle], first: usize, new_g: GroupID) {
+        let chain = if vehicles[first].type_ == VehicleType::Train {
+            chain_positions(vehicles, first)
+        } else {
+            // Only the head of other vehicles counts; their parts follow it
+            vec![first]
+        };
+        for pos in chain {
+            let v = &mut vehicles[pos];
+            if v.is_engine_countable() && v.group_id != new_g {
+                self.count_engine(v, -1);
+                v.group_id = new_g;
+                self.count_engine(v, 1);
+            }
+            v.group_id = new_g;
+        }
+    }
+
+    /// Add a primary vehicle to a group, or to the DEFAULT group
+    /// (matches C++ CmdAddVehicleGroup for an existing group)
+    pub fn add_vehicle(
+        &mut self,
+        id: GroupID,
+        vehicle: VehicleID,
+        vehicles: &mut [Vehicle],
+    ) -> Result<(), CoreError> {
+        let pos = position(vehicles, vehicle)
+            .filter(|&pos| vehicles[pos].is_primary_vehicle())
+            .ok_or_else(|| CoreError::InvalidData(format!("Invalid vehicle {}", vehicle.0)))?;
+        let v = &vehicles[pos];
+        if id != GroupID::DEFAULT {
+            match self.get(id) {
+                Some(g) if g.owner == v.owner && g.vehicle_type == v.type_ => {}
+                _ => {
+                    return Err(CoreError::InvalidData(format!(
+                        "Vehicle {} cannot join group {}",
+                        vehicle.0, id.0
+                    )))
+                }
+            }
+        }
+
+        self.count_vehicle(&vehicles[pos], -1);
+        self.move_chain(vehicles, pos, id);
+        self.count_vehicle(&vehicles[pos], 1);
+        Ok(())
+    }
+
+    /// Move all vehicles of a group to the DEFAULT group
+    /// (matches C++ CmdRemoveAllVehiclesGroup)
+    pub fn remove_all_vehicles(
+        &mut self,
+        id: GroupID,
+        vehicles: &mut [Vehicle],
+    ) -> Result<(), CoreError> {
+        if self.get(id).is_none() {
+            return Err(CoreError::InvalidData(format!("Invalid group {}", id.0)));
+        }
+        let members: Vec<VehicleID> = vehicles
+            .iter()
+            .filter(|v| v.group_id == id && v.is_primary_vehicle())
+            .map(|v| v.index)
+            .collect();
+        for vehicle in members {
+            self.add_vehicle(GroupID::DEFAULT, vehicle, vehicles)?;
+        }
+        Ok(())
+    }
+
+    /// Give all vehicles of a train the group of its front engine, or the
+    /// DEFAULT group for free wagons, after its composition changed
+    /// (matches C++ UpdateTrainGroupID)
+    pub fn update_train_group(&mut self, first: VehicleID, vehicles: &mut [Vehicle]) {
+        let Some(pos) = position(vehicles, first) else {
+            return;
+        };
+        let new_g = if vehicles[pos].is_front() {
+            vehicles[pos].group_id
+        } else {
+            GroupID::DEFAULT
+        };
+        self.move_chain(vehicles, pos, new_g);
+    }
+
+    /// Autoreplace rules of a company, most recently added first
+    pub fn engine_renew_list(&self, company: Owner) -> &[EngineRenew] {
+        company
+            .company_id()
+            .and_then(|c| self.companies.get(&c))
+            .map_or(&[], |groups| &groups.engine_renew)
+    }
+
+    /// Rule that replaces `engine` in `group`: one set for the group or a
+    /// parent group
+    fn find_engine_renew(
+        &self,
+        company: Owner,
+        engine: EngineID,
+        group: GroupID,
+    ) -> Option<&EngineRenew> {
+        self.engine_renew_list(company)
+            .iter()
+            .find(|rule| rule.from == engine && self.group_is_in_group(group, rule.group_id))
+    }
+
+    /// Engine that replaces `engine` in `group` and whether only old
+    /// vehicles are replaced; rules of the ALL group apply unless the group
+    /// is protected (matches C++ EngineReplacement)
+    pub fn engine_replacement(
+        &self,
+        company: Owner,
+        engine: EngineID,
+        group: GroupID,
+    ) -> Option<(EngineID, bool)> {
+        let protected = self
+            .get(group)
+            .is_none_or(|g| g.flags.contains(GroupFlags::REPLACE_PROTECTION));
+        let rule = self.find_engine_renew(company, engine, group).or_else(|| {
+            if group == GroupID::DEFAULT || !protected {
+                self.find_engine_renew(company, engine, GroupID::ALL)
+            } else {
+                None
+            }
+        })?;
+        // Replacing with the same model only happens when old
+        Some((rule.to, rule.to == engine || rule.replace_when_old))
+    }
+
+    /// Add or update the rule replacing `from` in `group`
+    /// (matches C++ AddEngineReplacement)
+    pub fn add_engine_replacement(
+        &mut self,
+        company: Owner,
+        from: EngineID,
+        to: EngineID,
+        group: GroupID,
+        replace_when_old: bool,
+    ) -> Result<(), CoreError> {
+        let Some(c) = company.company_id() else {
+            return Err(CoreError::InvalidData(format!(
+                "{:?} cannot autoreplace",
+                company
+            )));
+        };
+        let existing = self
+            .find_engine_renew(company, from, group)
+            .map(|rule| (rule.from, rule.group_id));
+        let rules = &mut self.companies.entry(c).or_default().engine_renew;
+        match existing.and_then(|key| rules.iter_mut().find(|r| (r.from, r.group_id) == key)) {
+            Some(rule) => {
+                rule.to = to;
+                rule.replace_when_old = replace_when_old;
+            }
+            None => rules.insert(
+                0,
+                EngineRenew {
+                    from,
+                    to,
+                    group_id: group,
+                    replace_when_old,
+                },
+            ),
+        }
+        Ok(())
+    }
+
+    /// Remove the rule replacing `engine` set for exactly `group`; returns
+    /// whether there was one (matches C++ RemoveEngineReplacement)
+    pub fn remove_engine_replacement(
+        &mut self,
+        company: Owner,
+        engine: EngineID,
+        group: GroupID,
+    ) -> bool {
+        let Some(groups) = company
+            .company_id()
+            .and_then(|c| self.companies.get_mut(&c))
+        else {
+            return false;
+        };
+        let Some(pos) = groups
+            .engine_renew
+            .iter()
+            .position(|rule| rule.from == engine && rule.group_id == group)
+        else {
+            return false;
+        };
+        groups.engine_renew.remove(pos);
+        true
+    }
+
+    /// Remove all autoreplace rules of a company
+    /// (matches C++ RemoveAllEngineReplacement)
+    pub fn remove_all_engine_replacement(&mut self, company: Owner) {
+        if let Some(groups) = company
+            .company_id()
+            .and_then(|c| self.companies.get_mut(&c))
+        {
+            groups.engine_renew.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::EngineOverrideManager;
+    use crate::types::EconomyDate;
+    use crate::vehicle::GroundVehicleSubtype;
+
+    fn company() -> Company {
+        let mut company = Company::new(1, 0);
+        company.livery[LiveryScheme::Default as usize] = Livery {
+            in_use: 0,
+            colour1: Colours::Red as u8,
+            colour2: Colours::Blue as u8,
+        };
+        company
+    }
+
+    fn bus(index: u32, engine: u16) -> Vehicle {
+        let mut v = Vehicle::new(VehicleID(index), VehicleType::Road);
+        v.owner = Owner::Company1;
+        v.subtype = 1 << GroundVehicleSubtype::Front as u8;
+        v.engine_type = EngineID(engine);
+        v.group_id = GroupID::DEFAULT;
+        v.profit_last_year = 1000 << 8;
+        v
+    }
+
+    fn engines() -> EnginePool {
+        let mut manager = EngineOverrideManager::default();
+        manager.reset_to_default_mapping();
+        EnginePool::setup(&manager)
+    }
+
+    #[test]
+    fn test_group_tree() {
+        let company = company();
+        let mut pool = GroupPool::default();
+        let top = pool
+            .create(&company, VehicleType::Road, GroupID::INVALID)
+            .unwrap();
+        let sub = pool.create(&company, VehicleType::Road, top).unwrap();
+        assert!(pool.create(&company, VehicleType::Ship, top).is_err());
+        assert_eq!(pool.get(top).unwrap().number, 1);
+        assert_eq!(pool.get(sub).unwrap().number, 2);
+        assert_eq!(pool.get(sub).unwrap().livery.colour1, Colours::Red as u8);
+        assert!(pool.group_is_in_group(sub, top));
+        assert!(!pool.group_is_in_group(top, sub));
+
+        // Loops are refused
+        let livery = company.livery[LiveryScheme::Default as usize];
+        assert!(pool.set_parent(top, sub, &livery).is_err());
+
+        // Colours are passed on to sub-groups without their own
+        pool.set_livery(top, true, Colours::Green, &livery).unwrap();
+        assert_eq!(pool.get(sub).unwrap().livery.colour1, Colours::Green as u8);
+        pool.set_livery(top, true, Colours::Invalid, &livery)
+            .unwrap();
+        assert_eq!(pool.get(sub).unwrap().livery.colour1, Colours::Red as u8);
+
+        pool.set_flag(top, GroupFlags::REPLACE_PROTECTION, true, true)
+            .unwrap();
+        assert!(pool
+            .get(sub)
+            .unwrap()
+            .flags
+            .contains(GroupFlags::REPLACE_PROTECTION));
+
+        assert!(pool.rename(top, "Buses").is_ok());
+        assert!(pool.rename(top, &"x".repeat(32)).is_err());
+
+        // A parent that is gone is dropped when linking the sub-groups
+        let mut orphan = Group::new(GroupID(5), Owner::Company1, VehicleType::Road);
+        orphan.parent = GroupID(9);
+        let pool = GroupPool::from_groups(vec![orphan]).unwrap();
+        assert_eq!(pool.get(GroupID(5)).unwrap().parent, GroupID::INVALID);
+    }
+
+    #[test]
+    fn test_group_statistics() {
+        let company = company();
+        let owner = Owner::Company1;
+        let engines = engines();
+        let mut pool = GroupPool::default();
+        let top = pool
+            .create(&company, VehicleType::Road, GroupID::INVALID)
+            .unwrap();
+        let sub = pool.create(&company, VehicleType::Road, top).unwrap();
+
+        let mut vehicles = vec![bus(0, 116), bus(1, 116), bus(2, 117)];
+        vehicles[0].economy_age = EconomyDate(VEHICLE_PROFIT_MIN_AGE + 1);
+        vehicles[0].trip_occupancy = 80;
+        vehicles[1].trip_occupancy = 40;
+        pool.update_after_load(&vehicles, &engines);
+        assert_eq!(
+            pool.num_vehicle(owner, GroupID::DEFAULT, VehicleType::Road),
+            3
+        );
+        assert_eq!(pool.num_vehicle(owner, GroupID::ALL, VehicleType::Road), 3);
+
+        pool.add_vehicle(sub, VehicleID(0), &mut vehicles).unwrap();
+        pool.add_vehicle(top, VehicleID(1), &mut vehicles).unwrap();
+        assert_eq!(vehicles[0].group_id, sub);
+        assert_eq!(pool.num_vehicle(owner, top, VehicleType::Road), 2);
+        assert_eq!(pool.num_vehicle(owner, sub, VehicleType::Road), 1);
+        assert_eq!(
+            pool.num_vehicle(owner, GroupID::DEFAULT, VehicleType::Road),
+            1
+        );
+        assert_eq!(
+            pool.num_engines(owner, top, EngineID(116), VehicleType::Road),
+            2
+        );
+        assert_eq!(pool.num_vehicle_min_age(owner, top, VehicleType::Road), 1);
+        assert_eq!(
+            pool.profit_last_year_min_age(owner, top, VehicleType::Road),
+            1000
+        );
+        assert_eq!(pool.occupancy(owner, top, VehicleType::Road), Some(60));
+
+        pool.set_trip_occupancy(&mut vehicles[1], 100);
+        assert_eq!(pool.occupancy(owner, top, VehicleType::Road), Some(90));
+        assert_eq!(
+            pool.occupancy(owner, GroupID::ALL, VehicleType::Road),
+            Some(60)
+        );
+
+        // Other vehicle types cannot join
+        assert!(pool.add_vehicle(top, VehicleID(5), &mut vehicles).is_err());
+
+        // Deleting a group moves its vehicles to the DEFAULT group
+        pool.delete(top, &mut vehicles).unwrap();
+        assert!(pool.get(sub).is_none());
+        assert!(vehicles.iter().all(|v| v.group_id == GroupID::DEFAULT));
+        assert_eq!(
+            pool.num_vehicle(owner, GroupID::DEFAULT, VehicleType::Road),
+            3
+        );
+        assert_eq!(
+            pool.statistics(owner, GroupID::DEFAULT, VehicleType::Road)
+                .unwrap()
+                .num_engines(EngineID(116)),
+            2
+        );
+    }
+
+    #[test]
+    fn test_engine_replacement() {
+        let company = company();
+        let owner = Owner::Company1;
+        let engines = engines();
+        let mut pool = GroupPool::default();
+        let top = pool
+            .create(&company, VehicleType::Road, GroupID::INVALID)
+            .unwrap();
+        let sub = pool.create(&company, VehicleType::Road, top).unwrap();
+
+        pool.add_engine_replacement(owner, EngineID(116), EngineID(117), GroupID::ALL, true)
+            .unwrap();
+        pool.add_engine_replacement(owner, EngineID(116), EngineID(118), top, false)
+            .unwrap();
+        assert_eq!(
+            pool.engine_replacement(owner, EngineID(116), sub),
+            Some((EngineID(118), false))
+        );
+        assert_eq!(
+            pool.engine_replacement(owner, EngineID(116), GroupID::DEFAULT),
+            Some((EngineID(117), true))
+        );
+
+        // Protected groups ignore the rules of the ALL group
+        assert!(pool.remove_engine_replacement(owner, EngineID(116), top));
+        assert!(!pool.remove_engine_replacement(owner, EngineID(116), top));
+        assert_eq!(
+            pool.engine_replacement(owner, EngineID(116), sub),
+            Some((EngineID(117), true))
+        );
+        pool.set_flag(sub, GroupFlags::REPLACE_PROTECTION, true, false)
+            .unwrap();
+        assert_eq!(pool.engine_replacement(owner, EngineID(116), sub), None);
+
+        let mut vehicles = vec![bus(0, 116)];
+        pool.update_after_load(&vehicles, &engines);
+        let all = pool
+            .statistics(owner, GroupID::ALL, VehicleType::Road)
+            .unwrap();
+        assert!(all.autoreplace_defined && !all.autoreplace_finished);
+
+        vehicles[0].engine_type = EngineID(117);
+        pool.update_after_load(&vehicles, &engines);
+        let all = pool
+            .statistics(owner, GroupID::ALL, VehicleType::Road)
+            .unwrap();
+        assert!(all.autoreplace_defined && all.autoreplace_finished);
+    }
+}
@@ -4,7 +4,10 @@
 //! All structures must maintain exact C++ compatibility for save/load.
 
 use crate::map::TileIndex;
-use crate::types::{CalendarDate, CargoType, EconomyDate, IndustryID, Owner, StationID, TownID};
+use crate::subsidy::PartOfSubsidy;
+use crate::types::{
+    CalendarDate, CargoType, EconomyDate, EconomyYear, IndustryID, Owner, StationID, TownID,
+};
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use serde_with::serde_as;
@@ -52,7 +55,7 @@ pub const INDUSTRYBEH_AFTER_1960: IndustryBehaviour = 1 << 8;
 pub const INDUSTRYBEH_HELICOPTER_STATION: IndustryBehaviour = 1 << 9;
 pub const INDUSTRYBEH_CAN_SUBSIDENCE: IndustryBehaviour = 1 << 10;
 
-/// Industry control flags (matches C++ IndustryControlFlags enum)
+/// Industry control flags (matches C++ IndustryControlFlags enum), combined as a bitmask
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum IndustryControlFlags {
@@ -80,9 +83,34 @@ pub const INDUSTRY_NUM_OUTPUTS: usize = 16;
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IndustryCargo {
     pub cargo: CargoType,
-    pub waiting: u16,        // Amount waiting (for inputs)
-    pub production_rate: u8, // Production rate (for outputs)
-    pub last_accepted: u32,  // Last accepted amount (for inputs)
+    pub waiting: u16,               // Amount waiting (for inputs)
+    pub production_rate: u8,        // Production rate (for outputs)
+    pub last_accepted: EconomyDate, // Last day cargo was accepted (for inputs)
+    pub accumulated_waiting: u32,   // Waiting total over the month, for the average (for inputs)
+}
+
+/// Monthly production record of an output slot (matches C++ Industry::ProducedHistory)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProducedHistory {
+    pub production: u16,
+    pub transported: u16,
+}
+
+impl ProducedHistory {
+    /// Transported fraction scaled to 0-255
+    pub fn pct_transported(&self) -> u8 {
+        if self.production == 0 {
+            return 0;
+        }
+        (self.transported as u32 * 256 / self.production as u32).min(255) as u8
+    }
+}
+
+/// Monthly acceptance record of an input slot (matches C++ Industry::AcceptedHistory)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceptedHistory {
+    pub accepted: u16,
+    pub waiting: u16,
 }
 
 /// Industry production statistics
@@ -110,7 +138,7 @@ impl Default for IndustryProduction {
 /// Industry structure (matches C++ Industry class for savegame compatibility)
 #[repr(C)]
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Industry {
     /// Industry index/ID
     pub index: IndustryID,
@@ -128,9 +156,15 @@ pub struct Industry {
     /// Associated town
     pub town: TownID,
 
+    /// Neutral station attached to this industry (oil rigs)
+    pub neutral_station: StationID,
+
     /// Owner (for certain industry types)
     pub owner: Owner,
 
+    /// Company that funded or prospected this industry
+    pub founder: Owner,
+
     /// Production multiplier
     pub prod_level: u8,
 
@@ -151,6 +185,18 @@ pub struct Industry {
     /// Last month's production statistics
     pub last_month_production: IndustryProduction,
 
+    /// Full production history per output slot, indexed by THIS_MONTH, LAST_MONTH, ...;
+    /// the first two records are kept in `production` and `last_month_production`
+    #[serde_as(as = "[_; 16]")]
+    pub produced_history: [Vec<ProducedHistory>; INDUSTRY_NUM_OUTPUTS],
+
+    /// Acceptance history per input slot, empty when not tracked
+    #[serde_as(as = "[_; 16]")]
+    pub accepted_history: [Vec<AcceptedHistory>; INDUSTRY_NUM_INPUTS],
+
+    /// Bitmask of history records holding valid data
+    pub valid_history: u64,
+
     /// Counter for production changes
     pub counter: u16,
 
@@ -164,7 +210,7 @@ pub struct Industry {
     pub random_colour: u8,
 
     /// Last year this industry was serviced
-    pub last_serviced_year: EconomyDate,
+    pub last_serviced_year: EconomyYear,
 
     /// Did this industry get any cargo delivered last month
     pub was_cargo_delivered: bool,
@@ -172,8 +218,8 @@ pub struct Industry {
     /// Callback flags
     pub callback_mask: u32,
 
-    /// Control flags
-    pub control_flags: IndustryControlFlags,
+    /// Control flags (IndustryControlFlags bits)
+    pub control_flags: u8,
 
     /// Text ID for production up/down messages
     pub last_text_message: u16,
@@ -190,6 +236,15 @@ pub struct Industry {
 
     /// Stations that serve this industry
     pub stations_near: Vec<StationID>,
+
+    /// Persistent storage of a NewGRF industry
+    pub psa: Option<u32>,
+
+    /// Additional text set by a game script
+    pub text: String,
+
+    /// Whether the industry is the source or destination of a subsidy
+    pub part_of_subsidy: PartOfSubsidy,
 }
 
 impl Industry {
@@ -202,27 +257,35 @@ impl Industry {
             height: 0,
             industry_type,
             town: TownID::INVALID,
+            neutral_station: StationID::INVALID,
             owner: Owner::None,
+            founder: Owner::None,
             prod_level: 0,
             random: 0,
             accepts_cargo: [IndustryCargo::default(); INDUSTRY_NUM_INPUTS],
             produced_cargo: [IndustryCargo::default(); INDUSTRY_NUM_OUTPUTS],
             production: IndustryProduction::default(),
             last_month_production: IndustryProduction::default(),
+            produced_history: std::array::from_fn(|_| Vec::new()),
+            accepted_history: std::array::from_fn(|_| Vec::new()),
+            valid_history: 0,
             counter: 0,
             type_at_last_rating: industry_type,
             construction_date: CalendarDate(0),
             random_colour: 0,
-            last_serviced_year: EconomyDate(0),
+            last_serviced_year: EconomyYear(0),
             was_cargo_delivered: false,
             callback_mask: 0,
-            control_flags: IndustryControlFlags::None,
+            control_flags: IndustryControlFlags::None as u8,
             last_text_message: 0,
             construction_type: 0,
             selected_layout: 0,
             exclusive_supplier: Owner::None,
             exclusive_consumer: Owner::None,
             stations_near: Vec::new(),
+            psa: None,
+            text: String::new(),
+            part_of_subsidy: PartOfSubsidy::empty(),
         }
     }
 
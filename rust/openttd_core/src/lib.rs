@@ -1,9 +1,22 @@
+pub mod cargopacket;
 pub mod company;
 pub mod endian;
+pub mod engine;
+pub mod engine_tables;
 pub mod error;
+pub mod gamelog;
+pub mod group;
 pub mod industry;
+pub mod linkgraph;
+pub mod linkgraphjob;
+pub mod linkgraphschedule;
 pub mod map;
+pub mod newgrf;
+pub mod order;
+pub mod random;
+pub mod signs;
 pub mod station;
+pub mod subsidy;
 pub mod town;
 pub mod types;
 pub mod vehicle;
@@ -0,0 +1,591 @@
+//! Link graph data structures for OpenTTD
+//!
+//! A link graph holds one node per station handling a cargo and an edge per
+//! link between two such stations. Graphs are copied into jobs that plan
+//! the cargo flows in the background, see `linkgraphjob`.
+
+use crate::error::CoreError;
+use crate::map::TileIndex;
+use crate::station::{GoodsEntry, Station};
+use crate::types::{CargoClasses, CargoType, EconomyDate, StationID};
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+/// Link graph pool index (matches C++ LinkGraphID)
+pub type LinkGraphID = u16;
+
+pub const INVALID_LINK_GRAPH: LinkGraphID = 0xFFFF;
+
+/// Index of a node within a link graph (matches C++ NodeID)
+pub type NodeID = u16;
+
+pub const INVALID_NODE: NodeID = u16::MAX;
+
+/// Invalid value of the dates in a link graph (matches C++ EconomyTime::INVALID_DATE)
+pub const INVALID_DATE: EconomyDate = EconomyDate(crate::types::dates::INVALID_DATE);
+
+/// Approximate seconds per day for the link graph settings (matches C++ EconomyTime::SECONDS_PER_DAY)
+pub const SECONDS_PER_DAY: u16 = 2;
+
+/// How cargo is distributed over the link graph (matches C++ DistributionType)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum DistributionType {
+    /// No link graph calculations are run
+    #[default]
+    Manual = 0,
+    /// Cargo usually only travels in one direction
+    Asymmetric = 1,
+    /// The same amount of cargo travels in each direction between each pair of nodes
+    Symmetric = 2,
+}
+
+impl TryFrom<u8> for DistributionType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => DistributionType::Manual,
+            1 => DistributionType::Asymmetric,
+            2 => DistributionType::Symmetric,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid distribution type {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+bitflags! {
+    /// How an edge is updated (matches C++ EdgeUpdateModes)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct EdgeUpdateModes: u8 {
+        const INCREASE = 1 << 0;
+        const REFRESH = 1 << 1;
+        const RESTRICTED = 1 << 2;
+        const UNRESTRICTED = 1 << 3;
+    }
+}
+
+/// Settings of the link graph, copied into each job (matches C++ LinkGraphSettings)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkGraphSettings {
+    /// Seconds each job may run before it is joined
+    pub recalc_time: u16,
+    /// Seconds between spawning jobs
+    pub recalc_interval: u16,
+    pub distribution_pax: DistributionType,
+    pub distribution_mail: DistributionType,
+    pub distribution_armoured: DistributionType,
+    pub distribution_default: DistributionType,
+    /// Lower values trade accuracy for running time
+    pub accuracy: u8,
+    /// Influence of the supply at the remote station on the demand
+    pub demand_size: u8,
+    /// Influence of the distance between stations on the demand
+    pub demand_distance: u8,
+    /// Percentage up to which short paths are saturated before others are used
+    pub short_path_saturation: u8,
+}
+
+impl Default for LinkGraphSettings {
+    fn default() -> Self {
+        Self {
+            recalc_time: 32,
+            recalc_interval: 8,
+            distribution_pax: DistributionType::Manual,
+            distribution_mail: DistributionType::Manual,
+            distribution_armoured: DistributionType::Manual,
+            distribution_default: DistributionType::Manual,
+            accuracy: 16,
+            demand_size: 100,
+            demand_distance: 100,
+            short_path_saturation: 80,
+        }
+    }
+}
+
+impl LinkGraphSettings {
+    /// Distribution type of a cargo with the given classes
+    pub fn distribution_type(&self, classes: CargoClasses) -> DistributionType {
+        if classes.contains(CargoClasses::PASSENGERS) {
+            self.distribution_pax
+        } else if classes.contains(CargoClasses::MAIL) {
+            self.distribution_mail
+        } else if classes.contains(CargoClasses::ARMOURED) {
+            self.distribution_armoured
+        } else {
+            self.distribution_default
+        }
+    }
+}
+
+/// A link between two stations (matches C++ LinkGraph::BaseEdge)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkGraphEdge {
+    pub capacity: u32,
+    pub usage: u32,
+    /// Sum of the travel times of the link, in ticks
+    pub travel_time_sum: u64,
+    pub last_unrestricted_update: EconomyDate,
+    pub last_restricted_update: EconomyDate,
+    pub dest_node: NodeID,
+}
+
+impl LinkGraphEdge {
+    pub fn new(dest_node: NodeID) -> Self {
+        Self {
+            capacity: 0,
+            usage: 0,
+            travel_time_sum: 0,
+            last_unrestricted_update: INVALID_DATE,
+            last_restricted_update: INVALID_DATE,
+            dest_node,
+        }
+    }
+
+    /// Average travel time in ticks
+    pub fn travel_time(&self) -> u32 {
+        self.travel_time_sum
+            .checked_div(self.capacity as u64)
+            .unwrap_or(0) as u32
+    }
+
+    /// Date of the last update to any part of the capacity
+    pub fn last_update(&self) -> EconomyDate {
+        EconomyDate(
+            self.last_unrestricted_update
+                .0
+                .max(self.last_restricted_update.0),
+        )
+    }
+
+    /// Update the capacity, usage and travel time of an existing edge
+    pub fn update(
+        &mut self,
+        capacity: u32,
+        usage: u32,
+        travel_time: u32,
+        modes: EdgeUpdateModes,
+        date: EconomyDate,
+    ) {
+        debug_assert!(self.capacity > 0 && capacity >= usage);
+        if modes.contains(EdgeUpdateModes::INCREASE) {
+            if self.travel_time_sum == 0 {
+                self.travel_time_sum = (self.capacity + capacity) as u64 * travel_time as u64;
+            } else if travel_time == 0 {
+                self.travel_time_sum +=
+                    self.travel_time_sum / self.capacity as u64 * capacity as u64;
+            } else {
+                self.travel_time_sum += travel_time as u64 * capacity as u64;
+            }
+            self.capacity += capacity;
+            self.usage += usage;
+        } else if modes.contains(EdgeUpdateModes::REFRESH) {
+            if self.travel_time_sum == 0 {
+                self.capacity = self.capacity.max(capacity);
+                self.travel_time_sum = travel_time as u64 * self.capacity as u64;
+            } else if capacity > self.capacity {
+                self.travel_time_sum =
+                    self.travel_time_sum / self.capacity as u64 * capacity as u64;
+                self.capacity = capacity;
+            }
+            self.usage = self.usage.max(usage);
+        }
+        if modes.contains(EdgeUpdateModes::UNRESTRICTED) {
+            self.last_unrestricted_update = date;
+        }
+        if modes.contains(EdgeUpdateModes::RESTRICTED) {
+            self.last_restricted_update = date;
+        }
+    }
+
+    /// Only let vehicles that explicitly go to the destination use the edge
+    pub fn restrict(&mut self) {
+        self.last_unrestricted_update = INVALID_DATE;
+    }
+
+    pub fn release(&mut self) {
+        self.last_restricted_update = INVALID_DATE;
+    }
+}
+
+/// A station in the link graph (matches C++ LinkGraph::BaseNode)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkGraphNode {
+    pub supply: u32,
+    /// Acceptance at the station
+    pub demand: u32,
+    pub station: StationID,
+    pub xy: TileIndex,
+    /// When the supply was last updated
+    pub last_update: EconomyDate,
+    /// Outgoing edges, sorted by destination
+    pub edges: Vec<LinkGraphEdge>,
+}
+
+impl Default for LinkGraphNode {
+    fn default() -> Self {
+        Self::new(TileIndex::INVALID, StationID::INVALID, 0)
+    }
+}
+
+impl LinkGraphNode {
+    pub fn new(xy: TileIndex, station: StationID, demand: u32) -> Self {
+        Self {
+            supply: 0,
+            demand,
+            station,
+            xy,
+            last_update: INVALID_DATE,
+            edges: Vec::new(),
+        }
+    }
+
+    pub fn update_supply(&mut self, supply: u32, date: EconomyDate) {
+        self.supply += supply;
+        self.last_update = date;
+    }
+
+    fn edge_position(&self, to: NodeID) -> Result<usize, usize> {
+        self.edges.binary_search_by_key(&to, |e| e.dest_node)
+    }
+
+    pub fn has_edge_to(&self, to: NodeID) -> bool {
+        self.edge_position(to).is_ok()
+    }
+
+    pub fn edge(&self, to: NodeID) -> Option<&LinkGraphEdge> {
+        self.edge_position(to).ok().map(|i| &self.edges[i])
+    }
+
+    pub fn edge_mut(&mut self, to: NodeID) -> Option<&mut LinkGraphEdge> {
+        self.edge_position(to).ok().map(|i| &mut self.edges[i])
+    }
+
+    /// Add an edge that does not exist yet
+    pub fn add_edge(
+        &mut self,
+        to: NodeID,
+        capacity: u32,
+        usage: u32,
+        travel_time: u32,
+        modes: EdgeUpdateModes,
+        date: EconomyDate,
+    ) {
+        let Err(position) = self.edge_position(to) else {
+            panic!("edge to node {} already exists", to);
+        };
+        let mut edge = LinkGraphEdge::new(to);
+        edge.capacity = capacity;
+        edge.usage = usage;
+        edge.travel_time_sum = travel_time as u64 * capacity as u64;
+        if modes.contains(EdgeUpdateModes::UNRESTRICTED) {
+            edge.last_unrestricted_update = date;
+        }
+        if modes.contains(EdgeUpdateModes::RESTRICTED) {
+            edge.last_restricted_update = date;
+        }
+        self.edges.insert(position, edge);
+    }
+
+    /// Add an edge or update the existing one
+    pub fn update_edge(
+        &mut self,
+        to: NodeID,
+        capacity: u32,
+        usage: u32,
+        travel_time: u32,
+        modes: EdgeUpdateModes,
+        date: EconomyDate,
+    ) {
+        debug_assert!(capacity > 0 && usage <= capacity);
+        match self.edge_mut(to) {
+            Some(edge) => edge.update(capacity, usage, travel_time, modes, date),
+            None => self.add_edge(to, capacity, usage, travel_time, modes, date),
+        }
+    }
+
+    pub fn remove_edge(&mut self, to: NodeID) {
+        if let Ok(position) = self.edge_position(to) {
+            self.edges.remove(position);
+        }
+    }
+}
+
+fn goods_mut(
+    stations: &mut [Station],
+    station: StationID,
+    cargo: CargoType,
+) -> Option<&mut GoodsEntry> {
+    stations
+        .iter_mut()
+        .find(|st| st.index == station)?
+        .goods
+        .get_mut(cargo.as_usize())
+}
+
+/// The link graph of one cargo in a connected set of stations (matches C++ LinkGraph)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkGraph {
+    pub index: LinkGraphID,
+    pub cargo: CargoType,
+    /// Last time the capacities and supplies were compressed
+    pub last_compression: EconomyDate,
+    pub nodes: Vec<LinkGraphNode>,
+}
+
+impl LinkGraph {
+    /// Minimum effective distance for timeout calculation
+    pub const MIN_TIMEOUT_DISTANCE: u32 = 32;
+    /// Days before deleting links served only by vehicles stopped in depot
+    pub const STALE_LINK_DEPOT_TIMEOUT: i32 = 1024;
+    /// Minimum number of days between subsequent compressions
+    pub const COMPRESSION_INTERVAL: i32 = 256;
+
+    pub fn new(index: LinkGraphID, cargo: CargoType, date: EconomyDate) -> Self {
+        Self {
+            index,
+            cargo,
+            last_compression: date,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Scale a value from a graph of age `orig_age` to one of age `target_age`,
+    /// keeping it above 0 if it was
+    pub fn scale(value: u32, target_age: i32, orig_age: i32) -> u32 {
+        if value > 0 {
+            ((value as i64 * target_age as i64 / orig_age as i64) as u32).max(1)
+        } else {
+            0
+        }
+    }
+
+    pub fn size(&self) -> NodeID {
+        self.nodes.len() as NodeID
+    }
+
+    /// Scale a value to its monthly equivalent, based on the last compression
+    pub fn monthly(&self, base: u32, date: EconomyDate) -> u32 {
+        (base as i64 * 30 / (date.0 - self.last_compression.0 + 1) as i64) as u32
+    }
+
+    pub fn init(&mut self, size: usize) {
+        debug_assert!(self.nodes.is_empty());
+        self.nodes.resize(size, LinkGraphNode::default());
+    }
+
+    /// Move all dates by `interval` days, e.g. when the economy date is reset
+    pub fn shift_dates(&mut self, interval: i32) {
+        self.last_compression.0 += interval;
+        for node in &mut self.nodes {
+            if node.last_update != INVALID_DATE {
+                node.last_update.0 += interval;
+            }
+            for edge in &mut node.edges {
+                if edge.last_unrestricted_update != INVALID_DATE {
+                    edge.last_unrestricted_update.0 += interval;
+                }
+                if edge.last_restricted_update != INVALID_DATE {
+                    edge.last_restricted_update.0 += interval;
+                }
+            }
+        }
+    }
+
+    /// Halve the supplies and capacities so that old data loses weight
+    pub fn compress(&mut self, date: EconomyDate) {
+        self.last_compression = EconomyDate((date.0 + self.last_compression.0) / 2);
+        for node in &mut self.nodes {
+            node.supply /= 2;
+            for edge in node.edges.iter_mut().filter(|e| e.capacity > 0) {
+                let new_capacity = (edge.capacity / 2).max(1);
+                if edge.capacity < 1 << 16 {
+                    edge.travel_time_sum =
+                        edge.travel_time_sum * new_capacity as u64 / edge.capacity as u64;
+                } else if edge.travel_time_sum != 0 {
+                    edge.travel_time_sum = (edge.travel_time_sum / 2).max(1);
+                }
+                edge.capacity = new_capacity;
+                edge.usage /= 2;
+            }
+        }
+    }
+
+    /// Append the nodes of `other`, scaled to the age of this graph, and
+    /// point the goods entries of their stations here
+    pub fn merge(&mut self, other: LinkGraph, date: EconomyDate, stations: &mut [Station]) {
+        let age = date.0 - self.last_compression.0 + 1;
+        let other_age = date.0 - other.last_compression.0 + 1;
+        let first = self.size();
+        for node in other.nodes {
+            let new_node = self.size();
+            let mut merged = LinkGraphNode::new(node.xy, node.station, node.demand);
+            if let Some(st) = stations.iter_mut().find(|st| st.index == node.station) {
+                merged.xy = st.xy;
+                if let Some(ge) = st.goods.get_mut(self.cargo.as_usize()) {
+                    merged.demand = ge.acceptance as u32;
+                    ge.link_graph = self.index;
+                    ge.node = new_node;
+                }
+            }
+            merged.supply = Self::scale(node.supply, age, other_age);
+            for edge in node.edges {
+                let mut new_edge = LinkGraphEdge::new(first + edge.dest_node);
+                new_edge.capacity = Self::scale(edge.capacity, age, other_age);
+                new_edge.usage = Self::scale(edge.usage, age, other_age);
+                new_edge.travel_time_sum =
+                    Self::scale(edge.travel_time_sum as u32, age, other_age) as u64;
+                merged.edges.push(new_edge);
+            }
+            self.nodes.push(merged);
+        }
+    }
+
+    /// Add a node for a station
+    pub fn add_node(&mut self, station: &Station) -> NodeID {
+        let acceptance = station
+            .goods
+            .get(self.cargo.as_usize())
+            .is_some_and(|ge| ge.acceptance);
+        self.nodes.push(LinkGraphNode::new(
+            station.xy,
+            station.index,
+            acceptance as u32,
+        ));
+        self.size() - 1
+    }
+
+    /// Remove a node by moving the last one into its place
+    pub fn remove_node(&mut self, id: NodeID, stations: &mut [Station]) {
+        let last_node = self.size() - 1;
+        if let Some(ge) = goods_mut(stations, self.nodes[last_node as usize].station, self.cargo) {
+            ge.node = id;
+        }
+        self.nodes.swap_remove(id as usize);
+        for node in &mut self.nodes {
+            node.remove_edge(id);
+            // The list is sorted, so an edge to the last node is the last edge
+            if node.edges.last().is_some_and(|e| e.dest_node == last_node) {
+                let mut edge = node.edges.pop().unwrap();
+                edge.dest_node = id;
+                let position = node.edge_position(id).unwrap_err();
+                node.edges.insert(position, edge);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Owner;
+
+    const MODES: EdgeUpdateModes = EdgeUpdateModes::INCREASE.union(EdgeUpdateModes::UNRESTRICTED);
+
+    fn graph(stations: &mut [Station]) -> LinkGraph {
+        let mut lg = LinkGraph::new(1, CargoType(2), EconomyDate(0));
+        for st in stations.iter_mut() {
+            let node = lg.add_node(st);
+            st.goods[2].link_graph = lg.index;
+            st.goods[2].node = node;
+        }
+        lg
+    }
+
+    #[test]
+    fn test_edge_update() {
+        let mut node = LinkGraphNode::new(TileIndex(0), StationID(0), 1);
+        node.update_edge(3, 100, 10, 20, MODES, EconomyDate(5));
+        node.update_edge(
+            1,
+            50,
+            0,
+            0,
+            EdgeUpdateModes::REFRESH | EdgeUpdateModes::RESTRICTED,
+            EconomyDate(6),
+        );
+        assert_eq!(
+            node.edges.iter().map(|e| e.dest_node).collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(node.edge(1).unwrap().last_unrestricted_update, INVALID_DATE);
+
+        node.update_edge(3, 100, 30, 40, MODES, EconomyDate(7));
+        let edge = node.edge(3).unwrap();
+        assert_eq!(
+            (edge.capacity, edge.usage, edge.travel_time()),
+            (200, 40, 30)
+        );
+        assert_eq!(edge.last_update(), EconomyDate(7));
+
+        // A refresh only raises the capacity
+        node.update_edge(3, 150, 10, 0, EdgeUpdateModes::REFRESH, EconomyDate(8));
+        assert_eq!(node.edge(3).unwrap().capacity, 200);
+
+        node.remove_edge(1);
+        assert!(!node.has_edge_to(1));
+    }
+
+    #[test]
+    fn test_compress_and_monthly() {
+        let mut stations = vec![Station::new(StationID(4), TileIndex(10), Owner::Company0)];
+        let mut lg = graph(&mut stations);
+        lg.nodes[0].update_supply(101, EconomyDate(10));
+        lg.nodes[0].update_edge(0, 7, 3, 10, MODES, EconomyDate(10));
+        assert_eq!(lg.monthly(101, EconomyDate(19)), 151);
+
+        lg.compress(EconomyDate(256));
+        assert_eq!(lg.last_compression, EconomyDate(128));
+        assert_eq!(lg.nodes[0].supply, 50);
+        let edge = &lg.nodes[0].edges[0];
+        assert_eq!(
+            (edge.capacity, edge.usage, edge.travel_time_sum),
+            (3, 1, 30)
+        );
+        assert_eq!(LinkGraph::scale(1, 1, 10), 1);
+        assert_eq!(LinkGraph::scale(0, 1, 10), 0);
+    }
+
+    #[test]
+    fn test_merge_and_remove_node() {
+        let mut stations: Vec<Station> = (0..4)
+            .map(|i| Station::new(StationID(i), TileIndex(i as u32), Owner::Company0))
+            .collect();
+        stations[3].goods[2].acceptance = true;
+        let mut lg = graph(&mut stations[..2]);
+        lg.nodes[0].update_edge(1, 10, 0, 0, MODES, EconomyDate(0));
+        let mut other = graph(&mut stations[2..]);
+        other.index = 2;
+        other.nodes[1].update_edge(0, 20, 0, 0, MODES, EconomyDate(0));
+
+        lg.merge(other, EconomyDate(0), &mut stations);
+        assert_eq!(lg.size(), 4);
+        assert_eq!(lg.nodes[3].demand, 1);
+        assert_eq!(lg.nodes[3].edges[0].dest_node, 2);
+        assert_eq!(
+            (stations[3].goods[2].link_graph, stations[3].goods[2].node),
+            (1, 3)
+        );
+
+        // The last node takes the place of the removed one
+        lg.nodes[0].update_edge(3, 5, 0, 0, MODES, EconomyDate(0));
+        lg.remove_node(1, &mut stations);
+        assert_eq!(lg.size(), 3);
+        assert_eq!(lg.nodes[1].station, StationID(3));
+        assert_eq!(stations[3].goods[2].node, 1);
+        assert_eq!(
+            lg.nodes[0]
+                .edges
+                .iter()
+                .map(|e| e.dest_node)
+                .collect::<Vec<_>>(),
+            [1]
+        );
+        assert_eq!(lg.nodes[1].edges[0].dest_node, 2);
+    }
+}
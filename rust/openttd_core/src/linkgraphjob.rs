@@ -0,0 +1,1345 @@
+//! Link graph jobs for cargo distribution
+//!
+//! A job works on a copy of a link graph so that the game can go on while it
+//! runs. It calculates the demands between the nodes, routes them with a
+//! multi-commodity flow solver and maps the resulting paths to flows per
+//! station, which are merged into the stations' flow stats when the job is
+//! joined.
+
+use crate::error::CoreError;
+use crate::linkgraph::{
+    DistributionType, LinkGraph, LinkGraphSettings, NodeID, INVALID_DATE, INVALID_NODE,
+    SECONDS_PER_DAY,
+};
+use crate::map::TileIndex;
+use crate::station::{FlowStat, FlowStatMap, Station};
+use crate::types::{CargoClasses, CargoType, EconomyDate, StationID};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Link graph job pool index (matches C++ LinkGraphJobID)
+pub type LinkGraphJobID = u16;
+
+/// Ticks per economy day (matches C++ Ticks::DAY_TICKS)
+const DAY_TICKS: u32 = 74;
+
+/// Game state a job needs besides the link graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobEnvironment {
+    pub map_size_x: u32,
+    pub map_size_y: u32,
+    /// Classes of the job's cargo
+    pub cargo_classes: CargoClasses,
+}
+
+impl JobEnvironment {
+    fn tile_xy(&self, tile: TileIndex) -> (u32, u32) {
+        (tile.0 % self.map_size_x, tile.0 / self.map_size_x)
+    }
+
+    /// Twice the larger plus the smaller axis distance (matches C++ DistanceMaxPlusManhattan)
+    fn distance_max_plus_manhattan(&self, t0: TileIndex, t1: TileIndex) -> u32 {
+        let (x0, y0) = self.tile_xy(t0);
+        let (x1, y1) = self.tile_xy(t1);
+        let dx = x0.abs_diff(x1);
+        let dy = y0.abs_diff(y1);
+        if dx > dy {
+            2 * dx + dy
+        } else {
+            2 * dy + dx
+        }
+    }
+
+    /// Distance between opposite corners of the map, used to scale demands by distance
+    fn base_distance(&self) -> u32 {
+        let corner = TileIndex(self.map_size_x * self.map_size_y - 1);
+        int_sqrt(self.distance_max_plus_manhattan(TileIndex(0), corner))
+    }
+}
+
+/// Integer square root, rounded to the nearest integer (matches C++ IntSqrt)
+fn int_sqrt(mut num: u32) -> u32 {
+    let mut res: u32 = 0;
+    let mut bit: u32 = 1 << 30;
+    while bit > num {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if num >= res + bit {
+            num -= res + bit;
+            res = (res >> 1) + bit;
+        } else {
+            res >>= 1;
+        }
+        bit >>= 2;
+    }
+    if num > res {
+        res += 1;
+    }
+    res
+}
+
+/// Error for a link graph whose copy turned out to be inconsistent while
+/// solving; such graphs can only come from loaded data
+fn inconsistent(what: &str) -> CoreError {
+    CoreError::InvalidData(format!("link graph job: {}", what))
+}
+
+/// Flags shared between a job and the thread running it
+#[derive(Debug, Default)]
+struct JobState {
+    completed: AtomicBool,
+    aborted: AtomicBool,
+}
+
+/// Demand between two nodes (matches C++ LinkGraphJob::DemandAnnotation)
+#[derive(Debug, Clone, Copy, Default)]
+struct DemandAnnotation {
+    demand: u32,
+    unsatisfied_demand: u32,
+}
+
+/// Calculation state of a node (matches C++ LinkGraphJob::NodeAnnotation)
+#[derive(Debug, Clone, Default)]
+struct NodeAnnotation {
+    undelivered_supply: u32,
+    /// Paths leaving this node, those with flow first
+    paths: VecDeque<usize>,
+    /// Planned flows to other nodes
+    flows: FlowStatMap,
+    /// Planned flow over each edge of the node, in the order of the graph's edges
+    edge_flows: Vec<u32>,
+    /// Demand to every other node
+    demands: Vec<DemandAnnotation>,
+}
+
+/// Annotated nodes of a finished calculation
+type JobResult = Result<Vec<NodeAnnotation>, CoreError>;
+
+/// A calculation of the flows in a copy of a link graph (matches C++ LinkGraphJob)
+#[derive(Debug)]
+pub struct LinkGraphJob {
+    pub index: LinkGraphJobID,
+    /// Copy of the link graph taken when the job was created; keeps its index
+    pub link_graph: LinkGraph,
+    /// Copy of the game's link graph settings at spawn time
+    pub settings: LinkGraphSettings,
+    /// Date when the job is to be joined
+    pub join_date: EconomyDate,
+    state: Arc<JobState>,
+    thread: Option<JoinHandle<JobResult>>,
+    /// Result of a job that had to run on the main thread
+    result: Option<JobResult>,
+}
+
+impl PartialEq for LinkGraphJob {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+            && self.link_graph == other.link_graph
+            && self.settings == other.settings
+            && self.join_date == other.join_date
+    }
+}
+
+impl LinkGraphJob {
+    /// Create a job for a copy of `link_graph`, to be joined after the
+    /// configured recalculation time
+    pub fn new(
+        index: LinkGraphJobID,
+        link_graph: LinkGraph,
+        settings: LinkGraphSettings,
+        date: EconomyDate,
+    ) -> Self {
+        Self {
+            index,
+            link_graph,
+            settings,
+            join_date: EconomyDate(date.0 + (settings.recalc_time / SECONDS_PER_DAY) as i32),
+            state: Arc::default(),
+            thread: None,
+            result: None,
+        }
+    }
+
+    pub fn cargo(&self) -> CargoType {
+        self.link_graph.cargo
+    }
+
+    /// Run the job on a thread, or right away if no thread can be started
+    pub fn spawn_thread(&mut self, environment: JobEnvironment) {
+        let graph = self.link_graph.clone();
+        let settings = self.settings;
+        let join_date = self.join_date;
+        let state = Arc::clone(&self.state);
+        let spawned = thread::Builder::new()
+            .name("ottd:linkgraph".to_string())
+            .spawn(move || run(&graph, &settings, join_date, &environment, &state));
+        match spawned {
+            Ok(handle) => self.thread = Some(handle),
+            Err(_) => {
+                self.result = Some(run(
+                    &self.link_graph,
+                    &self.settings,
+                    self.join_date,
+                    &environment,
+                    &self.state,
+                ))
+            }
+        }
+    }
+
+    /// Whether the calculation has finished; may be stale while it runs
+    pub fn is_job_completed(&self) -> bool {
+        self.state.completed.load(Ordering::Acquire)
+    }
+
+    pub fn is_job_aborted(&self) -> bool {
+        self.state.aborted.load(Ordering::Acquire)
+    }
+
+    /// Ask the calculation to stop; the job must not be joined afterwards
+    pub fn abort_job(&self) {
+        self.state.aborted.store(true, Ordering::Release);
+    }
+
+    pub fn is_scheduled_to_be_joined(&self, date: EconomyDate) -> bool {
+        self.join_date.0 <= date.0
+    }
+
+    pub fn shift_join_date(&mut self, interval: i32) {
+        self.join_date.0 += interval;
+    }
+
+    /// Wait for the calculation and merge its flows into the stations that
+    /// are still part of the job's link graph. `distribution` is the game's
+    /// current distribution type for the cargo: flows that were not planned
+    /// again are invalidated, or deleted for manual distribution. Cargo
+    /// already routed along deleted flows is not rerouted here.
+    ///
+    /// A calculation that failed on an inconsistent link graph is returned
+    /// as an error and nothing is merged; a panic of the job's thread is
+    /// resumed on the caller's thread.
+    pub fn join(
+        mut self,
+        graphs: &[LinkGraph],
+        stations: &mut [Station],
+        distribution: DistributionType,
+    ) -> Result<(), CoreError> {
+        let result = match self.thread.take() {
+            Some(handle) => handle
+                .join()
+                .unwrap_or_else(|err| panic::resume_unwind(err)),
+            None => match self.result.take() {
+                Some(result) => result,
+                None => return Ok(()),
+            },
+        };
+        let mut nodes = result?;
+        if self.is_job_aborted() {
+            return Ok(());
+        }
+        // The link graph has been merged into another one
+        let Some(lg) = graphs.iter().find(|lg| lg.index == self.link_graph.index) else {
+            return Ok(());
+        };
+        let cargo = self.cargo().as_usize();
+        let find =
+            |stations: &[Station], id: StationID| stations.iter().position(|st| st.index == id);
+
+        for node_id in 0..self.link_graph.size() {
+            let from = &self.link_graph.nodes[node_id as usize];
+            let erase_flows = |nodes: &mut [NodeAnnotation]| {
+                for node in nodes.iter_mut() {
+                    node.flows.remove(&from.station);
+                }
+            };
+
+            // The station can have been deleted, merged or moved to another node
+            let Some(position) = find(stations, from.station) else {
+                erase_flows(&mut nodes);
+                continue;
+            };
+            let ge = &stations[position].goods[cargo];
+            if ge.link_graph != lg.index || ge.node != node_id {
+                erase_flows(&mut nodes);
+                continue;
+            }
+
+            let mut flows = std::mem::take(&mut nodes[node_id as usize].flows);
+            let mut geflows = ge.flow_stats();
+            let lg_node = lg.nodes.get(node_id as usize);
+            for (edge, &flow) in from.edges.iter().zip(&nodes[node_id as usize].edge_flows) {
+                if flow == 0 {
+                    continue;
+                }
+                let dest_id = edge.dest_node;
+                let to = self.link_graph.nodes[dest_id as usize].station;
+                let dest_valid = find(stations, to).is_some_and(|p| {
+                    let ge = &stations[p].goods[cargo];
+                    ge.link_graph == lg.index && ge.node == dest_id
+                });
+                let lg_edge = lg_node.and_then(|n| n.edge(dest_id));
+                match lg_edge {
+                    Some(lg_edge) if dest_valid && lg_edge.last_update() != INVALID_DATE => {
+                        if lg_edge.last_unrestricted_update == INVALID_DATE {
+                            flows.restrict_flows(to);
+                        }
+                    }
+                    _ => {
+                        // Also delete the old flows of sources that are gone from the
+                        // new ones, so that old and new flows can't form cycles
+                        for station in flows.delete_flows(to) {
+                            geflows.remove(&station);
+                        }
+                    }
+                }
+            }
+
+            // Swap in the new shares. Old flows that were not planned again are
+            // kept but invalidated, so that no cargo becomes unroutable
+            geflows.retain(|source, stat| match flows.remove(source) {
+                Some(mut new_stat) => {
+                    stat.swap_shares(&mut new_stat);
+                    true
+                }
+                None if distribution != DistributionType::Manual => {
+                    stat.invalidate();
+                    true
+                }
+                None => false,
+            });
+            geflows.extend(flows.0);
+            stations[position].goods[cargo].set_flow_stats(&geflows);
+        }
+        Ok(())
+    }
+}
+
+/// Run the stages of a job in order, stopping early when it is aborted
+/// (matches C++ LinkGraphSchedule::Run). A stage failing on an inconsistent
+/// graph aborts the job, which still counts as completed so that the game
+/// does not wait for it.
+fn run(
+    graph: &LinkGraph,
+    settings: &LinkGraphSettings,
+    join_date: EconomyDate,
+    environment: &JobEnvironment,
+    state: &JobState,
+) -> JobResult {
+    type Stage = fn(&mut Solver) -> Result<(), CoreError>;
+    const STAGES: [Stage; 5] = [
+        |solver| solver.calc_demands(),
+        |solver| solver.mcf_first_pass(),
+        |solver| {
+            solver.map_flows(false);
+            Ok(())
+        },
+        |solver| solver.mcf_second_pass(),
+        |solver| {
+            solver.map_flows(true);
+            Ok(())
+        },
+    ];
+
+    let mut solver = Solver::new(graph, settings, join_date, environment, state);
+    for stage in STAGES {
+        if state.aborted.load(Ordering::Acquire) {
+            return Ok(solver.nodes);
+        }
+        if let Err(err) = stage(&mut solver) {
+            state.aborted.store(true, Ordering::Release);
+            state.completed.store(true, Ordering::Release);
+            return Err(err);
+        }
+    }
+    state.completed.store(true, Ordering::Release);
+    Ok(solver.nodes)
+}
+
+/// One leg of a path through the link graph (matches C++ Path)
+#[derive(Debug, Clone)]
+struct Path {
+    /// Sum of the distances of all legs up to this one
+    distance: u32,
+    /// Minimum capacity of all legs
+    capacity: u32,
+    /// Minimum free capacity of all legs in the current run of Dijkstra
+    free_capacity: i32,
+    flow: u32,
+    node: NodeID,
+    origin: NodeID,
+    num_children: u32,
+    parent: Option<usize>,
+}
+
+impl Path {
+    const CAP_MULTIPLIER: i32 = 16;
+    const CAP_MIN_FREE: i32 = (i32::MIN + 1) / Self::CAP_MULTIPLIER;
+    const CAP_MAX_FREE: i32 = (i32::MAX - 1) / Self::CAP_MULTIPLIER;
+
+    fn new(node: NodeID, source: bool) -> Self {
+        Self {
+            distance: if source { 0 } else { u32::MAX },
+            capacity: if source { u32::MAX } else { 0 },
+            free_capacity: if source { i32::MAX } else { i32::MIN },
+            flow: 0,
+            node,
+            origin: if source { node } else { INVALID_NODE },
+            num_children: 0,
+            parent: None,
+        }
+    }
+
+    /// Ratio of free to total capacity; like the C++ version the division is
+    /// unsigned, so negative free capacities give large ratios
+    fn capacity_ratio(free: i32, total: u32) -> i32 {
+        let free = free.clamp(Self::CAP_MIN_FREE, Self::CAP_MAX_FREE) * Self::CAP_MULTIPLIER;
+        ((free as u32) / total.max(1)) as i32
+    }
+}
+
+/// How Dijkstra compares paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Annotation {
+    /// Shortest paths along the edges of the graph (matches C++ DistanceAnnotation)
+    Distance,
+    /// Paths with the most free capacity along the planned flows (matches C++ CapacityAnnotation)
+    Capacity,
+}
+
+/// Visit state of a node while searching for cycles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    Unvisited,
+    Searched,
+    Via(usize),
+}
+
+/// Working state of a job's calculation
+struct Solver<'a> {
+    graph: &'a LinkGraph,
+    settings: &'a LinkGraphSettings,
+    join_date: EconomyDate,
+    environment: &'a JobEnvironment,
+    state: &'a JobState,
+    nodes: Vec<NodeAnnotation>,
+    /// All paths, referenced by index
+    paths: Vec<Path>,
+    free_paths: Vec<usize>,
+    max_saturation: u32,
+}
+
+impl<'a> Solver<'a> {
+    fn new(
+        graph: &'a LinkGraph,
+        settings: &'a LinkGraphSettings,
+        join_date: EconomyDate,
+        environment: &'a JobEnvironment,
+        state: &'a JobState,
+    ) -> Self {
+        let size = graph.nodes.len();
+        let nodes = graph
+            .nodes
+            .iter()
+            .map(|node| NodeAnnotation {
+                undelivered_supply: node.supply,
+                edge_flows: vec![0; node.edges.len()],
+                demands: vec![DemandAnnotation::default(); size],
+                ..Default::default()
+            })
+            .collect();
+        Self {
+            graph,
+            settings,
+            join_date,
+            environment,
+            state,
+            nodes,
+            paths: Vec::new(),
+            free_paths: Vec::new(),
+            max_saturation: settings.short_path_saturation as u32,
+        }
+    }
+
+    fn size(&self) -> NodeID {
+        self.graph.size()
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.state.aborted.load(Ordering::Acquire)
+    }
+
+    fn distribution(&self) -> DistributionType {
+        self.settings
+            .distribution_type(self.environment.cargo_classes)
+    }
+
+    /// Position of the edge between two nodes in the edge lists
+    fn edge_index(&self, from: NodeID, to: NodeID) -> Option<usize> {
+        self.graph.nodes[from as usize]
+            .edges
+            .binary_search_by_key(&to, |e| e.dest_node)
+            .ok()
+    }
+
+    fn deliver_supply(&mut self, from: NodeID, to: NodeID, amount: u32) {
+        let node = &mut self.nodes[from as usize];
+        node.undelivered_supply -= amount;
+        node.demands[to as usize].demand += amount;
+        node.demands[to as usize].unsatisfied_demand += amount;
+    }
+
+    /// Distribute the supply of each node to the nodes with demand
+    /// (matches C++ DemandCalculator)
+    fn calc_demands(&mut self) -> Result<(), CoreError> {
+        let symmetric = match self.distribution() {
+            DistributionType::Symmetric => true,
+            DistributionType::Asymmetric => false,
+            DistributionType::Manual => return Ok(()),
+        };
+        let base_distance = self.environment.base_distance() as i32;
+        let accuracy = self.settings.accuracy as i32;
+        let mut mod_dist = self.settings.demand_distance as i32;
+        if mod_dist > 100 {
+            // Increase the effect of values above 100 quadratically
+            let over100 = mod_dist - 100;
+            mod_dist = 100 + over100 * over100 / 12;
+        }
+        let mod_size = self.settings.demand_size as u32;
+
+        let mut supplies = VecDeque::new();
+        let mut demands = VecDeque::new();
+        let mut supply_sum: u32 = 0;
+        for (id, node) in self.graph.nodes.iter().enumerate() {
+            supply_sum = supply_sum.wrapping_add(node.supply);
+            if node.supply > 0 {
+                supplies.push_back(id as NodeID);
+            }
+            if node.demand > 0 {
+                demands.push_back(id as NodeID);
+            }
+        }
+        let mut num_supplies = supplies.len() as u32;
+        let mut num_demands = demands.len() as u32;
+        if num_supplies == 0 || num_demands == 0 {
+            return Ok(());
+        }
+
+        // Mean acceptance attributed to each node for symmetric distribution
+        let demand_per_node = (supply_sum / num_demands).max(1);
+        let mut chance: u32 = 0;
+
+        while let Some(from_id) = supplies.pop_front() {
+            if demands.is_empty() {
+                break;
+            }
+            let mut i = 0;
+            while i < num_demands {
+                i += 1;
+                let to_id = demands
+                    .pop_front()
+                    .ok_or_else(|| inconsistent("demand queue out of sync"))?;
+                if from_id == to_id {
+                    // Only one node with supply and demand left
+                    if demands.is_empty() && supplies.is_empty() {
+                        return Ok(());
+                    }
+                    demands.push_back(to_id);
+                    continue;
+                }
+
+                let from = &self.graph.nodes[from_id as usize];
+                let to = &self.graph.nodes[to_id as usize];
+                let supply = if symmetric {
+                    ((from.supply as u64 * to.supply.max(1) as u64 * mod_size as u64
+                        / 100
+                        / demand_per_node as u64) as u32)
+                        .max(1)
+                } else {
+                    from.supply
+                } as i32;
+
+                const DIVISOR_SCALE: i32 = 16;
+                let mut scaled_distance = base_distance;
+                if mod_dist > 0 {
+                    let distance =
+                        self.environment.distance_max_plus_manhattan(from.xy, to.xy) as i32;
+                    // Scale the distance around base_distance by mod_dist * (100 / 1024)
+                    scaled_distance =
+                        (base_distance + (distance - base_distance) * mod_dist / 1024).max(0);
+                }
+                // Scale the accuracy by distance around accuracy / 2
+                let divisor = DIVISOR_SCALE
+                    + accuracy * scaled_distance * DIVISOR_SCALE / (base_distance * 2);
+
+                let mut demand_forw: u32 = 0;
+                if divisor <= supply * DIVISOR_SCALE {
+                    // At first only distribute demand if the effective supply
+                    // divided by the distance is large enough
+                    demand_forw = (supply * DIVISOR_SCALE / divisor) as u32;
+                } else {
+                    chance += 1;
+                    if chance > accuracy as u32 * num_demands * num_supplies {
+                        // After some trying, distribute the rest regardless of distance
+                        demand_forw = 1;
+                    }
+                }
+                demand_forw = demand_forw.min(self.nodes[from_id as usize].undelivered_supply);
+
+                if symmetric && from.demand > 0 {
+                    let mut demand_back = demand_forw * mod_size / 100;
+                    let undelivered = self.nodes[to_id as usize].undelivered_supply;
+                    if demand_back > undelivered {
+                        demand_back = undelivered;
+                        demand_forw = (demand_back * 100 / mod_size).max(1);
+                    }
+                    self.deliver_supply(to_id, from_id, demand_back);
+                }
+                self.deliver_supply(from_id, to_id, demand_forw);
+
+                let to = &self.graph.nodes[to_id as usize];
+                let demand_left = if symmetric {
+                    (to.supply == 0 || self.nodes[to_id as usize].undelivered_supply > 0)
+                        && to.demand > 0
+                } else {
+                    to.demand > 0
+                };
+                if demand_left {
+                    demands.push_back(to_id);
+                } else {
+                    num_demands -= 1;
+                }
+
+                if self.nodes[from_id as usize].undelivered_supply == 0 {
+                    break;
+                }
+            }
+
+            if self.nodes[from_id as usize].undelivered_supply != 0 {
+                supplies.push_back(from_id);
+            } else {
+                num_supplies -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn new_path(&mut self, node: NodeID, source: bool) -> usize {
+        let path = Path::new(node, source);
+        match self.free_paths.pop() {
+            Some(id) => {
+                self.paths[id] = path;
+                id
+            }
+            None => {
+                self.paths.push(path);
+                self.paths.len() - 1
+            }
+        }
+    }
+
+    fn detach(&mut self, id: usize) {
+        if let Some(parent) = self.paths[id].parent.take() {
+            self.paths[parent].num_children -= 1;
+        }
+    }
+
+    /// Make `id` a continuation of `base` along a leg with the given capacities
+    fn fork(&mut self, id: usize, base: usize, cap: u32, free_cap: i32, dist: u32) {
+        let base_path = self.paths[base].clone();
+        let path = &mut self.paths[id];
+        path.capacity = base_path.capacity.min(cap);
+        path.free_capacity = base_path.free_capacity.min(free_cap);
+        path.distance = base_path.distance.wrapping_add(dist);
+        path.origin = base_path.origin;
+        if path.parent != Some(base) {
+            self.detach(id);
+            self.paths[id].parent = Some(base);
+            self.paths[base].num_children += 1;
+        }
+    }
+
+    /// Whether forking `id` from `base` improves it
+    fn is_better(
+        &self,
+        annotation: Annotation,
+        id: usize,
+        base: usize,
+        cap: u32,
+        free_cap: i32,
+        dist: u32,
+    ) -> bool {
+        let path = &self.paths[id];
+        let base = &self.paths[base];
+        match annotation {
+            Annotation::Distance => {
+                // A disconnected path is worse than any other
+                if base.distance == u32::MAX {
+                    false
+                } else if path.distance == u32::MAX {
+                    true
+                } else if free_cap > 0 && base.free_capacity > 0 {
+                    // Prefer paths with free capacity, then the shorter one
+                    path.free_capacity <= 0 || base.distance.wrapping_add(dist) < path.distance
+                } else {
+                    path.free_capacity <= 0 && base.distance.wrapping_add(dist) < path.distance
+                }
+            }
+            Annotation::Capacity => {
+                let min_cap =
+                    Path::capacity_ratio(base.free_capacity.min(free_cap), base.capacity.min(cap));
+                let this_cap = Path::capacity_ratio(path.free_capacity, path.capacity);
+                if min_cap == this_cap {
+                    base.distance != u32::MAX && base.distance.wrapping_add(dist) < path.distance
+                } else {
+                    min_cap > this_cap
+                }
+            }
+        }
+    }
+
+    /// Key ordering the nodes Dijkstra visits next first
+    fn annotation_key(&self, annotation: Annotation, id: usize) -> (i64, i64) {
+        let path = &self.paths[id];
+        match annotation {
+            Annotation::Distance => (path.distance as i64, path.node as i64),
+            Annotation::Capacity => (
+                -(Path::capacity_ratio(path.free_capacity, path.capacity) as i64),
+                -(path.node as i64),
+            ),
+        }
+    }
+
+    /// Next hops of `node` for paths from `source`: the graph's edges when
+    /// searching by distance, the planned flows when searching by capacity
+    fn next_hops(
+        &self,
+        annotation: Annotation,
+        source: NodeID,
+        node: NodeID,
+        station_to_node: &BTreeMap<StationID, NodeID>,
+    ) -> Vec<NodeID> {
+        match annotation {
+            Annotation::Distance => self.graph.nodes[node as usize]
+                .edges
+                .iter()
+                .map(|e| e.dest_node)
+                .collect(),
+            Annotation::Capacity => {
+                let source_station = self.graph.nodes[source as usize].station;
+                self.nodes[node as usize]
+                    .flows
+                    .get(&source_station)
+                    .map(|stat| {
+                        stat.shares()
+                            .values()
+                            .filter_map(|via| station_to_node.get(via).copied())
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    /// Find the best paths from `source_node` to all other nodes
+    /// (matches C++ MultiCommodityFlow::Dijkstra)
+    fn dijkstra(&mut self, annotation: Annotation, source_node: NodeID) -> Vec<Option<usize>> {
+        let size = self.size();
+        let station_to_node: BTreeMap<StationID, NodeID> = match annotation {
+            Annotation::Distance => BTreeMap::new(),
+            Annotation::Capacity => (0..size)
+                .map(|node| (self.graph.nodes[node as usize].station, node))
+                .collect(),
+        };
+        let express = self
+            .environment
+            .cargo_classes
+            .intersects(CargoClasses::PASSENGERS | CargoClasses::MAIL | CargoClasses::EXPRESS);
+
+        let mut annos = BTreeSet::new();
+        let mut paths = Vec::with_capacity(size as usize);
+        for node in 0..size {
+            let id = self.new_path(node, node == source_node);
+            annos.insert((self.annotation_key(annotation, id), id));
+            paths.push(Some(id));
+        }
+
+        while let Some((_, source)) = annos.pop_first() {
+            let from = self.paths[source].node;
+            for to in self.next_hops(annotation, source_node, from, &station_to_node) {
+                // Not a real edge but a sign of local consumption
+                if to == from {
+                    continue;
+                }
+                let Some(edge_index) = self.edge_index(from, to) else {
+                    continue;
+                };
+                let edge = &self.graph.nodes[from as usize].edges[edge_index];
+                let mut capacity = edge.capacity;
+                if self.max_saturation != u32::MAX {
+                    capacity = capacity.wrapping_mul(self.max_saturation) / 100;
+                    if capacity == 0 {
+                        capacity = 1;
+                    }
+                }
+                // Prioritize the fastest route for passengers, mail and express
+                // cargo, and the shortest route for other cargo
+                let distance = self.environment.distance_max_plus_manhattan(
+                    self.graph.nodes[from as usize].xy,
+                    self.graph.nodes[to as usize].xy,
+                ) + 1;
+                // Assume an average speed of 1 tile per day without a travel time
+                let time = match edge.travel_time() {
+                    0 => distance * DAY_TICKS,
+                    travel_time => travel_time + DAY_TICKS,
+                };
+                let distance_anno = if express { time } else { distance };
+                let free_capacity =
+                    capacity.wrapping_sub(self.nodes[from as usize].edge_flows[edge_index]) as i32;
+
+                let Some(dest) = paths[to as usize] else {
+                    continue;
+                };
+                if self.is_better(
+                    annotation,
+                    dest,
+                    source,
+                    capacity,
+                    free_capacity,
+                    distance_anno,
+                ) {
+                    annos.remove(&(self.annotation_key(annotation, dest), dest));
+                    self.fork(dest, source, capacity, free_capacity, distance_anno);
+                    annos.insert((self.annotation_key(annotation, dest), dest));
+                }
+            }
+        }
+        paths
+    }
+
+    /// Free the paths of a Dijkstra run that don't carry any flow
+    /// (matches C++ MultiCommodityFlow::CleanupPaths)
+    fn cleanup_paths(&mut self, source_id: NodeID, paths: &mut Vec<Option<usize>>) {
+        let Some(source) = paths[source_id as usize].take() else {
+            return;
+        };
+        for i in 0..paths.len() {
+            let Some(path) = paths[i] else { continue };
+            if self.paths[path].parent == Some(source) {
+                self.detach(path);
+            }
+            let mut current = Some(path);
+            while let Some(id) = current {
+                if id == source || self.paths[id].flow != 0 {
+                    break;
+                }
+                let parent = self.paths[id].parent;
+                self.detach(id);
+                if self.paths[id].num_children == 0 {
+                    paths[self.paths[id].node as usize] = None;
+                    self.free_paths.push(id);
+                }
+                current = parent;
+            }
+        }
+        self.free_paths.push(source);
+        paths.clear();
+    }
+
+    /// Push flow along a path and register it with the nodes it passes;
+    /// returns the flow actually pushed (matches C++ Path::AddFlow)
+    fn add_path_flow(
+        &mut self,
+        id: usize,
+        mut new_flow: u32,
+        max_saturation: u32,
+    ) -> Result<u32, CoreError> {
+        if let Some(parent) = self.paths[id].parent {
+            let from = self.paths[parent].node;
+            let edge_index = self
+                .edge_index(from, self.paths[id].node)
+                .ok_or_else(|| inconsistent("path along a missing edge"))?;
+            let edge_flow = self.nodes[from as usize].edge_flows[edge_index];
+            if max_saturation != u32::MAX {
+                let capacity = self.graph.nodes[from as usize].edges[edge_index].capacity;
+                let usable_cap = capacity.wrapping_mul(max_saturation) / 100;
+                if usable_cap > edge_flow {
+                    new_flow = new_flow.min(usable_cap - edge_flow);
+                } else {
+                    return Ok(0);
+                }
+            }
+            new_flow = self.add_path_flow(parent, new_flow, max_saturation)?;
+            if self.paths[id].flow == 0 && new_flow > 0 {
+                self.nodes[from as usize].paths.push_front(id);
+            }
+            self.nodes[from as usize].edge_flows[edge_index] += new_flow;
+        }
+        self.paths[id].flow += new_flow;
+        Ok(new_flow)
+    }
+
+    /// Push a part of the demand between two nodes along a path
+    /// (matches C++ MultiCommodityFlow::PushFlow)
+    fn push_flow(
+        &mut self,
+        from: NodeID,
+        to: NodeID,
+        path: usize,
+        max_saturation: u32,
+    ) -> Result<u32, CoreError> {
+        let accuracy = (self.settings.accuracy as u32).max(1);
+        let demand = self.nodes[from as usize].demands[to as usize];
+        debug_assert!(demand.unsatisfied_demand > 0);
+        let flow = (demand.demand / accuracy).clamp(1, demand.unsatisfied_demand);
+        let flow = self.add_path_flow(path, flow, max_saturation)?;
+        self.nodes[from as usize].demands[to as usize].unsatisfied_demand -= flow;
+        Ok(flow)
+    }
+
+    /// Saturate the shortest paths first, then route the remaining demand
+    /// over overloaded paths (matches C++ MCF1stPass)
+    fn mcf_first_pass(&mut self) -> Result<(), CoreError> {
+        let size = self.size();
+        let mut finished_sources = vec![false; size as usize];
+        loop {
+            let mut more_loops = false;
+            for source in 0..size {
+                if finished_sources[source as usize] {
+                    continue;
+                }
+                let mut paths = self.dijkstra(Annotation::Distance, source);
+                let mut source_demand_left = false;
+                for dest in 0..size {
+                    let demand = self.nodes[source as usize].demands[dest as usize];
+                    if demand.unsatisfied_demand == 0 {
+                        continue;
+                    }
+                    let path = paths[dest as usize]
+                        .ok_or_else(|| inconsistent("Dijkstra left a node without path"))?;
+                    let free_capacity = self.paths[path].free_capacity;
+                    // Only allow paths that don't exceed the saturation limit at first
+                    if free_capacity > 0
+                        && self.push_flow(source, dest, path, self.max_saturation)? > 0
+                    {
+                        // More flow may fit along the same path in the next loop
+                        more_loops = more_loops
+                            || self.nodes[source as usize].demands[dest as usize]
+                                .unsatisfied_demand
+                                > 0;
+                    } else if demand.unsatisfied_demand == demand.demand && free_capacity > i32::MIN
+                    {
+                        // Nothing could be assigned yet, so overload the shortest path
+                        self.push_flow(source, dest, path, u32::MAX)?;
+                    }
+                    if self.nodes[source as usize].demands[dest as usize].unsatisfied_demand > 0 {
+                        source_demand_left = true;
+                    }
+                }
+                finished_sources[source as usize] = !source_demand_left;
+                self.cleanup_paths(source, &mut paths);
+            }
+            if !(more_loops || self.eliminate_cycles()) || self.is_aborted() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Route the demand left after the first pass along the flows planned
+    /// so far, preferring those with free capacity (matches C++ MCF2ndPass)
+    fn mcf_second_pass(&mut self) -> Result<(), CoreError> {
+        // Disable the artificial cap on saturation
+        self.max_saturation = u32::MAX;
+        let size = self.size();
+        let mut finished_sources = vec![false; size as usize];
+        let mut demand_left = true;
+        while demand_left && !self.is_aborted() {
+            demand_left = false;
+            for source in 0..size {
+                if finished_sources[source as usize] {
+                    continue;
+                }
+                let mut paths = self.dijkstra(Annotation::Capacity, source);
+                let mut source_demand_left = false;
+                for dest in 0..size {
+                    let Some(path) = paths[dest as usize] else {
+                        continue;
+                    };
+                    if self.nodes[source as usize].demands[dest as usize].unsatisfied_demand > 0
+                        && self.paths[path].free_capacity > i32::MIN
+                    {
+                        self.push_flow(source, dest, path, u32::MAX)?;
+                        if self.nodes[source as usize].demands[dest as usize].unsatisfied_demand > 0
+                        {
+                            demand_left = true;
+                            source_demand_left = true;
+                        }
+                    }
+                }
+                finished_sources[source as usize] = !source_demand_left;
+                self.cleanup_paths(source, &mut paths);
+            }
+        }
+        Ok(())
+    }
+
+    /// Minimum flow along the cycle starting at `cycle_begin`
+    fn find_cycle_flow(&self, path: &[Visit], cycle_begin: usize) -> u32 {
+        let mut flow = u32::MAX;
+        let mut current = cycle_begin;
+        loop {
+            flow = flow.min(self.paths[current].flow);
+            match path[self.paths[current].node as usize] {
+                Visit::Via(next) if next != cycle_begin => current = next,
+                _ => return flow,
+            }
+        }
+    }
+
+    /// Remove `flow` from all paths and edges of a cycle
+    fn eliminate_cycle(&mut self, path: &[Visit], cycle_begin: usize, flow: u32) {
+        let mut current = cycle_begin;
+        loop {
+            let prev = self.paths[current].node;
+            self.paths[current].flow -= flow;
+            if self.paths[current].flow == 0 {
+                // Keep the paths with flow at the front of the list
+                if let Some(parent) = self.paths[current].parent {
+                    let node_paths = &mut self.nodes[self.paths[parent].node as usize].paths;
+                    if let Some(i) = node_paths.iter().position(|&p| p == current) {
+                        node_paths.remove(i);
+                        node_paths.push_back(current);
+                    }
+                }
+            }
+            let Visit::Via(next) = path[prev as usize] else {
+                return;
+            };
+            current = next;
+            if let Some(edge_index) = self.edge_index(prev, self.paths[current].node) {
+                self.nodes[prev as usize].edge_flows[edge_index] -= flow;
+            }
+            if current == cycle_begin {
+                return;
+            }
+        }
+    }
+
+    /// Eliminate the cycles of flow from `origin_id` reachable from `next_id`
+    fn eliminate_cycles_from(
+        &mut self,
+        path: &mut [Visit],
+        origin_id: NodeID,
+        next_id: NodeID,
+    ) -> bool {
+        match path[next_id as usize] {
+            // This node has already been searched
+            Visit::Searched => false,
+            Visit::Unvisited => {
+                // Add up the paths with the same origin and next hop
+                let mut next_hops: BTreeMap<NodeID, usize> = BTreeMap::new();
+                let mut i = 0;
+                while i < self.nodes[next_id as usize].paths.len() {
+                    let new_child = self.nodes[next_id as usize].paths[i];
+                    let new_flow = self.paths[new_child].flow;
+                    if new_flow == 0 {
+                        break;
+                    }
+                    if self.paths[new_child].origin != origin_id {
+                        i += 1;
+                        continue;
+                    }
+                    match next_hops.get(&self.paths[new_child].node) {
+                        None => {
+                            next_hops.insert(self.paths[new_child].node, new_child);
+                            i += 1;
+                        }
+                        Some(&child) => {
+                            self.paths[child].flow += new_flow;
+                            self.paths[new_child].flow -= new_flow;
+                            let node_paths = &mut self.nodes[next_id as usize].paths;
+                            node_paths.remove(i);
+                            node_paths.push_back(new_child);
+                        }
+                    }
+                }
+
+                // Search the next hops for nodes already visited
+                let mut found = false;
+                for child in next_hops.into_values() {
+                    if self.paths[child].flow > 0 {
+                        path[next_id as usize] = Visit::Via(child);
+                        found = self.eliminate_cycles_from(path, origin_id, self.paths[child].node)
+                            || found;
+                    }
+                }
+                // Nodes in a cycle may be visited again for the next cycle
+                path[next_id as usize] = if found {
+                    Visit::Unvisited
+                } else {
+                    Visit::Searched
+                };
+                found
+            }
+            // This node has already been visited, so there is a cycle
+            Visit::Via(at_next_pos) => {
+                let flow = self.find_cycle_flow(path, at_next_pos);
+                if flow > 0 {
+                    self.eliminate_cycle(path, at_next_pos, flow);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Eliminate all cycles of flow in the graph (matches C++ MCF1stPass::EliminateCycles)
+    fn eliminate_cycles(&mut self) -> bool {
+        let size = self.size();
+        let mut path = vec![Visit::Unvisited; size as usize];
+        let mut cycles_found = false;
+        for node in 0..size {
+            path.fill(Visit::Unvisited);
+            cycles_found |= self.eliminate_cycles_from(&mut path, node, node);
+        }
+        cycles_found
+    }
+
+    /// Turn the paths found by the solver into flows per station
+    /// (matches C++ FlowMapper)
+    fn map_flows(&mut self, scale: bool) {
+        let size = self.size();
+        for node_id in 0..size {
+            let prev = self.graph.nodes[node_id as usize].station;
+            let node_paths: Vec<usize> =
+                self.nodes[node_id as usize].paths.iter().copied().collect();
+            for id in node_paths {
+                let path = &self.paths[id];
+                let flow = path.flow;
+                if flow == 0 {
+                    break;
+                }
+                let path_node = path.node;
+                let via = self.graph.nodes[path_node as usize].station;
+                let origin = self.graph.nodes[path.origin as usize].station;
+                debug_assert!(prev != via && via != origin);
+                // Mark all of the flow for local consumption at the node
+                self.nodes[path_node as usize]
+                    .flows
+                    .add_flow(origin, via, flow);
+                if prev != origin {
+                    // Pass on some of the flow marked for local consumption at prev
+                    self.nodes[node_id as usize]
+                        .flows
+                        .pass_on_flow(origin, via, flow);
+                } else {
+                    self.nodes[node_id as usize]
+                        .flows
+                        .add_flow(origin, via, flow);
+                }
+            }
+        }
+
+        // Scale by the time the graph has been running without being
+        // compressed; add 1 to avoid dividing by 0
+        let runtime = self.join_date.0
+            - (self.settings.recalc_time / SECONDS_PER_DAY) as i32
+            - self.graph.last_compression.0
+            + 1;
+        for node_id in 0..size {
+            let station = self.graph.nodes[node_id as usize].station;
+            let node = &mut self.nodes[node_id as usize];
+            node.flows.finalize_local_consumption(station);
+            if scale {
+                node.flows
+                    .values_mut()
+                    .for_each(|stat: &mut FlowStat| stat.scale_to_monthly(runtime as u32));
+            }
+            node.paths.clear();
+        }
+        // All remaining paths were referenced by the nodes
+        self.paths.clear();
+        self.free_paths.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linkgraph::EdgeUpdateModes;
+    use crate::types::Owner;
+
+    fn environment() -> JobEnvironment {
+        JobEnvironment {
+            map_size_x: 256,
+            map_size_y: 256,
+            cargo_classes: CargoClasses::PASSENGERS,
+        }
+    }
+
+    /// Three stations in a row, linked in both directions
+    fn line_graph() -> LinkGraph {
+        let mut lg = LinkGraph::new(3, CargoType(0), EconomyDate(100));
+        let modes = EdgeUpdateModes::INCREASE | EdgeUpdateModes::UNRESTRICTED;
+        for (i, x) in [10u32, 30, 50].into_iter().enumerate() {
+            let mut node = crate::linkgraph::LinkGraphNode::new(
+                TileIndex(20 * 256 + x),
+                StationID(i as u16 + 1),
+                1,
+            );
+            node.update_supply(100, EconomyDate(100));
+            lg.nodes.push(node);
+        }
+        for (from, to) in [(0, 1), (1, 0), (1, 2), (2, 1)] {
+            lg.nodes[from].update_edge(to, 400, 0, 0, modes, EconomyDate(100));
+        }
+        lg
+    }
+
+    fn settings(distribution: DistributionType) -> LinkGraphSettings {
+        LinkGraphSettings {
+            distribution_pax: distribution,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_int_sqrt() {
+        assert_eq!(int_sqrt(0), 0);
+        assert_eq!(int_sqrt(16), 4);
+        assert_eq!(int_sqrt(20), 4);
+        assert_eq!(int_sqrt(21), 5);
+        assert_eq!(environment().base_distance(), 28);
+    }
+
+    #[test]
+    fn test_symmetric_flows() {
+        let lg = line_graph();
+        let settings = settings(DistributionType::Symmetric);
+        let state = JobState::default();
+        let nodes = run(&lg, &settings, EconomyDate(116), &environment(), &state).unwrap();
+        assert!(state.completed.load(Ordering::Acquire));
+
+        // All demand is routed, the same amount in each direction
+        for (from, node) in nodes.iter().enumerate() {
+            for (to, demand) in node.demands.iter().enumerate() {
+                assert_eq!(demand.unsatisfied_demand, 0);
+                assert_eq!(demand.demand, nodes[to].demands[from].demand);
+            }
+        }
+
+        // Cargo from station 1 to station 3 passes station 2
+        let via_middle = nodes[1].flows.get_flow_from_via(StationID(1), StationID(3));
+        // The flows are scaled from the one day the graph ran to a month
+        assert_eq!(via_middle, nodes[0].demands[2].demand * 30);
+        assert!(via_middle > 0);
+        assert_eq!(
+            nodes[0].flows.get_flow_from(StationID(1)),
+            nodes[0].edge_flows[0] * 30
+        );
+        // The middle station only sends cargo of its own directly
+        assert_eq!(
+            nodes[1].flows.get_flow_from_via(StationID(2), StationID(2)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_manual_distribution() {
+        let lg = line_graph();
+        let state = JobState::default();
+        let nodes = run(
+            &lg,
+            &LinkGraphSettings::default(),
+            EconomyDate(116),
+            &environment(),
+            &state,
+        )
+        .unwrap();
+        assert!(nodes.iter().all(|node| node.flows.is_empty()));
+    }
+
+    #[test]
+    fn test_join() {
+        let lg = line_graph();
+        let mut stations: Vec<Station> = (1..=3)
+            .map(|i| {
+                let mut st = Station::new(StationID(i), TileIndex(0), Owner::Company0);
+                st.goods[0].link_graph = lg.index;
+                st.goods[0].node = i - 1;
+                st
+            })
+            .collect();
+        // A stale flow of a source that is not planned again
+        let mut stale = FlowStatMap::default();
+        stale.add_flow(StationID(9), StationID(2), 5);
+        stations[0].goods[0].set_flow_stats(&stale);
+
+        let settings = settings(DistributionType::Asymmetric);
+        let mut job = LinkGraphJob::new(0, lg.clone(), settings, EconomyDate(100));
+        assert_eq!(job.join_date, EconomyDate(116));
+        assert!(!job.is_scheduled_to_be_joined(EconomyDate(115)));
+        job.spawn_thread(environment());
+        job.join(&[lg], &mut stations, DistributionType::Asymmetric)
+            .unwrap();
+
+        let flows = stations[0].goods[0].flow_stats();
+        assert!(flows.get_flow_from_via(StationID(1), StationID(2)) > 0);
+        // The stale flow is kept but invalidated
+        assert_eq!(flows[&StationID(9)].total(), 1);
+        assert!(
+            stations[2].goods[0]
+                .flow_stats()
+                .get_flow_from(StationID(3))
+                > 0
+        );
+    }
+
+    #[test]
+    fn test_join_merged_graph() {
+        let lg = line_graph();
+        let mut stations = vec![Station::new(StationID(1), TileIndex(0), Owner::Company0)];
+        let mut job = LinkGraphJob::new(
+            0,
+            lg,
+            settings(DistributionType::Symmetric),
+            EconomyDate(100),
+        );
+        job.spawn_thread(environment());
+        // The graph is gone, so nothing is merged
+        job.join(&[], &mut stations, DistributionType::Symmetric)
+            .unwrap();
+        assert!(stations[0].goods[0].flows.is_empty());
+    }
+
+    #[test]
+    fn test_join_failed_job() {
+        let lg = line_graph();
+        let mut stations = vec![Station::new(StationID(1), TileIndex(0), Owner::Company0)];
+        let settings = settings(DistributionType::Symmetric);
+
+        // A calculation that failed is reported and nothing is merged
+        let mut job = LinkGraphJob::new(0, lg.clone(), settings, EconomyDate(100));
+        job.result = Some(Err(inconsistent("path along a missing edge")));
+        assert!(job
+            .join(
+                std::slice::from_ref(&lg),
+                &mut stations,
+                DistributionType::Symmetric
+            )
+            .is_err());
+        assert!(stations[0].goods[0].flows.is_empty());
+
+        // A panic of the job's thread reaches the caller
+        let mut job = LinkGraphJob::new(1, lg.clone(), settings, EconomyDate(100));
+        job.thread = Some(thread::spawn(|| panic!("solver bug")));
+        let joined = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            job.join(&[lg], &mut stations, DistributionType::Symmetric)
+        }));
+        assert!(joined.is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Scheduling of link graph jobs
+//!
+//! Link graphs take turns: every recalculation interval the next graph in
+//! the schedule is copied into a job, and half an interval later the oldest
+//! running job is joined once its join date has come.
+
+use crate::error::CoreError;
+use crate::linkgraph::{DistributionType, LinkGraph, LinkGraphID, LinkGraphSettings};
+use crate::linkgraphjob::{JobEnvironment, LinkGraphJob, LinkGraphJobID};
+use crate::station::Station;
+use crate::types::{CargoType, EconomyDate};
+use std::collections::VecDeque;
+
+/// Link graphs waiting for a job and the jobs running (matches C++ LinkGraphSchedule)
+#[derive(Debug, Default)]
+pub struct LinkGraphSchedule {
+    /// Link graphs in the order they get a job
+    pub schedule: VecDeque<LinkGraphID>,
+    /// Running jobs, oldest first
+    pub running: VecDeque<LinkGraphJob>,
+}
+
+impl LinkGraphSchedule {
+    /// Tick of the day when jobs are spawned or joined
+    pub const SPAWN_JOIN_TICK: u32 = 21;
+
+    pub fn queue(&mut self, id: LinkGraphID) {
+        self.schedule.push_back(id);
+    }
+
+    pub fn dequeue(&mut self, id: LinkGraphID) {
+        self.schedule.retain(|&queued| queued != id);
+    }
+
+    /// Lowest job index not used by a running job
+    fn free_job_index(&self) -> LinkGraphJobID {
+        (0..=LinkGraphJobID::MAX)
+            .find(|&index| self.running.iter().all(|job| job.index != index))
+            .expect("link graph job pool is full")
+    }
+
+    /// Start a job for the first graph in the schedule with at least two
+    /// nodes; `environment` describes the game for a job's cargo
+    pub fn spawn_next(
+        &mut self,
+        graphs: &[LinkGraph],
+        settings: &LinkGraphSettings,
+        date: EconomyDate,
+        environment: impl Fn(CargoType) -> JobEnvironment,
+    ) {
+        let size = |id: LinkGraphID| {
+            graphs
+                .iter()
+                .find(|lg| lg.index == id)
+                .map_or(0, |lg| lg.nodes.len())
+        };
+        // Graphs that are too small to calculate anything go to the back
+        let mut skipped = 0;
+        while self.schedule.front().is_some_and(|&id| size(id) < 2) {
+            skipped += 1;
+            if skipped == self.schedule.len() {
+                return;
+            }
+            self.schedule.rotate_left(1);
+        }
+        let Some(id) = self.schedule.pop_front() else {
+            return;
+        };
+        let Some(lg) = graphs.iter().find(|lg| lg.index == id) else {
+            return;
+        };
+
+        let mut job = LinkGraphJob::new(self.free_job_index(), lg.clone(), *settings, date);
+        job.spawn_thread(environment(lg.cargo));
+        self.running.push_back(job);
+    }
+
+    /// Whether the oldest job is due to be joined but has not finished, so
+    /// that the game has to wait for it
+    pub fn is_join_with_unfinished_job_due(&self, date: EconomyDate) -> bool {
+        self.running
+            .front()
+            .is_some_and(|job| job.is_scheduled_to_be_joined(date) && !job.is_job_completed())
+    }
+
+    /// Join the oldest job if it is due and put its graph back into the schedule;
+    /// `distribution` gives the game's current distribution type for a cargo.
+    /// The graph is queued again even if its job failed.
+    pub fn join_next(
+        &mut self,
+        graphs: &[LinkGraph],
+        stations: &mut [Station],
+        date: EconomyDate,
+        distribution: impl Fn(CargoType) -> DistributionType,
+    ) -> Result<(), CoreError> {
+        if !self
+            .running
+            .front()
+            .is_some_and(|job| job.is_scheduled_to_be_joined(date))
+        {
+            return Ok(());
+        }
+        let job = self.running.pop_front().unwrap();
+        let id = job.link_graph.index;
+        let cargo = job.cargo();
+        let result = job.join(graphs, stations, distribution(cargo));
+        if graphs.iter().any(|lg| lg.index == id) {
+            // Dequeue first so that recycled IDs are not queued twice
+            self.dequeue(id);
+            self.queue(id);
+        }
+        result
+    }
+
+    /// Start the threads of all running jobs, e.g. after loading a game
+    pub fn spawn_all(&mut self, environment: impl Fn(CargoType) -> JobEnvironment) {
+        for job in &mut self.running {
+            job.spawn_thread(environment(job.cargo()));
+        }
+    }
+
+    /// Abort all jobs and empty the schedule
+    pub fn clear(&mut self) {
+        for job in &self.running {
+            job.abort_job();
+        }
+        self.running.clear();
+        self.schedule.clear();
+    }
+
+    /// Move the dates of all graphs and jobs by `interval` days
+    pub fn shift_dates(&mut self, graphs: &mut [LinkGraph], interval: i32) {
+        for lg in graphs {
+            lg.shift_dates(interval);
+        }
+        for job in &mut self.running {
+            job.shift_join_date(interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linkgraph::LinkGraphNode;
+    use crate::map::TileIndex;
+    use crate::types::{CargoClasses, StationID};
+
+    fn environment(_: CargoType) -> JobEnvironment {
+        JobEnvironment {
+            map_size_x: 64,
+            map_size_y: 64,
+            cargo_classes: CargoClasses::empty(),
+        }
+    }
+
+    fn graph(index: LinkGraphID, size: u16) -> LinkGraph {
+        let mut lg = LinkGraph::new(index, CargoType(0), EconomyDate(0));
+        for i in 0..size {
+            lg.nodes
+                .push(LinkGraphNode::new(TileIndex(i as u32), StationID(i), 0));
+        }
+        lg
+    }
+
+    #[test]
+    fn test_schedule() {
+        let mut graphs = vec![graph(0, 1), graph(1, 2)];
+        let mut schedule = LinkGraphSchedule::default();
+        schedule.queue(0);
+        schedule.queue(1);
+        let settings = LinkGraphSettings::default();
+
+        // The single node graph is skipped
+        schedule.spawn_next(&graphs, &settings, EconomyDate(10), environment);
+        assert_eq!(schedule.schedule, [0]);
+        assert_eq!(schedule.running.len(), 1);
+        assert_eq!(schedule.running[0].join_date, EconomyDate(26));
+
+        // Nothing left to spawn
+        schedule.spawn_next(&graphs, &settings, EconomyDate(18), environment);
+        assert_eq!(schedule.running.len(), 1);
+
+        schedule.shift_dates(&mut graphs, -5);
+        assert_eq!(graphs[1].last_compression, EconomyDate(-5));
+        assert!(!schedule.is_join_with_unfinished_job_due(EconomyDate(20)));
+        schedule
+            .join_next(&graphs, &mut [], EconomyDate(20), |_| {
+                DistributionType::Manual
+            })
+            .unwrap();
+        assert_eq!(schedule.running.len(), 1);
+        schedule
+            .join_next(&graphs, &mut [], EconomyDate(21), |_| {
+                DistributionType::Manual
+            })
+            .unwrap();
+        assert!(schedule.running.is_empty());
+        assert_eq!(schedule.schedule, [0, 1]);
+
+        schedule.spawn_next(&graphs, &settings, EconomyDate(30), environment);
+        schedule.clear();
+        assert!(schedule.schedule.is_empty() && schedule.running.is_empty());
+    }
+}
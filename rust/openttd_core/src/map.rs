@@ -7,6 +7,9 @@ use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::fmt;
 
+/// Size of a tile in world coordinates (matches C++ TILE_SIZE)
+pub const TILE_SIZE: u32 = 16;
+
 /// Type-safe wrapper for tile indices (matches C++ StrongType<uint32_t, TileIndexTag>)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(transparent)]
@@ -76,7 +79,7 @@ pub enum Direction {
 }
 
 /// Base tile data structure (8 bytes, matches C++ Tile::TileBase)
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(C)]
 pub struct TileBase {
     /// Tile type (bits 4-7), bridge above (2-3), climate zone (0-1)
@@ -133,7 +136,7 @@ impl TileBase {
 }
 
 /// Extended tile data (4 bytes, matches C++ Tile::TileExtended)
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(C)]
 pub struct TileExtended {
     /// General purpose field 6 (NewGRF support)
@@ -145,7 +148,7 @@ pub struct TileExtended {
 }
 
 /// Complete tile structure (12 bytes total, matches C++ Tile)
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(C)]
 pub struct Tile {
     /// Base tile information (8 bytes)
@@ -197,6 +200,7 @@ impl Tile {
 }
 
 /// Map dimensions and management (matches C++ Map static class)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Map {
     /// Logarithmic X size (actual size is 1 << log_x)
     pub log_x: u32,
@@ -277,6 +281,11 @@ impl Map {
     pub fn tile_y(&self, index: TileIndex) -> u32 {
         index.0 / self.size_x
     }
+
+    /// Manhattan distance between two tiles (matches C++ DistanceManhattan)
+    pub fn distance_manhattan(&self, a: TileIndex, b: TileIndex) -> u32 {
+        self.tile_x(a).abs_diff(self.tile_x(b)) + self.tile_y(a).abs_diff(self.tile_y(b))
+    }
 }
 
 #[cfg(test)]
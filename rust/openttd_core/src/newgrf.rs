@@ -0,0 +1,329 @@
+//! NewGRF configuration data structures for OpenTTD
+//!
+//! A savegame lists the NewGRFs it was played with by GRF ID and MD5 sum.
+//! This module also reads those identifiers from NewGRF files, to find the
+//! files a savegame needs the way the game does when loading it.
+
+use crate::endian::BigEndianReader;
+use crate::error::CoreError;
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of parameters of a NewGRF (matches C++ GRFConfig::MAX_NUM_PARAMS)
+pub const MAX_NUM_PARAMS: usize = 0x80;
+
+/// Palette the game uses for the NewGRF (matches C++ GRFPalette)
+pub const GRFP_USE_WINDOWS: u8 = 0x1;
+pub const GRFP_USE_MASK: u8 = 0x1;
+/// Palettes the NewGRF says it can be used with
+pub const GRFP_GRF_DOS: u8 = 0x1 << 2;
+pub const GRFP_GRF_WINDOWS: u8 = 0x2 << 2;
+pub const GRFP_GRF_MASK: u8 = GRFP_GRF_DOS | GRFP_GRF_WINDOWS;
+/// The NewGRF prefers a 32 bpp blitter
+pub const GRFP_BLT_32BPP: u8 = 0x1 << 4;
+
+/// Signature following the two zero bytes at the start of a container version 2 file
+const GRF_CONT_V2_SIG: [u8; 8] = [b'G', b'R', b'F', 0x82, 0x0D, 0x0A, 0x1A, 0x0A];
+/// Length of the container version 2 header up to the sprite section offset
+const GRF_CONT_V2_HEADER_LEN: usize = 14;
+
+/// A NewGRF used by a game, as saved in the NGRF chunk
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GrfConfig {
+    /// Path of the file relative to the content directory it was found in
+    pub filename: String,
+    pub grfid: u32,
+    pub md5sum: [u8; 16],
+    /// Version of the NewGRF from its action 14, 0 when it has none
+    pub version: u32,
+    pub params: Vec<u32>,
+    /// GRFP_* bits
+    pub palette: u8,
+}
+
+impl GrfConfig {
+    /// Find the file to load for this NewGRF (matches C++ IsGoodGRFConfigList):
+    /// one with the same MD5 sum, or else the newest compatible version
+    pub fn find<'a>(&self, files: &'a [GrfFile]) -> (GrfStatus, Option<&'a GrfFile>) {
+        if let Some(file) = files
+            .iter()
+            .find(|f| f.grfid == self.grfid && f.md5sum == self.md5sum)
+        {
+            return (GrfStatus::Found, Some(file));
+        }
+        match files
+            .iter()
+            .filter(|f| f.grfid == self.grfid && f.is_compatible(self.version))
+            .max_by_key(|f| f.version)
+        {
+            Some(file) => (GrfStatus::Compatible, Some(file)),
+            None => (GrfStatus::Missing, None),
+        }
+    }
+}
+
+/// Whether a NewGRF of a savegame is available (matches C++ GRFListCompatibility)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrfStatus {
+    /// A file with the same MD5 sum
+    Found,
+    /// A different version that can load the savegame
+    Compatible,
+    Missing,
+}
+
+/// Identification of a NewGRF file (the C++ GRFConfig fields filled by a file scan)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrfFile {
+    pub filename: String,
+    pub grfid: u32,
+    pub md5sum: [u8; 16],
+    pub version: u32,
+    pub min_loadable_version: u32,
+}
+
+impl GrfFile {
+    /// Read the GRF ID and version of a NewGRF file and calculate its MD5 sum
+    ///
+    /// Like the C++ file scan, this reads the pseudo sprites up to action 8;
+    /// the version comes from an action 14 before it.
+    pub fn scan(filename: &str, data: &[u8]) -> Result<Self, CoreError> {
+        let mut file = GrfFile {
+            filename: filename.into(),
+            grfid: 0,
+            md5sum: Md5::digest(&data[..grf_data_section_size(data)]).into(),
+            version: 0,
+            min_loadable_version: 0,
+        };
+
+        let invalid = |message: &str| CoreError::InvalidData(format!("{}: {}", filename, message));
+        let v2 = data.len() >= GRF_CONT_V2_HEADER_LEN
+            && data[..2] == [0, 0]
+            && data[2..10] == GRF_CONT_V2_SIG;
+        let mut reader = BigEndianReader::new(data);
+        if v2 {
+            reader.read_bytes(GRF_CONT_V2_HEADER_LEN)?;
+            if reader.read_u8()? != 0 {
+                return Err(invalid("unsupported compression format"));
+            }
+        }
+        let read_size = |reader: &mut BigEndianReader| -> Result<usize, CoreError> {
+            Ok(if v2 {
+                u32::from_le_bytes(reader.read_exact()?) as usize
+            } else {
+                u16::from_le_bytes(reader.read_exact()?) as usize
+            })
+        };
+
+        // The first sprite holds the number of sprites
+        if read_size(&mut reader)? != 4 || reader.read_u8()? != 0xFF {
+            return Err(invalid("invalid format"));
+        }
+        reader.read_bytes(4)?;
+
+        loop {
+            let size = read_size(&mut reader)?;
+            // Scanning stops at real sprites, which may not come before action 8
+            if size == 0 || reader.read_u8()? != 0xFF {
+                return Err(invalid("no action 8"));
+            }
+            let sprite = reader.read_bytes(size)?;
+            match sprite.first() {
+                Some(0x08) if sprite.len() >= 6 => {
+                    file.grfid = u32::from_le_bytes([sprite[2], sprite[3], sprite[4], sprite[5]]);
+                    return Ok(file);
+                }
+                Some(0x14) => file.read_static_info(&mut BigEndianReader::new(&sprite[1..]))?,
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether this file can load a savegame made with the given version
+    /// (matches C++ GRFConfig::IsCompatible)
+    pub fn is_compatible(&self, old_version: u32) -> bool {
+        self.min_loadable_version <= old_version && old_version <= self.version
+    }
+
+    /// Read the 'INFO'->'VRSN' and 'INFO'->'MINV' nodes of an action 14
+    fn read_static_info(&mut self, reader: &mut BigEndianReader) -> Result<(), CoreError> {
+        loop {
+            let node_type = reader.read_u8()?;
+            if node_type == 0 {
+                return Ok(());
+            }
+            let id: [u8; 4] = reader.read_exact()?;
+            match (node_type, &id) {
+                (b'C', b"INFO") => self.read_static_info(reader)?,
+                (b'B', b"VRSN" | b"MINV") => {
+                    let len = u16::from_le_bytes(reader.read_exact()?) as usize;
+                    let value = reader.read_bytes(len)?;
+                    let Ok(value) = <[u8; 4]>::try_from(value) else {
+                        continue;
+                    };
+                    let value = u32::from_le_bytes(value);
+                    if &id == b"VRSN" {
+                        // Also the minimum, unless MINV follows
+                        self.version = value;
+                        self.min_loadable_version = value;
+                    } else if self.version != 0 {
+                        self.min_loadable_version = value.min(self.version);
+                    }
+                }
+                _ => skip_info(reader, node_type)?,
+            }
+        }
+    }
+}
+
+/// Skip an action 14 node whose type and id were read (matches C++ SkipUnknownInfo)
+fn skip_info(reader: &mut BigEndianReader, node_type: u8) -> Result<(), CoreError> {
+    match node_type {
+        b'C' => loop {
+            let node_type = reader.read_u8()?;
+            if node_type == 0 {
+                return Ok(());
+            }
+            reader.read_bytes(4)?;
+            skip_info(reader, node_type)?;
+        },
+        b'T' => {
+            reader.read_u8()?;
+            while reader.read_u8()? != 0 {}
+        }
+        b'B' => {
+            let len = u16::from_le_bytes(reader.read_exact()?) as usize;
+            reader.read_bytes(len)?;
+        }
+        _ => {
+            return Err(CoreError::InvalidData(format!(
+                "Invalid action 14 node type {}",
+                node_type
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Number of bytes covered by the MD5 sum: container version 2 files leave
+/// out the sprite section (matches C++ GRFGetSizeOfDataSection)
+fn grf_data_section_size(data: &[u8]) -> usize {
+    if data.len() >= GRF_CONT_V2_HEADER_LEN && data[..2] == [0, 0] && data[2..10] == GRF_CONT_V2_SIG
+    {
+        let offset = u32::from_le_bytes([data[10], data[11], data[12], data[13]]) as usize;
+        return data
+            .len()
+            .min(GRF_CONT_V2_HEADER_LEN.saturating_add(offset));
+    }
+    data.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A container version 1 file with the sprite count, an action 14 and an action 8
+    fn sample_grf(version: Option<(u32, u32)>) -> Vec<u8> {
+        let mut sprites: Vec<Vec<u8>> = vec![vec![2, 0, 0, 0]];
+        if let Some((vrsn, minv)) = version {
+            let mut info = vec![0x14, b'C'];
+            info.extend(b"INFO");
+            info.extend([b'T']);
+            info.extend(b"NAME");
+            info.extend([0x7F, b'x', 0]);
+            for (id, value) in [(b"VRSN", vrsn), (b"MINV", minv)] {
+                info.push(b'B');
+                info.extend(id);
+                info.extend(4u16.to_le_bytes());
+                info.extend(value.to_le_bytes());
+            }
+            info.extend([0, 0]);
+            sprites.push(info);
+        }
+        sprites.push(vec![0x08, 0x08, 0x4D, 0x2A, 0x03, 0x04, b'N', 0]);
+
+        let mut data = Vec::new();
+        for sprite in sprites {
+            data.extend((sprite.len() as u16).to_le_bytes());
+            data.push(0xFF);
+            data.extend(sprite);
+        }
+        data.extend([0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_scan_grf() {
+        let data = sample_grf(Some((7, 3)));
+        let file = GrfFile::scan("a.grf", &data).unwrap();
+        assert_eq!(file.grfid, 0x0403_2A4D);
+        assert_eq!((file.version, file.min_loadable_version), (7, 3));
+        assert_eq!(file.md5sum, <[u8; 16]>::from(Md5::digest(&data)));
+        assert!(file.is_compatible(5));
+        assert!(!file.is_compatible(8));
+
+        let file = GrfFile::scan("b.grf", &sample_grf(None)).unwrap();
+        assert_eq!((file.version, file.min_loadable_version), (0, 0));
+
+        assert!(GrfFile::scan("c.grf", &[0, 0]).is_err());
+        assert!(GrfFile::scan("d.grf", &sample_grf(None)[..15]).is_err());
+    }
+
+    #[test]
+    fn test_scan_grf_container_v2() {
+        // Compression and the data section, whose size follows the signature
+        let mut body = vec![0];
+        for sprite in [&[2u8, 0, 0, 0][..], &[0x08, 0x08, 1, 2, 3, 4, 0]] {
+            body.extend((sprite.len() as u32).to_le_bytes());
+            body.push(0xFF);
+            body.extend(sprite);
+        }
+        body.extend(0u32.to_le_bytes());
+        let mut data = vec![0, 0];
+        data.extend(GRF_CONT_V2_SIG);
+        data.extend((body.len() as u32).to_le_bytes());
+        data.extend(&body);
+        // The sprite section is not part of the MD5 sum
+        data.extend([1, 2, 3]);
+
+        let file = GrfFile::scan("v2.grf", &data).unwrap();
+        assert_eq!(file.grfid, 0x0403_0201);
+        assert_eq!(
+            file.md5sum,
+            <[u8; 16]>::from(Md5::digest(&data[..data.len() - 3]))
+        );
+    }
+
+    #[test]
+    fn test_find_grf() {
+        let file = |name: &str, md5: u8, version: u32| GrfFile {
+            filename: name.into(),
+            grfid: 1,
+            md5sum: [md5; 16],
+            version,
+            min_loadable_version: 2,
+        };
+        let files = [
+            file("old.grf", 1, 2),
+            file("new.grf", 2, 5),
+            file("newer.grf", 3, 6),
+        ];
+        let mut config = GrfConfig {
+            grfid: 1,
+            md5sum: [1; 16],
+            version: 2,
+            ..GrfConfig::default()
+        };
+        assert_eq!(config.find(&files), (GrfStatus::Found, Some(&files[0])));
+
+        config.md5sum = [9; 16];
+        config.version = 4;
+        assert_eq!(
+            config.find(&files),
+            (GrfStatus::Compatible, Some(&files[2]))
+        );
+
+        config.version = 7;
+        assert_eq!(config.find(&files), (GrfStatus::Missing, None));
+    }
+}
@@ -0,0 +1,798 @@
+//! Order data structures for OpenTTD
+//!
+//! Orders keep the packed `type_`, `flags` and `dest` fields of the C++ Order,
+//! so they save back unchanged; the accessors decode the bit fields in the
+//! same way as C++ order_base.h.
+
+use crate::error::CoreError;
+use crate::map::TileIndex;
+use crate::types::{CargoType, DestinationID, GroupID, StationID, Tick, VehicleID};
+use crate::vehicle::Vehicle;
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use std::collections::{HashMap, HashSet};
+
+/// Index of an order within its order list (matches C++ VehicleOrderID)
+pub type VehicleOrderID = u8;
+
+pub const INVALID_VEH_ORDER_ID: VehicleOrderID = 0xFF;
+pub const MAX_VEH_ORDER_ID: VehicleOrderID = INVALID_VEH_ORDER_ID - 1;
+
+/// Order types (matches C++ OrderType)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum OrderType {
+    Nothing = 0,
+    GotoStation = 1,
+    GotoDepot = 2,
+    Loading = 3,
+    LeaveStation = 4,
+    Dummy = 5,
+    GotoWaypoint = 6,
+    Conditional = 7,
+    Implicit = 8,
+}
+
+impl TryFrom<u8> for OrderType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => OrderType::Nothing,
+            1 => OrderType::GotoStation,
+            2 => OrderType::GotoDepot,
+            3 => OrderType::Loading,
+            4 => OrderType::LeaveStation,
+            5 => OrderType::Dummy,
+            6 => OrderType::GotoWaypoint,
+            7 => OrderType::Conditional,
+            8 => OrderType::Implicit,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid order type {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+/// How cargo is unloaded at a station (matches C++ OrderUnloadType)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum OrderUnloadType {
+    UnloadIfPossible = 0,
+    Unload = 1,
+    Transfer = 2,
+    NoUnload = 4,
+}
+
+impl TryFrom<u8> for OrderUnloadType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => OrderUnloadType::UnloadIfPossible,
+            1 => OrderUnloadType::Unload,
+            2 => OrderUnloadType::Transfer,
+            4 => OrderUnloadType::NoUnload,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid unload type {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+/// How cargo is loaded at a station (matches C++ OrderLoadType)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum OrderLoadType {
+    LoadIfPossible = 0,
+    FullLoad = 2,
+    FullLoadAny = 3,
+    NoLoad = 4,
+}
+
+impl TryFrom<u8> for OrderLoadType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => OrderLoadType::LoadIfPossible,
+            2 => OrderLoadType::FullLoad,
+            3 => OrderLoadType::FullLoadAny,
+            4 => OrderLoadType::NoLoad,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid load type {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+bitflags! {
+    /// Stations a vehicle does not stop at (matches C++ OrderNonStopFlags)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct OrderNonStopFlags: u8 {
+        /// Non-stop: only stop at the destination
+        const NO_INTERMEDIATE = 1 << 0;
+        /// Via: stop anywhere except at the destination
+        const NO_DESTINATION = 1 << 1;
+    }
+}
+
+/// Where a train stops at a platform (matches C++ OrderStopLocation)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum OrderStopLocation {
+    NearEnd = 0,
+    Middle = 1,
+    FarEnd = 2,
+}
+
+impl TryFrom<u8> for OrderStopLocation {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => OrderStopLocation::NearEnd,
+            1 => OrderStopLocation::Middle,
+            2 => OrderStopLocation::FarEnd,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid stop location {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+bitflags! {
+    /// Reason for a depot order (matches C++ OrderDepotTypeFlags)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct OrderDepotTypeFlags: u8 {
+        /// Sent for servicing because of the service interval
+        const SERVICE = 1 << 0;
+        /// A regular order of the order list
+        const PART_OF_ORDERS = 1 << 1;
+    }
+}
+
+bitflags! {
+    /// What to do in the depot (matches C++ OrderDepotActionFlags)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct OrderDepotActionFlags: u8 {
+        /// Service the vehicle and stop it
+        const HALT = 1 << 0;
+        /// Go to the nearest depot instead of `dest`
+        const NEAREST_DEPOT = 1 << 1;
+        /// Service the vehicle and unbunch it
+        const UNBUNCH = 1 << 2;
+    }
+}
+
+/// Vehicle property tested by a conditional order (matches C++ OrderConditionVariable)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum OrderConditionVariable {
+    LoadPercentage = 0,
+    Reliability = 1,
+    MaxSpeed = 2,
+    Age = 3,
+    RequiresService = 4,
+    Unconditionally = 5,
+    RemainingLifetime = 6,
+    MaxReliability = 7,
+}
+
+impl TryFrom<u8> for OrderConditionVariable {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => OrderConditionVariable::LoadPercentage,
+            1 => OrderConditionVariable::Reliability,
+            2 => OrderConditionVariable::MaxSpeed,
+            3 => OrderConditionVariable::Age,
+            4 => OrderConditionVariable::RequiresService,
+            5 => OrderConditionVariable::Unconditionally,
+            6 => OrderConditionVariable::RemainingLifetime,
+            7 => OrderConditionVariable::MaxReliability,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid condition variable {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+/// Comparison of a conditional order (matches C++ OrderConditionComparator)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum OrderConditionComparator {
+    Equal = 0,
+    NotEqual = 1,
+    LessThan = 2,
+    LessThanOrEqual = 3,
+    MoreThan = 4,
+    MoreThanOrEqual = 5,
+    IsTrue = 6,
+    IsFalse = 7,
+}
+
+impl TryFrom<u8> for OrderConditionComparator {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => OrderConditionComparator::Equal,
+            1 => OrderConditionComparator::NotEqual,
+            2 => OrderConditionComparator::LessThan,
+            3 => OrderConditionComparator::LessThanOrEqual,
+            4 => OrderConditionComparator::MoreThan,
+            5 => OrderConditionComparator::MoreThanOrEqual,
+            6 => OrderConditionComparator::IsTrue,
+            7 => OrderConditionComparator::IsFalse,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid condition comparator {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+impl OrderConditionComparator {
+    /// Compare a vehicle's value with the order's value (matches C++ OrderConditionCompare)
+    pub fn compare(self, variable: u32, value: u32) -> bool {
+        match self {
+            OrderConditionComparator::Equal => variable == value,
+            OrderConditionComparator::NotEqual => variable != value,
+            OrderConditionComparator::LessThan => variable < value,
+            OrderConditionComparator::LessThanOrEqual => variable <= value,
+            OrderConditionComparator::MoreThan => variable > value,
+            OrderConditionComparator::MoreThanOrEqual => variable >= value,
+            OrderConditionComparator::IsTrue => variable != 0,
+            OrderConditionComparator::IsFalse => variable == 0,
+        }
+    }
+}
+
+fn get_bits(value: u16, start: u8, count: u8) -> u16 {
+    (value >> start) & ((1 << count) - 1)
+}
+
+fn set_bits(value: u16, start: u8, count: u8, bits: u16) -> u16 {
+    let mask = ((1 << count) - 1) << start;
+    (value & !mask) | ((bits << start) & mask)
+}
+
+/// A single order (matches C++ Order)
+///
+/// `type_` holds the order type in bits 0-3, the stop location in bits 4-5 or
+/// the condition comparator in bits 5-7, and the non-stop flags in bits 6-7.
+/// `flags` holds the unload (0-2) and load (4-6) types, the depot order
+/// (0-2) and action (3-6) types, or the skip target of a conditional order.
+/// Bits 3 and 7 of `flags` mark the wait and travel times as timetabled.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Order {
+    pub type_: u8,
+    pub flags: u8,
+    pub dest: DestinationID,
+    pub refit_cargo: CargoType,
+    /// Ticks to wait at the destination
+    pub wait_time: u16,
+    /// Ticks the journey to the destination should take
+    pub travel_time: u16,
+    /// Maximum speed on the way to the destination, u16::MAX for no limit
+    pub max_speed: u16,
+}
+
+impl Default for Order {
+    fn default() -> Self {
+        Self {
+            type_: OrderType::Nothing as u8,
+            flags: 0,
+            dest: 0,
+            refit_cargo: CargoType::NO_REFIT,
+            wait_time: 0,
+            travel_time: 0,
+            max_speed: u16::MAX,
+        }
+    }
+}
+
+impl Order {
+    fn with_type(order_type: OrderType, flags: u8, dest: DestinationID) -> Self {
+        Self {
+            type_: order_type as u8,
+            flags,
+            dest,
+            ..Self::default()
+        }
+    }
+
+    pub fn goto_station(station: StationID) -> Self {
+        Self::with_type(OrderType::GotoStation, 0, station.0)
+    }
+
+    pub fn goto_waypoint(waypoint: StationID) -> Self {
+        Self::with_type(OrderType::GotoWaypoint, 0, waypoint.0)
+    }
+
+    pub fn goto_depot(
+        depot: DestinationID,
+        order_type: OrderDepotTypeFlags,
+        non_stop: OrderNonStopFlags,
+        action: OrderDepotActionFlags,
+        cargo: CargoType,
+    ) -> Self {
+        let mut order = Self::with_type(OrderType::GotoDepot, 0, depot);
+        order.set_depot_order_type(order_type);
+        order.set_depot_action_type(action);
+        order.set_non_stop_type(non_stop);
+        order.refit_cargo = cargo;
+        order
+    }
+
+    /// A conditional order that jumps to `skip_to` when its condition holds
+    pub fn conditional(skip_to: VehicleOrderID) -> Self {
+        Self::with_type(OrderType::Conditional, skip_to, 0)
+    }
+
+    /// An order added automatically for a station the vehicle stopped at
+    pub fn implicit(station: StationID) -> Self {
+        Self::with_type(OrderType::Implicit, 0, station.0)
+    }
+
+    pub fn dummy() -> Self {
+        Self::with_type(OrderType::Dummy, 0, 0)
+    }
+
+    pub fn order_type(&self) -> Result<OrderType, CoreError> {
+        OrderType::try_from(self.type_ & 0x0F)
+    }
+
+    pub fn is_type(&self, order_type: OrderType) -> bool {
+        self.type_ & 0x0F == order_type as u8
+    }
+
+    /// Whether this order sends the vehicle to a station, depot or waypoint
+    pub fn is_goto_order(&self) -> bool {
+        self.is_type(OrderType::GotoStation)
+            || self.is_type(OrderType::GotoDepot)
+            || self.is_type(OrderType::GotoWaypoint)
+    }
+
+    fn type_bits(&self, start: u8, count: u8) -> u8 {
+        get_bits(self.type_ as u16, start, count) as u8
+    }
+
+    fn set_type_bits(&mut self, start: u8, count: u8, bits: u8) {
+        self.type_ = set_bits(self.type_ as u16, start, count, bits as u16) as u8;
+    }
+
+    fn flag_bits(&self, start: u8, count: u8) -> u8 {
+        get_bits(self.flags as u16, start, count) as u8
+    }
+
+    fn set_flag_bits(&mut self, start: u8, count: u8, bits: u8) {
+        self.flags = set_bits(self.flags as u16, start, count, bits as u16) as u8;
+    }
+
+    pub fn load_type(&self) -> Result<OrderLoadType, CoreError> {
+        OrderLoadType::try_from(self.flag_bits(4, 3))
+    }
+
+    pub fn set_load_type(&mut self, load_type: OrderLoadType) {
+        self.set_flag_bits(4, 3, load_type as u8);
+    }
+
+    pub fn unload_type(&self) -> Result<OrderUnloadType, CoreError> {
+        OrderUnloadType::try_from(self.flag_bits(0, 3))
+    }
+
+    pub fn set_unload_type(&mut self, unload_type: OrderUnloadType) {
+        self.set_flag_bits(0, 3, unload_type as u8);
+    }
+
+    pub fn is_full_load_order(&self) -> bool {
+        matches!(
+            self.load_type(),
+            Ok(OrderLoadType::FullLoad | OrderLoadType::FullLoadAny)
+        )
+    }
+
+    pub fn non_stop_type(&self) -> OrderNonStopFlags {
+        OrderNonStopFlags::from_bits_retain(self.type_bits(6, 2))
+    }
+
+    pub fn set_non_stop_type(&mut self, non_stop: OrderNonStopFlags) {
+        self.set_type_bits(6, 2, non_stop.bits());
+    }
+
+    pub fn stop_location(&self) -> Result<OrderStopLocation, CoreError> {
+        OrderStopLocation::try_from(self.type_bits(4, 2))
+    }
+
+    pub fn set_stop_location(&mut self, location: OrderStopLocation) {
+        self.set_type_bits(4, 2, location as u8);
+    }
+
+    pub fn depot_order_type(&self) -> OrderDepotTypeFlags {
+        OrderDepotTypeFlags::from_bits_retain(self.flag_bits(0, 3))
+    }
+
+    pub fn set_depot_order_type(&mut self, order_type: OrderDepotTypeFlags) {
+        self.set_flag_bits(0, 3, order_type.bits());
+    }
+
+    pub fn depot_action_type(&self) -> OrderDepotActionFlags {
+        OrderDepotActionFlags::from_bits_retain(self.flag_bits(3, 4))
+    }
+
+    pub fn set_depot_action_type(&mut self, action: OrderDepotActionFlags) {
+        self.set_flag_bits(3, 4, action.bits());
+    }
+
+    pub fn condition_variable(&self) -> Result<OrderConditionVariable, CoreError> {
+        OrderConditionVariable::try_from(get_bits(self.dest, 11, 5) as u8)
+    }
+
+    pub fn set_condition_variable(&mut self, variable: OrderConditionVariable) {
+        self.dest = set_bits(self.dest, 11, 5, variable as u16);
+    }
+
+    pub fn condition_comparator(&self) -> Result<OrderConditionComparator, CoreError> {
+        OrderConditionComparator::try_from(self.type_bits(5, 3))
+    }
+
+    pub fn set_condition_comparator(&mut self, comparator: OrderConditionComparator) {
+        self.set_type_bits(5, 3, comparator as u8);
+    }
+
+    /// Order to jump to when the condition of a conditional order holds
+    pub fn condition_skip_to_order(&self) -> VehicleOrderID {
+        self.flags
+    }
+
+    pub fn set_condition_skip_to_order(&mut self, order: VehicleOrderID) {
+        self.flags = order;
+    }
+
+    pub fn condition_value(&self) -> u16 {
+        get_bits(self.dest, 0, 11)
+    }
+
+    pub fn set_condition_value(&mut self, value: u16) {
+        self.dest = set_bits(self.dest, 0, 11, value);
+    }
+
+    // Conditional orders keep their skip target in `flags`, so they count
+    // any non-zero time as timetabled.
+    pub fn is_wait_timetabled(&self) -> bool {
+        if self.is_type(OrderType::Conditional) {
+            self.wait_time > 0
+        } else {
+            self.flags & (1 << 3) != 0
+        }
+    }
+
+    pub fn is_travel_timetabled(&self) -> bool {
+        if self.is_type(OrderType::Conditional) {
+            self.travel_time > 0
+        } else {
+            self.flags & (1 << 7) != 0
+        }
+    }
+
+    pub fn set_wait_timetabled(&mut self, timetabled: bool) {
+        if !self.is_type(OrderType::Conditional) {
+            self.set_flag_bits(3, 1, timetabled as u8);
+        }
+    }
+
+    pub fn set_travel_timetabled(&mut self, timetabled: bool) {
+        if !self.is_type(OrderType::Conditional) {
+            self.set_flag_bits(7, 1, timetabled as u8);
+        }
+    }
+
+    pub fn timetabled_wait(&self) -> u16 {
+        if self.is_wait_timetabled() {
+            self.wait_time
+        } else {
+            0
+        }
+    }
+
+    pub fn timetabled_travel(&self) -> u16 {
+        if self.is_travel_timetabled() {
+            self.travel_time
+        } else {
+            0
+        }
+    }
+
+    /// Whether all times this order needs are timetabled (matches C++ IsCompletelyTimetabled)
+    pub fn is_completely_timetabled(&self) -> bool {
+        if !self.is_travel_timetabled() && !self.is_type(OrderType::Conditional) {
+            return false;
+        }
+        !(!self.is_wait_timetabled()
+            && self.is_type(OrderType::GotoStation)
+            && !self
+                .non_stop_type()
+                .contains(OrderNonStopFlags::NO_DESTINATION))
+    }
+
+    /// Whether cargo can be handled when executing this order
+    pub fn can_load_or_unload(&self) -> bool {
+        (self.is_type(OrderType::GotoStation) || self.is_type(OrderType::Implicit))
+            && !self
+                .non_stop_type()
+                .contains(OrderNonStopFlags::NO_DESTINATION)
+            && (self.load_type().ok() != Some(OrderLoadType::NoLoad)
+                || self.unload_type().ok() != Some(OrderUnloadType::NoUnload))
+    }
+}
+
+/// Orders shared by the vehicles whose `orders` refer to it (matches C++ OrderList)
+///
+/// The vehicles sharing a list are chained through `Vehicle::next_shared`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderList {
+    pub index: u32,
+    pub orders: Vec<Order>,
+}
+
+impl OrderList {
+    pub fn new(index: u32, orders: Vec<Order>) -> Self {
+        Self { index, orders }
+    }
+
+    pub fn num_orders(&self) -> VehicleOrderID {
+        self.orders.len() as VehicleOrderID
+    }
+
+    /// Number of orders that were not added automatically
+    pub fn num_manual_orders(&self) -> VehicleOrderID {
+        self.orders
+            .iter()
+            .filter(|o| !o.is_type(OrderType::Implicit))
+            .count() as VehicleOrderID
+    }
+
+    pub fn order_at(&self, index: VehicleOrderID) -> Option<&Order> {
+        self.orders.get(index as usize)
+    }
+
+    /// The order after `current`, wrapping around at the end of the list
+    pub fn next(&self, current: VehicleOrderID) -> VehicleOrderID {
+        if self.orders.is_empty() {
+            return INVALID_VEH_ORDER_ID;
+        }
+        ((current as usize + 1) % self.orders.len()) as VehicleOrderID
+    }
+
+    /// Sum of the timetabled wait and travel times
+    pub fn timetable_duration(&self) -> u32 {
+        self.orders
+            .iter()
+            .map(|o| o.timetabled_wait() as u32 + o.timetabled_travel() as u32)
+            .sum()
+    }
+
+    /// Sum of the wait and travel times, whether timetabled or not
+    pub fn total_duration(&self) -> u32 {
+        self.orders
+            .iter()
+            .map(|o| o.wait_time as u32 + o.travel_time as u32)
+            .sum()
+    }
+
+    pub fn is_complete_timetable(&self) -> bool {
+        self.orders
+            .iter()
+            .filter(|o| !o.is_type(OrderType::Implicit))
+            .all(Order::is_completely_timetabled)
+    }
+
+    /// Vehicles using this list, in `next_shared` order from the first shared vehicle
+    ///
+    /// Stops at the end of the chain or at a vehicle that does not use this list.
+    pub fn shared_vehicles<'a>(&self, vehicles: &'a [Vehicle]) -> Vec<&'a Vehicle> {
+        let users: HashMap<VehicleID, &Vehicle> = vehicles
+            .iter()
+            .filter(|v| v.orders == Some(self.index))
+            .map(|v| (v.index, v))
+            .collect();
+        let linked: HashSet<VehicleID> = users.values().filter_map(|v| v.next_shared).collect();
+        let Some(first) = vehicles
+            .iter()
+            .find(|v| users.contains_key(&v.index) && !linked.contains(&v.index))
+        else {
+            return Vec::new();
+        };
+
+        let mut chain = vec![first];
+        let mut current = first;
+        while let Some(next) = current.next_shared.and_then(|i| users.get(&i)) {
+            if chain.len() == users.len() {
+                break;
+            }
+            chain.push(next);
+            current = next;
+        }
+        chain
+    }
+
+    pub fn is_shared(&self, vehicles: &[Vehicle]) -> bool {
+        self.shared_vehicles(vehicles).len() > 1
+    }
+}
+
+/// Orders and settings of a vehicle kept after it was sold in a depot, to be
+/// restored on a vehicle built there (matches C++ OrderBackup)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderBackup {
+    pub index: u32,
+    /// Client that sold the vehicle
+    pub user: u32,
+    /// Depot the vehicle was sold in
+    pub tile: TileIndex,
+    pub group: GroupID,
+    pub service_interval: u16,
+    pub name: String,
+    /// Vehicle the orders were shared with, if any
+    pub clone: Option<VehicleID>,
+    pub cur_real_order_index: VehicleOrderID,
+    pub cur_implicit_order_index: VehicleOrderID,
+    pub current_order_time: u32,
+    pub lateness_counter: i32,
+    pub timetable_start: Tick,
+    pub vehicle_flags: u16,
+    pub orders: Vec<Order>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vehicle::VehicleType;
+
+    #[test]
+    fn test_order_bit_layout() {
+        let mut order = Order::goto_station(StationID(12));
+        order.set_load_type(OrderLoadType::FullLoadAny);
+        order.set_unload_type(OrderUnloadType::Transfer);
+        order.set_non_stop_type(OrderNonStopFlags::NO_INTERMEDIATE);
+        order.set_stop_location(OrderStopLocation::FarEnd);
+        assert_eq!(order.type_, 0x01 | (2 << 4) | (1 << 6));
+        assert_eq!(order.flags, 0x32);
+        assert_eq!(order.dest, 12);
+        assert_eq!(order.order_type().unwrap(), OrderType::GotoStation);
+        assert_eq!(order.load_type().unwrap(), OrderLoadType::FullLoadAny);
+        assert_eq!(order.unload_type().unwrap(), OrderUnloadType::Transfer);
+        assert_eq!(order.stop_location().unwrap(), OrderStopLocation::FarEnd);
+        assert!(order.is_full_load_order());
+        assert!(order.can_load_or_unload());
+
+        order.flags = 0x10;
+        assert!(order.load_type().is_err());
+        order.type_ = 0x0F;
+        assert!(order.order_type().is_err());
+    }
+
+    #[test]
+    fn test_depot_order() {
+        let order = Order::goto_depot(
+            7,
+            OrderDepotTypeFlags::PART_OF_ORDERS,
+            OrderNonStopFlags::all(),
+            OrderDepotActionFlags::HALT | OrderDepotActionFlags::NEAREST_DEPOT,
+            CargoType(3),
+        );
+        assert_eq!(order.flags, 0x02 | (0x03 << 3));
+        assert_eq!(order.type_, 0x02 | (3 << 6));
+        assert_eq!(
+            order.depot_order_type(),
+            OrderDepotTypeFlags::PART_OF_ORDERS
+        );
+        assert!(order
+            .depot_action_type()
+            .contains(OrderDepotActionFlags::NEAREST_DEPOT));
+        assert_eq!(order.refit_cargo, CargoType(3));
+        assert!(order.is_goto_order());
+    }
+
+    #[test]
+    fn test_conditional_order() {
+        let mut order = Order::conditional(4);
+        order.set_condition_variable(OrderConditionVariable::MaxSpeed);
+        order.set_condition_comparator(OrderConditionComparator::MoreThanOrEqual);
+        order.set_condition_value(1500);
+        assert_eq!(order.dest, (2 << 11) | 1500);
+        assert_eq!(order.type_, 0x07 | (5 << 5));
+        assert_eq!(order.condition_skip_to_order(), 4);
+        assert_eq!(order.condition_value(), 1500);
+        let comparator = order.condition_comparator().unwrap();
+        assert!(comparator.compare(1500, order.condition_value() as u32));
+        assert!(!comparator.compare(1499, order.condition_value() as u32));
+
+        // The skip target overlaps the timetable bits
+        order.set_wait_timetabled(true);
+        assert_eq!(order.flags, 4);
+        assert!(!order.is_wait_timetabled());
+        order.wait_time = 10;
+        assert!(order.is_wait_timetabled());
+    }
+
+    #[test]
+    fn test_timetable() {
+        let mut station = Order::goto_station(StationID(1));
+        station.wait_time = 20;
+        station.travel_time = 100;
+        station.set_travel_timetabled(true);
+        let mut depot = Order::goto_depot(
+            2,
+            OrderDepotTypeFlags::PART_OF_ORDERS,
+            OrderNonStopFlags::empty(),
+            OrderDepotActionFlags::empty(),
+            CargoType::NO_REFIT,
+        );
+        depot.travel_time = 50;
+        depot.set_travel_timetabled(true);
+        let mut list = OrderList::new(0, vec![station, Order::implicit(StationID(3)), depot]);
+
+        assert_eq!(list.timetable_duration(), 150);
+        assert_eq!(list.total_duration(), 170);
+        assert!(!list.is_complete_timetable());
+        list.orders[0].set_wait_timetabled(true);
+        assert!(list.is_complete_timetable());
+        assert_eq!(list.timetable_duration(), 170);
+        assert_eq!(list.num_manual_orders(), 2);
+        assert_eq!(list.next(2), 0);
+        assert_eq!(OrderList::default().next(0), INVALID_VEH_ORDER_ID);
+    }
+
+    #[test]
+    fn test_shared_vehicles() {
+        let mut vehicles: Vec<Vehicle> = (0..4)
+            .map(|i| Vehicle::new(VehicleID(i), VehicleType::Road))
+            .collect();
+        for v in [3, 1, 0] {
+            vehicles[v].orders = Some(5);
+        }
+        vehicles[3].next_shared = Some(VehicleID(0));
+        vehicles[0].next_shared = Some(VehicleID(1));
+        vehicles[2].orders = Some(6);
+
+        let list = OrderList::new(5, vec![Order::goto_station(StationID(1))]);
+        let chain: Vec<u32> = list
+            .shared_vehicles(&vehicles)
+            .iter()
+            .map(|v| v.index.0)
+            .collect();
+        assert_eq!(chain, [3, 0, 1]);
+        assert!(list.is_shared(&vehicles));
+        assert!(!OrderList::new(6, vec![]).is_shared(&vehicles));
+
+        // A chain looping back ends after every user was visited once
+        vehicles[1].next_shared = Some(VehicleID(3));
+        assert!(list.shared_vehicles(&vehicles).is_empty());
+        vehicles[2].orders = Some(5);
+        vehicles[2].next_shared = Some(VehicleID(3));
+        assert_eq!(list.shared_vehicles(&vehicles).len(), 4);
+    }
+}
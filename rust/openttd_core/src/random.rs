@@ -0,0 +1,66 @@
+//! Pseudo random number generator
+//!
+//! The generator must produce the same numbers as the C++ one, as savegames
+//! and multiplayer games depend on values derived from it, e.g. the
+//! introduction dates and reliabilities of engines.
+
+use serde::{Deserialize, Serialize};
+
+/// Two-word pseudo random number generator (matches C++ Randomizer)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Randomizer {
+    pub state: [u32; 2],
+}
+
+impl Randomizer {
+    pub fn new(seed: u32) -> Self {
+        let mut randomizer = Self::default();
+        randomizer.set_seed(seed);
+        randomizer
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.state = [seed, seed];
+    }
+
+    /// Generate the next pseudo random number
+    pub fn next_u32(&mut self) -> u32 {
+        let [s, t] = self.state;
+        self.state[0] = s
+            .wrapping_add((t ^ 0x1234567F).rotate_right(7))
+            .wrapping_add(1);
+        self.state[1] = s.rotate_right(3).wrapping_sub(1);
+        self.state[1]
+    }
+
+    /// Generate a number in `0..limit`
+    pub fn next_range(&mut self, limit: u32) -> u32 {
+        ((self.next_u32() as u64 * limit as u64) >> 32) as u32
+    }
+}
+
+/// Extract `count` bits starting at bit `start` (matches C++ GB)
+pub fn gb(value: u32, start: u8, count: u8) -> u32 {
+    (value >> start) & ((1u32 << count) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_randomizer() {
+        let mut random = Randomizer::new(0);
+        assert_eq!(random.next_u32(), 0xFFFF_FFFF);
+        assert_eq!(random.state[0], 0x1234567Fu32.rotate_right(7) + 1);
+
+        // The same seed gives the same sequence
+        let mut a = Randomizer::new(0xDEADBEEF);
+        let mut b = Randomizer::new(0xDEADBEEF);
+        for _ in 0..10 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+        assert!(a.next_range(10) < 10);
+        assert_eq!(gb(0xABCD, 4, 8), 0xBC);
+    }
+}
@@ -0,0 +1,347 @@
+//! Sign data structures for OpenTTD
+//!
+//! Signs are texts placed on the map by companies, the scenario editor or
+//! game scripts. The commands follow C++ signs_cmd.cpp; the height of the
+//! ground under a sign is left to the caller.
+
+use crate::error::CoreError;
+use crate::map::{Map, TileIndex, TILE_SIZE};
+use crate::types::{Colours, Owner, SignID};
+use serde::{Deserialize, Serialize};
+
+/// Maximum length of a sign name in characters, including the terminator
+/// (matches C++ MAX_LENGTH_SIGN_NAME_CHARS)
+pub const MAX_LENGTH_SIGN_NAME_CHARS: usize = 32;
+
+/// A text on the map (matches C++ Sign)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sign {
+    pub index: SignID,
+    pub name: String,
+    /// World coordinates of the sign
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    /// Company that placed the sign; anyone may delete it though
+    pub owner: Owner,
+    /// Colour of the text, only used for signs of the deity
+    pub text_colour: Colours,
+}
+
+impl Sign {
+    pub fn new(index: SignID, owner: Owner, x: i32, y: i32, z: i32, name: &str) -> Self {
+        Self {
+            index,
+            name: name.into(),
+            x,
+            y,
+            z,
+            owner,
+            text_colour: Colours::White,
+        }
+    }
+
+    /// Tile coordinates of the sign, None when it lies off the map's north edges
+    fn tile_xy(&self) -> Option<(u32, u32)> {
+        let x = u32::try_from(self.x).ok()?;
+        let y = u32::try_from(self.y).ok()?;
+        Some((x / TILE_SIZE, y / TILE_SIZE))
+    }
+
+    /// Tile the sign stands on (matches C++ TileVirtXY)
+    pub fn tile(&self, map: &Map) -> TileIndex {
+        self.tile_xy()
+            .map_or(TileIndex::INVALID, |(x, y)| map.tile_xy(x, y))
+    }
+
+    /// Whether a company may rename or move the sign (matches C++ CompanyCanEditSign)
+    pub fn can_edit(&self, company: Owner, in_editor: bool) -> bool {
+        self.owner != Owner::Deity || company == Owner::Deity || in_editor
+    }
+}
+
+fn check_name_length(text: &str) -> Result<(), CoreError> {
+    if text.chars().count() >= MAX_LENGTH_SIGN_NAME_CHARS {
+        return Err(CoreError::InvalidData(format!(
+            "Sign name '{}' is too long",
+            text
+        )));
+    }
+    Ok(())
+}
+
+/// All signs of a game, by index
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignPool {
+    signs: Vec<Option<Sign>>,
+}
+
+impl SignPool {
+    /// Build a pool from signs at their own indices
+    pub fn from_signs(signs: Vec<Sign>) -> Result<Self, CoreError> {
+        let mut pool = Self::default();
+        for sign in signs {
+            let index = sign.index.0 as usize;
+            if !sign.index.is_valid() {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid sign index {}",
+                    index
+                )));
+            }
+            if index >= pool.signs.len() {
+                pool.signs.resize(index + 1, None);
+            }
+            if pool.signs[index].replace(sign).is_some() {
+                return Err(CoreError::InvalidData(format!("Duplicate sign {}", index)));
+            }
+        }
+        Ok(pool)
+    }
+
+    pub fn get(&self, id: SignID) -> Option<&Sign> {
+        self.signs.get(id.0 as usize)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: SignID) -> Option<&mut Sign> {
+        self.signs.get_mut(id.0 as usize)?.as_mut()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Sign> {
+        self.signs.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signs.iter().all(Option::is_none)
+    }
+
+    fn editable_mut(
+        &mut self,
+        id: SignID,
+        company: Owner,
+        in_editor: bool,
+    ) -> Result<&mut Sign, CoreError> {
+        match self.get_mut(id) {
+            Some(sign) if sign.can_edit(company, in_editor) => Ok(sign),
+            Some(_) => Err(CoreError::InvalidData(format!(
+                "Sign {} belongs to the deity",
+                id.0
+            ))),
+            None => Err(CoreError::InvalidData(format!("Invalid sign {}", id.0))),
+        }
+    }
+
+    /// Place a sign on a tile whose ground is at height `z`; signs placed in
+    /// the scenario editor belong to the deity (matches C++ CmdPlaceSign)
+    pub fn place(
+        &mut self,
+        map: &Map,
+        tile: TileIndex,
+        z: i32,
+        text: &str,
+        company: Owner,
+        in_editor: bool,
+    ) -> Result<SignID, CoreError> {
+        check_name_length(text)?;
+        let index = match self.signs.iter().position(Option::is_none) {
+            Some(index) => index,
+            None if self.signs.len() < SignID::MAX_SIGNS => {
+                self.signs.push(None);
+                self.signs.len() - 1
+            }
+            None => return Err(CoreError::InvalidData("Too many signs".into())),
+        };
+
+        let id = SignID(index as u16);
+        let owner = if in_editor { Owner::Deity } else { company };
+        let x = (map.tile_x(tile) * TILE_SIZE) as i32;
+        let y = (map.tile_y(tile) * TILE_SIZE) as i32;
+        self.signs[index] = Some(Sign::new(id, owner, x, y, z, text));
+        Ok(id)
+    }
+
+    /// Rename a sign, or remove it when `text` is empty; an Invalid colour keeps
+    /// the current one (matches C++ CmdRenameSign)
+    pub fn rename(
+        &mut self,
+        id: SignID,
+        text: &str,
+        text_colour: Colours,
+        company: Owner,
+        in_editor: bool,
+    ) -> Result<(), CoreError> {
+        let sign = self.editable_mut(id, company, in_editor)?;
+        if text.is_empty() {
+            self.signs[id.0 as usize] = None;
+            return Ok(());
+        }
+        check_name_length(text)?;
+
+        sign.name = text.into();
+        if text_colour != Colours::Invalid {
+            sign.text_colour = text_colour;
+        }
+        if !in_editor {
+            sign.owner = company;
+        }
+        Ok(())
+    }
+
+    /// Move a sign to a tile whose ground is at height `z` (matches C++ CmdMoveSign)
+    pub fn move_to(
+        &mut self,
+        id: SignID,
+        map: &Map,
+        tile: TileIndex,
+        z: i32,
+        company: Owner,
+        in_editor: bool,
+    ) -> Result<(), CoreError> {
+        let sign = self.editable_mut(id, company, in_editor)?;
+        sign.x = (map.tile_x(tile) * TILE_SIZE) as i32;
+        sign.y = (map.tile_y(tile) * TILE_SIZE) as i32;
+        sign.z = z;
+        if !in_editor {
+            sign.owner = company;
+        }
+        Ok(())
+    }
+
+    /// Signs standing on the tiles of the rectangle spanned by two corner tiles
+    pub fn in_area<'a>(
+        &'a self,
+        map: &Map,
+        corner_a: TileIndex,
+        corner_b: TileIndex,
+    ) -> impl Iterator<Item = &'a Sign> {
+        let (ax, ay) = (map.tile_x(corner_a), map.tile_y(corner_a));
+        let (bx, by) = (map.tile_x(corner_b), map.tile_y(corner_b));
+        let xs = ax.min(bx)..=ax.max(bx);
+        let ys = ay.min(by)..=ay.max(by);
+        self.iter().filter(move |sign| {
+            sign.tile_xy()
+                .is_some_and(|(x, y)| xs.contains(&x) && ys.contains(&y))
+        })
+    }
+
+    /// Signs standing on a tile
+    pub fn on_tile<'a>(&'a self, map: &Map, tile: TileIndex) -> impl Iterator<Item = &'a Sign> {
+        self.in_area(map, tile, tile)
+    }
+
+    /// Signs with the given text, e.g. labels used by scripts
+    pub fn with_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Sign> {
+        self.iter().filter(move |sign| sign.name == name)
+    }
+
+    /// Remove the signs of a company that goes bankrupt, or hand them to its buyer
+    /// (matches the sign part of C++ ChangeOwnershipOfCompanyItems)
+    pub fn change_owner(&mut self, old_owner: Owner, new_owner: Owner) {
+        for sign in self.signs.iter_mut().flatten() {
+            if sign.owner == old_owner {
+                sign.owner = if new_owner == Owner::Invalid {
+                    Owner::None
+                } else {
+                    new_owner
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_commands() {
+        let map = Map::new(8, 8).unwrap();
+        let mut pool = SignPool::default();
+        let hub = pool
+            .place(&map, map.tile_xy(3, 4), 8, "Hub", Owner::Company0, false)
+            .unwrap();
+        let sign = pool.get(hub).unwrap();
+        assert_eq!((sign.x, sign.y, sign.z), (48, 64, 8));
+        assert_eq!(sign.tile(&map), map.tile_xy(3, 4));
+        assert_eq!(sign.owner, Owner::Company0);
+        assert!(pool
+            .place(
+                &map,
+                TileIndex(0),
+                0,
+                &"x".repeat(32),
+                Owner::Company0,
+                false
+            )
+            .is_err());
+        // A rejected name does not leave an empty slot behind
+        assert_eq!(pool.signs.len(), 1);
+
+        // Editor signs belong to the deity, which companies may not edit
+        let goal = pool
+            .place(&map, map.tile_xy(100, 7), 0, "Goal", Owner::None, true)
+            .unwrap();
+        assert_eq!(goal, SignID(1));
+        assert_eq!(pool.get(goal).unwrap().owner, Owner::Deity);
+        assert!(pool
+            .rename(goal, "Mine", Colours::Red, Owner::Company1, false)
+            .is_err());
+        pool.rename(goal, "Finish", Colours::Red, Owner::Deity, false)
+            .unwrap();
+        assert_eq!(pool.get(goal).unwrap().text_colour, Colours::Red);
+
+        pool.rename(hub, "Main hub", Colours::Invalid, Owner::Company1, false)
+            .unwrap();
+        pool.move_to(hub, &map, map.tile_xy(5, 5), 16, Owner::Company1, false)
+            .unwrap();
+        let sign = pool.get(hub).unwrap();
+        assert_eq!(sign.name, "Main hub");
+        assert_eq!(sign.text_colour, Colours::White);
+        assert_eq!((sign.x, sign.y, sign.z), (80, 80, 16));
+        assert_eq!(sign.owner, Owner::Company1);
+
+        // Renaming to nothing removes the sign and frees its index
+        pool.rename(hub, "", Colours::Invalid, Owner::Company2, false)
+            .unwrap();
+        assert!(pool.get(hub).is_none());
+        assert_eq!(
+            pool.place(&map, TileIndex(0), 0, "", Owner::Company2, false)
+                .unwrap(),
+            hub
+        );
+    }
+
+    #[test]
+    fn test_sign_spatial_queries() {
+        let map = Map::new(8, 8).unwrap();
+        let signs = vec![
+            Sign::new(SignID(0), Owner::Company0, 16 * 10 + 5, 16 * 20, 0, "A"),
+            Sign::new(SignID(2), Owner::Company0, 16 * 12, 16 * 22 + 15, 0, "B"),
+            Sign::new(SignID(3), Owner::Company1, 16 * 40, 16 * 20, 0, "A"),
+            Sign::new(SignID(4), Owner::None, -8, 16, 0, "Off map"),
+        ];
+        let mut pool = SignPool::from_signs(signs).unwrap();
+        assert!(SignPool::from_signs(vec![
+            Sign::new(SignID(1), Owner::None, 0, 0, 0, ""),
+            Sign::new(SignID(1), Owner::None, 0, 0, 0, ""),
+        ])
+        .is_err());
+
+        let ids = |signs: Vec<&Sign>| signs.iter().map(|s| s.index.0).collect::<Vec<_>>();
+        // The corners may be given in any order and are included
+        let area = pool.in_area(&map, map.tile_xy(12, 22), map.tile_xy(10, 20));
+        assert_eq!(ids(area.collect()), [0, 2]);
+        let area = pool.in_area(&map, map.tile_xy(0, 0), map.tile_xy(255, 255));
+        assert_eq!(ids(area.collect()), [0, 2, 3]);
+        assert_eq!(ids(pool.on_tile(&map, map.tile_xy(10, 20)).collect()), [0]);
+        assert_eq!(ids(pool.with_name("A").collect()), [0, 3]);
+        assert_eq!(pool.get(SignID(4)).unwrap().tile(&map), TileIndex::INVALID);
+
+        pool.change_owner(Owner::Company0, Owner::Invalid);
+        assert_eq!(pool.get(SignID(2)).unwrap().owner, Owner::None);
+        assert_eq!(pool.get(SignID(3)).unwrap().owner, Owner::Company1);
+    }
+}
@@ -3,13 +3,17 @@
 //! This module contains station structures that are saved in savegames.
 //! All structures must maintain exact C++ compatibility for save/load.
 
+use crate::cargopacket::StationCargoList;
+use crate::error::CoreError;
 use crate::map::TileIndex;
 use crate::types::{
-    CalendarDate, CargoType, EconomyDate, IndustryID, Owner, StationID, StringID, TownID, INVALID_STRING_ID,
+    CalendarDate, CargoType, EconomyDate, IndustryID, Owner, StationID, StringID, TownID,
+    VehicleID, INVALID_STRING_ID,
 };
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use serde_with::serde_as;
+use std::collections::BTreeMap;
 
 /// Station facility types (matches C++ StationFacility enum)
 pub type StationFacility = u8;
@@ -57,40 +61,472 @@ pub enum AirportType {
     Intercontinental = 7,
     Helistation = 8,
     Oilrig = 9,
-    Invalid = 255,
+    Invalid = 254,
+    Dummy = 255,
 }
 
+impl TryFrom<u8> for AirportType {
+    type Error = CoreError;
+
+    /// NewGRF airports (10 up to 127) have no variant and are rejected
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => AirportType::Small,
+            1 => AirportType::Large,
+            2 => AirportType::Heliport,
+            3 => AirportType::Metropolitan,
+            4 => AirportType::International,
+            5 => AirportType::Commuter,
+            6 => AirportType::Helidepot,
+            7 => AirportType::Intercontinental,
+            8 => AirportType::Helistation,
+            9 => AirportType::Oilrig,
+            254 => AirportType::Invalid,
+            255 => AirportType::Dummy,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Unknown airport type {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+/// Road stop pool index (matches C++ RoadStopID)
+pub type RoadStopID = u16;
+
 /// Maximum number of cargo types
 pub const NUM_CARGO: usize = 64;
 
-/// Station cargo waiting information
-#[repr(C)]
+/// GoodsEntry status bits (matches C++ GoodsEntry::State)
+pub const GES_ACCEPTANCE: u8 = 1 << 0;
+pub const GES_RATING: u8 = 1 << 1;
+pub const GES_EVER_ACCEPTED: u8 = 1 << 2;
+pub const GES_LAST_MONTH: u8 = 1 << 3;
+pub const GES_CURRENT_MONTH: u8 = 1 << 4;
+pub const GES_ACCEPTED_BIGTICK: u8 = 1 << 5;
+
+/// Station rating of a cargo that has not been handled yet
+pub const INITIAL_STATION_RATING: u8 = 175;
+
+/// One share of the planned flow of a cargo from `source` through this station
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct StationCargoWaiting {
-    pub cargo: CargoType,
-    pub amount: u16,           // Amount waiting
-    pub source: StationID,     // Source station for cargo routing
-    pub days_in_transit: u16,  // Days cargo has been waiting
-    pub rating: u8,            // Station rating for this cargo (0-255)
-    pub last_speed: u8,        // Speed of last vehicle to load
-    pub last_age: u8,          // Age of last vehicle to load
-    pub time_since_pickup: u8, // Time since cargo was last picked up
+pub struct FlowShare {
+    pub source: StationID,
+    pub via: StationID,
+    pub share: u32,
+    /// Only used by vehicles that explicitly go to `via`
+    pub restricted: bool,
+}
+
+/// Planned flow of a cargo from one source, split up by next hop (matches C++ FlowStat)
+///
+/// Each next hop owns the shares between the previous key and its own key;
+/// shares up to `unrestricted` may be used by any vehicle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowStat {
+    shares: BTreeMap<u32, StationID>,
+    unrestricted: u32,
+}
+
+impl FlowStat {
+    pub fn new(via: StationID, flow: u32, restricted: bool) -> Self {
+        Self {
+            shares: BTreeMap::from([(flow, via)]),
+            unrestricted: if restricted { 0 } else { flow },
+        }
+    }
+
+    /// Add a share for `via` after all others
+    pub fn append_share(&mut self, via: StationID, flow: u32, restricted: bool) {
+        let last = self.shares.keys().next_back().copied().unwrap_or(0);
+        self.shares.insert(last + flow, via);
+        if !restricted {
+            self.unrestricted += flow;
+        }
+    }
+
+    pub fn shares(&self) -> &BTreeMap<u32, StationID> {
+        &self.shares
+    }
+
+    pub fn unrestricted(&self) -> u32 {
+        self.unrestricted
+    }
+
+    /// Sum of all shares
+    pub fn total(&self) -> u32 {
+        self.shares.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// The share of `via`, 0 if it has none
+    pub fn get_share(&self, via: StationID) -> u32 {
+        let mut prev = 0;
+        for (&share, &station) in &self.shares {
+            if station == via {
+                return share - prev;
+            }
+            prev = share;
+        }
+        0
+    }
+
+    /// Change the share of `via` by `flow`; `i32::MIN` removes it entirely
+    pub fn change_share(&mut self, via: StationID, mut flow: i32) {
+        let mut removed_shares = 0;
+        let mut added_shares = 0;
+        let mut last_share = 0;
+        let mut new_shares = BTreeMap::new();
+        for (&share, &station) in &self.shares {
+            if station == via {
+                if flow < 0 {
+                    let own = share - last_share;
+                    if flow == i32::MIN || flow.unsigned_abs() >= own {
+                        removed_shares += own;
+                        if share <= self.unrestricted {
+                            self.unrestricted -= own;
+                        }
+                        if flow != i32::MIN {
+                            flow += own as i32;
+                        }
+                        last_share = share;
+                        continue;
+                    }
+                    removed_shares += flow.unsigned_abs();
+                } else {
+                    added_shares += flow as u32;
+                }
+                if share <= self.unrestricted {
+                    self.unrestricted = self.unrestricted.wrapping_add_signed(flow);
+                }
+                flow = 0;
+            }
+            new_shares.insert(share + added_shares - removed_shares, station);
+            last_share = share;
+        }
+        if flow > 0 {
+            new_shares.insert(last_share + flow as u32, via);
+            if self.unrestricted < last_share {
+                // C++ releases the share before it is added, which does nothing
+                self.release_share(via);
+            } else {
+                self.unrestricted += flow as u32;
+            }
+        }
+        self.shares = new_shares;
+    }
+
+    /// Move the share of `via` behind the unrestricted ones
+    pub fn restrict_share(&mut self, via: StationID) {
+        let mut flow = 0;
+        let mut last_share = 0;
+        let mut new_shares = BTreeMap::new();
+        for (&share, &station) in &self.shares {
+            if flow == 0 {
+                if share > self.unrestricted {
+                    // Not present or already restricted
+                    return;
+                }
+                if station == via {
+                    flow = share - last_share;
+                    self.unrestricted -= flow;
+                } else {
+                    new_shares.insert(share, station);
+                }
+            } else {
+                new_shares.insert(share - flow, station);
+            }
+            last_share = share;
+        }
+        if flow == 0 {
+            return;
+        }
+        new_shares.insert(last_share + flow, via);
+        self.shares = new_shares;
+    }
+
+    /// Move the share of `via` in front of the unrestricted ones
+    pub fn release_share(&mut self, via: StationID) {
+        let mut flow = 0;
+        let mut next_share = 0;
+        let mut found = false;
+        for (&share, &station) in self.shares.iter().rev() {
+            // Not <= as the share may hit the limit
+            if share < self.unrestricted {
+                return;
+            }
+            if found {
+                flow = next_share - share;
+                self.unrestricted += flow;
+                break;
+            }
+            if share == self.unrestricted {
+                return;
+            }
+            if station == via {
+                found = true;
+            }
+            next_share = share;
+        }
+        if flow == 0 {
+            return;
+        }
+        let mut new_shares = BTreeMap::from([(flow, via)]);
+        for (&share, &station) in &self.shares {
+            if station != via {
+                new_shares.insert(flow + share, station);
+            } else {
+                flow = 0;
+            }
+        }
+        self.shares = new_shares;
+    }
+
+    /// Scale the shares from `runtime` days to a month, keeping every share
+    pub fn scale_to_monthly(&mut self, runtime: u32) {
+        let mut new_shares = BTreeMap::new();
+        let mut share = 0;
+        for (&old, &station) in &self.shares {
+            share = (share + 1).max((old as u64 * 30 / runtime as u64) as u32);
+            new_shares.insert(share, station);
+            if self.unrestricted == old {
+                self.unrestricted = share;
+            }
+        }
+        self.shares = new_shares;
+    }
+
+    pub fn swap_shares(&mut self, other: &mut FlowStat) {
+        std::mem::swap(self, other);
+    }
+
+    /// Reduce all shares to 1, for flows the link graph no longer plans
+    pub fn invalidate(&mut self) {
+        let mut new_shares = BTreeMap::new();
+        for (i, (&share, &station)) in self.shares.iter().enumerate() {
+            let i = i as u32 + 1;
+            new_shares.insert(i, station);
+            if share == self.unrestricted {
+                self.unrestricted = i;
+            }
+        }
+        self.shares = new_shares;
+    }
+}
+
+/// Planned flows of a cargo at a station by source (matches C++ FlowStatMap)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowStatMap(pub BTreeMap<StationID, FlowStat>);
+
+impl std::ops::Deref for FlowStatMap {
+    type Target = BTreeMap<StationID, FlowStat>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for FlowStatMap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FlowStatMap {
+    /// Rebuild the flows from their saved form
+    pub fn from_shares(shares: &[FlowShare]) -> Self {
+        let mut flows = Self::default();
+        let mut prev_source = None;
+        for flow in shares {
+            if prev_source != Some(flow.source) {
+                // Like C++ emplace, a source that reappears keeps its first flows
+                flows
+                    .entry(flow.source)
+                    .or_insert_with(|| FlowStat::new(flow.via, flow.share, flow.restricted));
+            } else if let Some(fs) = flows.get_mut(&flow.source) {
+                fs.append_share(flow.via, flow.share, flow.restricted);
+            }
+            prev_source = Some(flow.source);
+        }
+        flows
+    }
+
+    /// The flows as saved, with the share of each next hop on its own
+    pub fn to_shares(&self) -> Vec<FlowShare> {
+        let mut shares = Vec::new();
+        for (&source, fs) in self.iter() {
+            let mut sum = 0;
+            for (&share, &via) in fs.shares() {
+                shares.push(FlowShare {
+                    source,
+                    via,
+                    share: share - sum,
+                    restricted: share > fs.unrestricted(),
+                });
+                sum = share;
+            }
+        }
+        shares
+    }
+
+    pub fn get_flow(&self) -> u32 {
+        self.values().map(FlowStat::total).sum()
+    }
+
+    pub fn get_flow_via(&self, via: StationID) -> u32 {
+        self.values().map(|fs| fs.get_share(via)).sum()
+    }
+
+    pub fn get_flow_from(&self, from: StationID) -> u32 {
+        self.get(&from).map_or(0, FlowStat::total)
+    }
+
+    pub fn get_flow_from_via(&self, from: StationID, via: StationID) -> u32 {
+        self.get(&from).map_or(0, |fs| fs.get_share(via))
+    }
+
+    /// Add `flow` from `origin` via `via`
+    pub fn add_flow(&mut self, origin: StationID, via: StationID, flow: u32) {
+        match self.get_mut(&origin) {
+            Some(fs) => fs.change_share(via, flow as i32),
+            None => {
+                self.insert(origin, FlowStat::new(via, flow, false));
+            }
+        }
+    }
+
+    /// Pass on `flow` from `origin` that was marked for local consumption to `via`
+    pub fn pass_on_flow(&mut self, origin: StationID, via: StationID, flow: u32) {
+        match self.get_mut(&origin) {
+            Some(fs) => {
+                fs.change_share(via, flow as i32);
+                fs.change_share(StationID::INVALID, flow as i32);
+            }
+            None => {
+                let mut fs = FlowStat::new(via, flow, false);
+                fs.append_share(StationID::INVALID, flow, false);
+                self.insert(origin, fs);
+            }
+        }
+    }
+
+    /// Remove all flows via `via`; returns the sources left without flows
+    pub fn delete_flows(&mut self, via: StationID) -> Vec<StationID> {
+        let mut deleted = Vec::new();
+        self.retain(|&source, fs| {
+            fs.change_share(via, i32::MIN);
+            if fs.shares().is_empty() {
+                deleted.push(source);
+            }
+            !fs.shares().is_empty()
+        });
+        deleted
+    }
+
+    pub fn restrict_flows(&mut self, via: StationID) {
+        for fs in self.values_mut() {
+            fs.restrict_share(via);
+        }
+    }
+
+    pub fn release_flows(&mut self, via: StationID) {
+        for fs in self.values_mut() {
+            fs.release_share(via);
+        }
+    }
+
+    /// Turn the shares marked for local consumption into shares of `own`
+    pub fn finalize_local_consumption(&mut self, own: StationID) {
+        for fs in self.values_mut() {
+            let mut local = fs.get_share(StationID::INVALID);
+            if local > i32::MAX as u32 {
+                fs.change_share(own, -i32::MAX);
+                fs.change_share(StationID::INVALID, -i32::MAX);
+                local -= i32::MAX as u32;
+            }
+            fs.change_share(own, -(local as i32));
+            fs.change_share(StationID::INVALID, -(local as i32));
+        }
+    }
 }
 
 /// Good entry in station's goods list
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GoodsEntry {
-    pub acceptance: bool,       // Station accepts this cargo
-    pub rating: u8,             // Station rating (0-255)
-    pub last_speed: u8,         // Speed of last vehicle
-    pub last_age: u8,           // Age of last vehicle
-    pub amount_waiting: u16,    // Cargo amount waiting
-    pub time_since_pickup: u8,  // Time since last pickup
-    pub days_in_transit: u16,   // Average days in transit
-    pub max_waiting_cargo: u16, // Maximum cargo ever waiting
-    pub from: StationID,        // Source station for cargo
-    pub via: StationID,         // Next hop station
+    pub acceptance: bool,        // Station accepts this cargo
+    pub status: u8,              // GES_* bits; GES_ACCEPTANCE mirrors `acceptance`
+    pub rating: u8,              // Station rating (0-255)
+    pub last_speed: u8,          // Speed of last vehicle
+    pub last_age: u8,            // Age of last vehicle
+    pub amount_waiting: u16,     // Cargo amount waiting
+    pub amount_fract: u8,        // Fractional part of the waiting amount
+    pub time_since_pickup: u8,   // Time since last pickup
+    pub days_in_transit: u16,    // Average days in transit
+    pub max_waiting_cargo: u32,  // Maximum cargo ever waiting
+    pub link_graph: u16,         // Link graph this station belongs to
+    pub node: u16,               // Node of this goods entry in the link graph
+    pub from: StationID,         // Source station for cargo
+    pub via: StationID,          // Next hop station
+    pub flows: Vec<FlowShare>,   // Planned flows, grouped by source
+    pub cargo: StationCargoList, // Waiting cargo by next hop
+}
+
+impl Default for GoodsEntry {
+    fn default() -> Self {
+        Self {
+            acceptance: false,
+            status: 0,
+            rating: INITIAL_STATION_RATING,
+            last_speed: 0,
+            last_age: 255,
+            amount_waiting: 0,
+            amount_fract: 0,
+            time_since_pickup: 255,
+            days_in_transit: 0,
+            max_waiting_cargo: 0,
+            link_graph: 0xFFFF,
+            node: 0xFFFF,
+            from: StationID::INVALID,
+            via: StationID::INVALID,
+            flows: Vec::new(),
+            cargo: StationCargoList::default(),
+        }
+    }
+}
+
+impl GoodsEntry {
+    /// The planned flows in the form the link graph works with
+    pub fn flow_stats(&self) -> FlowStatMap {
+        FlowStatMap::from_shares(&self.flows)
+    }
+
+    pub fn set_flow_stats(&mut self, flows: &FlowStatMap) {
+        self.flows = flows.to_shares();
+    }
+}
+
+/// Area of tiles covered by a station part (matches C++ TileArea)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileArea {
+    pub tile: TileIndex,
+    pub w: u16,
+    pub h: u16,
+}
+
+/// Local index of a NewGRF spec used by a station (matches C++ SpecMapping)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecMapping {
+    pub grfid: u32,
+    pub localidx: u16,
+}
+
+/// Animation state of a NewGRF road stop tile (matches C++ RoadStopTileData)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoadStopTileData {
+    pub tile: TileIndex,
+    pub random_bits: u8,
+    pub animation_frame: u8,
 }
 
 /// Station specification for custom graphics
@@ -137,7 +573,7 @@ impl StationRect {
 /// Station structure (matches C++ BaseStation/Station for savegame compatibility)
 #[repr(C)]
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Station {
     /// Station index/ID
     pub index: StationID,
@@ -154,6 +590,9 @@ pub struct Station {
     /// Custom name string (if renamed)
     pub string_id: StringID,
 
+    /// Name given by the player, empty if not renamed
+    pub custom_name: String,
+
     /// Town this station belongs to
     pub town: TownID,
 
@@ -163,8 +602,22 @@ pub struct Station {
     /// Facilities available at station
     pub facilities: StationFacility,
 
-    /// Airport type (if applicable)
-    pub airport_type: AirportType,
+    /// Days until an unused station is removed
+    pub delete_ctr: u8,
+
+    /// Random bits and waiting triggers for NewGRF graphics
+    pub random_bits: u16,
+    pub waiting_triggers: u8,
+
+    /// Airport type (if applicable); see `AirportType`, NewGRF airports use higher values
+    pub airport_type: u8,
+
+    /// Airport tiles and layout
+    pub airport_area: TileArea,
+    pub airport_layout: u8,
+
+    /// Persistent storage of a NewGRF airport
+    pub airport_psa: Option<u32>,
 
     /// Airport flags/state
     pub airport_flags: u64,
@@ -175,8 +628,16 @@ pub struct Station {
     /// Dock tile location
     pub dock_tile: TileIndex,
 
-    /// Train station length/width
-    pub train_station: StationRect,
+    /// Train station tiles
+    pub train_station: TileArea,
+
+    /// Ship station and docking tiles
+    pub ship_station: TileArea,
+    pub docking_station: TileArea,
+
+    /// First bus and truck stop of the station's road stop lists
+    pub bus_stops: Option<RoadStopID>,
+    pub truck_stops: Option<RoadStopID>,
 
     /// Date station was built
     pub build_date: CalendarDate,
@@ -188,6 +649,18 @@ pub struct Station {
     /// Had vehicle of type (bit per vehicle type)
     pub had_vehicle_of_type: u8,
 
+    /// Type of the last vehicle that visited
+    pub last_vehicle_type: u8,
+
+    /// Industry type of the industry this station belongs to (oil rigs)
+    pub indtype: u8,
+
+    /// Vehicles currently loading at the station
+    pub loading_vehicles: Vec<VehicleID>,
+
+    /// Cargo types accepted by a tile of the station itself
+    pub always_accepted: u64,
+
     /// Cargo waiting at station
     #[serde_as(as = "[_; 64]")]
     pub goods: [GoodsEntry; NUM_CARGO],
@@ -203,15 +676,24 @@ pub struct Station {
     /// Station signs
     pub sign: StationRect,
 
-    /// Waiting cargo by destination
-    pub cargo_waiting: Vec<StationCargoWaiting>,
-
     /// Industries in catchment area
     pub industries_near: Vec<IndustryID>,
 
     /// Station specification for custom graphics
     pub spec: StationSpec,
 
+    /// NewGRF station and road stop specs in use
+    pub speclist: Vec<SpecMapping>,
+    pub roadstop_speclist: Vec<SpecMapping>,
+    pub roadstop_tile_data: Vec<RoadStopTileData>,
+
+    /// Waypoint only: number to distinguish waypoints of the same town
+    pub town_cn: u16,
+
+    /// Waypoint only: flags and road waypoint tiles
+    pub waypoint_flags: u16,
+    pub road_waypoint_area: TileArea,
+
     /// Date of last cargo pickup by type
     #[serde_as(as = "[_; 64]")]
     pub last_pickup_date: [EconomyDate; NUM_CARGO],
@@ -226,26 +708,46 @@ impl Station {
             rect: StationRect::default(),
             name: INVALID_STRING_ID,
             string_id: INVALID_STRING_ID,
+            custom_name: String::new(),
             town: TownID::INVALID,
             owner,
             facilities: FACIL_NONE,
-            airport_type: AirportType::Invalid,
+            delete_ctr: 0,
+            random_bits: 0,
+            waiting_triggers: 0,
+            airport_type: AirportType::Dummy as u8,
+            airport_area: TileArea::default(),
+            airport_layout: 0,
+            airport_psa: None,
             airport_flags: 0,
             airport_rotation: 0,
             dock_tile: TileIndex::INVALID,
-            train_station: StationRect::default(),
+            train_station: TileArea::default(),
+            ship_station: TileArea::default(),
+            docking_station: TileArea::default(),
+            bus_stops: None,
+            truck_stops: None,
             build_date: CalendarDate(0),
             bus_stop_status: 0,
             truck_stop_status: 0,
             had_vehicle_of_type: 0,
-            goods: [GoodsEntry::default(); NUM_CARGO],
+            last_vehicle_type: 0xFF,
+            indtype: 0xFF,
+            loading_vehicles: Vec::new(),
+            always_accepted: 0,
+            goods: std::array::from_fn(|_| GoodsEntry::default()),
             acceptance: [0; NUM_CARGO],
             time_since_load: 255,
             time_since_unload: 255,
             sign: StationRect::default(),
-            cargo_waiting: Vec::new(),
             industries_near: Vec::new(),
             spec: StationSpec::default(),
+            speclist: Vec::new(),
+            roadstop_speclist: Vec::new(),
+            roadstop_tile_data: Vec::new(),
+            town_cn: 0,
+            waypoint_flags: 0,
+            road_waypoint_area: TileArea::default(),
             last_pickup_date: [EconomyDate(0); NUM_CARGO],
         }
     }
@@ -255,9 +757,9 @@ impl Station {
         self.facilities & facilities != 0
     }
 
-    /// Check if station is a waypoint
+    /// Check if station is a waypoint (rail waypoints also have FACIL_TRAIN set)
     pub fn is_waypoint(&self) -> bool {
-        self.facilities == FACIL_WAYPOINT
+        self.facilities & FACIL_WAYPOINT != 0
     }
 
     /// Check if station has an airport
@@ -311,11 +813,11 @@ impl Station {
         }
         if self.has_airport() {
             // Airport catchment depends on type
-            radius = radius.max(match self.airport_type {
-                AirportType::Small | AirportType::Heliport => 4,
-                AirportType::Large | AirportType::Metropolitan => 6,
-                AirportType::International => 8,
-                AirportType::Intercontinental => 10,
+            radius = radius.max(match AirportType::try_from(self.airport_type) {
+                Ok(AirportType::Small | AirportType::Heliport) => 4,
+                Ok(AirportType::Large | AirportType::Metropolitan) => 6,
+                Ok(AirportType::International) => 8,
+                Ok(AirportType::Intercontinental) => 10,
                 _ => 4,
             });
         }
@@ -349,6 +851,20 @@ mod tests {
 
         station.facilities = FACIL_WAYPOINT;
         assert!(station.is_waypoint());
+        station.facilities = FACIL_WAYPOINT | FACIL_TRAIN;
+        assert!(station.is_waypoint());
+    }
+
+    #[test]
+    fn test_airport_type_values() {
+        assert_eq!(AirportType::try_from(9).unwrap(), AirportType::Oilrig);
+        assert_eq!(AirportType::try_from(255).unwrap(), AirportType::Dummy);
+        assert!(AirportType::try_from(10).is_err());
+
+        let mut station = Station::new(StationID(1), TileIndex(1000), Owner::Company0);
+        station.facilities = FACIL_AIRPORT;
+        station.airport_type = AirportType::International as u8;
+        assert_eq!(station.get_catchment_radius(), 8);
     }
 
     #[test]
@@ -364,4 +880,49 @@ mod tests {
         assert_eq!(rect.width(), 21);
         assert_eq!(rect.height(), 21);
     }
+
+    #[test]
+    fn test_flow_stat_shares() {
+        let (a, b, c) = (StationID(1), StationID(2), StationID(3));
+        let mut stat = FlowStat::new(a, 10, false);
+        stat.append_share(b, 20, false);
+        stat.append_share(c, 5, true);
+        assert_eq!(stat.total(), 35);
+        assert_eq!(stat.unrestricted(), 30);
+        assert_eq!(stat.get_share(b), 20);
+
+        stat.change_share(a, -4);
+        assert_eq!((stat.get_share(a), stat.unrestricted()), (6, 26));
+        stat.restrict_share(a);
+        assert_eq!(stat.shares().values().collect::<Vec<_>>(), [&b, &c, &a]);
+        assert_eq!(stat.unrestricted(), 20);
+        stat.release_share(a);
+        assert_eq!(stat.shares().values().collect::<Vec<_>>(), [&a, &b, &c]);
+        assert_eq!(stat.get_share(b), 20);
+
+        stat.change_share(b, i32::MIN);
+        assert_eq!(stat.total(), 17);
+        stat.invalidate();
+        assert_eq!(stat.shares().keys().collect::<Vec<_>>(), [&1, &2]);
+        assert_eq!(stat.unrestricted(), 1);
+    }
+
+    #[test]
+    fn test_flow_stat_map() {
+        let (a, b, c) = (StationID(1), StationID(2), StationID(3));
+        let mut flows = FlowStatMap::default();
+        flows.add_flow(a, b, 10);
+        flows.add_flow(c, b, 5);
+        flows.add_flow(a, c, 6);
+        assert_eq!(flows.get_flow(), 21);
+        assert_eq!(flows.get_flow_via(b), 15);
+        assert_eq!(flows.get_flow_from_via(a, c), 6);
+
+        let mut goods = GoodsEntry::default();
+        goods.set_flow_stats(&flows);
+        assert_eq!(goods.flow_stats(), flows);
+
+        assert_eq!(flows.delete_flows(b), [c]);
+        assert_eq!(flows.get_flow_from(a), 6);
+    }
 }
@@ -0,0 +1,932 @@
+//! Subsidy data structures for OpenTTD
+//!
+//! Subsidies are offered each month for cargo routes between towns and
+//! industries that are hardly served, and awarded to the first company to
+//! deliver on them. Deliveries on an awarded route earn multiplied income
+//! until the subsidy runs out. The logic follows C++ subsidy.cpp; news and
+//! script events are left to the caller.
+
+use crate::cargopacket::{Source, SourceType};
+use crate::error::CoreError;
+use crate::industry::Industry;
+use crate::linkgraph::{DistributionType, LinkGraphSettings};
+use crate::map::{Map, TileIndex};
+use crate::random::Randomizer;
+use crate::station::Station;
+use crate::town::{Town, TownCargo};
+use crate::types::{CargoClasses, CargoType, IndustryID, Money, Owner, TownID, LAST_MONTH};
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+/// Subsidy pool index (matches C++ SubsidyID)
+pub type SubsidyID = u16;
+
+/// Maximum number of subsidies (matches C++ SubsidyPool)
+pub const MAX_SUBSIDIES: usize = 256;
+
+/// Months a subsidy is offered before the offer expires
+pub const SUBSIDY_OFFER_MONTHS: u16 = 12;
+/// Minimum population of the towns of a passenger subsidy
+pub const SUBSIDY_PAX_MIN_POPULATION: u32 = 400;
+/// Minimum population of the source town of a cargo subsidy
+pub const SUBSIDY_CARGO_MIN_POPULATION: u32 = 900;
+/// Highest fraction of the source's production, scaled to 0-255, that may be transported already
+pub const SUBSIDY_MAX_PCT_TRANSPORTED: u8 = 42;
+/// Maximum Manhattan distance between source and destination
+pub const SUBSIDY_MAX_DISTANCE: u32 = 70;
+/// Radius around the town centre whose houses produce and accept a town's subsidy cargo
+pub const SUBSIDY_TOWN_CARGO_RADIUS: u32 = 6;
+
+/// Attempts at finding a route each month a subsidy is offered
+const SUBSIDY_ROUTE_ATTEMPTS: u32 = 1001;
+
+bitflags! {
+    /// Part a town or industry plays in subsidies (matches C++ PartOfSubsidy)
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct PartOfSubsidy: u8 {
+        const SOURCE = 1 << 0;
+        const DESTINATION = 1 << 1;
+    }
+}
+
+/// A subsidised route (matches C++ Subsidy)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subsidy {
+    pub index: SubsidyID,
+    /// Cargo to deliver
+    pub cargo_type: CargoType,
+    /// Months until the offer expires, or the award runs out once awarded
+    pub remaining: u16,
+    /// Company the subsidy is awarded to, Invalid while it is on offer
+    pub awarded: Owner,
+    pub src: Source,
+    pub dst: Source,
+}
+
+impl Subsidy {
+    pub fn new(index: SubsidyID, cargo_type: CargoType, src: Source, dst: Source) -> Self {
+        Self {
+            index,
+            cargo_type,
+            remaining: 0,
+            awarded: Owner::Invalid,
+            src,
+            dst,
+        }
+    }
+
+    pub fn is_awarded(&self) -> bool {
+        self.awarded != Owner::Invalid
+    }
+
+    /// Award the subsidy, which then runs for `duration` years (matches C++ Subsidy::AwardTo)
+    pub fn award_to(&mut self, company: Owner, duration: u16) {
+        assert!(!self.is_awarded(), "subsidy {} already awarded", self.index);
+        self.awarded = company;
+        self.remaining = duration * 12;
+    }
+}
+
+/// Difficulty settings of subsidies (matches C++ DifficultySettings)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubsidySettings {
+    /// Years an awarded subsidy runs; 0 disables new offers
+    pub duration: u16,
+    /// Income multiplier: 0 for x1.5, 1 for x2, 2 for x3 and 3 for x4
+    pub multiplier: u8,
+}
+
+impl Default for SubsidySettings {
+    fn default() -> Self {
+        Self {
+            duration: 1,
+            multiplier: 2,
+        }
+    }
+}
+
+impl SubsidySettings {
+    /// Income of a subsidised delivery (matches the subsidy part of C++ DeliverGoods)
+    pub fn subsidised_profit(&self, profit: Money) -> Money {
+        match self.multiplier {
+            0 => profit + (profit >> 1),
+            1 => profit * 2,
+            2 => profit * 3,
+            _ => profit * 4,
+        }
+    }
+}
+
+/// Knowledge of cargoes and houses the subsidy offers depend on
+pub struct SubsidyEnvironment<'a> {
+    /// Cargoes towns produce as passengers (matches C++ CargoSpec::town_production_cargoes[TPE_PASSENGERS])
+    pub passenger_cargoes: &'a [CargoType],
+    /// Classes of a cargo, None when the cargo has no valid spec
+    pub cargo_classes: &'a dyn Fn(CargoType) -> Option<CargoClasses>,
+    /// Production and acceptance of the houses within SUBSIDY_TOWN_CARGO_RADIUS of a town's centre
+    pub town_area_cargo: &'a dyn Fn(&Town) -> TownCargo,
+    pub linkgraph: &'a LinkGraphSettings,
+}
+
+impl SubsidyEnvironment<'_> {
+    fn is_manually_distributed(&self, cargo: CargoType) -> bool {
+        let classes = (self.cargo_classes)(cargo).unwrap_or_default();
+        self.linkgraph.distribution_type(classes) == DistributionType::Manual
+    }
+}
+
+/// What happened to the subsidies in a month
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubsidyEvent {
+    Offered(SubsidyID),
+    /// An offer ran out without being awarded
+    OfferExpired(Subsidy),
+    /// An awarded subsidy ran out
+    Expired(Subsidy),
+}
+
+fn town_index(towns: &[Town], id: u16) -> Option<usize> {
+    towns.iter().position(|t| t.index == TownID(id))
+}
+
+fn industry_index(industries: &[Industry], id: u16) -> Option<usize> {
+    industries.iter().position(|i| i.index == IndustryID(id))
+}
+
+/// Tile a source is measured from; None for missing towns and industries
+fn source_tile(source: Source, towns: &[Town], industries: &[Industry]) -> Option<TileIndex> {
+    match source.type_ {
+        SourceType::Town => town_index(towns, source.id).map(|t| towns[t].xy),
+        SourceType::Industry => {
+            industry_index(industries, source.id).map(|i| industries[i].location)
+        }
+        SourceType::Headquarters => None,
+    }
+}
+
+/// Random town or industry, in index order (matches C++ Town::GetRandom and Industry::GetRandom)
+fn random_index(len: usize, random: &mut Randomizer) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    Some(random.next_range(len as u16 as u32) as usize)
+}
+
+/// True with a chance of one in two (matches C++ Chance16(1, 2))
+fn chance_half(random: &mut Randomizer) -> bool {
+    ((random.next_u32() as u16 as u32 * 2 + 1) >> 16) < 1
+}
+
+/// One of the ways of finding a route for a new subsidy
+type RouteFinder = fn(
+    &mut SubsidyPool,
+    &mut [Town],
+    &mut [Industry],
+    &Map,
+    &mut Randomizer,
+    &SubsidyEnvironment,
+) -> Option<SubsidyID>;
+
+/// All subsidies of a game, by index
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubsidyPool {
+    subsidies: Vec<Option<Subsidy>>,
+}
+
+impl SubsidyPool {
+    /// Build a pool from subsidies at their own indices
+    pub fn from_subsidies(subsidies: Vec<Subsidy>) -> Result<Self, CoreError> {
+        let mut pool = Self::default();
+        for subsidy in subsidies {
+            let index = subsidy.index as usize;
+            if index >= MAX_SUBSIDIES {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid subsidy index {}",
+                    index
+                )));
+            }
+            if index >= pool.subsidies.len() {
+                pool.subsidies.resize(index + 1, None);
+            }
+            if pool.subsidies[index].replace(subsidy).is_some() {
+                return Err(CoreError::InvalidData(format!(
+                    "Duplicate subsidy {}",
+                    index
+                )));
+            }
+        }
+        Ok(pool)
+    }
+
+    pub fn get(&self, index: SubsidyID) -> Option<&Subsidy> {
+        self.subsidies.get(index as usize)?.as_ref()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Subsidy> {
+        self.subsidies.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subsidies.iter().all(Option::is_none)
+    }
+
+    fn can_allocate(&self) -> bool {
+        self.subsidies.len() < MAX_SUBSIDIES || self.subsidies.iter().any(Option::is_none)
+    }
+
+    /// Mark the towns and industries of all subsidies
+    /// (matches C++ RebuildSubsidisedSourceAndDestinationCache)
+    pub fn rebuild_cache(&self, towns: &mut [Town], industries: &mut [Industry]) {
+        for town in towns.iter_mut() {
+            town.part_of_subsidy = PartOfSubsidy::empty();
+        }
+        for industry in industries.iter_mut() {
+            industry.part_of_subsidy = PartOfSubsidy::empty();
+        }
+        for subsidy in self.iter() {
+            set_part_of_subsidy(subsidy.src, PartOfSubsidy::SOURCE, towns, industries);
+            set_part_of_subsidy(subsidy.dst, PartOfSubsidy::DESTINATION, towns, industries);
+        }
+    }
+
+    /// Remove the subsidies from or to a town or industry that is going away
+    /// (matches C++ DeleteSubsidyWith)
+    pub fn delete_with(&mut self, source: Source, towns: &mut [Town], industries: &mut [Industry]) {
+        let mut dirty = false;
+        for slot in &mut self.subsidies {
+            if slot
+                .as_ref()
+                .is_some_and(|s| s.src == source || s.dst == source)
+            {
+                *slot = None;
+                dirty = true;
+            }
+        }
+        if dirty {
+            self.rebuild_cache(towns, industries);
+        }
+    }
+
+    /// Hand the awarded subsidies of a company to its buyer, or remove them when
+    /// `new_owner` is Invalid (matches the subsidy part of C++ ChangeOwnershipOfCompanyItems)
+    pub fn change_owner(
+        &mut self,
+        old_owner: Owner,
+        new_owner: Owner,
+        towns: &mut [Town],
+        industries: &mut [Industry],
+    ) {
+        for slot in &mut self.subsidies {
+            let Some(subsidy) = slot else { continue };
+            if subsidy.awarded != old_owner {
+                continue;
+            }
+            if new_owner == Owner::Invalid {
+                *slot = None;
+            } else {
+                subsidy.awarded = new_owner;
+            }
+        }
+        if new_owner == Owner::Invalid {
+            self.rebuild_cache(towns, industries);
+        }
+    }
+
+    /// Offer a subsidy at the first free index (matches C++ CreateSubsidy)
+    fn offer(
+        &mut self,
+        cargo_type: CargoType,
+        src: Source,
+        dst: Source,
+        towns: &mut [Town],
+        industries: &mut [Industry],
+    ) -> Option<SubsidyID> {
+        let index = match self.subsidies.iter().position(Option::is_none) {
+            Some(index) => index,
+            None if self.subsidies.len() < MAX_SUBSIDIES => {
+                self.subsidies.push(None);
+                self.subsidies.len() - 1
+            }
+            None => return None,
+        };
+        let mut subsidy = Subsidy::new(index as SubsidyID, cargo_type, src, dst);
+        subsidy.remaining = SUBSIDY_OFFER_MONTHS;
+        self.subsidies[index] = Some(subsidy);
+        set_part_of_subsidy(src, PartOfSubsidy::SOURCE, towns, industries);
+        set_part_of_subsidy(dst, PartOfSubsidy::DESTINATION, towns, industries);
+        Some(index as SubsidyID)
+    }
+
+    /// Offer a subsidy chosen by a game script (matches C++ CmdCreateSubsidy);
+    /// checking that the cargo has a valid spec is left to the caller
+    pub fn create(
+        &mut self,
+        cargo_type: CargoType,
+        src: Source,
+        dst: Source,
+        towns: &mut [Town],
+        industries: &mut [Industry],
+    ) -> Result<SubsidyID, CoreError> {
+        if !cargo_type.is_valid() {
+            return Err(CoreError::InvalidData(format!(
+                "Invalid subsidy cargo {}",
+                cargo_type.0
+            )));
+        }
+        for source in [src, dst] {
+            if source.type_ == SourceType::Headquarters
+                || source_tile(source, towns, industries).is_none()
+            {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid subsidy source {:?} {}",
+                    source.type_, source.id
+                )));
+            }
+        }
+        self.offer(cargo_type, src, dst, towns, industries)
+            .ok_or_else(|| CoreError::InvalidData("Too many subsidies".into()))
+    }
+
+    /// Whether a subsidy for the route is offered or awarded already
+    /// (matches C++ CheckSubsidyDuplicate)
+    fn is_duplicate(&self, cargo_type: CargoType, src: Source, dst: Source) -> bool {
+        self.iter()
+            .any(|s| s.cargo_type == cargo_type && s.src == src && s.dst == dst)
+    }
+
+    /// Whether source and destination are close enough (matches C++ CheckSubsidyDistance)
+    fn is_within_distance(
+        src: Source,
+        dst: Source,
+        towns: &[Town],
+        industries: &[Industry],
+        map: &Map,
+    ) -> bool {
+        match (
+            source_tile(src, towns, industries),
+            source_tile(dst, towns, industries),
+        ) {
+            (Some(a), Some(b)) => map.distance_manhattan(a, b) <= SUBSIDY_MAX_DISTANCE,
+            _ => false,
+        }
+    }
+
+    /// Age the subsidies and try to offer a new one; `towns` and `industries`
+    /// must be in index order (matches C++ _economy_subsidies_monthly)
+    pub fn monthly_loop(
+        &mut self,
+        towns: &mut [Town],
+        industries: &mut [Industry],
+        map: &Map,
+        settings: &SubsidySettings,
+        random: &mut Randomizer,
+        env: &SubsidyEnvironment,
+    ) -> Vec<SubsidyEvent> {
+        let mut events = Vec::new();
+        for slot in &mut self.subsidies {
+            let Some(subsidy) = slot else { continue };
+            subsidy.remaining = subsidy.remaining.wrapping_sub(1);
+            if subsidy.remaining == 0 {
+                let subsidy = slot.take().unwrap();
+                events.push(if subsidy.is_awarded() {
+                    SubsidyEvent::Expired(subsidy)
+                } else {
+                    SubsidyEvent::OfferExpired(subsidy)
+                });
+            }
+        }
+
+        let linkgraph = env.linkgraph;
+        if !events.is_empty() {
+            self.rebuild_cache(towns, industries);
+        } else if settings.duration == 0
+            || [
+                linkgraph.distribution_pax,
+                linkgraph.distribution_mail,
+                linkgraph.distribution_armoured,
+                linkgraph.distribution_default,
+            ]
+            .iter()
+            .all(|&d| d != DistributionType::Manual)
+        {
+            // Disabled, or no cargo distributed manually
+            return events;
+        }
+
+        let route: RouteFinder = match random.next_range(16) {
+            0 | 1 if linkgraph.distribution_pax == DistributionType::Manual => {
+                Self::find_passenger_route
+            }
+            2 => Self::find_town_cargo_route,
+            3 => Self::find_industry_cargo_route,
+            _ => return events,
+        };
+        for _ in 0..SUBSIDY_ROUTE_ATTEMPTS {
+            if let Some(id) = route(self, towns, industries, map, random, env) {
+                events.push(SubsidyEvent::Offered(id));
+                break;
+            }
+        }
+        events
+    }
+
+    /// Try to offer a passenger subsidy between two towns (matches C++ FindSubsidyPassengerRoute)
+    fn find_passenger_route(
+        &mut self,
+        towns: &mut [Town],
+        industries: &mut [Industry],
+        map: &Map,
+        random: &mut Randomizer,
+        env: &SubsidyEnvironment,
+    ) -> Option<SubsidyID> {
+        if !self.can_allocate() || env.passenger_cargoes.is_empty() {
+            return None;
+        }
+        let r = random.next_range(env.passenger_cargoes.len() as u32);
+        let cargo_type = env.passenger_cargoes[r as usize];
+
+        let src = &towns[random_index(towns.len(), random)?];
+        if src.population < SUBSIDY_PAX_MIN_POPULATION
+            || src.get_percent_transported(cargo_type) > SUBSIDY_MAX_PCT_TRANSPORTED
+        {
+            return None;
+        }
+        let dst = &towns[random_index(towns.len(), random)?];
+        if dst.population < SUBSIDY_PAX_MIN_POPULATION || src.index == dst.index {
+            return None;
+        }
+        if map.distance_manhattan(src.xy, dst.xy) > SUBSIDY_MAX_DISTANCE {
+            return None;
+        }
+
+        let src = Source {
+            id: src.index.0,
+            type_: SourceType::Town,
+        };
+        let dst = Source {
+            id: dst.index.0,
+            type_: SourceType::Town,
+        };
+        if self.is_duplicate(cargo_type, src, dst) {
+            return None;
+        }
+        self.offer(cargo_type, src, dst, towns, industries)
+    }
+
+    /// Try to offer a subsidy for cargo produced by a town (matches C++ FindSubsidyTownCargoRoute)
+    fn find_town_cargo_route(
+        &mut self,
+        towns: &mut [Town],
+        industries: &mut [Industry],
+        map: &Map,
+        random: &mut Randomizer,
+        env: &SubsidyEnvironment,
+    ) -> Option<SubsidyID> {
+        if !self.can_allocate() {
+            return None;
+        }
+        let src_town = &towns[random_index(towns.len(), random)?];
+        if src_town.population < SUBSIDY_CARGO_MIN_POPULATION {
+            return None;
+        }
+
+        // Passenger subsidies are not handled here
+        let mut produced = (env.town_area_cargo)(src_town).produced;
+        for cargo in env.passenger_cargoes {
+            produced[cargo.as_usize()] = 0;
+        }
+        let cargo_count = produced.iter().filter(|&&amount| amount > 0).count() as u8;
+        if cargo_count == 0 {
+            return None;
+        }
+
+        let cargo_number = random.next_range(cargo_count as u32) as usize;
+        let cargo_type = produced
+            .iter()
+            .enumerate()
+            .filter(|(_, &amount)| amount > 0)
+            .nth(cargo_number)
+            .map(|(cargo, _)| CargoType(cargo as u8))?;
+        if (env.cargo_classes)(cargo_type).is_none() || !env.is_manually_distributed(cargo_type) {
+            return None;
+        }
+        if src_town.get_percent_transported(cargo_type) > SUBSIDY_MAX_PCT_TRANSPORTED {
+            return None;
+        }
+
+        let src = Source {
+            id: src_town.index.0,
+            type_: SourceType::Town,
+        };
+        self.find_cargo_destination(cargo_type, src, towns, industries, map, random, env)
+    }
+
+    /// Try to offer a subsidy for cargo produced by an industry
+    /// (matches C++ FindSubsidyIndustryCargoRoute)
+    fn find_industry_cargo_route(
+        &mut self,
+        towns: &mut [Town],
+        industries: &mut [Industry],
+        map: &Map,
+        random: &mut Randomizer,
+        env: &SubsidyEnvironment,
+    ) -> Option<SubsidyID> {
+        if !self.can_allocate() {
+            return None;
+        }
+        let src_ind = &industries[random_index(industries.len(), random)?];
+
+        let num_cargos = src_ind
+            .produced_cargo
+            .iter()
+            .filter(|p| p.cargo.is_valid())
+            .count();
+        if num_cargos == 0 {
+            return None;
+        }
+        let cargo_num = random.next_range(num_cargos as u32) as usize;
+        let slot = src_ind
+            .produced_cargo
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.cargo.is_valid())
+            .nth(cargo_num)
+            .map(|(slot, _)| slot)
+            .expect("cargo number within the valid produced cargoes");
+
+        let cargo_type = src_ind.produced_cargo[slot].cargo;
+        let last_month = src_ind.produced_history[slot]
+            .get(LAST_MONTH)
+            .copied()
+            .unwrap_or_default();
+        if last_month.production == 0
+            || last_month.pct_transported() > SUBSIDY_MAX_PCT_TRANSPORTED
+            || !env.is_manually_distributed(cargo_type)
+        {
+            return None;
+        }
+
+        let src = Source {
+            id: src_ind.index.0,
+            type_: SourceType::Industry,
+        };
+        self.find_cargo_destination(cargo_type, src, towns, industries, map, random, env)
+    }
+
+    /// Try to find a town or industry to deliver the cargo of a new subsidy to
+    /// (matches C++ FindSubsidyCargoDestination)
+    #[allow(clippy::too_many_arguments)]
+    fn find_cargo_destination(
+        &mut self,
+        cargo_type: CargoType,
+        src: Source,
+        towns: &mut [Town],
+        industries: &mut [Industry],
+        map: &Map,
+        random: &mut Randomizer,
+        env: &SubsidyEnvironment,
+    ) -> Option<SubsidyID> {
+        let dst = if chance_half(random) {
+            let dst_town = &towns[random_index(towns.len(), random)?];
+            if (env.town_area_cargo)(dst_town).accepted[cargo_type.as_usize()] < 8 {
+                return None;
+            }
+            Source {
+                id: dst_town.index.0,
+                type_: SourceType::Town,
+            }
+        } else {
+            let dst_ind = &industries[random_index(industries.len(), random)?];
+            if !dst_ind.accepts(cargo_type) {
+                return None;
+            }
+            Source {
+                id: dst_ind.index.0,
+                type_: SourceType::Industry,
+            }
+        };
+
+        if src == dst
+            || !Self::is_within_distance(src, dst, towns, industries, map)
+            || self.is_duplicate(cargo_type, src, dst)
+        {
+            return None;
+        }
+        self.offer(cargo_type, src, dst, towns, industries)
+    }
+
+    /// Check whether a delivery to a station is subsidised, awarding the subsidies
+    /// it fulfils first; `catchment_towns` lists the towns with a house in the
+    /// station's catchment and is only asked when needed (matches C++ CheckSubsidised)
+    pub fn check_subsidised(
+        &mut self,
+        cargo_type: CargoType,
+        company: Owner,
+        src: Source,
+        st: &Station,
+        settings: &SubsidySettings,
+        catchment_towns: impl FnOnce(&Station) -> Vec<TownID>,
+    ) -> bool {
+        if !src.is_valid() || src.type_ == SourceType::Headquarters {
+            return false;
+        }
+        let applies = |s: &Subsidy| {
+            s.cargo_type == cargo_type && s.src == src && (!s.is_awarded() || s.awarded == company)
+        };
+
+        // Only towns that are the destination of an applicable subsidy are of interest
+        let towns_near = if !st.rect.is_empty()
+            && self
+                .iter()
+                .any(|s| s.dst.type_ == SourceType::Town && applies(s))
+        {
+            catchment_towns(st)
+        } else {
+            Vec::new()
+        };
+
+        // A delivery may fulfil several subsidies, e.g. A->B and A->C when the
+        // station has both B and C in its catchment
+        let mut subsidised = false;
+        for subsidy in self.subsidies.iter_mut().flatten() {
+            if !applies(subsidy) {
+                continue;
+            }
+            let delivered = match subsidy.dst.type_ {
+                SourceType::Industry => st.industries_near.contains(&IndustryID(subsidy.dst.id)),
+                SourceType::Town => towns_near.contains(&TownID(subsidy.dst.id)),
+                SourceType::Headquarters => false,
+            };
+            if delivered {
+                subsidised = true;
+                if !subsidy.is_awarded() {
+                    subsidy.award_to(company, settings.duration);
+                }
+            }
+        }
+        subsidised
+    }
+}
+
+fn set_part_of_subsidy(
+    source: Source,
+    flag: PartOfSubsidy,
+    towns: &mut [Town],
+    industries: &mut [Industry],
+) {
+    match source.type_ {
+        SourceType::Town => {
+            if let Some(t) = town_index(towns, source.id) {
+                towns[t].part_of_subsidy |= flag;
+            }
+        }
+        SourceType::Industry => {
+            if let Some(i) = industry_index(industries, source.id) {
+                industries[i].part_of_subsidy |= flag;
+            }
+        }
+        SourceType::Headquarters => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::StationRect;
+    use crate::types::StationID;
+
+    const PASSENGERS: CargoType = CargoType(0);
+    const COAL: CargoType = CargoType(1);
+
+    fn town(id: u16, xy: TileIndex, population: u32) -> Town {
+        let mut town = Town::new(TownID(id), xy);
+        town.population = population;
+        town
+    }
+
+    fn town_source(id: u16) -> Source {
+        Source {
+            id,
+            type_: SourceType::Town,
+        }
+    }
+
+    fn industry_source(id: u16) -> Source {
+        Source {
+            id,
+            type_: SourceType::Industry,
+        }
+    }
+
+    fn sample() -> (Map, Vec<Town>, Vec<Industry>) {
+        let map = Map::new(8, 8).unwrap();
+        let towns = vec![
+            town(0, map.tile_xy(10, 10), 1000),
+            town(1, map.tile_xy(30, 20), 800),
+            town(2, map.tile_xy(200, 200), 5000),
+        ];
+        let mut mine = Industry::new(IndustryID(0), map.tile_xy(12, 14), 0);
+        mine.produced_cargo[0].cargo = COAL;
+        let mut plant = Industry::new(IndustryID(1), map.tile_xy(40, 10), 1);
+        plant.accepts_cargo[0].cargo = COAL;
+        (map, towns, vec![mine, plant])
+    }
+
+    #[test]
+    fn test_subsidy_monthly_loop() {
+        let (map, mut towns, mut industries) = sample();
+        let mut pool = SubsidyPool::default();
+        let settings = SubsidySettings::default();
+        let linkgraph = LinkGraphSettings::default();
+        let classes = |cargo: CargoType| match cargo {
+            PASSENGERS => Some(CargoClasses::PASSENGERS),
+            COAL => Some(CargoClasses::BULK),
+            _ => None,
+        };
+        let area_cargo = |_: &Town| TownCargo::default();
+        let env = SubsidyEnvironment {
+            passenger_cargoes: &[PASSENGERS],
+            cargo_classes: &classes,
+            town_area_cargo: &area_cargo,
+            linkgraph: &linkgraph,
+        };
+
+        // Sooner or later the only possible passenger route is offered
+        let mut random = Randomizer::new(0x5EED);
+        let mut offered = None;
+        for _ in 0..200 {
+            let events = pool.monthly_loop(
+                &mut towns,
+                &mut industries,
+                &map,
+                &settings,
+                &mut random,
+                &env,
+            );
+            if let Some(SubsidyEvent::Offered(id)) = events.first() {
+                offered = Some(*id);
+                break;
+            }
+        }
+        let subsidy = pool.get(offered.unwrap()).unwrap().clone();
+        assert_eq!(subsidy.cargo_type, PASSENGERS);
+        assert_eq!(subsidy.remaining, SUBSIDY_OFFER_MONTHS);
+        assert!(!subsidy.is_awarded());
+        assert_eq!(
+            towns[subsidy.src.id as usize].part_of_subsidy,
+            PartOfSubsidy::SOURCE
+        );
+        assert_eq!(
+            towns[subsidy.dst.id as usize].part_of_subsidy,
+            PartOfSubsidy::DESTINATION
+        );
+        assert!(towns[2].part_of_subsidy.is_empty());
+
+        // Disabled subsidies are not offered, but running offers expire after a year
+        let disabled = SubsidySettings {
+            duration: 0,
+            ..settings
+        };
+        for _ in 1..SUBSIDY_OFFER_MONTHS {
+            let events = pool.monthly_loop(
+                &mut towns,
+                &mut industries,
+                &map,
+                &disabled,
+                &mut random,
+                &env,
+            );
+            assert!(events.is_empty());
+        }
+        assert_eq!(pool.get(subsidy.index).unwrap().remaining, 1);
+        let events = pool.monthly_loop(
+            &mut towns,
+            &mut industries,
+            &map,
+            &disabled,
+            &mut random,
+            &env,
+        );
+        assert_eq!(
+            events[0],
+            SubsidyEvent::OfferExpired(Subsidy {
+                remaining: 0,
+                ..subsidy
+            })
+        );
+    }
+
+    #[test]
+    fn test_subsidy_delivery() {
+        let (_, mut towns, mut industries) = sample();
+        let mut pool = SubsidyPool::default();
+        let settings = SubsidySettings::default();
+        let coal = pool
+            .create(
+                COAL,
+                industry_source(0),
+                industry_source(1),
+                &mut towns,
+                &mut industries,
+            )
+            .unwrap();
+        let pax = pool
+            .create(
+                PASSENGERS,
+                town_source(0),
+                town_source(1),
+                &mut towns,
+                &mut industries,
+            )
+            .unwrap();
+        assert!(pool
+            .create(
+                COAL,
+                industry_source(0),
+                town_source(9),
+                &mut towns,
+                &mut industries
+            )
+            .is_err());
+
+        let mut st = Station::new(StationID(0), TileIndex(0), Owner::Company1);
+        st.rect = StationRect {
+            left: 0,
+            top: 0,
+            right: 3,
+            bottom: 3,
+        };
+        st.industries_near.push(IndustryID(1));
+
+        // Delivering from elsewhere earns nothing extra
+        assert!(!pool.check_subsidised(
+            COAL,
+            Owner::Company1,
+            industry_source(1),
+            &st,
+            &settings,
+            |_| unreachable!()
+        ));
+
+        // The first delivery awards the subsidy to the company
+        assert!(pool.check_subsidised(
+            COAL,
+            Owner::Company1,
+            industry_source(0),
+            &st,
+            &settings,
+            |_| unreachable!()
+        ));
+        let subsidy = pool.get(coal).unwrap();
+        assert_eq!(subsidy.awarded, Owner::Company1);
+        assert_eq!(subsidy.remaining, 12);
+        assert!(!pool.check_subsidised(
+            COAL,
+            Owner::Company2,
+            industry_source(0),
+            &st,
+            &settings,
+            |_| Vec::new()
+        ));
+        assert_eq!(settings.subsidised_profit(1000), 3000);
+        assert_eq!(
+            SubsidySettings {
+                multiplier: 0,
+                ..settings
+            }
+            .subsidised_profit(1000),
+            1500
+        );
+
+        // Town destinations are matched through the houses in the catchment
+        assert!(!pool.check_subsidised(
+            PASSENGERS,
+            Owner::Company2,
+            town_source(0),
+            &st,
+            &settings,
+            |_| vec![TownID(2)]
+        ));
+        assert!(pool.check_subsidised(
+            PASSENGERS,
+            Owner::Company2,
+            town_source(0),
+            &st,
+            &settings,
+            |_| vec![TownID(1)]
+        ));
+        assert_eq!(pool.get(pax).unwrap().awarded, Owner::Company2);
+
+        // Bankrupt companies lose their subsidies, closed industries all of theirs
+        pool.change_owner(Owner::Company2, Owner::Invalid, &mut towns, &mut industries);
+        assert!(pool.get(pax).is_none());
+        assert!(towns.iter().all(|t| t.part_of_subsidy.is_empty()));
+        pool.delete_with(industry_source(1), &mut towns, &mut industries);
+        assert!(pool.is_empty());
+        assert!(industries.iter().all(|i| i.part_of_subsidy.is_empty()));
+    }
+}
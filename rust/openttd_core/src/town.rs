@@ -3,10 +3,11 @@
 //! This module contains town structures that are saved in savegames.
 //! All structures must maintain exact C++ compatibility for save/load.
 
+use crate::error::CoreError;
 use crate::map::TileIndex;
+use crate::subsidy::PartOfSubsidy;
 use crate::types::{
-    CargoType, CompanyMask, Owner, StationID, StringID, TownID,
-    INVALID_STRING_ID,
+    CargoType, CompanyMask, Owner, StationID, StringID, TownID, INVALID_STRING_ID, LAST_MONTH,
 };
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
@@ -42,6 +43,24 @@ pub enum TownLayout {
     Random = 4,       // Random selection
 }
 
+impl TryFrom<u8> for TownLayout {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TownLayout::Original),
+            1 => Ok(TownLayout::Better),
+            2 => Ok(TownLayout::TwoByTwo),
+            3 => Ok(TownLayout::ThreeByThree),
+            4 => Ok(TownLayout::Random),
+            _ => Err(CoreError::InvalidData(format!(
+                "Invalid town layout {}",
+                value
+            ))),
+        }
+    }
+}
+
 /// Rating thresholds for town opinions
 pub const RATING_MINIMUM: i16 = -1000;
 pub const RATING_APPALLING: i16 = -400;
@@ -128,10 +147,47 @@ impl Default for TownCargo {
     }
 }
 
+/// Number of town acceptance effects (matches C++ NUM_TAE)
+pub const NUM_TAE: usize = 6;
+
+/// Monthly transport statistics (matches C++ TransportedCargoStat)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransportedCargoStat<T> {
+    pub old_max: T, // Maximum amount last month
+    pub new_max: T, // Maximum amount this month
+    pub old_act: T, // Actually transported last month
+    pub new_act: T, // Actually transported this month
+}
+
+/// One history record of supplied cargo (matches C++ Town::SuppliedHistory)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuppliedHistory {
+    pub production: u32,  // Amount produced by houses
+    pub transported: u32, // Amount picked up by stations
+}
+
+impl SuppliedHistory {
+    /// Transported fraction scaled to 0-255
+    pub fn pct_transported(&self) -> u8 {
+        if self.production == 0 {
+            return 0;
+        }
+        (self.transported as u64 * 256 / self.production as u64).min(255) as u8
+    }
+}
+
+/// Supplied cargo statistics (matches C++ Town::SuppliedCargo)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuppliedCargo {
+    pub cargo: CargoType,
+    /// History records, indexed by `THIS_MONTH`, `LAST_MONTH`, ...
+    pub history: Vec<SuppliedHistory>,
+}
+
 /// Town structure (matches C++ Town class for savegame compatibility)
 #[repr(C)]
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Town {
     /// Town index/ID
     pub index: TownID,
@@ -141,9 +197,10 @@ pub struct Town {
 
     /// Town name string IDs
     pub townnamegrfid: u32, // NewGRF providing the name
-    pub townnametype: u16,  // Town name style
-    pub townnameparts: u32, // Town name generation seed
-    pub name: StringID,     // Custom name string ID
+    pub townnametype: u16,   // Town name style
+    pub townnameparts: u32,  // Town name generation seed
+    pub name: StringID,      // Custom name string ID
+    pub custom_name: String, // Custom name set by the player, empty if generated
 
     /// Town status flags
     pub flags: TownFlags,
@@ -175,6 +232,13 @@ pub struct Town {
     #[serde_as(as = "[_; 15]")]
     pub test_ratings: [i16; MAX_COMPANIES],
 
+    /// Which companies have a rating in this town
+    pub have_ratings: CompanyMask,
+
+    /// Months each company is unwanted after a failed bribe
+    #[serde_as(as = "[_; 15]")]
+    pub unwanted: [i8; MAX_COMPANIES],
+
     /// Which companies have a statue
     pub have_statue: CompanyMask,
 
@@ -203,11 +267,32 @@ pub struct Town {
     #[serde_as(as = "[_; 64]")]
     pub received_last_month: [u32; 64],
 
+    /// Supplied cargo statistics, sorted by cargo type
+    pub supplied: Vec<SuppliedCargo>,
+
+    /// Received cargo statistics per acceptance effect
+    pub received: [TransportedCargoStat<u16>; NUM_TAE],
+
+    /// Cargo required per acceptance effect for the town to grow
+    pub goal: [u32; NUM_TAE],
+
+    /// Mask of valid supplied history records
+    pub valid_history: u64,
+
+    /// Text shown in the town window, set by game scripts
+    pub text: String,
+
+    /// Persistent storage for NewGRF town variables
+    pub psa_list: Vec<u32>,
+
     /// Airport noise accumulator
     pub noise_reached: u16, // Current noise level
 
     /// Stations that serve this town
     pub stations_near: Vec<StationID>,
+
+    /// Whether the town is the source or destination of a subsidy
+    pub part_of_subsidy: PartOfSubsidy,
 }
 
 impl Town {
@@ -220,6 +305,7 @@ impl Town {
             townnametype: 0,
             townnameparts: 0,
             name: INVALID_STRING_ID,
+            custom_name: String::new(),
             flags: 0,
             church_count: 0,
             stadium_count: 0,
@@ -232,6 +318,8 @@ impl Town {
             cargo: TownCargo::default(),
             ratings: [0; MAX_COMPANIES],
             test_ratings: [0; MAX_COMPANIES],
+            have_ratings: 0,
+            unwanted: [0; MAX_COMPANIES],
             have_statue: 0,
             exclusive_counter: 0,
             exclusivity: Owner::None,
@@ -242,8 +330,15 @@ impl Town {
             label_style: 0,
             supplied_last_month: [0; 64],
             received_last_month: [0; 64],
+            supplied: Vec::new(),
+            received: [TransportedCargoStat::default(); NUM_TAE],
+            goal: [0; NUM_TAE],
+            valid_history: 0,
+            text: String::new(),
+            psa_list: Vec::new(),
             noise_reached: 0,
             stations_near: Vec::new(),
+            part_of_subsidy: PartOfSubsidy::empty(),
         }
     }
 
@@ -270,6 +365,16 @@ impl Town {
         }
     }
 
+    /// Transported fraction of last month's supply of a cargo, scaled to 0-255
+    /// (matches C++ Town::GetPercentTransported)
+    pub fn get_percent_transported(&self, cargo: CargoType) -> u8 {
+        self.supplied
+            .iter()
+            .find(|s| s.cargo == cargo)
+            .and_then(|s| s.history.get(LAST_MONTH))
+            .map_or(0, SuppliedHistory::pct_transported)
+    }
+
     /// Get town size category based on population
     pub fn get_town_size(&self) -> TownSize {
         if self.population < 1000 {
@@ -2,6 +2,8 @@
 //!
 //! This module provides fundamental types used throughout the game.
 
+use crate::error::CoreError;
+use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
@@ -60,6 +62,22 @@ impl Owner {
     }
 }
 
+impl TryFrom<u8> for Owner {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0..=14 => Ok(Self::from_company_id(value)),
+            0x0F => Ok(Owner::Town),
+            0x10 => Ok(Owner::None),
+            0x11 => Ok(Owner::Water),
+            0x12 => Ok(Owner::Deity),
+            0xFF => Ok(Owner::Invalid),
+            _ => Err(CoreError::InvalidData(format!("Invalid owner {}", value))),
+        }
+    }
+}
+
 impl Default for Owner {
     fn default() -> Self {
         Owner::None
@@ -70,7 +88,7 @@ impl Default for Owner {
 pub type CompanyID = Owner;
 
 /// Station ID type (matches C++ StationID typedef)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct StationID(pub u16);
 
@@ -151,19 +169,24 @@ impl Default for VehicleID {
 }
 
 /// Engine ID type (matches C++ EngineID typedef)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct EngineID(pub u16);
 
 impl EngineID {
     pub const INVALID: EngineID = EngineID(0xFFFF);
+    pub const MAX_ENGINES: usize = 64000;
+
+    pub fn is_valid(&self) -> bool {
+        *self != Self::INVALID
+    }
 }
 
 /// Unit number for vehicles (matches C++ UnitID)
 pub type UnitID = u16;
 
 /// Group ID type (matches C++ GroupID typedef)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct GroupID(pub u16);
 
@@ -171,6 +194,26 @@ impl GroupID {
     pub const INVALID: GroupID = GroupID(0xFFFF);
     pub const DEFAULT: GroupID = GroupID(0xFFFE);
     pub const ALL: GroupID = GroupID(0xFFFD);
+    pub const MAX_GROUPS: usize = 64000;
+
+    /// Whether this is the ID of an actual group, not a pseudo group
+    pub fn is_valid(&self) -> bool {
+        (self.0 as usize) < Self::MAX_GROUPS
+    }
+}
+
+/// Sign ID type (matches C++ SignID typedef)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[repr(transparent)]
+pub struct SignID(pub u16);
+
+impl SignID {
+    pub const INVALID: SignID = SignID(0xFFFF);
+    pub const MAX_SIGNS: usize = 64000;
+
+    pub fn is_valid(&self) -> bool {
+        (self.0 as usize) < Self::MAX_SIGNS
+    }
 }
 
 /// Cargo type ID (matches C++ CargoType typedef)
@@ -180,6 +223,10 @@ pub struct CargoType(pub u8);
 
 impl CargoType {
     pub const INVALID: CargoType = CargoType(0xFF);
+    /// Automatically choose the cargo type when auto refitting (matches C++ CARGO_AUTO_REFIT)
+    pub const AUTO_REFIT: CargoType = CargoType(0xFD);
+    /// Do not refit, used in orders and autoreplace (matches C++ CARGO_NO_REFIT)
+    pub const NO_REFIT: CargoType = CargoType(0xFE);
     pub const NUM_CARGO: usize = 64;
 
     pub fn is_valid(&self) -> bool {
@@ -203,6 +250,91 @@ impl Default for CargoType {
     }
 }
 
+/// Four character cargo label such as "PASS" (matches C++ CargoLabel)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(transparent)]
+pub struct CargoLabel(pub u32);
+
+impl CargoLabel {
+    pub const INVALID: CargoLabel = CargoLabel(u32::MAX);
+
+    pub const fn from_bytes(label: &[u8; 4]) -> Self {
+        CargoLabel(u32::from_be_bytes(*label))
+    }
+}
+
+bitflags! {
+    /// Classes a cargo belongs to (matches C++ CargoClasses)
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct CargoClasses: u16 {
+        const PASSENGERS = 1 << 0;
+        const MAIL = 1 << 1;
+        /// Goods, food and candy, but also possible for passengers
+        const EXPRESS = 1 << 2;
+        const ARMOURED = 1 << 3;
+        const BULK = 1 << 4;
+        const PIECE_GOODS = 1 << 5;
+        const LIQUID = 1 << 6;
+        const REFRIGERATED = 1 << 7;
+        const HAZARDOUS = 1 << 8;
+        const COVERED = 1 << 9;
+        const OVERSIZED = 1 << 10;
+        const POWDERIZED = 1 << 11;
+        const NOT_POURABLE = 1 << 12;
+        const POTABLE = 1 << 13;
+        const NON_POTABLE = 1 << 14;
+        /// Used for livery refit tricks instead of normal cargoes
+        const SPECIAL = 1 << 15;
+    }
+}
+
+/// Climate of a game (matches C++ LandscapeType)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum LandscapeType {
+    #[default]
+    Temperate = 0,
+    Arctic = 1,
+    Tropic = 2,
+    Toyland = 3,
+}
+
+impl TryFrom<u8> for LandscapeType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => LandscapeType::Temperate,
+            1 => LandscapeType::Arctic,
+            2 => LandscapeType::Tropic,
+            3 => LandscapeType::Toyland,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid landscape type {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+bitflags! {
+    /// Set of climates, e.g. those an engine is available in (matches C++ LandscapeTypes)
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct LandscapeTypes: u8 {
+        const TEMPERATE = 1 << 0;
+        const ARCTIC = 1 << 1;
+        const TROPIC = 1 << 2;
+        const TOYLAND = 1 << 3;
+    }
+}
+
+impl LandscapeTypes {
+    pub fn contains_landscape(&self, landscape: LandscapeType) -> bool {
+        self.bits() & (1 << landscape as u8) != 0
+    }
+}
+
 /// Money type (matches C++ Money typedef int64_t)
 pub type Money = i64;
 
@@ -215,35 +347,137 @@ pub const INVALID_STRING_ID: StringID = 0xFFFF;
 /// Company mask for bitfield operations (matches C++ CompanyMask uint16_t)
 pub type CompanyMask = u16;
 
+/// Number of monthly, quarterly and yearly history records (matches C++ HISTORY_RECORDS)
+pub const HISTORY_RECORDS: usize = 61;
+
+/// History record slots (matches C++ THIS_MONTH and LAST_MONTH)
+pub const THIS_MONTH: usize = 0;
+pub const LAST_MONTH: usize = 1;
+
 /// Date types (matches C++ date system)
 pub mod dates {
     use serde::{Deserialize, Serialize};
 
     /// Calendar date (days since year 0)
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
     #[repr(transparent)]
     pub struct CalendarDate(pub i32);
 
     /// Calendar year
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
     #[repr(transparent)]
     pub struct CalendarYear(pub i32);
 
     /// Economy date (days since economy started)
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
     #[repr(transparent)]
     pub struct EconomyDate(pub i32);
 
     /// Economy year
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
     #[repr(transparent)]
     pub struct EconomyYear(pub i32);
 
     pub const INVALID_DATE: i32 = -1;
+
+    pub const DAYS_IN_YEAR: i32 = 365;
+    pub const DAYS_IN_LEAP_YEAR: i32 = 366;
+    /// First year of the original TTD date range
+    pub const ORIGINAL_BASE_YEAR: i32 = 1920;
+    /// Days from year 0 to 1 January of ORIGINAL_BASE_YEAR
+    pub const DAYS_TILL_ORIGINAL_BASE_YEAR: i32 = date_at_start_of_year(ORIGINAL_BASE_YEAR);
+
+    /// Days before the first of each month in a leap year
+    const ACCUM_DAYS_FOR_MONTH: [i32; 12] = [0, 31, 60, 91, 121, 152, 182, 213, 244, 274, 305, 335];
+    const ACCUM_MAR: i32 = ACCUM_DAYS_FOR_MONTH[2];
+
+    /// A date split into its parts; months count from 0, days from 1
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct YearMonthDay {
+        pub year: i32,
+        pub month: u8,
+        pub day: u8,
+    }
+
+    pub const fn is_leap_year(year: i32) -> bool {
+        year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+    }
+
+    /// Days from year 0 to 1 January of `year`
+    pub const fn date_at_start_of_year(year: i32) -> i32 {
+        let leap_years = if year == 0 {
+            0
+        } else {
+            (year - 1) / 4 - (year - 1) / 100 + (year - 1) / 400 + 1
+        };
+        DAYS_IN_YEAR * year + leap_years
+    }
+
+    fn days_in_year(year: i32) -> i32 {
+        if is_leap_year(year) {
+            DAYS_IN_LEAP_YEAR
+        } else {
+            DAYS_IN_YEAR
+        }
+    }
+
+    /// Split a day count into year, month and day (matches C++ ConvertDateToYMD)
+    pub fn convert_date_to_ymd(date: i32) -> YearMonthDay {
+        // There are 97 leap years in 400 years
+        let mut year = 400 * (date / (DAYS_IN_YEAR * 400 + 97));
+        let mut rem = date % (DAYS_IN_YEAR * 400 + 97);
+
+        if rem >= DAYS_IN_YEAR * 100 + 25 {
+            // 25 leap years in the first century, 24 in the others
+            year += 100;
+            rem -= DAYS_IN_YEAR * 100 + 25;
+            year += 100 * (rem / (DAYS_IN_YEAR * 100 + 24));
+            rem %= DAYS_IN_YEAR * 100 + 24;
+        }
+
+        if !is_leap_year(year) && rem >= DAYS_IN_YEAR * 4 {
+            // The first four years of a century are not always a leap year
+            year += 4;
+            rem -= DAYS_IN_YEAR * 4;
+        }
+
+        year += 4 * (rem / (DAYS_IN_YEAR * 4 + 1));
+        rem %= DAYS_IN_YEAR * 4 + 1;
+
+        while rem >= days_in_year(year) {
+            rem -= days_in_year(year);
+            year += 1;
+        }
+
+        // Skip 29 February in non-leap years
+        if !is_leap_year(year) && rem >= ACCUM_MAR - 1 {
+            rem += 1;
+        }
+
+        let month = ACCUM_DAYS_FOR_MONTH
+            .iter()
+            .rposition(|&accum| accum <= rem)
+            .unwrap();
+        YearMonthDay {
+            year,
+            month: month as u8,
+            day: (rem - ACCUM_DAYS_FOR_MONTH[month] + 1) as u8,
+        }
+    }
+
+    /// Join year, month (0-11) and day (1-31) into a day count (matches C++ ConvertYMDToDate)
+    pub fn convert_ymd_to_date(year: i32, month: u8, day: u8) -> i32 {
+        let mut days = ACCUM_DAYS_FOR_MONTH[month as usize] + day as i32 - 1;
+        // 29 February does not exist in non-leap years
+        if !is_leap_year(year) && days >= ACCUM_MAR {
+            days -= 1;
+        }
+        date_at_start_of_year(year) + days
+    }
 }
 
 // Re-export date types at module level for convenience
-pub use dates::{CalendarDate, CalendarYear, EconomyDate, EconomyYear};
+pub use dates::{CalendarDate, CalendarYear, EconomyDate, EconomyYear, YearMonthDay};
 
 /// Tick counter type (matches C++ TimerGameTick::TickCounter)
 pub type Tick = u64;
@@ -278,6 +512,38 @@ pub enum Colours {
     Invalid = 0xFF,
 }
 
+impl TryFrom<u8> for Colours {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        const COLOURS: [Colours; 16] = [
+            Colours::DarkBlue,
+            Colours::PaleGreen,
+            Colours::Pink,
+            Colours::Yellow,
+            Colours::Red,
+            Colours::LightBlue,
+            Colours::Green,
+            Colours::DarkGreen,
+            Colours::Blue,
+            Colours::Cream,
+            Colours::Mauve,
+            Colours::Purple,
+            Colours::Orange,
+            Colours::Brown,
+            Colours::Grey,
+            Colours::White,
+        ];
+        match value {
+            0xFF => Ok(Colours::Invalid),
+            _ => COLOURS
+                .get(value as usize)
+                .copied()
+                .ok_or_else(|| CoreError::InvalidData(format!("Invalid colour {}", value))),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,6 +562,43 @@ mod tests {
         assert!(Owner::Company14.is_company());
         assert!(!Owner::Town.is_company());
         assert!(!Owner::None.is_company());
+
+        // Test conversion from saved values
+        assert_eq!(Owner::try_from(3).unwrap(), Owner::Company3);
+        assert_eq!(Owner::try_from(0xFF).unwrap(), Owner::Invalid);
+        assert!(Owner::try_from(0x20).is_err());
+    }
+
+    #[test]
+    fn test_colours_try_from() {
+        assert_eq!(Colours::try_from(8).unwrap(), Colours::Blue);
+        assert_eq!(Colours::try_from(15).unwrap(), Colours::White);
+        assert_eq!(Colours::try_from(0xFF).unwrap(), Colours::Invalid);
+        assert!(Colours::try_from(16).is_err());
+    }
+
+    #[test]
+    fn test_date_conversion() {
+        assert_eq!(dates::DAYS_TILL_ORIGINAL_BASE_YEAR, 701265);
+        assert_eq!(dates::convert_ymd_to_date(1920, 0, 1), 701265);
+        for (year, month, day) in [(1920, 1, 29), (1921, 2, 1), (1950, 11, 31), (2000, 1, 29)] {
+            let date = dates::convert_ymd_to_date(year, month, day);
+            assert_eq!(
+                dates::convert_date_to_ymd(date),
+                YearMonthDay { year, month, day }
+            );
+        }
+        // 1 March follows 28 February outside leap years
+        assert_eq!(
+            dates::convert_date_to_ymd(dates::convert_ymd_to_date(1921, 1, 28) + 1),
+            YearMonthDay {
+                year: 1921,
+                month: 2,
+                day: 1
+            }
+        );
+        assert!(LandscapeTypes::ARCTIC.contains_landscape(LandscapeType::Arctic));
+        assert!(!LandscapeTypes::ARCTIC.contains_landscape(LandscapeType::Toyland));
     }
 
     #[test]
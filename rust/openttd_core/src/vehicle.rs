@@ -3,7 +3,11 @@
 //! This module contains the core vehicle structures that are saved in savegames.
 //! All structures must maintain exact C++ compatibility for save/load.
 
+use crate::cargopacket::VehicleCargoList;
+use crate::error::CoreError;
 use crate::map::TileIndex;
+use crate::order::Order;
+use crate::types::dates::DAYS_IN_YEAR;
 use crate::types::{
     CalendarDate, CalendarYear, CargoType, EconomyDate, EngineID, GroupID, Money, OwnerID,
     StationID, Tick, UnitID, VehicleID,
@@ -31,6 +35,27 @@ pub enum VehicleType {
     Invalid = 0xFF,
 }
 
+impl TryFrom<u8> for VehicleType {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => VehicleType::Train,
+            1 => VehicleType::Road,
+            2 => VehicleType::Ship,
+            3 => VehicleType::Aircraft,
+            4 => VehicleType::Effect,
+            5 => VehicleType::Disaster,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid vehicle type {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
 /// Direction enum matching C++
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
@@ -46,6 +71,30 @@ pub enum Direction {
     Invalid = 0xFF,
 }
 
+impl TryFrom<u8> for Direction {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Direction::N,
+            1 => Direction::NE,
+            2 => Direction::E,
+            3 => Direction::SE,
+            4 => Direction::S,
+            5 => Direction::SW,
+            6 => Direction::W,
+            7 => Direction::NW,
+            0xFF => Direction::Invalid,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid direction {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
 bitflags! {
     /// Vehicle states
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -61,7 +110,10 @@ bitflags! {
     }
 }
 
-/// Ground vehicle subtype flags
+/// Only vehicles older than this have a meaningful profit (matches C++ VEHICLE_PROFIT_MIN_AGE)
+pub const VEHICLE_PROFIT_MIN_AGE: i32 = DAYS_IN_YEAR * 2;
+
+/// Ground vehicle subtype flags, as bit numbers
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
 pub enum GroundVehicleSubtype {
@@ -209,25 +261,12 @@ impl Default for MutableSpriteCache {
     }
 }
 
-/// Order structure (simplified for now)
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Order {
-    pub type_flags: u8,
-    pub dest: u16,
-    pub flags: u8,
-    pub refit_cargo: u8,
-    pub wait_time: u16,
-    pub travel_time: u16,
-    pub max_speed: u16,
-}
-
 /// Main vehicle structure
 ///
 /// This represents the core vehicle data that is saved in savegames.
 /// The structure must maintain exact compatibility with C++ for save/load.
 #[repr(C)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Vehicle {
     // Pool item fields (would be inherited in C++)
     pub index: VehicleID,
@@ -238,6 +277,8 @@ pub struct Vehicle {
     pub first: Option<VehicleID>,
     pub next_shared: Option<VehicleID>,
 
+    pub name: String,
+
     // Position and movement
     pub tile: TileIndex,
     pub dest_tile: TileIndex,
@@ -258,6 +299,7 @@ pub struct Vehicle {
     pub max_age: CalendarDate,
     pub date_of_last_service: EconomyDate,
     pub date_of_last_service_newgrf: CalendarDate,
+    pub service_interval: u16,
 
     // Reliability and breakdowns
     pub reliability: u16,
@@ -288,6 +330,10 @@ pub struct Vehicle {
     pub cargo_cap: u16,
     pub refit_cap: u16,
     pub cargo_age_counter: u16,
+    pub cargo: VehicleCargoList,
+    /// Occupancy in percent of the current trip, updated when leaving a
+    /// station (not saved)
+    pub trip_occupancy: i8,
 
     // Stations
     pub last_station_visited: StationID,
@@ -302,6 +348,18 @@ pub struct Vehicle {
     pub vehstatus: VehicleStates,
     pub subtype: u8,
     pub current_order: Order,
+    pub vehicle_flags: u16,
+
+    // Orders and timetable
+    pub orders: Option<u32>, // Index of the (shared) order list
+    pub cur_implicit_order_index: u8,
+    pub cur_real_order_index: u8,
+    pub timetable_start: Tick,
+    pub current_order_time: i32,
+    pub lateness_counter: i32,
+    pub depot_unbunching_last_departure: Tick,
+    pub depot_unbunching_next_departure: Tick,
+    pub round_trip_time: i32,
 
     // Counters
     pub day_counter: u8,
@@ -332,6 +390,7 @@ impl Vehicle {
             next: None,
             first: None,
             next_shared: None,
+            name: String::new(),
             tile: TileIndex::INVALID,
             dest_tile: TileIndex::INVALID,
             x_pos: 0,
@@ -347,6 +406,7 @@ impl Vehicle {
             max_age: CalendarDate(0),
             date_of_last_service: EconomyDate(0),
             date_of_last_service_newgrf: CalendarDate(0),
+            service_interval: 0,
             reliability: 0,
             reliability_spd_dec: 0,
             breakdown_ctr: 0,
@@ -369,6 +429,8 @@ impl Vehicle {
             cargo_cap: 0,
             refit_cap: 0,
             cargo_age_counter: 0,
+            cargo: VehicleCargoList::default(),
+            trip_occupancy: 0,
             last_station_visited: StationID::INVALID,
             last_loading_station: StationID::INVALID,
             last_loading_tick: 0,
@@ -377,6 +439,16 @@ impl Vehicle {
             vehstatus: VehicleStates::empty(),
             subtype: 0,
             current_order: Order::default(),
+            vehicle_flags: 0,
+            orders: None,
+            cur_implicit_order_index: 0,
+            cur_real_order_index: 0,
+            timetable_start: 0,
+            current_order_time: 0,
+            lateness_counter: 0,
+            depot_unbunching_last_departure: 0,
+            depot_unbunching_next_departure: 0,
+            round_trip_time: 0,
             day_counter: 0,
             tick_counter: 0,
             running_ticks: 0,
@@ -389,9 +461,57 @@ impl Vehicle {
         }
     }
 
+    /// Check a subtype flag of a train or road vehicle
+    pub fn has_subtype(&self, flag: GroundVehicleSubtype) -> bool {
+        self.subtype & (1 << flag as u8) != 0
+    }
+
     /// Check if vehicle is a front engine
     pub fn is_front(&self) -> bool {
-        self.subtype == GroundVehicleSubtype::Front as u8
+        self.has_subtype(GroundVehicleSubtype::Front)
+    }
+
+    /// Check if vehicle is an articulated part of an engine or wagon
+    pub fn is_articulated_part(&self) -> bool {
+        self.has_subtype(GroundVehicleSubtype::ArticulatedPart)
+    }
+
+    /// Check if vehicle is a normal aircraft, not a shadow or rotor
+    pub fn is_normal_aircraft(&self) -> bool {
+        self.subtype <= AircraftSubType::Aircraft as u8
+    }
+
+    /// Check if vehicle is the head of a chain that takes orders
+    /// (matches C++ IsPrimaryVehicle)
+    pub fn is_primary_vehicle(&self) -> bool {
+        match self.type_ {
+            VehicleType::Train | VehicleType::Road => self.is_front(),
+            VehicleType::Ship => true,
+            VehicleType::Aircraft => self.is_normal_aircraft(),
+            _ => false,
+        }
+    }
+
+    /// Check if vehicle counts as an engine in group statistics
+    /// (matches C++ IsEngineCountable)
+    pub fn is_engine_countable(&self) -> bool {
+        match self.type_ {
+            VehicleType::Train => {
+                // The rear of a dual-headed engine is part of the front
+                let is_rear_dualheaded = self.has_subtype(GroundVehicleSubtype::Multiheaded)
+                    && !self.has_subtype(GroundVehicleSubtype::Engine);
+                !self.is_articulated_part() && !is_rear_dualheaded
+            }
+            VehicleType::Road => self.is_front(),
+            VehicleType::Ship => true,
+            VehicleType::Aircraft => self.is_normal_aircraft(),
+            _ => false,
+        }
+    }
+
+    /// Profit of last year in whole currency units
+    pub fn display_profit_last_year(&self) -> Money {
+        self.profit_last_year >> 8
     }
 
     /// Check if vehicle is crashed
@@ -420,9 +540,35 @@ pub enum TrackBits {
     Lower = 0x08,
     Left = 0x10,
     Right = 0x20,
-    Cross = 0x40,
+    Cross = 0x03,
+    Wormhole = 0x40,
     Depot = 0x80,
-    Wormhole = 0xFF,
+}
+
+impl TryFrom<u8> for TrackBits {
+    type Error = CoreError;
+
+    /// Only the values a vehicle can be on are accepted, not arbitrary combinations
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0x00 => TrackBits::None,
+            0x01 => TrackBits::X,
+            0x02 => TrackBits::Y,
+            0x04 => TrackBits::Upper,
+            0x08 => TrackBits::Lower,
+            0x10 => TrackBits::Left,
+            0x20 => TrackBits::Right,
+            0x03 => TrackBits::Cross,
+            0x40 => TrackBits::Wormhole,
+            0x80 => TrackBits::Depot,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid track bits {:#x}",
+                    value
+                )))
+            }
+        })
+    }
 }
 
 bitflags! {
@@ -448,6 +594,24 @@ pub enum TrainForceProceeding {
     Signal = 2,
 }
 
+impl TryFrom<u8> for TrainForceProceeding {
+    type Error = CoreError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => TrainForceProceeding::None,
+            1 => TrainForceProceeding::Stuck,
+            2 => TrainForceProceeding::Signal,
+            _ => {
+                return Err(CoreError::InvalidData(format!(
+                    "Invalid force proceed mode {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
 /// Train-specific cache data
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
@@ -470,18 +634,20 @@ pub struct TrainCache {
 /// - `railtypes` -> `Train::railtypes`
 /// - `track` -> `Train::track`
 /// - `force_proceed` -> `Train::force_proceed`
+/// - `gv_flags` -> `GroundVehicle::gv_flags`
 #[repr(C)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TrainData {
     pub flags: VehicleRailFlags,
     pub crash_anim_pos: u16,
     pub wait_counter: u16,
     pub tcache: TrainCache,
     pub other_multiheaded_part: Option<VehicleID>,
-    pub compatible_railtypes: u32, // Bitmask of rail types
-    pub railtypes: u32,            // Bitmask of rail types
+    pub compatible_railtypes: u64, // Bitmask of rail types
+    pub railtypes: u64,            // Bitmask of rail types
     pub track: TrackBits,
     pub force_proceed: TrainForceProceeding,
+    pub gv_flags: u16,
 }
 
 impl Default for TrainData {
@@ -496,6 +662,7 @@ impl Default for TrainData {
             railtypes: 0,
             track: TrackBits::None,
             force_proceed: TrainForceProceeding::None,
+            gv_flags: 0,
         }
     }
 }
@@ -530,8 +697,9 @@ pub struct RoadVehPathElement {
 /// - `roadtype` -> `RoadVehicle::roadtype` (NOSAVE in C++)
 /// - `disaster_vehicle` -> `RoadVehicle::disaster_vehicle` (NOSAVE in C++)
 /// - `compatible_roadtypes` -> `RoadVehicle::compatible_roadtypes` (NOSAVE in C++)
+/// - `gv_flags` -> `GroundVehicle::gv_flags`
 #[repr(C)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RoadVehicleData {
     pub path: Vec<RoadVehPathElement>, // Path cache
     pub state: u8,
@@ -544,6 +712,7 @@ pub struct RoadVehicleData {
     pub roadtype: u8, // INVALID_ROADTYPE = 0xFF
     pub disaster_vehicle: Option<VehicleID>,
     pub compatible_roadtypes: u64, // Bitmask of road types
+    pub gv_flags: u16,
 }
 
 impl Default for RoadVehicleData {
@@ -560,6 +729,7 @@ impl Default for RoadVehicleData {
             roadtype: 0xFF, // INVALID_ROADTYPE
             disaster_vehicle: None,
             compatible_roadtypes: 0,
+            gv_flags: 0,
         }
     }
 }
@@ -580,7 +750,7 @@ pub struct ShipPathElement {
 /// - `rotation_x_pos` -> `Ship::rotation_x_pos` (NOSAVE in C++)
 /// - `rotation_y_pos` -> `Ship::rotation_y_pos` (NOSAVE in C++)
 #[repr(C)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ShipData {
     pub path: Vec<ShipPathElement>, // Path cache
     pub state: TrackBits,
@@ -644,7 +814,7 @@ pub struct AircraftCache {
 /// - `flags` -> `Aircraft::flags`
 /// - `acache` -> `Aircraft::acache`
 #[repr(C)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AircraftData {
     pub crashed_counter: u16,
     pub pos: u8,
@@ -675,15 +845,55 @@ impl Default for AircraftData {
     }
 }
 
+/// Effect vehicle-specific data fields
+///
+/// Field parity with C++ `EffectVehicle` (src/effectvehicle_base.h):
+/// - `animation_state` -> `EffectVehicle::animation_state`
+/// - `animation_substate` -> `EffectVehicle::animation_substate`
+#[repr(C)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EffectData {
+    pub animation_state: u16,
+    pub animation_substate: u8,
+}
+
+/// Disaster vehicle-specific data fields
+///
+/// Field parity with C++ `DisasterVehicle` (src/disaster_vehicle.h):
+/// - `image_override` -> `DisasterVehicle::image_override`
+/// - `big_ufo_destroyer_target` -> `DisasterVehicle::big_ufo_destroyer_target`
+/// - `flags` -> `DisasterVehicle::flags`
+/// - `state` -> `DisasterVehicle::state`
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisasterData {
+    pub image_override: SpriteID,
+    pub big_ufo_destroyer_target: VehicleID,
+    pub flags: VehicleAirFlags,
+    pub state: u16,
+}
+
+impl Default for DisasterData {
+    fn default() -> Self {
+        Self {
+            image_override: 0,
+            big_ufo_destroyer_target: VehicleID::INVALID,
+            flags: VehicleAirFlags::empty(),
+            state: 0,
+        }
+    }
+}
+
 /// Union-like enum to hold vehicle type-specific data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum VehicleTypeData {
     None,
     Train(TrainData),
     RoadVehicle(RoadVehicleData),
     Ship(ShipData),
     Aircraft(AircraftData),
-    // Effect and Disaster vehicles would have their own data here if needed
+    Effect(EffectData),
+    Disaster(DisasterData),
 }
 
 impl Default for VehicleTypeData {
@@ -700,7 +910,9 @@ impl VehicleTypeData {
             VehicleType::Road => VehicleTypeData::RoadVehicle(RoadVehicleData::default()),
             VehicleType::Ship => VehicleTypeData::Ship(ShipData::default()),
             VehicleType::Aircraft => VehicleTypeData::Aircraft(AircraftData::default()),
-            _ => VehicleTypeData::None,
+            VehicleType::Effect => VehicleTypeData::Effect(EffectData::default()),
+            VehicleType::Disaster => VehicleTypeData::Disaster(DisasterData::default()),
+            VehicleType::Invalid => VehicleTypeData::None,
         }
     }
 }
@@ -736,6 +948,19 @@ mod tests {
         assert_eq!(Direction::S as u8, 4);
         assert_eq!(Direction::W as u8, 6);
         assert_eq!(Direction::Invalid as u8, 0xFF);
+        assert_eq!(Direction::try_from(5).unwrap(), Direction::SW);
+        assert!(Direction::try_from(8).is_err());
+    }
+
+    #[test]
+    fn test_track_bits_values() {
+        assert_eq!(
+            TrackBits::Cross as u8,
+            TrackBits::X as u8 | TrackBits::Y as u8
+        );
+        assert_eq!(TrackBits::try_from(0x40).unwrap(), TrackBits::Wormhole);
+        assert_eq!(TrackBits::try_from(0x80).unwrap(), TrackBits::Depot);
+        assert!(TrackBits::try_from(0x05).is_err());
     }
 
     #[test]
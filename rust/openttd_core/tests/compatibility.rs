@@ -286,4 +286,25 @@ mod integration_tests {
         // assert_eq!(std::mem::size_of::<Industry>(), 512);
         // assert_eq!(std::mem::size_of::<Station>(), 2048);
     }
+
+    /// Test that NewGRF scanning calculates the checksums the game build records
+    #[test]
+    fn test_scan_baseset_grfs() {
+        use openttd_core::newgrf::GrfFile;
+
+        for name in ["openttd.grf", "orig_extra.grf"] {
+            let path = format!("../../media/baseset/{}", name);
+            let (Ok(data), Ok(hash)) = (
+                std::fs::read(&path),
+                std::fs::read_to_string(format!("{}.hash", path)),
+            ) else {
+                eprintln!("Warning: {} not found, skipping test", path);
+                continue;
+            };
+            let file = GrfFile::scan(name, &data).unwrap();
+            let md5sum: String = file.md5sum.iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(md5sum, hash.trim(), "{}", name);
+            assert_ne!(file.grfid, 0);
+        }
+    }
 }
@@ -0,0 +1,23 @@
+//! Parse untrusted savegames through every stage that accepts raw input:
+//! header, decompression, chunk framing, table records and the chunk loaders.
+//! Any panic is a bug; malformed input must come back as an error.
+//!
+//! Seed the corpus with `cargo run --bin seed_corpus`, then fuzz with
+//! `cargo +nightly fuzz run savegame`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use openttd_savegame::{SavegameDocument, SavegameReader};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(reader) = SavegameReader::new(data) else {
+        return;
+    };
+    let Ok(chunks) = reader.read_chunks() else {
+        return;
+    };
+    for chunk in &chunks {
+        let _ = chunk.decode_records();
+    }
+    let _ = SavegameDocument::from_chunks(&chunks, reader.header().version);
+});
@@ -0,0 +1,52 @@
+//! Build the seed corpus of the `savegame` target from the regression saves
+//!
+//! Besides each save as it is, the corpus gets an uncompressed copy and one
+//! small uncompressed save per chunk, so mutations reach the chunk parser
+//! instead of failing in the decompressor.
+//!
+//! Run from this directory: `cargo run --bin seed_corpus`
+
+use openttd_savegame::{CompressionType, SavegameReader, SavegameWriter};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+const REGRESSION_DIR: &str = "../../../regression";
+const CORPUS_DIR: &str = "corpus/savegame";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(CORPUS_DIR)?;
+    let corpus = Path::new(CORPUS_DIR);
+
+    for entry in fs::read_dir(REGRESSION_DIR)? {
+        let path = entry?.path().join("test.sav");
+        let Ok(data) = fs::read(&path) else {
+            continue;
+        };
+        let name = path
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|name| name.to_str())
+            .unwrap_or("save");
+
+        let reader = SavegameReader::new(&data)?;
+        let version = reader.header().version;
+        let chunks = reader.read_chunks()?;
+        fs::write(corpus.join(format!("{name}.sav")), &data)?;
+
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        for chunk in &chunks {
+            writer.add_chunk(chunk)?;
+        }
+        fs::write(corpus.join(format!("{name}-none.sav")), writer.finalize()?)?;
+
+        for chunk in &chunks {
+            let mut writer = SavegameWriter::new(version, CompressionType::None);
+            writer.add_chunk(chunk)?;
+            let file = format!("{name}-{}.sav", chunk.tag.to_lowercase());
+            fs::write(corpus.join(file), writer.finalize()?)?;
+        }
+        println!("{}: {} chunks", path.display(), chunks.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+/// Loading and saving of the CAPA chunk
+///
+/// The next hop of a packet is saved under its old name, "loaded_at_xy",
+/// and was a 32-bit field before the tile it replaced was removed.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, missing, Record};
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::cargopacket::{CargoPacket, CargoPacketPool, Source, SourceType};
+use openttd_core::error::CoreError;
+use openttd_core::map::TileIndex;
+use openttd_core::types::StationID;
+
+fn packet_from_record(
+    index: usize,
+    record: &Record,
+    version: u16,
+) -> Result<CargoPacket, CoreError> {
+    let periods_in_transit = if version >= SaveLoadVersion::PeriodsInTransitRename {
+        int(record, "periods_in_transit")?
+    } else {
+        int(record, "days_in_transit")?
+    };
+    let source = if version >= SaveLoadVersion::V125 {
+        Source {
+            id: int(record, "source_id")? as u16,
+            type_: SourceType::try_from(int(record, "source_type")? as u8)?,
+        }
+    } else {
+        Source::default()
+    };
+    let (travelled_x, travelled_y) = if version >= SaveLoadVersion::CargoTravelled {
+        (
+            int(record, "travelled.x")? as i16,
+            int(record, "travelled.y")? as i16,
+        )
+    } else {
+        (0, 0)
+    };
+
+    Ok(CargoPacket {
+        index: index as u32,
+        count: int(record, "count")? as u16,
+        periods_in_transit: periods_in_transit as u16,
+        feeder_share: record
+            .get_i64("feeder_share")
+            .ok_or_else(|| missing("feeder_share"))?,
+        source_xy: TileIndex(int(record, "source_xy")? as u32),
+        travelled_x,
+        travelled_y,
+        source,
+        first_station: StationID(int(record, "source")? as u16),
+        next_hop: StationID(int(record, "loaded_at_xy")? as u16),
+    })
+}
+
+/// Load the cargo packet pool from the CAPA chunk
+pub fn load_cargo_packets(
+    chunks: &[Chunk],
+    version: u16,
+) -> Result<CargoPacketPool, SavegameError> {
+    let packets = chunk_records(
+        chunks,
+        b"CAPA",
+        version,
+        &cargo_packet_desc(),
+        &cargo_packet_compat(),
+    )?
+    .iter()
+    .map(|(index, record)| packet_from_record(*index, record, version))
+    .collect::<Result<Vec<_>, _>>()?;
+    Ok(CargoPacketPool::from_packets(packets)?)
+}
+
+/// Field declarations of CAPA (matches C++ GetCargoPacketDesc)
+fn cargo_packet_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U16, "source"),
+        SaveLoad::var(DataType::U32, "source_xy"),
+        SaveLoad::var(DataType::U32, "loaded_at_xy").until(SaveLoadVersion::RemoveLoadedAtXy),
+        SaveLoad::var(DataType::U16, "loaded_at_xy").since(SaveLoadVersion::RemoveLoadedAtXy),
+        SaveLoad::var(DataType::U16, "count"),
+        SaveLoad::var(DataType::U8, "days_in_transit").until(SaveLoadVersion::MoreCargoAge),
+        SaveLoad::var(DataType::U16, "days_in_transit")
+            .since(SaveLoadVersion::MoreCargoAge)
+            .until(SaveLoadVersion::PeriodsInTransitRename),
+        SaveLoad::var(DataType::U16, "periods_in_transit")
+            .since(SaveLoadVersion::PeriodsInTransitRename),
+        SaveLoad::var(DataType::I64, "feeder_share"),
+        SaveLoad::var(DataType::U8, "source_type").since(SaveLoadVersion::V125),
+        SaveLoad::var(DataType::U16, "source_id").since(SaveLoadVersion::V125),
+        SaveLoad::var(DataType::I16, "travelled.x").since(SaveLoadVersion::CargoTravelled),
+        SaveLoad::var(DataType::I16, "travelled.y").since(SaveLoadVersion::CargoTravelled),
+    ]
+}
+
+/// Order of the CAPA fields in savegames without a table header
+/// (matches C++ _cargopacket_sl_compat)
+fn cargo_packet_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::var("source"),
+        SaveLoadCompat::var("source_xy"),
+        SaveLoadCompat::var("loaded_at_xy"),
+        SaveLoadCompat::var("count"),
+        SaveLoadCompat::var("days_in_transit"),
+        SaveLoadCompat::var("feeder_share"),
+        SaveLoadCompat::var("source_type"),
+        SaveLoadCompat::var("source_id"),
+        SaveLoadCompat::null(1, SaveLoadVersion::MinVersion, SaveLoadVersion::V121),
+    ]
+}
+
+fn packet_to_record(packet: &CargoPacket, version: u16) -> Record {
+    let record = Record::default()
+        .with("source", packet.first_station.0)
+        .with("source_xy", packet.source_xy.0);
+    let record = if version >= SaveLoadVersion::RemoveLoadedAtXy {
+        record.with("loaded_at_xy", packet.next_hop.0)
+    } else {
+        record.with("loaded_at_xy", packet.next_hop.0 as u32)
+    };
+    let record = record.with("count", packet.count);
+    let record = if version >= SaveLoadVersion::PeriodsInTransitRename {
+        record.with("periods_in_transit", packet.periods_in_transit)
+    } else if version >= SaveLoadVersion::MoreCargoAge {
+        record.with("days_in_transit", packet.periods_in_transit)
+    } else {
+        record.with(
+            "days_in_transit",
+            packet.periods_in_transit.min(u8::MAX as u16) as u8,
+        )
+    };
+    record
+        .with("feeder_share", packet.feeder_share)
+        .with("source_type", packet.source.type_ as u8)
+        .with("source_id", packet.source.id)
+        .with("travelled.x", packet.travelled_x)
+        .with("travelled.y", packet.travelled_y)
+}
+
+/// Write the CAPA chunk in the layout of the writer's savegame version
+pub fn save_cargo_packets(
+    writer: &mut SavegameWriter,
+    pool: &CargoPacketPool,
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let records: Vec<(usize, Record)> = pool
+        .iter()
+        .map(|packet| (packet.index as usize, packet_to_record(packet, version)))
+        .collect();
+
+    writer.add_table_records(
+        b"CAPA",
+        ChunkType::Table,
+        &table_header(&cargo_packet_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+
+    fn sample_pool() -> CargoPacketPool {
+        let mut pool = CargoPacketPool::default();
+        let source = Source {
+            id: 12,
+            type_: SourceType::Town,
+        };
+        let mut packet = CargoPacket::new(StationID(4), 80, source);
+        packet.periods_in_transit = 300;
+        packet.feeder_share = -1234;
+        packet.source_xy = TileIndex(0x1234);
+        packet.travelled_x = -5;
+        packet.travelled_y = 17;
+        packet.next_hop = StationID(9);
+        pool.insert(packet.clone());
+        pool.insert(CargoPacket::new(StationID(5), 3, Source::default()));
+        pool.insert(packet);
+        pool.remove(1);
+        pool
+    }
+
+    fn round_trip(pool: &CargoPacketPool, version: u16) -> CargoPacketPool {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_cargo_packets(&mut writer, pool).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_cargo_packets(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_cargo_packets_round_trip() {
+        let pool = sample_pool();
+        assert_eq!(round_trip(&pool, SaveLoadVersion::CURRENT.into()), pool);
+        assert_eq!(
+            round_trip(&pool, 300).get(2).unwrap().periods_in_transit,
+            255
+        );
+
+        // Cargo has not recorded its travelled distance before CargoTravelled
+        let packet = round_trip(&pool, SaveLoadVersion::RemoveLoadedAtXy as u16);
+        let packet = packet.get(0).unwrap();
+        assert_eq!((packet.travelled_x, packet.travelled_y), (0, 0));
+        assert_eq!(packet.next_hop, StationID(9));
+    }
+
+    #[test]
+    fn test_cargo_packets_invalid() {
+        let mut record = packet_to_record(&sample_pool().get(0).unwrap().clone(), 316);
+        let source_type = record
+            .fields
+            .iter_mut()
+            .find(|(key, _)| key == "source_type")
+            .unwrap();
+        source_type.1 = 7u8.into();
+        assert!(packet_from_record(0, &record, 316).is_err());
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_cargo_packets(&mut writer, &sample_pool()),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
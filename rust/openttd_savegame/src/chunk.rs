@@ -77,7 +77,7 @@ impl ChunkHeader {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DataType {
     I8 = 1,
     U8 = 2,
@@ -92,6 +92,19 @@ pub enum DataType {
     Struct = 11,
 }
 
+impl DataType {
+    /// Size in bytes of a single element in the savegame, if fixed
+    pub fn file_size(&self) -> Option<usize> {
+        match self {
+            DataType::I8 | DataType::U8 => Some(1),
+            DataType::I16 | DataType::U16 | DataType::StringId => Some(2),
+            DataType::I32 | DataType::U32 => Some(4),
+            DataType::I64 | DataType::U64 => Some(8),
+            DataType::String | DataType::Struct => None,
+        }
+    }
+}
+
 impl TryFrom<u8> for DataType {
     type Error = CoreError;
 
@@ -116,37 +129,93 @@ impl TryFrom<u8> for DataType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TableField {
     pub data_type: DataType,
     pub key: String,
     pub is_list: bool, // 0x10 flag indicates if it's a list
+    /// Field layout of a `DataType::Struct` field
+    pub sub_header: Option<TableHeader>,
+}
+
+impl TableField {
+    /// A single value field; strings are always stored with their length
+    pub fn new(data_type: DataType, key: &str) -> Self {
+        Self {
+            data_type,
+            key: key.into(),
+            is_list: data_type == DataType::String,
+            sub_header: None,
+        }
+    }
+
+    /// A length-prefixed list of values
+    pub fn list(data_type: DataType, key: &str) -> Self {
+        Self {
+            is_list: true,
+            ..Self::new(data_type, key)
+        }
+    }
+
+    /// A list of structs with the given layout
+    pub fn structs(key: &str, sub_header: TableHeader) -> Self {
+        Self {
+            sub_header: Some(sub_header),
+            ..Self::list(DataType::Struct, key)
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Deepest nesting of struct fields accepted in a table header; the C++
+/// descriptions nest at most a few levels, so deeper headers are corrupt
+const MAX_STRUCT_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TableHeader {
     pub fields: Vec<TableField>,
 }
 
 impl TableHeader {
     pub fn parse(buf: &[u8]) -> Result<(Self, usize), CoreError> {
-        let mut offset = 0;
-        let mut fields = Vec::new();
-
         // Read header size
-        let (header_size, bytes_read) = gamma::decode_gamma(&buf[offset..])?;
-        offset += bytes_read;
+        let (header_size, bytes_read) = gamma::decode_gamma(buf)?;
 
         if header_size == 0 {
             return Err(CoreError::InvalidData("Table has no header".into()));
         }
 
-        let header_end = offset + (header_size as usize) - 1;
+        let header_end = usize::try_from(header_size - 1)
+            .ok()
+            .and_then(|size| size.checked_add(bytes_read))
+            .ok_or(CoreError::BufferTooSmall)?;
+        let header_buf = buf
+            .get(bytes_read..header_end)
+            .ok_or(CoreError::BufferTooSmall)?;
+
+        let mut reader = BigEndianReader::new(header_buf);
+        let header = Self::parse_fields(&mut reader, 0)?;
+
+        if reader.remaining() != 0 {
+            return Err(CoreError::InvalidData(format!(
+                "Table header has {} trailing bytes",
+                reader.remaining()
+            )));
+        }
+
+        Ok((header, header_end))
+    }
+
+    /// Read a field list up to its end marker, followed by the sub-headers of
+    /// its struct fields in declaration order
+    fn parse_fields(reader: &mut BigEndianReader, depth: usize) -> Result<Self, CoreError> {
+        if depth > MAX_STRUCT_DEPTH {
+            return Err(CoreError::InvalidData("Table header nests too deep".into()));
+        }
+        let mut fields = Vec::new();
 
         // Read field definitions
-        while offset < header_end {
-            let type_byte = buf[offset];
-            offset += 1;
+        loop {
+            let type_byte = reader.read_u8()?;
 
             if type_byte == 0 {
                 // End of field list
@@ -156,23 +225,63 @@ impl TableHeader {
             let is_list = (type_byte & 0x10) != 0;
             let data_type = DataType::try_from(type_byte)?;
 
-            // Read key length
-            let (key_length, bytes_read) = gamma::decode_gamma(&buf[offset..])?;
-            offset += bytes_read;
-
             // Read key
-            let key = String::from_utf8(buf[offset..offset + key_length as usize].to_vec())
+            let key_length = gamma::read_gamma(reader)? as usize;
+            let key = String::from_utf8(reader.read_bytes(key_length)?.to_vec())
                 .map_err(|e| CoreError::InvalidData(e.to_string()))?;
-            offset += key_length as usize;
 
             fields.push(TableField {
                 data_type,
                 key,
                 is_list,
+                sub_header: None,
             });
         }
 
-        Ok((Self { fields }, offset))
+        for field in &mut fields {
+            if field.data_type == DataType::Struct {
+                field.sub_header = Some(Self::parse_fields(reader, depth + 1)?);
+            }
+        }
+
+        Ok(Self { fields })
+    }
+
+    /// Find a field by key
+    pub fn field(&self, key: &str) -> Option<&TableField> {
+        self.fields.iter().find(|f| f.key == key)
+    }
+
+    /// Encode the header, including its size prefix and struct sub-headers
+    pub fn write(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        self.write_fields(&mut body);
+
+        let mut buf = gamma::encode_gamma(body.len() as u64 + 1);
+        buf.extend_from_slice(&body);
+        buf
+    }
+
+    fn write_fields(&self, buf: &mut Vec<u8>) {
+        for field in &self.fields {
+            // Strings and structs are always stored with a length field
+            let has_length =
+                field.is_list || matches!(field.data_type, DataType::String | DataType::Struct);
+            buf.push(field.data_type as u8 | if has_length { 0x10 } else { 0 });
+            buf.extend_from_slice(&gamma::encode_gamma(field.key.len() as u64));
+            buf.extend_from_slice(field.key.as_bytes());
+        }
+        buf.push(0);
+
+        for field in &self.fields {
+            if field.data_type == DataType::Struct {
+                field
+                    .sub_header
+                    .as_ref()
+                    .unwrap_or(&TableHeader { fields: Vec::new() })
+                    .write_fields(buf);
+            }
+        }
     }
 }
 
@@ -181,37 +290,36 @@ pub fn parse_riff_chunk(header: &ChunkHeader, buf: &[u8]) -> Result<(Vec<u8>, us
     let mut offset = 0;
 
     // Read the length (24-bit value + upper bits from mode)
-    if buf.len() < 3 {
-        return Err(CoreError::BufferTooSmall);
-    }
-
-    let mut reader = BigEndianReader::new(&buf[offset..]);
+    let mut reader = BigEndianReader::new(buf);
     let length_low = reader.read_u24()? as usize;
     offset += 3;
 
     // Upper 4 bits of mode byte contribute to length
     let length = length_low | (((header.mode_byte >> 4) as usize) << 24);
 
-    if buf.len() < offset + length {
-        return Err(CoreError::BufferTooSmall);
-    }
-
-    let data = buf[offset..offset + length].to_vec();
+    let data = buf
+        .get(offset..offset + length)
+        .ok_or(CoreError::BufferTooSmall)?
+        .to_vec();
     Ok((data, offset + length))
 }
 
-/// Parse an ARRAY or SPARSE_ARRAY chunk
-pub fn parse_array_chunk(
-    header: &ChunkHeader,
-    buf: &[u8],
-) -> Result<(Vec<(usize, Vec<u8>)>, usize), CoreError> {
+/// Raw records of an array or table chunk, keyed by index
+pub type Records = Vec<(usize, Vec<u8>)>;
+
+/// Parse the length-prefixed records shared by array and table chunks
+///
+/// Each record is prefixed with a gamma of its size plus one; a zero size ends
+/// the list. In sparse chunks the size also covers the gamma-encoded index that
+/// follows it. In dense chunks an empty record just skips an index.
+fn parse_records(sparse: bool, buf: &[u8]) -> Result<(Records, usize), CoreError> {
     let mut offset = 0;
     let mut items = Vec::new();
     let mut implicit_index = 0;
 
     loop {
         // Read item size
-        let (size_plus_one, bytes_read) = gamma::decode_gamma(&buf[offset..])?;
+        let (size_plus_one, bytes_read) = gamma::decode_gamma(buf.get(offset..).unwrap_or(&[]))?;
         offset += bytes_read;
 
         if size_plus_one == 0 {
@@ -219,12 +327,15 @@ pub fn parse_array_chunk(
             break;
         }
 
-        let size = (size_plus_one - 1) as usize;
+        let mut size = usize::try_from(size_plus_one - 1).map_err(|_| CoreError::BufferTooSmall)?;
 
-        let index = if header.chunk_type == ChunkType::SparseArray {
-            // Read explicit index
-            let (idx, bytes_read) = gamma::decode_gamma(&buf[offset..])?;
+        let index = if sparse {
+            // Read explicit index, which is included in the size
+            let (idx, bytes_read) = gamma::decode_gamma(buf.get(offset..).unwrap_or(&[]))?;
             offset += bytes_read;
+            size = size
+                .checked_sub(bytes_read)
+                .ok_or_else(|| CoreError::InvalidData("Sparse index exceeds record size".into()))?;
             idx as usize
         } else {
             // Use implicit index for regular array
@@ -235,77 +346,39 @@ pub fn parse_array_chunk(
 
         if size > 0 {
             // Read item data
-            if buf.len() < offset + size {
-                return Err(CoreError::BufferTooSmall);
-            }
-
-            let data = buf[offset..offset + size].to_vec();
+            let data = offset
+                .checked_add(size)
+                .and_then(|end| buf.get(offset..end))
+                .ok_or(CoreError::BufferTooSmall)?
+                .to_vec();
             offset += size;
             items.push((index, data));
-        } else if header.chunk_type == ChunkType::Array {
-            // Empty slot in regular array
-            implicit_index += 1;
         }
     }
 
     Ok((items, offset))
 }
 
+/// Parse an ARRAY or SPARSE_ARRAY chunk
+pub fn parse_array_chunk(header: &ChunkHeader, buf: &[u8]) -> Result<(Records, usize), CoreError> {
+    parse_records(header.chunk_type == ChunkType::SparseArray, buf)
+}
+
 /// Parse a TABLE or SPARSE_TABLE chunk
 pub fn parse_table_chunk(
     header: &ChunkHeader,
     buf: &[u8],
-) -> Result<(TableHeader, Vec<(usize, Vec<u8>)>, usize), CoreError> {
-    let mut offset = 0;
-
+) -> Result<(TableHeader, Records, usize), CoreError> {
     // Parse table header
-    let (table_header, bytes_read) = TableHeader::parse(&buf[offset..])?;
-    offset += bytes_read;
+    let (table_header, header_len) = TableHeader::parse(buf)?;
 
     // Parse records (similar to array parsing)
-    let mut items = Vec::new();
-    let mut implicit_index = 0;
-
-    loop {
-        // Read record size
-        let (size_plus_one, bytes_read) = gamma::decode_gamma(&buf[offset..])?;
-        offset += bytes_read;
+    let (items, records_len) = parse_records(
+        header.chunk_type == ChunkType::SparseTable,
+        buf.get(header_len..).ok_or(CoreError::BufferTooSmall)?,
+    )?;
 
-        if size_plus_one == 0 {
-            // End of records
-            break;
-        }
-
-        let size = (size_plus_one - 1) as usize;
-
-        let index = if header.chunk_type == ChunkType::SparseTable {
-            // Read explicit index
-            let (idx, bytes_read) = gamma::decode_gamma(&buf[offset..])?;
-            offset += bytes_read;
-            idx as usize
-        } else {
-            // Use implicit index for regular table
-            let idx = implicit_index;
-            implicit_index += 1;
-            idx
-        };
-
-        if size > 0 {
-            // Read record data
-            if buf.len() < offset + size {
-                return Err(CoreError::BufferTooSmall);
-            }
-
-            let data = buf[offset..offset + size].to_vec();
-            offset += size;
-            items.push((index, data));
-        } else if header.chunk_type == ChunkType::Table {
-            // Empty slot in regular table
-            implicit_index += 1;
-        }
-    }
-
-    Ok((table_header, items, offset))
+    Ok((table_header, items, header_len + records_len))
 }
 
 #[cfg(test)]
@@ -350,4 +423,137 @@ mod tests {
         assert!(DataType::try_from(0).is_err());
         assert!(DataType::try_from(12).is_err());
     }
+
+    #[test]
+    fn test_table_header_with_sub_header() {
+        // Fields: "at" (u8), "action" (struct list); then action's sub-header: "ct" (u8)
+        let mut body = vec![0x02, 2];
+        body.extend_from_slice(b"at");
+        body.extend_from_slice(&[0x1B, 6]);
+        body.extend_from_slice(b"action");
+        body.push(0);
+        body.extend_from_slice(&[0x02, 2]);
+        body.extend_from_slice(b"ct");
+        body.push(0);
+
+        let mut buf = vec![body.len() as u8 + 1];
+        buf.extend_from_slice(&body);
+        buf.push(0xAA); // first record byte, must not be consumed
+
+        let (header, bytes_read) = TableHeader::parse(&buf).unwrap();
+        assert_eq!(bytes_read, buf.len() - 1);
+        assert_eq!(header.fields.len(), 2);
+        assert!(header.fields[0].sub_header.is_none());
+
+        let action = header.field("action").unwrap();
+        assert_eq!(action.data_type, DataType::Struct);
+        assert!(action.is_list);
+        let sub_header = action.sub_header.as_ref().unwrap();
+        assert_eq!(sub_header.fields.len(), 1);
+        assert_eq!(sub_header.fields[0].key, "ct");
+    }
+
+    #[test]
+    fn test_sparse_array_size_includes_index() {
+        let header = ChunkHeader {
+            tag: *b"TEST",
+            chunk_type: ChunkType::SparseArray,
+            mode_byte: 2,
+        };
+        // Item at index 200 (2-byte gamma) with 1 byte of data, then terminator
+        let buf = [4, 0x80, 200, 0x42, 0];
+        let (items, bytes_read) = parse_array_chunk(&header, &buf).unwrap();
+        assert_eq!(items, vec![(200, vec![0x42])]);
+        assert_eq!(bytes_read, buf.len());
+    }
+
+    #[test]
+    fn test_array_empty_slots() {
+        let header = ChunkHeader {
+            tag: *b"TEST",
+            chunk_type: ChunkType::Array,
+            mode_byte: 1,
+        };
+        // Empty slots 0 and 1, then an item at index 2
+        let buf = [1, 1, 2, 0x42, 0];
+        let (items, _) = parse_array_chunk(&header, &buf).unwrap();
+        assert_eq!(items, vec![(2, vec![0x42])]);
+    }
+
+    #[test]
+    fn test_table_header_write_round_trip() {
+        let header = TableHeader {
+            fields: vec![
+                TableField {
+                    data_type: DataType::U8,
+                    key: "at".into(),
+                    is_list: false,
+                    sub_header: None,
+                },
+                TableField {
+                    data_type: DataType::Struct,
+                    key: "action".into(),
+                    is_list: true,
+                    sub_header: Some(TableHeader {
+                        fields: vec![TableField {
+                            data_type: DataType::String,
+                            key: "name".into(),
+                            is_list: true,
+                            sub_header: None,
+                        }],
+                    }),
+                },
+            ],
+        };
+
+        let buf = header.write();
+        let (parsed, bytes_read) = TableHeader::parse(&buf).unwrap();
+        assert_eq!(bytes_read, buf.len());
+        assert_eq!(parsed, header);
+    }
+
+    #[test]
+    fn test_truncated_chunks() {
+        let table = ChunkHeader {
+            tag: *b"TEST",
+            chunk_type: ChunkType::SparseTable,
+            mode_byte: 4,
+        };
+        let header = TableHeader {
+            fields: vec![TableField::new(DataType::U16, "a")],
+        };
+        let mut buf = header.write();
+        buf.extend_from_slice(&[5, 0x80, 200, 0x12, 0x34, 0]);
+        assert!(parse_table_chunk(&table, &buf).is_ok());
+        for len in 0..buf.len() {
+            assert!(parse_table_chunk(&table, &buf[..len]).is_err());
+        }
+
+        let riff = ChunkHeader {
+            tag: *b"TEST",
+            chunk_type: ChunkType::Riff,
+            mode_byte: 0x10,
+        };
+        assert!(parse_riff_chunk(&riff, &[0, 0, 1, 0x42]).is_err());
+        // A header size beyond the buffer
+        assert!(TableHeader::parse(&[0xF0, 0xFF, 0xFF, 0xFF, 0xFF, 0]).is_err());
+    }
+
+    #[test]
+    fn test_table_header_nesting_limit() {
+        // Every level is a single struct field "s" with the next level as sub-header
+        let nested = |depth: usize| {
+            let mut body = Vec::new();
+            for _ in 0..depth {
+                body.extend_from_slice(&[0x1B, 1, b's', 0]);
+            }
+            body.push(0);
+            let mut buf = gamma::encode_gamma(body.len() as u64 + 1);
+            buf.extend_from_slice(&body);
+            buf
+        };
+        assert!(TableHeader::parse(&nested(MAX_STRUCT_DEPTH)).is_ok());
+        assert!(TableHeader::parse(&nested(MAX_STRUCT_DEPTH + 1)).is_err());
+        assert!(TableHeader::parse(&nested(100_000)).is_err());
+    }
 }
@@ -0,0 +1,786 @@
+/// Loading and saving of the PLYR chunk
+///
+/// Besides the company properties, every record nests the company settings,
+/// the current and past quarterly economy entries and one livery per scheme.
+/// Infrastructure counts are not saved; like the C++ after-load they have to
+/// be recounted from the map. Values that the C++ after-load converts, such as
+/// the inaugurated year of a byte before version 31, are kept as saved.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, int_list, missing, part, reference, signed, to_reference, Record, Value};
+use crate::version::{table_header, ListLength, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::company::{
+    Company, CompanyEconomyEntry, CompanySettings, ExpensesType, Livery, LiveryScheme, LS_END,
+    MAX_HISTORY_QUARTERS,
+};
+use openttd_core::error::CoreError;
+use openttd_core::map::TileIndex;
+use openttd_core::types::{CalendarYear, Colours, EconomyYear, Owner};
+
+/// Years of expenses kept per company
+const EXPENSES_YEARS: usize = 3;
+/// Number of cargo types in the delivered cargo array
+const NUM_CARGO: usize = 64;
+/// Share owner slots of old savegames
+const OLD_SHARE_OWNERS: usize = 4;
+/// Delivered cargo slots before version 170
+const OLD_NUM_CARGO: usize = 32;
+/// Livery flags of a scheme that sets its own colours
+const LIVERY_PRIMARY_SECONDARY: u8 = 0b11;
+
+/// The allow list of these versions is a vector of strings, which the table
+/// header cannot tell apart from a single string
+fn check_allow_list_format(version: u16) -> Result<(), CoreError> {
+    if version >= SaveLoadVersion::CompanyAllowList && version < SaveLoadVersion::CompanyAllowListV2
+    {
+        return Err(CoreError::InvalidData(format!(
+            "PLYR: the allow list format of version {} is not supported",
+            version
+        )));
+    }
+    Ok(())
+}
+
+fn load_settings(company: &mut Company, r: &Record, version: u16) -> Result<(), CoreError> {
+    let settings = &mut company.settings;
+    *settings = CompanySettings::default();
+    if version >= SaveLoadVersion::V19 {
+        company.engine_renew_list = reference(r, "engine_renew_list")?;
+    }
+    if version >= SaveLoadVersion::V16 {
+        settings.engine_renew = int(r, "settings.engine_renew")? != 0;
+        settings.engine_renew_months = int(r, "settings.engine_renew_months")? as i16;
+        settings.engine_renew_money = int(r, "settings.engine_renew_money")? as u32 as i64;
+    }
+    if version >= SaveLoadVersion::V2 {
+        settings.renew_keep_length = int(r, "settings.renew_keep_length")? != 0;
+    }
+    if version >= SaveLoadVersion::V120 {
+        settings.servint_ispercent = int(r, "settings.vehicle.servint_ispercent")? != 0;
+        settings.servint_trains = int(r, "settings.vehicle.servint_trains")? as u16;
+        settings.servint_roadveh = int(r, "settings.vehicle.servint_roadveh")? as u16;
+        settings.servint_aircraft = int(r, "settings.vehicle.servint_aircraft")? as u16;
+        settings.servint_ships = int(r, "settings.vehicle.servint_ships")? as u16;
+    }
+    Ok(())
+}
+
+fn load_economy(r: &Record, version: u16) -> Result<CompanyEconomyEntry, CoreError> {
+    let mut entry = CompanyEconomyEntry {
+        income: signed(r, "income")?,
+        expenses: signed(r, "expenses")?,
+        company_value: signed(r, "company_value")?,
+        performance_history: int(r, "performance_history")? as i32,
+        ..CompanyEconomyEntry::default()
+    };
+    if version < SaveLoadVersion::V170 {
+        // A single total, kept in the last slot
+        entry.delivered_cargo[NUM_CARGO - 1] = int(r, "delivered_cargo[NUM_CARGO - 1]")? as u32;
+        return Ok(entry);
+    }
+    let delivered = int_list(r, "delivered_cargo")?;
+    if delivered.len() > NUM_CARGO {
+        return Err(CoreError::InvalidData(
+            "PLYR: too many delivered cargo entries".into(),
+        ));
+    }
+    for (slot, value) in entry.delivered_cargo.iter_mut().zip(delivered) {
+        *slot = value as u32;
+    }
+    Ok(entry)
+}
+
+fn company_from_record(index: usize, record: &Record, version: u16) -> Result<Company, CoreError> {
+    let index = u8::try_from(index)
+        .map_err(|_| CoreError::InvalidData(format!("PLYR: invalid company {}", index)))?;
+    let mut company = Company::new(index, int(record, "name_1")? as u16);
+    company.name_2 = int(record, "name_2")? as u32;
+    company.name = record.get_str("name").unwrap_or_default().into();
+    company.president_name_1 = int(record, "president_name_1")? as u16;
+    company.president_name_2 = int(record, "president_name_2")? as u32;
+    company.president_name = record.get_str("president_name").unwrap_or_default().into();
+    if version >= SaveLoadVersion::CompanyAllowListV2 {
+        company.allow_list = record
+            .get_structs("allow_list")
+            .map(|k| Ok(k.get_str("key").ok_or_else(|| missing("key"))?.into()))
+            .collect::<Result<_, CoreError>>()?;
+    }
+    company.face = int(record, "face")? as u32;
+    if version >= SaveLoadVersion::FaceStyles {
+        company.face_style = record.get_str("face_style").unwrap_or_default().into();
+    }
+    company.money = signed(record, "money")?;
+    company.current_loan = signed(record, "current_loan")?;
+    if version >= SaveLoadVersion::MaxLoanForCompany {
+        company.max_loan = int(record, "max_loan")? as i64;
+    }
+    company.colour = Colours::try_from(int(record, "colour")? as u8)?;
+    company.money_fraction = int(record, "money_fraction")? as u8;
+    company.block_preview = int(record, "block_preview")? as u8;
+    company.location_of_hq = TileIndex(int(record, "location_of_HQ")? as u32);
+    company.last_build_coordinate = TileIndex(int(record, "last_build_coordinate")? as u32);
+    company.inaugurated_year = EconomyYear(int(record, "inaugurated_year")? as i32);
+    company.inaugurated_year_calendar = if version >= SaveLoadVersion::CompanyInauguratedPeriodV2 {
+        CalendarYear(int(record, "inaugurated_year_calendar")? as i32)
+    } else {
+        // Economy and calendar years only diverge in wallclock mode, which these saves predate
+        CalendarYear(company.inaugurated_year.0)
+    };
+    company.months_of_bankruptcy = int(record, "months_of_bankruptcy")? as u8;
+    company.bankrupt_asked = int(record, "bankrupt_asked")? as u16;
+    company.bankrupt_timeout = int(record, "bankrupt_timeout")? as i16;
+    company.bankrupt_value = signed(record, "bankrupt_value")?;
+
+    let expenses: Vec<i64> = record
+        .get_list("yearly_expenses")
+        .ok_or_else(|| missing("yearly_expenses"))?
+        .iter()
+        .map(|v| v.as_i64().ok_or_else(|| missing("yearly_expenses")))
+        .collect::<Result<_, _>>()?;
+    if expenses.len() != company.yearly_expenses.len() * company.yearly_expenses[0].len() {
+        return Err(CoreError::InvalidData(format!(
+            "PLYR: expected {} yearly expenses, found {}",
+            EXPENSES_YEARS * company.yearly_expenses[0].len(),
+            expenses.len()
+        )));
+    }
+    for (slot, value) in company.yearly_expenses.iter_mut().flatten().zip(expenses) {
+        *slot = value;
+    }
+
+    if version >= SaveLoadVersion::V2 {
+        company.is_ai = int(record, "is_ai")? != 0;
+    }
+    if version >= SaveLoadVersion::V156 {
+        company.terraform_limit = int(record, "terraform_limit")? as u32;
+        company.clear_limit = int(record, "clear_limit")? as u32;
+    }
+    if version >= SaveLoadVersion::V175 {
+        company.tree_limit = int(record, "tree_limit")? as u32;
+    }
+
+    load_settings(&mut company, part(record, "settings")?, version)?;
+    company.cur_economy = load_economy(part(record, "cur_economy")?, version)?;
+    let old_economy: Vec<&Record> = record.get_structs("old_economy").collect();
+    if old_economy.len() > MAX_HISTORY_QUARTERS {
+        return Err(CoreError::InvalidData(
+            "PLYR: too many old economy entries".into(),
+        ));
+    }
+    company.num_valid_stat_ent = old_economy.len() as u8;
+    for (slot, r) in company.old_economy.iter_mut().zip(old_economy) {
+        *slot = load_economy(r, version)?;
+    }
+
+    let liveries: Vec<&Record> = record.get_structs("liveries").collect();
+    if liveries.len() > LS_END {
+        return Err(CoreError::InvalidData("PLYR: too many liveries".into()));
+    }
+    let num_liveries = liveries.len();
+    for (slot, r) in company.livery.iter_mut().zip(liveries) {
+        *slot = Livery {
+            in_use: int(r, "in_use")? as u8,
+            colour1: int(r, "colour1")? as u8,
+            colour2: int(r, "colour2")? as u8,
+        };
+    }
+    convert_old_liveries(&mut company, num_liveries, version);
+
+    Ok(company)
+}
+
+/// Update the liveries of savegames that had fewer schemes
+/// (matches the C++ SlCompanyLiveries::Load)
+fn convert_old_liveries(company: &mut Company, num_liveries: usize, version: u16) {
+    let livery = &mut company.livery;
+    if version < SaveLoadVersion::GroupLiveries {
+        let default = livery[LiveryScheme::Default as usize];
+        for l in livery.iter_mut().take(num_liveries).skip(1) {
+            if l.in_use & LIVERY_PRIMARY_SECONDARY == 0 {
+                l.colour1 = default.colour1;
+                l.colour2 = default.colour2;
+            } else {
+                l.in_use = LIVERY_PRIMARY_SECONDARY;
+            }
+        }
+    }
+    if version < SaveLoadVersion::V85 {
+        // The monorail and maglev passenger wagons were inserted before the freight wagons
+        let freight = LiveryScheme::FreightWagon as usize;
+        livery.copy_within(freight - 2..LS_END - 2, freight);
+        livery[LiveryScheme::PassengerWagonMonorail as usize] =
+            livery[LiveryScheme::MonorailEngine as usize];
+        livery[LiveryScheme::PassengerWagonMaglev as usize] =
+            livery[LiveryScheme::MaglevEngine as usize];
+    }
+    if version < SaveLoadVersion::V63 {
+        livery[LiveryScheme::PassengerTram as usize] = livery[LiveryScheme::Bus as usize];
+        livery[LiveryScheme::FreightTram as usize] = livery[LiveryScheme::Truck as usize];
+    }
+}
+
+/// Load all companies from the PLYR chunk
+pub fn load_companies(chunks: &[Chunk], version: u16) -> Result<Vec<Company>, SavegameError> {
+    let records = chunk_records(chunks, b"PLYR", version, &company_desc(), &company_compat())?;
+    if !records.is_empty() {
+        check_allow_list_format(version)?;
+    }
+    records
+        .iter()
+        .map(|(index, record)| Ok(company_from_record(*index, record, version)?))
+        .collect()
+}
+
+fn economy_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::I32, "income").until(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I64, "income").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I32, "expenses").until(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I64, "expenses").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I32, "company_value").until(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I64, "company_value").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I32, "delivered_cargo[NUM_CARGO - 1]").until(SaveLoadVersion::V170),
+        SaveLoad::array(DataType::U32, "delivered_cargo", OLD_NUM_CARGO)
+            .since(SaveLoadVersion::V170)
+            .until(SaveLoadVersion::ExtendCargotypes),
+        SaveLoad::array(DataType::U32, "delivered_cargo", NUM_CARGO)
+            .since(SaveLoadVersion::ExtendCargotypes),
+        SaveLoad::var(DataType::I32, "performance_history"),
+    ]
+}
+
+/// Field declarations of PLYR (matches C++ _company_desc)
+fn company_desc() -> Vec<SaveLoad> {
+    let settings = vec![
+        SaveLoad::var(DataType::U16, "engine_renew_list")
+            .since(SaveLoadVersion::V19)
+            .until(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, "engine_renew_list").since(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::I8, "settings.engine_renew").since(SaveLoadVersion::V16),
+        SaveLoad::var(DataType::I16, "settings.engine_renew_months").since(SaveLoadVersion::V16),
+        SaveLoad::var(DataType::U32, "settings.engine_renew_money").since(SaveLoadVersion::V16),
+        SaveLoad::var(DataType::I8, "settings.renew_keep_length").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I8, "settings.vehicle.servint_ispercent")
+            .since(SaveLoadVersion::V120),
+        SaveLoad::var(DataType::U16, "settings.vehicle.servint_trains")
+            .since(SaveLoadVersion::V120),
+        SaveLoad::var(DataType::U16, "settings.vehicle.servint_roadveh")
+            .since(SaveLoadVersion::V120),
+        SaveLoad::var(DataType::U16, "settings.vehicle.servint_aircraft")
+            .since(SaveLoadVersion::V120),
+        SaveLoad::var(DataType::U16, "settings.vehicle.servint_ships").since(SaveLoadVersion::V120),
+    ];
+    // Only the number of building records is kept; the records are skipped
+    let old_ai = vec![
+        SaveLoad::var(DataType::U8, "num_build_rec").until(SaveLoadVersion::V107),
+        SaveLoad::structs("buildrec", Vec::new())
+            .length(ListLength::Field("num_build_rec".into()))
+            .compat(old_ai_buildrec_compat()),
+    ];
+    let liveries = vec![
+        SaveLoad::var(DataType::U8, "in_use").since(SaveLoadVersion::V34),
+        SaveLoad::var(DataType::U8, "colour1").since(SaveLoadVersion::V34),
+        SaveLoad::var(DataType::U8, "colour2").since(SaveLoadVersion::V34),
+    ];
+    let liveries = |count: usize| {
+        SaveLoad::structs("liveries", liveries.clone())
+            .length(ListLength::Fixed(count))
+            .compat(liveries_compat())
+    };
+
+    vec![
+        SaveLoad::var(DataType::U32, "name_2"),
+        SaveLoad::var(DataType::StringId, "name_1"),
+        SaveLoad::var(DataType::String, "name").since(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::StringId, "president_name_1"),
+        SaveLoad::var(DataType::U32, "president_name_2"),
+        SaveLoad::var(DataType::String, "president_name").since(SaveLoadVersion::V84),
+        SaveLoad::structs("allow_list", vec![SaveLoad::var(DataType::String, "key")])
+            .since(SaveLoadVersion::CompanyAllowListV2),
+        SaveLoad::var(DataType::U32, "face"),
+        SaveLoad::var(DataType::String, "face_style").since(SaveLoadVersion::FaceStyles),
+        SaveLoad::var(DataType::I32, "money").until(SaveLoadVersion::V1),
+        SaveLoad::var(DataType::I64, "money").since(SaveLoadVersion::V1),
+        SaveLoad::var(DataType::I32, "current_loan").until(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I64, "current_loan").since(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I64, "max_loan").since(SaveLoadVersion::MaxLoanForCompany),
+        SaveLoad::var(DataType::U8, "colour"),
+        SaveLoad::var(DataType::U8, "money_fraction"),
+        SaveLoad::var(DataType::U8, "block_preview"),
+        SaveLoad::var(DataType::U16, "location_of_HQ").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "location_of_HQ").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U16, "last_build_coordinate").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "last_build_coordinate").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U8, "inaugurated_year").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "inaugurated_year").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "inaugurated_year_calendar")
+            .since(SaveLoadVersion::CompanyInauguratedPeriodV2),
+        // Removed by PR#10709 without a version bump of its own
+        SaveLoad::list(DataType::U8, "share_owners").until(SaveLoadVersion::ExtendVehicleRandom),
+        SaveLoad::var(DataType::U8, "num_valid_stat_ent")
+            .until(SaveLoadVersion::SaveloadListLength),
+        SaveLoad::var(DataType::U8, "months_of_bankruptcy"),
+        SaveLoad::var(DataType::U8, "bankrupt_asked").until(SaveLoadVersion::V104),
+        SaveLoad::var(DataType::U16, "bankrupt_asked").since(SaveLoadVersion::V104),
+        SaveLoad::var(DataType::I16, "bankrupt_timeout"),
+        SaveLoad::var(DataType::I32, "bankrupt_value").until(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I64, "bankrupt_value").since(SaveLoadVersion::V65),
+        SaveLoad::array(
+            DataType::I32,
+            "yearly_expenses",
+            EXPENSES_YEARS * ExpensesType::End as usize,
+        )
+        .until(SaveLoadVersion::V2),
+        SaveLoad::array(
+            DataType::I64,
+            "yearly_expenses",
+            EXPENSES_YEARS * ExpensesType::End as usize,
+        )
+        .since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I8, "is_ai").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::U32, "terraform_limit").since(SaveLoadVersion::V156),
+        SaveLoad::var(DataType::U32, "clear_limit").since(SaveLoadVersion::V156),
+        SaveLoad::var(DataType::U32, "tree_limit").since(SaveLoadVersion::V175),
+        SaveLoad::structs("settings", settings).compat(settings_compat()),
+        // The C++ handler only reads this struct for AI companies
+        SaveLoad::structs("old_ai", old_ai)
+            .until(SaveLoadVersion::V107)
+            .length(ListLength::Computed(|r| {
+                (r.get_u64("is_ai").unwrap_or(0) != 0) as u64
+            }))
+            .compat(old_ai_compat()),
+        SaveLoad::structs("cur_economy", economy_desc()).compat(economy_compat()),
+        SaveLoad::structs("old_economy", economy_desc())
+            .length(ListLength::Field("num_valid_stat_ent".into()))
+            .compat(economy_compat()),
+        liveries(LS_END - 4)
+            .since(SaveLoadVersion::V34)
+            .until(SaveLoadVersion::V63),
+        liveries(LS_END - 2)
+            .since(SaveLoadVersion::V63)
+            .until(SaveLoadVersion::V85),
+        liveries(LS_END).since(SaveLoadVersion::V85),
+    ]
+}
+
+/// Order of the skipped SlCompanyOldAIBuildRec fields
+/// (matches C++ _company_old_ai_buildrec_compat)
+fn old_ai_buildrec_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::null(2, SaveLoadVersion::MinVersion, SaveLoadVersion::V6),
+        SaveLoadCompat::null(4, SaveLoadVersion::V6, SaveLoadVersion::V107),
+        SaveLoadCompat::null(2, SaveLoadVersion::MinVersion, SaveLoadVersion::V6),
+        SaveLoadCompat::null(4, SaveLoadVersion::V6, SaveLoadVersion::V107),
+        SaveLoadCompat::null(8, SaveLoadVersion::MinVersion, SaveLoadVersion::V107),
+    ]
+}
+
+/// Order of the SlCompanyOldAI fields (matches C++ _company_old_ai_compat)
+fn old_ai_compat() -> Vec<SaveLoadCompat> {
+    let null = SaveLoadCompat::null;
+    let min = SaveLoadVersion::MinVersion;
+    let (v6, v107) = (SaveLoadVersion::V6, SaveLoadVersion::V107);
+    // Two tiles and a word of the old AI state
+    let tiles = [
+        null(2, min, v6),
+        null(4, v6, v107),
+        null(2, min, v6),
+        null(4, v6, v107),
+    ];
+    let mut compat = vec![
+        null(2, min, v107),
+        null(2, min, SaveLoadVersion::V13),
+        null(4, SaveLoadVersion::V13, v107),
+        null(8, min, v107),
+        SaveLoadCompat::var("num_build_rec"),
+        null(3, min, v107),
+    ];
+    for _ in 0..2 {
+        compat.extend(tiles.clone());
+        compat.push(null(2, min, v107));
+    }
+    compat.extend([
+        null(2, min, SaveLoadVersion::V69),
+        null(4, SaveLoadVersion::V69, v107),
+        null(18, min, v107),
+        null(20, min, v107),
+        null(32, min, v107),
+        null(64, SaveLoadVersion::V2, v107),
+        SaveLoadCompat::var("buildrec"),
+    ]);
+    compat
+}
+
+/// Order of the SlCompanySettings fields in savegames without a table header
+/// (matches C++ _company_settings_compat)
+fn settings_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = vec![SaveLoadCompat::null(
+        512,
+        SaveLoadVersion::V16,
+        SaveLoadVersion::V19,
+    )];
+    compat.extend(
+        [
+            "engine_renew_list",
+            "settings.engine_renew",
+            "settings.engine_renew_months",
+            "settings.engine_renew_money",
+            "settings.renew_keep_length",
+            "settings.vehicle.servint_ispercent",
+            "settings.vehicle.servint_trains",
+            "settings.vehicle.servint_roadveh",
+            "settings.vehicle.servint_aircraft",
+            "settings.vehicle.servint_ships",
+        ]
+        .map(SaveLoadCompat::var),
+    );
+    compat.push(SaveLoadCompat::null(
+        63,
+        SaveLoadVersion::V2,
+        SaveLoadVersion::V144,
+    ));
+    compat
+}
+
+/// Order of the SlCompanyEconomy fields in savegames without a table header
+/// (matches C++ _company_economy_compat)
+fn economy_compat() -> Vec<SaveLoadCompat> {
+    [
+        "income",
+        "expenses",
+        "company_value",
+        "delivered_cargo[NUM_CARGO - 1]",
+        "delivered_cargo",
+        "performance_history",
+    ]
+    .map(SaveLoadCompat::var)
+    .into()
+}
+
+/// Order of the SlCompanyLiveries fields in savegames without a table header
+/// (matches C++ _company_liveries_compat)
+fn liveries_compat() -> Vec<SaveLoadCompat> {
+    ["in_use", "colour1", "colour2"]
+        .map(SaveLoadCompat::var)
+        .into()
+}
+
+/// Order of the PLYR fields in savegames without a table header
+/// (matches C++ _company_sl_compat)
+fn company_compat() -> Vec<SaveLoadCompat> {
+    let var = SaveLoadCompat::var;
+    let null = SaveLoadCompat::null;
+    let min = SaveLoadVersion::MinVersion;
+    vec![
+        var("name_2"),
+        var("name_1"),
+        var("name"),
+        var("president_name_1"),
+        var("president_name_2"),
+        var("president_name"),
+        var("face"),
+        var("money"),
+        var("current_loan"),
+        var("colour"),
+        var("money_fraction"),
+        null(1, min, SaveLoadVersion::V58),
+        var("block_preview"),
+        null(2, min, SaveLoadVersion::V94),
+        null(4, SaveLoadVersion::V94, SaveLoadVersion::V170),
+        var("location_of_HQ"),
+        var("last_build_coordinate"),
+        var("inaugurated_year"),
+        // The share owners
+        null(OLD_SHARE_OWNERS, min, SaveLoadVersion::TableChunks),
+        var("num_valid_stat_ent"),
+        var("months_of_bankruptcy"),
+        var("bankrupt_asked"),
+        var("bankrupt_timeout"),
+        var("bankrupt_value"),
+        var("yearly_expenses"),
+        var("is_ai"),
+        null(1, SaveLoadVersion::V107, SaveLoadVersion::V112),
+        null(1, SaveLoadVersion::V4, SaveLoadVersion::V100),
+        var("terraform_limit"),
+        var("clear_limit"),
+        var("tree_limit"),
+        var("settings"),
+        var("old_ai"),
+        var("cur_economy"),
+        var("old_economy"),
+        var("liveries"),
+    ]
+}
+
+fn economy_record(entry: &CompanyEconomyEntry) -> Record {
+    Record::default()
+        .with("income", entry.income)
+        .with("expenses", entry.expenses)
+        .with("company_value", entry.company_value)
+        .with("delivered_cargo", entry.delivered_cargo.to_vec())
+        .with("performance_history", entry.performance_history)
+}
+
+fn settings_record(company: &Company) -> Record {
+    let settings = &company.settings;
+    Record::default()
+        .with("engine_renew_list", to_reference(company.engine_renew_list))
+        .with("settings.engine_renew", settings.engine_renew as i8)
+        .with("settings.engine_renew_months", settings.engine_renew_months)
+        .with(
+            "settings.engine_renew_money",
+            settings.engine_renew_money as u32,
+        )
+        .with(
+            "settings.renew_keep_length",
+            settings.renew_keep_length as i8,
+        )
+        .with(
+            "settings.vehicle.servint_ispercent",
+            settings.servint_ispercent as i8,
+        )
+        .with("settings.vehicle.servint_trains", settings.servint_trains)
+        .with("settings.vehicle.servint_roadveh", settings.servint_roadveh)
+        .with(
+            "settings.vehicle.servint_aircraft",
+            settings.servint_aircraft,
+        )
+        .with("settings.vehicle.servint_ships", settings.servint_ships)
+}
+
+fn company_to_record(company: &Company, version: u16) -> Record {
+    let mut record = Record::default()
+        .with("name_2", company.name_2)
+        .with("name_1", Value::StringId(company.name_1))
+        .with("name", company.name.as_str())
+        .with(
+            "president_name_1",
+            Value::StringId(company.president_name_1),
+        )
+        .with("president_name_2", company.president_name_2)
+        .with("president_name", company.president_name.as_str());
+    if version >= SaveLoadVersion::CompanyAllowListV2 {
+        let keys: Vec<Record> = company
+            .allow_list
+            .iter()
+            .map(|key| Record::default().with("key", key.as_str()))
+            .collect();
+        record = record.with("allow_list", keys);
+    }
+    record = record.with("face", company.face);
+    if version >= SaveLoadVersion::FaceStyles {
+        record = record.with("face_style", company.face_style.as_str());
+    }
+    record = record
+        .with("money", company.money)
+        .with("current_loan", company.current_loan);
+    if version >= SaveLoadVersion::MaxLoanForCompany {
+        record = record.with("max_loan", company.max_loan);
+    }
+    record = record
+        .with("colour", company.colour as u8)
+        .with("money_fraction", company.money_fraction)
+        .with("block_preview", company.block_preview)
+        .with("location_of_HQ", company.location_of_hq.0)
+        .with("last_build_coordinate", company.last_build_coordinate.0)
+        .with("inaugurated_year", company.inaugurated_year.0);
+    if version >= SaveLoadVersion::CompanyInauguratedPeriodV2 {
+        record = record.with(
+            "inaugurated_year_calendar",
+            company.inaugurated_year_calendar.0,
+        );
+    }
+    if version < SaveLoadVersion::ExtendVehicleRandom {
+        // Shares are not modelled; nobody owns any
+        record = record.with("share_owners", vec![Owner::Invalid as u8; OLD_SHARE_OWNERS]);
+    }
+
+    let expenses: Vec<i64> = company.yearly_expenses.iter().flatten().copied().collect();
+    let old_economy: Vec<Record> = company
+        .old_economy
+        .iter()
+        .take(company.num_valid_stat_ent as usize)
+        .map(economy_record)
+        .collect();
+    let liveries: Vec<Record> = company
+        .livery
+        .iter()
+        .map(|l| {
+            Record::default()
+                .with("in_use", l.in_use)
+                .with("colour1", l.colour1)
+                .with("colour2", l.colour2)
+        })
+        .collect();
+
+    record
+        .with("months_of_bankruptcy", company.months_of_bankruptcy)
+        .with("bankrupt_asked", company.bankrupt_asked)
+        .with("bankrupt_timeout", company.bankrupt_timeout)
+        .with("bankrupt_value", company.bankrupt_value)
+        .with("yearly_expenses", expenses)
+        .with("is_ai", company.is_ai as i8)
+        .with("terraform_limit", company.terraform_limit)
+        .with("clear_limit", company.clear_limit)
+        .with("tree_limit", company.tree_limit)
+        .with("settings", vec![settings_record(company)])
+        .with("cur_economy", vec![economy_record(&company.cur_economy)])
+        .with("old_economy", old_economy)
+        .with("liveries", liveries)
+}
+
+/// Write the PLYR chunk in the layout of the writer's savegame version
+pub fn save_companies(
+    writer: &mut SavegameWriter,
+    companies: &[Company],
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+    check_allow_list_format(version)?;
+
+    let header = table_header(&company_desc(), version);
+    let mut companies: Vec<&Company> = companies.iter().collect();
+    companies.sort_by_key(|c| c.index);
+    let records: Vec<(usize, Record)> = companies
+        .iter()
+        .map(|c| (c.index as usize, company_to_record(c, version)))
+        .collect();
+
+    writer.add_table_records(b"PLYR", ChunkType::Table, &header, &records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+    use openttd_core::company::{ExpensesType, LiveryScheme};
+
+    fn sample_company(index: u8) -> Company {
+        let mut company = Company::new(index, 0x6001);
+        company.name = "Rust Transport".into();
+        company.president_name_2 = 1_371_281_687;
+        company.face = 204_603_921;
+        company.money = -12_345;
+        company.current_loan = 300_000;
+        company.colour = Colours::Pink;
+        company.location_of_hq = TileIndex(4096);
+        company.inaugurated_year = EconomyYear(1950);
+        company.inaugurated_year_calendar = CalendarYear(1950);
+        company.yearly_expenses[0][ExpensesType::LoanInt as usize] = 1640;
+        company.yearly_expenses[2][ExpensesType::Other as usize] = 24;
+        company.is_ai = index % 2 == 1;
+        company.terraform_limit = 1 << 28;
+        company.settings = CompanySettings {
+            engine_renew: true,
+            engine_renew_months: -6,
+            engine_renew_money: 100_000,
+            servint_trains: 150,
+            servint_ships: 360,
+            ..CompanySettings::default()
+        };
+        company.engine_renew_list = Some(3);
+        company.cur_economy.expenses = -328;
+        company.cur_economy.delivered_cargo[5] = 70;
+        company.old_economy[0] = CompanyEconomyEntry {
+            income: 500,
+            expenses: -492,
+            company_value: 1,
+            performance_history: 120,
+            ..CompanyEconomyEntry::default()
+        };
+        company.num_valid_stat_ent = 1;
+        for livery in &mut company.livery {
+            livery.colour1 = 2;
+            livery.colour2 = 2;
+        }
+        company.livery[LiveryScheme::Bus as usize] = Livery {
+            in_use: 3,
+            colour1: 7,
+            colour2: 9,
+        };
+        company
+    }
+
+    fn round_trip(companies: &[Company], version: u16) -> Vec<Company> {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_companies(&mut writer, companies).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_companies(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_companies_round_trip() {
+        let companies = vec![sample_company(1), sample_company(0)];
+        for version in [
+            SaveLoadVersion::TableChunks,
+            SaveLoadVersion::ExtendVehicleRandom,
+            SaveLoadVersion::MaxLoanForCompany,
+        ]
+        .map(u16::from)
+        {
+            let loaded = round_trip(&companies, version);
+            assert_eq!(loaded.len(), 2);
+            assert_eq!(loaded[0], companies[1]);
+            assert_eq!(loaded[1], companies[0]);
+            assert_eq!(loaded[0].old_economy[0].performance_history, 120);
+        }
+    }
+
+    #[test]
+    fn test_companies_newer_fields() {
+        let mut company = sample_company(0);
+        company.max_loan = 750_000;
+        company.allow_list = vec!["key-a".into(), "key-b".into()];
+        company.face_style = "default/1".into();
+        company.inaugurated_year_calendar = CalendarYear(1);
+
+        let loaded = round_trip(&[company.clone()], SaveLoadVersion::FaceStyles.into());
+        assert_eq!(loaded[0], company);
+
+        // Before the maximum loan was saved the default applies
+        let loaded = round_trip(&[company], SaveLoadVersion::TableChunks.into());
+        assert_eq!(loaded[0].max_loan, Company::new(0, 0).max_loan);
+        assert!(loaded[0].allow_list.is_empty());
+        assert_eq!(loaded[0].inaugurated_year_calendar, CalendarYear(1950));
+    }
+
+    #[test]
+    fn test_old_liveries_converted() {
+        let mut company = Company::new(0, 0);
+        let loaded = LS_END - 4;
+        for (i, livery) in company.livery.iter_mut().take(loaded).enumerate() {
+            *livery = Livery {
+                in_use: (i % 2) as u8,
+                colour1: i as u8,
+                colour2: i as u8,
+            };
+        }
+        convert_old_liveries(&mut company, loaded, SaveLoadVersion::V62.into());
+
+        let livery = |scheme: LiveryScheme| company.livery[scheme as usize];
+        // Unused schemes take the default colours, used ones both of their own
+        assert_eq!(livery(LiveryScheme::DieselEngine).colour1, 0);
+        assert_eq!(livery(LiveryScheme::SteamEngine).in_use, 3);
+        // Wagons and later schemes moved up by two
+        assert_eq!(livery(LiveryScheme::FreightWagon).colour1, 11);
+        assert_eq!(livery(LiveryScheme::PassengerWagonMonorail).colour1, 0);
+        assert_eq!(
+            livery(LiveryScheme::PassengerTram),
+            livery(LiveryScheme::Bus)
+        );
+        assert_eq!(livery(LiveryScheme::Bus).colour1, 0);
+        assert_eq!(livery(LiveryScheme::Truck).colour1, 13);
+    }
+
+    #[test]
+    fn test_companies_unsupported_versions() {
+        for version in [294, SaveLoadVersion::CompanyAllowList.into()] {
+            let mut writer = SavegameWriter::new(version, CompressionType::None);
+            assert!(save_companies(&mut writer, &[sample_company(0)]).is_err());
+        }
+    }
+}
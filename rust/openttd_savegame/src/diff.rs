@@ -0,0 +1,426 @@
+/// Semantic comparison of two savegames
+///
+/// Saves are compared at three levels: whole chunks, the records of table and
+/// array chunks, and the decoded map, companies, towns, stations, industries
+/// and vehicles. Every difference carries a path such as `CITY[3].name` or
+/// `tile(12,40).base.m5` that names what changed.
+use crate::savegame::{find_chunk, Chunk, ChunkData, SavegameError, SavegameReader};
+use crate::table::{Record, Value};
+use crate::{company, industry, map, station, town, vehicle};
+use openttd_core::map::Map;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// How deep into the savegame a difference was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiffLevel {
+    Chunk,
+    Record,
+    Field,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Changed { old: String, new: String },
+}
+
+/// A single difference between the old and the new savegame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub level: DiffLevel,
+    pub path: String,
+    pub change: Change,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.change {
+            Change::Added => write!(f, "+ {}", self.path),
+            Change::Removed => write!(f, "- {}", self.path),
+            Change::Changed { old, new } => write!(f, "~ {}: {} -> {}", self.path, old, new),
+        }
+    }
+}
+
+/// Collects differences in the order they are found
+struct Differ {
+    level: DiffLevel,
+    differences: Vec<Difference>,
+}
+
+impl Differ {
+    fn push(&mut self, path: String, change: Change) {
+        self.differences.push(Difference {
+            level: self.level,
+            path,
+            change,
+        });
+    }
+
+    fn changed(&mut self, path: String, old: impl ToString, new: impl ToString) {
+        let (old, new) = (old.to_string(), new.to_string());
+        if old != new {
+            self.push(path, Change::Changed { old, new });
+        }
+    }
+
+    /// Match items by key and report the ones only present on one side
+    fn keyed<K: Ord + fmt::Display, T>(
+        &mut self,
+        name: &str,
+        old: impl IntoIterator<Item = (K, T)>,
+        new: impl IntoIterator<Item = (K, T)>,
+        mut compare: impl FnMut(&mut Self, String, T, T),
+    ) {
+        let mut old: BTreeMap<K, T> = old.into_iter().collect();
+        for (key, new_item) in new {
+            let path = format!("{}[{}]", name, key);
+            match old.remove(&key) {
+                Some(old_item) => compare(self, path, old_item, new_item),
+                None => self.push(path, Change::Added),
+            }
+        }
+        for key in old.into_keys() {
+            self.push(format!("{}[{}]", name, key), Change::Removed);
+        }
+    }
+}
+
+/// The chunks of one side of the comparison
+struct Save<'a> {
+    chunks: &'a [Chunk],
+    version: u16,
+}
+
+fn chunk_summary(data: &ChunkData) -> String {
+    match data {
+        ChunkData::Riff(bytes) => format!("riff, {} bytes", bytes.len()),
+        ChunkData::Array(records) => format!("array, {} records", records.len()),
+        ChunkData::Table { records, .. } => format!("table, {} records", records.len()),
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::I8(v) => v.to_string(),
+        Value::U8(v) => v.to_string(),
+        Value::I16(v) => v.to_string(),
+        Value::U16(v) => v.to_string(),
+        Value::I32(v) => v.to_string(),
+        Value::U32(v) => v.to_string(),
+        Value::I64(v) => v.to_string(),
+        Value::U64(v) => v.to_string(),
+        Value::StringId(v) => format!("string {}", v),
+        Value::String(v) => format!("{:?}", v),
+        Value::List(items) => {
+            let items: Vec<String> = items.iter().map(format_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Struct(record) => {
+            let fields: Vec<String> = record
+                .fields
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key, format_value(value)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+    }
+}
+
+fn diff_values(differ: &mut Differ, path: String, old: &Value, new: &Value) {
+    match (old, new) {
+        (Value::Struct(old), Value::Struct(new)) => diff_records(differ, path, old, new),
+        (Value::List(old), Value::List(new)) if old.len() == new.len() => {
+            for (i, (old, new)) in old.iter().zip(new).enumerate() {
+                diff_values(differ, format!("{}[{}]", path, i), old, new);
+            }
+        }
+        _ if old != new => differ.changed(path, format_value(old), format_value(new)),
+        _ => {}
+    }
+}
+
+fn diff_records(differ: &mut Differ, path: String, old: &Record, new: &Record) {
+    for (key, new_value) in &new.fields {
+        let field_path = format!("{}.{}", path, key);
+        match old.get(key) {
+            Some(old_value) => diff_values(differ, field_path, old_value, new_value),
+            None => differ.push(field_path, Change::Added),
+        }
+    }
+    for (key, _) in &old.fields {
+        if new.get(key).is_none() {
+            differ.push(format!("{}.{}", path, key), Change::Removed);
+        }
+    }
+    if old.trailing != new.trailing {
+        let change = Change::Changed {
+            old: format!("{} bytes", old.trailing.len()),
+            new: format!("{} bytes", new.trailing.len()),
+        };
+        differ.push(format!("{}.(trailing)", path), change);
+    }
+}
+
+fn diff_chunk_records(differ: &mut Differ, old: &Chunk, new: &Chunk) -> Result<(), SavegameError> {
+    match (&old.data, &new.data) {
+        (ChunkData::Array(old_records), ChunkData::Array(new_records)) => {
+            differ.keyed(
+                &old.tag,
+                old_records.clone(),
+                new_records.clone(),
+                |differ, path, old, new| {
+                    if old != new {
+                        let old = format!("{} bytes", old.len());
+                        let new = format!("{} bytes", new.len());
+                        differ.push(path, Change::Changed { old, new });
+                    }
+                },
+            );
+        }
+        (ChunkData::Table { .. }, ChunkData::Table { .. }) => {
+            differ.keyed(
+                &old.tag,
+                old.decode_records()?,
+                new.decode_records()?,
+                |differ, path, old, new| diff_records(differ, path, &old, &new),
+            );
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn diff_json(differ: &mut Differ, path: String, old: &serde_json::Value, new: &serde_json::Value) {
+    use serde_json::Value as Json;
+    match (old, new) {
+        (Json::Object(old), Json::Object(new)) => {
+            for (key, new_value) in new {
+                let field_path = format!("{}.{}", path, key);
+                match old.get(key) {
+                    Some(old_value) => diff_json(differ, field_path, old_value, new_value),
+                    None => differ.push(field_path, Change::Added),
+                }
+            }
+        }
+        (Json::Array(old), Json::Array(new)) if old.len() == new.len() => {
+            for (i, (old, new)) in old.iter().zip(new).enumerate() {
+                diff_json(differ, format!("{}[{}]", path, i), old, new);
+            }
+        }
+        _ => differ.changed(path, old, new),
+    }
+}
+
+/// Compare decoded objects, matched by their `index` field
+fn diff_objects<T: Serialize>(
+    differ: &mut Differ,
+    name: &str,
+    load: fn(&[Chunk], u16) -> Result<Vec<T>, SavegameError>,
+    old: &Save,
+    new: &Save,
+) -> Result<(), SavegameError> {
+    let keyed = |save: &Save| -> Result<Vec<(u64, serde_json::Value)>, SavegameError> {
+        load(save.chunks, save.version)?
+            .iter()
+            .map(|item| {
+                let value = serde_json::to_value(item)?;
+                Ok((value["index"].as_u64().unwrap_or_default(), value))
+            })
+            .collect()
+    };
+    differ.keyed(name, keyed(old)?, keyed(new)?, |differ, path, old, new| {
+        diff_json(differ, path, &old, &new)
+    });
+    Ok(())
+}
+
+fn diff_maps(differ: &mut Differ, old: &Map, new: &Map) -> Result<(), SavegameError> {
+    if (old.size_x, old.size_y) != (new.size_x, new.size_y) {
+        differ.changed(
+            "map.size".into(),
+            format!("{}x{}", old.size_x, old.size_y),
+            format!("{}x{}", new.size_x, new.size_y),
+        );
+        return Ok(());
+    }
+    for (i, (old_tile, new_tile)) in old.tiles.iter().zip(&new.tiles).enumerate() {
+        if old_tile != new_tile {
+            let (x, y) = (i as u32 % old.size_x, i as u32 / old.size_x);
+            diff_json(
+                differ,
+                format!("tile({},{})", x, y),
+                &serde_json::to_value(old_tile)?,
+                &serde_json::to_value(new_tile)?,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Compare the chunks of two savegames
+pub fn diff_chunks<'a>(
+    old: &'a [Chunk],
+    old_version: u16,
+    new: &'a [Chunk],
+    new_version: u16,
+) -> Result<Vec<Difference>, SavegameError> {
+    let mut differ = Differ {
+        level: DiffLevel::Chunk,
+        differences: Vec::new(),
+    };
+    differ.changed("version".into(), old_version, new_version);
+
+    let by_tag = |chunks: &'a [Chunk]| {
+        chunks
+            .iter()
+            .map(|c| (c.tag.clone(), c))
+            .collect::<Vec<_>>()
+    };
+    let mut changed = Vec::new();
+    differ.keyed(
+        "chunk",
+        by_tag(old),
+        by_tag(new),
+        |differ, path, old: &Chunk, new: &Chunk| {
+            if old.data != new.data {
+                differ.push(
+                    path,
+                    Change::Changed {
+                        old: chunk_summary(&old.data),
+                        new: chunk_summary(&new.data),
+                    },
+                );
+                changed.push((old, new));
+            }
+        },
+    );
+
+    differ.level = DiffLevel::Record;
+    for (old_chunk, new_chunk) in changed {
+        diff_chunk_records(&mut differ, old_chunk, new_chunk)?;
+    }
+
+    differ.level = DiffLevel::Field;
+    if find_chunk(old, b"MAPS").is_some() && find_chunk(new, b"MAPS").is_some() {
+        let old_map = map::load_map(old, old_version)?;
+        let new_map = map::load_map(new, new_version)?;
+        diff_maps(&mut differ, &old_map, &new_map)?;
+    }
+    let old = Save {
+        chunks: old,
+        version: old_version,
+    };
+    let new = Save {
+        chunks: new,
+        version: new_version,
+    };
+    diff_objects(&mut differ, "company", company::load_companies, &old, &new)?;
+    diff_objects(&mut differ, "town", town::load_towns, &old, &new)?;
+    diff_objects(&mut differ, "station", station::load_stations, &old, &new)?;
+    diff_objects(
+        &mut differ,
+        "industry",
+        industry::load_industries,
+        &old,
+        &new,
+    )?;
+    diff_objects(&mut differ, "vehicle", vehicle::load_vehicles, &old, &new)?;
+
+    Ok(differ.differences)
+}
+/// Compare two complete savegame files
+pub fn diff_savegames(old: &[u8], new: &[u8]) -> Result<Vec<Difference>, SavegameError> {
+    let old = SavegameReader::new(old)?;
+    let new = SavegameReader::new(new)?;
+    diff_chunks(
+        &old.read_chunks()?,
+        old.header().version,
+        &new.read_chunks()?,
+        new.header().version,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameWriter;
+    use crate::types::CompressionType;
+    use crate::version::SaveLoadVersion;
+    use openttd_core::town::Town;
+    use openttd_core::types::TownID;
+
+    fn savegame(map: &Map, towns: &[Town], date: &[u8]) -> Vec<u8> {
+        let mut writer =
+            SavegameWriter::new(SaveLoadVersion::TableChunks.into(), CompressionType::None);
+        writer.add_riff_chunk(b"DATE", date).unwrap();
+        map::save_map(&mut writer, map).unwrap();
+        town::save_towns(&mut writer, towns).unwrap();
+        writer.finalize().unwrap()
+    }
+
+    fn town(index: u16, text: &str) -> Town {
+        let mut town = Town::new(TownID(index), Default::default());
+        town.text = text.into();
+        town
+    }
+
+    fn paths(differences: &[Difference], level: DiffLevel) -> Vec<String> {
+        differences
+            .iter()
+            .filter(|d| d.level == level)
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_savegames() {
+        let data = savegame(&Map::new(6, 6).unwrap(), &[town(0, "A")], &[1]);
+        assert!(diff_savegames(&data, &data).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_levels() {
+        let mut map = Map::new(6, 6).unwrap();
+        let old = savegame(&map, &[town(0, "A"), town(1, "B")], &[1]);
+        map.tiles[64 * 3 + 2].base.m5 = 7;
+        let new = savegame(&map, &[town(0, "C"), town(2, "B")], &[2]);
+
+        let differences = diff_savegames(&old, &new).unwrap();
+        assert_eq!(
+            paths(&differences, DiffLevel::Chunk),
+            [
+                "~ chunk[DATE]: riff, 1 bytes -> riff, 1 bytes",
+                "~ chunk[MAP5]: riff, 4096 bytes -> riff, 4096 bytes",
+                "~ chunk[CITY]: table, 2 records -> table, 2 records",
+            ]
+        );
+        assert_eq!(
+            paths(&differences, DiffLevel::Record),
+            ["~ CITY[0].text: \"A\" -> \"C\"", "+ CITY[2]", "- CITY[1]",]
+        );
+        assert_eq!(
+            paths(&differences, DiffLevel::Field),
+            [
+                "~ tile(2,3).base.m5: 0 -> 7",
+                "~ town[0].text: \"A\" -> \"C\"",
+                "+ town[2]",
+                "- town[1]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_map_size() {
+        let old = savegame(&Map::new(6, 6).unwrap(), &[], &[1]);
+        let new = savegame(&Map::new(7, 6).unwrap(), &[], &[1]);
+        let differences = diff_savegames(&old, &new).unwrap();
+        assert_eq!(
+            paths(&differences, DiffLevel::Field),
+            ["~ map.size: 64x64 -> 128x64"]
+        );
+    }
+}
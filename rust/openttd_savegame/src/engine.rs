@@ -0,0 +1,323 @@
+/// Loading and saving of the ENGN and EIDS chunks
+///
+/// ENGN holds the model life of each engine and EIDS the GRF each engine
+/// ID belongs to. The engine pool itself is rebuilt from the mappings and
+/// the NewGRFs, so loaded engines carry only the saved state, ready to be
+/// copied over with EnginePool::copy_saved_data.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, signed, Record};
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::engine::{Engine, EngineFlags, EngineOverrideManager, EnginePool, ALL_COMPANIES};
+use openttd_core::error::CoreError;
+use openttd_core::types::{CalendarDate, EngineID, Owner};
+use openttd_core::vehicle::VehicleType;
+
+fn engine_from_record(index: usize, record: &Record, version: u16) -> Result<Engine, CoreError> {
+    let mut engine = Engine::new(EngineID(index as u16), VehicleType::Invalid, 0);
+    engine.intro_date = CalendarDate(signed(record, "intro_date")? as i32);
+    engine.age = signed(record, "age")? as i32;
+    engine.reliability = int(record, "reliability")? as u16;
+    engine.reliability_spd_dec = int(record, "reliability_spd_dec")? as u16;
+    engine.reliability_start = int(record, "reliability_start")? as u16;
+    engine.reliability_max = int(record, "reliability_max")? as u16;
+    engine.reliability_final = int(record, "reliability_final")? as u16;
+    engine.duration_phase_1 = int(record, "duration_phase_1")? as u16;
+    engine.duration_phase_2 = int(record, "duration_phase_2")? as u16;
+    engine.duration_phase_3 = int(record, "duration_phase_3")? as u16;
+    engine.flags = EngineFlags::from_bits_retain(int(record, "flags")? as u8);
+    if version >= SaveLoadVersion::V179 {
+        engine.preview_asked = int(record, "preview_asked")? as u16;
+        engine.preview_company = Owner::try_from(int(record, "preview_company")? as u8)?;
+    } else {
+        // The ranking of the preview was replaced; cancel any previews
+        // (bit 2 is the C++ ENGINE_OFFER_WINDOW_OPEN flag)
+        engine.flags.remove(EngineFlags::from_bits_retain(1 << 2));
+        engine.preview_company = Owner::Invalid;
+        engine.preview_asked = ALL_COMPANIES;
+    }
+    engine.preview_wait = int(record, "preview_wait")? as u8;
+    engine.company_avail = int(record, "company_avail")? as u16;
+    if version >= SaveLoadVersion::V193 {
+        engine.company_hidden = int(record, "company_hidden")? as u16;
+    }
+    engine.name = record.get_str("name").unwrap_or_default().into();
+    Ok(engine)
+}
+
+/// Load the saved engine state from the ENGN chunk, indexed by engine ID
+pub fn load_engines(chunks: &[Chunk], version: u16) -> Result<Vec<Engine>, SavegameError> {
+    let mut engines = Vec::new();
+    let records = chunk_records(chunks, b"ENGN", version, &engine_desc(), &engine_compat())?;
+    for (index, record) in records {
+        if index >= EngineID::MAX_ENGINES {
+            return Err(
+                CoreError::InvalidData(format!("ENGN: engine {} out of range", index)).into(),
+            );
+        }
+        // Gaps in the array are engines without saved state
+        while engines.len() < index {
+            let id = EngineID(engines.len() as u16);
+            engines.push(Engine::new(id, VehicleType::Invalid, 0));
+        }
+        engines.push(engine_from_record(index, &record, version)?);
+    }
+    Ok(engines)
+}
+
+/// Field declarations of ENGN (matches C++ _engine_desc)
+fn engine_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U16, "intro_date").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "intro_date").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::U16, "age").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "age").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::U16, "reliability"),
+        SaveLoad::var(DataType::U16, "reliability_spd_dec"),
+        SaveLoad::var(DataType::U16, "reliability_start"),
+        SaveLoad::var(DataType::U16, "reliability_max"),
+        SaveLoad::var(DataType::U16, "reliability_final"),
+        SaveLoad::var(DataType::U16, "duration_phase_1"),
+        SaveLoad::var(DataType::U16, "duration_phase_2"),
+        SaveLoad::var(DataType::U16, "duration_phase_3"),
+        SaveLoad::var(DataType::U8, "flags"),
+        SaveLoad::var(DataType::U16, "preview_asked").since(SaveLoadVersion::V179),
+        SaveLoad::var(DataType::U8, "preview_company").since(SaveLoadVersion::V179),
+        SaveLoad::var(DataType::U8, "preview_wait"),
+        SaveLoad::var(DataType::U8, "company_avail").until(SaveLoadVersion::V104),
+        SaveLoad::var(DataType::U16, "company_avail").since(SaveLoadVersion::V104),
+        SaveLoad::var(DataType::U16, "company_hidden").since(SaveLoadVersion::V193),
+        SaveLoad::var(DataType::String, "name").since(SaveLoadVersion::V84),
+    ]
+}
+
+/// Order of the ENGN fields in savegames without a table header
+/// (matches C++ _engine_sl_compat)
+fn engine_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::var("intro_date"),
+        SaveLoadCompat::var("age"),
+        SaveLoadCompat::var("reliability"),
+        SaveLoadCompat::var("reliability_spd_dec"),
+        SaveLoadCompat::var("reliability_start"),
+        SaveLoadCompat::var("reliability_max"),
+        SaveLoadCompat::var("reliability_final"),
+        SaveLoadCompat::var("duration_phase_1"),
+        SaveLoadCompat::var("duration_phase_2"),
+        SaveLoadCompat::var("duration_phase_3"),
+        SaveLoadCompat::null(1, SaveLoadVersion::MinVersion, SaveLoadVersion::V121),
+        SaveLoadCompat::var("flags"),
+        SaveLoadCompat::null(1, SaveLoadVersion::MinVersion, SaveLoadVersion::V179),
+        SaveLoadCompat::var("preview_asked"),
+        SaveLoadCompat::var("preview_company"),
+        SaveLoadCompat::var("preview_wait"),
+        SaveLoadCompat::null(1, SaveLoadVersion::MinVersion, SaveLoadVersion::V45),
+        SaveLoadCompat::var("company_avail"),
+        SaveLoadCompat::var("company_hidden"),
+        SaveLoadCompat::var("name"),
+        SaveLoadCompat::null(16, SaveLoadVersion::V2, SaveLoadVersion::V144),
+    ]
+}
+
+fn engine_to_record(engine: &Engine) -> Record {
+    Record::default()
+        .with("intro_date", engine.intro_date.0)
+        .with("age", engine.age)
+        .with("reliability", engine.reliability)
+        .with("reliability_spd_dec", engine.reliability_spd_dec)
+        .with("reliability_start", engine.reliability_start)
+        .with("reliability_max", engine.reliability_max)
+        .with("reliability_final", engine.reliability_final)
+        .with("duration_phase_1", engine.duration_phase_1)
+        .with("duration_phase_2", engine.duration_phase_2)
+        .with("duration_phase_3", engine.duration_phase_3)
+        .with("flags", engine.flags.bits())
+        .with("preview_asked", engine.preview_asked)
+        .with("preview_company", engine.preview_company as u8)
+        .with("preview_wait", engine.preview_wait)
+        .with("company_avail", engine.company_avail)
+        .with("company_hidden", engine.company_hidden)
+        .with("name", engine.name.as_str())
+}
+
+/// Write the ENGN chunk in the layout of the writer's savegame version
+pub fn save_engines(writer: &mut SavegameWriter, pool: &EnginePool) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let records: Vec<(usize, Record)> = pool
+        .iter()
+        .map(|engine| (engine.index.0 as usize, engine_to_record(engine)))
+        .collect();
+
+    writer.add_table_records(
+        b"ENGN",
+        ChunkType::Table,
+        &table_header(&engine_desc(), version),
+        &records,
+    )
+}
+
+/// Load the engine ID mappings from the EIDS chunk
+pub fn load_engine_id_mappings(
+    chunks: &[Chunk],
+    version: u16,
+) -> Result<EngineOverrideManager, SavegameError> {
+    let mut manager = EngineOverrideManager::default();
+    let compat = ["grfid", "internal_id", "type", "substitute_id"].map(SaveLoadCompat::var);
+    let records = chunk_records(chunks, b"EIDS", version, &engine_id_mapping_desc(), &compat)?;
+    for (index, record) in records {
+        let type_ = VehicleType::try_from(int(&record, "type")? as u8)?;
+        if type_ as u8 > VehicleType::Aircraft as u8 {
+            return Err(CoreError::InvalidData(format!(
+                "EIDS: engine {} has vehicle type {:?}",
+                index, type_
+            ))
+            .into());
+        }
+        manager.set_id(
+            type_,
+            int(&record, "internal_id")? as u16,
+            int(&record, "grfid")? as u32,
+            int(&record, "substitute_id")? as u8,
+            EngineID(index as u16),
+        );
+    }
+    Ok(manager)
+}
+
+/// Field declarations of EIDS (matches C++ _engine_id_mapping_desc)
+fn engine_id_mapping_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U32, "grfid"),
+        SaveLoad::var(DataType::U16, "internal_id"),
+        SaveLoad::var(DataType::U8, "type"),
+        SaveLoad::var(DataType::U8, "substitute_id"),
+    ]
+}
+
+/// Write the EIDS chunk, with the mappings of all vehicle types ordered by engine
+pub fn save_engine_id_mappings(
+    writer: &mut SavegameWriter,
+    manager: &EngineOverrideManager,
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let records: Vec<(usize, Record)> = manager
+        .sorted_by_engine()
+        .iter()
+        .map(|eid| {
+            let record = Record::default()
+                .with("grfid", eid.grfid)
+                .with("internal_id", eid.internal_id)
+                .with("type", eid.type_ as u8)
+                .with("substitute_id", eid.substitute_id);
+            (eid.engine.0 as usize, record)
+        })
+        .collect();
+
+    writer.add_table_records(
+        b"EIDS",
+        ChunkType::Table,
+        &table_header(&engine_id_mapping_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+    use openttd_core::engine::{EngineSettings, INVALID_GRFID};
+
+    fn sample() -> (EngineOverrideManager, EnginePool) {
+        let mut manager = EngineOverrideManager::default();
+        manager.reset_to_default_mapping();
+        manager.use_unreserved_id(VehicleType::Road, 4, 0x12345678, false);
+        manager.set_id(VehicleType::Aircraft, 7, 0x12345678, 2, EngineID(256));
+
+        let mut pool = EnginePool::setup(&manager);
+        pool.set_year_engine_aging_stops(EngineSettings::default().landscape);
+        pool.startup(&EngineSettings::default(), CalendarDate(730000), 7);
+        let engine = pool.get_mut(EngineID(3)).unwrap();
+        engine.name = "Ghost Train".into();
+        engine.preview_company = Owner::Company2;
+        engine.company_hidden = 0x0004;
+        (manager, pool)
+    }
+
+    fn round_trip(
+        manager: &EngineOverrideManager,
+        pool: &EnginePool,
+        version: u16,
+    ) -> (EngineOverrideManager, Vec<Engine>) {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_engine_id_mappings(&mut writer, manager).unwrap();
+        save_engines(&mut writer, pool).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        (
+            load_engine_id_mappings(&chunks, version).unwrap(),
+            load_engines(&chunks, version).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_engines_round_trip() {
+        let (manager, pool) = sample();
+        let (loaded_manager, saved) = round_trip(&manager, &pool, SaveLoadVersion::CURRENT.into());
+        assert_eq!(loaded_manager, manager);
+        assert_eq!(
+            loaded_manager.get_id(VehicleType::Road, 4, 0x12345678),
+            EngineID(120)
+        );
+        assert_eq!(
+            loaded_manager.get_id(VehicleType::Road, 4, INVALID_GRFID),
+            EngineID::INVALID
+        );
+        assert_eq!(saved.len(), 257);
+
+        // Rebuilding the pool from the mappings restores the saved state
+        let mut loaded = EnginePool::setup(&loaded_manager);
+        loaded.year_engine_aging_stops = pool.year_engine_aging_stops;
+        loaded.copy_saved_data(&saved);
+        assert_eq!(loaded, pool);
+        assert_eq!(loaded.get(EngineID(3)).unwrap().name, "Ghost Train");
+    }
+
+    #[test]
+    fn test_engines_invalid() {
+        let (manager, pool) = sample();
+        let mut record = engine_to_record(pool.get(EngineID(0)).unwrap());
+        let company = record
+            .fields
+            .iter_mut()
+            .find(|(key, _)| key == "preview_company")
+            .unwrap();
+        company.1 = 0x20u8.into();
+        assert!(engine_from_record(0, &record, SaveLoadVersion::CURRENT.into()).is_err());
+
+        // Previews are cancelled in savegames that saved their ranking
+        let record = engine_to_record(pool.get(EngineID(3)).unwrap());
+        let engine = engine_from_record(3, &record, 178).unwrap();
+        assert_eq!(engine.preview_company, Owner::Invalid);
+        assert_eq!(engine.preview_asked, ALL_COMPANIES);
+        assert_eq!(engine.company_hidden, 0);
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_engines(&mut writer, &pool),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+        assert!(matches!(
+            save_engine_id_mappings(&mut writer, &manager),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
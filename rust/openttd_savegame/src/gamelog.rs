@@ -0,0 +1,512 @@
+/// Loading and saving of the GLOG chunk
+///
+/// Every action is a record with a list of changes. A change saves its type
+/// byte followed by one sub-struct per change type, of which only the one
+/// matching the type holds an element. Before SaveLoadVersion::RiffToArray
+/// the chunk is a single RIFF block in which the actions and the changes of
+/// each action are lists ended by a byte of 0xFF.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, missing, Record};
+use crate::version::{table_header, ListLength, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::error::CoreError;
+use openttd_core::gamelog::{GamelogAction, GamelogActionType, GamelogChange, GamelogChangeType};
+
+/// Length of the fixed revision text before SaveLoadVersion::StringGamelog
+/// (matches C++ GAMELOG_REVISION_LENGTH)
+const GAMELOG_REVISION_LENGTH: usize = 15;
+
+/// Byte that ends the lists of actions and of changes in RIFF chunks
+/// (matches C++ GLAT_NONE and GLCT_NONE)
+const GAMELOG_END: u8 = 0xFF;
+
+/// Sub-struct keys in the order of the C++ SlGamelogAction description
+const CHANGE_KEYS: [&str; 11] = [
+    "mode",
+    "revision",
+    "oldver",
+    "setting",
+    "grfadd",
+    "grfrem",
+    "grfcompat",
+    "grfparam",
+    "grfmove",
+    "grfbug",
+    "emergency",
+];
+
+fn md5sum(record: &Record, key: &str) -> Result<[u8; 16], CoreError> {
+    let mut md5sum = [0; 16];
+    let list = record.get_list(key).ok_or_else(|| missing(key))?;
+    if list.len() != md5sum.len() {
+        return Err(CoreError::InvalidData(format!(
+            "GLOG: '{}' has {} bytes",
+            key,
+            list.len()
+        )));
+    }
+    for (byte, value) in md5sum.iter_mut().zip(list) {
+        *byte = value.as_u64().ok_or_else(|| missing(key))? as u8;
+    }
+    Ok(md5sum)
+}
+
+fn revision_text(record: &Record) -> Result<String, CoreError> {
+    let key = "revision.text";
+    if let Some(text) = record.get_str(key) {
+        return Ok(text.into());
+    }
+    // A fixed size, NUL terminated array before SaveLoadVersion::StringGamelog
+    let bytes: Vec<u8> = record
+        .get_list(key)
+        .ok_or_else(|| missing(key))?
+        .iter()
+        .map_while(|v| v.as_u64().map(|b| b as u8).filter(|&b| b != 0))
+        .collect();
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn change_from_record(record: &Record) -> Result<GamelogChange, CoreError> {
+    let change_type = GamelogChangeType::try_from(int(record, "ct")? as u8)?;
+    let key = CHANGE_KEYS[change_type as usize];
+    let data = record.get_struct(key).ok_or_else(|| missing(key))?;
+
+    Ok(match change_type {
+        GamelogChangeType::Mode => GamelogChange::Mode {
+            mode: int(data, "mode.mode")? as u8,
+            landscape: int(data, "mode.landscape")? as u8,
+        },
+        GamelogChangeType::Revision => GamelogChange::Revision {
+            text: revision_text(data)?,
+            newgrf: int(data, "revision.newgrf")? as u32,
+            slver: int(data, "revision.slver")? as u16,
+            modified: int(data, "revision.modified")? as u8,
+        },
+        GamelogChangeType::OldVersion => GamelogChange::OldVersion {
+            savegame_type: int(data, "oldver.type")? as u32,
+            version: int(data, "oldver.version")? as u32,
+        },
+        GamelogChangeType::Setting => GamelogChange::Setting {
+            name: data
+                .get_str("setting.name")
+                .ok_or_else(|| missing("setting.name"))?
+                .into(),
+            old_value: int(data, "setting.oldval")? as i32,
+            new_value: int(data, "setting.newval")? as i32,
+        },
+        GamelogChangeType::GrfAdd => GamelogChange::GrfAdd {
+            grfid: int(data, "grfadd.grfid")? as u32,
+            md5sum: md5sum(data, "grfadd.md5sum")?,
+        },
+        GamelogChangeType::GrfRemove => GamelogChange::GrfRemove {
+            grfid: int(data, "grfrem.grfid")? as u32,
+        },
+        GamelogChangeType::GrfCompat => GamelogChange::GrfCompat {
+            grfid: int(data, "grfcompat.grfid")? as u32,
+            md5sum: md5sum(data, "grfcompat.md5sum")?,
+        },
+        GamelogChangeType::GrfParameter => GamelogChange::GrfParameter {
+            grfid: int(data, "grfparam.grfid")? as u32,
+        },
+        GamelogChangeType::GrfMove => GamelogChange::GrfMove {
+            grfid: int(data, "grfmove.grfid")? as u32,
+            offset: int(data, "grfmove.offset")? as i32,
+        },
+        GamelogChangeType::GrfBug => GamelogChange::GrfBug {
+            data: int(data, "grfbug.data")?,
+            grfid: int(data, "grfbug.grfid")? as u32,
+            bug: int(data, "grfbug.bug")? as u8,
+        },
+        GamelogChangeType::Emergency => GamelogChange::Emergency,
+    })
+}
+
+fn action_from_record(record: &Record) -> Result<GamelogAction, CoreError> {
+    Ok(GamelogAction {
+        action_type: GamelogActionType::try_from(int(record, "at")? as u8)?,
+        tick: int(record, "tick")?,
+        changes: record
+            .get_structs("action")
+            .map(change_from_record)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Load the gamelog actions from the GLOG chunk, oldest first
+pub fn load_gamelog(chunks: &[Chunk], version: u16) -> Result<Vec<GamelogAction>, SavegameError> {
+    if version < SaveLoadVersion::RiffToArray {
+        let riff_desc = vec![SaveLoad::structs("actions", gamelog_desc())
+            .length(ListLength::Terminated(GAMELOG_END))
+            .compat(gamelog_compat())];
+        let riff_compat = vec![SaveLoadCompat::var("actions")];
+        let records = chunk_records(chunks, b"GLOG", version, &riff_desc, &riff_compat)?;
+        return records
+            .iter()
+            .flat_map(|(_, record)| record.get_structs("actions"))
+            .map(|record| Ok(action_from_record(record)?))
+            .collect();
+    }
+    chunk_records(chunks, b"GLOG", version, &gamelog_desc(), &gamelog_compat())?
+        .iter()
+        .map(|(_, record)| Ok(action_from_record(record)?))
+        .collect()
+}
+
+/// Whether the sub-struct of a change type is saved, which the C++ handlers
+/// decide by the type byte at the start of the change
+fn change_count(record: &Record, change_type: GamelogChangeType) -> u64 {
+    (record.get_u64("ct") == Some(change_type as u64)) as u64
+}
+
+/// Fields of the C++ SlGamelogAction handler and its per-type sub-structs
+fn change_desc() -> Vec<SaveLoad> {
+    let md5sum = |key: &str| SaveLoad::array(DataType::U8, key, 16);
+    let desc = vec![
+        SaveLoad::var(DataType::U8, "ct"),
+        SaveLoad::structs(
+            "mode",
+            vec![
+                SaveLoad::var(DataType::U8, "mode.mode"),
+                SaveLoad::var(DataType::U8, "mode.landscape"),
+            ],
+        ),
+        SaveLoad::structs(
+            "revision",
+            vec![
+                SaveLoad::array(DataType::U8, "revision.text", GAMELOG_REVISION_LENGTH)
+                    .until(SaveLoadVersion::StringGamelog),
+                SaveLoad::var(DataType::String, "revision.text")
+                    .since(SaveLoadVersion::StringGamelog),
+                SaveLoad::var(DataType::U32, "revision.newgrf"),
+                SaveLoad::var(DataType::U16, "revision.slver"),
+                SaveLoad::var(DataType::U8, "revision.modified"),
+            ],
+        ),
+        SaveLoad::structs(
+            "oldver",
+            vec![
+                SaveLoad::var(DataType::U32, "oldver.type"),
+                SaveLoad::var(DataType::U32, "oldver.version"),
+            ],
+        ),
+        SaveLoad::structs(
+            "setting",
+            vec![
+                SaveLoad::var(DataType::String, "setting.name"),
+                SaveLoad::var(DataType::I32, "setting.oldval"),
+                SaveLoad::var(DataType::I32, "setting.newval"),
+            ],
+        ),
+        SaveLoad::structs(
+            "grfadd",
+            vec![
+                SaveLoad::var(DataType::U32, "grfadd.grfid"),
+                md5sum("grfadd.md5sum"),
+            ],
+        ),
+        SaveLoad::structs("grfrem", vec![SaveLoad::var(DataType::U32, "grfrem.grfid")]),
+        SaveLoad::structs(
+            "grfcompat",
+            vec![
+                SaveLoad::var(DataType::U32, "grfcompat.grfid"),
+                md5sum("grfcompat.md5sum"),
+            ],
+        ),
+        SaveLoad::structs(
+            "grfparam",
+            vec![SaveLoad::var(DataType::U32, "grfparam.grfid")],
+        ),
+        SaveLoad::structs(
+            "grfmove",
+            vec![
+                SaveLoad::var(DataType::U32, "grfmove.grfid"),
+                SaveLoad::var(DataType::I32, "grfmove.offset"),
+            ],
+        ),
+        SaveLoad::structs(
+            "grfbug",
+            vec![
+                SaveLoad::var(DataType::U64, "grfbug.data"),
+                SaveLoad::var(DataType::U32, "grfbug.grfid"),
+                SaveLoad::var(DataType::U8, "grfbug.bug"),
+            ],
+        ),
+        // C++ saves a bool, which is stored as a signed byte
+        SaveLoad::structs(
+            "emergency",
+            vec![SaveLoad::var(DataType::I8, "is_emergency_save")
+                .since(SaveLoadVersion::RiffToArray)],
+        ),
+    ];
+
+    // The sub-struct compat lists of C++ name every field in this order
+    let lengths: [fn(&Record) -> u64; 11] = [
+        |r| change_count(r, GamelogChangeType::Mode),
+        |r| change_count(r, GamelogChangeType::Revision),
+        |r| change_count(r, GamelogChangeType::OldVersion),
+        |r| change_count(r, GamelogChangeType::Setting),
+        |r| change_count(r, GamelogChangeType::GrfAdd),
+        |r| change_count(r, GamelogChangeType::GrfRemove),
+        |r| change_count(r, GamelogChangeType::GrfCompat),
+        |r| change_count(r, GamelogChangeType::GrfParameter),
+        |r| change_count(r, GamelogChangeType::GrfMove),
+        |r| change_count(r, GamelogChangeType::GrfBug),
+        |r| change_count(r, GamelogChangeType::Emergency),
+    ];
+    let mut desc = desc.into_iter();
+    desc.next()
+        .into_iter()
+        .chain(
+            desc.zip(lengths)
+                .map(|(sld, length)| sld.length(ListLength::Computed(length))),
+        )
+        .collect()
+}
+
+/// Field declarations of GLOG (matches C++ _gamelog_desc)
+fn gamelog_desc() -> Vec<SaveLoad> {
+    vec![
+        // Saved from RiffToArray on; the C++ handler reads it before that
+        SaveLoad::var(DataType::U8, "at"),
+        SaveLoad::var(DataType::U16, "tick").until(SaveLoadVersion::U64TickCounter),
+        SaveLoad::var(DataType::U64, "tick").since(SaveLoadVersion::U64TickCounter),
+        SaveLoad::structs("action", change_desc())
+            .until(SaveLoadVersion::RiffToArray)
+            .length(ListLength::Terminated(GAMELOG_END))
+            .compat(action_compat()),
+        SaveLoad::structs("action", change_desc())
+            .since(SaveLoadVersion::RiffToArray)
+            .compat(action_compat()),
+    ]
+}
+
+/// Order of the SlGamelogAction fields in savegames without a table header
+/// (matches C++ _gamelog_action_sl_compat)
+fn action_compat() -> Vec<SaveLoadCompat> {
+    std::iter::once("ct")
+        .chain(CHANGE_KEYS)
+        .map(SaveLoadCompat::var)
+        .collect()
+}
+
+/// Order of the GLOG fields in savegames without a table header
+/// (matches C++ _gamelog_sl_compat)
+fn gamelog_compat() -> Vec<SaveLoadCompat> {
+    ["at", "tick", "action"].map(SaveLoadCompat::var).into()
+}
+
+fn change_data(change: &GamelogChange, version: u16) -> Record {
+    let record = Record::default();
+    match change {
+        GamelogChange::Mode { mode, landscape } => record
+            .with("mode.mode", *mode)
+            .with("mode.landscape", *landscape),
+        GamelogChange::Revision {
+            text,
+            newgrf,
+            slver,
+            modified,
+        } => {
+            let record = if version < SaveLoadVersion::StringGamelog {
+                let mut bytes = text.as_bytes().to_vec();
+                bytes.resize(GAMELOG_REVISION_LENGTH, 0);
+                record.with("revision.text", bytes)
+            } else {
+                record.with("revision.text", text.as_str())
+            };
+            record
+                .with("revision.newgrf", *newgrf)
+                .with("revision.slver", *slver)
+                .with("revision.modified", *modified)
+        }
+        GamelogChange::OldVersion {
+            savegame_type,
+            version,
+        } => record
+            .with("oldver.type", *savegame_type)
+            .with("oldver.version", *version),
+        GamelogChange::Setting {
+            name,
+            old_value,
+            new_value,
+        } => record
+            .with("setting.name", name.as_str())
+            .with("setting.oldval", *old_value)
+            .with("setting.newval", *new_value),
+        GamelogChange::GrfAdd { grfid, md5sum } => record
+            .with("grfadd.grfid", *grfid)
+            .with("grfadd.md5sum", md5sum.to_vec()),
+        GamelogChange::GrfRemove { grfid } => record.with("grfrem.grfid", *grfid),
+        GamelogChange::GrfCompat { grfid, md5sum } => record
+            .with("grfcompat.grfid", *grfid)
+            .with("grfcompat.md5sum", md5sum.to_vec()),
+        GamelogChange::GrfParameter { grfid } => record.with("grfparam.grfid", *grfid),
+        GamelogChange::GrfMove { grfid, offset } => record
+            .with("grfmove.grfid", *grfid)
+            .with("grfmove.offset", *offset),
+        GamelogChange::GrfBug { data, grfid, bug } => record
+            .with("grfbug.data", *data)
+            .with("grfbug.grfid", *grfid)
+            .with("grfbug.bug", *bug),
+        GamelogChange::Emergency => record.with("is_emergency_save", 1i8),
+    }
+}
+
+fn change_to_record(change: &GamelogChange, version: u16) -> Record {
+    let change_type = change.change_type();
+    let mut record = Record::default().with("ct", change_type as u8);
+    for (i, key) in CHANGE_KEYS.iter().enumerate() {
+        let data = if i == change_type as usize {
+            vec![change_data(change, version)]
+        } else {
+            vec![]
+        };
+        record = record.with(key, data);
+    }
+    record
+}
+
+fn action_to_record(action: &GamelogAction, version: u16) -> Record {
+    let record = Record::default().with("at", action.action_type as u8);
+    let record = if version < SaveLoadVersion::U64TickCounter {
+        record.with("tick", action.tick as u16)
+    } else {
+        record.with("tick", action.tick)
+    };
+    let changes: Vec<Record> = action
+        .changes
+        .iter()
+        .map(|change| change_to_record(change, version))
+        .collect();
+    record.with("action", changes)
+}
+
+/// Write the GLOG chunk in the layout of the writer's savegame version
+pub fn save_gamelog(
+    writer: &mut SavegameWriter,
+    actions: &[GamelogAction],
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let records: Vec<(usize, Record)> = actions
+        .iter()
+        .enumerate()
+        .map(|(i, action)| (i, action_to_record(action, version)))
+        .collect();
+
+    writer.add_table_records(
+        b"GLOG",
+        ChunkType::Table,
+        &table_header(&gamelog_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::table::Value;
+    use crate::types::CompressionType;
+
+    fn sample_actions() -> Vec<GamelogAction> {
+        vec![
+            GamelogAction {
+                action_type: GamelogActionType::Start,
+                tick: 0,
+                changes: vec![
+                    GamelogChange::Revision {
+                        text: "14.1".into(),
+                        newgrf: 0x1E01_0000,
+                        slver: 308,
+                        modified: 0,
+                    },
+                    GamelogChange::Mode {
+                        mode: 1,
+                        landscape: 0,
+                    },
+                    GamelogChange::GrfAdd {
+                        grfid: 0x0403_2A4D,
+                        md5sum: [7; 16],
+                    },
+                ],
+            },
+            GamelogAction {
+                action_type: GamelogActionType::Setting,
+                tick: 70_000,
+                changes: vec![
+                    GamelogChange::Setting {
+                        name: "vehicle.road_side".into(),
+                        old_value: 0,
+                        new_value: -1,
+                    },
+                    GamelogChange::GrfMove {
+                        grfid: 0x0403_2A4D,
+                        offset: -1,
+                    },
+                    GamelogChange::GrfBug {
+                        data: 12,
+                        grfid: 0x0403_2A4D,
+                        bug: 0,
+                    },
+                ],
+            },
+            GamelogAction {
+                action_type: GamelogActionType::Emergency,
+                tick: 70_001,
+                changes: vec![GamelogChange::Emergency],
+            },
+        ]
+    }
+
+    fn round_trip(actions: &[GamelogAction], version: u16) -> Vec<GamelogAction> {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_gamelog(&mut writer, actions).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_gamelog(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_gamelog_round_trip() {
+        let actions = sample_actions();
+        assert_eq!(
+            round_trip(&actions, SaveLoadVersion::CURRENT.into()),
+            actions
+        );
+        // Fixed revision text before SaveLoadVersion::StringGamelog
+        assert_eq!(
+            round_trip(&actions, SaveLoadVersion::U64TickCounter.into()),
+            actions
+        );
+
+        // Ticks were 16 bit before SaveLoadVersion::U64TickCounter
+        let loaded = round_trip(&actions, SaveLoadVersion::TableChunks.into());
+        assert_eq!(loaded[1].tick, 70_000 & 0xFFFF);
+        assert_eq!(loaded[0].changes, actions[0].changes);
+    }
+
+    #[test]
+    fn test_gamelog_invalid_change() {
+        let version = SaveLoadVersion::CURRENT.into();
+        let mut record = change_to_record(&GamelogChange::Emergency, version);
+        record.fields[0].1 = Value::U8(11);
+        assert!(change_from_record(&record).is_err());
+
+        // The type byte says revision, but only the emergency struct is present
+        record.fields[0].1 = Value::U8(GamelogChangeType::Revision as u8);
+        assert!(change_from_record(&record).is_err());
+    }
+
+    #[test]
+    fn test_gamelog_unsupported_version() {
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_gamelog(&mut writer, &sample_actions()),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
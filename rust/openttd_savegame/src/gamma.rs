@@ -1,5 +1,6 @@
 /// Gamma encoding/decoding for variable-length integers
 /// Used in OpenTTD savegames for length fields and array indices
+use openttd_core::endian::BigEndianReader;
 use openttd_core::error::CoreError;
 
 /// Read a gamma-encoded value from a buffer
@@ -24,9 +25,14 @@ pub fn decode_gamma(buf: &[u8]) -> Result<(u64, usize), CoreError> {
     } else if first_byte & 0x10 == 0 {
         // 1110xxxx - 4 bytes
         4
-    } else {
+    } else if first_byte & 0x08 == 0 {
         // 11110xxx - 5 bytes (max)
         5
+    } else {
+        return Err(CoreError::InvalidData(format!(
+            "Unsupported gamma prefix {:#04x}",
+            first_byte
+        )));
     };
 
     if buf.len() < byte_count {
@@ -50,8 +56,8 @@ pub fn decode_gamma(buf: &[u8]) -> Result<(u64, usize), CoreError> {
             value | buf[3] as u64
         }
         5 => {
-            let mut value = ((first_byte & 0x07) as u64) << 32;
-            value |= (buf[1] as u64) << 24;
+            // The low bits of the prefix are unused; C++ SlReadSimpleGamma drops them
+            let mut value = (buf[1] as u64) << 24;
             value |= (buf[2] as u64) << 16;
             value |= (buf[3] as u64) << 8;
             value | buf[4] as u64
@@ -62,6 +68,13 @@ pub fn decode_gamma(buf: &[u8]) -> Result<(u64, usize), CoreError> {
     Ok((value, byte_count))
 }
 
+/// Read a gamma-encoded value from a reader, advancing past it
+pub fn read_gamma(reader: &mut BigEndianReader) -> Result<u64, CoreError> {
+    let (value, bytes_read) = decode_gamma(reader.rest())?;
+    reader.read_bytes(bytes_read)?;
+    Ok(value)
+}
+
 /// Encode a value using gamma encoding
 /// Returns the encoded bytes
 pub fn encode_gamma(value: u64) -> Vec<u8> {
@@ -166,4 +179,21 @@ mod tests {
             assert_eq!(decoded, value, "Failed round-trip for value {}", value);
         }
     }
+
+    #[test]
+    fn test_gamma_invalid() {
+        assert!(decode_gamma(&[]).is_err());
+        assert!(decode_gamma(&[0xC0, 0x01]).is_err());
+        assert!(decode_gamma(&[0xF8, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_gamma_five_bytes() {
+        let encoded = encode_gamma(0xFFFF_FFFF);
+        assert_eq!(encoded, [0xF0, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(decode_gamma(&encoded).unwrap(), (0xFFFF_FFFF, 5));
+
+        // Bits in the low part of the prefix do not add to the value
+        assert_eq!(decode_gamma(&[0xF7, 0, 0, 1, 0]).unwrap(), (256, 5));
+    }
 }
@@ -0,0 +1,179 @@
+/// Loading and saving of the GRPS chunk
+///
+/// Group numbers are saved since GroupNumbers; older groups use their index
+/// as number, as C++ does after loading.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, Record};
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::company::Livery;
+use openttd_core::error::CoreError;
+use openttd_core::group::{Group, GroupFlags, GroupPool};
+use openttd_core::types::{GroupID, Owner};
+use openttd_core::vehicle::VehicleType;
+
+fn group_from_record(index: usize, record: &Record, version: u16) -> Result<Group, CoreError> {
+    let mut group = Group::new(
+        GroupID(index as u16),
+        Owner::try_from(int(record, "owner")? as u8)?,
+        VehicleType::try_from(int(record, "vehicle_type")? as u8)?,
+    );
+    group.name = record.get_str("name").unwrap_or_default().into();
+    group.flags = GroupFlags::from_bits_retain(int(record, "flags")? as u8);
+    if version >= SaveLoadVersion::GroupLiveries {
+        group.livery = Livery {
+            in_use: int(record, "livery.in_use")? as u8,
+            colour1: int(record, "livery.colour1")? as u8,
+            colour2: int(record, "livery.colour2")? as u8,
+        };
+    }
+    if version >= SaveLoadVersion::V189 {
+        group.parent = GroupID(int(record, "parent")? as u16);
+    }
+    group.number = if version >= SaveLoadVersion::GroupNumbers {
+        int(record, "number")? as u16
+    } else {
+        index as u16
+    };
+    Ok(group)
+}
+
+/// Load the groups from the GRPS chunk
+pub fn load_groups(chunks: &[Chunk], version: u16) -> Result<GroupPool, SavegameError> {
+    let groups = chunk_records(chunks, b"GRPS", version, &group_desc(), &group_compat())?
+        .iter()
+        .map(|(index, record)| group_from_record(*index, record, version))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(GroupPool::from_groups(groups)?)
+}
+
+/// Field declarations of GRPS (matches C++ _group_desc)
+fn group_desc() -> Vec<SaveLoad> {
+    vec![
+        // Names of old savegames are string IDs, which are not kept
+        SaveLoad::var(DataType::U16, "name").until(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::String, "name").since(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::U8, "owner"),
+        SaveLoad::var(DataType::U8, "vehicle_type"),
+        SaveLoad::var(DataType::U8, "flags"),
+        SaveLoad::var(DataType::U8, "livery.in_use").since(SaveLoadVersion::GroupLiveries),
+        SaveLoad::var(DataType::U8, "livery.colour1").since(SaveLoadVersion::GroupLiveries),
+        SaveLoad::var(DataType::U8, "livery.colour2").since(SaveLoadVersion::GroupLiveries),
+        SaveLoad::var(DataType::U16, "parent").since(SaveLoadVersion::V189),
+        SaveLoad::var(DataType::U16, "number").since(SaveLoadVersion::GroupNumbers),
+    ]
+}
+
+/// Order of the GRPS fields in savegames without a table header
+/// (matches C++ _group_sl_compat)
+fn group_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::var("name"),
+        SaveLoadCompat::null(2, SaveLoadVersion::MinVersion, SaveLoadVersion::V164),
+        SaveLoadCompat::var("owner"),
+        SaveLoadCompat::var("vehicle_type"),
+        SaveLoadCompat::var("flags"),
+        SaveLoadCompat::var("livery.in_use"),
+        SaveLoadCompat::var("livery.colour1"),
+        SaveLoadCompat::var("livery.colour2"),
+        SaveLoadCompat::var("parent"),
+    ]
+}
+
+fn group_to_record(group: &Group) -> Record {
+    Record::default()
+        .with("name", group.name.as_str())
+        .with("owner", group.owner as u8)
+        .with("vehicle_type", group.vehicle_type as u8)
+        .with("flags", group.flags.bits())
+        .with("livery.in_use", group.livery.in_use)
+        .with("livery.colour1", group.livery.colour1)
+        .with("livery.colour2", group.livery.colour2)
+        .with("parent", group.parent.0)
+        .with("number", group.number)
+}
+
+/// Write the GRPS chunk in the layout of the writer's savegame version
+pub fn save_groups(writer: &mut SavegameWriter, pool: &GroupPool) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let records: Vec<(usize, Record)> = pool
+        .iter()
+        .map(|group| (group.index.0 as usize, group_to_record(group)))
+        .collect();
+
+    writer.add_table_records(
+        b"GRPS",
+        ChunkType::Table,
+        &table_header(&group_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+
+    fn sample_pool() -> GroupPool {
+        let mut top = Group::new(GroupID(0), Owner::Company0, VehicleType::Train);
+        top.name = "Express".into();
+        top.number = 3;
+        top.livery = Livery {
+            in_use: 1,
+            colour1: 4,
+            colour2: 8,
+        };
+        let mut sub = Group::new(GroupID(2), Owner::Company0, VehicleType::Train);
+        sub.parent = GroupID(0);
+        sub.flags = GroupFlags::REPLACE_PROTECTION;
+        sub.number = 1;
+        GroupPool::from_groups(vec![top, sub]).unwrap()
+    }
+
+    fn round_trip(pool: &GroupPool, version: u16) -> GroupPool {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_groups(&mut writer, pool).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_groups(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_groups_round_trip() {
+        let pool = sample_pool();
+        let loaded = round_trip(&pool, SaveLoadVersion::CURRENT.into());
+        assert_eq!(loaded, pool);
+        assert!(loaded
+            .get(GroupID(0))
+            .unwrap()
+            .children
+            .contains(&GroupID(2)));
+
+        // Groups are numbered by their index before GroupNumbers
+        let loaded = round_trip(&pool, SaveLoadVersion::TableChunks.into());
+        assert_eq!(loaded.get(GroupID(2)).unwrap().number, 2);
+    }
+
+    #[test]
+    fn test_groups_invalid() {
+        let mut record = group_to_record(sample_pool().get(GroupID(0)).unwrap());
+        let vehicle_type = record
+            .fields
+            .iter_mut()
+            .find(|(key, _)| key == "vehicle_type")
+            .unwrap();
+        vehicle_type.1 = 9u8.into();
+        assert!(group_from_record(0, &record, 336).is_err());
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_groups(&mut writer, &sample_pool()),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
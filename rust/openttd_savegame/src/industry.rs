@@ -0,0 +1,691 @@
+/// Loading and saving of the INDY chunk
+///
+/// Before version 315 the cargo slots were stored as parallel fixed-size arrays
+/// holding only this and last month's production; since then every slot is a
+/// sub-struct with its own history list.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, reference, to_reference, Record, Value};
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::error::CoreError;
+use openttd_core::industry::{
+    AcceptedHistory, Industry, IndustryCargo, ProducedHistory, INDUSTRY_NUM_INPUTS,
+    INDUSTRY_NUM_OUTPUTS,
+};
+use openttd_core::map::TileIndex;
+use openttd_core::types::{
+    CalendarDate, CargoType, EconomyDate, EconomyYear, IndustryID, Owner, StationID, TownID,
+    HISTORY_RECORDS, LAST_MONTH, THIS_MONTH,
+};
+
+/// Number of history records saved per produced slot before SaveLoadVersion::ProductionHistory
+const OLD_HISTORY_RECORDS: usize = 2;
+
+/// Number of input slots before SaveLoadVersion::ExtendIndustryCargoSlots
+const ORIGINAL_NUM_INPUTS: usize = 3;
+
+/// Number of output slots before SaveLoadVersion::ExtendIndustryCargoSlots
+const ORIGINAL_NUM_OUTPUTS: usize = 2;
+
+/// Read element `slot` of one of the old fixed-size cargo arrays; slots
+/// beyond the saved ones, and arrays older savegames lack, read as `None`
+fn slot(record: &Record, key: &str, slot: usize) -> Option<u64> {
+    record.get_list(key)?.get(slot).and_then(Value::as_u64)
+}
+
+/// Move the old cargo arrays into the slots, like the C++ LoadMoveAcceptsProduced;
+/// unsaved slots keep an invalid cargo
+fn load_old_slots(industry: &mut Industry, record: &Record) {
+    let cargo = |key, j| slot(record, key, j).map_or(CargoType::INVALID, |c| CargoType(c as u8));
+    let value = |key, j| slot(record, key, j).unwrap_or(0);
+
+    for j in 0..INDUSTRY_NUM_INPUTS {
+        // Before ExtendIndustryCargoSlots only the first input's date was saved
+        let last_accepted = match record.get_u64("last_cargo_accepted_at[0]") {
+            Some(date) if j == 0 => date,
+            _ => value("last_cargo_accepted_at", j),
+        };
+        industry.accepts_cargo[j] = IndustryCargo {
+            cargo: cargo("accepts_cargo", j),
+            waiting: value("incoming_cargo_waiting", j) as u16,
+            last_accepted: EconomyDate(last_accepted as i32),
+            ..IndustryCargo::default()
+        };
+    }
+    for j in 0..INDUSTRY_NUM_OUTPUTS {
+        industry.produced_cargo[j] = IndustryCargo {
+            cargo: cargo("produced_cargo", j),
+            waiting: value("produced_cargo_waiting", j) as u16,
+            production_rate: value("production_rate", j) as u8,
+            ..IndustryCargo::default()
+        };
+        let history = &mut industry.produced_history[j];
+        history[THIS_MONTH] = ProducedHistory {
+            production: value("this_month_production", j) as u16,
+            transported: value("this_month_transported", j) as u16,
+        };
+        history[LAST_MONTH] = ProducedHistory {
+            production: value("last_month_production", j) as u16,
+            transported: value("last_month_transported", j) as u16,
+        };
+    }
+}
+
+fn load_slots(industry: &mut Industry, record: &Record) -> Result<(), CoreError> {
+    let accepted: Vec<&Record> = record.get_structs("accepted").collect();
+    let produced: Vec<&Record> = record.get_structs("produced").collect();
+    if accepted.len() > INDUSTRY_NUM_INPUTS || produced.len() > INDUSTRY_NUM_OUTPUTS {
+        return Err(CoreError::InvalidData("INDY: too many cargo slots".into()));
+    }
+
+    for (j, a) in accepted.into_iter().enumerate() {
+        industry.accepts_cargo[j] = IndustryCargo {
+            cargo: CargoType(int(a, "cargo")? as u8),
+            waiting: int(a, "waiting")? as u16,
+            last_accepted: EconomyDate(int(a, "last_accepted")? as i32),
+            accumulated_waiting: a.get_u64("accumulated_waiting").unwrap_or(0) as u32,
+            ..IndustryCargo::default()
+        };
+        industry.accepted_history[j] = a
+            .get_structs("history")
+            .map(|h| {
+                Ok(AcceptedHistory {
+                    accepted: int(h, "accepted")? as u16,
+                    waiting: int(h, "waiting")? as u16,
+                })
+            })
+            .collect::<Result<_, CoreError>>()?;
+    }
+    for (j, p) in produced.into_iter().enumerate() {
+        industry.produced_cargo[j] = IndustryCargo {
+            cargo: CargoType(int(p, "cargo")? as u8),
+            waiting: int(p, "waiting")? as u16,
+            production_rate: int(p, "rate")? as u8,
+            ..IndustryCargo::default()
+        };
+        for (slot, h) in industry.produced_history[j]
+            .iter_mut()
+            .zip(p.get_structs("history"))
+        {
+            *slot = ProducedHistory {
+                production: int(h, "production")? as u16,
+                transported: int(h, "transported")? as u16,
+            };
+        }
+    }
+    Ok(())
+}
+
+/// Derive the valid history mask for saves that did not store it, like the C++ loader
+fn guess_valid_history(industry: &Industry, version: u16) -> u64 {
+    // The last month has always been recorded
+    let mut oldest_valid = LAST_MONTH;
+    if version >= SaveLoadVersion::ProductionHistory {
+        for (cargo, history) in industry
+            .produced_cargo
+            .iter()
+            .zip(&industry.produced_history)
+        {
+            if !cargo.cargo.is_valid() {
+                continue;
+            }
+            for (n, h) in history.iter().enumerate().skip(LAST_MONTH) {
+                if *h != ProducedHistory::default() {
+                    oldest_valid = oldest_valid.max(n);
+                }
+            }
+        }
+    }
+    (u64::MAX >> (64 - (oldest_valid + 1 - LAST_MONTH))) << LAST_MONTH
+}
+
+fn industry_from_record(
+    index: usize,
+    record: &Record,
+    version: u16,
+) -> Result<Industry, CoreError> {
+    let mut industry = Industry::new(
+        IndustryID(index as u16),
+        TileIndex(int(record, "location.tile")? as u32),
+        int(record, "type")? as u16,
+    );
+    industry.width = int(record, "location.w")? as u8;
+    industry.height = int(record, "location.h")? as u8;
+    industry.town = reference(record, "town")?.map_or(TownID::INVALID, |v| TownID(v as u16));
+    if version >= SaveLoadVersion::ServeNeutralIndustries {
+        industry.neutral_station = reference(record, "neutral_station")?
+            .map_or(StationID::INVALID, |v| StationID(v as u16));
+    }
+    industry.prod_level = int(record, "prod_level")? as u8;
+    industry.counter = int(record, "counter")? as u16;
+    industry.owner = Owner::try_from(int(record, "owner")? as u8)?;
+    industry.random_colour = int(record, "random_colour")? as u8;
+    industry.last_serviced_year = EconomyYear(int(record, "last_prod_year")? as i32);
+    industry.was_cargo_delivered = int(record, "was_cargo_delivered")? != 0;
+    if version >= SaveLoadVersion::V70 {
+        industry.founder = Owner::try_from(int(record, "founder")? as u8)?;
+        industry.construction_date = CalendarDate(int(record, "construction_date")? as i32);
+        industry.construction_type = int(record, "construction_type")? as u8;
+    }
+    if version >= SaveLoadVersion::V73 {
+        industry.selected_layout = int(record, "selected_layout")? as u8;
+    }
+    if version >= SaveLoadVersion::GsIndustryControl {
+        industry.control_flags = int(record, "ctlflags")? as u8;
+        industry.exclusive_supplier = Owner::try_from(int(record, "exclusive_supplier")? as u8)?;
+        industry.exclusive_consumer = Owner::try_from(int(record, "exclusive_consumer")? as u8)?;
+    } else {
+        // Anyone may deliver to and take from industries of older savegames
+        industry.exclusive_supplier = Owner::Invalid;
+        industry.exclusive_consumer = Owner::Invalid;
+    }
+    if version >= SaveLoadVersion::V161 {
+        industry.psa = reference(record, "psa")?;
+    }
+    if version >= SaveLoadVersion::V82 {
+        industry.random = int(record, "random")? as u16;
+    }
+    industry.text = record.get_str("text").unwrap_or_default().into();
+
+    for history in &mut industry.produced_history {
+        *history = vec![ProducedHistory::default(); HISTORY_RECORDS];
+    }
+    if version < SaveLoadVersion::IndustryCargoReorganise {
+        load_old_slots(&mut industry, record);
+    } else {
+        load_slots(&mut industry, record)?;
+    }
+
+    for (j, history) in industry.produced_history.iter().enumerate() {
+        industry.production.produced[j] = history[THIS_MONTH].production;
+        industry.production.transported[j] = history[THIS_MONTH].transported;
+        industry.last_month_production.produced[j] = history[LAST_MONTH].production;
+        industry.last_month_production.transported[j] = history[LAST_MONTH].transported;
+    }
+
+    industry.valid_history = if version < SaveLoadVersion::IndustryNumValidHistory {
+        guess_valid_history(&industry, version)
+    } else {
+        int(record, "valid_history")?
+    };
+
+    Ok(industry)
+}
+
+/// Load all industries from the INDY chunk
+pub fn load_industries(chunks: &[Chunk], version: u16) -> Result<Vec<Industry>, SavegameError> {
+    chunk_records(
+        chunks,
+        b"INDY",
+        version,
+        &industry_desc(),
+        &industry_compat(),
+    )?
+    .iter()
+    .map(|(index, record)| Ok(industry_from_record(*index, record, version)?))
+    .collect()
+}
+
+/// Field declarations of INDY (matches C++ _industry_desc)
+fn industry_desc() -> Vec<SaveLoad> {
+    let accepted = vec![
+        SaveLoad::var(DataType::U8, "cargo"),
+        SaveLoad::var(DataType::U16, "waiting"),
+        SaveLoad::var(DataType::I32, "last_accepted"),
+        SaveLoad::var(DataType::U32, "accumulated_waiting")
+            .since(SaveLoadVersion::IndustryAcceptedHistory),
+        SaveLoad::structs(
+            "history",
+            vec![
+                SaveLoad::var(DataType::U16, "accepted"),
+                SaveLoad::var(DataType::U16, "waiting"),
+            ],
+        )
+        .since(SaveLoadVersion::IndustryAcceptedHistory),
+    ];
+    let produced = vec![
+        SaveLoad::var(DataType::U8, "cargo"),
+        SaveLoad::var(DataType::U16, "waiting"),
+        SaveLoad::var(DataType::U8, "rate"),
+        SaveLoad::structs(
+            "history",
+            vec![
+                SaveLoad::var(DataType::U16, "production"),
+                SaveLoad::var(DataType::U16, "transported"),
+            ],
+        ),
+    ];
+
+    // The old cargo arrays come in the original and the extended size
+    let original = |data_type, key, length| {
+        SaveLoad::array(data_type, key, length).until(SaveLoadVersion::ExtendIndustryCargoSlots)
+    };
+    let extended = |data_type, key| {
+        SaveLoad::array(data_type, key, 16)
+            .since(SaveLoadVersion::ExtendIndustryCargoSlots)
+            .until(SaveLoadVersion::IndustryCargoReorganise)
+    };
+    let (inputs, outputs) = (ORIGINAL_NUM_INPUTS, ORIGINAL_NUM_OUTPUTS);
+
+    vec![
+        SaveLoad::var(DataType::U16, "location.tile").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "location.tile").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U8, "location.w"),
+        SaveLoad::var(DataType::U8, "location.h"),
+        SaveLoad::var(DataType::U16, "town").until(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, "town").since(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, "neutral_station")
+            .since(SaveLoadVersion::ServeNeutralIndustries),
+        original(DataType::U8, "produced_cargo", outputs).since(SaveLoadVersion::V78),
+        extended(DataType::U8, "produced_cargo"),
+        original(DataType::U16, "incoming_cargo_waiting", inputs).since(SaveLoadVersion::V70),
+        extended(DataType::U16, "incoming_cargo_waiting"),
+        original(DataType::U16, "produced_cargo_waiting", outputs),
+        extended(DataType::U16, "produced_cargo_waiting"),
+        original(DataType::U8, "production_rate", outputs),
+        extended(DataType::U8, "production_rate"),
+        original(DataType::U8, "accepts_cargo", inputs).since(SaveLoadVersion::V78),
+        extended(DataType::U8, "accepts_cargo"),
+        SaveLoad::var(DataType::U8, "prod_level"),
+        original(DataType::U16, "this_month_production", outputs),
+        extended(DataType::U16, "this_month_production"),
+        original(DataType::U16, "this_month_transported", outputs),
+        extended(DataType::U16, "this_month_transported"),
+        // Only in table headers; older savegames skip it as null bytes
+        extended(DataType::U8, "last_month_pct_transported"),
+        original(DataType::U16, "last_month_production", outputs),
+        extended(DataType::U16, "last_month_production"),
+        original(DataType::U16, "last_month_transported", outputs),
+        extended(DataType::U16, "last_month_transported"),
+        SaveLoad::var(DataType::U16, "counter"),
+        SaveLoad::var(DataType::U8, "type"),
+        SaveLoad::var(DataType::U8, "owner"),
+        SaveLoad::var(DataType::U8, "random_colour"),
+        SaveLoad::var(DataType::U8, "last_prod_year").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "last_prod_year").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::U8, "was_cargo_delivered"),
+        SaveLoad::var(DataType::U8, "ctlflags").since(SaveLoadVersion::GsIndustryControl),
+        SaveLoad::var(DataType::U8, "founder").since(SaveLoadVersion::V70),
+        SaveLoad::var(DataType::I32, "construction_date").since(SaveLoadVersion::V70),
+        SaveLoad::var(DataType::U8, "construction_type").since(SaveLoadVersion::V70),
+        SaveLoad::var(DataType::I32, "last_cargo_accepted_at[0]")
+            .since(SaveLoadVersion::V70)
+            .until(SaveLoadVersion::ExtendIndustryCargoSlots),
+        extended(DataType::I32, "last_cargo_accepted_at"),
+        SaveLoad::var(DataType::U8, "selected_layout").since(SaveLoadVersion::V73),
+        SaveLoad::var(DataType::U8, "exclusive_supplier").since(SaveLoadVersion::GsIndustryControl),
+        SaveLoad::var(DataType::U8, "exclusive_consumer").since(SaveLoadVersion::GsIndustryControl),
+        SaveLoad::array(DataType::U32, "storage", 16)
+            .since(SaveLoadVersion::V76)
+            .until(SaveLoadVersion::V161),
+        SaveLoad::var(DataType::U32, "psa").since(SaveLoadVersion::V161),
+        SaveLoad::var(DataType::U16, "random").since(SaveLoadVersion::V82),
+        SaveLoad::var(DataType::String, "text").since(SaveLoadVersion::IndustryText),
+        SaveLoad::var(DataType::U64, "valid_history")
+            .since(SaveLoadVersion::IndustryNumValidHistory),
+        SaveLoad::structs("accepted", accepted).since(SaveLoadVersion::IndustryCargoReorganise),
+        SaveLoad::structs("produced", produced).since(SaveLoadVersion::IndustryCargoReorganise),
+    ]
+}
+
+/// Order of the INDY fields in savegames without a table header
+/// (matches C++ _industry_sl_compat)
+fn industry_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::var("location.tile"),
+        SaveLoadCompat::var("location.w"),
+        SaveLoadCompat::var("location.h"),
+        SaveLoadCompat::var("town"),
+        SaveLoadCompat::var("neutral_station"),
+        SaveLoadCompat::null(2, SaveLoadVersion::MinVersion, SaveLoadVersion::V61),
+        SaveLoadCompat::var("produced_cargo"),
+        SaveLoadCompat::var("incoming_cargo_waiting"),
+        SaveLoadCompat::var("produced_cargo_waiting"),
+        SaveLoadCompat::var("production_rate"),
+        SaveLoadCompat::null(3, SaveLoadVersion::MinVersion, SaveLoadVersion::V61),
+        SaveLoadCompat::var("accepts_cargo"),
+        SaveLoadCompat::var("prod_level"),
+        SaveLoadCompat::var("this_month_production"),
+        SaveLoadCompat::var("this_month_transported"),
+        SaveLoadCompat::null(
+            ORIGINAL_NUM_OUTPUTS,
+            SaveLoadVersion::MinVersion,
+            SaveLoadVersion::ExtendIndustryCargoSlots,
+        ),
+        SaveLoadCompat::null(
+            INDUSTRY_NUM_OUTPUTS,
+            SaveLoadVersion::ExtendIndustryCargoSlots,
+            SaveLoadVersion::IndustryCargoReorganise,
+        ),
+        SaveLoadCompat::var("last_month_production"),
+        SaveLoadCompat::var("last_month_transported"),
+        SaveLoadCompat::var("counter"),
+        SaveLoadCompat::var("type"),
+        SaveLoadCompat::var("owner"),
+        SaveLoadCompat::var("random_colour"),
+        SaveLoadCompat::var("last_prod_year"),
+        SaveLoadCompat::var("was_cargo_delivered"),
+        SaveLoadCompat::var("ctlflags"),
+        SaveLoadCompat::var("founder"),
+        SaveLoadCompat::var("construction_date"),
+        SaveLoadCompat::var("construction_type"),
+        SaveLoadCompat::var("last_cargo_accepted_at[0]"),
+        SaveLoadCompat::var("last_cargo_accepted_at"),
+        SaveLoadCompat::var("selected_layout"),
+        SaveLoadCompat::var("exclusive_supplier"),
+        SaveLoadCompat::var("exclusive_consumer"),
+        SaveLoadCompat::var("storage"),
+        SaveLoadCompat::var("psa"),
+        SaveLoadCompat::null(1, SaveLoadVersion::V82, SaveLoadVersion::V197),
+        SaveLoadCompat::var("random"),
+        SaveLoadCompat::var("text"),
+        SaveLoadCompat::null(32, SaveLoadVersion::V2, SaveLoadVersion::V144),
+    ]
+}
+
+/// Production record `month` of output `slot`; the first two months come from
+/// `production` and `last_month_production`
+fn produced_month(industry: &Industry, slot: usize, month: usize) -> ProducedHistory {
+    match month {
+        THIS_MONTH => ProducedHistory {
+            production: industry.production.produced[slot],
+            transported: industry.production.transported[slot],
+        },
+        LAST_MONTH => ProducedHistory {
+            production: industry.last_month_production.produced[slot],
+            transported: industry.last_month_production.transported[slot],
+        },
+        _ => industry.produced_history[slot]
+            .get(month)
+            .copied()
+            .unwrap_or_default(),
+    }
+}
+
+/// Number of slots the C++ game allocates: up to the last valid cargo
+fn used_slots(slots: &[IndustryCargo]) -> usize {
+    slots
+        .iter()
+        .rposition(|s| s.cargo.is_valid())
+        .map_or(0, |i| i + 1)
+}
+
+fn old_slot_fields(mut record: Record, industry: &Industry) -> Record {
+    let inputs = &industry.accepts_cargo;
+    let outputs = &industry.produced_cargo;
+    let month = |m: usize| -> Vec<ProducedHistory> {
+        (0..INDUSTRY_NUM_OUTPUTS)
+            .map(|j| produced_month(industry, j, m))
+            .collect()
+    };
+    let (this_month, last_month) = (month(THIS_MONTH), month(LAST_MONTH));
+
+    let cargo = |slots: &[IndustryCargo]| -> Vec<u8> { slots.iter().map(|c| c.cargo.0).collect() };
+    let waiting =
+        |slots: &[IndustryCargo]| -> Vec<u16> { slots.iter().map(|c| c.waiting).collect() };
+    let production =
+        |h: &[ProducedHistory]| -> Vec<u16> { h.iter().map(|h| h.production).collect() };
+    let transported =
+        |h: &[ProducedHistory]| -> Vec<u16> { h.iter().map(|h| h.transported).collect() };
+    let rate: Vec<u8> = outputs.iter().map(|c| c.production_rate).collect();
+    let pct: Vec<u8> = last_month
+        .iter()
+        .map(ProducedHistory::pct_transported)
+        .collect();
+
+    record = record
+        .with("produced_cargo", cargo(outputs))
+        .with("incoming_cargo_waiting", waiting(inputs))
+        .with("produced_cargo_waiting", waiting(outputs))
+        .with("production_rate", rate)
+        .with("accepts_cargo", cargo(inputs))
+        .with("this_month_production", production(&this_month))
+        .with("this_month_transported", transported(&this_month))
+        .with("last_month_pct_transported", pct)
+        .with("last_month_production", production(&last_month))
+        .with("last_month_transported", transported(&last_month));
+    let last_accepted: Vec<i32> = inputs.iter().map(|c| c.last_accepted.0).collect();
+    record.with("last_cargo_accepted_at", last_accepted)
+}
+
+fn accepted_records(industry: &Industry, version: u16) -> Vec<Record> {
+    let slots = &industry.accepts_cargo[..used_slots(&industry.accepts_cargo)];
+    slots
+        .iter()
+        .zip(&industry.accepted_history)
+        .map(|(a, history)| {
+            let record = Record::default()
+                .with("cargo", a.cargo.0)
+                .with("waiting", a.waiting)
+                .with("last_accepted", a.last_accepted.0);
+            if version < SaveLoadVersion::IndustryAcceptedHistory {
+                return record;
+            }
+            let history: Vec<Record> = if a.cargo.is_valid() {
+                history
+                    .iter()
+                    .map(|h| {
+                        Record::default()
+                            .with("accepted", h.accepted)
+                            .with("waiting", h.waiting)
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            record
+                .with("accumulated_waiting", a.accumulated_waiting)
+                .with("history", history)
+        })
+        .collect()
+}
+
+fn produced_records(industry: &Industry, version: u16) -> Vec<Record> {
+    let records = if version < SaveLoadVersion::ProductionHistory {
+        OLD_HISTORY_RECORDS
+    } else {
+        HISTORY_RECORDS
+    };
+    let slots = &industry.produced_cargo[..used_slots(&industry.produced_cargo)];
+    slots
+        .iter()
+        .enumerate()
+        .map(|(j, p)| {
+            // Like the C++ saver, unused slots have no history
+            let history: Vec<Record> = if p.cargo.is_valid() {
+                (0..records)
+                    .map(|m| {
+                        let h = produced_month(industry, j, m);
+                        Record::default()
+                            .with("production", h.production)
+                            .with("transported", h.transported)
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            Record::default()
+                .with("cargo", p.cargo.0)
+                .with("waiting", p.waiting)
+                .with("rate", p.production_rate)
+                .with("history", history)
+        })
+        .collect()
+}
+
+fn industry_to_record(industry: &Industry, version: u16) -> Record {
+    let old_slots = version < SaveLoadVersion::IndustryCargoReorganise;
+    let town = industry.town.is_valid().then_some(industry.town.0 as u32);
+    let neutral_station = industry
+        .neutral_station
+        .is_valid()
+        .then_some(industry.neutral_station.0 as u32);
+
+    let mut record = Record::default()
+        .with("location.tile", industry.location.0)
+        .with("location.w", industry.width)
+        .with("location.h", industry.height)
+        .with("town", to_reference(town))
+        .with("neutral_station", to_reference(neutral_station))
+        .with("prod_level", industry.prod_level)
+        .with("counter", industry.counter)
+        .with("type", industry.industry_type as u8)
+        .with("owner", industry.owner as u8)
+        .with("random_colour", industry.random_colour)
+        .with("last_prod_year", industry.last_serviced_year.0)
+        .with("was_cargo_delivered", industry.was_cargo_delivered as u8)
+        .with("ctlflags", industry.control_flags)
+        .with("founder", industry.founder as u8)
+        .with("construction_date", industry.construction_date.0)
+        .with("construction_type", industry.construction_type)
+        .with("selected_layout", industry.selected_layout)
+        .with("exclusive_supplier", industry.exclusive_supplier as u8)
+        .with("exclusive_consumer", industry.exclusive_consumer as u8)
+        .with("psa", to_reference(industry.psa))
+        .with("random", industry.random)
+        .with("text", industry.text.as_str());
+    if old_slots {
+        record = old_slot_fields(record, industry);
+    }
+    if version >= SaveLoadVersion::IndustryNumValidHistory {
+        record = record.with("valid_history", industry.valid_history);
+    }
+    if !old_slots {
+        record = record
+            .with("accepted", accepted_records(industry, version))
+            .with("produced", produced_records(industry, version));
+    }
+    record
+}
+
+/// Write the INDY chunk in the layout of the writer's savegame version
+pub fn save_industries(
+    writer: &mut SavegameWriter,
+    industries: &[Industry],
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let header = table_header(&industry_desc(), version);
+    let mut industries: Vec<&Industry> = industries.iter().collect();
+    industries.sort_by_key(|i| i.index.0);
+    let records: Vec<(usize, Record)> = industries
+        .iter()
+        .map(|i| (i.index.0 as usize, industry_to_record(i, version)))
+        .collect();
+
+    writer.add_table_records(b"INDY", ChunkType::Table, &header, &records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+
+    fn sample_industry(index: u16) -> Industry {
+        let mut industry = Industry::new(IndustryID(index), TileIndex(800), 4);
+        industry.width = 3;
+        industry.height = 5;
+        industry.town = TownID(1);
+        industry.prod_level = 16;
+        industry.founder = Owner::Company0;
+        industry.construction_date = CalendarDate(712_000);
+        industry.exclusive_supplier = Owner::Company1;
+        industry.exclusive_consumer = Owner::Invalid;
+        industry.text = "Closing soon".into();
+
+        industry.accepts_cargo[0] = IndustryCargo {
+            cargo: CargoType(3),
+            waiting: 20,
+            last_accepted: EconomyDate(711_900),
+            ..IndustryCargo::default()
+        };
+        industry.produced_cargo[0] = IndustryCargo {
+            cargo: CargoType(5),
+            waiting: 7,
+            production_rate: 9,
+            ..IndustryCargo::default()
+        };
+        industry.production.produced[0] = 72;
+        industry.production.transported[0] = 54;
+        industry.last_month_production.produced[0] = 80;
+        industry.last_month_production.transported[0] = 40;
+        for history in &mut industry.produced_history {
+            *history = vec![ProducedHistory::default(); HISTORY_RECORDS];
+        }
+        industry.produced_history[0][THIS_MONTH] = ProducedHistory {
+            production: 72,
+            transported: 54,
+        };
+        industry.produced_history[0][LAST_MONTH] = ProducedHistory {
+            production: 80,
+            transported: 40,
+        };
+        industry.valid_history = 1 << LAST_MONTH;
+        industry
+    }
+
+    fn round_trip(industries: &[Industry], version: u16) -> Vec<Industry> {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_industries(&mut writer, industries).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_industries(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_industries_round_trip() {
+        let industries = vec![sample_industry(2), sample_industry(0)];
+        for version in [
+            SaveLoadVersion::TableChunks,
+            SaveLoadVersion::IndustryCargoReorganise,
+            SaveLoadVersion::ProductionHistory,
+        ]
+        .map(u16::from)
+        {
+            let loaded = round_trip(&industries, version);
+            assert_eq!(loaded.len(), 2);
+            assert_eq!(loaded[1].index, IndustryID(2));
+            assert_eq!(loaded[0], industries[1]);
+            assert_eq!(loaded[0].get_transport_percentage(), 75);
+        }
+    }
+
+    #[test]
+    fn test_industries_history_round_trip() {
+        let mut industry = sample_industry(0);
+        industry.produced_history[0][13] = ProducedHistory {
+            production: 30,
+            transported: 1,
+        };
+        industry.accepted_history[0] = vec![AcceptedHistory {
+            accepted: 12,
+            waiting: 4,
+        }];
+        industry.accepts_cargo[0].accumulated_waiting = 99;
+        industry.valid_history = 0x3FFE;
+
+        let loaded = round_trip(
+            &[industry.clone()],
+            SaveLoadVersion::IndustryAcceptedHistory.into(),
+        );
+        assert_eq!(loaded[0], industry);
+
+        // Without a saved mask the valid history reaches back to the oldest non-empty record
+        let loaded = round_trip(&[industry], SaveLoadVersion::ProductionHistory.into());
+        assert_eq!(loaded[0].valid_history, 0x3FFE);
+        assert!(loaded[0].accepted_history[0].is_empty());
+    }
+
+    #[test]
+    fn test_industries_unsupported_version() {
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_industries(&mut writer, &[sample_industry(0)]),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
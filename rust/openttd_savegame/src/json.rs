@@ -0,0 +1,281 @@
+/// JSON export and import of savegames
+///
+/// A `SavegameDocument` holds the decoded map, companies, towns, stations,
+/// industries and vehicles next to the list of chunks in savegame order.
+/// Chunks without a decoder keep their bytes as a hex string, so a document
+/// can be written back to a complete savegame after the decoded parts were
+/// edited.
+///
+/// The decoders only write table chunks. Documents of older savegames keep
+/// the bytes of every chunk and write them back as they were loaded, so
+/// their decoded parts are read-only.
+use crate::savegame::{Chunk, SavegameError, SavegameReader, SavegameWriter};
+use crate::types::SavegameFormat;
+use crate::version::SaveLoadVersion;
+use crate::{company, industry, map, station, town, vehicle};
+use openttd_core::company::Company;
+use openttd_core::error::CoreError;
+use openttd_core::industry::Industry;
+use openttd_core::map::Map;
+use openttd_core::station::Station;
+use openttd_core::town::Town;
+use openttd_core::vehicle::Vehicle;
+use serde::{Deserialize, Serialize};
+
+/// Map chunks written by `map::save_map`, in savegame order
+const MAP_TAGS: [&str; 11] = [
+    "MAPS", "MAPT", "MAPH", "MAPO", "MAP2", "M3LO", "M3HI", "MAP5", "MAPE", "MAP7", "MAP8",
+];
+/// Chunks decoded into the typed fields of a document, besides the map
+const DECODED_TAGS: [&str; 5] = ["PLYR", "CITY", "STNN", "INDY", "VEHS"];
+
+/// A chunk of the savegame; `data` is only present for chunks that are not decoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentChunk {
+    pub tag: String,
+    /// Hex encoded chunk type and contents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+/// A decoded savegame that round-trips through JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavegameDocument {
+    pub version: u16,
+    pub map: Map,
+    pub companies: Vec<Company>,
+    pub towns: Vec<Town>,
+    pub stations: Vec<Station>,
+    pub industries: Vec<Industry>,
+    pub vehicles: Vec<Vehicle>,
+    pub chunks: Vec<DocumentChunk>,
+}
+
+fn is_decoded(tag: &str) -> bool {
+    MAP_TAGS.contains(&tag) || DECODED_TAGS.contains(&tag)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(tag: &str, hex: &str) -> Result<Vec<u8>, CoreError> {
+    let invalid = || CoreError::InvalidData(format!("{}: invalid hex data", tag));
+    if !hex.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+/// A chunk from its tag and the hex data of a `DocumentChunk`
+fn raw_chunk(tag: &str, hex: &str) -> Result<Chunk, SavegameError> {
+    let mut bytes = tag.as_bytes().to_vec();
+    bytes.extend(from_hex(tag, hex)?);
+    Chunk::from_bytes(&bytes)
+}
+
+impl SavegameDocument {
+    /// Decode the chunks of a savegame
+    pub fn from_chunks(chunks: &[Chunk], version: u16) -> Result<Self, SavegameError> {
+        let read_only = version < SaveLoadVersion::TableChunks;
+        let chunks_list = chunks
+            .iter()
+            .map(|chunk| {
+                let data = if is_decoded(&chunk.tag) && !read_only {
+                    None
+                } else {
+                    // Leave out the tag, which is saved next to the data
+                    Some(to_hex(&chunk.to_bytes()?[4..]))
+                };
+                Ok(DocumentChunk {
+                    tag: chunk.tag.clone(),
+                    data,
+                })
+            })
+            .collect::<Result<_, SavegameError>>()?;
+
+        Ok(Self {
+            version,
+            map: map::load_map(chunks, version)?,
+            companies: company::load_companies(chunks, version)?,
+            towns: town::load_towns(chunks, version)?,
+            stations: station::load_stations(chunks, version)?,
+            industries: industry::load_industries(chunks, version)?,
+            vehicles: vehicle::load_vehicles(chunks, version)?,
+            chunks: chunks_list,
+        })
+    }
+
+    /// Decode a complete savegame file
+    pub fn from_savegame(data: &[u8]) -> Result<Self, SavegameError> {
+        let reader = SavegameReader::new(data)?;
+        Self::from_chunks(&reader.read_chunks()?, reader.header().version)
+    }
+
+    /// Write all chunks in document order
+    pub fn write(&self, writer: &mut SavegameWriter) -> Result<(), SavegameError> {
+        if writer.version() != self.version {
+            return Err(SavegameError::UnsupportedVersion(writer.version()));
+        }
+        self.check_map()?;
+        if self.version < SaveLoadVersion::TableChunks {
+            return self.write_read_only(writer);
+        }
+
+        for chunk in &self.chunks {
+            match (chunk.tag.as_str(), &chunk.data) {
+                (tag, Some(hex)) => writer.add_chunk(&raw_chunk(tag, hex)?)?,
+                ("MAPS", None) => map::save_map(writer, &self.map)?,
+                // Written together with MAPS
+                (tag, None) if MAP_TAGS.contains(&tag) => {}
+                ("PLYR", None) => company::save_companies(writer, &self.companies)?,
+                ("CITY", None) => town::save_towns(writer, &self.towns)?,
+                ("STNN", None) => station::save_stations(writer, &self.stations)?,
+                ("INDY", None) => industry::save_industries(writer, &self.industries)?,
+                ("VEHS", None) => vehicle::save_vehicles(writer, &self.vehicles)?,
+                (tag, None) => {
+                    return Err(
+                        CoreError::InvalidData(format!("{}: chunk has no data", tag)).into(),
+                    )
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the chunks of a savegame before table chunks back unchanged,
+    /// after checking that the decoded parts were not edited
+    fn write_read_only(&self, writer: &mut SavegameWriter) -> Result<(), SavegameError> {
+        let chunks = self
+            .chunks
+            .iter()
+            .map(|chunk| match &chunk.data {
+                Some(hex) => raw_chunk(&chunk.tag, hex),
+                None => {
+                    Err(CoreError::InvalidData(format!("{}: chunk has no data", chunk.tag)).into())
+                }
+            })
+            .collect::<Result<Vec<_>, SavegameError>>()?;
+
+        let loaded = Self::from_chunks(&chunks, self.version)?;
+        if loaded.map != self.map
+            || loaded.companies != self.companies
+            || loaded.towns != self.towns
+            || loaded.stations != self.stations
+            || loaded.industries != self.industries
+            || loaded.vehicles != self.vehicles
+        {
+            return Err(CoreError::InvalidData(format!(
+                "decoded chunks of savegame version {} can not be edited",
+                self.version
+            ))
+            .into());
+        }
+
+        for chunk in &chunks {
+            writer.add_chunk(chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Encode the document as a complete savegame file
+    pub fn to_savegame(&self, format: impl Into<SavegameFormat>) -> Result<Vec<u8>, SavegameError> {
+        let mut writer = SavegameWriter::with_format(self.version, format.into());
+        self.write(&mut writer)?;
+        writer.finalize()
+    }
+
+    pub fn to_json(&self) -> Result<String, SavegameError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SavegameError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// The map dimensions are saved with the tiles; reject edits that break them
+    fn check_map(&self) -> Result<(), CoreError> {
+        let map = &self.map;
+        let expected = Map::new(map.log_x, map.log_y).map_err(CoreError::InvalidData)?;
+        if (map.size_x, map.size_y) != (expected.size_x, expected.size_y)
+            || map.tiles.len() != expected.tiles.len()
+        {
+            return Err(CoreError::InvalidData(format!(
+                "map of {}x{} tiles has {} tiles",
+                expected.size_x,
+                expected.size_y,
+                map.tiles.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CompressionType;
+    use openttd_core::town::Town;
+    use openttd_core::types::TownID;
+
+    fn sample_document() -> SavegameDocument {
+        let mut writer =
+            SavegameWriter::new(SaveLoadVersion::TableChunks.into(), CompressionType::None);
+        writer.add_riff_chunk(b"DATE", &[1, 2, 3]).unwrap();
+        map::save_map(&mut writer, &Map::new(6, 6).unwrap()).unwrap();
+        town::save_towns(&mut writer, &[Town::new(TownID(2), Default::default())]).unwrap();
+        let data = writer.finalize().unwrap();
+        SavegameDocument::from_savegame(&data).unwrap()
+    }
+
+    #[test]
+    fn test_document_chunks() {
+        let document = sample_document();
+        let tags: Vec<&str> = document.chunks.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(tags[0], "DATE");
+        assert_eq!(tags[1..12], MAP_TAGS);
+        assert_eq!(tags[12], "CITY");
+        // Chunk type 0 (RIFF), 24-bit length and the contents
+        assert_eq!(document.chunks[0].data.as_deref(), Some("00000003010203"));
+        assert!(document.chunks[1].data.is_none());
+        assert_eq!(document.towns.len(), 1);
+        assert_eq!(document.map.tiles.len(), 64 * 64);
+    }
+
+    #[test]
+    fn test_document_json_round_trip() {
+        let document = sample_document();
+        let json = document.to_json().unwrap();
+        let mut loaded = SavegameDocument::from_json(&json).unwrap();
+        loaded.towns[0].text = "Edited".into();
+
+        let data = loaded.to_savegame(CompressionType::Zlib).unwrap();
+        let reloaded = SavegameDocument::from_savegame(&data).unwrap();
+        assert_eq!(reloaded.chunks, document.chunks);
+        assert_eq!(reloaded.towns[0].text, "Edited");
+        assert_eq!(reloaded.towns[0].index, TownID(2));
+    }
+
+    #[test]
+    fn test_document_rejects_invalid_edits() {
+        let mut document = sample_document();
+        document.chunks[0].data = Some("0".into());
+        assert!(document.to_savegame(CompressionType::None).is_err());
+
+        let mut document = sample_document();
+        document.map.tiles.pop();
+        assert!(document.to_savegame(CompressionType::None).is_err());
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        writer.add_riff_chunk(b"DATE", &[1]).unwrap();
+        let data = writer.finalize().unwrap();
+        assert!(SavegameDocument::from_savegame(&data).is_err());
+    }
+}
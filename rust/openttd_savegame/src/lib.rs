@@ -1,10 +1,38 @@
+pub mod cargopacket;
 pub mod chunk;
+pub mod company;
+pub mod diff;
+pub mod engine;
+pub mod gamelog;
 pub mod gamma;
+pub mod group;
 pub mod header;
+pub mod industry;
+pub mod json;
+pub mod linkgraph;
+pub mod lzo;
+pub mod map;
+pub mod newgrf;
+pub mod order;
 pub mod savegame;
+pub mod signs;
+pub mod station;
+pub mod stream;
+pub mod subsidy;
+pub mod table;
+pub mod town;
 pub mod types;
+pub mod validate;
+pub mod vehicle;
+pub mod version;
 
 // Re-export main types
+pub use diff::{diff_savegames, Difference};
 pub use header::{SavegameError as HeaderError, SavegameHeader};
+pub use json::SavegameDocument;
 pub use savegame::{Chunk, ChunkData, SavegameReader, SavegameWriter};
-pub use types::CompressionType;
+pub use stream::{ChunkInfo, SavegameStream};
+pub use table::{Record, Value};
+pub use types::{CompressionType, SavegameFormat};
+pub use validate::{validate, Violation};
+pub use version::SaveLoadVersion;
@@ -0,0 +1,650 @@
+/// Loading and saving of the LGRP, LGRJ and LGRS chunks
+///
+/// Before SaveLoadVersion::LinkgraphEdges the edges of a node were saved as
+/// a sparse matrix: a chain through `next_edge` that starts at the node's
+/// own, empty entry. Jobs are saved with a copy of the game's link graph
+/// settings in front of their fields.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, missing, signed, Record};
+use crate::version::{table_header, ListLength, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::error::CoreError;
+use openttd_core::linkgraph::{
+    DistributionType, LinkGraph, LinkGraphEdge, LinkGraphID, LinkGraphNode, LinkGraphSettings,
+    NodeID, INVALID_DATE, INVALID_NODE,
+};
+use openttd_core::linkgraphjob::LinkGraphJob;
+use openttd_core::linkgraphschedule::LinkGraphSchedule;
+use openttd_core::map::TileIndex;
+use openttd_core::types::{CargoType, EconomyDate, StationID};
+
+fn date(record: &Record, key: &str) -> Result<EconomyDate, CoreError> {
+    Ok(EconomyDate(signed(record, key)? as i32))
+}
+
+fn edge_from_record(
+    record: &Record,
+    dest_node: NodeID,
+    version: u16,
+) -> Result<LinkGraphEdge, CoreError> {
+    Ok(LinkGraphEdge {
+        capacity: int(record, "capacity")? as u32,
+        usage: int(record, "usage")? as u32,
+        travel_time_sum: if version >= SaveLoadVersion::LinkgraphTravelTime {
+            int(record, "travel_time_sum")?
+        } else {
+            0
+        },
+        last_unrestricted_update: date(record, "last_unrestricted_update")?,
+        last_restricted_update: if version >= SaveLoadVersion::V187 {
+            date(record, "last_restricted_update")?
+        } else {
+            INVALID_DATE
+        },
+        dest_node,
+    })
+}
+
+/// Rebuild the sorted edge list of node `from` from its saved chain, or
+/// from its row of the edge matrix before version 191
+fn edges_from_records(
+    records: &[&Record],
+    from: NodeID,
+    version: u16,
+) -> Result<Vec<LinkGraphEdge>, CoreError> {
+    if version >= SaveLoadVersion::LinkgraphEdges {
+        return records
+            .iter()
+            .map(|record| edge_from_record(record, int(record, "dest_node")? as NodeID, version))
+            .collect();
+    }
+
+    let corrupt = || CoreError::InvalidData("link graph: corrupted edge chain".into());
+    let mut edges = Vec::new();
+    if version < SaveLoadVersion::V191 {
+        // The full matrix, indexed by destination
+        let next = |to: NodeID| -> Result<NodeID, CoreError> {
+            let record = records.get(to as usize).ok_or_else(corrupt)?;
+            Ok(int(record, "next_edge")? as NodeID)
+        };
+        let mut to = next(from)?;
+        while to != INVALID_NODE {
+            if edges.len() == records.len() {
+                return Err(corrupt());
+            }
+            edges.push(edge_from_record(records[to as usize], to, version)?);
+            to = next(to)?;
+        }
+        edges.sort_by_key(|e| e.dest_node);
+        return Ok(edges);
+    }
+    let mut to = from;
+    for (i, record) in records.iter().enumerate() {
+        let next = int(record, "next_edge")? as NodeID;
+        if i > 0 {
+            edges.push(edge_from_record(record, to, version)?);
+        }
+        to = next;
+        if to == INVALID_NODE {
+            if i + 1 != records.len() {
+                return Err(corrupt());
+            }
+            edges.sort_by_key(|e| e.dest_node);
+            return Ok(edges);
+        }
+    }
+    Err(corrupt())
+}
+
+fn node_from_record(
+    record: &Record,
+    from: NodeID,
+    version: u16,
+) -> Result<LinkGraphNode, CoreError> {
+    let edges: Vec<&Record> = record.get_structs("edges").collect();
+    Ok(LinkGraphNode {
+        supply: int(record, "supply")? as u32,
+        demand: int(record, "demand")? as u32,
+        station: StationID(int(record, "station")? as u16),
+        // Older savegames take the location from the station after loading
+        xy: if version >= SaveLoadVersion::V191 {
+            TileIndex(int(record, "xy")? as u32)
+        } else {
+            TileIndex::INVALID
+        },
+        last_update: date(record, "last_update")?,
+        edges: edges_from_records(&edges, from, version)?,
+    })
+}
+
+fn link_graph_from_record(
+    index: usize,
+    record: &Record,
+    version: u16,
+) -> Result<LinkGraph, CoreError> {
+    let nodes = record
+        .get_structs("nodes")
+        .enumerate()
+        .map(|(from, node)| node_from_record(node, from as NodeID, version))
+        .collect::<Result<_, _>>()?;
+    Ok(LinkGraph {
+        index: index as LinkGraphID,
+        cargo: CargoType(int(record, "cargo")? as u8),
+        last_compression: date(record, "last_compression")?,
+        nodes,
+    })
+}
+
+/// Load the link graphs from the LGRP chunk
+pub fn load_link_graphs(chunks: &[Chunk], version: u16) -> Result<Vec<LinkGraph>, SavegameError> {
+    Ok(chunk_records(
+        chunks,
+        b"LGRP",
+        version,
+        &link_graph_desc(),
+        &link_graph_compat(),
+    )?
+    .iter()
+    .map(|(index, record)| link_graph_from_record(*index, record, version))
+    .collect::<Result<_, _>>()?)
+}
+
+fn settings_from_record(record: &Record) -> Result<LinkGraphSettings, CoreError> {
+    let setting = |name: &str| int(record, &format!("linkgraph.{}", name));
+    let distribution = |name: &str| DistributionType::try_from(setting(name)? as u8);
+    Ok(LinkGraphSettings {
+        recalc_time: setting("recalc_time")? as u16,
+        recalc_interval: setting("recalc_interval")? as u16,
+        distribution_pax: distribution("distribution_pax")?,
+        distribution_mail: distribution("distribution_mail")?,
+        distribution_armoured: distribution("distribution_armoured")?,
+        distribution_default: distribution("distribution_default")?,
+        accuracy: setting("accuracy")? as u8,
+        demand_size: setting("demand_size")? as u8,
+        demand_distance: setting("demand_distance")? as u8,
+        short_path_saturation: setting("short_path_saturation")? as u8,
+    })
+}
+
+fn job_from_record(index: usize, record: &Record, version: u16) -> Result<LinkGraphJob, CoreError> {
+    let graph = record
+        .get_structs("linkgraph")
+        .next()
+        .ok_or_else(|| missing("linkgraph"))?;
+    let graph_index = int(record, "link_graph.index")? as usize;
+    let mut job = LinkGraphJob::new(
+        index as u16,
+        link_graph_from_record(graph_index, graph, version)?,
+        settings_from_record(record)?,
+        EconomyDate(0),
+    );
+    job.join_date = date(record, "join_date")?;
+    Ok(job)
+}
+
+/// Load the link graph jobs from the LGRJ chunk; their threads are not started
+pub fn load_link_graph_jobs(
+    chunks: &[Chunk],
+    version: u16,
+) -> Result<Vec<LinkGraphJob>, SavegameError> {
+    Ok(chunk_records(
+        chunks,
+        b"LGRJ",
+        version,
+        &link_graph_job_desc(),
+        &link_graph_job_compat(),
+    )?
+    .iter()
+    .map(|(index, record)| job_from_record(*index, record, version))
+    .collect::<Result<_, _>>()?)
+}
+
+/// Load the schedule from the LGRS chunk, moving the running jobs out of
+/// `jobs` in the order they were started
+pub fn load_link_graph_schedule(
+    chunks: &[Chunk],
+    version: u16,
+    jobs: Vec<LinkGraphJob>,
+) -> Result<LinkGraphSchedule, SavegameError> {
+    let compat = ["schedule", "running"].map(SaveLoadCompat::var);
+    let records = chunk_records(
+        chunks,
+        b"LGRS",
+        version,
+        &link_graph_schedule_desc(),
+        &compat,
+    )?;
+    let mut schedule = LinkGraphSchedule::default();
+    let Some((_, record)) = records.first() else {
+        return Ok(schedule);
+    };
+    if records.len() > 1 {
+        return Err(CoreError::InvalidData("LGRS: too many entries".into()).into());
+    }
+    // References are saved as index + 1, with 0 for none
+    let refs = |key: &str| -> Result<Vec<u16>, CoreError> {
+        let values = record.get_list(key).ok_or_else(|| missing(key))?;
+        let mut refs = Vec::new();
+        for value in values {
+            let value = value.as_u64().ok_or_else(|| missing(key))?;
+            refs.extend(value.checked_sub(1).map(|index| index as u16));
+        }
+        Ok(refs)
+    };
+    schedule.schedule = refs("schedule")?.into();
+    let mut jobs: Vec<Option<LinkGraphJob>> = jobs.into_iter().map(Some).collect();
+    for index in refs("running")? {
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.as_ref().is_some_and(|job| job.index == index))
+            .and_then(Option::take)
+            .ok_or_else(|| CoreError::InvalidData(format!("LGRS: unknown job {}", index)))?;
+        schedule.running.push_back(job);
+    }
+    Ok(schedule)
+}
+
+/// Fields of the C++ SlLinkgraphEdge handler
+fn edge_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U32, "capacity"),
+        SaveLoad::var(DataType::U32, "usage"),
+        SaveLoad::var(DataType::U64, "travel_time_sum").since(SaveLoadVersion::LinkgraphTravelTime),
+        SaveLoad::var(DataType::I32, "last_unrestricted_update"),
+        SaveLoad::var(DataType::I32, "last_restricted_update").since(SaveLoadVersion::V187),
+        SaveLoad::var(DataType::U16, "dest_node"),
+        SaveLoad::var(DataType::U16, "next_edge").until(SaveLoadVersion::LinkgraphEdges),
+    ]
+}
+
+/// Order of the edge fields in savegames without a table header
+/// (matches C++ _linkgraph_edge_sl_compat)
+fn edge_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::null(4, SaveLoadVersion::MinVersion, SaveLoadVersion::V191),
+        SaveLoadCompat::var("capacity"),
+        SaveLoadCompat::var("usage"),
+        SaveLoadCompat::var("last_unrestricted_update"),
+        SaveLoadCompat::var("last_restricted_update"),
+        SaveLoadCompat::var("next_edge"),
+    ]
+}
+
+/// Fields of the C++ SlLinkgraphNode handler
+///
+/// Without list lengths the handler reads a row of the edge matrix before
+/// version 191, and follows the chain from the node's own entry since.
+fn node_desc() -> Vec<SaveLoad> {
+    let edges = || SaveLoad::structs("edges", edge_desc()).compat(edge_compat());
+    vec![
+        SaveLoad::var(DataType::U32, "xy").since(SaveLoadVersion::V191),
+        SaveLoad::var(DataType::U32, "supply"),
+        SaveLoad::var(DataType::U32, "demand"),
+        SaveLoad::var(DataType::U16, "station"),
+        SaveLoad::var(DataType::I32, "last_update"),
+        edges()
+            .length(ListLength::Field("num_nodes".into()))
+            .until(SaveLoadVersion::V191),
+        edges()
+            .length(ListLength::Chain {
+                key: "next_edge".into(),
+                end: INVALID_NODE as u64,
+            })
+            .since(SaveLoadVersion::V191),
+    ]
+}
+
+/// Field declarations of LGRP (matches C++ GetLinkGraphDesc)
+fn link_graph_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::I32, "last_compression"),
+        SaveLoad::var(DataType::U16, "num_nodes").until(SaveLoadVersion::SaveloadListLength),
+        SaveLoad::var(DataType::U8, "cargo"),
+        SaveLoad::structs("nodes", node_desc())
+            .length(ListLength::Field("num_nodes".into()))
+            .compat(
+                ["xy", "supply", "demand", "station", "last_update", "edges"]
+                    .map(SaveLoadCompat::var)
+                    .into(),
+            ),
+    ]
+}
+
+/// Order of the LGRP fields in savegames without a table header
+/// (matches C++ _linkgraph_sl_compat)
+fn link_graph_compat() -> Vec<SaveLoadCompat> {
+    ["last_compression", "num_nodes", "cargo", "nodes"]
+        .map(SaveLoadCompat::var)
+        .into()
+}
+
+/// Field declarations of LGRJ (matches C++ GetLinkGraphJobDesc)
+fn link_graph_job_desc() -> Vec<SaveLoad> {
+    let mut desc = vec![
+        SaveLoad::var(DataType::U16, "linkgraph.recalc_interval"),
+        SaveLoad::var(DataType::U16, "linkgraph.recalc_time"),
+    ];
+    desc.extend(
+        [
+            "distribution_pax",
+            "distribution_mail",
+            "distribution_armoured",
+            "distribution_default",
+            "accuracy",
+            "demand_distance",
+            "demand_size",
+            "short_path_saturation",
+        ]
+        .map(|name| SaveLoad::var(DataType::U8, &format!("linkgraph.{}", name))),
+    );
+    for setting in &mut desc {
+        *setting = setting.clone().since(SaveLoadVersion::V183);
+    }
+    desc.extend([
+        SaveLoad::var(DataType::I32, "join_date"),
+        SaveLoad::var(DataType::U16, "link_graph.index"),
+        SaveLoad::structs("linkgraph", link_graph_desc()).compat(link_graph_compat()),
+    ]);
+    desc
+}
+
+/// Order of the LGRJ fields in savegames without a table header
+/// (matches C++ _linkgraph_job_sl_compat)
+fn link_graph_job_compat() -> Vec<SaveLoadCompat> {
+    let settings = [
+        "recalc_interval",
+        "recalc_time",
+        "distribution_pax",
+        "distribution_mail",
+        "distribution_armoured",
+        "distribution_default",
+        "accuracy",
+        "demand_distance",
+        "demand_size",
+        "short_path_saturation",
+    ]
+    .map(|name| SaveLoadCompat::var(&format!("linkgraph.{}", name)));
+    let mut compat = settings.to_vec();
+    compat.extend(["join_date", "link_graph.index", "linkgraph"].map(SaveLoadCompat::var));
+    compat
+}
+
+/// Field declarations of LGRS (matches C++ GetLinkGraphScheduleDesc)
+fn link_graph_schedule_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::list(DataType::U32, "schedule"),
+        SaveLoad::list(DataType::U32, "running"),
+    ]
+}
+
+fn edge_record(edge: &LinkGraphEdge, next_edge: NodeID) -> Record {
+    Record::default()
+        .with("capacity", edge.capacity)
+        .with("usage", edge.usage)
+        .with("travel_time_sum", edge.travel_time_sum)
+        .with("last_unrestricted_update", edge.last_unrestricted_update.0)
+        .with("last_restricted_update", edge.last_restricted_update.0)
+        .with("dest_node", edge.dest_node)
+        .with("next_edge", next_edge)
+}
+
+/// The saved edges of node `from`, as a chain before SaveLoadVersion::LinkgraphEdges
+fn edge_records(node: &LinkGraphNode, from: NodeID, version: u16) -> Vec<Record> {
+    let next = |i: usize| node.edges.get(i).map_or(INVALID_NODE, |e| e.dest_node);
+    if version >= SaveLoadVersion::LinkgraphEdges {
+        return node
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, e)| edge_record(e, next(i + 1)))
+            .collect();
+    }
+    let mut records = vec![edge_record(&LinkGraphEdge::new(from), next(0))];
+    records.extend(
+        node.edges
+            .iter()
+            .enumerate()
+            .map(|(i, e)| edge_record(e, next(i + 1))),
+    );
+    records
+}
+
+fn link_graph_record(lg: &LinkGraph, version: u16) -> Record {
+    let nodes: Vec<Record> = lg
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(from, node)| {
+            Record::default()
+                .with("xy", node.xy.0)
+                .with("supply", node.supply)
+                .with("demand", node.demand)
+                .with("station", node.station.0)
+                .with("last_update", node.last_update.0)
+                .with("edges", edge_records(node, from as NodeID, version))
+        })
+        .collect();
+    Record::default()
+        .with("last_compression", lg.last_compression.0)
+        .with("num_nodes", lg.size())
+        .with("cargo", lg.cargo.0)
+        .with("nodes", nodes)
+}
+
+fn job_record(job: &LinkGraphJob, version: u16) -> Record {
+    let settings = &job.settings;
+    Record::default()
+        .with("linkgraph.recalc_interval", settings.recalc_interval)
+        .with("linkgraph.recalc_time", settings.recalc_time)
+        .with(
+            "linkgraph.distribution_pax",
+            settings.distribution_pax as u8,
+        )
+        .with(
+            "linkgraph.distribution_mail",
+            settings.distribution_mail as u8,
+        )
+        .with(
+            "linkgraph.distribution_armoured",
+            settings.distribution_armoured as u8,
+        )
+        .with(
+            "linkgraph.distribution_default",
+            settings.distribution_default as u8,
+        )
+        .with("linkgraph.accuracy", settings.accuracy)
+        .with("linkgraph.demand_distance", settings.demand_distance)
+        .with("linkgraph.demand_size", settings.demand_size)
+        .with(
+            "linkgraph.short_path_saturation",
+            settings.short_path_saturation,
+        )
+        .with("join_date", job.join_date.0)
+        .with("link_graph.index", job.link_graph.index)
+        .with(
+            "linkgraph",
+            vec![link_graph_record(&job.link_graph, version)],
+        )
+}
+
+/// Write the LGRP chunk in the layout of the writer's savegame version
+pub fn save_link_graphs(
+    writer: &mut SavegameWriter,
+    graphs: &[LinkGraph],
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+    let records: Vec<(usize, Record)> = graphs
+        .iter()
+        .map(|lg| (lg.index as usize, link_graph_record(lg, version)))
+        .collect();
+    writer.add_table_records(
+        b"LGRP",
+        ChunkType::Table,
+        &table_header(&link_graph_desc(), version),
+        &records,
+    )
+}
+
+/// Write the LGRJ and LGRS chunks in the layout of the writer's savegame version
+pub fn save_link_graph_schedule(
+    writer: &mut SavegameWriter,
+    schedule: &LinkGraphSchedule,
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+    let mut jobs: Vec<&LinkGraphJob> = schedule.running.iter().collect();
+    jobs.sort_by_key(|job| job.index);
+    let records: Vec<(usize, Record)> = jobs
+        .into_iter()
+        .map(|job| (job.index as usize, job_record(job, version)))
+        .collect();
+    writer.add_table_records(
+        b"LGRJ",
+        ChunkType::Table,
+        &table_header(&link_graph_job_desc(), version),
+        &records,
+    )?;
+
+    let refs = |ids: Vec<u16>| {
+        ids.into_iter()
+            .map(|id| id as u32 + 1)
+            .collect::<Vec<u32>>()
+    };
+    let record = Record::default()
+        .with(
+            "schedule",
+            refs(schedule.schedule.iter().copied().collect()),
+        )
+        .with(
+            "running",
+            refs(schedule.running.iter().map(|job| job.index).collect()),
+        );
+    writer.add_table_records(
+        b"LGRS",
+        ChunkType::Table,
+        &table_header(&link_graph_schedule_desc(), version),
+        &[(0, record)],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+    use openttd_core::linkgraph::EdgeUpdateModes;
+
+    fn sample_graph(index: LinkGraphID) -> LinkGraph {
+        let mut lg = LinkGraph::new(index, CargoType(3), EconomyDate(700));
+        for i in 0..3 {
+            let mut node = LinkGraphNode::new(TileIndex(100 + i), StationID(i as u16 * 2), i);
+            node.update_supply(20 * i, EconomyDate(710));
+            lg.nodes.push(node);
+        }
+        let modes = EdgeUpdateModes::INCREASE | EdgeUpdateModes::RESTRICTED;
+        lg.nodes[0].update_edge(2, 30, 5, 100, modes, EconomyDate(705));
+        lg.nodes[0].update_edge(1, 10, 0, 0, EdgeUpdateModes::UNRESTRICTED, EconomyDate(706));
+        lg.nodes[2].update_edge(0, 40, 40, 12, modes, EconomyDate(709));
+        lg
+    }
+
+    fn sample_schedule() -> LinkGraphSchedule {
+        let mut schedule = LinkGraphSchedule::default();
+        schedule.queue(4);
+        schedule.queue(1);
+        let settings = LinkGraphSettings {
+            distribution_pax: DistributionType::Symmetric,
+            accuracy: 40,
+            ..Default::default()
+        };
+        for (index, graph) in [(3, 2), (1, 0)] {
+            let job = LinkGraphJob::new(index, sample_graph(graph), settings, EconomyDate(711));
+            schedule.running.push_back(job);
+        }
+        schedule
+    }
+
+    fn round_trip(
+        graphs: &[LinkGraph],
+        schedule: &LinkGraphSchedule,
+        version: u16,
+    ) -> (Vec<LinkGraph>, LinkGraphSchedule) {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_link_graphs(&mut writer, graphs).unwrap();
+        save_link_graph_schedule(&mut writer, schedule).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        let jobs = load_link_graph_jobs(&chunks, version).unwrap();
+        (
+            load_link_graphs(&chunks, version).unwrap(),
+            load_link_graph_schedule(&chunks, version, jobs).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_link_graphs_round_trip() {
+        let graphs = vec![sample_graph(1), sample_graph(4)];
+        let schedule = sample_schedule();
+        for version in [SaveLoadVersion::CURRENT.into(), 300] {
+            let (loaded_graphs, loaded_schedule) = round_trip(&graphs, &schedule, version);
+            assert_eq!(loaded_graphs, graphs);
+            assert_eq!(loaded_schedule.schedule, [4, 1]);
+            assert_eq!(loaded_schedule.running, schedule.running);
+        }
+
+        // No travel times before LinkgraphTravelTime
+        let (loaded_graphs, _) = round_trip(&graphs, &schedule, 296);
+        assert_eq!(loaded_graphs[0].nodes[0].edges[1].travel_time_sum, 0);
+        assert_eq!(loaded_graphs[0].nodes[0].edges[1].capacity, 30);
+    }
+
+    #[test]
+    fn test_link_graphs_invalid() {
+        let mut record = job_record(&sample_schedule().running[0], 316);
+        let distribution = record
+            .fields
+            .iter_mut()
+            .find(|(key, _)| key == "linkgraph.distribution_mail")
+            .unwrap();
+        distribution.1 = 3u8.into();
+        assert!(job_from_record(0, &record, 316).is_err());
+
+        // An edge chain that does not end
+        let node = &sample_graph(0).nodes[0];
+        let mut edges = edge_records(node, 0, 300);
+        edges.pop();
+        let edges: Vec<&Record> = edges.iter().collect();
+        assert!(edges_from_records(&edges, 0, 300).is_err());
+
+        // A matrix row chained from the entry of the node itself
+        let edge = |next: NodeID| {
+            Record::default()
+                .with("capacity", 8u32)
+                .with("usage", 2u32)
+                .with("last_unrestricted_update", 650i32)
+                .with("next_edge", next)
+        };
+        let row = [edge(1), edge(2), edge(INVALID_NODE)];
+        let row: Vec<&Record> = row.iter().collect();
+        let edges = edges_from_records(&row, 0, 186).unwrap();
+        assert_eq!(
+            edges.iter().map(|e| e.dest_node).collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert_eq!(edges[0].last_restricted_update, INVALID_DATE);
+        let looped = [edge(1), edge(0), edge(INVALID_NODE)];
+        let looped: Vec<&Record> = looped.iter().collect();
+        assert!(edges_from_records(&looped, 0, 186).is_err());
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_link_graphs(&mut writer, &[]),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
@@ -0,0 +1,266 @@
+/// Loading and saving of the map chunks (MAPS and the per-field MAP* chunks)
+///
+/// Every tile field is stored in its own RIFF chunk, in tile index order. The
+/// loader mirrors the C++ `Load()` handlers and leaves any after-load conversion
+/// of old savegames to the caller.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, find_chunk, Chunk, ChunkData, SavegameError, SavegameWriter};
+use crate::table::Record;
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::error::CoreError;
+use openttd_core::map::{Map, Tile};
+
+/// Accessor of a single byte of a tile
+type ByteField = fn(&mut Tile) -> &mut u8;
+
+/// Per-tile byte fields, in the order the C++ chunk handlers are registered
+const BYTE_FIELDS: [(&[u8; 4], ByteField); 8] = [
+    (b"MAPT", |t| &mut t.base.type_height),
+    (b"MAPH", |t| &mut t.base.height),
+    (b"MAPO", |t| &mut t.base.m1),
+    (b"M3LO", |t| &mut t.base.m3),
+    (b"M3HI", |t| &mut t.base.m4),
+    (b"MAP5", |t| &mut t.base.m5),
+    (b"MAPE", |t| &mut t.extended.m6),
+    (b"MAP7", |t| &mut t.extended.m7),
+];
+
+fn riff_data(chunk: &Chunk, expected: usize) -> Result<&[u8], SavegameError> {
+    match &chunk.data {
+        ChunkData::Riff(data) if data.len() == expected => Ok(data),
+        ChunkData::Riff(data) => Err(CoreError::InvalidData(format!(
+            "{}: expected {} bytes, found {}",
+            chunk.tag,
+            expected,
+            data.len()
+        ))
+        .into()),
+        _ => Err(SavegameError::InvalidFormat),
+    }
+}
+
+/// Field declarations of MAPS (matches C++ _map_desc)
+fn map_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U32, "dim_x"),
+        SaveLoad::var(DataType::U32, "dim_y"),
+    ]
+}
+
+/// Order of the MAPS fields in savegames without a table header
+/// (matches C++ _map_sl_compat)
+fn map_compat() -> Vec<SaveLoadCompat> {
+    ["dim_x", "dim_y"].map(SaveLoadCompat::var).into()
+}
+
+/// Read the map dimensions from the MAPS chunk
+fn load_dimensions(chunks: &[Chunk], version: u16) -> Result<(u32, u32), SavegameError> {
+    // Before the dimensions were stored, maps were always 256x256
+    if version < SaveLoadVersion::V6 {
+        return Ok((256, 256));
+    }
+    if find_chunk(chunks, b"MAPS").is_none() {
+        return Err(SavegameError::MissingChunk("MAPS".into()));
+    }
+
+    // A RIFF chunk before RiffToArray, a single entry after
+    let records = chunk_records(chunks, b"MAPS", version, &map_desc(), &map_compat())?;
+    let record = match records.as_slice() {
+        [(_, record)] => record,
+        _ => return Err(CoreError::InvalidData("Expected one MAPS entry".into()).into()),
+    };
+    let dim = |key: &str| {
+        record
+            .get_u64(key)
+            .ok_or_else(|| CoreError::InvalidData(format!("MAPS: missing {}", key)))
+    };
+    Ok((dim("dim_x")? as u32, dim("dim_y")? as u32))
+}
+
+/// Build a map from the MAP* chunks of a savegame of the given version
+pub fn load_map(chunks: &[Chunk], version: u16) -> Result<Map, SavegameError> {
+    let (dim_x, dim_y) = load_dimensions(chunks, version)?;
+    if !dim_x.is_power_of_two() || !dim_y.is_power_of_two() {
+        return Err(CoreError::InvalidData(format!("Invalid map size {}x{}", dim_x, dim_y)).into());
+    }
+    let mut map =
+        Map::new(dim_x.trailing_zeros(), dim_y.trailing_zeros()).map_err(CoreError::InvalidData)?;
+
+    // Like `Map::Allocate`, start from zeroed tiles; absent chunks leave their field at 0
+    map.tiles.fill(Tile::new_clear(0));
+    let size = map.tiles.len();
+
+    for (tag, field) in BYTE_FIELDS {
+        let Some(chunk) = find_chunk(chunks, tag) else {
+            continue;
+        };
+
+        if tag == b"MAPE" && version < SaveLoadVersion::V42 {
+            // Two bits per tile, four tiles per byte
+            let data = riff_data(chunk, size / 4)?;
+            for (i, tile) in map.tiles.iter_mut().enumerate() {
+                *field(tile) = (data[i / 4] >> ((i % 4) * 2)) & 0x03;
+            }
+        } else {
+            let data = riff_data(chunk, size)?;
+            for (tile, &value) in map.tiles.iter_mut().zip(data) {
+                *field(tile) = value;
+            }
+        }
+    }
+
+    if let Some(chunk) = find_chunk(chunks, b"MAP2") {
+        if version < SaveLoadVersion::V5 {
+            let data = riff_data(chunk, size)?;
+            for (tile, &value) in map.tiles.iter_mut().zip(data) {
+                tile.base.m2 = value as u16;
+            }
+        } else {
+            let data = riff_data(chunk, size * 2)?;
+            for (tile, value) in map.tiles.iter_mut().zip(data.chunks_exact(2)) {
+                tile.base.m2 = u16::from_be_bytes([value[0], value[1]]);
+            }
+        }
+    }
+
+    if let Some(chunk) = find_chunk(chunks, b"MAP8") {
+        let data = riff_data(chunk, size * 2)?;
+        for (tile, value) in map.tiles.iter_mut().zip(data.chunks_exact(2)) {
+            tile.extended.m8 = u16::from_be_bytes([value[0], value[1]]);
+        }
+    }
+
+    Ok(map)
+}
+
+/// Write the map chunks in the current savegame format
+pub fn save_map(writer: &mut SavegameWriter, map: &Map) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let header = table_header(&map_desc(), writer.version());
+    let record = Record::default()
+        .with("dim_x", map.size_x)
+        .with("dim_y", map.size_y);
+    writer.add_table_records(b"MAPS", ChunkType::Table, &header, &[(0, record)])?;
+
+    let bytes = |field: ByteField| -> Vec<u8> {
+        map.tiles
+            .iter()
+            .map(|t| {
+                let mut t = *t;
+                *field(&mut t)
+            })
+            .collect()
+    };
+    let words = |field: fn(&Tile) -> u16| -> Vec<u8> {
+        map.tiles
+            .iter()
+            .flat_map(|t| field(t).to_be_bytes())
+            .collect()
+    };
+
+    // Same order as the C++ chunk handler table
+    for (tag, field) in &BYTE_FIELDS[..3] {
+        writer.add_riff_chunk(tag, &bytes(*field))?;
+    }
+    writer.add_riff_chunk(b"MAP2", &words(|t| t.base.m2))?;
+    for (tag, field) in &BYTE_FIELDS[3..] {
+        writer.add_riff_chunk(tag, &bytes(*field))?;
+    }
+    writer.add_riff_chunk(b"MAP8", &words(|t| t.extended.m8))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+
+    fn sample_map() -> Map {
+        let mut map = Map::new(6, 7).unwrap();
+        for (i, tile) in map.tiles.iter_mut().enumerate() {
+            tile.base.type_height = (i % 11) as u8;
+            tile.base.height = (i % 16) as u8;
+            tile.base.m1 = i as u8;
+            tile.base.m2 = (i * 7) as u16;
+            tile.base.m3 = (i >> 2) as u8;
+            tile.base.m4 = (i >> 3) as u8;
+            tile.base.m5 = (i >> 4) as u8;
+            tile.extended.m6 = (i >> 5) as u8;
+            tile.extended.m7 = (i >> 6) as u8;
+            tile.extended.m8 = (i * 13) as u16;
+        }
+        map
+    }
+
+    fn write_map(map: &Map) -> Vec<Chunk> {
+        let mut writer = SavegameWriter::new(300, CompressionType::None);
+        save_map(&mut writer, map).unwrap();
+        let data = writer.finalize().unwrap();
+        SavegameReader::new(&data).unwrap().read_chunks().unwrap()
+    }
+
+    #[test]
+    fn test_map_round_trip() {
+        let map = sample_map();
+        let chunks = write_map(&map);
+        let tags: Vec<&str> = chunks.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(
+            tags,
+            [
+                "MAPS", "MAPT", "MAPH", "MAPO", "MAP2", "M3LO", "M3HI", "MAP5", "MAPE", "MAP7",
+                "MAP8"
+            ]
+        );
+
+        let loaded = load_map(&chunks, 300).unwrap();
+        assert_eq!((loaded.size_x, loaded.size_y), (64, 128));
+        for (a, b) in loaded.tiles.iter().zip(&map.tiles) {
+            assert_eq!(a.base.type_height, b.base.type_height);
+            assert_eq!(a.base.height, b.base.height);
+            assert_eq!(a.base.m1, b.base.m1);
+            assert_eq!(a.base.m2, b.base.m2);
+            assert_eq!(a.base.m3, b.base.m3);
+            assert_eq!(a.base.m4, b.base.m4);
+            assert_eq!(a.base.m5, b.base.m5);
+            assert_eq!(a.extended.m6, b.extended.m6);
+            assert_eq!(a.extended.m7, b.extended.m7);
+            assert_eq!(a.extended.m8, b.extended.m8);
+        }
+    }
+
+    #[test]
+    fn test_load_old_formats() {
+        let size = 256 * 256;
+        let riff = |tag: &str, data: Vec<u8>| Chunk {
+            tag: tag.into(),
+            chunk_type: ChunkType::Riff,
+            data: ChunkData::Riff(data),
+        };
+        // Version 4: implicit 256x256, 8-bit m2 and 2-bit m6
+        let chunks = vec![
+            riff("MAPT", vec![0x10; size]),
+            riff("MAP2", vec![0xAB; size]),
+            riff("MAPE", vec![0b11_10_01_00; size / 4]),
+        ];
+
+        let map = load_map(&chunks, 4).unwrap();
+        assert_eq!(map.size, size as u32);
+        assert_eq!(map.tiles[0].base.type_height, 0x10);
+        assert_eq!(map.tiles[0].base.m2, 0xAB);
+        let m6: Vec<u8> = map.tiles[..4].iter().map(|t| t.extended.m6).collect();
+        assert_eq!(m6, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_load_size_mismatch() {
+        let mut chunks = write_map(&sample_map());
+        chunks[1].data = ChunkData::Riff(vec![0; 10]);
+        assert!(load_map(&chunks, 300).is_err());
+    }
+}
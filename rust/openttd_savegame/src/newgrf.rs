@@ -0,0 +1,229 @@
+/// Loading and saving of the NGRF chunk
+///
+/// The parameters are saved as a fixed array of MAX_NUM_PARAMS values with
+/// the number of used ones next to it; unused entries are saved as zero.
+/// Before version 101 no palette is saved; C++ then picks one from the
+/// NewGRF file, so it is left at zero here.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, int_list, missing, Record};
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::error::CoreError;
+use openttd_core::newgrf::{GrfConfig, MAX_NUM_PARAMS};
+
+fn config_from_record(record: &Record, version: u16) -> Result<GrfConfig, CoreError> {
+    let md5sum: Vec<u8> = int_list(record, "ident.md5sum")?
+        .into_iter()
+        .map(|b| b as u8)
+        .collect();
+    let md5sum = <[u8; 16]>::try_from(md5sum).map_err(|md5sum| {
+        CoreError::InvalidData(format!("NGRF: md5sum has {} bytes", md5sum.len()))
+    })?;
+    let num_params = int(record, "num_params")? as usize;
+
+    Ok(GrfConfig {
+        filename: record
+            .get_str("filename")
+            .ok_or_else(|| missing("filename"))?
+            .into(),
+        grfid: int(record, "ident.grfid")? as u32,
+        md5sum,
+        version: if version >= SaveLoadVersion::V151 {
+            int(record, "version")? as u32
+        } else {
+            0
+        },
+        params: int_list(record, "param")?
+            .into_iter()
+            .take(num_params)
+            .map(|v| v as u32)
+            .collect(),
+        palette: if version >= SaveLoadVersion::V101 {
+            int(record, "palette")? as u8
+        } else {
+            0
+        },
+    })
+}
+
+/// Load the NewGRFs of the game from the NGRF chunk, in load order
+pub fn load_newgrf_configs(
+    chunks: &[Chunk],
+    version: u16,
+) -> Result<Vec<GrfConfig>, SavegameError> {
+    chunk_records(chunks, b"NGRF", version, &newgrf_desc(), &newgrf_compat())?
+        .iter()
+        .map(|(_, record)| Ok(config_from_record(record, version)?))
+        .collect()
+}
+
+/// Field declarations of NGRF (matches C++ NGRFChunkHandler::description)
+fn newgrf_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::String, "filename"),
+        SaveLoad::var(DataType::U32, "ident.grfid"),
+        SaveLoad::array(DataType::U8, "ident.md5sum", 16),
+        SaveLoad::var(DataType::U32, "version").since(SaveLoadVersion::V151),
+        SaveLoad::array(DataType::U32, "param", MAX_NUM_PARAMS),
+        SaveLoad::var(DataType::U8, "num_params"),
+        SaveLoad::var(DataType::U8, "palette").since(SaveLoadVersion::V101),
+    ]
+}
+
+/// Order of the NGRF fields in savegames without a table header
+/// (matches C++ _grfconfig_sl_compat)
+fn newgrf_compat() -> Vec<SaveLoadCompat> {
+    [
+        "filename",
+        "ident.grfid",
+        "ident.md5sum",
+        "version",
+        "param",
+        "num_params",
+        "palette",
+    ]
+    .map(SaveLoadCompat::var)
+    .into()
+}
+
+fn config_to_record(config: &GrfConfig) -> Record {
+    let mut params = config.params.clone();
+    params.resize(MAX_NUM_PARAMS, 0);
+    Record::default()
+        .with("filename", config.filename.as_str())
+        .with("ident.grfid", config.grfid)
+        .with("ident.md5sum", config.md5sum.to_vec())
+        .with("version", config.version)
+        .with("param", params)
+        .with("num_params", config.params.len() as u8)
+        .with("palette", config.palette)
+}
+
+/// Write the NGRF chunk in the layout of the writer's savegame version
+pub fn save_newgrf_configs(
+    writer: &mut SavegameWriter,
+    configs: &[GrfConfig],
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+    if let Some(config) = configs.iter().find(|c| c.params.len() > MAX_NUM_PARAMS) {
+        return Err(CoreError::InvalidData(format!(
+            "NGRF: {} has {} parameters",
+            config.filename,
+            config.params.len()
+        ))
+        .into());
+    }
+
+    let records: Vec<(usize, Record)> = configs
+        .iter()
+        .enumerate()
+        .map(|(i, config)| (i, config_to_record(config)))
+        .collect();
+
+    writer.add_table_records(
+        b"NGRF",
+        ChunkType::Table,
+        &table_header(&newgrf_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::{ChunkData, SavegameReader};
+    use crate::types::CompressionType;
+    use openttd_core::newgrf::{GRFP_GRF_WINDOWS, GRFP_USE_WINDOWS};
+
+    fn sample_configs() -> Vec<GrfConfig> {
+        vec![
+            GrfConfig {
+                filename: "opengfx/ogfx1_base.grf".into(),
+                grfid: 0x0103_4F47,
+                md5sum: [0x5A; 16],
+                version: 7,
+                params: vec![],
+                palette: GRFP_USE_WINDOWS | GRFP_GRF_WINDOWS,
+            },
+            GrfConfig {
+                filename: "av8.grf".into(),
+                grfid: 0x0403_2A4D,
+                md5sum: [1; 16],
+                version: 0,
+                params: vec![0, 3, 0xFFFF_FFFF],
+                palette: 0,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_newgrf_round_trip() {
+        let configs = sample_configs();
+        let version = SaveLoadVersion::CURRENT.into();
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_newgrf_configs(&mut writer, &configs).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        assert_eq!(load_newgrf_configs(&chunks, version).unwrap(), configs);
+
+        // The parameter array is always saved in full
+        let records = chunks[0].decode_records().unwrap();
+        assert_eq!(
+            records[1].1.get_list("param").unwrap().len(),
+            MAX_NUM_PARAMS
+        );
+        assert_eq!(records[1].1.get_i64("num_params"), Some(3));
+    }
+
+    #[test]
+    fn test_newgrf_before_table_chunks() {
+        let mut data = vec![5];
+        data.extend_from_slice(b"a.grf");
+        data.extend_from_slice(&0x0403_2A4Du32.to_be_bytes());
+        data.extend_from_slice(&[1; 16]);
+        let params = (0..MAX_NUM_PARAMS as u32).flat_map(|i| (i * 2).to_be_bytes());
+        data.extend(params);
+        data.extend_from_slice(&[2, GRFP_USE_WINDOWS]);
+        let chunk = |data: &[u8]| Chunk {
+            tag: "NGRF".into(),
+            chunk_type: ChunkType::Array,
+            data: ChunkData::Array(vec![(0, data.to_vec())]),
+        };
+
+        let configs = load_newgrf_configs(&[chunk(&data)], 150).unwrap();
+        assert_eq!(configs[0].filename, "a.grf");
+        assert_eq!(configs[0].md5sum, [1; 16]);
+        assert_eq!(configs[0].params, [0, 2]);
+        assert_eq!(
+            (configs[0].version, configs[0].palette),
+            (0, GRFP_USE_WINDOWS)
+        );
+
+        // No palette is saved before version 101
+        let configs = load_newgrf_configs(&[chunk(&data[..data.len() - 1])], 100).unwrap();
+        assert_eq!(configs[0].palette, 0);
+        assert!(load_newgrf_configs(&[chunk(&data)], 100).is_err());
+    }
+
+    #[test]
+    fn test_newgrf_invalid_configs() {
+        let version = SaveLoadVersion::CURRENT.into();
+        let mut configs = sample_configs();
+        configs[1].params = vec![0; MAX_NUM_PARAMS + 1];
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        assert!(save_newgrf_configs(&mut writer, &configs).is_err());
+
+        let mut record = config_to_record(&sample_configs()[0]);
+        record.fields[2].1 = vec![0u8; 15].into();
+        assert!(config_from_record(&record, version).is_err());
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_newgrf_configs(&mut writer, &sample_configs()),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
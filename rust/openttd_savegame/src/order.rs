@@ -0,0 +1,544 @@
+/// Loading and saving of the ORDR, ORDL and BKOR chunks
+///
+/// Before SaveLoadVersion::OrdersOwnedByOrderlist the orders live in the ORDR
+/// pool as a linked list through their `next` reference, and order lists and
+/// backups only refer to their first order. Since then ORDR is gone and the
+/// orders are saved inside the ORDL and BKOR records.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, find_chunk, Chunk, ChunkData, SavegameError, SavegameWriter};
+use crate::table::{int, missing, reference, to_reference, Record};
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::error::CoreError;
+use openttd_core::map::TileIndex;
+use openttd_core::order::{Order, OrderBackup, OrderList};
+use openttd_core::types::{CargoType, GroupID, VehicleID};
+use std::collections::HashMap;
+
+fn order_from_record(record: &Record, version: u16) -> Result<Order, CoreError> {
+    let mut order = Order {
+        type_: int(record, "type")? as u8,
+        flags: int(record, "flags")? as u8,
+        dest: int(record, "dest")? as u16,
+        ..Order::default()
+    };
+    if version >= SaveLoadVersion::V36 {
+        order.refit_cargo = CargoType(int(record, "refit_cargo")? as u8);
+    }
+    if version >= SaveLoadVersion::V67 {
+        order.wait_time = int(record, "wait_time")? as u16;
+        order.travel_time = int(record, "travel_time")? as u16;
+    }
+    if version >= SaveLoadVersion::V172 {
+        order.max_speed = int(record, "max_speed")? as u16;
+    }
+    Ok(order)
+}
+
+fn order_record(order: &Order) -> Record {
+    Record::default()
+        .with("type", order.type_)
+        .with("flags", order.flags)
+        .with("dest", order.dest)
+        .with("refit_cargo", order.refit_cargo.0)
+        .with("wait_time", order.wait_time)
+        .with("travel_time", order.travel_time)
+        .with("max_speed", order.max_speed)
+}
+
+/// Orders of the ORDR pool by index, with the reference to their next order
+type OldOrders = HashMap<usize, (Order, u32)>;
+
+fn load_old_orders(chunks: &[Chunk], version: u16) -> Result<OldOrders, SavegameError> {
+    if version >= SaveLoadVersion::OrdersOwnedByOrderlist {
+        return Ok(OldOrders::new());
+    }
+    // Before version 5.2 the orders were a packed array without next references
+    let ordr = find_chunk(chunks, b"ORDR").map(|chunk| &chunk.data);
+    if matches!(ordr, Some(ChunkData::Riff(_))) {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+    chunk_records(
+        chunks,
+        b"ORDR",
+        version,
+        &old_order_desc(),
+        &old_order_compat(),
+    )?
+    .iter()
+    .map(|(index, record)| {
+        let next = int(record, "next")? as u32;
+        Ok((*index, (order_from_record(record, version)?, next)))
+    })
+    .collect()
+}
+
+/// Collect the chain of old orders starting at the reference `first`
+fn old_order_chain(pool: &OldOrders, first: u32) -> Result<Vec<Order>, CoreError> {
+    let mut orders = Vec::new();
+    let mut next = first;
+    while next != 0 {
+        if orders.len() == pool.len() {
+            return Err(CoreError::InvalidData(format!(
+                "ORDR: order chain from {} loops",
+                first - 1
+            )));
+        }
+        let (order, following) = pool
+            .get(&(next as usize - 1))
+            .ok_or_else(|| CoreError::InvalidData(format!("ORDR: missing order {}", next - 1)))?;
+        orders.push(*order);
+        next = *following;
+    }
+    Ok(orders)
+}
+
+/// The orders of an ORDL or BKOR record, from the record or the old pool
+fn orders_from_record(
+    record: &Record,
+    key: &str,
+    pool: &OldOrders,
+    version: u16,
+) -> Result<Vec<Order>, CoreError> {
+    if version < SaveLoadVersion::OrdersOwnedByOrderlist {
+        old_order_chain(pool, int(record, key)? as u32)
+    } else {
+        record
+            .get_structs(key)
+            .map(|order| order_from_record(order, version))
+            .collect()
+    }
+}
+
+/// Load the order lists from the ORDL chunk
+pub fn load_order_lists(chunks: &[Chunk], version: u16) -> Result<Vec<OrderList>, SavegameError> {
+    let pool = load_old_orders(chunks, version)?;
+    chunk_records(
+        chunks,
+        b"ORDL",
+        version,
+        &order_list_desc(),
+        &[SaveLoadCompat::var("first")],
+    )?
+    .iter()
+    .map(|(index, record)| {
+        let key = if version < SaveLoadVersion::OrdersOwnedByOrderlist {
+            "first"
+        } else {
+            "orders"
+        };
+        Ok(OrderList::new(
+            *index as u32,
+            orders_from_record(record, key, &pool, version)?,
+        ))
+    })
+    .collect()
+}
+
+fn backup_from_record(
+    index: usize,
+    record: &Record,
+    pool: &OldOrders,
+    version: u16,
+) -> Result<OrderBackup, CoreError> {
+    let mut backup = OrderBackup {
+        index: index as u32,
+        user: int(record, "user")? as u32,
+        tile: TileIndex(int(record, "tile")? as u32),
+        group: GroupID(int(record, "group")? as u16),
+        service_interval: int(record, "service_interval")? as u16,
+        name: record
+            .get_str("name")
+            .ok_or_else(|| missing("name"))?
+            .into(),
+        clone: None,
+        cur_real_order_index: int(record, "cur_real_order_index")? as u8,
+        cur_implicit_order_index: 0,
+        current_order_time: 0,
+        lateness_counter: 0,
+        timetable_start: 0,
+        vehicle_flags: 0,
+        orders: orders_from_record(record, "orders", pool, version)?,
+    };
+    if version >= SaveLoadVersion::V192 {
+        backup.clone = reference(record, "clone")?.map(VehicleID);
+    }
+    if version >= SaveLoadVersion::V176 {
+        backup.cur_implicit_order_index = int(record, "cur_implicit_order_index")? as u8;
+        backup.current_order_time = int(record, "current_order_time")? as u32;
+        backup.lateness_counter = int(record, "lateness_counter")? as i32;
+        backup.timetable_start = if version < SaveLoadVersion::TimetableStartTicksFix {
+            int(record, "timetable_start")? as i32 as u64
+        } else {
+            int(record, "timetable_start")?
+        };
+        backup.vehicle_flags = int(record, "vehicle_flags")? as u16;
+    }
+    Ok(backup)
+}
+
+/// Load the order backups from the BKOR chunk
+pub fn load_order_backups(
+    chunks: &[Chunk],
+    version: u16,
+) -> Result<Vec<OrderBackup>, SavegameError> {
+    let pool = load_old_orders(chunks, version)?;
+    chunk_records(
+        chunks,
+        b"BKOR",
+        version,
+        &order_backup_desc(),
+        &order_backup_compat(),
+    )?
+    .iter()
+    .map(|(index, record)| Ok(backup_from_record(*index, record, &pool, version)?))
+    .collect()
+}
+
+/// Fields of an order (matches C++ SlOrders::description)
+fn order_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U8, "type"),
+        SaveLoad::var(DataType::U8, "flags"),
+        SaveLoad::var(DataType::U16, "dest"),
+        SaveLoad::var(DataType::U8, "refit_cargo"),
+        SaveLoad::var(DataType::U16, "wait_time"),
+        SaveLoad::var(DataType::U16, "travel_time"),
+        SaveLoad::var(DataType::U16, "max_speed"),
+    ]
+}
+
+/// Field declarations of ORDR (matches C++ GetOrderDescription)
+fn old_order_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U8, "type"),
+        SaveLoad::var(DataType::U8, "flags"),
+        SaveLoad::var(DataType::U16, "dest"),
+        SaveLoad::var(DataType::U16, "next").until(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, "next").since(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U8, "refit_cargo").since(SaveLoadVersion::V36),
+        SaveLoad::var(DataType::U16, "wait_time").since(SaveLoadVersion::V67),
+        SaveLoad::var(DataType::U16, "travel_time").since(SaveLoadVersion::V67),
+        SaveLoad::var(DataType::U16, "max_speed").since(SaveLoadVersion::V172),
+    ]
+}
+
+/// Order of the ORDR fields in savegames without a table header
+/// (matches C++ _order_sl_compat)
+fn old_order_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::var("type"),
+        SaveLoadCompat::var("flags"),
+        SaveLoadCompat::var("dest"),
+        SaveLoadCompat::var("next"),
+        SaveLoadCompat::var("refit_cargo"),
+        SaveLoadCompat::null(1, SaveLoadVersion::V36, SaveLoadVersion::V182),
+        SaveLoadCompat::var("wait_time"),
+        SaveLoadCompat::var("travel_time"),
+        SaveLoadCompat::var("max_speed"),
+        SaveLoadCompat::null(10, SaveLoadVersion::V5, SaveLoadVersion::V36),
+    ]
+}
+
+/// The reference to the first order, or the orders themselves, by version
+fn orders_desc(key: &str) -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U16, key).until(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, key)
+            .since(SaveLoadVersion::V69)
+            .until(SaveLoadVersion::OrdersOwnedByOrderlist),
+        SaveLoad::structs("orders", order_desc()).since(SaveLoadVersion::OrdersOwnedByOrderlist),
+    ]
+}
+
+/// Field declarations of ORDL (matches C++ GetOrderListDescription)
+fn order_list_desc() -> Vec<SaveLoad> {
+    orders_desc("first")
+}
+
+/// Field declarations of BKOR (matches C++ GetOrderBackupDescription)
+fn order_backup_desc() -> Vec<SaveLoad> {
+    let mut desc = vec![
+        SaveLoad::var(DataType::U32, "user"),
+        SaveLoad::var(DataType::U32, "tile"),
+        SaveLoad::var(DataType::U16, "group"),
+        SaveLoad::var(DataType::U32, "service_interval").until(SaveLoadVersion::V192),
+        SaveLoad::var(DataType::U16, "service_interval").since(SaveLoadVersion::V192),
+        SaveLoad::var(DataType::String, "name"),
+        SaveLoad::var(DataType::U32, "clone").since(SaveLoadVersion::V192),
+        SaveLoad::var(DataType::U8, "cur_real_order_index"),
+        SaveLoad::var(DataType::U8, "cur_implicit_order_index").since(SaveLoadVersion::V176),
+        SaveLoad::var(DataType::U32, "current_order_time").since(SaveLoadVersion::V176),
+        SaveLoad::var(DataType::I32, "lateness_counter").since(SaveLoadVersion::V176),
+        SaveLoad::var(DataType::I32, "timetable_start")
+            .since(SaveLoadVersion::V176)
+            .until(SaveLoadVersion::TimetableStartTicksFix),
+        SaveLoad::var(DataType::U64, "timetable_start")
+            .since(SaveLoadVersion::TimetableStartTicksFix),
+        SaveLoad::var(DataType::U8, "vehicle_flags")
+            .since(SaveLoadVersion::V176)
+            .until(SaveLoadVersion::V180),
+        SaveLoad::var(DataType::U16, "vehicle_flags").since(SaveLoadVersion::V180),
+    ];
+    desc.extend(orders_desc("orders"));
+    desc
+}
+
+/// Order of the BKOR fields in savegames without a table header
+/// (matches C++ _order_backup_sl_compat)
+fn order_backup_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::var("user"),
+        SaveLoadCompat::var("tile"),
+        SaveLoadCompat::var("group"),
+        SaveLoadCompat::var("service_interval"),
+        SaveLoadCompat::var("name"),
+        SaveLoadCompat::null(2, SaveLoadVersion::MinVersion, SaveLoadVersion::V192),
+        SaveLoadCompat::var("clone"),
+        SaveLoadCompat::var("cur_real_order_index"),
+        SaveLoadCompat::var("cur_implicit_order_index"),
+        SaveLoadCompat::var("current_order_time"),
+        SaveLoadCompat::var("lateness_counter"),
+        SaveLoadCompat::var("timetable_start"),
+        SaveLoadCompat::var("vehicle_flags"),
+        SaveLoadCompat::var("orders"),
+    ]
+}
+
+/// Builds the ORDR pool while saving before SaveLoadVersion::OrdersOwnedByOrderlist
+#[derive(Default)]
+struct OldOrderPool {
+    records: Vec<(usize, Record)>,
+}
+
+impl OldOrderPool {
+    /// Add a chain of orders and return the reference to its first order
+    fn add_chain(&mut self, orders: &[Order]) -> u32 {
+        let first = self.records.len();
+        for (i, order) in orders.iter().enumerate() {
+            let index = first + i;
+            let next = if i + 1 < orders.len() {
+                to_reference(Some(index as u32 + 1))
+            } else {
+                0
+            };
+            let record = order_record(order).with("next", next);
+            self.records.push((index, record));
+        }
+        if orders.is_empty() {
+            0
+        } else {
+            to_reference(Some(first as u32))
+        }
+    }
+}
+
+/// Add the orders to a record in the layout of `version`
+fn with_orders(
+    record: Record,
+    key: &str,
+    orders: &[Order],
+    pool: &mut OldOrderPool,
+    version: u16,
+) -> Record {
+    if version < SaveLoadVersion::OrdersOwnedByOrderlist {
+        record.with(key, pool.add_chain(orders))
+    } else {
+        record.with(
+            "orders",
+            orders.iter().map(order_record).collect::<Vec<_>>(),
+        )
+    }
+}
+
+fn backup_record(backup: &OrderBackup, pool: &mut OldOrderPool, version: u16) -> Record {
+    let record = Record::default()
+        .with("user", backup.user)
+        .with("tile", backup.tile.0)
+        .with("group", backup.group.0)
+        .with("service_interval", backup.service_interval)
+        .with("name", backup.name.as_str())
+        .with("clone", to_reference(backup.clone.map(|v| v.0)))
+        .with("cur_real_order_index", backup.cur_real_order_index)
+        .with("cur_implicit_order_index", backup.cur_implicit_order_index)
+        .with("current_order_time", backup.current_order_time)
+        .with("lateness_counter", backup.lateness_counter);
+    let record = if version < SaveLoadVersion::TimetableStartTicksFix {
+        record.with("timetable_start", backup.timetable_start as i32)
+    } else {
+        record.with("timetable_start", backup.timetable_start)
+    };
+    let record = record.with("vehicle_flags", backup.vehicle_flags);
+    with_orders(record, "orders", &backup.orders, pool, version)
+}
+
+/// Write the BKOR, ORDR and ORDL chunks in the layout of the writer's savegame version
+///
+/// ORDR is only written before SaveLoadVersion::OrdersOwnedByOrderlist; its
+/// pool is numbered in the order of the lists followed by the backups.
+pub fn save_orders(
+    writer: &mut SavegameWriter,
+    lists: &[OrderList],
+    backups: &[OrderBackup],
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let mut pool = OldOrderPool::default();
+    let list_records: Vec<(usize, Record)> = lists
+        .iter()
+        .map(|list| {
+            let record = with_orders(Record::default(), "first", &list.orders, &mut pool, version);
+            (list.index as usize, record)
+        })
+        .collect();
+    let backup_records: Vec<(usize, Record)> = backups
+        .iter()
+        .map(|backup| {
+            (
+                backup.index as usize,
+                backup_record(backup, &mut pool, version),
+            )
+        })
+        .collect();
+
+    writer.add_table_records(
+        b"BKOR",
+        ChunkType::Table,
+        &table_header(&order_backup_desc(), version),
+        &backup_records,
+    )?;
+    if version < SaveLoadVersion::OrdersOwnedByOrderlist {
+        writer.add_table_records(
+            b"ORDR",
+            ChunkType::Table,
+            &table_header(&old_order_desc(), version),
+            &pool.records,
+        )?;
+    }
+    writer.add_table_records(
+        b"ORDL",
+        ChunkType::Table,
+        &table_header(&order_list_desc(), version),
+        &list_records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::table::Value;
+    use crate::types::CompressionType;
+    use openttd_core::order::{OrderConditionComparator, OrderConditionVariable};
+    use openttd_core::types::StationID;
+
+    fn sample_lists() -> Vec<OrderList> {
+        let mut station = Order::goto_station(StationID(4));
+        station.wait_time = 120;
+        station.set_wait_timetabled(true);
+        let mut conditional = Order::conditional(0);
+        conditional.set_condition_variable(OrderConditionVariable::Age);
+        conditional.set_condition_comparator(OrderConditionComparator::MoreThan);
+        conditional.set_condition_value(20);
+        vec![
+            OrderList::new(
+                0,
+                vec![station, conditional, Order::goto_waypoint(StationID(9))],
+            ),
+            OrderList::new(2, vec![]),
+            OrderList::new(3, vec![Order::implicit(StationID(1))]),
+        ]
+    }
+
+    fn sample_backups() -> Vec<OrderBackup> {
+        vec![OrderBackup {
+            index: 1,
+            user: 7,
+            tile: TileIndex(0x1234),
+            group: GroupID::DEFAULT,
+            service_interval: 150,
+            name: "Bus 3".into(),
+            clone: Some(VehicleID(12)),
+            cur_real_order_index: 1,
+            cur_implicit_order_index: 1,
+            current_order_time: 300,
+            lateness_counter: -40,
+            timetable_start: 86_400,
+            vehicle_flags: 0x10,
+            orders: vec![Order::goto_station(StationID(2)), Order::dummy()],
+        }]
+    }
+
+    fn round_trip(version: u16) -> Vec<Chunk> {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_orders(&mut writer, &sample_lists(), &sample_backups()).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        assert_eq!(load_order_lists(&chunks, version).unwrap(), sample_lists());
+        assert_eq!(
+            load_order_backups(&chunks, version).unwrap(),
+            sample_backups()
+        );
+        chunks
+    }
+
+    #[test]
+    fn test_orders_round_trip() {
+        let chunks = round_trip(SaveLoadVersion::CURRENT.into());
+        let tags: Vec<&str> = chunks.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(tags, ["BKOR", "ORDL"]);
+        let records = chunks[1].decode_records().unwrap();
+        assert_eq!(records[0].1.get_structs("orders").count(), 3);
+    }
+
+    #[test]
+    fn test_old_orders_round_trip() {
+        let chunks = round_trip(SaveLoadVersion::TimetableStartTicksFix as u16 - 1);
+        let tags: Vec<&str> = chunks.iter().map(|c| c.tag.as_str()).collect();
+        assert_eq!(tags, ["BKOR", "ORDR", "ORDL"]);
+
+        // The lists are numbered before the backups, empty lists have no first order
+        let lists = chunks[2].decode_records().unwrap();
+        let first: Vec<Option<i64>> = lists.iter().map(|(_, r)| r.get_i64("first")).collect();
+        assert_eq!(first, [Some(1), Some(0), Some(4)]);
+        let pool = chunks[1].decode_records().unwrap();
+        let next: Vec<Option<i64>> = pool.iter().map(|(_, r)| r.get_i64("next")).collect();
+        assert_eq!(next, [Some(2), Some(3), Some(0), Some(0), Some(6), Some(0)]);
+    }
+
+    #[test]
+    fn test_invalid_order_chains() {
+        let version = SaveLoadVersion::OrdersOwnedByOrderlist as u16 - 1;
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_orders(&mut writer, &sample_lists(), &[]).unwrap();
+        let data = writer.finalize().unwrap();
+        let mut chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        let pool = load_old_orders(&chunks, version).unwrap();
+        assert!(old_order_chain(&pool, 9).is_err());
+
+        // Point the last order of the first list back to its first order
+        let mut records = chunks[1].decode_records().unwrap();
+        let next = records[2].1.fields.iter_mut().find(|(k, _)| k == "next");
+        next.unwrap().1 = Value::U32(1);
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        writer
+            .add_table_records(
+                b"ORDR",
+                ChunkType::Table,
+                &table_header(&old_order_desc(), version),
+                &records,
+            )
+            .unwrap();
+        let data = writer.finalize().unwrap();
+        chunks[1] = SavegameReader::new(&data).unwrap().read_chunks().unwrap()[0].clone();
+        assert!(load_order_lists(&chunks, version).is_err());
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_orders(&mut writer, &sample_lists(), &[]),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
@@ -1,10 +1,15 @@
 /// OpenTTD savegame reader and writer
 use crate::chunk::{
-    parse_array_chunk, parse_riff_chunk, parse_table_chunk, ChunkHeader, ChunkType,
+    parse_array_chunk, parse_riff_chunk, parse_table_chunk, ChunkHeader, ChunkType, TableHeader,
 };
+use crate::gamma;
 use crate::header;
-use crate::types::CompressionType;
+use crate::lzo;
+use crate::table::{self, Record};
+use crate::types::{CompressionType, SavegameFormat};
+use crate::version::{self, SaveLoad, SaveLoadCompat, SaveLoadVersion};
 use flate2::read::ZlibDecoder;
+use lzma_rust2::XzReader;
 use openttd_core::error::CoreError;
 use std::io::Read;
 use thiserror::Error;
@@ -17,28 +22,122 @@ pub enum SavegameError {
     Header(#[from] header::SavegameError),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
-    #[error("lzma error: {0}")]
-    Lzma(#[from] lzma_rs::error::Error),
+    #[error("lzo error: {0}")]
+    Lzo(#[from] lzo::LzoError),
     #[error("unsupported compression: {0:?}")]
     UnsupportedCompression(CompressionType),
     #[error("invalid savegame format")]
     InvalidFormat,
+    #[error("chunk {0} is not a table")]
+    NotATable(String),
+    #[error("chunk too large: {0} bytes")]
+    ChunkTooLarge(usize),
+    #[error("array index {0} is not in ascending order")]
+    IndexOutOfOrder(usize),
+    #[error("missing chunk {0}")]
+    MissingChunk(String),
+    #[error("unsupported savegame version {0}")]
+    UnsupportedVersion(u16),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid savegame format: {0}")]
+    InvalidSavegameFormat(String),
 }
 
 /// A parsed chunk from a savegame
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Chunk {
     pub tag: String,
     pub chunk_type: ChunkType,
     pub data: ChunkData,
 }
 
-#[derive(Debug)]
+impl Chunk {
+    /// Decode every record of a TABLE or SPARSE_TABLE chunk into typed values
+    pub fn decode_records(&self) -> Result<Vec<(usize, Record)>, SavegameError> {
+        match &self.data {
+            ChunkData::Table { header, records } => Ok(table::decode_records(header, records)?),
+            _ => Err(SavegameError::NotATable(self.tag.clone())),
+        }
+    }
+
+    /// Decode every record of a chunk: by its table header if it has one,
+    /// otherwise as laid out by the field declarations for `version`. A RIFF
+    /// chunk holds a single record with index 0.
+    pub fn decode_records_with(
+        &self,
+        desc: &[SaveLoad],
+        version: u16,
+    ) -> Result<Vec<(usize, Record)>, SavegameError> {
+        let decode = |data: &[u8]| version::decode_array_record(desc, version, data);
+        match &self.data {
+            ChunkData::Table { .. } => self.decode_records(),
+            ChunkData::Array(records) => Ok(records
+                .iter()
+                .map(|(index, data)| Ok((*index, decode(data)?)))
+                .collect::<Result<_, CoreError>>()?),
+            ChunkData::Riff(data) => Ok(vec![(0, decode(data)?)]),
+        }
+    }
+
+    /// Encode the chunk as stored in the uncompressed savegame body
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SavegameError> {
+        let mut writer = SavegameWriter::new(0, CompressionType::None);
+        writer.add_chunk(self)?;
+        Ok(writer.chunks)
+    }
+
+    /// Decode a single chunk as returned by `to_bytes`
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SavegameError> {
+        match parse_chunk(data)? {
+            Some((chunk, len)) if len == data.len() => Ok(chunk),
+            _ => Err(SavegameError::InvalidFormat),
+        }
+    }
+}
+
+/// Find a chunk by tag
+pub(crate) fn find_chunk<'a>(chunks: &'a [Chunk], tag: &[u8; 4]) -> Option<&'a Chunk> {
+    chunks.iter().find(|c| c.tag.as_bytes() == tag)
+}
+
+/// Decode the records of a chunk, which has a table header from TableChunks
+/// on and is laid out by `desc` in the order of `compat` before; a missing
+/// chunk has no records
+pub(crate) fn chunk_records(
+    chunks: &[Chunk],
+    tag: &[u8; 4],
+    version: u16,
+    desc: &[SaveLoad],
+    compat: &[SaveLoadCompat],
+) -> Result<Vec<(usize, Record)>, SavegameError> {
+    match find_chunk(chunks, tag) {
+        None => Ok(Vec::new()),
+        Some(chunk) if version < SaveLoadVersion::TableChunks => {
+            let records =
+                chunk.decode_records_with(&version::compat_desc(desc, compat), version)?;
+            // Like C++, fail on records that are longer than their description
+            if let Some((index, record)) = records.iter().find(|(_, r)| !r.trailing.is_empty()) {
+                return Err(CoreError::InvalidData(format!(
+                    "{}: record {} has {} bytes after its fields",
+                    chunk.tag,
+                    index,
+                    record.trailing.len()
+                ))
+                .into());
+            }
+            Ok(records)
+        }
+        Some(chunk) => chunk.decode_records(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ChunkData {
     Riff(Vec<u8>),
     Array(Vec<(usize, Vec<u8>)>),
     Table {
-        header: crate::chunk::TableHeader,
+        header: TableHeader,
         records: Vec<(usize, Vec<u8>)>,
     },
 }
@@ -72,13 +171,12 @@ impl SavegameReader {
             }
             CompressionType::Lzma => {
                 // OTTX format uses XZ compression (LZMA2)
+                let mut decoder = XzReader::new(compressed_data, false);
                 let mut decompressed = Vec::new();
-                lzma_rs::xz_decompress(&mut compressed_data.as_ref(), &mut decompressed)?;
+                decoder.read_to_end(&mut decompressed)?;
                 decompressed
             }
-            CompressionType::Lzo => {
-                return Err(SavegameError::UnsupportedCompression(CompressionType::Lzo));
-            }
+            CompressionType::Lzo => lzo::decompress_blocks(compressed_data, header.version)?,
         };
 
         Ok(Self {
@@ -98,89 +196,105 @@ impl SavegameReader {
         let mut offset = 0;
         let data = &self.decompressed_data;
 
-        loop {
-            // Check if we have enough data for a header
-            if offset + 5 > data.len() {
-                break;
-            }
-
-            // Parse chunk header
-            let (header, bytes_read) = ChunkHeader::parse(&data[offset..])?;
-            offset += bytes_read;
-
-            // Check for end marker
-            if header.is_end_marker() {
-                break;
-            }
-
-            // Parse chunk data based on type
-            let chunk_data = match header.chunk_type {
-                ChunkType::Riff => {
-                    let (data, bytes_read) = parse_riff_chunk(&header, &data[offset..])?;
+        // Stop when there is not enough data left for a chunk header
+        while offset + 5 <= data.len() {
+            match parse_chunk(&data[offset..])? {
+                Some((chunk, bytes_read)) => {
+                    chunks.push(chunk);
                     offset += bytes_read;
-                    ChunkData::Riff(data)
                 }
-                ChunkType::Array | ChunkType::SparseArray => {
-                    let (items, bytes_read) = parse_array_chunk(&header, &data[offset..])?;
-                    offset += bytes_read;
-                    ChunkData::Array(items)
-                }
-                ChunkType::Table | ChunkType::SparseTable => {
-                    let (table_header, records, bytes_read) =
-                        parse_table_chunk(&header, &data[offset..])?;
-                    offset += bytes_read;
-                    ChunkData::Table {
-                        header: table_header,
-                        records,
-                    }
-                }
-            };
-
-            chunks.push(Chunk {
-                tag: header.tag_string(),
-                chunk_type: header.chunk_type,
-                data: chunk_data,
-            });
+                None => break,
+            }
         }
 
         Ok(chunks)
     }
 }
 
+/// Parse the chunk at the start of `data`, returning it with the number of
+/// bytes it takes up, or `None` at the end marker
+fn parse_chunk(data: &[u8]) -> Result<Option<(Chunk, usize)>, SavegameError> {
+    let (header, mut offset) = ChunkHeader::parse(data)?;
+
+    // Check for end marker
+    if header.is_end_marker() {
+        return Ok(None);
+    }
+
+    // Parse chunk data based on type
+    let chunk_data = match header.chunk_type {
+        ChunkType::Riff => {
+            let (data, bytes_read) = parse_riff_chunk(&header, &data[offset..])?;
+            offset += bytes_read;
+            ChunkData::Riff(data)
+        }
+        ChunkType::Array | ChunkType::SparseArray => {
+            let (items, bytes_read) = parse_array_chunk(&header, &data[offset..])?;
+            offset += bytes_read;
+            ChunkData::Array(items)
+        }
+        ChunkType::Table | ChunkType::SparseTable => {
+            let (table_header, records, bytes_read) = parse_table_chunk(&header, &data[offset..])?;
+            offset += bytes_read;
+            ChunkData::Table {
+                header: table_header,
+                records,
+            }
+        }
+    };
+
+    let chunk = Chunk {
+        tag: header.tag_string(),
+        chunk_type: header.chunk_type,
+        data: chunk_data,
+    };
+    Ok(Some((chunk, offset)))
+}
+
 /// Writer functionality for creating savegames
 pub struct SavegameWriter {
     header: header::SavegameHeader,
+    level: u8,
     chunks: Vec<u8>,
 }
 
 impl SavegameWriter {
-    /// Create a new savegame writer
+    /// Create a new savegame writer, compressing at the default level
     pub fn new(version: u16, compression: CompressionType) -> Self {
+        Self::with_format(version, compression.into())
+    }
+
+    /// Create a new savegame writer with an explicit compression level
+    pub fn with_format(version: u16, format: SavegameFormat) -> Self {
         Self {
             header: header::SavegameHeader {
-                compression,
+                compression: format.compression,
                 version,
                 flags: 0,
             },
+            level: format.level,
             chunks: Vec::new(),
         }
     }
 
+    /// Savegame version being written
+    pub fn version(&self) -> u16 {
+        self.header.version
+    }
+
     /// Add a RIFF chunk
     pub fn add_riff_chunk(&mut self, tag: &[u8; 4], data: &[u8]) -> Result<(), SavegameError> {
+        // The length has 28 bits: 24 after the mode byte and 4 in its upper half
+        let length = data.len();
+        if length >= (1 << 28) {
+            return Err(SavegameError::ChunkTooLarge(length));
+        }
+
         // Write chunk tag
         self.chunks.extend_from_slice(tag);
 
-        // Calculate mode byte and length encoding
-        let length = data.len();
-        let mode_byte = if length < (1 << 24) {
-            // Length fits in 24 bits
-            0x00 // RIFF type in lower 4 bits
-        } else {
-            // Use upper 4 bits for high part of length
-            ((length >> 24) as u8) << 4
-        };
-        self.chunks.push(mode_byte);
+        // Mode byte: RIFF type in the lower 4 bits, length bits 24..28 in the upper
+        self.chunks.push(((length >> 24) as u8) << 4);
 
         // Write 24-bit length (big endian, as per OpenTTD format)
         self.chunks.push(((length >> 16) & 0xFF) as u8);
@@ -193,10 +307,131 @@ impl SavegameWriter {
         Ok(())
     }
 
+    /// Add an ARRAY chunk; indices must be ascending, gaps become empty slots
+    pub fn add_array_chunk(
+        &mut self,
+        tag: &[u8; 4],
+        items: &[(usize, Vec<u8>)],
+    ) -> Result<(), SavegameError> {
+        self.write_chunk(tag, ChunkType::Array, None, items)
+    }
+
+    /// Add a SPARSE_ARRAY chunk; every item is stored with its index
+    pub fn add_sparse_array_chunk(
+        &mut self,
+        tag: &[u8; 4],
+        items: &[(usize, Vec<u8>)],
+    ) -> Result<(), SavegameError> {
+        self.write_chunk(tag, ChunkType::SparseArray, None, items)
+    }
+
+    /// Add a TABLE chunk from already encoded records
+    pub fn add_table_chunk(
+        &mut self,
+        tag: &[u8; 4],
+        header: &TableHeader,
+        records: &[(usize, Vec<u8>)],
+    ) -> Result<(), SavegameError> {
+        self.write_chunk(tag, ChunkType::Table, Some(header), records)
+    }
+
+    /// Add a SPARSE_TABLE chunk from already encoded records
+    pub fn add_sparse_table_chunk(
+        &mut self,
+        tag: &[u8; 4],
+        header: &TableHeader,
+        records: &[(usize, Vec<u8>)],
+    ) -> Result<(), SavegameError> {
+        self.write_chunk(tag, ChunkType::SparseTable, Some(header), records)
+    }
+
+    /// Add a TABLE or SPARSE_TABLE chunk, encoding typed records against the header
+    pub fn add_table_records(
+        &mut self,
+        tag: &[u8; 4],
+        chunk_type: ChunkType,
+        header: &TableHeader,
+        records: &[(usize, Record)],
+    ) -> Result<(), SavegameError> {
+        if !matches!(chunk_type, ChunkType::Table | ChunkType::SparseTable) {
+            return Err(SavegameError::NotATable(
+                String::from_utf8_lossy(tag).into(),
+            ));
+        }
+        let encoded = records
+            .iter()
+            .map(|(index, record)| Ok((*index, table::encode_record(header, record)?)))
+            .collect::<Result<Vec<_>, CoreError>>()?;
+        self.write_chunk(tag, chunk_type, Some(header), &encoded)
+    }
+
+    /// Add a chunk as returned by `SavegameReader::read_chunks`
+    pub fn add_chunk(&mut self, chunk: &Chunk) -> Result<(), SavegameError> {
+        let tag: [u8; 4] = chunk
+            .tag
+            .as_bytes()
+            .try_into()
+            .map_err(|_| SavegameError::InvalidFormat)?;
+        match (&chunk.data, chunk.chunk_type) {
+            (ChunkData::Riff(data), ChunkType::Riff) => self.add_riff_chunk(&tag, data),
+            (ChunkData::Array(items), ChunkType::Array | ChunkType::SparseArray) => {
+                self.write_chunk(&tag, chunk.chunk_type, None, items)
+            }
+            (ChunkData::Table { header, records }, ChunkType::Table | ChunkType::SparseTable) => {
+                self.write_chunk(&tag, chunk.chunk_type, Some(header), records)
+            }
+            _ => Err(SavegameError::InvalidFormat),
+        }
+    }
+
+    /// Write an array or table chunk, mirroring `SlSetLength` in saveload.cpp
+    fn write_chunk(
+        &mut self,
+        tag: &[u8; 4],
+        chunk_type: ChunkType,
+        header: Option<&TableHeader>,
+        items: &[(usize, Vec<u8>)],
+    ) -> Result<(), SavegameError> {
+        let sparse = matches!(chunk_type, ChunkType::SparseArray | ChunkType::SparseTable);
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(tag);
+        buf.push(chunk_type as u8);
+        if let Some(header) = header {
+            buf.extend_from_slice(&header.write());
+        }
+
+        let mut next_index = 0;
+        for (index, data) in items {
+            if sparse {
+                let index_bytes = gamma::encode_gamma(*index as u64);
+                let length = data.len() + 1 + index_bytes.len();
+                buf.extend_from_slice(&gamma::encode_gamma(length as u64));
+                buf.extend_from_slice(&index_bytes);
+            } else {
+                if *index < next_index {
+                    return Err(SavegameError::IndexOutOfOrder(*index));
+                }
+                for _ in next_index..*index {
+                    buf.extend_from_slice(&gamma::encode_gamma(1));
+                }
+                next_index = index + 1;
+                buf.extend_from_slice(&gamma::encode_gamma(data.len() as u64 + 1));
+            }
+            buf.extend_from_slice(data);
+        }
+
+        // Terminate the list
+        buf.extend_from_slice(&gamma::encode_gamma(0));
+
+        self.chunks.extend_from_slice(&buf);
+        Ok(())
+    }
+
     /// Finalize the savegame and return the compressed data
     pub fn finalize(mut self) -> Result<Vec<u8>, SavegameError> {
         // Add end-of-savegame marker
-        self.chunks.extend_from_slice(&[0, 0, 0, 0, 0]);
+        self.chunks.extend_from_slice(&[0, 0, 0, 0]);
 
         // Build header using the header's write method
         let mut result = self.header.write();
@@ -209,18 +444,22 @@ impl SavegameWriter {
                 use flate2::Compression;
                 use std::io::Write;
 
-                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(self.level.into()));
                 encoder.write_all(&self.chunks)?;
                 encoder.finish()?
             }
             CompressionType::Lzma => {
-                let mut compressed = Vec::new();
-                lzma_rs::lzma_compress(&mut self.chunks.as_slice(), &mut compressed)?;
-                compressed
-            }
-            CompressionType::Lzo => {
-                return Err(SavegameError::UnsupportedCompression(CompressionType::Lzo));
+                use lzma_rust2::{CheckType, XzOptions, XzWriter};
+                use std::io::Write;
+
+                // Like `lzma_easy_encoder` with LZMA_CHECK_CRC32 in the C++ LZMASaveFilter
+                let mut options = XzOptions::with_preset(self.level.into());
+                options.set_check_sum_type(CheckType::Crc32);
+                let mut encoder = XzWriter::new(Vec::new(), options)?;
+                encoder.write_all(&self.chunks)?;
+                encoder.finish()?
             }
+            CompressionType::Lzo => lzo::compress_blocks(&self.chunks, self.version()),
         };
 
         result.extend_from_slice(&compressed);
@@ -282,4 +521,166 @@ mod tests {
             _ => panic!("Expected RIFF chunk"),
         }
     }
+
+    #[test]
+    fn test_savegame_formats() {
+        let payload: Vec<u8> = (0..20000u32).map(|i| (i % 251) as u8).collect();
+        for format in [
+            "none", "zlib:0", "zlib:9", "lzo", "lzma:0", "lzma:2", "lzma:9",
+        ] {
+            let mut writer = SavegameWriter::with_format(295, format.parse().unwrap());
+            writer.add_riff_chunk(b"DATA", &payload).unwrap();
+            let data = writer.finalize().unwrap();
+
+            let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+            assert_eq!(
+                chunks[0].data,
+                ChunkData::Riff(payload.clone()),
+                "{}",
+                format
+            );
+        }
+
+        let data = SavegameWriter::new(295, CompressionType::Lzma)
+            .finalize()
+            .unwrap();
+        assert_eq!(&data[0..4], b"OTTX");
+        // XZ stream header magic, then stream flags selecting a CRC32 check
+        assert_eq!(&data[8..14], b"\xFD7zXZ\0");
+        assert_eq!(&data[14..16], [0, 1]);
+    }
+
+    #[test]
+    fn test_lzo_round_trip() {
+        // Test with LZO compression, spanning several 8 KiB blocks
+        let payload: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+        let mut writer = SavegameWriter::new(295, CompressionType::Lzo);
+        writer.add_riff_chunk(b"DATA", &payload).unwrap();
+        writer.add_riff_chunk(b"MORE", b"LZO data test").unwrap();
+        let data = writer.finalize().unwrap();
+
+        // Verify header
+        assert_eq!(&data[0..4], b"OTTD");
+
+        // Read it back
+        let reader = SavegameReader::new(&data).unwrap();
+        assert_eq!(reader.header().compression, CompressionType::Lzo);
+
+        let chunks = reader.read_chunks().unwrap();
+        assert_eq!(chunks.len(), 2);
+
+        match (&chunks[0].data, &chunks[1].data) {
+            (ChunkData::Riff(first), ChunkData::Riff(second)) => {
+                assert_eq!(first, &payload);
+                assert_eq!(second, b"LZO data test");
+            }
+            _ => panic!("Expected RIFF chunks"),
+        }
+
+        // Version 0 saves frame their blocks little-endian
+        let mut writer = SavegameWriter::new(0, CompressionType::Lzo);
+        writer.add_riff_chunk(b"DATA", &payload).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        assert_eq!(chunks[0].data, ChunkData::Riff(payload));
+    }
+
+    #[test]
+    fn test_lzo_corrupt_block() {
+        let mut writer = SavegameWriter::new(295, CompressionType::Lzo);
+        writer.add_riff_chunk(b"DATA", b"Corrupt me").unwrap();
+        let mut data = writer.finalize().unwrap();
+
+        let last = data.len() - 1;
+        data[last] ^= 0xFF;
+        assert!(matches!(
+            SavegameReader::new(&data),
+            Err(SavegameError::Lzo(_))
+        ));
+    }
+
+    #[test]
+    fn test_array_chunks_round_trip() {
+        let items = vec![(0, vec![1, 2]), (3, vec![3]), (200, vec![0; 300])];
+
+        let mut writer = SavegameWriter::new(295, CompressionType::None);
+        writer.add_array_chunk(b"ARRY", &items).unwrap();
+        writer.add_sparse_array_chunk(b"SPRS", &items).unwrap();
+        let data = writer.finalize().unwrap();
+
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].chunk_type, ChunkType::Array);
+        assert_eq!(chunks[1].chunk_type, ChunkType::SparseArray);
+        for chunk in &chunks {
+            assert_eq!(chunk.data, ChunkData::Array(items.clone()));
+        }
+
+        // Dense arrays fill the gaps with empty slots
+        assert_eq!(&data[8..17], &[b'A', b'R', b'R', b'Y', 1, 3, 1, 2, 1]);
+    }
+
+    #[test]
+    fn test_table_chunks_round_trip() {
+        use crate::chunk::{DataType, TableField};
+        use crate::table::Value;
+
+        let header = TableHeader {
+            fields: vec![
+                TableField {
+                    data_type: DataType::U16,
+                    key: "id".into(),
+                    is_list: false,
+                    sub_header: None,
+                },
+                TableField {
+                    data_type: DataType::String,
+                    key: "name".into(),
+                    is_list: true,
+                    sub_header: None,
+                },
+            ],
+        };
+        let record = |id: u16, name: &str| Record {
+            fields: vec![
+                ("id".into(), Value::U16(id)),
+                ("name".into(), Value::String(name.into())),
+            ],
+            trailing: Vec::new(),
+        };
+        let records = vec![(1, record(7, "Foo")), (5, record(9, "Bar"))];
+
+        let mut writer = SavegameWriter::new(295, CompressionType::Zlib);
+        writer
+            .add_table_records(b"TABL", ChunkType::Table, &header, &records)
+            .unwrap();
+        writer
+            .add_table_records(b"SPTB", ChunkType::SparseTable, &header, &records)
+            .unwrap();
+        let data = writer.finalize().unwrap();
+
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        assert_eq!(chunks.len(), 2);
+        for chunk in &chunks {
+            match &chunk.data {
+                ChunkData::Table { header: parsed, .. } => assert_eq!(parsed, &header),
+                _ => panic!("Expected table chunk"),
+            }
+            assert_eq!(chunk.decode_records().unwrap(), records);
+        }
+
+        // Writing the parsed chunks again gives the same stream
+        let mut rewriter = SavegameWriter::new(295, CompressionType::Zlib);
+        for chunk in &chunks {
+            rewriter.add_chunk(chunk).unwrap();
+        }
+        assert_eq!(rewriter.finalize().unwrap(), data);
+    }
+
+    #[test]
+    fn test_array_chunk_index_order() {
+        let mut writer = SavegameWriter::new(295, CompressionType::None);
+        let result = writer.add_array_chunk(b"ARRY", &[(2, vec![1]), (2, vec![2])]);
+        assert!(matches!(result, Err(SavegameError::IndexOutOfOrder(2))));
+    }
 }
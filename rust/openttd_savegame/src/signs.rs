@@ -0,0 +1,149 @@
+/// Loading and saving of the SIGN chunk
+///
+/// Text colours are saved since SignTextColours; older signs are white.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, signed, Record};
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::error::CoreError;
+use openttd_core::signs::{Sign, SignPool};
+use openttd_core::types::{Colours, Owner, SignID};
+
+fn sign_from_record(index: usize, record: &Record, version: u16) -> Result<Sign, CoreError> {
+    // All saved signs are valid, also those of old savegames without an owner
+    let owner = if version >= SaveLoadVersion::V6 {
+        Owner::try_from(int(record, "owner")? as u8)?
+    } else {
+        Owner::None
+    };
+    let owner = match owner {
+        Owner::Invalid if version < SaveLoadVersion::V83 => Owner::None,
+        owner => owner,
+    };
+    let mut sign = Sign::new(
+        SignID(index as u16),
+        owner,
+        signed(record, "x")? as i32,
+        signed(record, "y")? as i32,
+        signed(record, "z")? as i32,
+        record.get_str("name").unwrap_or_default(),
+    );
+    if version >= SaveLoadVersion::SignTextColours {
+        sign.text_colour = Colours::try_from(int(record, "text_colour")? as u8)?;
+    }
+    Ok(sign)
+}
+
+/// Load the signs from the SIGN chunk
+pub fn load_signs(chunks: &[Chunk], version: u16) -> Result<SignPool, SavegameError> {
+    let compat = ["name", "x", "y", "owner", "z"].map(SaveLoadCompat::var);
+    let signs = chunk_records(chunks, b"SIGN", version, &sign_desc(), &compat)?
+        .iter()
+        .map(|(index, record)| sign_from_record(*index, record, version))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(SignPool::from_signs(signs)?)
+}
+
+/// Field declarations of SIGN (matches C++ _sign_desc)
+fn sign_desc() -> Vec<SaveLoad> {
+    vec![
+        // Names of old savegames are string IDs, which are not kept
+        SaveLoad::var(DataType::U16, "name").until(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::String, "name").since(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::I16, "x").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::I16, "y").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::I32, "x").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::I32, "y").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U8, "owner").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U8, "z").until(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::I32, "z").since(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::U8, "text_colour").since(SaveLoadVersion::SignTextColours),
+    ]
+}
+
+fn sign_to_record(sign: &Sign) -> Record {
+    Record::default()
+        .with("name", sign.name.as_str())
+        .with("x", sign.x)
+        .with("y", sign.y)
+        .with("owner", sign.owner as u8)
+        .with("z", sign.z)
+        .with("text_colour", sign.text_colour as u8)
+}
+
+/// Write the SIGN chunk in the layout of the writer's savegame version
+pub fn save_signs(writer: &mut SavegameWriter, pool: &SignPool) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let records: Vec<(usize, Record)> = pool
+        .iter()
+        .map(|sign| (sign.index.0 as usize, sign_to_record(sign)))
+        .collect();
+
+    writer.add_table_records(
+        b"SIGN",
+        ChunkType::Table,
+        &table_header(&sign_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+
+    fn sample_pool() -> SignPool {
+        let depot = Sign::new(SignID(0), Owner::Company0, 160, 320, 8, "Depot");
+        let mut goal = Sign::new(SignID(3), Owner::Deity, 1024, 48, -16, "Goal");
+        goal.text_colour = Colours::Red;
+        SignPool::from_signs(vec![depot, goal]).unwrap()
+    }
+
+    fn round_trip(pool: &SignPool, version: u16) -> SignPool {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_signs(&mut writer, pool).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_signs(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_signs_round_trip() {
+        let pool = sample_pool();
+        assert_eq!(round_trip(&pool, SaveLoadVersion::CURRENT.into()), pool);
+
+        // Text colours are not saved before SignTextColours
+        let loaded = round_trip(&pool, SaveLoadVersion::TableChunks.into());
+        let goal = loaded.get(SignID(3)).unwrap();
+        assert_eq!(goal.text_colour, Colours::White);
+        assert_eq!(goal.z, -16);
+    }
+
+    #[test]
+    fn test_signs_invalid() {
+        let mut record = sign_to_record(sample_pool().get(SignID(3)).unwrap());
+        let colour = record
+            .fields
+            .iter_mut()
+            .find(|(key, _)| key == "text_colour")
+            .unwrap();
+        colour.1 = 0x40u8.into();
+        assert!(sign_from_record(3, &record, SaveLoadVersion::CURRENT.into()).is_err());
+
+        // Signs without a valid owner in old savegames belong to no one
+        let sign = Sign::new(SignID(1), Owner::Invalid, 0, 0, 0, "Sign");
+        let loaded = sign_from_record(1, &sign_to_record(&sign), 82).unwrap();
+        assert_eq!(loaded.owner, Owner::None);
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_signs(&mut writer, &sample_pool()),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
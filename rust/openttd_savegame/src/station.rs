@@ -0,0 +1,860 @@
+/// Loading and saving of the STNN chunk
+///
+/// Stations and waypoints share one pool and one chunk; the saved facilities
+/// byte decides which of the `normal` and `waypoint` sub-structs is present.
+/// References to other pools (towns, road stops, vehicles, cargo packets and
+/// persistent storage) are saved as index + 1, with 0 meaning none.
+///
+/// Savegames before SaveLoadVersion::V123 keep their stations in the old STNS
+/// chunk instead, which needs the conversions of the C++ afterload (buoys,
+/// docks, old cargo packets) to become stations; they are not supported.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, missing, part, reference, to_reference, Record, Value};
+use crate::version::{table_header, ListLength, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::cargopacket::StationCargoPackets;
+use openttd_core::error::CoreError;
+use openttd_core::map::TileIndex;
+use openttd_core::station::{
+    FlowShare, GoodsEntry, RoadStopTileData, SpecMapping, Station, TileArea, FACIL_WAYPOINT,
+    GES_ACCEPTANCE, NUM_CARGO,
+};
+use openttd_core::types::{CalendarDate, Owner, StationID, TownID, VehicleID};
+
+fn tile_area(record: &Record, prefix: &str) -> Result<TileArea, CoreError> {
+    Ok(TileArea {
+        tile: TileIndex(int(record, &format!("{}.tile", prefix))? as u32),
+        w: int(record, &format!("{}.w", prefix))? as u16,
+        h: int(record, &format!("{}.h", prefix))? as u16,
+    })
+}
+
+fn load_spec_list(record: &Record, key: &str) -> Result<Vec<SpecMapping>, CoreError> {
+    record
+        .get_structs(key)
+        .map(|spec| {
+            Ok(SpecMapping {
+                grfid: int(spec, "grfid")? as u32,
+                localidx: int(spec, "localidx")? as u16,
+            })
+        })
+        .collect()
+}
+
+fn load_roadstop_tile_data(record: &Record, key: &str) -> Result<Vec<RoadStopTileData>, CoreError> {
+    record
+        .get_structs(key)
+        .map(|data| {
+            Ok(RoadStopTileData {
+                tile: TileIndex(int(data, "tile")? as u32),
+                random_bits: int(data, "random_bits")? as u8,
+                animation_frame: int(data, "animation_frame")? as u8,
+            })
+        })
+        .collect()
+}
+
+fn load_packets(record: &Record, key: &str) -> Result<Vec<u32>, CoreError> {
+    Ok(record
+        .get_list(key)
+        .ok_or_else(|| missing(key))?
+        .iter()
+        .filter_map(|v| v.as_u64()?.checked_sub(1).map(|v| v as u32))
+        .collect())
+}
+
+fn load_goods(record: &Record, version: u16) -> Result<GoodsEntry, CoreError> {
+    let mut ge = GoodsEntry {
+        status: int(record, "status")? as u8,
+        time_since_pickup: int(record, "time_since_pickup")? as u8,
+        rating: int(record, "rating")? as u8,
+        last_speed: int(record, "last_speed")? as u8,
+        last_age: int(record, "last_age")? as u8,
+        ..GoodsEntry::default()
+    };
+    ge.acceptance = ge.status & GES_ACCEPTANCE != 0;
+    if version >= SaveLoadVersion::V150 {
+        ge.amount_fract = int(record, "amount_fract")? as u8;
+    }
+    if version >= SaveLoadVersion::V181 {
+        ge.cargo.reserved_count = int(record, "cargo.reserved_count")? as u32;
+    }
+
+    if version < SaveLoadVersion::V183 {
+        // Cargo waiting without a next hop, as there were no flows yet
+        let packets = load_packets(record, "packets")?;
+        if !packets.is_empty() {
+            ge.cargo.packets.push(StationCargoPackets {
+                next_hop: StationID::INVALID,
+                packets,
+            });
+        }
+        return Ok(ge);
+    }
+
+    ge.link_graph = int(record, "link_graph")? as u16;
+    ge.node = int(record, "node")? as u16;
+    ge.max_waiting_cargo = int(record, "max_waiting_cargo")? as u32;
+    for flow in record.get_structs("flow") {
+        ge.flows.push(FlowShare {
+            source: StationID(int(flow, "source")? as u16),
+            via: StationID(int(flow, "via")? as u16),
+            share: int(flow, "share")? as u32,
+            restricted: version >= SaveLoadVersion::V187 && int(flow, "restricted")? != 0,
+        });
+    }
+    for cargo in record.get_structs("cargo") {
+        ge.cargo.packets.push(StationCargoPackets {
+            next_hop: StationID(int(cargo, "first")? as u16),
+            packets: load_packets(cargo, "second")?,
+        });
+    }
+
+    Ok(ge)
+}
+
+/// Fill the fields shared by stations and waypoints (C++ BaseStation)
+fn load_base(station: &mut Station, record: &Record) -> Result<(), CoreError> {
+    station.xy = TileIndex(int(record, "xy")? as u32);
+    station.town = reference(record, "town")?.map_or(TownID::INVALID, |v| TownID(v as u16));
+    station.string_id = int(record, "string_id")? as u16;
+    station.custom_name = record.get_str("name").unwrap_or_default().into();
+    station.delete_ctr = int(record, "delete_ctr")? as u8;
+    station.owner = Owner::try_from(int(record, "owner")? as u8)?;
+    station.facilities = int(record, "facilities")? as u8;
+    station.build_date = CalendarDate(int(record, "build_date")? as i32);
+    station.random_bits = int(record, "random_bits")? as u16;
+    station.waiting_triggers = int(record, "waiting_triggers")? as u8;
+    Ok(())
+}
+
+fn load_normal(station: &mut Station, record: &Record, version: u16) -> Result<(), CoreError> {
+    load_base(station, part(record, "base")?)?;
+    station.train_station = tile_area(record, "train_station")?;
+    station.bus_stops = reference(record, "bus_stops")?.map(|v| v as u16);
+    station.truck_stops = reference(record, "truck_stops")?.map(|v| v as u16);
+    if version >= SaveLoadVersion::MultitileDocks {
+        station.ship_station = tile_area(record, "ship_station")?;
+        station.docking_station = tile_area(record, "docking_station")?;
+    }
+    station.airport_area.tile = TileIndex(int(record, "airport.tile")? as u32);
+    if version >= SaveLoadVersion::V140 {
+        station.airport_area = tile_area(record, "airport")?;
+    }
+    station.airport_type = int(record, "airport.type")? as u8;
+    station.airport_flags = int(record, "airport.flags")?;
+    if version >= SaveLoadVersion::V145 {
+        station.airport_layout = int(record, "airport.layout")? as u8;
+        station.airport_rotation = int(record, "airport.rotation")? as u8;
+    }
+    if version >= SaveLoadVersion::V161 {
+        station.airport_psa = reference(record, "airport.psa")?;
+    }
+    station.indtype = int(record, "indtype")? as u8;
+    station.time_since_load = int(record, "time_since_load")? as u8;
+    station.time_since_unload = int(record, "time_since_unload")? as u8;
+    station.last_vehicle_type = int(record, "last_vehicle_type")? as u8;
+    station.had_vehicle_of_type = int(record, "had_vehicle_of_type")? as u8;
+    station.loading_vehicles = record
+        .get_list("loading_vehicles")
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| v.as_u64()?.checked_sub(1).map(|v| VehicleID(v as u32)))
+        .collect();
+    if version >= SaveLoadVersion::V127 {
+        station.always_accepted = int(record, "always_accepted")?;
+    }
+    // Road stop tile data moved to the outer struct in SaveLoadVersion::RoadStopTileData
+    station.roadstop_tile_data = load_roadstop_tile_data(record, "speclist")?;
+
+    for (slot, goods) in station.goods.iter_mut().zip(record.get_structs("goods")) {
+        *slot = load_goods(goods, version)?;
+    }
+    Ok(())
+}
+
+fn load_waypoint(station: &mut Station, record: &Record, version: u16) -> Result<(), CoreError> {
+    load_base(station, part(record, "base")?)?;
+    station.town_cn = int(record, "town_cn")? as u16;
+    if version >= SaveLoadVersion::V124 {
+        station.train_station = tile_area(record, "train_station")?;
+    }
+    if version >= SaveLoadVersion::RoadWaypoints {
+        station.waypoint_flags = int(record, "waypoint_flags")? as u16;
+        station.road_waypoint_area = tile_area(record, "road_waypoint_area")?;
+    }
+    Ok(())
+}
+
+fn station_from_record(index: usize, record: &Record, version: u16) -> Result<Station, CoreError> {
+    let mut station = Station::new(StationID(index as u16), TileIndex::INVALID, Owner::None);
+
+    let facilities = int(record, "facilities")? as u8;
+    if facilities & FACIL_WAYPOINT != 0 {
+        load_waypoint(&mut station, part(record, "waypoint")?, version)?;
+    } else {
+        load_normal(&mut station, part(record, "normal")?, version)?;
+    }
+
+    station.speclist = load_spec_list(record, "speclist")?;
+    station.roadstop_speclist = load_spec_list(record, "roadstopspeclist")?;
+    station
+        .roadstop_tile_data
+        .extend(load_roadstop_tile_data(record, "roadstoptiledata")?);
+
+    Ok(station)
+}
+
+/// Load all stations and waypoints from the STNN chunk; savegames before
+/// SaveLoadVersion::V123, which save the STNS chunk, are not supported
+pub fn load_stations(chunks: &[Chunk], version: u16) -> Result<Vec<Station>, SavegameError> {
+    if version < SaveLoadVersion::V123 {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+    chunk_records(chunks, b"STNN", version, &station_desc(), &station_compat())?
+        .iter()
+        .map(|(index, record)| Ok(station_from_record(*index, record, version)?))
+        .collect()
+}
+
+fn tile_area_desc(prefix: &str) -> [SaveLoad; 3] {
+    [
+        SaveLoad::var(DataType::U32, &format!("{}.tile", prefix)),
+        SaveLoad::var(DataType::U8, &format!("{}.w", prefix)),
+        SaveLoad::var(DataType::U8, &format!("{}.h", prefix)),
+    ]
+}
+
+/// Fields of the C++ SlStationSpecList handler
+fn spec_list_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U32, "grfid").since(SaveLoadVersion::V27),
+        SaveLoad::var(DataType::U8, "localidx")
+            .since(SaveLoadVersion::V27)
+            .until(SaveLoadVersion::ExtendEntityMapping),
+        SaveLoad::var(DataType::U16, "localidx").since(SaveLoadVersion::ExtendEntityMapping),
+    ]
+}
+
+fn roadstop_tile_data_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U32, "tile"),
+        SaveLoad::var(DataType::U8, "random_bits"),
+        SaveLoad::var(DataType::U8, "animation_frame"),
+    ]
+}
+
+/// Fields of the C++ SlStationBase handler
+fn base_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U32, "xy"),
+        SaveLoad::var(DataType::U32, "town"),
+        SaveLoad::var(DataType::StringId, "string_id"),
+        SaveLoad::var(DataType::String, "name"),
+        SaveLoad::var(DataType::U8, "delete_ctr"),
+        SaveLoad::var(DataType::U8, "owner"),
+        SaveLoad::var(DataType::U8, "facilities"),
+        SaveLoad::var(DataType::I32, "build_date"),
+        SaveLoad::var(DataType::U16, "random_bits"),
+        SaveLoad::var(DataType::U8, "waiting_triggers"),
+        SaveLoad::var(DataType::U8, "num_specs").until(SaveLoadVersion::SaveloadListLength),
+    ]
+}
+
+/// Fields of the C++ SlStationGoods handler; before flows and next hops
+/// (SaveLoadVersion::V183) all cargo is a single list of packets, and before
+/// SaveLoadVersion::V68 a single amount described by `waiting_acceptance`
+fn goods_desc() -> Vec<SaveLoad> {
+    let flow = vec![
+        SaveLoad::var(DataType::U16, "source"),
+        SaveLoad::var(DataType::U16, "via"),
+        SaveLoad::var(DataType::U32, "share"),
+        SaveLoad::var(DataType::I8, "restricted").since(SaveLoadVersion::V187),
+    ];
+    let cargo = vec![
+        SaveLoad::var(DataType::U16, "first"),
+        SaveLoad::list(DataType::U32, "second"),
+    ];
+    vec![
+        SaveLoad::var(DataType::U16, "waiting_acceptance").until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U8, "status").since(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U8, "time_since_pickup"),
+        SaveLoad::var(DataType::U8, "rating"),
+        SaveLoad::var(DataType::U8, "cargo_source").until(SaveLoadVersion::V7),
+        SaveLoad::var(DataType::U16, "cargo_source")
+            .since(SaveLoadVersion::V7)
+            .until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U32, "cargo_source_xy")
+            .since(SaveLoadVersion::V44)
+            .until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U8, "cargo_days").until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U8, "last_speed"),
+        SaveLoad::var(DataType::U8, "last_age"),
+        SaveLoad::var(DataType::U32, "cargo_feeder_share")
+            .since(SaveLoadVersion::V14)
+            .until(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I64, "cargo_feeder_share")
+            .since(SaveLoadVersion::V65)
+            .until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U8, "amount_fract").since(SaveLoadVersion::V150),
+        SaveLoad::list(DataType::U32, "packets")
+            .since(SaveLoadVersion::V68)
+            .until(SaveLoadVersion::V183),
+        SaveLoad::var(DataType::U32, "old_num_dests")
+            .since(SaveLoadVersion::V183)
+            .until(SaveLoadVersion::SaveloadListLength),
+        SaveLoad::var(DataType::U32, "cargo.reserved_count").since(SaveLoadVersion::V181),
+        SaveLoad::var(DataType::U16, "link_graph").since(SaveLoadVersion::V183),
+        SaveLoad::var(DataType::U16, "node").since(SaveLoadVersion::V183),
+        SaveLoad::var(DataType::U32, "old_num_flows")
+            .since(SaveLoadVersion::V183)
+            .until(SaveLoadVersion::SaveloadListLength),
+        SaveLoad::var(DataType::U32, "max_waiting_cargo").since(SaveLoadVersion::V183),
+        SaveLoad::structs("flow", flow)
+            .length(ListLength::Field("old_num_flows".into()))
+            .since(SaveLoadVersion::V183),
+        SaveLoad::structs("cargo", cargo)
+            .length(ListLength::Field("old_num_dests".into()))
+            .since(SaveLoadVersion::V183),
+    ]
+}
+
+/// Fields of the C++ SlStationNormal handler
+fn normal_desc() -> Vec<SaveLoad> {
+    let mut desc = vec![SaveLoad::structs("base", base_desc()).compat(base_compat())];
+    desc.extend(tile_area_desc("train_station"));
+    desc.push(SaveLoad::var(DataType::U32, "bus_stops"));
+    desc.push(SaveLoad::var(DataType::U32, "truck_stops"));
+    for prefix in ["ship_station", "docking_station"] {
+        desc.extend(tile_area_desc(prefix).map(|sld| sld.since(SaveLoadVersion::MultitileDocks)));
+    }
+    let [tile, w, h] = tile_area_desc("airport");
+    desc.extend([
+        tile,
+        w.since(SaveLoadVersion::V140),
+        h.since(SaveLoadVersion::V140),
+        SaveLoad::var(DataType::U8, "airport.type"),
+        SaveLoad::var(DataType::U8, "airport.layout").since(SaveLoadVersion::V145),
+        SaveLoad::var(DataType::U64, "airport.flags"),
+        SaveLoad::var(DataType::U8, "airport.rotation").since(SaveLoadVersion::V145),
+        SaveLoad::array(DataType::U32, "storage", 16)
+            .since(SaveLoadVersion::V145)
+            .until(SaveLoadVersion::V161),
+        SaveLoad::var(DataType::U32, "airport.psa").since(SaveLoadVersion::V161),
+        SaveLoad::var(DataType::U8, "indtype"),
+        SaveLoad::var(DataType::U8, "time_since_load"),
+        SaveLoad::var(DataType::U8, "time_since_unload"),
+        SaveLoad::var(DataType::U8, "last_vehicle_type"),
+        SaveLoad::var(DataType::U8, "had_vehicle_of_type"),
+        SaveLoad::list(DataType::U32, "loading_vehicles"),
+        SaveLoad::var(DataType::U32, "always_accepted")
+            .since(SaveLoadVersion::V127)
+            .until(SaveLoadVersion::ExtendCargotypes),
+        SaveLoad::var(DataType::U64, "always_accepted").since(SaveLoadVersion::ExtendCargotypes),
+        SaveLoad::structs("speclist", roadstop_tile_data_desc())
+            .since(SaveLoadVersion::NewgrfRoadStops)
+            .until(SaveLoadVersion::RoadStopTileData),
+    ]);
+    // One goods entry per cargo type of the savegame version
+    let goods = SaveLoad::structs("goods", goods_desc()).compat(goods_compat());
+    desc.extend([
+        goods
+            .clone()
+            .length(ListLength::Fixed(12))
+            .until(SaveLoadVersion::V55),
+        goods
+            .clone()
+            .length(ListLength::Fixed(32))
+            .since(SaveLoadVersion::V55)
+            .until(SaveLoadVersion::ExtendCargotypes),
+        goods
+            .length(ListLength::Fixed(NUM_CARGO))
+            .since(SaveLoadVersion::ExtendCargotypes),
+    ]);
+    desc
+}
+
+/// Fields of the C++ SlStationWaypoint handler
+fn waypoint_desc() -> Vec<SaveLoad> {
+    let mut desc = vec![
+        SaveLoad::structs("base", base_desc()).compat(base_compat()),
+        SaveLoad::var(DataType::U16, "town_cn"),
+    ];
+    desc.extend(tile_area_desc("train_station").map(|sld| sld.since(SaveLoadVersion::V124)));
+    desc.push(SaveLoad::var(DataType::U16, "waypoint_flags").since(SaveLoadVersion::RoadWaypoints));
+    desc.extend(
+        tile_area_desc("road_waypoint_area").map(|sld| sld.since(SaveLoadVersion::RoadWaypoints)),
+    );
+    desc
+}
+
+/// Whether the `normal` struct is saved, which C++ SlStationNormal decides
+/// by the facilities byte at the start of the record
+fn normal_count(record: &Record) -> u64 {
+    (record.get_u64("facilities").unwrap_or_default() as u8 & FACIL_WAYPOINT == 0) as u64
+}
+
+fn waypoint_count(record: &Record) -> u64 {
+    1 - normal_count(record)
+}
+
+/// Number of station specs, saved in the base struct before
+/// SaveLoadVersion::SaveloadListLength
+fn num_specs(record: &Record) -> u64 {
+    ["normal", "waypoint"]
+        .into_iter()
+        .flat_map(|key| record.get_structs(key))
+        .flat_map(|station| station.get_structs("base"))
+        .find_map(|base| base.get_u64("num_specs"))
+        .unwrap_or_default()
+}
+
+/// Field declarations of STNN (matches C++ _station_desc)
+fn station_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U8, "facilities"),
+        SaveLoad::structs("normal", normal_desc())
+            .length(ListLength::Computed(normal_count))
+            .compat(normal_compat()),
+        SaveLoad::structs("waypoint", waypoint_desc())
+            .length(ListLength::Computed(waypoint_count))
+            .compat(waypoint_compat()),
+        SaveLoad::structs("speclist", spec_list_desc())
+            .length(ListLength::Computed(num_specs))
+            .since(SaveLoadVersion::V27),
+        SaveLoad::structs("roadstopspeclist", spec_list_desc())
+            .since(SaveLoadVersion::NewgrfRoadStops),
+        SaveLoad::structs("roadstoptiledata", roadstop_tile_data_desc())
+            .since(SaveLoadVersion::RoadStopTileData),
+    ]
+}
+
+/// Order of the SlStationGoods fields in savegames without a table header
+/// (matches C++ _station_goods_sl_compat)
+fn goods_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = vec![
+        SaveLoadCompat::var("waiting_acceptance"),
+        SaveLoadCompat::var("status"),
+        SaveLoadCompat::null(2, SaveLoadVersion::V51, SaveLoadVersion::V68),
+    ];
+    compat.extend(
+        [
+            "time_since_pickup",
+            "rating",
+            "cargo_source",
+            "cargo_source_xy",
+            "cargo_days",
+            "last_speed",
+            "last_age",
+            "cargo_feeder_share",
+            "amount_fract",
+            "packets",
+            "old_num_dests",
+            "cargo.reserved_count",
+            "link_graph",
+            "node",
+            "old_num_flows",
+            "max_waiting_cargo",
+            "flow",
+            "cargo",
+        ]
+        .map(SaveLoadCompat::var),
+    );
+    compat
+}
+
+/// Order of the SlStationBase fields in savegames without a table header
+/// (matches C++ _station_base_sl_compat)
+fn base_compat() -> Vec<SaveLoadCompat> {
+    [
+        "xy",
+        "town",
+        "string_id",
+        "name",
+        "delete_ctr",
+        "owner",
+        "facilities",
+        "build_date",
+        "random_bits",
+        "waiting_triggers",
+        "num_specs",
+    ]
+    .map(SaveLoadCompat::var)
+    .into()
+}
+
+/// Order of the SlStationNormal fields in savegames without a table header
+/// (matches C++ _station_normal_sl_compat)
+fn normal_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = [
+        "base",
+        "train_station.tile",
+        "train_station.w",
+        "train_station.h",
+    ]
+    .map(SaveLoadCompat::var)
+    .to_vec();
+    compat.extend([
+        SaveLoadCompat::var("bus_stops"),
+        SaveLoadCompat::var("truck_stops"),
+        SaveLoadCompat::null(
+            4,
+            SaveLoadVersion::MinVersion,
+            SaveLoadVersion::MultitileDocks,
+        ),
+    ]);
+    compat.extend(
+        [
+            "ship_station.tile",
+            "ship_station.w",
+            "ship_station.h",
+            "docking_station.tile",
+            "docking_station.w",
+            "docking_station.h",
+            "airport.tile",
+            "airport.w",
+            "airport.h",
+            "airport.type",
+            "airport.layout",
+            "airport.flags",
+            "airport.rotation",
+            "storage",
+            "airport.psa",
+            "indtype",
+            "time_since_load",
+            "time_since_unload",
+            "last_vehicle_type",
+            "had_vehicle_of_type",
+            "loading_vehicles",
+            "always_accepted",
+            "goods",
+        ]
+        .map(SaveLoadCompat::var),
+    );
+    compat
+}
+
+/// Order of the SlStationWaypoint fields in savegames without a table header
+/// (matches C++ _station_waypoint_sl_compat)
+fn waypoint_compat() -> Vec<SaveLoadCompat> {
+    [
+        "base",
+        "town_cn",
+        "train_station.tile",
+        "train_station.w",
+        "train_station.h",
+    ]
+    .map(SaveLoadCompat::var)
+    .into()
+}
+
+/// Order of the STNN fields in savegames without a table header
+/// (matches C++ _station_sl_compat)
+fn station_compat() -> Vec<SaveLoadCompat> {
+    ["facilities", "normal", "waypoint", "speclist"]
+        .map(SaveLoadCompat::var)
+        .into()
+}
+
+fn tile_area_record(record: Record, prefix: &str, area: &TileArea) -> Record {
+    record
+        .with(&format!("{}.tile", prefix), area.tile.0)
+        .with(&format!("{}.w", prefix), area.w as u8)
+        .with(&format!("{}.h", prefix), area.h as u8)
+}
+
+fn spec_list_records(specs: &[SpecMapping], version: u16) -> Vec<Record> {
+    specs
+        .iter()
+        .map(|spec| {
+            let record = Record::default().with("grfid", spec.grfid);
+            if version < SaveLoadVersion::ExtendEntityMapping {
+                record.with("localidx", spec.localidx as u8)
+            } else {
+                record.with("localidx", spec.localidx)
+            }
+        })
+        .collect()
+}
+
+fn roadstop_tile_data_records(data: &[RoadStopTileData]) -> Vec<Record> {
+    data.iter()
+        .map(|d| {
+            Record::default()
+                .with("tile", d.tile.0)
+                .with("random_bits", d.random_bits)
+                .with("animation_frame", d.animation_frame)
+        })
+        .collect()
+}
+
+fn base_record(station: &Station) -> Record {
+    let town = station.town.is_valid().then_some(station.town.0 as u32);
+    Record::default()
+        .with("xy", station.xy.0)
+        .with("town", to_reference(town))
+        .with("string_id", Value::StringId(station.string_id))
+        .with("name", station.custom_name.as_str())
+        .with("delete_ctr", station.delete_ctr)
+        .with("owner", station.owner as u8)
+        .with("facilities", station.facilities)
+        .with("build_date", station.build_date.0)
+        .with("random_bits", station.random_bits)
+        .with("waiting_triggers", station.waiting_triggers)
+}
+
+fn goods_record(ge: &GoodsEntry) -> Record {
+    let status = (ge.status & !GES_ACCEPTANCE) | if ge.acceptance { GES_ACCEPTANCE } else { 0 };
+    let flows: Vec<Record> = ge
+        .flows
+        .iter()
+        .map(|flow| {
+            Record::default()
+                .with("source", flow.source.0)
+                .with("via", flow.via.0)
+                .with("share", flow.share)
+                .with("restricted", flow.restricted as i8)
+        })
+        .collect();
+    let cargo: Vec<Record> = ge
+        .cargo
+        .packets
+        .iter()
+        .map(|c| {
+            let packets: Vec<u32> = c.packets.iter().map(|&p| p + 1).collect();
+            Record::default()
+                .with("first", c.next_hop.0)
+                .with("second", packets)
+        })
+        .collect();
+    Record::default()
+        .with("status", status)
+        .with("time_since_pickup", ge.time_since_pickup)
+        .with("rating", ge.rating)
+        .with("last_speed", ge.last_speed)
+        .with("last_age", ge.last_age)
+        .with("amount_fract", ge.amount_fract)
+        .with("cargo.reserved_count", ge.cargo.reserved_count)
+        .with("link_graph", ge.link_graph)
+        .with("node", ge.node)
+        .with("max_waiting_cargo", ge.max_waiting_cargo)
+        .with("flow", flows)
+        .with("cargo", cargo)
+}
+
+fn normal_record(station: &Station, version: u16) -> Record {
+    let mut record = Record::default().with("base", vec![base_record(station)]);
+    record = tile_area_record(record, "train_station", &station.train_station);
+    record = record
+        .with("bus_stops", to_reference(station.bus_stops.map(u32::from)))
+        .with(
+            "truck_stops",
+            to_reference(station.truck_stops.map(u32::from)),
+        );
+    record = tile_area_record(record, "ship_station", &station.ship_station);
+    record = tile_area_record(record, "docking_station", &station.docking_station);
+    record = tile_area_record(record, "airport", &station.airport_area);
+
+    let loading_vehicles: Vec<u32> = station.loading_vehicles.iter().map(|v| v.0 + 1).collect();
+    record = record
+        .with("airport.type", station.airport_type)
+        .with("airport.layout", station.airport_layout)
+        .with("airport.flags", station.airport_flags)
+        .with("airport.rotation", station.airport_rotation)
+        .with("airport.psa", to_reference(station.airport_psa))
+        .with("indtype", station.indtype)
+        .with("time_since_load", station.time_since_load)
+        .with("time_since_unload", station.time_since_unload)
+        .with("last_vehicle_type", station.last_vehicle_type)
+        .with("had_vehicle_of_type", station.had_vehicle_of_type)
+        .with("loading_vehicles", loading_vehicles)
+        .with("always_accepted", station.always_accepted);
+    if version >= SaveLoadVersion::NewgrfRoadStops && version < SaveLoadVersion::RoadStopTileData {
+        record = record.with(
+            "speclist",
+            roadstop_tile_data_records(&station.roadstop_tile_data),
+        );
+    }
+
+    // The C++ saver always writes all NUM_CARGO entries
+    let goods: Vec<Record> = station.goods[..NUM_CARGO]
+        .iter()
+        .map(goods_record)
+        .collect();
+    record.with("goods", goods)
+}
+
+fn waypoint_record(station: &Station, version: u16) -> Record {
+    let mut record = Record::default()
+        .with("base", vec![base_record(station)])
+        .with("town_cn", station.town_cn);
+    record = tile_area_record(record, "train_station", &station.train_station);
+    if version >= SaveLoadVersion::RoadWaypoints {
+        record = record.with("waypoint_flags", station.waypoint_flags);
+        record = tile_area_record(record, "road_waypoint_area", &station.road_waypoint_area);
+    }
+    record
+}
+
+fn station_to_record(station: &Station, version: u16) -> Record {
+    let (normal, waypoint) = if station.is_waypoint() {
+        (vec![], vec![waypoint_record(station, version)])
+    } else {
+        (vec![normal_record(station, version)], vec![])
+    };
+    let mut record = Record::default()
+        .with("facilities", station.facilities)
+        .with("normal", normal)
+        .with("waypoint", waypoint)
+        .with("speclist", spec_list_records(&station.speclist, version));
+    if version >= SaveLoadVersion::NewgrfRoadStops {
+        record = record.with(
+            "roadstopspeclist",
+            spec_list_records(&station.roadstop_speclist, version),
+        );
+    }
+    if version >= SaveLoadVersion::RoadStopTileData {
+        record = record.with(
+            "roadstoptiledata",
+            roadstop_tile_data_records(&station.roadstop_tile_data),
+        );
+    }
+    record
+}
+
+/// Write the STNN chunk in the layout of the writer's savegame version
+pub fn save_stations(
+    writer: &mut SavegameWriter,
+    stations: &[Station],
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let mut stations: Vec<&Station> = stations.iter().collect();
+    stations.sort_by_key(|s| s.index.0);
+    let records: Vec<(usize, Record)> = stations
+        .iter()
+        .map(|s| (s.index.0 as usize, station_to_record(s, version)))
+        .collect();
+
+    writer.add_table_records(
+        b"STNN",
+        ChunkType::Table,
+        &table_header(&station_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+    use openttd_core::station::{FACIL_BUS_STOP, FACIL_TRAIN};
+
+    fn sample_station(index: u16) -> Station {
+        let mut station = Station::new(StationID(index), TileIndex(4321), Owner::Company1);
+        station.town = TownID(2);
+        station.string_id = 0x300D;
+        station.custom_name = "Central".into();
+        station.facilities = FACIL_TRAIN | FACIL_BUS_STOP;
+        station.build_date = CalendarDate(730_000);
+        station.train_station = TileArea {
+            tile: TileIndex(4321),
+            w: 1,
+            h: 5,
+        };
+        station.bus_stops = Some(3);
+        station.loading_vehicles = vec![VehicleID(0), VehicleID(17)];
+        station.speclist = vec![
+            SpecMapping::default(),
+            SpecMapping {
+                grfid: 0x12345678,
+                localidx: 2,
+            },
+        ];
+
+        let ge = &mut station.goods[5];
+        ge.acceptance = true;
+        ge.status = GES_ACCEPTANCE;
+        ge.rating = 200;
+        ge.max_waiting_cargo = 70_000;
+        ge.link_graph = 1;
+        ge.node = 0;
+        ge.flows = vec![FlowShare {
+            source: StationID(index),
+            via: StationID(9),
+            share: 42,
+            restricted: true,
+        }];
+        ge.cargo.packets = vec![StationCargoPackets {
+            next_hop: StationID(9),
+            packets: vec![0, 4, 5],
+        }];
+        station
+    }
+
+    fn sample_waypoint(index: u16) -> Station {
+        let mut waypoint = Station::new(StationID(index), TileIndex(99), Owner::None);
+        waypoint.facilities = FACIL_WAYPOINT | FACIL_TRAIN;
+        waypoint.town_cn = 3;
+        waypoint.train_station = TileArea {
+            tile: TileIndex(99),
+            w: 1,
+            h: 1,
+        };
+        waypoint.roadstop_tile_data = vec![RoadStopTileData {
+            tile: TileIndex(99),
+            random_bits: 7,
+            animation_frame: 1,
+        }];
+        waypoint
+    }
+
+    fn round_trip(stations: &[Station], version: u16) -> Vec<Station> {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_stations(&mut writer, stations).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_stations(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_stations_round_trip() {
+        let stations = vec![sample_waypoint(4), sample_station(1)];
+        for version in [
+            SaveLoadVersion::TableChunks,
+            SaveLoadVersion::RoadStopTileData,
+        ]
+        .map(u16::from)
+        {
+            let loaded = round_trip(&stations, version);
+            assert_eq!(loaded.len(), 2);
+            assert_eq!(loaded[0], stations[1]);
+            assert!(loaded[1].is_waypoint());
+            assert_eq!(loaded[1].town_cn, 3);
+        }
+
+        // Waypoint road stop tile data is only saved since SaveLoadVersion::RoadStopTileData
+        let loaded = round_trip(&stations, SaveLoadVersion::RoadStopTileData.into());
+        assert_eq!(loaded[1], stations[0]);
+        let loaded = round_trip(&stations, SaveLoadVersion::TableChunks.into());
+        assert!(loaded[1].roadstop_tile_data.is_empty());
+    }
+
+    #[test]
+    fn test_stations_unsupported_version() {
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_stations(&mut writer, &[sample_station(0)]),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+        // Stations are saved in STNS before SaveLoadVersion::V123
+        assert!(matches!(
+            load_stations(&[], 122),
+            Err(SavegameError::UnsupportedVersion(122))
+        ));
+    }
+}
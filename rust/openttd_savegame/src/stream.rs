@@ -0,0 +1,413 @@
+/// Incremental savegame reading over `std::io::Read`
+///
+/// `SavegameReader` decompresses the whole body before parsing it. `SavegameStream`
+/// decompresses on demand and yields one chunk header at a time; the chunk's
+/// body can then be streamed, read record by record, or skipped.
+use crate::chunk::{ChunkType, TableHeader};
+use crate::gamma;
+use crate::header::SavegameHeader;
+use crate::lzo;
+use crate::savegame::{ChunkData, SavegameError};
+use crate::types::CompressionType;
+use flate2::read::ZlibDecoder;
+use lzma_rust2::XzReader;
+use openttd_core::error::CoreError;
+use std::io::{self, Read};
+
+/// Decompressor for the savegame body
+enum Decoder<R: Read> {
+    None(R),
+    Zlib(ZlibDecoder<R>),
+    Xz(Box<XzReader<R>>),
+    Lzo(lzo::BlockReader<R>),
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Decoder::None(r) => r.read(buf),
+            Decoder::Zlib(r) => r.read(buf),
+            Decoder::Xz(r) => r.read(buf),
+            Decoder::Lzo(r) => r.read(buf),
+        }
+    }
+}
+
+/// Header of a chunk in a savegame stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkInfo {
+    pub tag: String,
+    pub chunk_type: ChunkType,
+    /// Body length of a RIFF chunk; array and table bodies are not length-prefixed
+    pub length: Option<usize>,
+    /// Field layout of a TABLE or SPARSE_TABLE chunk
+    pub table_header: Option<TableHeader>,
+}
+
+/// Unread part of the current chunk
+enum Body {
+    None,
+    Riff { remaining: u64 },
+    Records { sparse: bool, next_index: usize },
+}
+
+/// Streaming savegame reader
+pub struct SavegameStream<R: Read> {
+    header: SavegameHeader,
+    decoder: Decoder<R>,
+    body: Body,
+    finished: bool,
+}
+
+impl<R: Read> SavegameStream<R> {
+    /// Read the savegame header and prepare to decompress the body
+    pub fn new(mut reader: R) -> Result<Self, SavegameError> {
+        let mut buf = [0u8; SavegameHeader::size()];
+        reader.read_exact(&mut buf)?;
+        let header = SavegameHeader::parse(&buf)?;
+
+        let decoder = match header.compression {
+            CompressionType::None => Decoder::None(reader),
+            CompressionType::Zlib => Decoder::Zlib(ZlibDecoder::new(reader)),
+            CompressionType::Lzma => Decoder::Xz(Box::new(XzReader::new(reader, false))),
+            CompressionType::Lzo => Decoder::Lzo(lzo::BlockReader::new(reader, header.version)),
+        };
+
+        Ok(Self {
+            header,
+            decoder,
+            body: Body::None,
+            finished: false,
+        })
+    }
+
+    /// Get the savegame header
+    pub fn header(&self) -> &SavegameHeader {
+        &self.header
+    }
+
+    /// Advance to the next chunk, skipping whatever is left of the current one.
+    /// Returns `None` at the end-of-savegame marker.
+    pub fn next_chunk(&mut self) -> Result<Option<ChunkInfo>, SavegameError> {
+        self.skip_chunk()?;
+        if self.finished {
+            return Ok(None);
+        }
+
+        // Like `SavegameReader`, accept a body that ends without the marker
+        let mut tag = [0u8; 4];
+        if !read_or_eof(&mut self.decoder, &mut tag)? || tag == [0, 0, 0, 0] {
+            self.finished = true;
+            return Ok(None);
+        }
+
+        let mut mode = [0u8];
+        self.decoder.read_exact(&mut mode)?;
+        let chunk_type = ChunkType::try_from(mode[0])?;
+
+        let mut info = ChunkInfo {
+            tag: String::from_utf8_lossy(&tag).to_string(),
+            chunk_type,
+            length: None,
+            table_header: None,
+        };
+
+        match chunk_type {
+            ChunkType::Riff => {
+                let mut length = [0u8; 3];
+                self.decoder.read_exact(&mut length)?;
+                let length = ((mode[0] as usize >> 4) << 24)
+                    | (length[0] as usize) << 16
+                    | (length[1] as usize) << 8
+                    | length[2] as usize;
+                info.length = Some(length);
+                self.body = Body::Riff {
+                    remaining: length as u64,
+                };
+            }
+            ChunkType::Array | ChunkType::SparseArray => {
+                self.body = Body::Records {
+                    sparse: chunk_type == ChunkType::SparseArray,
+                    next_index: 0,
+                };
+            }
+            ChunkType::Table | ChunkType::SparseTable => {
+                info.table_header = Some(self.read_table_header()?);
+                self.body = Body::Records {
+                    sparse: chunk_type == ChunkType::SparseTable,
+                    next_index: 0,
+                };
+            }
+        }
+
+        Ok(Some(info))
+    }
+
+    /// Reader over the unread body of the current RIFF chunk
+    pub fn riff_body(&mut self) -> RiffBody<'_, R> {
+        RiffBody { stream: self }
+    }
+
+    /// Read the next non-empty record of the current array or table chunk.
+    /// Returns `None` once the chunk's records are exhausted.
+    pub fn next_record(&mut self) -> Result<Option<(usize, Vec<u8>)>, SavegameError> {
+        loop {
+            let Some((index, size)) = self.next_record_header()? else {
+                return Ok(None);
+            };
+            if size == 0 {
+                continue;
+            }
+
+            let mut data = Vec::new();
+            (&mut self.decoder).take(size).read_to_end(&mut data)?;
+            if data.len() as u64 != size {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            return Ok(Some((index, data)));
+        }
+    }
+
+    /// Skip the unread part of the current chunk
+    pub fn skip_chunk(&mut self) -> Result<(), SavegameError> {
+        match self.body {
+            Body::None => {}
+            Body::Riff { remaining } => {
+                let skipped = io::copy(&mut (&mut self.decoder).take(remaining), &mut io::sink())?;
+                if skipped != remaining {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+                self.body = Body::None;
+            }
+            Body::Records { .. } => {
+                while let Some((_, size)) = self.next_record_header()? {
+                    let skipped = io::copy(&mut (&mut self.decoder).take(size), &mut io::sink())?;
+                    if skipped != size {
+                        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the unread part of the current chunk into memory
+    pub fn read_body(&mut self, info: &ChunkInfo) -> Result<ChunkData, SavegameError> {
+        if info.chunk_type == ChunkType::Riff {
+            let mut data = Vec::new();
+            self.riff_body().read_to_end(&mut data)?;
+            return Ok(ChunkData::Riff(data));
+        }
+
+        match &info.table_header {
+            Some(header) => Ok(ChunkData::Table {
+                header: header.clone(),
+                records: self.collect_records()?,
+            }),
+            None => Ok(ChunkData::Array(self.collect_records()?)),
+        }
+    }
+
+    fn collect_records(&mut self) -> Result<Vec<(usize, Vec<u8>)>, SavegameError> {
+        let mut records = Vec::new();
+        while let Some(record) = self.next_record()? {
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// Read the index and data size of the next record, leaving the data unread
+    fn next_record_header(&mut self) -> Result<Option<(usize, u64)>, SavegameError> {
+        let Body::Records { sparse, next_index } = self.body else {
+            return Ok(None);
+        };
+
+        let (size_plus_one, _) = read_gamma(&mut self.decoder)?;
+        if size_plus_one == 0 {
+            self.body = Body::None;
+            return Ok(None);
+        }
+        let size = size_plus_one - 1;
+
+        if sparse {
+            // The explicit index is included in the size
+            let (index, index_len) = read_gamma(&mut self.decoder)?;
+            let size = size
+                .checked_sub(index_len as u64)
+                .ok_or_else(|| CoreError::InvalidData("Sparse index exceeds record size".into()))?;
+            Ok(Some((index as usize, size)))
+        } else {
+            self.body = Body::Records {
+                sparse,
+                next_index: next_index + 1,
+            };
+            Ok(Some((next_index, size)))
+        }
+    }
+
+    fn read_table_header(&mut self) -> Result<TableHeader, SavegameError> {
+        let (size_plus_one, _) = read_gamma(&mut self.decoder)?;
+        let size = size_plus_one
+            .checked_sub(1)
+            .ok_or_else(|| CoreError::InvalidData("Table has no header".into()))?;
+
+        let mut buf = gamma::encode_gamma(size_plus_one);
+        let start = buf.len();
+        (&mut self.decoder).take(size).read_to_end(&mut buf)?;
+        if (buf.len() - start) as u64 != size {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let (header, _) = TableHeader::parse(&buf)?;
+        Ok(header)
+    }
+}
+
+/// Reader over the body of a RIFF chunk, see `SavegameStream::riff_body`
+pub struct RiffBody<'a, R: Read> {
+    stream: &'a mut SavegameStream<R>,
+}
+
+impl<R: Read> Read for RiffBody<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Body::Riff { remaining } = self.stream.body else {
+            return Ok(0);
+        };
+        if remaining == 0 {
+            self.stream.body = Body::None;
+            return Ok(0);
+        }
+
+        let len = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        let n = self.stream.decoder.read(&mut buf[..len])?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.stream.body = Body::Riff {
+            remaining: remaining - n as u64,
+        };
+        Ok(n)
+    }
+}
+
+/// Fill `buf` completely; returns false if the input ended before the first byte
+fn read_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// Read a gamma-encoded value, returning it with its encoded length
+fn read_gamma(reader: &mut impl Read) -> Result<(u64, usize), SavegameError> {
+    let mut buf = [0u8; 5];
+    reader.read_exact(&mut buf[..1])?;
+    let len = (buf[0].leading_ones() as usize + 1).min(5);
+    reader.read_exact(&mut buf[1..len])?;
+    Ok(gamma::decode_gamma(&buf[..len])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{DataType, TableField};
+    use crate::savegame::{SavegameReader, SavegameWriter};
+
+    fn sample_savegame(compression: CompressionType) -> Vec<u8> {
+        let header = TableHeader {
+            fields: vec![TableField {
+                data_type: DataType::U32,
+                key: "value".into(),
+                is_list: false,
+                sub_header: None,
+            }],
+        };
+        let payload: Vec<u8> = (0..30_000u32).map(|i| (i % 251) as u8).collect();
+
+        let mut writer = SavegameWriter::new(300, compression);
+        writer.add_riff_chunk(b"RIFF", &payload).unwrap();
+        writer
+            .add_array_chunk(b"ARRY", &[(0, vec![1]), (4, vec![2, 3])])
+            .unwrap();
+        writer
+            .add_sparse_table_chunk(b"SPTB", &header, &[(7, vec![0, 0, 0, 1])])
+            .unwrap();
+        writer.add_riff_chunk(b"LAST", b"end").unwrap();
+        writer.finalize().unwrap()
+    }
+
+    #[test]
+    fn test_stream_matches_reader() {
+        for compression in [
+            CompressionType::None,
+            CompressionType::Zlib,
+            CompressionType::Lzo,
+            CompressionType::Lzma,
+        ] {
+            let data = sample_savegame(compression);
+            let expected = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+
+            let mut stream = SavegameStream::new(data.as_slice()).unwrap();
+            assert_eq!(stream.header().version, 300);
+            let mut chunks = Vec::new();
+            while let Some(info) = stream.next_chunk().unwrap() {
+                let data = stream.read_body(&info).unwrap();
+                chunks.push((info.tag, info.chunk_type, data));
+            }
+
+            assert_eq!(chunks.len(), expected.len());
+            for ((tag, chunk_type, data), chunk) in chunks.iter().zip(&expected) {
+                assert_eq!(tag, &chunk.tag);
+                assert_eq!(chunk_type, &chunk.chunk_type);
+                assert_eq!(data, &chunk.data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_skip_and_partial_reads() {
+        let data = sample_savegame(CompressionType::Lzo);
+        let mut stream = SavegameStream::new(data.as_slice()).unwrap();
+
+        // Read a few bytes of the RIFF body, then move on
+        let info = stream.next_chunk().unwrap().unwrap();
+        assert_eq!(info.length, Some(30_000));
+        let mut start = [0u8; 3];
+        stream.riff_body().read_exact(&mut start).unwrap();
+        assert_eq!(start, [0, 1, 2]);
+
+        // Read one record of the array, then move on
+        let info = stream.next_chunk().unwrap().unwrap();
+        assert_eq!(info.tag, "ARRY");
+        assert_eq!(stream.next_record().unwrap(), Some((0, vec![1])));
+
+        // Skip the table entirely
+        let info = stream.next_chunk().unwrap().unwrap();
+        assert_eq!(info.tag, "SPTB");
+        assert!(info.table_header.is_some());
+
+        let info = stream.next_chunk().unwrap().unwrap();
+        assert_eq!(info.tag, "LAST");
+        assert_eq!(
+            stream.read_body(&info).unwrap(),
+            ChunkData::Riff(b"end".to_vec())
+        );
+        assert_eq!(stream.next_chunk().unwrap(), None);
+        assert_eq!(stream.next_chunk().unwrap(), None);
+    }
+
+    #[test]
+    fn test_stream_truncated() {
+        let data = sample_savegame(CompressionType::None);
+        let mut stream = SavegameStream::new(&data[..100]).unwrap();
+        assert!(stream.next_chunk().unwrap().is_some());
+        assert!(stream.next_chunk().is_err());
+    }
+}
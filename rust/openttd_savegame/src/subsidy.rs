@@ -0,0 +1,174 @@
+/// Loading and saving of the SUBS chunk
+///
+/// The towns and industries taking part in a subsidy are not saved; call
+/// SubsidyPool::rebuild_cache once they are loaded, as C++ does after loading.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, Record};
+use crate::version::{table_header, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::cargopacket::{Source, SourceType};
+use openttd_core::error::CoreError;
+use openttd_core::subsidy::{Subsidy, SubsidyID, SubsidyPool};
+use openttd_core::types::{CargoType, Owner};
+
+fn subsidy_from_record(index: usize, record: &Record, version: u16) -> Result<Subsidy, CoreError> {
+    // Source types are not saved before version 125
+    let source = |type_key, id_key| -> Result<Source, CoreError> {
+        Ok(Source {
+            id: int(record, id_key)? as u16,
+            type_: if version >= SaveLoadVersion::V125 {
+                SourceType::try_from(int(record, type_key)? as u8)?
+            } else {
+                SourceType::Industry
+            },
+        })
+    };
+    let mut subsidy = Subsidy::new(
+        index as SubsidyID,
+        CargoType(int(record, "cargo_type")? as u8),
+        source("src_type", "src")?,
+        source("dst_type", "dst")?,
+    );
+    subsidy.remaining = int(record, "remaining")? as u16;
+    if version >= SaveLoadVersion::V125 {
+        subsidy.awarded = Owner::try_from(int(record, "awarded")? as u8)?;
+    }
+    Ok(subsidy)
+}
+
+/// Load the subsidies from the SUBS chunk
+pub fn load_subsidies(chunks: &[Chunk], version: u16) -> Result<SubsidyPool, SavegameError> {
+    let compat = [
+        "cargo_type",
+        "remaining",
+        "awarded",
+        "src_type",
+        "dst_type",
+        "src",
+        "dst",
+    ]
+    .map(SaveLoadCompat::var);
+    let subsidies = chunk_records(chunks, b"SUBS", version, &subsidy_desc(), &compat)?
+        .iter()
+        .map(|(index, record)| subsidy_from_record(*index, record, version))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(SubsidyPool::from_subsidies(subsidies)?)
+}
+
+/// Field declarations of SUBS (matches C++ _subsidies_desc)
+fn subsidy_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U8, "cargo_type"),
+        SaveLoad::var(DataType::U8, "remaining").until(SaveLoadVersion::CustomSubsidyDuration),
+        SaveLoad::var(DataType::U16, "remaining").since(SaveLoadVersion::CustomSubsidyDuration),
+        SaveLoad::var(DataType::U8, "awarded").since(SaveLoadVersion::V125),
+        SaveLoad::var(DataType::U8, "src_type").since(SaveLoadVersion::V125),
+        SaveLoad::var(DataType::U8, "dst_type").since(SaveLoadVersion::V125),
+        SaveLoad::var(DataType::U8, "src").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U16, "src").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U8, "dst").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U16, "dst").since(SaveLoadVersion::V5),
+    ]
+}
+
+fn subsidy_to_record(subsidy: &Subsidy) -> Record {
+    Record::default()
+        .with("cargo_type", subsidy.cargo_type.0)
+        .with("remaining", subsidy.remaining)
+        .with("awarded", subsidy.awarded as u8)
+        .with("src_type", subsidy.src.type_ as u8)
+        .with("dst_type", subsidy.dst.type_ as u8)
+        .with("src", subsidy.src.id)
+        .with("dst", subsidy.dst.id)
+}
+
+/// Write the SUBS chunk in the layout of the writer's savegame version
+pub fn save_subsidies(
+    writer: &mut SavegameWriter,
+    pool: &SubsidyPool,
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let records: Vec<(usize, Record)> = pool
+        .iter()
+        .map(|subsidy| (subsidy.index as usize, subsidy_to_record(subsidy)))
+        .collect();
+
+    writer.add_table_records(
+        b"SUBS",
+        ChunkType::Table,
+        &table_header(&subsidy_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+
+    fn sample_pool() -> SubsidyPool {
+        let mut offered = Subsidy::new(
+            0,
+            CargoType(0),
+            Source {
+                id: 3,
+                type_: SourceType::Town,
+            },
+            Source {
+                id: 7,
+                type_: SourceType::Town,
+            },
+        );
+        offered.remaining = 12;
+        let mut awarded = Subsidy::new(
+            4,
+            CargoType(1),
+            Source {
+                id: 2,
+                type_: SourceType::Industry,
+            },
+            Source {
+                id: 5,
+                type_: SourceType::Town,
+            },
+        );
+        awarded.award_to(Owner::Company3, 2);
+        SubsidyPool::from_subsidies(vec![offered, awarded]).unwrap()
+    }
+
+    #[test]
+    fn test_subsidies_round_trip() {
+        let pool = sample_pool();
+        let version = SaveLoadVersion::CURRENT.into();
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_subsidies(&mut writer, &pool).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        let loaded = load_subsidies(&chunks, version).unwrap();
+        assert_eq!(loaded, pool);
+        assert_eq!(loaded.get(4).unwrap().remaining, 24);
+    }
+
+    #[test]
+    fn test_subsidies_invalid() {
+        let mut record = subsidy_to_record(sample_pool().get(0).unwrap());
+        let dst_type = record
+            .fields
+            .iter_mut()
+            .find(|(key, _)| key == "dst_type")
+            .unwrap();
+        dst_type.1 = 7u8.into();
+        assert!(subsidy_from_record(0, &record, SaveLoadVersion::CURRENT.into()).is_err());
+
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_subsidies(&mut writer, &sample_pool()),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
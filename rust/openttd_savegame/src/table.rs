@@ -0,0 +1,553 @@
+/// Typed decoding and encoding of TABLE and SPARSE_TABLE records
+///
+/// Table chunks are self-describing: the header lists every field's type and
+/// key, so any record can be turned into a value tree without knowing the
+/// chunk's layout in advance.
+use crate::chunk::{DataType, TableField, TableHeader};
+use crate::gamma;
+use openttd_core::endian::BigEndianReader;
+use openttd_core::error::CoreError;
+use openttd_core::types::StringID;
+
+/// A single decoded field value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    StringId(StringID),
+    String(String),
+    /// A length-prefixed field (arrays, vectors, struct lists)
+    List(Vec<Value>),
+    /// One element of a struct field
+    Struct(Record),
+}
+
+impl Value {
+    /// Get any integer value, widened to i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::I8(v) => Some(v as i64),
+            Value::U8(v) => Some(v as i64),
+            Value::I16(v) => Some(v as i64),
+            Value::U16(v) => Some(v as i64),
+            Value::I32(v) => Some(v as i64),
+            Value::U32(v) => Some(v as i64),
+            Value::I64(v) => Some(v),
+            Value::U64(v) => Some(v as i64),
+            Value::StringId(v) => Some(v as i64),
+            _ => None,
+        }
+    }
+
+    /// Get any integer value as its unsigned bit pattern, widened to u64
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::I8(v) => Some(v as u8 as u64),
+            Value::I16(v) => Some(v as u16 as u64),
+            Value::I32(v) => Some(v as u32 as u64),
+            Value::I64(v) => Some(v as u64),
+            Value::U64(v) => Some(v),
+            _ => self.as_i64().map(|v| v as u64),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<&Record> {
+        match self {
+            Value::Struct(record) => Some(record),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! value_from {
+    ($($t:ty => $variant:ident),*) => {
+        $(impl From<$t> for Value {
+            fn from(v: $t) -> Self {
+                Value::$variant(v)
+            }
+        })*
+    };
+}
+
+value_from!(i8 => I8, u8 => U8, i16 => I16, u16 => U16, i32 => I32, u32 => U32,
+    i64 => I64, u64 => U64, String => String, Record => Struct);
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.into())
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Value::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+/// A decoded record: field values in header order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Record {
+    pub fields: Vec<(String, Value)>,
+    /// Bytes following the described fields, which some chunks use for
+    /// handler-specific data (e.g. script state in AIPL and GSDT)
+    pub trailing: Vec<u8>,
+}
+
+impl Record {
+    /// Append a field; used to build records for encoding
+    pub fn with(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.fields.push((key.into(), value.into()));
+        self
+    }
+
+    /// Get a field value by key
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Get an integer field by key, widened to i64
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key).and_then(Value::as_i64)
+    }
+
+    /// Get an integer field by key as its unsigned bit pattern
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        self.get(key).and_then(Value::as_u64)
+    }
+
+    /// Get a string field by key
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Value::as_str)
+    }
+
+    /// Get a list field by key
+    pub fn get_list(&self, key: &str) -> Option<&[Value]> {
+        self.get(key).and_then(Value::as_list)
+    }
+
+    /// Get the first element of a struct field (C++ SL_STRUCT stores 0 or 1 elements)
+    pub fn get_struct(&self, key: &str) -> Option<&Record> {
+        self.get_list(key)?.first().and_then(Value::as_struct)
+    }
+
+    /// Get all elements of a struct list field
+    pub fn get_structs(&self, key: &str) -> impl Iterator<Item = &Record> {
+        self.get_list(key)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(Value::as_struct)
+    }
+}
+
+/// Error for a field that a record of a chunk lacks
+pub(crate) fn missing(key: &str) -> CoreError {
+    CoreError::InvalidData(format!("missing field '{}'", key))
+}
+
+/// Read a required struct field
+pub(crate) fn part<'a>(record: &'a Record, key: &str) -> Result<&'a Record, CoreError> {
+    record.get_struct(key).ok_or_else(|| missing(key))
+}
+
+/// Read a required integer field as its unsigned bit pattern
+pub(crate) fn int(record: &Record, key: &str) -> Result<u64, CoreError> {
+    record.get_u64(key).ok_or_else(|| missing(key))
+}
+
+/// Read a required integer field, widened to i64
+pub(crate) fn signed(record: &Record, key: &str) -> Result<i64, CoreError> {
+    record.get_i64(key).ok_or_else(|| missing(key))
+}
+
+/// Read a required list of integers
+pub(crate) fn int_list(record: &Record, key: &str) -> Result<Vec<u64>, CoreError> {
+    record
+        .get_list(key)
+        .ok_or_else(|| missing(key))?
+        .iter()
+        .map(|v| v.as_u64().ok_or_else(|| missing(key)))
+        .collect()
+}
+
+/// Convert a saved reference (index + 1, 0 for none) to an index
+/// (matches C++ SL_REF)
+pub(crate) fn reference(record: &Record, key: &str) -> Result<Option<u32>, CoreError> {
+    Ok(int(record, key)?.checked_sub(1).map(|v| v as u32))
+}
+
+/// Convert an index to a saved reference
+pub(crate) fn to_reference(index: Option<u32>) -> u32 {
+    index.map_or(0, |v| v + 1)
+}
+
+pub(crate) fn decode_scalar(
+    data_type: DataType,
+    reader: &mut BigEndianReader,
+) -> Result<Value, CoreError> {
+    Ok(match data_type {
+        DataType::I8 => Value::I8(reader.read_i8()?),
+        DataType::U8 => Value::U8(reader.read_u8()?),
+        DataType::I16 => Value::I16(reader.read_i16()?),
+        DataType::U16 => Value::U16(reader.read_u16()?),
+        DataType::I32 => Value::I32(reader.read_i32()?),
+        DataType::U32 => Value::U32(reader.read_u32()?),
+        DataType::I64 => Value::I64(reader.read_i64()?),
+        DataType::U64 => Value::U64(reader.read_u64()?),
+        DataType::StringId => Value::StringId(reader.read_u16()?),
+        DataType::String => {
+            let length = gamma::read_gamma(reader)? as usize;
+            let bytes = reader.read_bytes(length)?;
+            // Like C++ StrMakeValid, replace invalid bytes instead of failing the record
+            Value::String(String::from_utf8_lossy(bytes).into_owned())
+        }
+        DataType::Struct => {
+            return Err(CoreError::InvalidData(
+                "Struct field without sub-header".into(),
+            ))
+        }
+    })
+}
+
+/// Read the length of a list
+fn read_count(reader: &mut BigEndianReader) -> Result<usize, CoreError> {
+    let count = gamma::read_gamma(reader)?;
+    check_count(count, reader)
+}
+
+/// Check the length of a list; a list longer than the rest of the record is
+/// corrupt, which also bounds lists of structs without fields
+pub(crate) fn check_count(count: u64, reader: &BigEndianReader) -> Result<usize, CoreError> {
+    usize::try_from(count)
+        .ok()
+        .filter(|&count| count <= reader.remaining())
+        .ok_or_else(|| CoreError::InvalidData(format!("list of {} items exceeds record", count)))
+}
+
+fn decode_field(field: &TableField, reader: &mut BigEndianReader) -> Result<Value, CoreError> {
+    match (field.data_type, &field.sub_header) {
+        (DataType::Struct, Some(sub_header)) => {
+            let count = read_count(reader)?;
+            let mut items = Vec::with_capacity(count);
+            for _ in 0..count {
+                items.push(Value::Struct(decode_fields(sub_header, reader)?));
+            }
+            Ok(Value::List(items))
+        }
+        // Strings always carry the list flag, as they are a list of chars
+        (DataType::String, _) => decode_scalar(field.data_type, reader),
+        (data_type, _) if field.is_list => {
+            let count = read_count(reader)?;
+            let mut items = Vec::with_capacity(count);
+            for _ in 0..count {
+                items.push(decode_scalar(data_type, reader)?);
+            }
+            Ok(Value::List(items))
+        }
+        (data_type, _) => decode_scalar(data_type, reader),
+    }
+}
+
+fn decode_fields(header: &TableHeader, reader: &mut BigEndianReader) -> Result<Record, CoreError> {
+    let mut fields = Vec::with_capacity(header.fields.len());
+    for field in &header.fields {
+        let value = decode_field(field, reader).map_err(|e| match e {
+            CoreError::InvalidData(msg) => {
+                CoreError::InvalidData(format!("field '{}': {}", field.key, msg))
+            }
+            other => other,
+        })?;
+        fields.push((field.key.clone(), value));
+    }
+    Ok(Record {
+        fields,
+        trailing: Vec::new(),
+    })
+}
+
+/// Decode a single record; bytes not described by the header are kept in `trailing`
+pub fn decode_record(header: &TableHeader, data: &[u8]) -> Result<Record, CoreError> {
+    let mut reader = BigEndianReader::new(data);
+    let mut record = decode_fields(header, &mut reader)?;
+    record.trailing = reader.rest().to_vec();
+    Ok(record)
+}
+
+/// Decode every record of a table chunk, keeping their indices
+pub fn decode_records(
+    header: &TableHeader,
+    records: &[(usize, Vec<u8>)],
+) -> Result<Vec<(usize, Record)>, CoreError> {
+    records
+        .iter()
+        .map(|(index, data)| Ok((*index, decode_record(header, data)?)))
+        .collect()
+}
+
+fn type_mismatch(data_type: DataType, value: &Value) -> CoreError {
+    CoreError::InvalidData(format!("expected {:?}, found {:?}", data_type, value))
+}
+
+fn encode_scalar(data_type: DataType, value: &Value, buf: &mut Vec<u8>) -> Result<(), CoreError> {
+    match (data_type, value) {
+        (DataType::I8, Value::I8(v)) => buf.extend_from_slice(&v.to_be_bytes()),
+        (DataType::U8, Value::U8(v)) => buf.push(*v),
+        (DataType::I16, Value::I16(v)) => buf.extend_from_slice(&v.to_be_bytes()),
+        (DataType::U16, Value::U16(v)) => buf.extend_from_slice(&v.to_be_bytes()),
+        (DataType::I32, Value::I32(v)) => buf.extend_from_slice(&v.to_be_bytes()),
+        (DataType::U32, Value::U32(v)) => buf.extend_from_slice(&v.to_be_bytes()),
+        (DataType::I64, Value::I64(v)) => buf.extend_from_slice(&v.to_be_bytes()),
+        (DataType::U64, Value::U64(v)) => buf.extend_from_slice(&v.to_be_bytes()),
+        (DataType::StringId, Value::StringId(v)) => buf.extend_from_slice(&v.to_be_bytes()),
+        (DataType::String, Value::String(s)) => {
+            buf.extend_from_slice(&gamma::encode_gamma(s.len() as u64));
+            buf.extend_from_slice(s.as_bytes());
+        }
+        (data_type, value) => return Err(type_mismatch(data_type, value)),
+    }
+    Ok(())
+}
+
+fn encode_field(field: &TableField, value: &Value, buf: &mut Vec<u8>) -> Result<(), CoreError> {
+    match (field.data_type, &field.sub_header) {
+        (DataType::Struct, Some(sub_header)) => {
+            let items = value
+                .as_list()
+                .ok_or_else(|| type_mismatch(field.data_type, value))?;
+            buf.extend_from_slice(&gamma::encode_gamma(items.len() as u64));
+            for item in items {
+                let record = item
+                    .as_struct()
+                    .ok_or_else(|| type_mismatch(field.data_type, item))?;
+                encode_fields(sub_header, record, buf)?;
+            }
+            Ok(())
+        }
+        (DataType::String, _) => encode_scalar(field.data_type, value, buf),
+        (data_type, _) if field.is_list => {
+            let items = value
+                .as_list()
+                .ok_or_else(|| type_mismatch(data_type, value))?;
+            buf.extend_from_slice(&gamma::encode_gamma(items.len() as u64));
+            for item in items {
+                encode_scalar(data_type, item, buf)?;
+            }
+            Ok(())
+        }
+        (data_type, _) => encode_scalar(data_type, value, buf),
+    }
+}
+
+fn encode_fields(
+    header: &TableHeader,
+    record: &Record,
+    buf: &mut Vec<u8>,
+) -> Result<(), CoreError> {
+    for field in &header.fields {
+        let value = record.get(&field.key).ok_or_else(|| {
+            CoreError::InvalidData(format!("field '{}': missing from record", field.key))
+        })?;
+        encode_field(field, value, buf).map_err(|e| match e {
+            CoreError::InvalidData(msg) => {
+                CoreError::InvalidData(format!("field '{}': {}", field.key, msg))
+            }
+            other => other,
+        })?;
+    }
+    Ok(())
+}
+
+/// Encode a single record in header order, followed by its trailing bytes
+pub fn encode_record(header: &TableHeader, record: &Record) -> Result<Vec<u8>, CoreError> {
+    let mut buf = Vec::new();
+    encode_fields(header, record, &mut buf)?;
+    buf.extend_from_slice(&record.trailing);
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(data_type: DataType, key: &str, is_list: bool) -> TableField {
+        TableField {
+            data_type,
+            key: key.into(),
+            is_list,
+            sub_header: None,
+        }
+    }
+
+    #[test]
+    fn test_decode_scalars() {
+        let header = TableHeader {
+            fields: vec![
+                field(DataType::I8, "a", false),
+                field(DataType::U16, "b", false),
+                field(DataType::I32, "c", false),
+                field(DataType::U64, "d", false),
+                field(DataType::StringId, "e", false),
+            ],
+        };
+        let mut data = vec![0xFF, 0x12, 0x34];
+        data.extend_from_slice(&(-5i32).to_be_bytes());
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        data.extend_from_slice(&0x7001u16.to_be_bytes());
+
+        let record = decode_record(&header, &data).unwrap();
+        assert_eq!(record.get("a"), Some(&Value::I8(-1)));
+        assert_eq!(record.get_i64("b"), Some(0x1234));
+        assert_eq!(record.get_i64("c"), Some(-5));
+        assert_eq!(record.get_u64("d"), Some(u64::MAX));
+        assert_eq!(record.get("e"), Some(&Value::StringId(0x7001)));
+        assert_eq!(record.get("missing"), None);
+    }
+
+    #[test]
+    fn test_decode_lists_and_strings() {
+        let header = TableHeader {
+            fields: vec![
+                field(DataType::String, "name", true),
+                field(DataType::U8, "md5sum", true),
+            ],
+        };
+        let data = [5, b'h', b'e', b'l', b'l', b'o', 3, 1, 2, 3];
+
+        let record = decode_record(&header, &data).unwrap();
+        assert_eq!(record.get_str("name"), Some("hello"));
+        assert_eq!(
+            record.get_list("md5sum"),
+            Some(&[Value::U8(1), Value::U8(2), Value::U8(3)][..])
+        );
+
+        // Invalid UTF-8 is replaced, keeping the rest of the record
+        let data = [3, b'a', 0xFF, b'b', 1, 7];
+        let record = decode_record(&header, &data).unwrap();
+        assert_eq!(record.get_str("name"), Some("a\u{FFFD}b"));
+        assert_eq!(record.get_list("md5sum"), Some(&[Value::U8(7)][..]));
+    }
+
+    #[test]
+    fn test_decode_nested_structs() {
+        let mut action = field(DataType::Struct, "action", true);
+        let mut mode = field(DataType::Struct, "mode", true);
+        mode.sub_header = Some(TableHeader {
+            fields: vec![field(DataType::U8, "mode.mode", false)],
+        });
+        action.sub_header = Some(TableHeader {
+            fields: vec![field(DataType::U8, "ct", false), mode],
+        });
+        let header = TableHeader {
+            fields: vec![field(DataType::U8, "at", false), action],
+        };
+
+        // at=1, two actions: (ct=0, one mode=3) and (ct=2, no mode)
+        let data = [1, 2, 0, 1, 3, 2, 0];
+        let record = decode_record(&header, &data).unwrap();
+        let actions: Vec<&Record> = record.get_structs("action").collect();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].get_i64("ct"), Some(0));
+        assert_eq!(
+            actions[0].get_struct("mode").unwrap().get_i64("mode.mode"),
+            Some(3)
+        );
+        assert_eq!(actions[1].get_i64("ct"), Some(2));
+        assert!(actions[1].get_struct("mode").is_none());
+
+        assert_eq!(encode_record(&header, &record).unwrap(), data);
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let header = TableHeader {
+            fields: vec![
+                field(DataType::I16, "x", false),
+                field(DataType::String, "name", true),
+                field(DataType::U32, "list", true),
+            ],
+        };
+        let mut data = vec![0xFF, 0xFE, 2, b'h', b'i', 2];
+        data.extend_from_slice(&7u32.to_be_bytes());
+        data.extend_from_slice(&9u32.to_be_bytes());
+        data.extend_from_slice(&[0xAA, 0xBB]);
+
+        let record = decode_record(&header, &data).unwrap();
+        assert_eq!(encode_record(&header, &record).unwrap(), data);
+    }
+
+    #[test]
+    fn test_build_record() {
+        let header = TableHeader {
+            fields: vec![
+                TableField::new(DataType::U8, "flags"),
+                TableField::new(DataType::String, "name"),
+                TableField::list(DataType::I16, "ratings"),
+            ],
+        };
+        let record = Record::default()
+            .with("flags", 3u8)
+            .with("name", "Foo")
+            .with("ratings", vec![-1i16, 2]);
+
+        let data = encode_record(&header, &record).unwrap();
+        assert_eq!(data, [3, 3, b'F', b'o', b'o', 2, 0xFF, 0xFF, 0, 2]);
+        assert_eq!(decode_record(&header, &data).unwrap(), record);
+    }
+
+    #[test]
+    fn test_encode_type_mismatch() {
+        let header = TableHeader {
+            fields: vec![field(DataType::U16, "a", false)],
+        };
+        let record = Record {
+            fields: vec![("a".into(), Value::U8(1))],
+            trailing: Vec::new(),
+        };
+        assert!(encode_record(&header, &record).is_err());
+        assert!(encode_record(&header, &Record::default()).is_err());
+    }
+
+    #[test]
+    fn test_decode_size_mismatch() {
+        let header = TableHeader {
+            fields: vec![field(DataType::U16, "a", false)],
+        };
+        assert!(decode_record(&header, &[0]).is_err());
+
+        let record = decode_record(&header, &[0, 1, 2]).unwrap();
+        assert_eq!(record.get_i64("a"), Some(1));
+        assert_eq!(record.trailing, vec![2]);
+    }
+
+    #[test]
+    fn test_decode_oversized_list() {
+        let header = TableHeader {
+            fields: vec![TableField::structs("s", TableHeader { fields: Vec::new() })],
+        };
+        // 2^32 empty structs in a record of 5 bytes
+        assert!(decode_record(&header, &[0xF0, 0xFF, 0xFF, 0xFF, 0xFF]).is_err());
+        assert_eq!(
+            decode_record(&header, &[0]).unwrap().get("s"),
+            Some(&Value::List(Vec::new()))
+        );
+    }
+}
@@ -0,0 +1,567 @@
+/// Loading and saving of the CITY chunk
+///
+/// Population and house counts are caches rebuilt from the map by the game,
+/// so they are not part of the chunk and stay zero after loading.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, Record, Value};
+use crate::version::{table_header, ListLength, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::error::CoreError;
+use openttd_core::map::TileIndex;
+use openttd_core::town::{
+    SuppliedCargo, SuppliedHistory, Town, TownLayout, TransportedCargoStat, NUM_TAE,
+};
+use openttd_core::types::{CargoType, Owner, TownID, HISTORY_RECORDS, LAST_MONTH, THIS_MONTH};
+
+/// Copy a saved list into a fixed-size array, leaving missing elements untouched
+fn fill<T, const N: usize>(
+    dst: &mut [T; N],
+    record: &Record,
+    key: &str,
+    conv: fn(&Value) -> Option<T>,
+) {
+    for (slot, value) in dst.iter_mut().zip(record.get_list(key).unwrap_or_default()) {
+        if let Some(value) = conv(value) {
+            *slot = value;
+        }
+    }
+}
+
+fn cargo_stat<T>(
+    record: &Record,
+    conv: fn(u64) -> T,
+) -> Result<TransportedCargoStat<T>, CoreError> {
+    Ok(TransportedCargoStat {
+        old_max: conv(int(record, "old_max")?),
+        new_max: conv(int(record, "new_max")?),
+        old_act: conv(int(record, "old_act")?),
+        new_act: conv(int(record, "new_act")?),
+    })
+}
+
+/// Cargo types of the passengers and mail statistics of old savegames
+const OLD_SUPPLIED: [(u8, &str); 2] = [(0, "CT_PASSENGERS"), (2, "CT_MAIL")];
+
+/// Acceptance effects of the received statistics of old savegames
+const OLD_RECEIVED: [(usize, &str); 2] = [(5, "TE_FOOD"), (4, "TE_WATER")];
+
+fn load_supplied(town: &mut Town, record: &Record, version: u16) -> Result<(), CoreError> {
+    if version < SaveLoadVersion::V165 {
+        // Passengers and mail are always kept, even without statistics
+        for (cargo, name) in OLD_SUPPLIED {
+            let stat = |key: &str| int(record, &format!("supplied[{}].{}", name, key));
+            let mut history = vec![SuppliedHistory::default(); HISTORY_RECORDS];
+            history[LAST_MONTH] = SuppliedHistory {
+                production: stat("old_max")? as u32,
+                transported: stat("old_act")? as u32,
+            };
+            history[THIS_MONTH] = SuppliedHistory {
+                production: stat("new_max")? as u32,
+                transported: stat("new_act")? as u32,
+            };
+            town.supplied.push(SuppliedCargo {
+                cargo: CargoType(cargo),
+                history,
+            });
+        }
+        town.valid_history = 1 << LAST_MONTH;
+    } else if version < SaveLoadVersion::TownSupplyHistory {
+        // One entry per cargo type; like the C++ loader, empty statistics are dropped
+        for (cargo, stat) in record.get_structs("supplied").enumerate() {
+            let stat = cargo_stat(stat, |v| v as u32)?;
+            if stat == TransportedCargoStat::default() {
+                continue;
+            }
+            let mut history = vec![SuppliedHistory::default(); HISTORY_RECORDS];
+            history[LAST_MONTH] = SuppliedHistory {
+                production: stat.old_max,
+                transported: stat.old_act,
+            };
+            history[THIS_MONTH] = SuppliedHistory {
+                production: stat.new_max,
+                transported: stat.new_act,
+            };
+            town.supplied.push(SuppliedCargo {
+                cargo: CargoType(cargo as u8),
+                history,
+            });
+        }
+        town.valid_history = 1 << LAST_MONTH;
+    } else {
+        for supplied in record.get_structs("supplied") {
+            let history = supplied
+                .get_structs("history")
+                .map(|h| {
+                    Ok(SuppliedHistory {
+                        production: int(h, "production")? as u32,
+                        transported: int(h, "transported")? as u32,
+                    })
+                })
+                .collect::<Result<_, CoreError>>()?;
+            town.supplied.push(SuppliedCargo {
+                cargo: CargoType(int(supplied, "cargo")? as u8),
+                history,
+            });
+        }
+        town.valid_history = int(record, "valid_history")?;
+    }
+
+    for supplied in &town.supplied {
+        if let Some(history) = supplied.history.get(LAST_MONTH) {
+            if supplied.cargo.is_valid() {
+                town.supplied_last_month[supplied.cargo.as_usize()] = history.production;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn load_received(town: &mut Town, record: &Record, version: u16) -> Result<(), CoreError> {
+    if version < SaveLoadVersion::V165 {
+        // Only what was actually received of food and water was kept
+        for (effect, name) in OLD_RECEIVED {
+            let stat = |key: &str| int(record, &format!("received[{}].{}", name, key));
+            town.received[effect].old_act = stat("old_act")? as u16;
+            town.received[effect].new_act = stat("new_act")? as u16;
+        }
+        return Ok(());
+    }
+    for (slot, stat) in town.received.iter_mut().zip(record.get_structs("received")) {
+        *slot = cargo_stat(stat, |v| v as u16)?;
+    }
+    Ok(())
+}
+
+fn town_from_record(index: usize, record: &Record, version: u16) -> Result<Town, CoreError> {
+    let mut town = Town::new(TownID(index as u16), TileIndex(int(record, "xy")? as u32));
+
+    if version >= SaveLoadVersion::V66 {
+        town.townnamegrfid = int(record, "townnamegrfid")? as u32;
+    }
+    town.townnametype = int(record, "townnametype")? as u16;
+    town.townnameparts = int(record, "townnameparts")? as u32;
+    town.custom_name = record.get_str("name").unwrap_or_default().into();
+    town.flags = int(record, "flags")? as u8;
+    town.have_statue = int(record, "statues")? as u16;
+    town.have_ratings = int(record, "have_ratings")? as u16;
+    fill(&mut town.ratings, record, "ratings", |v| {
+        v.as_i64().map(|v| v as i16)
+    });
+    fill(&mut town.unwanted, record, "unwanted", |v| {
+        v.as_i64().map(|v| v as i8)
+    });
+    fill(&mut town.goal, record, "goal", |v| {
+        v.as_u64().map(|v| v as u32)
+    });
+    town.text = record.get_str("text").unwrap_or_default().into();
+
+    town.time_until_rebuild = int(record, "time_until_rebuild")? as u16;
+    town.grow_counter = int(record, "grow_counter")? as u16;
+    town.growth_rate = int(record, "growth_rate")? as u16 as i16;
+    town.fund_buildings_months = int(record, "fund_buildings_months")? as u8;
+    town.road_build_months = int(record, "road_build_months")? as u8;
+    if version >= SaveLoadVersion::V2 {
+        town.exclusivity = Owner::try_from(int(record, "exclusivity")? as u8)?;
+        town.exclusive_counter = int(record, "exclusive_counter")? as u8;
+    }
+    if version >= SaveLoadVersion::V56 {
+        town.larger_town = int(record, "larger_town")? != 0;
+    }
+    if version >= SaveLoadVersion::V113 {
+        town.layout = TownLayout::try_from(int(record, "layout")? as u8)?;
+    }
+    town.psa_list = record
+        .get_list("psa_list")
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| v.as_u64().map(|v| v as u32))
+        .collect();
+
+    load_supplied(&mut town, record, version)?;
+    load_received(&mut town, record, version)?;
+    Ok(town)
+}
+
+/// Load all towns from the CITY chunk
+pub fn load_towns(chunks: &[Chunk], version: u16) -> Result<Vec<Town>, SavegameError> {
+    chunk_records(chunks, b"CITY", version, &town_desc(), &town_compat())?
+        .iter()
+        .map(|(index, record)| Ok(town_from_record(*index, record, version)?))
+        .collect()
+}
+
+fn cargo_stat_desc(data_type: DataType) -> Vec<SaveLoad> {
+    ["old_max", "new_max", "old_act", "new_act"]
+        .into_iter()
+        .map(|key| SaveLoad::var(data_type, key))
+        .collect()
+}
+
+/// Number of cells of the acceptance matrix, which has one per 4x4 tiles
+/// of its area (matches C++ SlTownAcceptanceMatrix)
+fn acceptance_cells(area: &Record) -> u64 {
+    let side = |key| area.get_u64(key).unwrap_or(0) / 4;
+    side("area.w") * side("area.h")
+}
+
+/// Field declarations of CITY (matches C++ _town_desc)
+fn town_desc() -> Vec<SaveLoad> {
+    let mut desc = vec![
+        SaveLoad::var(DataType::U16, "xy").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "xy").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "townnamegrfid").since(SaveLoadVersion::V66),
+        SaveLoad::var(DataType::U16, "townnametype"),
+        SaveLoad::var(DataType::U32, "townnameparts"),
+        SaveLoad::var(DataType::String, "name").since(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::U8, "flags"),
+        SaveLoad::var(DataType::U8, "statues").until(SaveLoadVersion::V104),
+        SaveLoad::var(DataType::U16, "statues").since(SaveLoadVersion::V104),
+        SaveLoad::var(DataType::U8, "have_ratings").until(SaveLoadVersion::V104),
+        SaveLoad::var(DataType::U16, "have_ratings").since(SaveLoadVersion::V104),
+        SaveLoad::array(DataType::I16, "ratings", 8).until(SaveLoadVersion::V104),
+        SaveLoad::array(DataType::I16, "ratings", 15).since(SaveLoadVersion::V104),
+        SaveLoad::array(DataType::I8, "unwanted", 8)
+            .since(SaveLoadVersion::V4)
+            .until(SaveLoadVersion::V104),
+        SaveLoad::array(DataType::I8, "unwanted", 15).since(SaveLoadVersion::V104),
+    ];
+
+    // Passengers and mail statistics of old savegames
+    for key in ["old_max", "new_max", "old_act", "new_act"] {
+        for (_, name) in OLD_SUPPLIED {
+            let key = format!("supplied[{}].{}", name, key);
+            desc.extend([
+                SaveLoad::var(DataType::U16, &key).until(SaveLoadVersion::V9),
+                SaveLoad::var(DataType::U32, &key)
+                    .since(SaveLoadVersion::V9)
+                    .until(SaveLoadVersion::V165),
+            ]);
+        }
+    }
+    for key in ["old_act", "new_act"] {
+        for (_, name) in OLD_RECEIVED {
+            let key = format!("received[{}].{}", name, key);
+            desc.push(SaveLoad::var(DataType::U16, &key).until(SaveLoadVersion::V165));
+        }
+    }
+
+    desc.extend([
+        SaveLoad::array(DataType::U32, "goal", NUM_TAE).since(SaveLoadVersion::V165),
+        SaveLoad::var(DataType::String, "text").since(SaveLoadVersion::V168),
+        SaveLoad::var(DataType::U8, "time_until_rebuild").until(SaveLoadVersion::V54),
+        SaveLoad::var(DataType::U16, "time_until_rebuild").since(SaveLoadVersion::V54),
+        SaveLoad::var(DataType::U8, "grow_counter").until(SaveLoadVersion::V54),
+        SaveLoad::var(DataType::U16, "grow_counter").since(SaveLoadVersion::V54),
+        SaveLoad::var(DataType::U8, "growth_rate").until(SaveLoadVersion::V54),
+        SaveLoad::var(DataType::I16, "growth_rate")
+            .since(SaveLoadVersion::V54)
+            .until(SaveLoadVersion::V165),
+        SaveLoad::var(DataType::U16, "growth_rate").since(SaveLoadVersion::V165),
+        SaveLoad::var(DataType::U8, "fund_buildings_months"),
+        SaveLoad::var(DataType::U8, "road_build_months"),
+        SaveLoad::var(DataType::U8, "exclusivity").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::U8, "exclusive_counter").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::I8, "larger_town").since(SaveLoadVersion::V56),
+        SaveLoad::var(DataType::U8, "layout").since(SaveLoadVersion::V113),
+        SaveLoad::var(DataType::U64, "valid_history").since(SaveLoadVersion::TownSupplyHistory),
+        SaveLoad::list(DataType::U32, "psa_list").since(SaveLoadVersion::V161),
+        // One statistic per cargo type, of which there were 32 at first
+        SaveLoad::structs("supplied", cargo_stat_desc(DataType::U32))
+            .length(ListLength::Fixed(32))
+            .since(SaveLoadVersion::V165)
+            .until(SaveLoadVersion::ExtendCargotypes),
+        SaveLoad::structs("supplied", cargo_stat_desc(DataType::U32))
+            .length(ListLength::Fixed(CargoType::NUM_CARGO))
+            .since(SaveLoadVersion::ExtendCargotypes)
+            .until(SaveLoadVersion::TownSupplyHistory),
+        SaveLoad::structs(
+            "supplied",
+            vec![
+                SaveLoad::var(DataType::U8, "cargo"),
+                SaveLoad::structs(
+                    "history",
+                    vec![
+                        SaveLoad::var(DataType::U32, "production"),
+                        SaveLoad::var(DataType::U32, "transported"),
+                    ],
+                ),
+            ],
+        )
+        .since(SaveLoadVersion::TownSupplyHistory),
+        SaveLoad::structs("received", cargo_stat_desc(DataType::U16))
+            .length(ListLength::Fixed(NUM_TAE))
+            .since(SaveLoadVersion::V165),
+        // Discarded when loading, including the cells following the area
+        SaveLoad::structs(
+            "acceptance_matrix",
+            vec![
+                SaveLoad::var(DataType::U32, "area.tile"),
+                SaveLoad::var(DataType::U16, "area.w"),
+                SaveLoad::var(DataType::U16, "area.h"),
+                SaveLoad::list(DataType::U32, "cells")
+                    .length(ListLength::Computed(acceptance_cells)),
+            ],
+        )
+        .since(SaveLoadVersion::V166)
+        .until(SaveLoadVersion::RemoveTownCargoCache),
+    ]);
+    desc
+}
+
+/// Order of the CITY fields in savegames without a table header
+/// (matches C++ _town_sl_compat)
+fn town_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = vec![
+        SaveLoadCompat::var("xy"),
+        SaveLoadCompat::null(2, SaveLoadVersion::MinVersion, SaveLoadVersion::V3),
+        SaveLoadCompat::null(4, SaveLoadVersion::V3, SaveLoadVersion::V85),
+        SaveLoadCompat::null(2, SaveLoadVersion::MinVersion, SaveLoadVersion::V92),
+        SaveLoadCompat::var("townnamegrfid"),
+        SaveLoadCompat::var("townnametype"),
+        SaveLoadCompat::var("townnameparts"),
+        SaveLoadCompat::var("name"),
+        SaveLoadCompat::var("flags"),
+        SaveLoadCompat::var("statues"),
+        SaveLoadCompat::null(1, SaveLoadVersion::MinVersion, SaveLoadVersion::V2),
+        SaveLoadCompat::var("have_ratings"),
+        SaveLoadCompat::var("ratings"),
+        SaveLoadCompat::var("unwanted"),
+    ];
+    for key in ["old_max", "new_max", "old_act", "new_act"] {
+        for (_, name) in OLD_SUPPLIED {
+            compat.push(SaveLoadCompat::var(&format!("supplied[{}].{}", name, key)));
+        }
+    }
+    compat.push(SaveLoadCompat::null(
+        2,
+        SaveLoadVersion::MinVersion,
+        SaveLoadVersion::V164,
+    ));
+    for key in ["old_act", "new_act"] {
+        for (_, name) in OLD_RECEIVED {
+            compat.push(SaveLoadCompat::var(&format!("received[{}].{}", name, key)));
+        }
+    }
+    compat.extend(
+        [
+            "goal",
+            "text",
+            "time_until_rebuild",
+            "grow_counter",
+            "growth_rate",
+            "fund_buildings_months",
+            "road_build_months",
+            "exclusivity",
+            "exclusive_counter",
+            "larger_town",
+            "layout",
+            "psa_list",
+        ]
+        .map(SaveLoadCompat::var),
+    );
+    compat.extend([
+        SaveLoadCompat::null(4, SaveLoadVersion::V166, SaveLoadVersion::ExtendCargotypes),
+        SaveLoadCompat::null(
+            8,
+            SaveLoadVersion::ExtendCargotypes,
+            SaveLoadVersion::RemoveTownCargoCache,
+        ),
+        SaveLoadCompat::null(
+            30,
+            SaveLoadVersion::V2,
+            SaveLoadVersion::RemoveTownCargoCache,
+        ),
+        SaveLoadCompat::var("supplied"),
+        SaveLoadCompat::var("received"),
+        SaveLoadCompat::var("acceptance_matrix"),
+    ]);
+    compat
+}
+
+fn cargo_stat_record<T: Into<Value>>(stat: TransportedCargoStat<T>) -> Record {
+    Record::default()
+        .with("old_max", stat.old_max)
+        .with("new_max", stat.new_max)
+        .with("old_act", stat.old_act)
+        .with("new_act", stat.new_act)
+}
+
+fn supplied_records(town: &Town, version: u16) -> Vec<Record> {
+    if version < SaveLoadVersion::TownSupplyHistory {
+        return (0..CargoType::NUM_CARGO)
+            .map(|cargo| {
+                let supplied = town.supplied.iter().find(|s| s.cargo.as_usize() == cargo);
+                let history = |i: usize| {
+                    supplied
+                        .and_then(|s| s.history.get(i))
+                        .copied()
+                        .unwrap_or_default()
+                };
+                cargo_stat_record(TransportedCargoStat {
+                    old_max: history(LAST_MONTH).production,
+                    new_max: history(THIS_MONTH).production,
+                    old_act: history(LAST_MONTH).transported,
+                    new_act: history(THIS_MONTH).transported,
+                })
+            })
+            .collect();
+    }
+
+    town.supplied
+        .iter()
+        .map(|supplied| {
+            let history: Vec<Record> = (0..HISTORY_RECORDS)
+                .map(|i| {
+                    let h = supplied.history.get(i).copied().unwrap_or_default();
+                    Record::default()
+                        .with("production", h.production)
+                        .with("transported", h.transported)
+                })
+                .collect();
+            Record::default()
+                .with("cargo", supplied.cargo.0)
+                .with("history", history)
+        })
+        .collect()
+}
+
+fn town_to_record(town: &Town, version: u16) -> Record {
+    let mut record = Record::default()
+        .with("xy", town.xy.0)
+        .with("townnamegrfid", town.townnamegrfid)
+        .with("townnametype", town.townnametype)
+        .with("townnameparts", town.townnameparts)
+        .with("name", town.custom_name.as_str())
+        .with("flags", town.flags)
+        .with("statues", town.have_statue)
+        .with("have_ratings", town.have_ratings)
+        .with("ratings", town.ratings.to_vec())
+        .with("unwanted", town.unwanted.to_vec())
+        .with("goal", town.goal.to_vec())
+        .with("text", town.text.as_str())
+        .with("time_until_rebuild", town.time_until_rebuild)
+        .with("grow_counter", town.grow_counter)
+        .with("growth_rate", town.growth_rate as u16)
+        .with("fund_buildings_months", town.fund_buildings_months)
+        .with("road_build_months", town.road_build_months)
+        .with("exclusivity", town.exclusivity as u8)
+        .with("exclusive_counter", town.exclusive_counter)
+        .with("larger_town", town.larger_town as i8)
+        .with("layout", town.layout as u8);
+
+    if version >= SaveLoadVersion::TownSupplyHistory {
+        record = record.with("valid_history", town.valid_history);
+    }
+
+    let received: Vec<Record> = town
+        .received
+        .iter()
+        .map(|s| cargo_stat_record(*s))
+        .collect();
+    record
+        .with("psa_list", town.psa_list.clone())
+        .with("supplied", supplied_records(town, version))
+        .with("received", received)
+}
+
+/// Write the CITY chunk in the layout of the writer's savegame version
+pub fn save_towns(writer: &mut SavegameWriter, towns: &[Town]) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let mut towns: Vec<&Town> = towns.iter().collect();
+    towns.sort_by_key(|t| t.index.0);
+    let records: Vec<(usize, Record)> = towns
+        .iter()
+        .map(|t| (t.index.0 as usize, town_to_record(t, version)))
+        .collect();
+
+    writer.add_table_records(
+        b"CITY",
+        ChunkType::Table,
+        &table_header(&town_desc(), version),
+        &records,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+
+    fn sample_town(index: u16) -> Town {
+        let mut town = Town::new(TownID(index), TileIndex(1234));
+        town.townnametype = 0x20C0;
+        town.custom_name = "Fooville".into();
+        town.have_ratings = 0b101;
+        town.ratings[0] = -200;
+        town.ratings[2] = 650;
+        town.unwanted[1] = 3;
+        town.goal = [0, 0, 0, 0, 5000, 1000];
+        town.growth_rate = 5669;
+        town.exclusivity = Owner::Company2;
+        town.exclusive_counter = 7;
+        town.larger_town = true;
+        town.layout = TownLayout::ThreeByThree;
+        town.psa_list = vec![4];
+        let mut history = vec![SuppliedHistory::default(); HISTORY_RECORDS];
+        history[THIS_MONTH] = SuppliedHistory {
+            production: 40,
+            transported: 12,
+        };
+        history[LAST_MONTH] = SuppliedHistory {
+            production: 98,
+            transported: 30,
+        };
+        town.supplied.push(SuppliedCargo {
+            cargo: CargoType(2),
+            history,
+        });
+        town.valid_history = 1 << LAST_MONTH;
+        town.supplied_last_month[2] = 98;
+        town.received[4] = TransportedCargoStat {
+            old_max: 1,
+            new_max: 2,
+            old_act: 3,
+            new_act: 4,
+        };
+        town
+    }
+
+    fn round_trip(towns: &[Town], version: u16) -> Vec<Town> {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_towns(&mut writer, towns).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_towns(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_towns_round_trip() {
+        let towns = vec![sample_town(3), sample_town(0)];
+        for version in [
+            SaveLoadVersion::TableChunks,
+            SaveLoadVersion::TownSupplyHistory,
+        ]
+        .map(u16::from)
+        {
+            let loaded = round_trip(&towns, version);
+            assert_eq!(loaded.len(), 2);
+            assert_eq!(loaded[0].index, TownID(0));
+            assert_eq!(loaded[1].index, TownID(3));
+            assert_eq!(loaded[1], towns[0]);
+        }
+    }
+
+    #[test]
+    fn test_towns_unsupported_version() {
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_towns(&mut writer, &[sample_town(0)]),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
@@ -1,3 +1,7 @@
+use crate::savegame::SavegameError;
+use std::fmt;
+use std::str::FromStr;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionType {
     Lzo,
@@ -5,3 +9,112 @@ pub enum CompressionType {
     Zlib,
     Lzma,
 }
+
+impl CompressionType {
+    const ALL: [CompressionType; 4] = [
+        CompressionType::Lzo,
+        CompressionType::None,
+        CompressionType::Zlib,
+        CompressionType::Lzma,
+    ];
+
+    /// Name used by the `savegame_format` setting
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressionType::Lzo => "lzo",
+            CompressionType::None => "none",
+            CompressionType::Zlib => "zlib",
+            CompressionType::Lzma => "lzma",
+        }
+    }
+
+    /// Lowest, default and highest compression level (matches C++ `_saveload_formats`)
+    pub fn levels(&self) -> (u8, u8, u8) {
+        match self {
+            CompressionType::Lzo | CompressionType::None => (0, 0, 0),
+            CompressionType::Zlib => (0, 6, 9),
+            CompressionType::Lzma => (0, 2, 9),
+        }
+    }
+}
+
+/// A compression type with the level to write it at, written as `name[:level]`
+/// like the C++ `savegame_format` setting, e.g. `lzma:2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SavegameFormat {
+    pub compression: CompressionType,
+    pub level: u8,
+}
+
+impl SavegameFormat {
+    pub fn new(compression: CompressionType, level: u8) -> Result<Self, SavegameError> {
+        let (min, _, max) = compression.levels();
+        if !(min..=max).contains(&level) {
+            return Err(SavegameError::InvalidSavegameFormat(format!(
+                "{}:{}",
+                compression.name(),
+                level
+            )));
+        }
+        Ok(Self { compression, level })
+    }
+}
+
+impl From<CompressionType> for SavegameFormat {
+    fn from(compression: CompressionType) -> Self {
+        Self {
+            compression,
+            level: compression.levels().1,
+        }
+    }
+}
+
+impl FromStr for SavegameFormat {
+    type Err = SavegameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SavegameError::InvalidSavegameFormat(s.into());
+        let (name, level) = match s.split_once(':') {
+            Some((name, level)) => (name, Some(level.parse().map_err(|_| invalid())?)),
+            None => (s, None),
+        };
+        let compression = CompressionType::ALL
+            .into_iter()
+            .find(|c| c.name() == name)
+            .ok_or_else(invalid)?;
+        match level {
+            Some(level) => Self::new(compression, level).map_err(|_| invalid()),
+            None => Ok(compression.into()),
+        }
+    }
+}
+
+impl fmt::Display for SavegameFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.compression.name(), self.level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_savegame_format() {
+        let format: SavegameFormat = "lzma:2".parse().unwrap();
+        assert_eq!(
+            format,
+            SavegameFormat::new(CompressionType::Lzma, 2).unwrap()
+        );
+        assert_eq!(format.to_string(), "lzma:2");
+
+        let format: SavegameFormat = "zlib".parse().unwrap();
+        assert_eq!(format.compression, CompressionType::Zlib);
+        assert_eq!(format.level, 6);
+        assert_eq!("none:0".parse::<SavegameFormat>().unwrap().level, 0);
+
+        for invalid in ["lzma:10", "lzo:1", "zlib:", "zlib:x", "gzip", "", "lzma:-1"] {
+            assert!(invalid.parse::<SavegameFormat>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,322 @@
+/// Cross-reference checks over a decoded savegame
+///
+/// The loaders check each chunk on its own; `validate` checks the references
+/// between them, which the C++ game assumes to hold and crashes on otherwise:
+/// vehicle chains, the towns of stations and industries, the pool indices in
+/// `m2` of town, station and industry tiles, and the owners in `m1`.
+use crate::json::SavegameDocument;
+use openttd_core::map::{Map, TileType};
+use openttd_core::types::{IndustryID, Owner, StationID, TownID, VehicleID};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Ground type of a clear tile that is a farm field (matches C++ CLEAR_FIELDS)
+const CLEAR_FIELDS: u8 = 3;
+/// Road tile type of depots in m5 bits 7..6 (matches C++ RoadTileType::Depot)
+const ROAD_TILE_DEPOT: u8 = 2;
+
+/// What a violation was found on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    Tile { x: u32, y: u32 },
+    Vehicle(VehicleID),
+    Station(StationID),
+    Industry(IndustryID),
+}
+
+/// A broken reference in the game state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub subject: Subject,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.subject {
+            Subject::Tile { x, y } => write!(f, "tile ({}, {})", x, y)?,
+            Subject::Vehicle(id) => write!(f, "vehicle {}", id.0)?,
+            Subject::Station(id) => write!(f, "station {}", id.0)?,
+            Subject::Industry(id) => write!(f, "industry {}", id.0)?,
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+struct Validator<'a> {
+    document: &'a SavegameDocument,
+    towns: HashSet<TownID>,
+    stations: HashSet<StationID>,
+    industries: HashSet<IndustryID>,
+    companies: HashSet<u8>,
+    violations: Vec<Violation>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, subject: Subject, message: String) {
+        self.violations.push(Violation { subject, message });
+    }
+
+    fn check_vehicles(&mut self) {
+        let vehicles: HashMap<VehicleID, Option<VehicleID>> = self
+            .document
+            .vehicles
+            .iter()
+            .map(|v| (v.index, v.next))
+            .collect();
+
+        let mut previous = HashMap::new();
+        for v in &self.document.vehicles {
+            let subject = Subject::Vehicle(v.index);
+            for (name, target) in [
+                ("next", v.next),
+                ("first", v.first),
+                ("next_shared", v.next_shared),
+            ] {
+                match target {
+                    Some(id) if !vehicles.contains_key(&id) => self.report(
+                        subject,
+                        format!("{} refers to missing vehicle {}", name, id.0),
+                    ),
+                    _ => {}
+                }
+            }
+            if let Some(next) = v.next {
+                if let Some(other) = previous.insert(next, v.index) {
+                    self.report(
+                        subject,
+                        format!(
+                            "vehicle {} follows both {} and {}",
+                            next.0, other.0, v.index.0
+                        ),
+                    );
+                }
+            }
+        }
+
+        // Walk every chain once; a chain that reaches a vehicle of its own walk is a loop
+        let mut done = HashSet::new();
+        for v in &self.document.vehicles {
+            let mut walk = Vec::new();
+            let mut current = Some(v.index);
+            while let Some(id) = current.filter(|id| !done.contains(id)) {
+                if walk.contains(&id) {
+                    self.report(
+                        Subject::Vehicle(id),
+                        "is part of a vehicle chain loop".into(),
+                    );
+                    break;
+                }
+                walk.push(id);
+                current = vehicles.get(&id).copied().flatten();
+            }
+            done.extend(walk);
+        }
+
+        // `first` is the front of the chain, which is not anyone's next vehicle
+        for v in &self.document.vehicles {
+            if let Some(first) = v.first.filter(|first| previous.contains_key(first)) {
+                self.report(
+                    Subject::Vehicle(v.index),
+                    format!(
+                        "first refers to vehicle {}, which is not a front vehicle",
+                        first.0
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_pools(&mut self) {
+        for station in &self.document.stations {
+            if !self.towns.contains(&station.town) {
+                let message = format!("refers to missing town {}", station.town.0);
+                self.report(Subject::Station(station.index), message);
+            }
+        }
+        for industry in &self.document.industries {
+            if !self.towns.contains(&industry.town) {
+                let message = format!("refers to missing town {}", industry.town.0);
+                self.report(Subject::Industry(industry.index), message);
+            }
+        }
+    }
+
+    fn check_tiles(&mut self, map: &Map) {
+        for (i, tile) in map.tiles.iter().enumerate() {
+            let subject = Subject::Tile {
+                x: i as u32 % map.size_x,
+                y: i as u32 / map.size_x,
+            };
+            let base = &tile.base;
+            // `tile_type` clamps, so check the raw bits
+            if base.type_height >> 4 > TileType::Object as u8 {
+                self.report(
+                    subject,
+                    format!("invalid tile type {}", base.type_height >> 4),
+                );
+                continue;
+            }
+
+            let tile_type = base.tile_type();
+            let m2 = base.m2;
+            let missing = match tile_type {
+                TileType::House => (!self.towns.contains(&TownID(m2))).then_some("town"),
+                TileType::Road if base.m5 >> 6 != ROAD_TILE_DEPOT => {
+                    let town = TownID(m2);
+                    (town != TownID::INVALID && !self.towns.contains(&town)).then_some("town")
+                }
+                TileType::Station => (!self.stations.contains(&StationID(m2))).then_some("station"),
+                TileType::Industry => {
+                    (!self.industries.contains(&IndustryID(m2))).then_some("industry")
+                }
+                TileType::Clear if (base.m5 >> 2) & 0x07 == CLEAR_FIELDS => {
+                    let industry = IndustryID(m2);
+                    (industry != IndustryID::INVALID && !self.industries.contains(&industry))
+                        .then_some("industry")
+                }
+                _ => None,
+            };
+            if let Some(pool) = missing {
+                self.report(subject, format!("m2 refers to missing {} {}", pool, m2));
+            }
+
+            // Houses and industries use m1 for other data; void tiles have no owner
+            if matches!(
+                tile_type,
+                TileType::House | TileType::Industry | TileType::Void
+            ) {
+                continue;
+            }
+            let owner = base.m1 & 0x1F;
+            match Owner::try_from(owner) {
+                Err(_) => self.report(subject, format!("invalid owner {}", owner)),
+                Ok(owner) if owner.is_company() && !self.companies.contains(&(owner as u8)) => {
+                    self.report(subject, format!("owned by missing company {}", owner as u8))
+                }
+                Ok(_) => {}
+            }
+        }
+    }
+}
+
+/// Check the references between the parts of a decoded savegame
+pub fn validate(document: &SavegameDocument) -> Vec<Violation> {
+    let mut validator = Validator {
+        document,
+        towns: document.towns.iter().map(|t| t.index).collect(),
+        stations: document.stations.iter().map(|s| s.index).collect(),
+        industries: document.industries.iter().map(|i| i.index).collect(),
+        companies: document.companies.iter().map(|c| c.index).collect(),
+        violations: Vec::new(),
+    };
+    validator.check_vehicles();
+    validator.check_pools();
+    validator.check_tiles(&document.map);
+    validator.violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::SaveLoadVersion;
+    use openttd_core::company::Company;
+    use openttd_core::industry::Industry;
+    use openttd_core::map::{Tile, TileIndex};
+    use openttd_core::station::Station;
+    use openttd_core::town::Town;
+    use openttd_core::vehicle::{Vehicle, VehicleType};
+
+    fn train(index: u32, next: Option<u32>, first: u32) -> Vehicle {
+        let mut v = Vehicle::new(VehicleID(index), VehicleType::Train);
+        v.next = next.map(VehicleID);
+        v.first = Some(VehicleID(first));
+        v
+    }
+
+    fn valid_document() -> SavegameDocument {
+        let mut map = Map::new(6, 6).unwrap();
+        let mut clear = Tile::new_clear(0);
+        clear.base.m1 = Owner::None as u8;
+        map.tiles.fill(clear);
+        let mut station = Station::new(StationID(4), TileIndex(65), Owner::Company0);
+        station.town = TownID(1);
+        let mut industry = Industry::new(IndustryID(2), TileIndex(200), 0);
+        industry.town = TownID(1);
+
+        let mut house = Tile::new_clear(0);
+        house.base.set_tile_type(TileType::House);
+        house.base.m2 = 1;
+        map.tiles[64] = house;
+        let mut station_tile = Tile::new_clear(0);
+        station_tile.base.set_tile_type(TileType::Station);
+        station_tile.base.m2 = 4;
+        station_tile.base.m1 = Owner::Company0 as u8;
+        map.tiles[65] = station_tile;
+
+        SavegameDocument {
+            version: SaveLoadVersion::CURRENT.into(),
+            map,
+            companies: vec![Company::new(0, 0)],
+            towns: vec![Town::new(TownID(1), TileIndex(66))],
+            stations: vec![station],
+            industries: vec![industry],
+            vehicles: vec![train(0, Some(1), 0), train(1, None, 0), train(2, None, 2)],
+            chunks: Vec::new(),
+        }
+    }
+
+    fn messages(document: &SavegameDocument) -> Vec<String> {
+        validate(document).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_valid_document() {
+        assert!(validate(&valid_document()).is_empty());
+    }
+
+    #[test]
+    fn test_vehicle_chains() {
+        let mut document = valid_document();
+        document.vehicles[1].next = Some(VehicleID(0));
+        document.vehicles[2].next = Some(VehicleID(9));
+        assert_eq!(
+            messages(&document),
+            [
+                "vehicle 2: next refers to missing vehicle 9",
+                "vehicle 0: is part of a vehicle chain loop",
+                "vehicle 0: first refers to vehicle 0, which is not a front vehicle",
+                "vehicle 1: first refers to vehicle 0, which is not a front vehicle",
+            ]
+        );
+
+        let mut document = valid_document();
+        document.vehicles[2].next = Some(VehicleID(1));
+        assert_eq!(
+            messages(&document),
+            ["vehicle 2: vehicle 1 follows both 0 and 2"]
+        );
+    }
+
+    #[test]
+    fn test_pool_references() {
+        let mut document = valid_document();
+        document.stations[0].town = TownID(7);
+        document.industries[0].town = TownID::INVALID;
+        document.map.tiles[64].base.m2 = 3;
+        document.map.tiles[65].base.m1 = Owner::Company2 as u8;
+        document.map.tiles[66].base.m1 = 0x13;
+        document.map.tiles[67].base.type_height = 0xB0;
+        assert_eq!(
+            messages(&document),
+            [
+                "station 4: refers to missing town 7",
+                "industry 2: refers to missing town 65535",
+                "tile (0, 1): m2 refers to missing town 3",
+                "tile (1, 1): owned by missing company 2",
+                "tile (2, 1): invalid owner 19",
+                "tile (3, 1): invalid tile type 11",
+            ]
+        );
+    }
+}
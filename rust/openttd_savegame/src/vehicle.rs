@@ -0,0 +1,1540 @@
+/// Loading and saving of the VEHS chunk
+///
+/// Every vehicle record holds its type byte followed by one sub-struct per
+/// vehicle type, of which only the one matching the type is filled; savegames
+/// without a table header hold that one alone. The four company vehicle types
+/// share a `common` sub-struct. Vehicle, order list and
+/// cargo packet references are saved as index + 1, with 0 meaning none. Values
+/// whose meaning changed between versions (such as the timetable start, a date
+/// before version 321) are kept as saved; converting them is left to the caller.
+use crate::chunk::{ChunkType, DataType};
+use crate::savegame::{chunk_records, Chunk, SavegameError, SavegameWriter};
+use crate::table::{int, int_list, part, reference, signed, to_reference, Record, Value};
+use crate::version::{table_header, ListLength, SaveLoad, SaveLoadCompat, SaveLoadVersion};
+use openttd_core::cargopacket::NUM_MOVE_TO_ACTION;
+use openttd_core::error::CoreError;
+use openttd_core::map::TileIndex;
+use openttd_core::types::{
+    CalendarDate, CalendarYear, CargoType, EconomyDate, EngineID, GroupID, Owner, StationID,
+    VehicleID,
+};
+use openttd_core::vehicle::{
+    AircraftData, Direction, DisasterData, EffectData, RoadVehPathElement, RoadVehicleData,
+    ShipData, ShipPathElement, TrackBits, TrainData, TrainForceProceeding, Vehicle,
+    VehicleAirFlags, VehicleRailFlags, VehicleRandomTriggers, VehicleStates, VehicleType,
+    VehicleTypeData,
+};
+use std::collections::HashMap;
+
+/// Rail type saved for trains without one
+const INVALID_RAILTYPE: u8 = 0xFF;
+
+fn vehicle_reference(record: &Record, key: &str) -> Result<Option<VehicleID>, CoreError> {
+    Ok(reference(record, key)?.map(VehicleID))
+}
+
+fn to_vehicle_reference(id: Option<VehicleID>) -> u32 {
+    to_reference(id.map(|v| v.0))
+}
+
+/// Fields shared by all company vehicle types (C++ SlVehicleCommon)
+fn load_common(v: &mut Vehicle, c: &Record, version: u16) -> Result<(), CoreError> {
+    v.subtype = int(c, "subtype")? as u8;
+    v.next = vehicle_reference(c, "next")?;
+    // Names before SaveLoadVersion::V84 are string IDs into the old name table
+    v.name = c.get_str("name").unwrap_or_default().into();
+    v.unitnumber = int(c, "unitnumber")? as u16;
+    v.owner = Owner::try_from(int(c, "owner")? as u8)?;
+    v.tile = TileIndex(int(c, "tile")? as u32);
+    v.dest_tile = TileIndex(int(c, "dest_tile")? as u32);
+    v.x_pos = int(c, "x_pos")? as i32;
+    v.y_pos = int(c, "y_pos")? as i32;
+    v.z_pos = int(c, "z_pos")? as i32;
+    v.direction = Direction::try_from(int(c, "direction")? as u8)?;
+    v.spritenum = int(c, "spritenum")? as u8;
+    v.engine_type = EngineID(int(c, "engine_type")? as u16);
+    v.cur_speed = int(c, "cur_speed")? as u16;
+    v.subspeed = int(c, "subspeed")? as u8;
+    v.acceleration = int(c, "acceleration")? as u8;
+    if version >= SaveLoadVersion::VehMotionCounter {
+        v.motion_counter = int(c, "motion_counter")? as u32;
+    }
+    v.progress = int(c, "progress")? as u8;
+    v.vehstatus = VehicleStates::from_bits_retain(int(c, "vehstatus")? as u8);
+    v.last_station_visited = match int(c, "last_station_visited")? as u16 {
+        // Old savegames used 0xFF for no station
+        0xFF if version < SaveLoadVersion::V5 => StationID::INVALID,
+        station => StationID(station),
+    };
+    if version >= SaveLoadVersion::V182 {
+        v.last_loading_station = StationID(int(c, "last_loading_station")? as u16);
+        v.refit_cap = int(c, "refit_cap")? as u16;
+    }
+    v.cargo_type = CargoType(int(c, "cargo_type")? as u8);
+    if version >= SaveLoadVersion::V35 {
+        v.cargo_subtype = int(c, "cargo_subtype")? as u8;
+    }
+    v.cargo_cap = int(c, "cargo_cap")? as u16;
+    // Before cargo packets the cargo is a single amount, which is not converted
+    if version >= SaveLoadVersion::V68 {
+        v.cargo.packets = int_list(c, "cargo.packets")?
+            .into_iter()
+            .filter_map(|p| p.checked_sub(1).map(|p| p as u32))
+            .collect();
+    }
+    if version >= SaveLoadVersion::V181 {
+        let action_counts = int_list(c, "cargo.action_counts")?;
+        if action_counts.len() != NUM_MOVE_TO_ACTION {
+            return Err(CoreError::InvalidData(format!(
+                "VEHS: expected {} cargo action counts, found {}",
+                NUM_MOVE_TO_ACTION,
+                action_counts.len()
+            )));
+        }
+        for (count, value) in v.cargo.action_counts.iter_mut().zip(action_counts) {
+            *count = value as u32;
+        }
+    }
+    if version >= SaveLoadVersion::V162 {
+        v.cargo_age_counter = int(c, "cargo_age_counter")? as u16;
+    }
+    v.day_counter = int(c, "day_counter")? as u8;
+    v.tick_counter = int(c, "tick_counter")? as u8;
+    if version >= SaveLoadVersion::V88 {
+        v.running_ticks = int(c, "running_ticks")? as u8;
+    }
+    v.cur_implicit_order_index = int(c, "cur_implicit_order_index")? as u8;
+    if version >= SaveLoadVersion::V158 {
+        v.cur_real_order_index = int(c, "cur_real_order_index")? as u8;
+    }
+    let order_type = int(c, "current_order.type")? as u8;
+    if version < SaveLoadVersion::V5 {
+        // Type and flags shared one byte of four bits each
+        v.current_order.type_ = order_type & 0x0F;
+        v.current_order.flags = order_type >> 4;
+    } else {
+        v.current_order.type_ = order_type;
+        v.current_order.flags = int(c, "current_order.flags")? as u8;
+    }
+    v.current_order.dest = int(c, "current_order.dest")? as u16;
+    if version >= SaveLoadVersion::V36 {
+        v.current_order.refit_cargo = CargoType(int(c, "current_order.refit_cargo")? as u8);
+    }
+    if version >= SaveLoadVersion::V67 {
+        v.current_order.wait_time = int(c, "current_order.wait_time")? as u16;
+        v.current_order.travel_time = int(c, "current_order.travel_time")? as u16;
+    }
+    if version >= SaveLoadVersion::V174 {
+        v.current_order.max_speed = int(c, "current_order.max_speed")? as u16;
+    }
+    if version >= SaveLoadVersion::TimetableStartTicks {
+        v.timetable_start = int(c, "timetable_start")?;
+    } else if version >= SaveLoadVersion::V129 {
+        v.timetable_start = int(c, "timetable_start")? as i32 as u64;
+    }
+    // Before order lists, `orders` is the index of the first order in ORDR
+    if version >= SaveLoadVersion::V105 {
+        v.orders = reference(c, "orders")?;
+    }
+    v.age = CalendarDate(int(c, "age")? as i32);
+    if version >= SaveLoadVersion::VehicleEconomyAge {
+        v.economy_age = EconomyDate(int(c, "economy_age")? as i32);
+    }
+    v.max_age = CalendarDate(int(c, "max_age")? as i32);
+    v.date_of_last_service = EconomyDate(int(c, "date_of_last_service")? as i32);
+    if version >= SaveLoadVersion::NewgrfLastService {
+        v.date_of_last_service_newgrf = CalendarDate(int(c, "date_of_last_service_newgrf")? as i32);
+    }
+    v.service_interval = int(c, "service_interval")? as u16;
+    v.reliability = int(c, "reliability")? as u16;
+    v.reliability_spd_dec = int(c, "reliability_spd_dec")? as u16;
+    v.breakdown_ctr = int(c, "breakdown_ctr")? as u8;
+    v.breakdown_delay = int(c, "breakdown_delay")? as u8;
+    v.breakdowns_since_last_service = int(c, "breakdowns_since_last_service")? as u8;
+    v.breakdown_chance = int(c, "breakdown_chance")? as u8;
+    v.build_year = CalendarYear(int(c, "build_year")? as i32);
+    v.load_unload_ticks = int(c, "load_unload_ticks")? as u16;
+    if version >= SaveLoadVersion::V40 {
+        v.vehicle_flags = int(c, "vehicle_flags")? as u16;
+    }
+    v.profit_this_year = signed(c, "profit_this_year")?;
+    v.profit_last_year = signed(c, "profit_last_year")?;
+    v.value = signed(c, "value")?;
+    if version >= SaveLoadVersion::V2 {
+        v.random_bits = int(c, "random_bits")? as u16;
+        v.waiting_random_triggers =
+            VehicleRandomTriggers::from_bits_retain(int(c, "waiting_triggers")? as u8);
+        v.next_shared = vehicle_reference(c, "next_shared")?;
+    }
+    v.group_id = if version >= SaveLoadVersion::V60 {
+        GroupID(int(c, "group_id")? as u16)
+    } else {
+        GroupID::DEFAULT
+    };
+    if version >= SaveLoadVersion::V67 {
+        v.current_order_time = int(c, "current_order_time")? as i32;
+        v.lateness_counter = int(c, "lateness_counter")? as i32;
+    }
+    if version >= SaveLoadVersion::LastLoadingTick {
+        v.last_loading_tick = int(c, "last_loading_tick")?;
+    }
+    if version >= SaveLoadVersion::DepotUnbunching {
+        v.depot_unbunching_last_departure = int(c, "depot_unbunching_last_departure")?;
+        v.depot_unbunching_next_departure = int(c, "depot_unbunching_next_departure")?;
+        v.round_trip_time = int(c, "round_trip_time")? as i32;
+    }
+    Ok(())
+}
+
+fn load_train(r: &Record, version: u16) -> Result<TrainData, CoreError> {
+    let mut train = TrainData {
+        crash_anim_pos: int(r, "crash_anim_pos")? as u16,
+        force_proceed: TrainForceProceeding::try_from(int(r, "force_proceed")? as u8)?,
+        track: TrackBits::try_from(int(r, "track")? as u8)?,
+        ..TrainData::default()
+    };
+    if version >= SaveLoadVersion::V2 {
+        train.flags = VehicleRailFlags::from_bits_retain(int(r, "flags")? as u16);
+    }
+    if version >= SaveLoadVersion::V136 {
+        train.wait_counter = int(r, "wait_counter")? as u16;
+    }
+    if version >= SaveLoadVersion::V139 {
+        train.gv_flags = int(r, "gv_flags")? as u16;
+    }
+    if version < SaveLoadVersion::EngineMultiRailtype {
+        train.railtypes = 1u64.checked_shl(int(r, "railtype")? as u32).unwrap_or(0);
+    }
+    Ok(train)
+}
+
+fn load_road_vehicle(r: &Record, version: u16) -> Result<RoadVehicleData, CoreError> {
+    let path = if version < SaveLoadVersion::RoadvehPathCache {
+        Vec::new()
+    } else if version < SaveLoadVersion::PathCacheFormat {
+        let trackdirs = int_list(r, "path.td")?;
+        let tiles = int_list(r, "path.tile")?;
+        // Like the C++ loader, ignore a cache whose halves do not match
+        if trackdirs.len() == tiles.len() {
+            trackdirs
+                .into_iter()
+                .zip(tiles)
+                .rev()
+                .map(|(trackdir, tile)| RoadVehPathElement {
+                    trackdir: trackdir as u8,
+                    tile: TileIndex(tile as u32),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    } else {
+        r.get_structs("path")
+            .map(|p| {
+                Ok(RoadVehPathElement {
+                    trackdir: int(p, "trackdir")? as u8,
+                    tile: TileIndex(int(p, "tile")? as u32),
+                })
+            })
+            .collect::<Result<_, CoreError>>()?
+    };
+
+    let mut road = RoadVehicleData {
+        path,
+        state: int(r, "state")? as u8,
+        frame: int(r, "frame")? as u8,
+        blocked_ctr: int(r, "blocked_ctr")? as u16,
+        overtaking: int(r, "overtaking")? as u8,
+        overtaking_ctr: int(r, "overtaking_ctr")? as u8,
+        crashed_ctr: int(r, "crashed_ctr")? as u16,
+        reverse_ctr: int(r, "reverse_ctr")? as u8,
+        ..RoadVehicleData::default()
+    };
+    if version >= SaveLoadVersion::V139 {
+        road.gv_flags = int(r, "gv_flags")? as u16;
+    }
+    Ok(road)
+}
+
+fn load_ship(r: &Record, version: u16) -> Result<ShipData, CoreError> {
+    let path = if version < SaveLoadVersion::ShipPathCache {
+        Vec::new()
+    } else if version < SaveLoadVersion::PathCacheFormat {
+        int_list(r, "path")?
+            .into_iter()
+            .rev()
+            .map(|trackdir| ShipPathElement {
+                trackdir: trackdir as u8,
+            })
+            .collect()
+    } else {
+        r.get_structs("path")
+            .map(|p| {
+                Ok(ShipPathElement {
+                    trackdir: int(p, "trackdir")? as u8,
+                })
+            })
+            .collect::<Result<_, CoreError>>()?
+    };
+
+    let mut ship = ShipData {
+        path,
+        state: TrackBits::try_from(int(r, "state")? as u8)?,
+        ..ShipData::default()
+    };
+    if version >= SaveLoadVersion::ShipRotation {
+        ship.rotation = Direction::try_from(int(r, "rotation")? as u8)?;
+    }
+    Ok(ship)
+}
+
+fn load_aircraft(r: &Record, version: u16) -> Result<AircraftData, CoreError> {
+    let mut aircraft = AircraftData {
+        crashed_counter: int(r, "crashed_counter")? as u16,
+        pos: int(r, "pos")? as u8,
+        targetairport: StationID(int(r, "targetairport")? as u16),
+        state: int(r, "state")? as u8,
+        ..AircraftData::default()
+    };
+    if version >= SaveLoadVersion::V2 {
+        aircraft.previous_pos = int(r, "previous_pos")? as u8;
+        aircraft.last_direction = Direction::try_from(int(r, "last_direction")? as u8)?;
+        aircraft.number_consecutive_turns = int(r, "number_consecutive_turns")? as u8;
+    }
+    if version >= SaveLoadVersion::V136 {
+        aircraft.turn_counter = int(r, "turn_counter")? as u8;
+    }
+    if version >= SaveLoadVersion::V167 {
+        aircraft.flags = VehicleAirFlags::from_bits_retain(int(r, "flags")? as u8);
+    }
+    Ok(aircraft)
+}
+
+/// Effect vehicles only save their position and animation (C++ SlVehicleEffect)
+fn load_effect(v: &mut Vehicle, r: &Record, version: u16) -> Result<EffectData, CoreError> {
+    v.subtype = int(r, "subtype")? as u8;
+    v.tile = TileIndex(int(r, "tile")? as u32);
+    v.x_pos = signed(r, "x_pos")? as i32;
+    v.y_pos = signed(r, "y_pos")? as i32;
+    v.z_pos = int(r, "z_pos")? as i32;
+    v.sprite_cache.sprite_seq.seq[0].sprite =
+        int(r, "sprite_cache.sprite_seq.seq[0].sprite")? as u32;
+    v.progress = int(r, "progress")? as u8;
+    v.vehstatus = VehicleStates::from_bits_retain(int(r, "vehstatus")? as u8);
+    if version >= SaveLoadVersion::V2 {
+        v.spritenum = int(r, "spritenum")? as u8;
+    }
+    Ok(EffectData {
+        animation_state: int(r, "animation_state")? as u16,
+        animation_substate: int(r, "animation_substate")? as u8,
+    })
+}
+
+fn load_disaster(v: &mut Vehicle, r: &Record, version: u16) -> Result<DisasterData, CoreError> {
+    v.next = vehicle_reference(r, "next")?;
+    v.subtype = int(r, "subtype")? as u8;
+    v.tile = TileIndex(int(r, "tile")? as u32);
+    v.dest_tile = TileIndex(int(r, "dest_tile")? as u32);
+    v.x_pos = signed(r, "x_pos")? as i32;
+    v.y_pos = signed(r, "y_pos")? as i32;
+    v.z_pos = int(r, "z_pos")? as i32;
+    v.direction = Direction::try_from(int(r, "direction")? as u8)?;
+    v.owner = Owner::try_from(int(r, "owner")? as u8)?;
+    v.vehstatus = VehicleStates::from_bits_retain(int(r, "vehstatus")? as u8);
+    v.sprite_cache.sprite_seq.seq[0].sprite =
+        int(r, "sprite_cache.sprite_seq.seq[0].sprite")? as u32;
+    v.age = CalendarDate(int(r, "age")? as i32);
+    v.tick_counter = int(r, "tick_counter")? as u8;
+
+    let state_key = if version < SaveLoadVersion::DisasterVehState {
+        "current_order.dest"
+    } else {
+        "state"
+    };
+    let mut disaster = DisasterData {
+        image_override: int(r, "image_override")? as u32,
+        big_ufo_destroyer_target: VehicleID(int(r, "big_ufo_destroyer_target")? as u32),
+        state: int(r, state_key)? as u16,
+        ..DisasterData::default()
+    };
+    if version >= SaveLoadVersion::V194 {
+        disaster.flags = VehicleAirFlags::from_bits_retain(int(r, "flags")? as u8);
+    }
+    Ok(disaster)
+}
+
+fn vehicle_from_record(index: usize, record: &Record, version: u16) -> Result<Vehicle, CoreError> {
+    let type_ = VehicleType::try_from(int(record, "type")? as u8)?;
+    let mut v = Vehicle::new(VehicleID(index as u32), type_);
+
+    v.type_data = match type_ {
+        VehicleType::Train => {
+            let r = part(record, "train")?;
+            load_common(&mut v, part(r, "common")?, version)?;
+            VehicleTypeData::Train(load_train(r, version)?)
+        }
+        VehicleType::Road => {
+            let r = part(record, "roadveh")?;
+            load_common(&mut v, part(r, "common")?, version)?;
+            VehicleTypeData::RoadVehicle(load_road_vehicle(r, version)?)
+        }
+        VehicleType::Ship => {
+            let r = part(record, "ship")?;
+            load_common(&mut v, part(r, "common")?, version)?;
+            VehicleTypeData::Ship(load_ship(r, version)?)
+        }
+        VehicleType::Aircraft => {
+            let r = part(record, "aircraft")?;
+            load_common(&mut v, part(r, "common")?, version)?;
+            VehicleTypeData::Aircraft(load_aircraft(r, version)?)
+        }
+        VehicleType::Effect => {
+            VehicleTypeData::Effect(load_effect(&mut v, part(record, "effect")?, version)?)
+        }
+        VehicleType::Disaster => {
+            VehicleTypeData::Disaster(load_disaster(&mut v, part(record, "disaster")?, version)?)
+        }
+        VehicleType::Invalid => unreachable!("rejected by VehicleType::try_from"),
+    };
+    Ok(v)
+}
+
+/// Check the saved references between vehicles and set `first` of every
+/// vehicle to the front of its chain, like C++ `AfterLoadVehicles`
+fn link_chains(vehicles: &mut [Vehicle]) -> Result<(), CoreError> {
+    let positions: HashMap<VehicleID, usize> = vehicles
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (v.index, i))
+        .collect();
+    let lookup = |from: VehicleID, to: VehicleID| {
+        positions.get(&to).copied().ok_or_else(|| {
+            CoreError::InvalidData(format!(
+                "VEHS: vehicle {} refers to missing vehicle {}",
+                from.0, to.0
+            ))
+        })
+    };
+
+    let mut previous = vec![None; vehicles.len()];
+    for (i, v) in vehicles.iter().enumerate() {
+        if let Some(shared) = v.next_shared {
+            lookup(v.index, shared)?;
+        }
+        if let Some(next) = v.next {
+            let n = lookup(v.index, next)?;
+            if previous[n].replace(i).is_some() {
+                return Err(CoreError::InvalidData(format!(
+                    "VEHS: vehicle {} is part of two chains",
+                    next.0
+                )));
+            }
+        }
+    }
+
+    let mut linked = 0;
+    for front in (0..vehicles.len()).filter(|&i| previous[i].is_none()) {
+        let front_id = vehicles[front].index;
+        let mut current = Some(front);
+        while let Some(i) = current {
+            vehicles[i].first = Some(front_id);
+            linked += 1;
+            current = vehicles[i].next.map(|next| positions[&next]);
+        }
+    }
+    if linked != vehicles.len() {
+        return Err(CoreError::InvalidData("VEHS: vehicle chain loop".into()));
+    }
+    Ok(())
+}
+
+/// Load all vehicles from the VEHS chunk, with their chains linked
+pub fn load_vehicles(chunks: &[Chunk], version: u16) -> Result<Vec<Vehicle>, SavegameError> {
+    let mut vehicles = chunk_records(chunks, b"VEHS", version, &vehicle_desc(), &vehicle_compat())?
+        .iter()
+        .map(|(index, record)| vehicle_from_record(*index, record, version))
+        .collect::<Result<Vec<_>, CoreError>>()?;
+    link_chains(&mut vehicles)?;
+    Ok(vehicles)
+}
+
+/// Fields of the C++ SlVehicleCommon handler
+fn common_desc() -> Vec<SaveLoad> {
+    vec![
+        SaveLoad::var(DataType::U8, "subtype"),
+        SaveLoad::var(DataType::U16, "next").until(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, "next").since(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::StringId, "name").until(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::String, "name").since(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::U8, "unitnumber").until(SaveLoadVersion::V8),
+        SaveLoad::var(DataType::U16, "unitnumber").since(SaveLoadVersion::V8),
+        SaveLoad::var(DataType::U8, "owner"),
+        SaveLoad::var(DataType::U16, "tile").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "tile").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U16, "dest_tile").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "dest_tile").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U16, "x_pos").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "x_pos").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U16, "y_pos").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "y_pos").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U8, "z_pos").until(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::I32, "z_pos").since(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::U8, "direction"),
+        SaveLoad::var(DataType::U8, "spritenum"),
+        SaveLoad::var(DataType::U16, "engine_type"),
+        SaveLoad::var(DataType::U16, "cur_speed"),
+        SaveLoad::var(DataType::U8, "subspeed"),
+        SaveLoad::var(DataType::U8, "acceleration"),
+        SaveLoad::var(DataType::U32, "motion_counter").since(SaveLoadVersion::VehMotionCounter),
+        SaveLoad::var(DataType::U8, "progress"),
+        SaveLoad::var(DataType::U8, "vehstatus"),
+        SaveLoad::var(DataType::U8, "last_station_visited").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U16, "last_station_visited").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U16, "last_loading_station").since(SaveLoadVersion::V182),
+        SaveLoad::var(DataType::U8, "cargo_type"),
+        SaveLoad::var(DataType::U8, "cargo_subtype").since(SaveLoadVersion::V35),
+        SaveLoad::var(DataType::U8, "cargo_days").until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U8, "cargo_source").until(SaveLoadVersion::V7),
+        SaveLoad::var(DataType::U16, "cargo_source")
+            .since(SaveLoadVersion::V7)
+            .until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U32, "cargo_source_xy")
+            .since(SaveLoadVersion::V44)
+            .until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::U16, "cargo_cap"),
+        SaveLoad::var(DataType::U16, "refit_cap").since(SaveLoadVersion::V182),
+        SaveLoad::var(DataType::U16, "cargo_count").until(SaveLoadVersion::V68),
+        SaveLoad::list(DataType::U16, "cargo.packets")
+            .since(SaveLoadVersion::V68)
+            .until(SaveLoadVersion::V69),
+        SaveLoad::list(DataType::U32, "cargo.packets").since(SaveLoadVersion::V69),
+        SaveLoad::array(DataType::U32, "cargo.action_counts", NUM_MOVE_TO_ACTION)
+            .since(SaveLoadVersion::V181),
+        SaveLoad::var(DataType::U16, "cargo_age_counter").since(SaveLoadVersion::V162),
+        SaveLoad::var(DataType::U8, "day_counter"),
+        SaveLoad::var(DataType::U8, "tick_counter"),
+        SaveLoad::var(DataType::U8, "running_ticks").since(SaveLoadVersion::V88),
+        SaveLoad::var(DataType::U8, "cur_implicit_order_index"),
+        SaveLoad::var(DataType::U8, "cur_real_order_index").since(SaveLoadVersion::V158),
+        // Type and flags in one byte, and a station index of a byte
+        SaveLoad::var(DataType::U8, "current_order.type").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U8, "current_order.dest").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U8, "current_order.type").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U8, "current_order.flags").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U16, "current_order.dest").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U8, "current_order.refit_cargo").since(SaveLoadVersion::V36),
+        SaveLoad::var(DataType::U16, "current_order.wait_time").since(SaveLoadVersion::V67),
+        SaveLoad::var(DataType::U16, "current_order.travel_time").since(SaveLoadVersion::V67),
+        SaveLoad::var(DataType::U16, "current_order.max_speed").since(SaveLoadVersion::V174),
+        SaveLoad::var(DataType::I32, "timetable_start")
+            .since(SaveLoadVersion::V129)
+            .until(SaveLoadVersion::TimetableStartTicks),
+        SaveLoad::var(DataType::U64, "timetable_start").since(SaveLoadVersion::TimetableStartTicks),
+        SaveLoad::var(DataType::U16, "orders").until(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, "orders").since(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U16, "age").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "age").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "economy_age").since(SaveLoadVersion::VehicleEconomyAge),
+        SaveLoad::var(DataType::U16, "max_age").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "max_age").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::U16, "date_of_last_service").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "date_of_last_service").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "date_of_last_service_newgrf")
+            .since(SaveLoadVersion::NewgrfLastService),
+        SaveLoad::var(DataType::U16, "service_interval").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::U32, "service_interval")
+            .since(SaveLoadVersion::V31)
+            .until(SaveLoadVersion::V180),
+        SaveLoad::var(DataType::U16, "service_interval").since(SaveLoadVersion::V180),
+        SaveLoad::var(DataType::U16, "reliability"),
+        SaveLoad::var(DataType::U16, "reliability_spd_dec"),
+        SaveLoad::var(DataType::U8, "breakdown_ctr"),
+        SaveLoad::var(DataType::U8, "breakdown_delay"),
+        SaveLoad::var(DataType::U8, "breakdowns_since_last_service"),
+        SaveLoad::var(DataType::U8, "breakdown_chance"),
+        SaveLoad::var(DataType::U8, "build_year").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "build_year").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::U16, "load_unload_ticks"),
+        SaveLoad::var(DataType::U16, "cargo_paid_for").since(SaveLoadVersion::V45),
+        SaveLoad::var(DataType::U8, "vehicle_flags")
+            .since(SaveLoadVersion::V40)
+            .until(SaveLoadVersion::V180),
+        SaveLoad::var(DataType::U16, "vehicle_flags").since(SaveLoadVersion::V180),
+        SaveLoad::var(DataType::I32, "profit_this_year").until(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I64, "profit_this_year").since(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I32, "profit_last_year").until(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I64, "profit_last_year").since(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I32, "cargo_feeder_share")
+            .since(SaveLoadVersion::V51)
+            .until(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I64, "cargo_feeder_share")
+            .since(SaveLoadVersion::V65)
+            .until(SaveLoadVersion::V68),
+        SaveLoad::var(DataType::I32, "value").until(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::I64, "value").since(SaveLoadVersion::V65),
+        SaveLoad::var(DataType::U8, "random_bits")
+            .since(SaveLoadVersion::V2)
+            .until(SaveLoadVersion::ExtendVehicleRandom),
+        SaveLoad::var(DataType::U16, "random_bits").since(SaveLoadVersion::ExtendVehicleRandom),
+        SaveLoad::var(DataType::U8, "waiting_triggers").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::U16, "next_shared")
+            .since(SaveLoadVersion::V2)
+            .until(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, "next_shared").since(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U16, "group_id").since(SaveLoadVersion::V60),
+        SaveLoad::var(DataType::U32, "current_order_time")
+            .since(SaveLoadVersion::V67)
+            .until(SaveLoadVersion::TimetableTicksType),
+        SaveLoad::var(DataType::I32, "current_order_time")
+            .since(SaveLoadVersion::TimetableTicksType),
+        SaveLoad::var(DataType::U64, "last_loading_tick").since(SaveLoadVersion::LastLoadingTick),
+        SaveLoad::var(DataType::I32, "lateness_counter").since(SaveLoadVersion::V67),
+        SaveLoad::var(DataType::U64, "depot_unbunching_last_departure")
+            .since(SaveLoadVersion::DepotUnbunching),
+        SaveLoad::var(DataType::U64, "depot_unbunching_next_departure")
+            .since(SaveLoadVersion::DepotUnbunching),
+        SaveLoad::var(DataType::I32, "round_trip_time").since(SaveLoadVersion::DepotUnbunching),
+    ]
+}
+
+/// Whether the sub-struct of a vehicle type is saved, which the C++ handler
+/// decides by the type byte at the start of the record
+fn type_count(record: &Record, type_: VehicleType) -> u64 {
+    (record.get_u64("type") == Some(type_ as u64)) as u64
+}
+
+/// Field declarations of VEHS (matches C++ _vehicle_desc)
+fn vehicle_desc() -> Vec<SaveLoad> {
+    let common = || SaveLoad::structs("common", common_desc()).compat(common_compat());
+    let old_paths = |sld: SaveLoad| sld.until(SaveLoadVersion::PathCacheFormat);
+    let new_paths = |sld: SaveLoad| sld.since(SaveLoadVersion::PathCacheFormat);
+
+    let train = vec![
+        common(),
+        SaveLoad::var(DataType::U16, "crash_anim_pos"),
+        SaveLoad::var(DataType::U8, "force_proceed"),
+        SaveLoad::var(DataType::U8, "railtype").until(SaveLoadVersion::EngineMultiRailtype),
+        SaveLoad::var(DataType::U8, "track"),
+        SaveLoad::var(DataType::U8, "flags")
+            .since(SaveLoadVersion::V2)
+            .until(SaveLoadVersion::V100),
+        SaveLoad::var(DataType::U16, "flags").since(SaveLoadVersion::V100),
+        SaveLoad::var(DataType::U16, "wait_counter").since(SaveLoadVersion::V136),
+        SaveLoad::var(DataType::U16, "gv_flags").since(SaveLoadVersion::V139),
+    ];
+
+    let roadveh = vec![
+        common(),
+        SaveLoad::var(DataType::U8, "state"),
+        SaveLoad::var(DataType::U8, "frame"),
+        SaveLoad::var(DataType::U16, "blocked_ctr"),
+        SaveLoad::var(DataType::U8, "overtaking"),
+        SaveLoad::var(DataType::U8, "overtaking_ctr"),
+        SaveLoad::var(DataType::U16, "crashed_ctr"),
+        SaveLoad::var(DataType::U8, "reverse_ctr"),
+        old_paths(SaveLoad::list(DataType::U8, "path.td").since(SaveLoadVersion::RoadvehPathCache)),
+        old_paths(
+            SaveLoad::list(DataType::U32, "path.tile").since(SaveLoadVersion::RoadvehPathCache),
+        ),
+        new_paths(SaveLoad::structs(
+            "path",
+            vec![
+                SaveLoad::var(DataType::U8, "trackdir"),
+                SaveLoad::var(DataType::U32, "tile"),
+            ],
+        )),
+        SaveLoad::var(DataType::U16, "gv_flags").since(SaveLoadVersion::V139),
+    ];
+
+    let ship = vec![
+        common(),
+        SaveLoad::var(DataType::U8, "state"),
+        old_paths(SaveLoad::list(DataType::U8, "path").since(SaveLoadVersion::ShipPathCache)),
+        new_paths(SaveLoad::structs(
+            "path",
+            vec![SaveLoad::var(DataType::U8, "trackdir")],
+        )),
+        SaveLoad::var(DataType::U8, "rotation").since(SaveLoadVersion::ShipRotation),
+    ];
+
+    let aircraft = vec![
+        common(),
+        SaveLoad::var(DataType::U16, "crashed_counter"),
+        SaveLoad::var(DataType::U8, "pos"),
+        SaveLoad::var(DataType::U8, "targetairport").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U16, "targetairport").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U8, "state"),
+        SaveLoad::var(DataType::U8, "previous_pos").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::U8, "last_direction").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::U8, "number_consecutive_turns").since(SaveLoadVersion::V2),
+        SaveLoad::var(DataType::U8, "turn_counter").since(SaveLoadVersion::V136),
+        SaveLoad::var(DataType::U8, "flags").since(SaveLoadVersion::V167),
+    ];
+
+    let effect = vec![
+        SaveLoad::var(DataType::U8, "subtype"),
+        SaveLoad::var(DataType::U16, "tile").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "tile").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::I16, "x_pos").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::I32, "x_pos").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::I16, "y_pos").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::I32, "y_pos").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U8, "z_pos").until(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::I32, "z_pos").since(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::U16, "sprite_cache.sprite_seq.seq[0].sprite"),
+        SaveLoad::var(DataType::U8, "progress"),
+        SaveLoad::var(DataType::U8, "vehstatus"),
+        SaveLoad::var(DataType::U16, "animation_state"),
+        SaveLoad::var(DataType::U8, "animation_substate"),
+        SaveLoad::var(DataType::U8, "spritenum").since(SaveLoadVersion::V2),
+    ];
+
+    let disaster = vec![
+        SaveLoad::var(DataType::U16, "next").until(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U32, "next").since(SaveLoadVersion::V69),
+        SaveLoad::var(DataType::U8, "subtype"),
+        SaveLoad::var(DataType::U16, "tile").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "tile").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U16, "dest_tile").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U32, "dest_tile").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::I16, "x_pos").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::I32, "x_pos").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::I16, "y_pos").until(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::I32, "y_pos").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U8, "z_pos").until(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::I32, "z_pos").since(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::U8, "direction"),
+        SaveLoad::var(DataType::U8, "owner"),
+        SaveLoad::var(DataType::U8, "vehstatus"),
+        SaveLoad::var(DataType::U8, "current_order.dest").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U16, "current_order.dest")
+            .since(SaveLoadVersion::V5)
+            .until(SaveLoadVersion::DisasterVehState),
+        SaveLoad::var(DataType::U16, "state").since(SaveLoadVersion::DisasterVehState),
+        SaveLoad::var(DataType::U16, "sprite_cache.sprite_seq.seq[0].sprite"),
+        SaveLoad::var(DataType::U16, "age").until(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::I32, "age").since(SaveLoadVersion::V31),
+        SaveLoad::var(DataType::U8, "tick_counter"),
+        SaveLoad::var(DataType::U16, "image_override").until(SaveLoadVersion::V191),
+        SaveLoad::var(DataType::U32, "image_override").since(SaveLoadVersion::V191),
+        SaveLoad::var(DataType::U16, "big_ufo_destroyer_target").until(SaveLoadVersion::V191),
+        SaveLoad::var(DataType::U32, "big_ufo_destroyer_target").since(SaveLoadVersion::V191),
+        SaveLoad::var(DataType::U8, "flags").since(SaveLoadVersion::V194),
+    ];
+
+    vec![
+        SaveLoad::var(DataType::U8, "type"),
+        SaveLoad::structs("train", train)
+            .length(ListLength::Computed(|r| type_count(r, VehicleType::Train)))
+            .compat(train_compat()),
+        SaveLoad::structs("roadveh", roadveh)
+            .length(ListLength::Computed(|r| type_count(r, VehicleType::Road)))
+            .compat(roadveh_compat()),
+        SaveLoad::structs("ship", ship)
+            .length(ListLength::Computed(|r| type_count(r, VehicleType::Ship)))
+            .compat(ship_compat()),
+        SaveLoad::structs("aircraft", aircraft)
+            .length(ListLength::Computed(|r| {
+                type_count(r, VehicleType::Aircraft)
+            }))
+            .compat(aircraft_compat()),
+        SaveLoad::structs("effect", effect)
+            .length(ListLength::Computed(|r| type_count(r, VehicleType::Effect)))
+            .compat(effect_compat()),
+        SaveLoad::structs("disaster", disaster)
+            .length(ListLength::Computed(|r| {
+                type_count(r, VehicleType::Disaster)
+            }))
+            .compat(disaster_compat()),
+    ]
+}
+
+/// Order of the SlVehicleCommon fields in savegames without a table header
+/// (matches C++ _vehicle_common_sl_compat)
+fn common_compat() -> Vec<SaveLoadCompat> {
+    let var = SaveLoadCompat::var;
+    let null = SaveLoadCompat::null;
+    let min = SaveLoadVersion::MinVersion;
+    vec![
+        var("subtype"),
+        var("next"),
+        var("name"),
+        var("unitnumber"),
+        var("owner"),
+        var("tile"),
+        var("dest_tile"),
+        var("x_pos"),
+        var("y_pos"),
+        var("z_pos"),
+        var("direction"),
+        null(2, min, SaveLoadVersion::V58),
+        var("spritenum"),
+        null(5, min, SaveLoadVersion::V58),
+        var("engine_type"),
+        null(2, min, SaveLoadVersion::V152),
+        var("cur_speed"),
+        var("subspeed"),
+        var("acceleration"),
+        var("motion_counter"),
+        var("progress"),
+        var("vehstatus"),
+        var("last_station_visited"),
+        var("last_loading_station"),
+        var("cargo_type"),
+        var("cargo_subtype"),
+        var("cargo_days"),
+        var("cargo_source"),
+        var("cargo_source_xy"),
+        var("cargo_cap"),
+        var("refit_cap"),
+        var("cargo_count"),
+        var("cargo.packets"),
+        var("cargo.action_counts"),
+        var("cargo_age_counter"),
+        var("day_counter"),
+        var("tick_counter"),
+        var("running_ticks"),
+        var("cur_implicit_order_index"),
+        var("cur_real_order_index"),
+        null(1, min, SaveLoadVersion::V105),
+        var("current_order.type"),
+        var("current_order.flags"),
+        var("current_order.dest"),
+        var("current_order.refit_cargo"),
+        null(1, SaveLoadVersion::V36, SaveLoadVersion::V182),
+        var("current_order.wait_time"),
+        var("current_order.travel_time"),
+        var("current_order.max_speed"),
+        var("timetable_start"),
+        var("orders"),
+        var("age"),
+        var("max_age"),
+        var("date_of_last_service"),
+        var("service_interval"),
+        var("reliability"),
+        var("reliability_spd_dec"),
+        var("breakdown_ctr"),
+        var("breakdown_delay"),
+        var("breakdowns_since_last_service"),
+        var("breakdown_chance"),
+        var("build_year"),
+        var("load_unload_ticks"),
+        var("cargo_paid_for"),
+        var("vehicle_flags"),
+        var("profit_this_year"),
+        var("profit_last_year"),
+        var("cargo_feeder_share"),
+        null(4, SaveLoadVersion::V51, SaveLoadVersion::V68),
+        var("value"),
+        var("random_bits"),
+        var("waiting_triggers"),
+        var("next_shared"),
+        null(2, SaveLoadVersion::V2, SaveLoadVersion::V69),
+        null(4, SaveLoadVersion::V69, SaveLoadVersion::V101),
+        var("group_id"),
+        var("current_order_time"),
+        var("lateness_counter"),
+        null(10, SaveLoadVersion::V2, SaveLoadVersion::V144),
+    ]
+}
+
+/// Order of the SlVehicleTrain fields in savegames without a table header
+/// (matches C++ _vehicle_train_sl_compat, which skips the rail type that
+/// this loader still reads)
+fn train_compat() -> Vec<SaveLoadCompat> {
+    vec![
+        SaveLoadCompat::var("common"),
+        SaveLoadCompat::var("crash_anim_pos"),
+        SaveLoadCompat::var("force_proceed"),
+        SaveLoadCompat::var("railtype"),
+        SaveLoadCompat::var("track"),
+        SaveLoadCompat::var("flags"),
+        SaveLoadCompat::null(2, SaveLoadVersion::V2, SaveLoadVersion::V60),
+        SaveLoadCompat::var("wait_counter"),
+        SaveLoadCompat::null(2, SaveLoadVersion::V2, SaveLoadVersion::V20),
+        SaveLoadCompat::var("gv_flags"),
+        SaveLoadCompat::null(11, SaveLoadVersion::V2, SaveLoadVersion::V144),
+    ]
+}
+
+/// Order of the SlVehicleRoadVeh fields in savegames without a table header
+/// (matches C++ _vehicle_roadveh_sl_compat)
+fn roadveh_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = [
+        "common",
+        "state",
+        "frame",
+        "blocked_ctr",
+        "overtaking",
+        "overtaking_ctr",
+        "crashed_ctr",
+        "reverse_ctr",
+        "path.td",
+        "path.tile",
+    ]
+    .map(SaveLoadCompat::var)
+    .to_vec();
+    compat.extend([
+        SaveLoadCompat::null(2, SaveLoadVersion::V6, SaveLoadVersion::V69),
+        SaveLoadCompat::var("gv_flags"),
+        SaveLoadCompat::null(4, SaveLoadVersion::V69, SaveLoadVersion::V131),
+        SaveLoadCompat::null(2, SaveLoadVersion::V6, SaveLoadVersion::V131),
+        SaveLoadCompat::null(16, SaveLoadVersion::V2, SaveLoadVersion::V144),
+    ]);
+    compat
+}
+
+/// Order of the SlVehicleShip fields in savegames without a table header
+/// (matches C++ _vehicle_ship_sl_compat)
+fn ship_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = ["common", "state", "path", "rotation"]
+        .map(SaveLoadCompat::var)
+        .to_vec();
+    compat.push(SaveLoadCompat::null(
+        16,
+        SaveLoadVersion::V2,
+        SaveLoadVersion::V144,
+    ));
+    compat
+}
+
+/// Order of the SlVehicleAircraft fields in savegames without a table header
+/// (matches C++ _vehicle_aircraft_sl_compat)
+fn aircraft_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = [
+        "common",
+        "crashed_counter",
+        "pos",
+        "targetairport",
+        "state",
+        "previous_pos",
+        "last_direction",
+        "number_consecutive_turns",
+        "turn_counter",
+        "flags",
+    ]
+    .map(SaveLoadCompat::var)
+    .to_vec();
+    compat.push(SaveLoadCompat::null(
+        13,
+        SaveLoadVersion::V2,
+        SaveLoadVersion::V144,
+    ));
+    compat
+}
+
+/// Order of the SlVehicleEffect fields in savegames without a table header
+/// (matches C++ _vehicle_effect_sl_compat)
+fn effect_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = [
+        "subtype",
+        "tile",
+        "x_pos",
+        "y_pos",
+        "z_pos",
+        "sprite_cache.sprite_seq.seq[0].sprite",
+    ]
+    .map(SaveLoadCompat::var)
+    .to_vec();
+    compat.push(SaveLoadCompat::null(
+        5,
+        SaveLoadVersion::MinVersion,
+        SaveLoadVersion::V59,
+    ));
+    compat.extend(
+        [
+            "progress",
+            "vehstatus",
+            "animation_state",
+            "animation_substate",
+            "spritenum",
+        ]
+        .map(SaveLoadCompat::var),
+    );
+    compat.push(SaveLoadCompat::null(
+        15,
+        SaveLoadVersion::V2,
+        SaveLoadVersion::V144,
+    ));
+    compat
+}
+
+/// Order of the SlVehicleDisaster fields in savegames without a table header
+/// (matches C++ _vehicle_disaster_sl_compat)
+fn disaster_compat() -> Vec<SaveLoadCompat> {
+    let mut compat = [
+        "next",
+        "subtype",
+        "tile",
+        "dest_tile",
+        "x_pos",
+        "y_pos",
+        "z_pos",
+        "direction",
+    ]
+    .map(SaveLoadCompat::var)
+    .to_vec();
+    compat.push(SaveLoadCompat::null(
+        5,
+        SaveLoadVersion::MinVersion,
+        SaveLoadVersion::V58,
+    ));
+    compat.extend(
+        [
+            "owner",
+            "vehstatus",
+            "current_order.dest",
+            "sprite_cache.sprite_seq.seq[0].sprite",
+            "age",
+            "tick_counter",
+            "image_override",
+            "big_ufo_destroyer_target",
+            "flags",
+        ]
+        .map(SaveLoadCompat::var),
+    );
+    compat.push(SaveLoadCompat::null(
+        16,
+        SaveLoadVersion::V2,
+        SaveLoadVersion::V144,
+    ));
+    compat
+}
+
+/// Order of the VEHS fields in savegames without a table header
+/// (matches C++ _vehicle_sl_compat)
+fn vehicle_compat() -> Vec<SaveLoadCompat> {
+    [
+        "type", "train", "roadveh", "ship", "aircraft", "effect", "disaster",
+    ]
+    .map(SaveLoadCompat::var)
+    .into()
+}
+
+fn common_record(v: &Vehicle, version: u16) -> Record {
+    let packets: Vec<u32> = v.cargo.packets.iter().map(|&p| p + 1).collect();
+
+    let mut record = Record::default()
+        .with("subtype", v.subtype)
+        .with("next", to_vehicle_reference(v.next))
+        .with("name", v.name.as_str())
+        .with("unitnumber", v.unitnumber)
+        .with("owner", v.owner as u8)
+        .with("tile", v.tile.0)
+        .with("dest_tile", v.dest_tile.0)
+        .with("x_pos", v.x_pos as u32)
+        .with("y_pos", v.y_pos as u32)
+        .with("z_pos", v.z_pos)
+        .with("direction", v.direction as u8)
+        .with("spritenum", v.spritenum)
+        .with("engine_type", v.engine_type.0)
+        .with("cur_speed", v.cur_speed)
+        .with("subspeed", v.subspeed)
+        .with("acceleration", v.acceleration)
+        .with("motion_counter", v.motion_counter)
+        .with("progress", v.progress)
+        .with("vehstatus", v.vehstatus.bits())
+        .with("last_station_visited", v.last_station_visited.0)
+        .with("last_loading_station", v.last_loading_station.0)
+        .with("cargo_type", v.cargo_type.0)
+        .with("cargo_subtype", v.cargo_subtype)
+        .with("cargo_cap", v.cargo_cap)
+        .with("refit_cap", v.refit_cap)
+        .with("cargo.packets", packets)
+        .with("cargo.action_counts", v.cargo.action_counts.to_vec())
+        .with("cargo_age_counter", v.cargo_age_counter)
+        .with("day_counter", v.day_counter)
+        .with("tick_counter", v.tick_counter)
+        .with("running_ticks", v.running_ticks)
+        .with("cur_implicit_order_index", v.cur_implicit_order_index)
+        .with("cur_real_order_index", v.cur_real_order_index)
+        .with("current_order.type", v.current_order.type_)
+        .with("current_order.flags", v.current_order.flags)
+        .with("current_order.dest", v.current_order.dest)
+        .with("current_order.refit_cargo", v.current_order.refit_cargo.0)
+        .with("current_order.wait_time", v.current_order.wait_time)
+        .with("current_order.travel_time", v.current_order.travel_time)
+        .with("current_order.max_speed", v.current_order.max_speed);
+    record = if version < SaveLoadVersion::TimetableStartTicks {
+        record.with("timetable_start", v.timetable_start as i32)
+    } else {
+        record.with("timetable_start", v.timetable_start)
+    };
+    record = record
+        .with("orders", to_reference(v.orders))
+        .with("age", v.age.0);
+    if version >= SaveLoadVersion::VehicleEconomyAge {
+        record = record.with("economy_age", v.economy_age.0);
+    }
+    record = record
+        .with("max_age", v.max_age.0)
+        .with("date_of_last_service", v.date_of_last_service.0);
+    if version >= SaveLoadVersion::NewgrfLastService {
+        record = record.with(
+            "date_of_last_service_newgrf",
+            v.date_of_last_service_newgrf.0,
+        );
+    }
+    record = record
+        .with("service_interval", v.service_interval)
+        .with("reliability", v.reliability)
+        .with("reliability_spd_dec", v.reliability_spd_dec)
+        .with("breakdown_ctr", v.breakdown_ctr)
+        .with("breakdown_delay", v.breakdown_delay)
+        .with(
+            "breakdowns_since_last_service",
+            v.breakdowns_since_last_service,
+        )
+        .with("breakdown_chance", v.breakdown_chance)
+        .with("build_year", v.build_year.0)
+        .with("load_unload_ticks", v.load_unload_ticks)
+        // Only read by loaders of saves before cargo packets; C++ writes a stale global
+        .with("cargo_paid_for", 0u16)
+        .with("vehicle_flags", v.vehicle_flags)
+        .with("profit_this_year", v.profit_this_year)
+        .with("profit_last_year", v.profit_last_year)
+        .with("value", v.value);
+    record = if version < SaveLoadVersion::ExtendVehicleRandom {
+        record.with("random_bits", v.random_bits as u8)
+    } else {
+        record.with("random_bits", v.random_bits)
+    };
+    record = record
+        .with("waiting_triggers", v.waiting_random_triggers.bits())
+        .with("next_shared", to_vehicle_reference(v.next_shared))
+        .with("group_id", v.group_id.0);
+    record = if version < SaveLoadVersion::TimetableTicksType {
+        record.with("current_order_time", v.current_order_time as u32)
+    } else {
+        record.with("current_order_time", v.current_order_time)
+    };
+    if version >= SaveLoadVersion::LastLoadingTick {
+        record = record.with("last_loading_tick", v.last_loading_tick);
+    }
+    record = record.with("lateness_counter", v.lateness_counter);
+    if version >= SaveLoadVersion::DepotUnbunching {
+        record = record
+            .with(
+                "depot_unbunching_last_departure",
+                v.depot_unbunching_last_departure,
+            )
+            .with(
+                "depot_unbunching_next_departure",
+                v.depot_unbunching_next_departure,
+            )
+            .with("round_trip_time", v.round_trip_time);
+    }
+    record
+}
+
+fn train_record(common: Record, train: &TrainData, version: u16) -> Record {
+    let mut record = Record::default()
+        .with("common", vec![common])
+        .with("crash_anim_pos", train.crash_anim_pos)
+        .with("force_proceed", train.force_proceed as u8);
+    if version < SaveLoadVersion::EngineMultiRailtype {
+        let railtype = match train.railtypes {
+            0 => INVALID_RAILTYPE,
+            types => types.trailing_zeros() as u8,
+        };
+        record = record.with("railtype", railtype);
+    }
+    record
+        .with("track", train.track as u8)
+        .with("flags", train.flags.bits())
+        .with("wait_counter", train.wait_counter)
+        .with("gv_flags", train.gv_flags)
+}
+
+fn road_vehicle_record(common: Record, road: &RoadVehicleData, version: u16) -> Record {
+    let mut record = Record::default()
+        .with("common", vec![common])
+        .with("state", road.state)
+        .with("frame", road.frame)
+        .with("blocked_ctr", road.blocked_ctr)
+        .with("overtaking", road.overtaking)
+        .with("overtaking_ctr", road.overtaking_ctr)
+        .with("crashed_ctr", road.crashed_ctr)
+        .with("reverse_ctr", road.reverse_ctr);
+    if version < SaveLoadVersion::PathCacheFormat {
+        // The old format stores the path from front to back
+        let trackdirs: Vec<u8> = road.path.iter().rev().map(|p| p.trackdir).collect();
+        let tiles: Vec<u32> = road.path.iter().rev().map(|p| p.tile.0).collect();
+        record = record.with("path.td", trackdirs).with("path.tile", tiles);
+    } else {
+        let path: Vec<Record> = road
+            .path
+            .iter()
+            .map(|p| {
+                Record::default()
+                    .with("trackdir", p.trackdir)
+                    .with("tile", p.tile.0)
+            })
+            .collect();
+        record = record.with("path", path);
+    }
+    record.with("gv_flags", road.gv_flags)
+}
+
+fn ship_record(common: Record, ship: &ShipData, version: u16) -> Record {
+    let path: Value = if version < SaveLoadVersion::PathCacheFormat {
+        let trackdirs: Vec<u8> = ship.path.iter().rev().map(|p| p.trackdir).collect();
+        trackdirs.into()
+    } else {
+        let path: Vec<Record> = ship
+            .path
+            .iter()
+            .map(|p| Record::default().with("trackdir", p.trackdir))
+            .collect();
+        path.into()
+    };
+    Record::default()
+        .with("common", vec![common])
+        .with("state", ship.state as u8)
+        .with("path", path)
+        .with("rotation", ship.rotation as u8)
+}
+
+fn aircraft_record(common: Record, aircraft: &AircraftData) -> Record {
+    Record::default()
+        .with("common", vec![common])
+        .with("crashed_counter", aircraft.crashed_counter)
+        .with("pos", aircraft.pos)
+        .with("targetairport", aircraft.targetairport.0)
+        .with("state", aircraft.state)
+        .with("previous_pos", aircraft.previous_pos)
+        .with("last_direction", aircraft.last_direction as u8)
+        .with(
+            "number_consecutive_turns",
+            aircraft.number_consecutive_turns,
+        )
+        .with("turn_counter", aircraft.turn_counter)
+        .with("flags", aircraft.flags.bits())
+}
+
+fn effect_record(v: &Vehicle, effect: &EffectData) -> Record {
+    Record::default()
+        .with("subtype", v.subtype)
+        .with("tile", v.tile.0)
+        .with("x_pos", v.x_pos)
+        .with("y_pos", v.y_pos)
+        .with("z_pos", v.z_pos)
+        .with(
+            "sprite_cache.sprite_seq.seq[0].sprite",
+            v.sprite_cache.sprite_seq.seq[0].sprite as u16,
+        )
+        .with("progress", v.progress)
+        .with("vehstatus", v.vehstatus.bits())
+        .with("animation_state", effect.animation_state)
+        .with("animation_substate", effect.animation_substate)
+        .with("spritenum", v.spritenum)
+}
+
+fn disaster_record(v: &Vehicle, disaster: &DisasterData, version: u16) -> Record {
+    let state_key = if version < SaveLoadVersion::DisasterVehState {
+        "current_order.dest"
+    } else {
+        "state"
+    };
+    Record::default()
+        .with("next", to_vehicle_reference(v.next))
+        .with("subtype", v.subtype)
+        .with("tile", v.tile.0)
+        .with("dest_tile", v.dest_tile.0)
+        .with("x_pos", v.x_pos)
+        .with("y_pos", v.y_pos)
+        .with("z_pos", v.z_pos)
+        .with("direction", v.direction as u8)
+        .with("owner", v.owner as u8)
+        .with("vehstatus", v.vehstatus.bits())
+        .with(state_key, disaster.state)
+        .with(
+            "sprite_cache.sprite_seq.seq[0].sprite",
+            v.sprite_cache.sprite_seq.seq[0].sprite as u16,
+        )
+        .with("age", v.age.0)
+        .with("tick_counter", v.tick_counter)
+        .with("image_override", disaster.image_override)
+        .with(
+            "big_ufo_destroyer_target",
+            disaster.big_ufo_destroyer_target.0,
+        )
+        .with("flags", disaster.flags.bits())
+}
+
+fn vehicle_to_record(v: &Vehicle, version: u16) -> Result<Record, CoreError> {
+    // Only the sub-struct of the vehicle's own type is filled
+    let mut parts: [Vec<Record>; 6] = Default::default();
+    let (slot, part) = match (&v.type_, &v.type_data) {
+        (VehicleType::Train, VehicleTypeData::Train(t)) => {
+            (0, train_record(common_record(v, version), t, version))
+        }
+        (VehicleType::Road, VehicleTypeData::RoadVehicle(r)) => (
+            1,
+            road_vehicle_record(common_record(v, version), r, version),
+        ),
+        (VehicleType::Ship, VehicleTypeData::Ship(s)) => {
+            (2, ship_record(common_record(v, version), s, version))
+        }
+        (VehicleType::Aircraft, VehicleTypeData::Aircraft(a)) => {
+            (3, aircraft_record(common_record(v, version), a))
+        }
+        (VehicleType::Effect, VehicleTypeData::Effect(e)) => (4, effect_record(v, e)),
+        (VehicleType::Disaster, VehicleTypeData::Disaster(d)) => {
+            (5, disaster_record(v, d, version))
+        }
+        _ => {
+            return Err(CoreError::InvalidData(format!(
+                "VEHS: vehicle {} of type {:?} has mismatched type data",
+                v.index.0, v.type_
+            )))
+        }
+    };
+    parts[slot].push(part);
+
+    let [train, roadveh, ship, aircraft, effect, disaster] = parts;
+    Ok(Record::default()
+        .with("type", v.type_ as u8)
+        .with("train", train)
+        .with("roadveh", roadveh)
+        .with("ship", ship)
+        .with("aircraft", aircraft)
+        .with("effect", effect)
+        .with("disaster", disaster))
+}
+
+/// Write the VEHS chunk in the layout of the writer's savegame version
+pub fn save_vehicles(
+    writer: &mut SavegameWriter,
+    vehicles: &[Vehicle],
+) -> Result<(), SavegameError> {
+    let version = writer.version();
+    if version < SaveLoadVersion::TableChunks {
+        return Err(SavegameError::UnsupportedVersion(version));
+    }
+
+    let header = table_header(&vehicle_desc(), version);
+    let mut vehicles: Vec<&Vehicle> = vehicles.iter().collect();
+    vehicles.sort_by_key(|v| v.index.0);
+    let records = vehicles
+        .iter()
+        .map(|v| Ok((v.index.0 as usize, vehicle_to_record(v, version)?)))
+        .collect::<Result<Vec<_>, CoreError>>()?;
+
+    writer.add_table_records(b"VEHS", ChunkType::SparseTable, &header, &records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::savegame::SavegameReader;
+    use crate::types::CompressionType;
+
+    /// A two-part train sharing its orders with a road vehicle
+    fn sample_vehicles() -> Vec<Vehicle> {
+        let mut engine = Vehicle::new(VehicleID(0), VehicleType::Train);
+        engine.next = Some(VehicleID(3));
+        engine.next_shared = Some(VehicleID(5));
+        engine.name = "Express".into();
+        engine.owner = Owner::Company0;
+        engine.tile = TileIndex(1234);
+        engine.x_pos = 512;
+        engine.direction = Direction::SE;
+        engine.engine_type = EngineID(7);
+        engine.cargo.packets = vec![0, 4];
+        engine.cargo.action_counts = [0, 10, 20, 0];
+        engine.orders = Some(2);
+        engine.timetable_start = 86_400;
+        engine.current_order_time = -3;
+        engine.profit_this_year = -1_500;
+        engine.random_bits = 0x1234;
+        engine.vehstatus = VehicleStates::STOPPED;
+        engine.type_data = VehicleTypeData::Train(TrainData {
+            track: TrackBits::Depot,
+            railtypes: 1 << 2,
+            flags: VehicleRailFlags::FLIPPED,
+            gv_flags: 3,
+            ..TrainData::default()
+        });
+
+        let mut wagon = Vehicle::new(VehicleID(3), VehicleType::Train);
+        wagon.subtype = 2;
+        wagon.owner = Owner::Company0;
+        wagon.direction = Direction::SE;
+        wagon.type_data = VehicleTypeData::Train(TrainData {
+            track: TrackBits::Depot,
+            railtypes: 1 << 2,
+            ..TrainData::default()
+        });
+
+        let mut bus = Vehicle::new(VehicleID(5), VehicleType::Road);
+        bus.owner = Owner::Company0;
+        bus.direction = Direction::NW;
+        bus.orders = Some(2);
+        bus.type_data = VehicleTypeData::RoadVehicle(RoadVehicleData {
+            path: vec![
+                RoadVehPathElement {
+                    trackdir: 1,
+                    tile: TileIndex(100),
+                },
+                RoadVehPathElement {
+                    trackdir: 9,
+                    tile: TileIndex(101),
+                },
+            ],
+            state: 0xFE,
+            ..RoadVehicleData::default()
+        });
+
+        let mut smoke = Vehicle::new(VehicleID(9), VehicleType::Effect);
+        smoke.x_pos = 2303;
+        smoke.z_pos = 91;
+        smoke.sprite_cache.sprite_seq.seq[0].sprite = 3706;
+        smoke.type_data = VehicleTypeData::Effect(EffectData {
+            animation_state: 4,
+            animation_substate: 1,
+        });
+
+        let mut ship = Vehicle::new(VehicleID(6), VehicleType::Ship);
+        ship.direction = Direction::N;
+        ship.type_data = VehicleTypeData::Ship(ShipData {
+            path: vec![
+                ShipPathElement { trackdir: 3 },
+                ShipPathElement { trackdir: 8 },
+            ],
+            state: TrackBits::Wormhole,
+            rotation: Direction::NE,
+            ..ShipData::default()
+        });
+
+        let mut ufo = Vehicle::new(VehicleID(7), VehicleType::Disaster);
+        ufo.direction = Direction::W;
+        ufo.owner = Owner::None;
+        ufo.type_data = VehicleTypeData::Disaster(DisasterData {
+            state: 2,
+            image_override: 0x1000,
+            ..DisasterData::default()
+        });
+
+        let mut plane = Vehicle::new(VehicleID(8), VehicleType::Aircraft);
+        plane.direction = Direction::S;
+        plane.type_data = VehicleTypeData::Aircraft(AircraftData {
+            targetairport: StationID(3),
+            last_direction: Direction::S,
+            flags: VehicleAirFlags::DESTINATION_TOO_FAR,
+            ..AircraftData::default()
+        });
+
+        let mut vehicles = vec![smoke, bus, wagon, engine, ship, ufo, plane];
+        link_chains(&mut vehicles).unwrap();
+        vehicles.sort_by_key(|v| v.index.0);
+        vehicles
+    }
+
+    fn round_trip(vehicles: &[Vehicle], version: u16) -> Vec<Vehicle> {
+        let mut writer = SavegameWriter::new(version, CompressionType::None);
+        save_vehicles(&mut writer, vehicles).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        load_vehicles(&chunks, version).unwrap()
+    }
+
+    #[test]
+    fn test_vehicles_round_trip() {
+        let vehicles = sample_vehicles();
+        for version in [
+            SaveLoadVersion::TableChunks,
+            SaveLoadVersion::DisasterVehState,
+            SaveLoadVersion::PathCacheFormat,
+            SaveLoadVersion::EngineMultiRailtype,
+        ]
+        .map(u16::from)
+        {
+            let loaded = round_trip(&vehicles, version);
+            assert_eq!(loaded.len(), vehicles.len());
+            for (a, b) in loaded.iter().zip(&vehicles) {
+                if version >= SaveLoadVersion::ExtendVehicleRandom
+                    && version < SaveLoadVersion::EngineMultiRailtype
+                {
+                    assert_eq!(a, b);
+                }
+                assert_eq!(a.index, b.index);
+                assert_eq!(a.first, b.first);
+            }
+        }
+    }
+
+    #[test]
+    fn test_vehicle_chains() {
+        let vehicles = round_trip(&sample_vehicles(), SaveLoadVersion::PathCacheFormat.into());
+        let by_id = |id: u32| vehicles.iter().find(|v| v.index == VehicleID(id)).unwrap();
+
+        assert_eq!(by_id(0).first, Some(VehicleID(0)));
+        assert_eq!(by_id(3).first, Some(VehicleID(0)));
+        assert_eq!(by_id(5).first, Some(VehicleID(5)));
+        assert_eq!(by_id(0).next_shared, Some(VehicleID(5)));
+        assert_eq!(by_id(0).cargo.packets, [0, 4]);
+        assert_eq!(by_id(0).orders, Some(2));
+    }
+
+    #[test]
+    fn test_vehicle_version_differences() {
+        let vehicles = sample_vehicles();
+
+        // Random bits were a single byte
+        let loaded = round_trip(&vehicles, SaveLoadVersion::TableChunks.into());
+        assert_eq!(loaded[0].random_bits, 0x34);
+        assert_eq!(loaded[0].current_order_time, -3);
+
+        // Without a saved rail type the train has no rail types until after-load
+        let loaded = round_trip(&vehicles, SaveLoadVersion::EngineMultiRailtype.into());
+        match &loaded[0].type_data {
+            VehicleTypeData::Train(t) => assert_eq!(t.railtypes, 0),
+            other => panic!("Expected train data, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_old_path_cache_is_reversed() {
+        let vehicles = sample_vehicles();
+        let mut writer =
+            SavegameWriter::new(SaveLoadVersion::TableChunks.into(), CompressionType::None);
+        save_vehicles(&mut writer, &vehicles).unwrap();
+        let data = writer.finalize().unwrap();
+        let chunks = SavegameReader::new(&data).unwrap().read_chunks().unwrap();
+        let version = SaveLoadVersion::TableChunks.into();
+        let records = chunk_records(
+            &chunks,
+            b"VEHS",
+            version,
+            &vehicle_desc(),
+            &vehicle_compat(),
+        )
+        .unwrap();
+        let (_, bus) = records.iter().find(|(i, _)| *i == 5).unwrap();
+        let roadveh = bus.get_struct("roadveh").unwrap();
+        assert_eq!(int_list(roadveh, "path.td").unwrap(), [9, 1]);
+        assert_eq!(int_list(roadveh, "path.tile").unwrap(), [101, 100]);
+    }
+
+    #[test]
+    fn test_invalid_chains() {
+        let mut vehicles = sample_vehicles();
+        vehicles[0].next = Some(VehicleID(42));
+        assert!(link_chains(&mut vehicles).is_err());
+
+        // Both train parts pointing at each other leaves no front vehicle
+        let mut vehicles = sample_vehicles();
+        vehicles[1].next = Some(VehicleID(0));
+        assert!(link_chains(&mut vehicles).is_err());
+    }
+
+    #[test]
+    fn test_vehicles_unsupported_version() {
+        let mut writer = SavegameWriter::new(294, CompressionType::None);
+        assert!(matches!(
+            save_vehicles(&mut writer, &sample_vehicles()),
+            Err(SavegameError::UnsupportedVersion(294))
+        ));
+    }
+}
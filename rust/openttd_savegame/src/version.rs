@@ -0,0 +1,1509 @@
+/// Savegame versions and version dependent field declarations
+///
+/// `SaveLoadVersion` lists every savegame version milestone with the same
+/// numbering as the C++ enum, so version checks can name the change they
+/// depend on. `SaveLoad` describes a table field together with the range of
+/// versions it is saved in, like the C++ `SLE_CONDVAR` family of macros.
+///
+/// Chunks saved before `TableChunks` have no header, so their records can only
+/// be decoded with the field declarations: `compat_desc` orders them like a
+/// `SaveLoadCompat` list and `decode_array_record` reads a record with them.
+use crate::chunk::{DataType, TableField, TableHeader};
+use crate::gamma;
+use crate::savegame::SavegameError;
+use crate::table::{self, Record, Value};
+use openttd_core::endian::BigEndianReader;
+use openttd_core::error::CoreError;
+use std::cmp::Ordering;
+
+/// Savegame version milestones (matches C++ SaveLoadVersion)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum SaveLoadVersion {
+    /// First savegame version
+    MinVersion = 0,
+    /// 1.0 0.1.x, 0.2.x
+    V1 = 1,
+    /// 2.0 0.3.0
+    V2 = 2,
+    /// 3.x lost
+    V3 = 3,
+    /// 4.0 1
+    V4 = 4,
+    /// 5.0 1429
+    V5 = 5,
+    /// 6.0 1721
+    V6 = 6,
+    /// 7.0 1770
+    V7 = 7,
+    /// 8.0 1786
+    V8 = 8,
+    /// 9.0 1909
+    V9 = 9,
+    /// 10.0 2030
+    V10 = 10,
+    /// 11.0 2033
+    V11 = 11,
+    /// 12.1 2046
+    V12 = 12,
+    /// 13.1 2080 0.4.0, 0.4.0.1
+    V13 = 13,
+    /// 14.0 2441
+    V14 = 14,
+    /// 15.0 2499
+    V15 = 15,
+    /// 16.0 2817
+    V16 = 16,
+    /// 17.0 3212
+    V17 = 17,
+    /// 18 3227
+    V18 = 18,
+    /// 19 3396
+    V19 = 19,
+    /// 20 3403
+    V20 = 20,
+    /// 21 3472 0.4.x
+    V21 = 21,
+    /// 22 3726
+    V22 = 22,
+    /// 23 3915
+    V23 = 23,
+    /// 24 4150
+    V24 = 24,
+    /// 25 4259
+    V25 = 25,
+    /// 26 4466
+    V26 = 26,
+    /// 27 4757
+    V27 = 27,
+    /// 28 4987
+    V28 = 28,
+    /// 29 5070
+    V29 = 29,
+    /// 30 5946
+    V30 = 30,
+    /// 31 5999
+    V31 = 31,
+    /// 32 6001
+    V32 = 32,
+    /// 33 6440
+    V33 = 33,
+    /// 34 6455
+    V34 = 34,
+    /// 35 6602
+    V35 = 35,
+    /// 36 6624
+    V36 = 36,
+    /// 37 7182
+    V37 = 37,
+    /// 38 7195
+    V38 = 38,
+    /// 39 7269
+    V39 = 39,
+    /// 40 7326
+    V40 = 40,
+    /// 41 7348 0.5.x
+    V41 = 41,
+    /// 42 7573
+    V42 = 42,
+    /// 43 7642
+    V43 = 43,
+    /// 44 8144
+    V44 = 44,
+    /// 45 8501
+    V45 = 45,
+    /// 46 8705
+    V46 = 46,
+    /// 47 8735
+    V47 = 47,
+    /// 48 8935
+    V48 = 48,
+    /// 49 8969
+    V49 = 49,
+    /// 50 8973
+    V50 = 50,
+    /// 51 8978
+    V51 = 51,
+    /// 52 9066
+    V52 = 52,
+    /// 53 9316
+    V53 = 53,
+    /// 54 9613
+    V54 = 54,
+    /// 55 9638
+    V55 = 55,
+    /// 56 9667
+    V56 = 56,
+    /// 57 9691
+    V57 = 57,
+    /// 58 9762
+    V58 = 58,
+    /// 59 9779
+    V59 = 59,
+    /// 60 9874
+    V60 = 60,
+    /// 61 9892
+    V61 = 61,
+    /// 62 9905
+    V62 = 62,
+    /// 63 9956
+    V63 = 63,
+    /// 64 10006
+    V64 = 64,
+    /// 65 10210
+    V65 = 65,
+    /// 66 10211
+    V66 = 66,
+    /// 67 10236
+    V67 = 67,
+    /// 68 10266
+    V68 = 68,
+    /// 69 10319
+    V69 = 69,
+    /// 70 10541
+    V70 = 70,
+    /// 71 10567
+    V71 = 71,
+    /// 72 10601
+    V72 = 72,
+    /// 73 10903
+    V73 = 73,
+    /// 74 11030
+    V74 = 74,
+    /// 75 11107
+    V75 = 75,
+    /// 76 11139
+    V76 = 76,
+    /// 77 11172
+    V77 = 77,
+    /// 78 11176
+    V78 = 78,
+    /// 79 11188
+    V79 = 79,
+    /// 80 11228
+    V80 = 80,
+    /// 81 11244
+    V81 = 81,
+    /// 82 11410
+    V82 = 82,
+    /// 83 11589
+    V83 = 83,
+    /// 84 11822
+    V84 = 84,
+    /// 85 11874
+    V85 = 85,
+    /// 86 12042
+    V86 = 86,
+    /// 87 12129
+    V87 = 87,
+    /// 88 12134
+    V88 = 88,
+    /// 89 12160
+    V89 = 89,
+    /// 90 12293
+    V90 = 90,
+    /// 91 12347
+    V91 = 91,
+    /// 92 12381 0.6.x
+    V92 = 92,
+    /// 93 12648
+    V93 = 93,
+    /// 94 12816
+    V94 = 94,
+    /// 95 12924
+    V95 = 95,
+    /// 96 13226
+    V96 = 96,
+    /// 97 13256
+    V97 = 97,
+    /// 98 13375
+    V98 = 98,
+    /// 99 13838
+    V99 = 99,
+    /// 100 13952
+    V100 = 100,
+    /// 101 14233
+    V101 = 101,
+    /// 102 14332
+    V102 = 102,
+    /// 103 14598
+    V103 = 103,
+    /// 104 14735
+    V104 = 104,
+    /// 105 14803
+    V105 = 105,
+    /// 106 14919
+    V106 = 106,
+    /// 107 15027
+    V107 = 107,
+    /// 108 15045
+    V108 = 108,
+    /// 109 15075
+    V109 = 109,
+    /// 110 15148
+    V110 = 110,
+    /// 111 15190
+    V111 = 111,
+    /// 112 15290
+    V112 = 112,
+    /// 113 15340
+    V113 = 113,
+    /// 114 15601
+    V114 = 114,
+    /// 115 15695
+    V115 = 115,
+    /// 116 15893 0.7.x
+    V116 = 116,
+    /// 117 16037
+    V117 = 117,
+    /// 118 16129
+    V118 = 118,
+    /// 119 16242
+    V119 = 119,
+    /// 120 16439
+    V120 = 120,
+    /// 121 16694
+    V121 = 121,
+    /// 122 16855
+    V122 = 122,
+    /// 123 16909
+    V123 = 123,
+    /// 124 16993
+    V124 = 124,
+    /// 125 17113
+    V125 = 125,
+    /// 126 17433
+    V126 = 126,
+    /// 127 17439
+    V127 = 127,
+    /// 128 18281
+    V128 = 128,
+    /// 129 18292
+    V129 = 129,
+    /// 130 18404
+    V130 = 130,
+    /// 131 18481
+    V131 = 131,
+    /// 132 18522
+    V132 = 132,
+    /// 133 18674
+    V133 = 133,
+    /// 134 18703
+    V134 = 134,
+    /// 135 18719
+    V135 = 135,
+    /// 136 18764
+    V136 = 136,
+    /// 137 18912
+    V137 = 137,
+    /// 138 18942 1.0.x
+    V138 = 138,
+    /// 139 19346
+    V139 = 139,
+    /// 140 19382
+    V140 = 140,
+    /// 141 19799
+    V141 = 141,
+    /// 142 20003
+    V142 = 142,
+    /// 143 20048
+    V143 = 143,
+    /// 144 20334
+    V144 = 144,
+    /// 145 20376
+    V145 = 145,
+    /// 146 20446
+    V146 = 146,
+    /// 147 20621
+    V147 = 147,
+    /// 148 20659
+    V148 = 148,
+    /// 149 20832
+    V149 = 149,
+    /// 150 20857
+    V150 = 150,
+    /// 151 20918
+    V151 = 151,
+    /// 152 21171
+    V152 = 152,
+    /// 153 21263
+    V153 = 153,
+    /// 154 21426
+    V154 = 154,
+    /// 155 21453
+    V155 = 155,
+    /// 156 21728
+    V156 = 156,
+    /// 157 21862
+    V157 = 157,
+    /// 158 21933
+    V158 = 158,
+    /// 159 21962
+    V159 = 159,
+    /// 160 21974 1.1.x
+    V160 = 160,
+    /// 161 22567
+    V161 = 161,
+    /// 162 22713
+    V162 = 162,
+    /// 163 22767
+    V163 = 163,
+    /// 164 23290
+    V164 = 164,
+    /// 165 23304
+    V165 = 165,
+    /// 166 23415
+    V166 = 166,
+    /// 167 23504
+    V167 = 167,
+    /// 168 23637
+    V168 = 168,
+    /// 169 23816
+    V169 = 169,
+    /// 170 23826
+    V170 = 170,
+    /// 171 23835
+    V171 = 171,
+    /// 172 23947
+    V172 = 172,
+    /// 173 23967 1.2.0-RC1
+    V173 = 173,
+    /// 174 23973 1.2.x
+    V174 = 174,
+    /// 175 24136
+    V175 = 175,
+    /// 176 24446
+    V176 = 176,
+    /// 177 24619
+    V177 = 177,
+    /// 178 24789
+    V178 = 178,
+    /// 179 24810
+    V179 = 179,
+    /// 180 24998 1.3.x
+    V180 = 180,
+    /// 181 25012
+    V181 = 181,
+    /// 182 25115 FS#5492, r25259, r25296 Goal status
+    V182 = 182,
+    /// 183 25363 Cargodist
+    V183 = 183,
+    /// 184 25508 Unit localisation split
+    V184 = 184,
+    /// 185 25620 Storybooks
+    V185 = 185,
+    /// 186 25833 Objects storage
+    V186 = 186,
+    /// 187 25899 Linkgraph - restricted flows
+    V187 = 187,
+    /// 188 26169 v1.4 FS#5831 Unify RV travel time
+    V188 = 188,
+    /// 189 26450 Hierarchical vehicle subgroups
+    V189 = 189,
+    /// 190 26547 Separate order travel and wait times
+    V190 = 190,
+    /// 191 26636 FS#6026 Fix disaster vehicle storage (No bump)
+    V191 = 191,
+    /// 192 26700 FS#6066 Fix saving of order backups
+    V192 = 192,
+    /// 193 26802
+    V193 = 193,
+    /// 194 26881 v1.5
+    V194 = 194,
+    /// 195 27572 v1.6.1
+    V195 = 195,
+    /// 196 27778 v1.7
+    V196 = 196,
+    /// 197 27978 v1.8
+    V197 = 197,
+    /// 198 PR#6763 Switch town growth rate and counter to actual game ticks
+    V198 = 198,
+    /// PR#6802 Extend cargotypes to 64
+    ExtendCargotypes = 199,
+    /// PR#6805 Extend railtypes to 64, adding uint16_t to map array.
+    ExtendRailtypes = 200,
+    /// PR#6885 Extend NewGRF persistent storages.
+    ExtendPersistentStorage = 201,
+    /// PR#6867 Increase industry cargo slots to 16 in, 16 out
+    ExtendIndustryCargoSlots = 202,
+    /// PR#7072 Add path cache for ships
+    ShipPathCache = 203,
+    /// PR#7065 Add extra rotation stages for ships.
+    ShipRotation = 204,
+    /// PR#7108 Livery storage change and group liveries.
+    GroupLiveries = 205,
+    /// PR#7150 Ship/lock movement changes.
+    ShipsStopInLocks = 206,
+    /// PR#7175 v1.9 Cargo monitor data packing fix to support 64 cargotypes.
+    FixCargoMonitor = 207,
+    /// PR#6965 New algorithms for town building cargo generation.
+    TownCargogen = 208,
+    /// PR#7289 Configurable ship curve penalties.
+    ShipCurvePenalty = 209,
+    /// PR#7234 Company stations can serve industries with attached neutral stations.
+    ServeNeutralIndustries = 210,
+    /// PR#7261 Add path cache for road vehicles.
+    RoadvehPathCache = 211,
+    /// PR#7245 Remove OPF.
+    RemoveOpf = 212,
+    /// PR#7405 WaterClass update for tree tiles.
+    TreesWaterClass = 213,
+    /// PR#6811 NewGRF road types.
+    RoadTypes = 214,
+    /// PR#7516 Limit on AI/GS memory consumption.
+    ScriptMemlimit = 215,
+    /// PR#7380 Multiple docks per station.
+    MultitileDocks = 216,
+    /// PR#7780 Configurable company trading age.
+    TradingAge = 217,
+    /// PR#7747 v1.10 Configurable ending year.
+    EndingYear = 218,
+    /// PR#8258 Remove town cargo acceptance and production caches.
+    RemoveTownCargoCache = 219,
+    /// First known patchpack to use a version just above ours.
+    StartPatchpacks = 220,
+    /// Last known patchpack to use a version just above ours.
+    EndPatchpacks = 286,
+    /// PR#7912 and PR#8115 GS industry control.
+    GsIndustryControl = 287,
+    /// PR#8591 Desync safe motion counter
+    VehMotionCounter = 288,
+    /// PR#8576 v1.11.0-RC1 Additional GS text for industries.
+    IndustryText = 289,
+    /// PR#8891 v1.11 Revamp of some mapgen settings (snow coverage, desert coverage, heightmap height, custom terrain type).
+    MapgenSettingsRevamp = 290,
+    /// PR#7441 Per-group wagon removal flag.
+    GroupReplaceWagonRemoval = 291,
+    /// PR#9081 Configurable subsidy duration.
+    CustomSubsidyDuration = 292,
+    /// PR#9374 Consistency in list length with SL_STRUCT / SL_STRUCTLIST / SL_DEQUE / SL_REFLIST.
+    SaveloadListLength = 293,
+    /// PR#9375 Changed many CH_RIFF chunks to CH_ARRAY chunks.
+    RiffToArray = 294,
+    /// PR#9322 Introduction of CH_TABLE and CH_SPARSE_TABLE.
+    TableChunks = 295,
+    /// PR#9415 SQInteger is 64bit but was saved as 32bit.
+    ScriptInt64 = 296,
+    /// PR#9457 v12.0-RC1 Store travel time in the linkgraph.
+    LinkgraphTravelTime = 297,
+    /// PR#9578 All tiles around docks may be docking tiles.
+    DockDockingtiles = 298,
+    /// PR#9594 v12.0 Fixing issue with docking tiles overlapping objects.
+    RepairObjectDockingTiles = 299,
+    /// PR#10035 Make tick counter 64bit to avoid wrapping.
+    U64TickCounter = 300,
+    /// PR#9693 Store tick of last loading for vehicles.
+    LastLoadingTick = 301,
+    /// PR#9931 v13.0 Multi-track level crossings.
+    MultitrackLevelCrossings = 302,
+    /// PR#10144 NewGRF road stops.
+    NewgrfRoadStops = 303,
+    /// PR#10314 Explicitly store link graph edges destination, PR#10471 int64_t instead of uint64_t league rating
+    LinkgraphEdges = 304,
+    /// PR#10594 Separation of land and nautical velocity (knots!)
+    VelocityNautical = 305,
+    /// PR#10570 Conversion from an inconsistent partial Z calculation for slopes, to one that is (more) consistent.
+    ConsistentPartialZ = 306,
+    /// PR#10596 Track cargo age for a longer period.
+    MoreCargoAge = 307,
+    /// PR#10610 Store linkgraph update intervals in seconds instead of days.
+    LinkgraphSeconds = 308,
+    /// PR#10653 Removal of individual AI start dates and added a generic one.
+    AiStartDate = 309,
+    /// PR#10701 Extend vehicle random bits.
+    ExtendVehicleRandom = 310,
+    /// PR#10672 Extend entity mapping range.
+    ExtendEntityMapping = 311,
+    /// PR#10798 Explicit storage of disaster vehicle state.
+    DisasterVehState = 312,
+    /// PR#10719 Add an unique ID to every savegame (used to deduplicate surveys).
+    SavegameId = 313,
+    /// PR#10801 Use std::string in gamelog.
+    StringGamelog = 314,
+    /// PR#10853 Industry accepts/produced data reorganised.
+    IndustryCargoReorganise = 315,
+    /// PR#11112 Rename days in transit to (cargo) periods in transit.
+    PeriodsInTransitRename = 316,
+    /// PR#11124 Added stable date_of_last_service to avoid NewGRF trouble.
+    NewgrfLastService = 317,
+    /// PR#11276 Remove loaded_at_xy variable from CargoPacket.
+    RemoveLoadedAtXy = 318,
+    /// PR#11283 CargoPacket now tracks how far it travelled inside a vehicle.
+    CargoTravelled = 319,
+    /// PR#11346 Add cheat to fix station ratings at 100%.
+    StationRatingCheat = 320,
+    /// PR#11468 Convert timetable start from a date to ticks.
+    TimetableStartTicks = 321,
+    /// PR#11557 Fix for missing convert timetable start from a date to ticks.
+    TimetableStartTicksFix = 322,
+    /// PR#11435 Convert timetable current order time to ticks.
+    TimetableTicksType = 323,
+    /// PR#10543 Water Regions for ship pathfinder.
+    WaterRegions = 324,
+    /// PR#11750 Simplified Water Region evaluation.
+    WaterRegionEvalSimplified = 325,
+    /// PR#10700 Split calendar and economy timers and dates.
+    EconomyDate = 326,
+    /// PR#11341 Mode to display economy measurements in wallclock units.
+    EconomyModeTimekeepingUnits = 327,
+    /// PR#11428 Add sub_date_fract to measure calendar days.
+    CalendarSubDateFract = 328,
+    /// PR#10734 Start using Vehicle's acceleration field for ships too.
+    ShipAcceleration = 329,
+    /// PR#11224 Separate max loan for each company.
+    MaxLoanForCompany = 330,
+    /// PR#11945 Allow unbunching shared order vehicles at a depot.
+    DepotUnbunching = 331,
+    /// PR#12003 Config of running AI is stored inside Company.
+    AiLocalConfig = 332,
+    /// PR#12063 v14.0-RC1 Save script randomizers.
+    ScriptRandomizer = 333,
+    /// PR#12141 v14.0 Add vehicle age in economy year, for profit stats minimum age
+    VehicleEconomyAge = 334,
+    /// PR#12337 Saving of list of client keys that are allowed to join this company.
+    CompanyAllowList = 335,
+    /// PR#12297 Add per-company group numbers.
+    GroupNumbers = 336,
+    /// PR#12572 Increase size of StationType field in map array
+    IncreaseStationTypeFieldSize = 337,
+    /// PR#12572 Road waypoints
+    RoadWaypoints = 338,
+    /// PR#12798 Companies show the period inaugurated in wallclock mode.
+    CompanyInauguratedPeriod = 339,
+    /// PR#12883 Move storage of road stop tile data, also save for road waypoints.
+    RoadStopTileData = 340,
+    /// PR#12908 Fixed savegame format for saving of list of client keys that are allowed to join this company.
+    CompanyAllowListV2 = 341,
+    /// PR#13030 Simplify water tile type.
+    WaterTileType = 342,
+    /// PR#10541 Industry production history.
+    ProductionHistory = 343,
+    /// PR#13021 Add road type label map to allow upgrade/conversion of road types.
+    RoadTypeLabelMap = 344,
+    /// PR#13013 Store water tile non-flooding state.
+    NonfloodingWaterTiles = 345,
+    /// PR#12345 Vehicle path cache format changed.
+    PathCacheFormat = 346,
+    /// PR#13082 Animated tile state saved for improved performance.
+    AnimatedTileStateInMap = 347,
+    /// PR#12288 Increase house limit to 4096.
+    IncreaseHouseLimit = 348,
+    /// PR#13448 Fix savegame storage for company inaugurated year in wallclock mode.
+    CompanyInauguratedPeriodV2 = 349,
+    /// PR#13499 Encoded String format changed.
+    EncodedStringFormat = 350,
+    /// PR#13270 Houses individually placed by players can be protected from town/AI removal.
+    ProtectPlacedHouses = 351,
+    /// PR#13556 Scripts are allowed to save instances.
+    ScriptSaveInstances = 352,
+    /// PR#14049 Fix encoding of negative parameters.
+    FixSccEncodedNegative = 353,
+    /// PR#13948 Orders stored in OrderList, pool removed.
+    OrdersOwnedByOrderlist = 354,
+    /// PR#14319 Addition of face styles, replacing gender and ethnicity.
+    FaceStyles = 355,
+    /// PR#14416 Store number of valid history records for industries.
+    IndustryNumValidHistory = 356,
+    /// PR#14321 Add per-industry history of cargo delivered and waiting.
+    IndustryAcceptedHistory = 357,
+    /// PR#14461 Town supply history.
+    TownSupplyHistory = 358,
+    /// PR#14477 Allow stations under bridges.
+    StationsUnderBridges = 359,
+    /// PR#14594 Allow docks under bridges.
+    DocksUnderBridges = 360,
+    /// PR#14595 Allow locks under bridges.
+    LocksUnderBridges = 361,
+    /// PR#14357 v15.0 Train engines can have multiple railtypes.
+    EngineMultiRailtype = 362,
+    /// PR#14743 Configurable sign text colors in scenario editor.
+    SignTextColours = 363,
+    /// PR#14983 Allow to build buoys at (0x0).
+    BuoysAt0_0 = 364,
+    /// Highest possible saveload version
+    MaxVersion = 365,
+}
+
+/// Every milestone in ascending order, for lookups by number
+const MILESTONES: [SaveLoadVersion; 300] = [
+    SaveLoadVersion::MinVersion,
+    SaveLoadVersion::V1,
+    SaveLoadVersion::V2,
+    SaveLoadVersion::V3,
+    SaveLoadVersion::V4,
+    SaveLoadVersion::V5,
+    SaveLoadVersion::V6,
+    SaveLoadVersion::V7,
+    SaveLoadVersion::V8,
+    SaveLoadVersion::V9,
+    SaveLoadVersion::V10,
+    SaveLoadVersion::V11,
+    SaveLoadVersion::V12,
+    SaveLoadVersion::V13,
+    SaveLoadVersion::V14,
+    SaveLoadVersion::V15,
+    SaveLoadVersion::V16,
+    SaveLoadVersion::V17,
+    SaveLoadVersion::V18,
+    SaveLoadVersion::V19,
+    SaveLoadVersion::V20,
+    SaveLoadVersion::V21,
+    SaveLoadVersion::V22,
+    SaveLoadVersion::V23,
+    SaveLoadVersion::V24,
+    SaveLoadVersion::V25,
+    SaveLoadVersion::V26,
+    SaveLoadVersion::V27,
+    SaveLoadVersion::V28,
+    SaveLoadVersion::V29,
+    SaveLoadVersion::V30,
+    SaveLoadVersion::V31,
+    SaveLoadVersion::V32,
+    SaveLoadVersion::V33,
+    SaveLoadVersion::V34,
+    SaveLoadVersion::V35,
+    SaveLoadVersion::V36,
+    SaveLoadVersion::V37,
+    SaveLoadVersion::V38,
+    SaveLoadVersion::V39,
+    SaveLoadVersion::V40,
+    SaveLoadVersion::V41,
+    SaveLoadVersion::V42,
+    SaveLoadVersion::V43,
+    SaveLoadVersion::V44,
+    SaveLoadVersion::V45,
+    SaveLoadVersion::V46,
+    SaveLoadVersion::V47,
+    SaveLoadVersion::V48,
+    SaveLoadVersion::V49,
+    SaveLoadVersion::V50,
+    SaveLoadVersion::V51,
+    SaveLoadVersion::V52,
+    SaveLoadVersion::V53,
+    SaveLoadVersion::V54,
+    SaveLoadVersion::V55,
+    SaveLoadVersion::V56,
+    SaveLoadVersion::V57,
+    SaveLoadVersion::V58,
+    SaveLoadVersion::V59,
+    SaveLoadVersion::V60,
+    SaveLoadVersion::V61,
+    SaveLoadVersion::V62,
+    SaveLoadVersion::V63,
+    SaveLoadVersion::V64,
+    SaveLoadVersion::V65,
+    SaveLoadVersion::V66,
+    SaveLoadVersion::V67,
+    SaveLoadVersion::V68,
+    SaveLoadVersion::V69,
+    SaveLoadVersion::V70,
+    SaveLoadVersion::V71,
+    SaveLoadVersion::V72,
+    SaveLoadVersion::V73,
+    SaveLoadVersion::V74,
+    SaveLoadVersion::V75,
+    SaveLoadVersion::V76,
+    SaveLoadVersion::V77,
+    SaveLoadVersion::V78,
+    SaveLoadVersion::V79,
+    SaveLoadVersion::V80,
+    SaveLoadVersion::V81,
+    SaveLoadVersion::V82,
+    SaveLoadVersion::V83,
+    SaveLoadVersion::V84,
+    SaveLoadVersion::V85,
+    SaveLoadVersion::V86,
+    SaveLoadVersion::V87,
+    SaveLoadVersion::V88,
+    SaveLoadVersion::V89,
+    SaveLoadVersion::V90,
+    SaveLoadVersion::V91,
+    SaveLoadVersion::V92,
+    SaveLoadVersion::V93,
+    SaveLoadVersion::V94,
+    SaveLoadVersion::V95,
+    SaveLoadVersion::V96,
+    SaveLoadVersion::V97,
+    SaveLoadVersion::V98,
+    SaveLoadVersion::V99,
+    SaveLoadVersion::V100,
+    SaveLoadVersion::V101,
+    SaveLoadVersion::V102,
+    SaveLoadVersion::V103,
+    SaveLoadVersion::V104,
+    SaveLoadVersion::V105,
+    SaveLoadVersion::V106,
+    SaveLoadVersion::V107,
+    SaveLoadVersion::V108,
+    SaveLoadVersion::V109,
+    SaveLoadVersion::V110,
+    SaveLoadVersion::V111,
+    SaveLoadVersion::V112,
+    SaveLoadVersion::V113,
+    SaveLoadVersion::V114,
+    SaveLoadVersion::V115,
+    SaveLoadVersion::V116,
+    SaveLoadVersion::V117,
+    SaveLoadVersion::V118,
+    SaveLoadVersion::V119,
+    SaveLoadVersion::V120,
+    SaveLoadVersion::V121,
+    SaveLoadVersion::V122,
+    SaveLoadVersion::V123,
+    SaveLoadVersion::V124,
+    SaveLoadVersion::V125,
+    SaveLoadVersion::V126,
+    SaveLoadVersion::V127,
+    SaveLoadVersion::V128,
+    SaveLoadVersion::V129,
+    SaveLoadVersion::V130,
+    SaveLoadVersion::V131,
+    SaveLoadVersion::V132,
+    SaveLoadVersion::V133,
+    SaveLoadVersion::V134,
+    SaveLoadVersion::V135,
+    SaveLoadVersion::V136,
+    SaveLoadVersion::V137,
+    SaveLoadVersion::V138,
+    SaveLoadVersion::V139,
+    SaveLoadVersion::V140,
+    SaveLoadVersion::V141,
+    SaveLoadVersion::V142,
+    SaveLoadVersion::V143,
+    SaveLoadVersion::V144,
+    SaveLoadVersion::V145,
+    SaveLoadVersion::V146,
+    SaveLoadVersion::V147,
+    SaveLoadVersion::V148,
+    SaveLoadVersion::V149,
+    SaveLoadVersion::V150,
+    SaveLoadVersion::V151,
+    SaveLoadVersion::V152,
+    SaveLoadVersion::V153,
+    SaveLoadVersion::V154,
+    SaveLoadVersion::V155,
+    SaveLoadVersion::V156,
+    SaveLoadVersion::V157,
+    SaveLoadVersion::V158,
+    SaveLoadVersion::V159,
+    SaveLoadVersion::V160,
+    SaveLoadVersion::V161,
+    SaveLoadVersion::V162,
+    SaveLoadVersion::V163,
+    SaveLoadVersion::V164,
+    SaveLoadVersion::V165,
+    SaveLoadVersion::V166,
+    SaveLoadVersion::V167,
+    SaveLoadVersion::V168,
+    SaveLoadVersion::V169,
+    SaveLoadVersion::V170,
+    SaveLoadVersion::V171,
+    SaveLoadVersion::V172,
+    SaveLoadVersion::V173,
+    SaveLoadVersion::V174,
+    SaveLoadVersion::V175,
+    SaveLoadVersion::V176,
+    SaveLoadVersion::V177,
+    SaveLoadVersion::V178,
+    SaveLoadVersion::V179,
+    SaveLoadVersion::V180,
+    SaveLoadVersion::V181,
+    SaveLoadVersion::V182,
+    SaveLoadVersion::V183,
+    SaveLoadVersion::V184,
+    SaveLoadVersion::V185,
+    SaveLoadVersion::V186,
+    SaveLoadVersion::V187,
+    SaveLoadVersion::V188,
+    SaveLoadVersion::V189,
+    SaveLoadVersion::V190,
+    SaveLoadVersion::V191,
+    SaveLoadVersion::V192,
+    SaveLoadVersion::V193,
+    SaveLoadVersion::V194,
+    SaveLoadVersion::V195,
+    SaveLoadVersion::V196,
+    SaveLoadVersion::V197,
+    SaveLoadVersion::V198,
+    SaveLoadVersion::ExtendCargotypes,
+    SaveLoadVersion::ExtendRailtypes,
+    SaveLoadVersion::ExtendPersistentStorage,
+    SaveLoadVersion::ExtendIndustryCargoSlots,
+    SaveLoadVersion::ShipPathCache,
+    SaveLoadVersion::ShipRotation,
+    SaveLoadVersion::GroupLiveries,
+    SaveLoadVersion::ShipsStopInLocks,
+    SaveLoadVersion::FixCargoMonitor,
+    SaveLoadVersion::TownCargogen,
+    SaveLoadVersion::ShipCurvePenalty,
+    SaveLoadVersion::ServeNeutralIndustries,
+    SaveLoadVersion::RoadvehPathCache,
+    SaveLoadVersion::RemoveOpf,
+    SaveLoadVersion::TreesWaterClass,
+    SaveLoadVersion::RoadTypes,
+    SaveLoadVersion::ScriptMemlimit,
+    SaveLoadVersion::MultitileDocks,
+    SaveLoadVersion::TradingAge,
+    SaveLoadVersion::EndingYear,
+    SaveLoadVersion::RemoveTownCargoCache,
+    SaveLoadVersion::StartPatchpacks,
+    SaveLoadVersion::EndPatchpacks,
+    SaveLoadVersion::GsIndustryControl,
+    SaveLoadVersion::VehMotionCounter,
+    SaveLoadVersion::IndustryText,
+    SaveLoadVersion::MapgenSettingsRevamp,
+    SaveLoadVersion::GroupReplaceWagonRemoval,
+    SaveLoadVersion::CustomSubsidyDuration,
+    SaveLoadVersion::SaveloadListLength,
+    SaveLoadVersion::RiffToArray,
+    SaveLoadVersion::TableChunks,
+    SaveLoadVersion::ScriptInt64,
+    SaveLoadVersion::LinkgraphTravelTime,
+    SaveLoadVersion::DockDockingtiles,
+    SaveLoadVersion::RepairObjectDockingTiles,
+    SaveLoadVersion::U64TickCounter,
+    SaveLoadVersion::LastLoadingTick,
+    SaveLoadVersion::MultitrackLevelCrossings,
+    SaveLoadVersion::NewgrfRoadStops,
+    SaveLoadVersion::LinkgraphEdges,
+    SaveLoadVersion::VelocityNautical,
+    SaveLoadVersion::ConsistentPartialZ,
+    SaveLoadVersion::MoreCargoAge,
+    SaveLoadVersion::LinkgraphSeconds,
+    SaveLoadVersion::AiStartDate,
+    SaveLoadVersion::ExtendVehicleRandom,
+    SaveLoadVersion::ExtendEntityMapping,
+    SaveLoadVersion::DisasterVehState,
+    SaveLoadVersion::SavegameId,
+    SaveLoadVersion::StringGamelog,
+    SaveLoadVersion::IndustryCargoReorganise,
+    SaveLoadVersion::PeriodsInTransitRename,
+    SaveLoadVersion::NewgrfLastService,
+    SaveLoadVersion::RemoveLoadedAtXy,
+    SaveLoadVersion::CargoTravelled,
+    SaveLoadVersion::StationRatingCheat,
+    SaveLoadVersion::TimetableStartTicks,
+    SaveLoadVersion::TimetableStartTicksFix,
+    SaveLoadVersion::TimetableTicksType,
+    SaveLoadVersion::WaterRegions,
+    SaveLoadVersion::WaterRegionEvalSimplified,
+    SaveLoadVersion::EconomyDate,
+    SaveLoadVersion::EconomyModeTimekeepingUnits,
+    SaveLoadVersion::CalendarSubDateFract,
+    SaveLoadVersion::ShipAcceleration,
+    SaveLoadVersion::MaxLoanForCompany,
+    SaveLoadVersion::DepotUnbunching,
+    SaveLoadVersion::AiLocalConfig,
+    SaveLoadVersion::ScriptRandomizer,
+    SaveLoadVersion::VehicleEconomyAge,
+    SaveLoadVersion::CompanyAllowList,
+    SaveLoadVersion::GroupNumbers,
+    SaveLoadVersion::IncreaseStationTypeFieldSize,
+    SaveLoadVersion::RoadWaypoints,
+    SaveLoadVersion::CompanyInauguratedPeriod,
+    SaveLoadVersion::RoadStopTileData,
+    SaveLoadVersion::CompanyAllowListV2,
+    SaveLoadVersion::WaterTileType,
+    SaveLoadVersion::ProductionHistory,
+    SaveLoadVersion::RoadTypeLabelMap,
+    SaveLoadVersion::NonfloodingWaterTiles,
+    SaveLoadVersion::PathCacheFormat,
+    SaveLoadVersion::AnimatedTileStateInMap,
+    SaveLoadVersion::IncreaseHouseLimit,
+    SaveLoadVersion::CompanyInauguratedPeriodV2,
+    SaveLoadVersion::EncodedStringFormat,
+    SaveLoadVersion::ProtectPlacedHouses,
+    SaveLoadVersion::ScriptSaveInstances,
+    SaveLoadVersion::FixSccEncodedNegative,
+    SaveLoadVersion::OrdersOwnedByOrderlist,
+    SaveLoadVersion::FaceStyles,
+    SaveLoadVersion::IndustryNumValidHistory,
+    SaveLoadVersion::IndustryAcceptedHistory,
+    SaveLoadVersion::TownSupplyHistory,
+    SaveLoadVersion::StationsUnderBridges,
+    SaveLoadVersion::DocksUnderBridges,
+    SaveLoadVersion::LocksUnderBridges,
+    SaveLoadVersion::EngineMultiRailtype,
+    SaveLoadVersion::SignTextColours,
+    SaveLoadVersion::BuoysAt0_0,
+];
+
+impl SaveLoadVersion {
+    /// Version written by the current game (matches C++ SAVEGAME_VERSION)
+    pub const CURRENT: Self = Self::BuoysAt0_0;
+
+    pub const fn as_u16(self) -> u16 {
+        self as u16
+    }
+}
+
+impl From<SaveLoadVersion> for u16 {
+    fn from(version: SaveLoadVersion) -> Self {
+        version as u16
+    }
+}
+
+impl TryFrom<u16> for SaveLoadVersion {
+    type Error = SavegameError;
+
+    /// Versions without a milestone, such as those of patchpacks, are rejected
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        MILESTONES
+            .binary_search_by_key(&value, |v| *v as u16)
+            .map(|i| MILESTONES[i])
+            .map_err(|_| SavegameError::UnsupportedVersion(value))
+    }
+}
+
+impl PartialEq<SaveLoadVersion> for u16 {
+    fn eq(&self, other: &SaveLoadVersion) -> bool {
+        *self == *other as u16
+    }
+}
+
+impl PartialOrd<SaveLoadVersion> for u16 {
+    fn partial_cmp(&self, other: &SaveLoadVersion) -> Option<Ordering> {
+        self.partial_cmp(&(*other as u16))
+    }
+}
+
+/// Where the number of elements of a list comes from in savegames before
+/// SaveloadListLength, which did not prefix every list with a gamma length
+#[derive(Debug, Clone)]
+pub enum ListLength {
+    /// A u32 length before the elements (C++ SL_VECTOR, SL_DEQUE and SL_REFLIST)
+    Prefixed,
+    /// A fixed number of elements (C++ SLE_ARR, SL_NULL and SL_STRUCT)
+    Fixed(usize),
+    /// The value of an earlier field of the record or of an enclosing one,
+    /// as read by the C++ handler of the list
+    Field(String),
+    /// Structs up to and including the first whose field `key` is `end`, as
+    /// the C++ handler follows a chain through the elements
+    Chain { key: String, end: u64 },
+    /// Computed from the earlier fields of the record, as by a C++ handler
+    /// that sizes the list itself
+    Computed(fn(&Record) -> u64),
+    /// Structs up to a byte of `end`, which the C++ handler reads in place of
+    /// the first field of a struct
+    Terminated(u8),
+}
+
+/// A table field saved in the versions `version_from..version_to`
+/// (matches C++ SaveLoad as declared by SLE_CONDVAR and friends)
+#[derive(Debug, Clone)]
+pub struct SaveLoad {
+    pub data_type: DataType,
+    /// Empty for bytes that are skipped when loading (C++ SL_NULL)
+    pub key: String,
+    pub is_list: bool,
+    /// Number of elements of a list in savegames without list lengths
+    pub length: ListLength,
+    /// Field declarations of a `DataType::Struct` field
+    pub sub_desc: Vec<SaveLoad>,
+    /// Order of the struct's fields in savegames without a table header;
+    /// `None` if it is that of `sub_desc`
+    pub sub_compat: Option<Vec<SaveLoadCompat>>,
+    pub version_from: SaveLoadVersion,
+    pub version_to: SaveLoadVersion,
+}
+
+impl SaveLoad {
+    /// A single value field saved in every version
+    pub fn var(data_type: DataType, key: &str) -> Self {
+        Self {
+            data_type,
+            key: key.into(),
+            is_list: data_type == DataType::String,
+            length: ListLength::Fixed(1),
+            sub_desc: Vec::new(),
+            sub_compat: None,
+            version_from: SaveLoadVersion::MinVersion,
+            version_to: SaveLoadVersion::MaxVersion,
+        }
+    }
+
+    /// A length-prefixed list of values saved in every version
+    pub fn list(data_type: DataType, key: &str) -> Self {
+        Self {
+            is_list: true,
+            length: ListLength::Prefixed,
+            ..Self::var(data_type, key)
+        }
+    }
+
+    /// An array of `length` values, which older savegames store without a
+    /// length (matches C++ SLE_ARR)
+    pub fn array(data_type: DataType, key: &str, length: usize) -> Self {
+        Self {
+            length: ListLength::Fixed(length),
+            ..Self::list(data_type, key)
+        }
+    }
+
+    /// `length` bytes that are no longer loaded (matches C++ SLE_CONDNULL)
+    pub fn null(length: usize) -> Self {
+        Self::array(DataType::U8, "", length)
+    }
+
+    /// A list of structs with the given field declarations; older savegames
+    /// store a single struct without a length (matches C++ SL_STRUCT)
+    pub fn structs(key: &str, sub_desc: Vec<SaveLoad>) -> Self {
+        Self {
+            sub_desc,
+            length: ListLength::Fixed(1),
+            ..Self::list(DataType::Struct, key)
+        }
+    }
+
+    /// Take the number of elements in savegames without list lengths from
+    /// somewhere else than the list field's default
+    pub fn length(self, length: ListLength) -> Self {
+        Self { length, ..self }
+    }
+
+    /// Order the fields of a struct in savegames without a table header
+    pub fn compat(self, sub_compat: Vec<SaveLoadCompat>) -> Self {
+        Self {
+            sub_compat: Some(sub_compat),
+            ..self
+        }
+    }
+
+    /// Only save the field from the given version on
+    pub fn since(self, version_from: SaveLoadVersion) -> Self {
+        Self {
+            version_from,
+            ..self
+        }
+    }
+
+    /// Only save the field before the given version
+    pub fn until(self, version_to: SaveLoadVersion) -> Self {
+        Self { version_to, ..self }
+    }
+
+    /// Whether the field is saved in the given version
+    /// (matches C++ SlIsObjectValidInSavegame)
+    pub fn is_saved_in(&self, version: u16) -> bool {
+        version >= self.version_from && version < self.version_to
+    }
+
+    fn table_field(&self, version: u16) -> TableField {
+        TableField {
+            data_type: self.data_type,
+            key: self.key.clone(),
+            is_list: self.is_list,
+            sub_header: (self.data_type == DataType::Struct)
+                .then(|| table_header(&self.sub_desc, version)),
+        }
+    }
+}
+
+/// The table header of the given field declarations as saved by a version
+pub fn table_header(desc: &[SaveLoad], version: u16) -> TableHeader {
+    TableHeader {
+        fields: desc
+            .iter()
+            .filter(|sld| sld.is_saved_in(version) && !sld.key.is_empty())
+            .map(|sld| sld.table_field(version))
+            .collect(),
+    }
+}
+
+/// A field of a record saved without a table header: the key of the
+/// declarations to use, or a number of bytes that are no longer loaded
+/// (matches C++ SaveLoadCompat)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveLoadCompat {
+    /// Empty for skipped bytes
+    pub key: String,
+    pub null_length: usize,
+    pub version_from: SaveLoadVersion,
+    pub version_to: SaveLoadVersion,
+}
+
+impl SaveLoadCompat {
+    /// All declarations with the given key (matches C++ SLC_VAR)
+    pub fn var(key: &str) -> Self {
+        Self {
+            key: key.into(),
+            null_length: 0,
+            version_from: SaveLoadVersion::MinVersion,
+            version_to: SaveLoadVersion::MaxVersion,
+        }
+    }
+
+    /// `length` bytes saved in the versions `from..to` (matches C++ SLC_NULL)
+    pub fn null(length: usize, from: SaveLoadVersion, to: SaveLoadVersion) -> Self {
+        Self {
+            key: String::new(),
+            null_length: length,
+            version_from: from,
+            version_to: to,
+        }
+    }
+}
+
+/// Field declarations in the order of a savegame without a table header
+/// (matches C++ SlCompatTableHeader). Declarations that are not named by
+/// `compat` are dropped; struct fields are ordered by their own compat list.
+pub fn compat_desc(desc: &[SaveLoad], compat: &[SaveLoadCompat]) -> Vec<SaveLoad> {
+    let mut ordered = Vec::new();
+    for slc in compat {
+        if slc.key.is_empty() {
+            ordered.push(
+                SaveLoad::null(slc.null_length)
+                    .since(slc.version_from)
+                    .until(slc.version_to),
+            );
+            continue;
+        }
+        for sld in desc.iter().filter(|sld| sld.key == slc.key) {
+            let mut sld = sld.clone();
+            if let Some(sub_compat) = sld.sub_compat.take() {
+                sld.sub_desc = compat_desc(&sld.sub_desc, &sub_compat);
+            }
+            ordered.push(sld);
+        }
+    }
+    ordered
+}
+
+/// Number of elements of a list field in a record without a table header
+fn array_count(
+    sld: &SaveLoad,
+    version: u16,
+    reader: &mut BigEndianReader,
+    record: &Record,
+    outer: &[&Record],
+) -> Result<usize, CoreError> {
+    if version >= SaveLoadVersion::SaveloadListLength {
+        let count = gamma::read_gamma(reader)?;
+        return table::check_count(count, reader);
+    }
+    let count = match &sld.length {
+        ListLength::Fixed(count) => return Ok(*count),
+        ListLength::Prefixed => reader.read_u32()? as u64,
+        ListLength::Field(key) => std::iter::once(record)
+            .chain(outer.iter().rev().copied())
+            .find_map(|record| record.get_u64(key))
+            .ok_or_else(|| CoreError::InvalidData(format!("no length field '{}'", key)))?,
+        ListLength::Computed(length) => length(record),
+        ListLength::Chain { .. } | ListLength::Terminated(_) => {
+            return Err(CoreError::InvalidData("chain of non-struct values".into()))
+        }
+    };
+    table::check_count(count, reader)
+}
+
+fn decode_array_field(
+    sld: &SaveLoad,
+    version: u16,
+    reader: &mut BigEndianReader,
+    record: &Record,
+    outer: &[&Record],
+) -> Result<Value, CoreError> {
+    if sld.data_type == DataType::String || !sld.is_list {
+        return table::decode_scalar(sld.data_type, reader);
+    }
+    let mut items = Vec::new();
+    if sld.data_type == DataType::Struct {
+        let records = [outer, &[record]].concat();
+        let decode = |reader: &mut BigEndianReader| {
+            decode_array_fields(&sld.sub_desc, version, reader, &records)
+        };
+        match &sld.length {
+            ListLength::Chain { key, end } if version < SaveLoadVersion::SaveloadListLength => {
+                loop {
+                    let item = decode(reader)?;
+                    let last = item.get_u64(key) == Some(*end);
+                    items.push(Value::Struct(item));
+                    if last {
+                        break;
+                    }
+                }
+            }
+            ListLength::Terminated(end) => loop {
+                match reader.rest().first() {
+                    Some(byte) if byte == end => {
+                        reader.read_u8()?;
+                        break;
+                    }
+                    Some(_) => items.push(Value::Struct(decode(reader)?)),
+                    None => return Err(CoreError::UnexpectedEof),
+                }
+            },
+            _ => {
+                for _ in 0..array_count(sld, version, reader, record, outer)? {
+                    items.push(Value::Struct(decode(reader)?));
+                }
+            }
+        }
+    } else {
+        for _ in 0..array_count(sld, version, reader, record, outer)? {
+            items.push(table::decode_scalar(sld.data_type, reader)?);
+        }
+    }
+    Ok(Value::List(items))
+}
+
+fn decode_array_fields(
+    desc: &[SaveLoad],
+    version: u16,
+    reader: &mut BigEndianReader,
+    outer: &[&Record],
+) -> Result<Record, CoreError> {
+    let mut record = Record::default();
+    for sld in desc.iter().filter(|sld| sld.is_saved_in(version)) {
+        if sld.key.is_empty() {
+            if let ListLength::Fixed(length) = sld.length {
+                reader.read_bytes(length)?;
+            }
+            continue;
+        }
+        let value =
+            decode_array_field(sld, version, reader, &record, outer).map_err(|e| match e {
+                CoreError::InvalidData(msg) => {
+                    CoreError::InvalidData(format!("field '{}': {}", sld.key, msg))
+                }
+                other => other,
+            })?;
+        record.fields.push((sld.key.clone(), value));
+    }
+    Ok(record)
+}
+
+/// Decode a record of a chunk without a table header, as laid out by the
+/// field declarations for `version` (see `compat_desc`); bytes not described
+/// by them are kept in `trailing`
+pub fn decode_array_record(
+    desc: &[SaveLoad],
+    version: u16,
+    data: &[u8],
+) -> Result<Record, CoreError> {
+    let mut reader = BigEndianReader::new(data);
+    let mut record = decode_array_fields(desc, version, &mut reader, &[])?;
+    record.trailing = reader.rest().to_vec();
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_numbers() {
+        // Spot checks against the C++ numbering, including the patchpack gap
+        assert_eq!(SaveLoadVersion::V1.as_u16(), 1);
+        assert_eq!(SaveLoadVersion::V123.as_u16(), 123);
+        assert_eq!(SaveLoadVersion::V198.as_u16(), 198);
+        assert_eq!(SaveLoadVersion::StartPatchpacks.as_u16(), 220);
+        assert_eq!(SaveLoadVersion::EndPatchpacks.as_u16(), 286);
+        assert_eq!(SaveLoadVersion::GsIndustryControl.as_u16(), 287);
+        assert_eq!(SaveLoadVersion::TableChunks.as_u16(), 295);
+        assert_eq!(SaveLoadVersion::FaceStyles.as_u16(), 355);
+        assert_eq!(SaveLoadVersion::CURRENT.as_u16(), 364);
+        assert!(MILESTONES.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_version_from_u16() {
+        assert_eq!(
+            SaveLoadVersion::try_from(308).unwrap(),
+            SaveLoadVersion::LinkgraphSeconds
+        );
+        assert_eq!(
+            SaveLoadVersion::try_from(0).unwrap(),
+            SaveLoadVersion::MinVersion
+        );
+        assert!(SaveLoadVersion::try_from(250).is_err());
+        assert!(SaveLoadVersion::try_from(SaveLoadVersion::MaxVersion.as_u16()).is_err());
+
+        assert!(308u16 >= SaveLoadVersion::TableChunks);
+        assert!(308u16 < SaveLoadVersion::ExtendVehicleRandom);
+        assert!(308u16 == SaveLoadVersion::LinkgraphSeconds);
+    }
+
+    #[test]
+    fn test_table_header_versions() {
+        let desc = vec![
+            SaveLoad::var(DataType::U32, "xy"),
+            SaveLoad::var(DataType::U8, "old").until(SaveLoadVersion::ExtendEntityMapping),
+            SaveLoad::var(DataType::U16, "old").since(SaveLoadVersion::ExtendEntityMapping),
+            SaveLoad::structs(
+                "history",
+                vec![
+                    SaveLoad::var(DataType::U32, "amount"),
+                    SaveLoad::var(DataType::U32, "waiting")
+                        .since(SaveLoadVersion::IndustryAcceptedHistory),
+                ],
+            ),
+        ];
+
+        let header = table_header(&desc, 308);
+        assert_eq!(header.fields.len(), 3);
+        assert_eq!(header.field("old").unwrap().data_type, DataType::U8);
+        let history = header.field("history").unwrap();
+        assert!(history.is_list);
+        assert_eq!(history.sub_header.as_ref().unwrap().fields.len(), 1);
+
+        let header = table_header(&desc, SaveLoadVersion::CURRENT.as_u16());
+        assert_eq!(header.field("old").unwrap().data_type, DataType::U16);
+        let history = header
+            .field("history")
+            .unwrap()
+            .sub_header
+            .as_ref()
+            .unwrap();
+        assert_eq!(history.fields.len(), 2);
+
+        // The same declarations give the same bytes as a hand built header
+        let expected = TableHeader {
+            fields: vec![
+                TableField::new(DataType::U32, "xy"),
+                TableField::new(DataType::U8, "old"),
+                TableField::structs(
+                    "history",
+                    TableHeader {
+                        fields: vec![TableField::new(DataType::U32, "amount")],
+                    },
+                ),
+            ],
+        };
+        assert_eq!(table_header(&desc, 308), expected);
+    }
+
+    #[test]
+    fn test_compat_desc() {
+        let desc = vec![
+            SaveLoad::var(DataType::U32, "xy"),
+            SaveLoad::var(DataType::U8, "type").until(SaveLoadVersion::V5),
+            SaveLoad::var(DataType::U16, "type").since(SaveLoadVersion::V5),
+            SaveLoad::structs(
+                "parts",
+                vec![
+                    SaveLoad::var(DataType::U8, "a"),
+                    SaveLoad::var(DataType::U8, "b"),
+                ],
+            )
+            .compat(vec![SaveLoadCompat::var("b"), SaveLoadCompat::var("a")]),
+            SaveLoad::var(DataType::U8, "new").since(SaveLoadVersion::TableChunks),
+        ];
+        let compat = vec![
+            SaveLoadCompat::var("type"),
+            SaveLoadCompat::null(2, SaveLoadVersion::V4, SaveLoadVersion::V100),
+            SaveLoadCompat::var("xy"),
+            SaveLoadCompat::var("parts"),
+        ];
+
+        let ordered = compat_desc(&desc, &compat);
+        let keys: Vec<&str> = ordered.iter().map(|sld| sld.key.as_str()).collect();
+        assert_eq!(keys, ["type", "type", "", "xy", "parts"]);
+        assert_eq!(ordered[2].version_from, SaveLoadVersion::V4);
+        let sub_keys: Vec<&str> = ordered[4]
+            .sub_desc
+            .iter()
+            .map(|sld| sld.key.as_str())
+            .collect();
+        assert_eq!(sub_keys, ["b", "a"]);
+        // Skipped bytes never show up in a table header
+        assert_eq!(table_header(&ordered, 50).fields.len(), 3);
+    }
+
+    #[test]
+    fn test_decode_array_record() {
+        let desc = vec![
+            SaveLoad::var(DataType::U8, "num_parts").until(SaveLoadVersion::SaveloadListLength),
+            SaveLoad::null(2).until(SaveLoadVersion::V100),
+            SaveLoad::var(DataType::U16, "x").until(SaveLoadVersion::V5),
+            SaveLoad::var(DataType::I32, "x").since(SaveLoadVersion::V5),
+            SaveLoad::array(DataType::U8, "cargo", 2),
+            SaveLoad::list(DataType::U16, "history"),
+            SaveLoad::structs(
+                "parts",
+                vec![
+                    SaveLoad::var(DataType::U8, "kind"),
+                    SaveLoad::list(DataType::U8, "counts")
+                        .length(ListLength::Field("num_parts".into())),
+                ],
+            )
+            .length(ListLength::Field("num_parts".into())),
+            SaveLoad::var(DataType::String, "name"),
+        ];
+
+        // Version 50: two skipped bytes, no list lengths but a u32 for vectors;
+        // the count of the inner list is found in the enclosing record
+        let mut data = vec![2, 0xAA, 0xBB];
+        data.extend_from_slice(&(-3i32).to_be_bytes());
+        data.extend_from_slice(&[4, 5]);
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&7u16.to_be_bytes());
+        data.extend_from_slice(&[1, 10, 11, 2, 20, 21]);
+        data.extend_from_slice(&[2, b'h', b'i', 0xFF]);
+        let record = decode_array_record(&desc, 50, &data).unwrap();
+        let keys: Vec<&str> = record.fields.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            ["num_parts", "x", "cargo", "history", "parts", "name"]
+        );
+        assert_eq!(record.get_i64("x"), Some(-3));
+        assert_eq!(record.get("cargo"), Some(&Value::from(vec![4u8, 5])));
+        assert_eq!(record.get("history"), Some(&Value::from(vec![7u16])));
+        let parts: Vec<&Record> = record.get_structs("parts").collect();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[1].get_i64("kind"), Some(2));
+        assert_eq!(parts[1].get("counts"), Some(&Value::from(vec![20u8, 21])));
+        assert_eq!(record.get_str("name"), Some("hi"));
+        assert_eq!(record.trailing, [0xFF]);
+
+        // Version 2 saves x as u16; a missing length field is an error
+        let record = decode_array_record(&desc[..4], 2, &[0, 0, 0, 0x12, 0x34]).unwrap();
+        assert_eq!(record.get("x"), Some(&Value::U16(0x1234)));
+        assert!(decode_array_record(&desc[6..], 50, &[1]).is_err());
+
+        // From SaveloadListLength on every list has a gamma length
+        let mut data = (-3i32).to_be_bytes().to_vec();
+        data.extend_from_slice(&[2, 4, 5, 0, 1, 2, 1, 20, 0]);
+        let record =
+            decode_array_record(&desc, SaveLoadVersion::SaveloadListLength.into(), &data).unwrap();
+        assert!(record.get("num_parts").is_none());
+        assert_eq!(record.get("history"), Some(&Value::List(Vec::new())));
+        let parts: Vec<&Record> = record.get_structs("parts").collect();
+        assert_eq!(parts[0].get("counts"), Some(&Value::from(vec![20u8])));
+        assert_eq!(record.get_str("name"), Some(""));
+
+        // A chain ends with the first element whose field has the end value
+        let chain = vec![
+            SaveLoad::structs("links", vec![SaveLoad::var(DataType::U8, "next")]).length(
+                ListLength::Chain {
+                    key: "next".into(),
+                    end: 0xFF,
+                },
+            ),
+        ];
+        let record = decode_array_record(&chain, 50, &[2, 1, 0xFF, 9]).unwrap();
+        assert_eq!(record.get_structs("links").count(), 3);
+        assert_eq!(record.trailing, [9]);
+        assert!(decode_array_record(&chain, 50, &[2, 1]).is_err());
+
+        // A terminated list ends before a lone end byte, in any version
+        let terminated = vec![SaveLoad::structs(
+            "changes",
+            vec![
+                SaveLoad::var(DataType::U8, "type"),
+                SaveLoad::var(DataType::U16, "value"),
+            ],
+        )
+        .length(ListLength::Terminated(0xFF))];
+        for version in [50, SaveLoadVersion::SaveloadListLength.into()] {
+            let record =
+                decode_array_record(&terminated, version, &[1, 0, 2, 3, 0, 4, 0xFF, 9]).unwrap();
+            let changes: Vec<&Record> = record.get_structs("changes").collect();
+            assert_eq!(changes.len(), 2);
+            assert_eq!(changes[1].get_u64("value"), Some(4));
+            assert_eq!(record.trailing, [9]);
+        }
+        assert!(decode_array_record(&terminated, 50, &[1, 0, 2]).is_err());
+    }
+}
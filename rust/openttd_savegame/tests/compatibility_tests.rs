@@ -1,6 +1,28 @@
 /// Compatibility tests using real OpenTTD save files
-use openttd_savegame::{ChunkData, CompressionType, SavegameReader};
+use openttd_core::cargopacket::{Source, SourceType};
+use openttd_core::engine::{EngineFlags, EnginePool, INVALID_GRFID};
+use openttd_core::gamelog::{print_gamelog, GamelogActionType, GamelogChange};
+use openttd_core::map::TileIndex;
+use openttd_core::order::OrderType;
+use openttd_core::signs::Sign;
+use openttd_core::station::FlowShare;
+use openttd_core::subsidy::Subsidy;
+use openttd_core::types::{
+    CalendarDate, CargoType, EconomyDate, EngineID, GroupID, Owner, SignID, StationID, TownID,
+    LAST_MONTH,
+};
+use openttd_core::vehicle::{VehicleType, VehicleTypeData};
+use openttd_savegame::chunk::DataType;
+use openttd_savegame::diff::{diff_chunks, DiffLevel};
+use openttd_savegame::savegame::SavegameError;
+use openttd_savegame::version::{self, SaveLoad, SaveLoadCompat};
+use openttd_savegame::{
+    cargopacket, company, engine, gamelog, group, industry, linkgraph, map, newgrf, order, signs,
+    station, subsidy, town, validate, vehicle, Chunk, ChunkData, CompressionType, SaveLoadVersion,
+    SavegameDocument, SavegameReader, SavegameStream, SavegameWriter,
+};
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 
 fn test_savegame(path: &Path) {
@@ -40,6 +62,65 @@ fn test_savegame(path: &Path) {
     // Verify we found expected chunks
     assert!(chunks.len() > 0, "No chunks found in savegame");
 
+    // Every table chunk must decode into typed records
+    for chunk in &chunks {
+        if let ChunkData::Table { records, .. } = &chunk.data {
+            let decoded = chunk
+                .decode_records()
+                .unwrap_or_else(|e| panic!("Failed to decode {}: {}", chunk.tag, e));
+            assert_eq!(decoded.len(), records.len());
+        }
+    }
+
+    // Writing every chunk back must reproduce the original chunk stream
+    let mut writer = SavegameWriter::new(header.version, CompressionType::None);
+    for chunk in &chunks {
+        writer
+            .add_chunk(chunk)
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", chunk.tag, e));
+    }
+    let rewritten = writer.finalize().expect("Failed to finalize savegame");
+    let reread = SavegameReader::new(&rewritten).expect("Failed to re-read savegame");
+    assert_eq!(
+        reread.read_chunks().expect("Failed to re-read chunks"),
+        chunks
+    );
+    let mut writer = SavegameWriter::with_format(header.version, "lzma:2".parse().unwrap());
+    for chunk in &chunks {
+        writer.add_chunk(chunk).unwrap();
+    }
+    let recompressed = writer.finalize().expect("Failed to compress savegame");
+    let reread = SavegameReader::new(&recompressed).expect("Failed to read XZ savegame");
+    assert!(
+        reread.read_chunks().unwrap() == chunks,
+        "XZ round trip differs"
+    );
+
+    if header.compression == CompressionType::Lzma {
+        let mut original = Vec::new();
+        lzma_rust2::XzReader::new(&data[8..], false)
+            .read_to_end(&mut original)
+            .expect("Failed to decompress");
+        assert!(
+            rewritten[8..] == original[..],
+            "Rewritten chunk stream differs from the original"
+        );
+    }
+
+    // Streaming the file must yield the same chunks
+    let mut stream = SavegameStream::new(data.as_slice()).expect("Failed to open stream");
+    let mut streamed = 0;
+    while let Some(info) = stream.next_chunk().expect("Failed to stream chunk header") {
+        let chunk = &chunks[streamed];
+        assert_eq!((&info.tag, info.chunk_type), (&chunk.tag, chunk.chunk_type));
+        let body = stream
+            .read_body(&info)
+            .expect("Failed to stream chunk body");
+        assert!(body == chunk.data, "Streamed {} differs", info.tag);
+        streamed += 1;
+    }
+    assert_eq!(streamed, chunks.len());
+
     // The test saves might be minimal and not have all chunks
     // Just verify we can parse them without errors
     println!(
@@ -68,6 +149,703 @@ fn test_stationlist_save() {
     }
 }
 
+/// Parsed chunks of every regression savegame that is present
+fn regression_saves() -> Vec<(&'static str, u16, Vec<Chunk>)> {
+    let mut saves = Vec::new();
+    for path in [
+        "../../regression/regression/test.sav",
+        "../../regression/stationlist/test.sav",
+    ] {
+        let Ok(data) = fs::read(path) else {
+            eprintln!("Warning: {} not found, skipping test", path);
+            continue;
+        };
+        let reader = SavegameReader::new(&data).expect("Failed to parse savegame");
+        let version = reader.header().version;
+        saves.push((
+            path,
+            version,
+            reader.read_chunks().expect("Failed to read chunks"),
+        ));
+    }
+    saves
+}
+
+/// Check that `save` reproduces the original chunks with the given tags exactly,
+/// or rejects the version if it is older than the table chunks it writes
+fn assert_saved_identically(
+    chunks: &[Chunk],
+    version: u16,
+    tags: &[&str],
+    save: impl FnOnce(&mut SavegameWriter) -> Result<(), SavegameError>,
+) {
+    let mut saved = SavegameWriter::new(version, CompressionType::None);
+    if version < SaveLoadVersion::TableChunks {
+        let result = save(&mut saved);
+        assert!(
+            matches!(result, Err(SavegameError::UnsupportedVersion(v)) if v == version),
+            "Saving {:?} in version {} gave {:?}",
+            tags,
+            version,
+            result
+        );
+        return;
+    }
+    save(&mut saved).expect("Failed to save chunks");
+    let mut original = SavegameWriter::new(version, CompressionType::None);
+    for chunk in chunks.iter().filter(|c| tags.contains(&c.tag.as_str())) {
+        original.add_chunk(chunk).unwrap();
+    }
+    assert!(
+        saved.finalize().unwrap() == original.finalize().unwrap(),
+        "Saved {:?} differ from the original",
+        tags
+    );
+}
+
+#[test]
+fn test_decode_records_without_header() {
+    // The C++ _sign_desc with all of its versions, in the order of _sign_sl_compat
+    let desc = vec![
+        SaveLoad::var(DataType::U16, "name").until(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::String, "name").since(SaveLoadVersion::V84),
+        SaveLoad::var(DataType::I16, "x").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::I16, "y").until(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::I32, "x").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::I32, "y").since(SaveLoadVersion::V5),
+        SaveLoad::var(DataType::U8, "owner").since(SaveLoadVersion::V6),
+        SaveLoad::var(DataType::U8, "z").until(SaveLoadVersion::V164),
+        SaveLoad::var(DataType::I32, "z").since(SaveLoadVersion::V164),
+    ];
+    let compat: Vec<SaveLoadCompat> = ["name", "x", "y", "owner", "z"]
+        .into_iter()
+        .map(SaveLoadCompat::var)
+        .collect();
+    let desc = version::compat_desc(&desc, &compat);
+
+    for (_, version, chunks) in regression_saves() {
+        if version >= 295 {
+            continue;
+        }
+        let chunk = chunks.iter().find(|c| c.tag == "SIGN").unwrap();
+        let records = chunk.decode_records_with(&desc, version).unwrap();
+        assert_eq!(records.len(), 2);
+        let (index, sign) = &records[0];
+        assert_eq!(*index, 0);
+        assert_eq!(sign.get_str("name"), Some("Some Sign"));
+        assert_eq!(
+            (sign.get_i64("x"), sign.get_i64("y")),
+            (Some(2080), Some(2080))
+        );
+        assert_eq!(
+            (sign.get_i64("owner"), sign.get_i64("z")),
+            (Some(1), Some(24))
+        );
+        assert!(sign.trailing.is_empty());
+        assert_eq!(records[1].1.get_str("name"), Some("Test2"));
+    }
+}
+
+#[test]
+fn test_map_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let map = map::load_map(&chunks, version).expect("Failed to load map");
+        assert_eq!(map.tiles.len(), (map.size_x * map.size_y) as usize);
+
+        let tags = [
+            "MAPS", "MAPT", "MAPH", "MAPO", "MAP2", "M3LO", "M3HI", "MAP5", "MAPE", "MAP7", "MAP8",
+        ];
+        assert_saved_identically(&chunks, version, &tags, |w| map::save_map(w, &map));
+    }
+}
+
+#[test]
+fn test_towns_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let towns = town::load_towns(&chunks, version).expect("Failed to load towns");
+        assert!(!towns.is_empty());
+        assert_eq!(towns[0].xy.0, 15508);
+        assert!(towns.iter().all(|t| t.townnametype != 0));
+        if version < 295 {
+            // Generated names only; population is a cache rebuilt from the map
+            assert_eq!(towns.len(), 28);
+            assert!(towns.iter().all(|t| t.custom_name.is_empty()));
+            assert_eq!(
+                (towns[0].townnametype, towns[0].townnameparts),
+                (0x20C0, 2753259172)
+            );
+            assert_eq!(towns[1].xy.0, 46751);
+            assert_eq!(towns[27].xy.0, 45525);
+            assert_eq!(towns[0].growth_rate, 5669);
+            assert!(towns[0].larger_town);
+            // Passengers and mail supplied last month
+            assert_eq!(towns[0].supplied_last_month[..3], [72, 0, 25]);
+            assert_eq!(towns[1].supplied_last_month[..3], [14, 0, 10]);
+            assert_eq!(towns[0].valid_history, 1 << LAST_MONTH);
+        }
+
+        assert_saved_identically(&chunks, version, &["CITY"], |w| town::save_towns(w, &towns));
+    }
+}
+
+#[test]
+fn test_stations_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let stations = station::load_stations(&chunks, version).expect("Failed to load stations");
+        if version < 295 {
+            assert_eq!(stations.len(), 8);
+            assert_eq!(stations[0].custom_name, "Look, a station");
+            assert_eq!((stations[0].xy.0, stations[0].town), (32116, TownID(15)));
+            assert_eq!(
+                (stations[0].airport_area.w, stations[0].airport_area.h),
+                (4, 3)
+            );
+            assert!(stations[1..].iter().all(|s| s.custom_name.is_empty()));
+            assert_eq!(stations[2].bus_stops, Some(9));
+
+            // Passengers planned through the link graph, waiting per next hop
+            let ge = &stations[2].goods[0];
+            assert!(ge.acceptance);
+            assert_eq!((ge.rating, ge.link_graph, ge.node), (166, 0, 1));
+            assert_eq!(ge.flows.len(), 3);
+            assert_eq!(
+                ge.flows[0],
+                FlowShare {
+                    source: StationID(2),
+                    via: StationID(6),
+                    share: 7,
+                    restricted: false,
+                }
+            );
+            assert_eq!(ge.cargo.packets.len(), 1);
+            assert_eq!(ge.cargo.packets[0].next_hop, StationID(6));
+            assert_eq!(ge.cargo.packets[0].packets, [0]);
+            let ge = &stations[6].goods[0];
+            assert_eq!(ge.flows.len(), 6);
+            assert_eq!(ge.cargo.packets.len(), 2);
+
+            // Mail is rated at the truck stop, but not part of the link graph
+            let ge = &stations[5].goods[1];
+            assert_eq!((ge.rating, ge.link_graph), (175, 0xFFFF));
+            assert!(ge.flows.is_empty());
+        }
+
+        assert_saved_identically(&chunks, version, &["STNN"], |w| {
+            station::save_stations(w, &stations)
+        });
+    }
+}
+
+#[test]
+fn test_industries_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let industries =
+            industry::load_industries(&chunks, version).expect("Failed to load industries");
+        assert_eq!(industries.len(), 71);
+        assert_eq!(industries[0].location.0, 19695);
+        assert_eq!((industries[0].width, industries[0].height), (3, 5));
+        assert!(industries[0].produces(CargoType(5)));
+        assert!(industries[0].accepts(CargoType(3)));
+        assert_eq!(industries[1].produced_cargo[0].production_rate, 9);
+        assert_eq!(industries[1].last_month_production.produced[0], 72);
+        assert_eq!(industries[70].location.0, 6498);
+        assert_eq!(industries[70].construction_date.0, 713311);
+        assert_eq!(industries[70].town.0, 19);
+
+        if version < 295 {
+            assert_eq!(industries[1].produced_cargo[0].waiting, 9);
+            assert_eq!(industries[70].exclusive_supplier, Owner::Invalid);
+        }
+        assert_saved_identically(&chunks, version, &["INDY"], |w| {
+            industry::save_industries(w, &industries)
+        });
+    }
+}
+
+#[test]
+fn test_vehicles_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let vehicles = vehicle::load_vehicles(&chunks, version).expect("Failed to load vehicles");
+        if version < 295 {
+            assert_eq!(vehicles.len(), 22);
+            let types: Vec<VehicleType> = vehicles[12..].iter().map(|v| v.type_).collect();
+            assert_eq!(
+                types,
+                [
+                    VehicleType::Road,
+                    VehicleType::Road,
+                    VehicleType::Aircraft,
+                    VehicleType::Aircraft,
+                    VehicleType::Ship,
+                    VehicleType::Train,
+                    VehicleType::Train,
+                    VehicleType::Train,
+                    VehicleType::Train,
+                    VehicleType::Road,
+                ]
+            );
+            assert!(vehicles[..12]
+                .iter()
+                .all(|v| v.type_ == VehicleType::Effect));
+
+            // A locomotive with two wagons, and an aircraft with its shadow
+            let chain = |index: usize| {
+                let v = &vehicles[index];
+                (v.next.map(|n| n.0), v.first.map(|f| f.0))
+            };
+            assert_eq!(chain(17), (Some(18), Some(17)));
+            assert_eq!(chain(18), (Some(19), Some(17)));
+            assert_eq!(chain(19), (None, Some(17)));
+            assert_eq!(chain(14), (Some(15), Some(14)));
+            assert_eq!(chain(15), (None, Some(14)));
+            assert_eq!(chain(20), (None, Some(20)));
+            let train = &vehicles[17];
+            assert_eq!((train.x_pos, train.y_pos), (394, 632));
+            assert_eq!((train.engine_type, train.unitnumber), (EngineID(9), 1));
+            assert_eq!(
+                (vehicles[18].engine_type, vehicles[18].cargo_cap),
+                (EngineID(27), 40)
+            );
+            assert_eq!(vehicles[15].cargo_cap, 6);
+
+            // Every order list has a single vehicle, so nothing is shared
+            assert!(vehicles.iter().all(|v| v.next_shared.is_none()));
+            let orders: Vec<(usize, u32)> = vehicles
+                .iter()
+                .filter_map(|v| v.orders.map(|o| (v.index.0 as usize, o)))
+                .collect();
+            assert_eq!(orders, [(12, 0), (20, 1), (21, 2)]);
+            let lists = order::load_order_lists(&chunks, version).unwrap();
+            assert_eq!(lists[2].orders.len(), 4);
+
+            let road = &vehicles[21];
+            assert_eq!(road.owner, Owner::Company1);
+            assert_eq!((road.profit_last_year, road.value), (0, 4807));
+            assert_eq!(road.build_year.0, 1955);
+            assert_eq!(road.last_station_visited, StationID(7));
+            assert_eq!(road.current_order.dest, 6);
+            assert_eq!(
+                (road.current_order.wait_time, road.current_order.travel_time),
+                (148, 444)
+            );
+            assert_eq!(vehicles[12].profit_last_year, -593);
+        } else {
+            assert_eq!(vehicles.len(), 12);
+            for v in &vehicles {
+                assert_eq!(v.type_, VehicleType::Effect);
+                assert!(matches!(v.type_data, VehicleTypeData::Effect(_)));
+                assert_eq!(v.first, Some(v.index));
+            }
+            assert_eq!((vehicles[0].x_pos, vehicles[0].y_pos), (2303, 2142));
+        }
+        assert_saved_identically(&chunks, version, &["VEHS"], |w| {
+            vehicle::save_vehicles(w, &vehicles)
+        });
+    }
+}
+
+#[test]
+fn test_companies_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let companies =
+            company::load_companies(&chunks, version).expect("Failed to load companies");
+        assert_eq!(companies.len(), 2);
+        if version < 295 {
+            let human = &companies[0];
+            assert!(!human.is_ai);
+            assert_eq!((human.money, human.current_loan), (89605, 100000));
+            assert_eq!(human.inaugurated_year.0, 1950);
+            assert_eq!(human.location_of_hq, TileIndex(u32::MAX));
+            assert_eq!(human.num_valid_stat_ent, 20);
+            assert_eq!(human.old_economy[0].expenses, -495);
+            assert_eq!(human.cur_economy.expenses, -500);
+            assert_eq!(human.yearly_expenses[0][11..], [334, 50]);
+            assert_eq!(human.settings.engine_renew_months, -6);
+            assert_eq!(human.settings.servint_ships, 360);
+            assert_eq!(human.terraform_limit, 1 << 28);
+            assert_eq!((human.livery[0].colour1, human.livery[0].colour2), (2, 2));
+
+            let ai = &companies[1];
+            assert!(ai.is_ai);
+            assert_eq!((ai.money, ai.current_loan), (166870, 500000));
+            assert_eq!(ai.location_of_hq, TileIndex(33153));
+            assert_eq!(ai.num_valid_stat_ent, 1);
+            assert_eq!(ai.yearly_expenses[1][1], 113516);
+            assert_eq!(ai.yearly_expenses[0][8], -106);
+            assert_eq!(
+                (ai.cur_economy.income, ai.cur_economy.expenses),
+                (106, -3373)
+            );
+            assert_eq!(ai.cur_economy.delivered_cargo[0], 58);
+        } else {
+            assert_eq!(companies[0].money, 90372);
+            assert_eq!(companies[0].current_loan, 100000);
+            assert_eq!(companies[0].inaugurated_year.0, 1950);
+            assert!(!companies[0].is_ai);
+            assert!(companies[1].is_ai);
+            assert_eq!(companies[1].num_valid_stat_ent, 0);
+        }
+        assert_saved_identically(&chunks, version, &["PLYR"], |w| {
+            company::save_companies(w, &companies)
+        });
+    }
+}
+
+#[test]
+fn test_gamelog_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let actions = gamelog::load_gamelog(&chunks, version).expect("Failed to load gamelog");
+        // The save was converted from an old version and loaded in newer revisions since
+        assert_eq!(actions[0].action_type, GamelogActionType::Load);
+        assert!(matches!(
+            actions[0].changes[0],
+            GamelogChange::OldVersion { .. }
+        ));
+        let last = actions.last().unwrap();
+        assert!(matches!(
+            last.changes[..],
+            [GamelogChange::Revision { slver, .. }] if slver == version
+        ));
+        let lines = print_gamelog(&actions);
+        assert_eq!(lines[1], "Tick 74: game loaded");
+        assert_eq!(
+            lines[2],
+            "Conversion from OTTD savegame without gamelog: version 53, 0"
+        );
+        if version < 295 {
+            // The RIFF chunk of older saves holds the same actions
+            assert_eq!(actions.len(), 7);
+            assert_eq!(
+                lines[3],
+                "Revision text changed to r13512M-noai, savegame version 98, modified, \
+                 _openttd_newgrf_version = 0x070034c8"
+            );
+            assert!(matches!(
+                &actions[4].changes[..],
+                [GamelogChange::Setting { name, old_value: 1, new_value: 3 }]
+                    if name == "construction.command_pause_level"
+            ));
+        }
+
+        assert_saved_identically(&chunks, version, &["GLOG"], |w| {
+            gamelog::save_gamelog(w, &actions)
+        });
+    }
+}
+
+#[test]
+fn test_newgrf_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let configs =
+            newgrf::load_newgrf_configs(&chunks, version).expect("Failed to load NewGRFs");
+        // Neither save uses NewGRFs, but both have the chunk
+        assert!(configs.is_empty());
+        assert!(chunks.iter().any(|c| c.tag == "NGRF"));
+        assert_saved_identically(&chunks, version, &["NGRF"], |w| {
+            newgrf::save_newgrf_configs(w, &configs)
+        });
+    }
+}
+
+#[test]
+fn test_orders_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let lists = order::load_order_lists(&chunks, version).expect("Failed to load order lists");
+        let backups =
+            order::load_order_backups(&chunks, version).expect("Failed to load order backups");
+        assert!(backups.is_empty());
+
+        // Older saves keep the orders in the ORDR pool, chained by their next reference
+        if version < 295 {
+            let lengths: Vec<usize> = lists.iter().map(|l| l.orders.len()).collect();
+            assert_eq!(lengths, [2, 1, 4]);
+            let order = &lists[2].orders[0];
+            assert!(order.is_type(OrderType::GotoStation));
+            assert_eq!(order.dest, 2);
+            assert_eq!((order.wait_time, order.travel_time), (74, 222));
+            assert_eq!(lists[2].orders[2].dest, 7);
+        }
+        let vehicles = vehicle::load_vehicles(&chunks, version).unwrap();
+        for v in vehicles.iter().filter(|v| v.orders.is_some()) {
+            assert!(lists.iter().any(|l| Some(l.index) == v.orders));
+        }
+        assert_saved_identically(&chunks, version, &["BKOR", "ORDR", "ORDL"], |w| {
+            order::save_orders(w, &lists, &backups)
+        });
+    }
+}
+
+#[test]
+fn test_cargo_packets_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let pool = cargopacket::load_cargo_packets(&chunks, version)
+            .expect("Failed to load cargo packets");
+
+        // Older saves store CAPA without a table header
+        if version < 295 {
+            assert_eq!(pool.iter().count(), 6);
+            let packet = pool.get(3).unwrap();
+            assert_eq!((packet.count, packet.periods_in_transit), (20, 3));
+            assert_eq!(packet.first_station, StationID(7));
+            assert_eq!(packet.source_xy.0, 41825);
+            assert_eq!(packet.source.type_, SourceType::Town);
+            assert_eq!(packet.source.id, 15);
+            assert!(pool.get(5).is_none());
+        }
+        let stations = station::load_stations(&chunks, version).unwrap();
+        for ge in stations.iter().flat_map(|s| s.goods.iter()) {
+            for group in &ge.cargo.packets {
+                assert!(group.packets.iter().all(|&p| pool.get(p).is_some()));
+            }
+        }
+        assert_saved_identically(&chunks, version, &["CAPA"], |w| {
+            cargopacket::save_cargo_packets(w, &pool)
+        });
+    }
+}
+
+#[test]
+fn test_link_graphs_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let graphs =
+            linkgraph::load_link_graphs(&chunks, version).expect("Failed to load link graphs");
+        let jobs = linkgraph::load_link_graph_jobs(&chunks, version).unwrap();
+        let schedule = linkgraph::load_link_graph_schedule(&chunks, version, jobs).unwrap();
+        for id in &schedule.schedule {
+            assert!(graphs.iter().any(|lg| lg.index == *id));
+        }
+        if version < 295 {
+            assert_eq!(graphs.len(), 1);
+            let nodes = &graphs[0].nodes;
+            assert_eq!(nodes.len(), 3);
+            assert_eq!(graphs[0].last_compression, EconomyDate(714052));
+            assert_eq!(nodes[0].station, StationID(6));
+            assert_eq!(nodes[0].xy, TileIndex(42341));
+            assert_eq!((nodes[0].supply, nodes[0].demand), (33, 8));
+            let edges: Vec<_> = nodes[0]
+                .edges
+                .iter()
+                .map(|e| (e.dest_node, e.capacity, e.usage))
+                .collect();
+            assert_eq!(edges, [(1, 93, 17), (2, 124, 10)]);
+            assert_eq!(
+                nodes[2].edges[0].last_unrestricted_update,
+                EconomyDate(714107)
+            );
+
+            assert!(schedule.schedule.is_empty());
+            assert_eq!(schedule.running.len(), 1);
+            let job = &schedule.running[0];
+            assert_eq!(job.join_date, EconomyDate(714112));
+            assert_eq!(job.settings.recalc_interval, 4);
+            assert_eq!(job.settings.short_path_saturation, 80);
+            assert_eq!(job.link_graph.index, graphs[0].index);
+            assert_eq!(job.link_graph.nodes[0].supply, 25);
+        }
+        assert_saved_identically(&chunks, version, &["LGRP", "LGRJ", "LGRS"], |w| {
+            linkgraph::save_link_graphs(w, &graphs)?;
+            linkgraph::save_link_graph_schedule(w, &schedule)
+        });
+    }
+}
+
+#[test]
+fn test_engines_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let manager =
+            engine::load_engine_id_mappings(&chunks, version).expect("Failed to load engine IDs");
+        let saved = engine::load_engines(&chunks, version).expect("Failed to load engines");
+        let mut pool = EnginePool::setup(&manager);
+        pool.copy_saved_data(&saved);
+        assert_eq!(pool.len(), saved.len());
+
+        // Both games use the original vehicles only
+        assert_eq!(saved.len(), 256);
+        assert_eq!(
+            manager.get_id(VehicleType::Road, 0, INVALID_GRFID),
+            EngineID(116)
+        );
+        assert_eq!(
+            manager.get_id(VehicleType::Aircraft, 40, INVALID_GRFID),
+            EngineID(255)
+        );
+        assert_eq!(saved[0].intro_date, CalendarDate(703189));
+        assert_eq!(saved[0].reliability_max, 54137);
+        assert_eq!(saved[0].company_avail, 0xFFFF);
+        assert_eq!(saved[255].intro_date, CalendarDate(715309));
+        assert_eq!(saved[255].preview_company, Owner::Invalid);
+        assert_eq!(saved[255].flags, EngineFlags::AVAILABLE);
+        if version < 295 {
+            assert_eq!((saved[0].age, saved[0].reliability), (344, 48557));
+        } else {
+            assert_eq!((saved[0].age, saved[0].reliability), (340, 49234));
+        }
+        assert_saved_identically(&chunks, version, &["EIDS", "ENGN"], |w| {
+            engine::save_engine_id_mappings(w, &manager)?;
+            engine::save_engines(w, &pool)
+        });
+    }
+}
+
+#[test]
+fn test_groups_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let groups = group::load_groups(&chunks, version).expect("Failed to load groups");
+        for g in groups.iter() {
+            assert!(g.owner.is_company());
+        }
+        if version < 295 {
+            assert_eq!(groups.len(), 1);
+            let g = groups.get(GroupID(0)).unwrap();
+            assert_eq!(
+                (g.owner, g.vehicle_type),
+                (Owner::Company1, VehicleType::Road)
+            );
+            assert_eq!((g.livery.colour1, g.livery.colour2), (4, 4));
+            assert_eq!(g.parent, GroupID::INVALID);
+            assert!(g.name.is_empty());
+        }
+        assert_saved_identically(&chunks, version, &["GRPS"], |w| {
+            group::save_groups(w, &groups)
+        });
+    }
+}
+
+#[test]
+fn test_subsidies_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let subsidies =
+            subsidy::load_subsidies(&chunks, version).expect("Failed to load subsidies");
+        for s in subsidies.iter() {
+            assert!(s.cargo_type.is_valid());
+            assert!(!s.is_awarded() || s.awarded.is_company());
+        }
+
+        // Both games offer passengers from town 9 to town 21
+        let town = |id| Source {
+            id,
+            type_: SourceType::Town,
+        };
+        let mut offered = Subsidy::new(3, CargoType(0), town(9), town(21));
+        offered.remaining = 4;
+        if version < 295 {
+            assert_eq!(subsidies.len(), 2);
+            assert_eq!(subsidies.get(3), Some(&offered));
+            assert_eq!(subsidies.get(2).unwrap().remaining, 2);
+        } else {
+            offered.remaining = 8;
+            assert_eq!(subsidies.len(), 4);
+            assert_eq!(subsidies.get(3), Some(&offered));
+        }
+        assert_saved_identically(&chunks, version, &["SUBS"], |w| {
+            subsidy::save_subsidies(w, &subsidies)
+        });
+    }
+}
+
+#[test]
+fn test_signs_load_save() {
+    for (_, version, chunks) in regression_saves() {
+        let signs = signs::load_signs(&chunks, version).expect("Failed to load signs");
+        for s in signs.iter() {
+            assert!(s.index.is_valid());
+        }
+        if version < 295 {
+            let expected = Sign::new(SignID(0), Owner::Company1, 2080, 2080, 24, "Some Sign");
+            assert_eq!(signs.get(SignID(0)), Some(&expected));
+            assert_eq!(signs.get(SignID(1)).unwrap().name, "Test2");
+            assert_eq!(signs.iter().count(), 2);
+        }
+        assert_saved_identically(&chunks, version, &["SIGN"], |w| {
+            signs::save_signs(w, &signs)
+        });
+    }
+}
+
+#[test]
+fn test_json_round_trip() {
+    for (path, version, chunks) in regression_saves() {
+        let document =
+            SavegameDocument::from_chunks(&chunks, version).expect("Failed to decode savegame");
+        let json = document.to_json().expect("Failed to export JSON");
+        let imported = SavegameDocument::from_json(&json).expect("Failed to import JSON");
+        assert_eq!(imported.map, document.map);
+        assert_eq!(imported.companies, document.companies);
+        assert_eq!(imported.towns, document.towns);
+        assert_eq!(imported.stations, document.stations);
+        assert_eq!(imported.industries, document.industries);
+        assert_eq!(imported.vehicles, document.vehicles);
+        if version < 295 {
+            // The decoders don't write these versions, so every chunk keeps its data
+            assert!(imported.chunks.iter().all(|chunk| chunk.data.is_some()));
+            let mut edited = imported.clone();
+            edited.towns[0].population += 1;
+            assert!(edited.to_savegame(CompressionType::Zlib).is_err());
+        }
+        let data = imported
+            .to_savegame(CompressionType::Zlib)
+            .expect("Failed to write savegame");
+
+        let reader = SavegameReader::new(&data).unwrap();
+        assert_eq!(reader.header().version, version);
+        assert!(
+            reader.read_chunks().unwrap() == chunks,
+            "{} changed in a JSON round trip",
+            path
+        );
+    }
+}
+
+#[test]
+fn test_diff_regression_saves() {
+    let saves = regression_saves();
+    for (path, version, chunks) in &saves {
+        let (path, version) = (*path, *version);
+        let differences = diff_chunks(chunks, version, chunks, version).unwrap();
+        assert!(differences.is_empty(), "{} differs from itself", path);
+        if version < 295 {
+            // Older saves cannot be written after an edit, so compare with the newer save
+            for (_, new_version, new_chunks) in saves.iter().filter(|(_, v, _)| *v >= 295) {
+                let fields: Vec<String> = diff_chunks(chunks, version, new_chunks, *new_version)
+                    .unwrap()
+                    .iter()
+                    .filter(|d| d.level == DiffLevel::Field)
+                    .map(ToString::to_string)
+                    .collect();
+                assert!(fields.contains(&"~ company[0].money: 89605 -> 90372".to_string()));
+                assert!(fields.contains(&"~ company[1].name: \"Regression\" -> \"\"".to_string()));
+            }
+        } else {
+            let mut document = SavegameDocument::from_chunks(chunks, version).unwrap();
+            document.companies[0].money += 1000;
+            let index = document.companies[0].index;
+            let data = document.to_savegame(CompressionType::None).unwrap();
+            let reader = SavegameReader::new(&data).unwrap();
+            let edited = reader.read_chunks().unwrap();
+
+            let fields: Vec<String> = diff_chunks(chunks, version, &edited, version)
+                .unwrap()
+                .iter()
+                .filter(|d| d.level == DiffLevel::Field)
+                .map(|d| d.path.clone())
+                .collect();
+            assert_eq!(fields, [format!("company[{}].money", index)]);
+        }
+    }
+}
+
+#[test]
+fn test_validate_regression_saves() {
+    for (path, version, chunks) in regression_saves() {
+        let mut document = SavegameDocument::from_chunks(&chunks, version).unwrap();
+        let violations = validate(&document);
+        assert!(violations.is_empty(), "{}: {:?}", path, violations);
+
+        document.towns.remove(0);
+        assert!(!validate(&document).is_empty());
+    }
+}
+
 #[test]
 fn test_create_and_read_savegame() {
     use openttd_savegame::SavegameWriter;